@@ -0,0 +1,361 @@
+//! `analyze` (or `analyze-file`): batch analysis of positions read from a file.
+//!
+//! Each non-empty line that does not start with `#` is one position:
+//! - Connect Four: a sequence of 0-based column digits, e.g. `3342`.
+//! - Tic-Tac-Toe: a 9-character board such as `X.O...O..`; the side to
+//!   move is inferred from the mark counts.
+//!
+//! Without `--depth`, each position is searched to its game's recommended
+//! depth (see `GameState::recommended_depth`).
+//!
+//! Positions are solved in parallel and the results are written in input
+//! order as CSV or JSON.
+
+use std::fs;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+use solver_core::game::GameState;
+use solver_core::games::c4_bitboard::BitboardState;
+use solver_core::games::ttt::TicTacToeState;
+use solver_core::solvers::minimax::{WIN_SCORE, minimax_best_move_ab_depth_with_stats};
+
+use crate::args;
+
+pub const USAGE: &str = "usage: solver_cli analyze <positions.txt> [--game c4|ttt] [--depth N] [--jobs N] [--format csv|json] [--output FILE]";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Game {
+    ConnectFour,
+    TicTacToe,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Csv,
+    Json,
+}
+
+#[derive(Debug)]
+struct Options {
+    input: String,
+    game: Game,
+    depth: Option<u32>,
+    jobs: usize,
+    format: Format,
+    output: Option<String>,
+}
+
+/// The analysis of one input line.
+#[derive(Debug)]
+struct Row {
+    line: usize,
+    position: String,
+    result: Result<Analysis, String>,
+}
+
+#[derive(Debug)]
+struct Analysis {
+    /// `None` if the position is already terminal.
+    best_move: Option<String>,
+    score: i32,
+    nodes: u64,
+    time_ms: u128,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut input = None;
+    let mut opts = Options {
+        input: String::new(),
+        game: Game::ConnectFour,
+        depth: None,
+        jobs: thread::available_parallelism().map_or(1, |n| n.get()),
+        format: Format::Csv,
+        output: None,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| args::value(name, iter.next());
+        match arg.as_str() {
+            "--game" => {
+                opts.game = match value("--game")?.as_str() {
+                    "c4" => Game::ConnectFour,
+                    "ttt" => Game::TicTacToe,
+                    other => return Err(format!("unknown game '{other}'")),
+                }
+            }
+            "--depth" => {
+                let depth = value("--depth")?;
+                opts.depth = Some(
+                    depth
+                        .parse()
+                        .ok()
+                        .filter(|&d| d > 0)
+                        .ok_or_else(|| format!("invalid depth '{depth}'"))?,
+                );
+            }
+            "--jobs" => {
+                let jobs = value("--jobs")?;
+                opts.jobs = jobs
+                    .parse()
+                    .ok()
+                    .filter(|&j| j > 0)
+                    .ok_or_else(|| format!("invalid job count '{jobs}'"))?;
+            }
+            "--format" => {
+                opts.format = match value("--format")?.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format '{other}'")),
+                }
+            }
+            "--output" => opts.output = Some(value("--output")?),
+            flag if flag.starts_with("--") => return Err(format!("unknown option '{flag}'")),
+            path if input.is_none() => input = Some(path.to_string()),
+            extra => return Err(format!("unexpected argument '{extra}'")),
+        }
+    }
+    opts.input = input.ok_or("missing positions file")?;
+    Ok(opts)
+}
+
+/// Solves one position with depth-limited alpha-beta.
+fn analyze_state<G: GameState>(
+    state: &G,
+    depth: u32,
+    fmt_move: impl Fn(&G::Move) -> String,
+) -> Analysis {
+    let start = Instant::now();
+    if let Some(v) = state.terminal_value() {
+        return Analysis {
+            best_move: None,
+            score: v * WIN_SCORE,
+            nodes: 0,
+            time_ms: 0,
+        };
+    }
+    let (best, stats) = minimax_best_move_ab_depth_with_stats(state, depth);
+    let (mv, score) = best.expect("non-terminal positions have legal moves");
+    Analysis {
+        best_move: Some(fmt_move(&mv)),
+        score,
+        nodes: stats.nodes,
+        time_ms: start.elapsed().as_millis(),
+    }
+}
+
+fn analyze_position(game: Game, position: &str, depth: Option<u32>) -> Result<Analysis, String> {
+    match game {
+        Game::ConnectFour => {
+            let state = BitboardState::from_move_sequence(position).map_err(|e| e.to_string())?;
+            let depth = depth.unwrap_or_else(|| state.recommended_depth());
+            Ok(analyze_state(&state, depth, |c| c.to_string()))
+        }
+        Game::TicTacToe => {
+            let state = TicTacToeState::from_board_str(position).map_err(|e| e.to_string())?;
+            let depth = depth.unwrap_or_else(|| state.recommended_depth());
+            Ok(analyze_state(&state, depth, |m| m.index.to_string()))
+        }
+    }
+}
+
+/// Analyzes `positions` (line number, text) on `jobs` worker threads,
+/// returning rows in input order.
+fn analyze_all(
+    game: Game,
+    depth: Option<u32>,
+    jobs: usize,
+    positions: &[(usize, String)],
+) -> Vec<Row> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Row>>> = Mutex::new(positions.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(positions.len()) {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((line, position)) = positions.get(i) else {
+                        break;
+                    };
+                    let row = Row {
+                        line: *line,
+                        position: position.clone(),
+                        result: analyze_position(game, position, depth),
+                    };
+                    results.lock().unwrap()[i] = Some(row);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every position is analyzed"))
+        .collect()
+}
+
+/// Quotes a CSV field if it contains a separator, quote, or newline.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Returns `s` as a JSON string literal.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn write_csv<W: Write>(w: &mut W, rows: &[Row]) -> io::Result<()> {
+    writeln!(w, "line,position,best_move,score,nodes,time_ms,error")?;
+    for row in rows {
+        match &row.result {
+            Ok(a) => writeln!(
+                w,
+                "{},{},{},{},{},{},",
+                row.line,
+                csv_field(&row.position),
+                a.best_move.as_deref().unwrap_or(""),
+                a.score,
+                a.nodes,
+                a.time_ms
+            )?,
+            Err(e) => writeln!(
+                w,
+                "{},{},,,,,{}",
+                row.line,
+                csv_field(&row.position),
+                csv_field(e)
+            )?,
+        }
+    }
+    Ok(())
+}
+
+fn write_json<W: Write>(w: &mut W, rows: &[Row]) -> io::Result<()> {
+    writeln!(w, "[")?;
+    for (i, row) in rows.iter().enumerate() {
+        let body = match &row.result {
+            Ok(a) => format!(
+                "\"best_move\": {}, \"score\": {}, \"nodes\": {}, \"time_ms\": {}",
+                a.best_move
+                    .as_deref()
+                    .map_or("null".to_string(), json_string),
+                a.score,
+                a.nodes,
+                a.time_ms
+            ),
+            Err(e) => format!("\"error\": {}", json_string(e)),
+        };
+        let sep = if i + 1 < rows.len() { "," } else { "" };
+        writeln!(
+            w,
+            "  {{\"line\": {}, \"position\": {}, {body}}}{sep}",
+            row.line,
+            json_string(&row.position)
+        )?;
+    }
+    writeln!(w, "]")
+}
+
+/// Runs the `analyze` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let opts = parse_options(args)?;
+    if let Some(depth) = opts.depth {
+        match opts.game {
+            Game::ConnectFour => crate::warn_if_impractical(&BitboardState::new(), depth),
+            Game::TicTacToe => crate::warn_if_impractical(&TicTacToeState::new(), depth),
+        }
+    }
+    let text = fs::read_to_string(&opts.input)
+        .map_err(|e| format!("failed to read {}: {e}", opts.input))?;
+    let positions: Vec<(usize, String)> = text
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim().to_string()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+
+    let rows = analyze_all(opts.game, opts.depth, opts.jobs, &positions);
+
+    let mut out: Box<dyn Write> = match &opts.output {
+        Some(path) => {
+            Box::new(fs::File::create(path).map_err(|e| format!("failed to create {path}: {e}"))?)
+        }
+        None => Box::new(io::stdout().lock()),
+    };
+    match opts.format {
+        Format::Csv => write_csv(&mut out, &rows),
+        Format::Json => write_json(&mut out, &rows),
+    }
+    .map_err(|e| format!("failed to write results: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::split_args;
+
+    #[test]
+    fn parses_options() {
+        let opts = parse_options(&split_args(
+            "pos.txt --depth 12 --jobs 8 --format json --game ttt",
+        ))
+        .unwrap();
+        assert_eq!(opts.input, "pos.txt");
+        assert_eq!(opts.depth, Some(12));
+        assert_eq!(opts.jobs, 8);
+        assert_eq!(opts.format, Format::Json);
+        assert_eq!(opts.game, Game::TicTacToe);
+        assert!(parse_options(&split_args("--depth 0 pos.txt")).is_err());
+        assert!(parse_options(&split_args("--depth 3")).is_err());
+    }
+
+    #[test]
+    fn rows_keep_input_order_and_report_errors() {
+        let positions = vec![
+            (1, "012".to_string()),
+            (2, "9".to_string()),
+            (3, "0616263".to_string()),
+        ];
+        let rows = analyze_all(Game::ConnectFour, Some(2), 2, &positions);
+        assert_eq!(
+            rows.iter().map(|r| r.line).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert!(rows[0].result.as_ref().unwrap().best_move.is_some());
+        assert!(rows[1].result.is_err());
+        // Already won by Player1: no move, terminal score.
+        let done = rows[2].result.as_ref().unwrap();
+        assert_eq!((done.best_move.as_deref(), done.score), (None, WIN_SCORE));
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &rows).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().nth(2).unwrap().starts_with("2,9,,,,,"));
+
+        let mut json = Vec::new();
+        write_json(&mut json, &rows).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.contains("\"best_move\": null"));
+        assert!(json.contains("\"error\": \"invalid character '9' at index 0\""));
+    }
+}
@@ -0,0 +1,63 @@
+//! Argument parsing shared by the subcommands.
+//!
+//! Every subcommand takes `--name value` options after its name; these
+//! helpers give their missing and malformed values the same messages.
+
+use std::str::FromStr;
+
+use solver_core::render::Labels;
+
+/// The value following the option `name`, or an error if there is none.
+pub fn value(name: &str, value: Option<&String>) -> Result<String, String> {
+    value
+        .cloned()
+        .ok_or_else(|| format!("{name} requires a value"))
+}
+
+/// The value following the option `name`, parsed as a number.
+pub fn parse_number<T: FromStr>(name: &str, value: Option<&String>) -> Result<T, String> {
+    let value = self::value(name, value)?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{value}' for {name}"))
+}
+
+/// The value of `--coords`: `numbers` or `letters`.
+pub fn parse_labels(value: &str) -> Result<Labels, String> {
+    match value {
+        "numbers" => Ok(Labels::Numbers),
+        "letters" => Ok(Labels::Letters),
+        other => Err(format!(
+            "invalid coordinates '{other}' (expected numbers or letters)"
+        )),
+    }
+}
+
+/// Splits `s` at whitespace into command-line arguments, for tests.
+#[cfg(test)]
+pub(crate) fn split_args(s: &str) -> Vec<String> {
+    s.split_whitespace().map(String::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_missing_and_malformed_values() {
+        let seven = "7".to_string();
+        assert_eq!(parse_number::<u32>("--depth", Some(&seven)), Ok(7));
+        assert_eq!(
+            parse_number::<u32>("--depth", None),
+            Err("--depth requires a value".to_string())
+        );
+        let bad = "x".to_string();
+        assert_eq!(
+            parse_number::<u32>("--depth", Some(&bad)),
+            Err("invalid value 'x' for --depth".to_string())
+        );
+        assert_eq!(value("--output", Some(&bad)), Ok("x".to_string()));
+        assert_eq!(parse_labels("letters"), Ok(Labels::Letters));
+        assert!(parse_labels("roman").is_err());
+    }
+}
@@ -0,0 +1,78 @@
+//! `book`: opening book maintenance for Connect Four.
+//!
+//! `book build` searches every position before `--plies` to `--depth`
+//! plies; with `--exact` it solves them instead, so engines using the book
+//! play perfectly through the opening (see `OpeningBook::build_exact`).
+//!
+//! An exact build saves the positions solved so far to `FILE.partial`
+//! about once a minute; after an interruption, `--resume` picks up from
+//! there instead of solving everything again.
+
+use std::io::{self, Write};
+
+use solver_core::book::{Checkpoint, DEFAULT_BOOK_PATH, OpeningBook};
+
+use crate::args::{self, parse_number};
+
+pub const USAGE: &str =
+    "usage: solver_cli book build [--plies N] [--depth N | --exact [--resume]] [--output FILE]";
+
+fn build(args: &[String]) -> Result<(), String> {
+    let mut plies: u8 = 6;
+    let mut depth: u32 = 10;
+    let mut exact = false;
+    let mut resume = false;
+    let mut output = DEFAULT_BOOK_PATH.to_string();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--plies" => plies = parse_number(arg, iter.next())?,
+            "--depth" => depth = parse_number(arg, iter.next())?,
+            "--exact" => exact = true,
+            "--resume" => resume = true,
+            "--output" => output = args::value(arg, iter.next())?,
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    if depth == 0 {
+        return Err("depth must be at least 1".to_string());
+    }
+    if resume && !exact {
+        return Err("--resume only applies to --exact builds".to_string());
+    }
+
+    let progress = |done: usize, total: usize| {
+        if done.is_multiple_of(100) || done == total {
+            print!("\r{done}/{total} positions");
+            let _ = io::stdout().flush();
+        }
+    };
+    let book = if exact {
+        let checkpoint = Checkpoint {
+            resume,
+            ..Checkpoint::new(format!("{output}.partial"))
+        };
+        println!(
+            "Solving book: positions before ply {plies}, saving progress to {}",
+            checkpoint.path.display()
+        );
+        OpeningBook::build_exact_resumable(plies, &checkpoint, progress)?
+    } else {
+        println!("Building book: positions before ply {plies}, depth {depth}");
+        OpeningBook::build(plies, depth, progress)
+    };
+    println!();
+    book.save(&output)
+        .map_err(|e| format!("failed to write {output}: {e}"))?;
+    println!("Wrote {} positions to {output}", book.len());
+    Ok(())
+}
+
+/// Runs the `book` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("build") => build(&args[1..]),
+        Some(other) => Err(format!("unknown book command '{other}'")),
+        None => Err("missing book command".to_string()),
+    }
+}
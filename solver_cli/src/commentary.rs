@@ -0,0 +1,227 @@
+//! Remarks the AI makes after its moves (`--commentary PERSONA`).
+//!
+//! Each remark is derived from the position: a forced result found by
+//! search, lines the move blocked or opened (see
+//! `solver_core::heuristics::open_lines`), and how much the AI's
+//! evaluation changed since its previous move. The persona sets the tone.
+
+use solver_core::game::{BoardGame, Player};
+use solver_core::heuristics::open_lines;
+use solver_core::solvers::minimax::{WIN_SCORE, minimax_value_ab_depth_root};
+use solver_core::utils::opposite_player;
+
+/// Evaluation change (in heuristic units) worth remarking on.
+const SWING: i32 = 50;
+
+/// The voice of the commentary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Persona {
+    /// Friendly and explanatory.
+    Coach,
+    /// Smug.
+    Rival,
+}
+
+impl Persona {
+    /// Parses a persona name: `coach` or `rival`.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "coach" => Ok(Persona::Coach),
+            "rival" => Ok(Persona::Rival),
+            other => Err(format!(
+                "unknown persona '{other}' (expected coach or rival)"
+            )),
+        }
+    }
+
+    /// The name `parse` accepts.
+    pub fn name(self) -> &'static str {
+        match self {
+            Persona::Coach => "coach",
+            Persona::Rival => "rival",
+        }
+    }
+}
+
+/// What one AI move did, from the AI's point of view.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MoveFacts {
+    /// The AI wins by force in this many of its own moves.
+    pub win_in: Option<u32>,
+    /// The opponent wins by force in this many of their moves.
+    pub loss_in: Option<u32>,
+    /// Opponent lines one short of winning that the move filled.
+    pub blocked: usize,
+    /// The AI's lines one short of winning after the move.
+    pub threats: usize,
+    /// Change in the AI's evaluation since its previous move.
+    pub swing: i32,
+}
+
+/// The shallowest search depth (up to `max_depth`) that proves a forced
+/// result from `state`, with the winner.
+fn forced_result<G: BoardGame>(state: &G, max_depth: u32) -> Option<(Player, u32)> {
+    (1..=max_depth).find_map(|depth| match minimax_value_ab_depth_root(state, depth) {
+        WIN_SCORE => Some((Player::Player1, depth)),
+        v if v == -WIN_SCORE => Some((Player::Player2, depth)),
+        _ => None,
+    })
+}
+
+/// Collects the facts about the AI (`ai`) moving from `before` to `after`
+/// in a game won by `k` in a row.
+///
+/// `value` is the search value of the move and `previous` that of the AI's
+/// previous move, both from Player1's perspective. Forced results are
+/// searched for up to `depth` plies.
+pub fn move_facts<G: BoardGame>(
+    before: &G,
+    after: &G,
+    ai: Player,
+    k: usize,
+    value: i32,
+    previous: Option<i32>,
+    depth: u32,
+) -> MoveFacts {
+    let human = opposite_player(ai);
+    let sign = if ai == Player::Player1 { 1 } else { -1 };
+    let mut facts = MoveFacts {
+        blocked: open_lines(before, k, human, k - 1)
+            .len()
+            .saturating_sub(open_lines(after, k, human, k - 1).len()),
+        threats: open_lines(after, k, ai, k - 1).len(),
+        swing: previous.map_or(0, |p| (value - p).saturating_mul(sign)),
+        ..MoveFacts::default()
+    };
+    if !after.is_terminal() {
+        match forced_result(after, depth) {
+            // The opponent moves first: their n-th move is ply 2n - 1 and
+            // the AI's is ply 2n.
+            Some((winner, plies)) if winner == ai => facts.win_in = Some(plies.div_ceil(2)),
+            Some((_, plies)) => facts.loss_in = Some(plies.div_ceil(2)),
+            None => {}
+        }
+    }
+    facts
+}
+
+/// The remark for `facts`, if anything is worth saying.
+pub fn remark(persona: Persona, facts: &MoveFacts) -> Option<String> {
+    let moves = |n: u32| {
+        if n == 1 {
+            "1 move".to_string()
+        } else {
+            format!("{n} moves")
+        }
+    };
+    let text = match (persona, facts) {
+        (
+            Persona::Coach,
+            MoveFacts {
+                win_in: Some(n), ..
+            },
+        ) => {
+            format!("You're losing by force in {}, I'm afraid.", moves(*n))
+        }
+        (
+            Persona::Rival,
+            MoveFacts {
+                win_in: Some(n), ..
+            },
+        ) => {
+            format!("I win in {}. You might as well resign.", moves(*n))
+        }
+        (
+            Persona::Coach,
+            MoveFacts {
+                loss_in: Some(n), ..
+            },
+        ) => {
+            format!("You can force a win in {} - find it!", moves(*n))
+        }
+        (
+            Persona::Rival,
+            MoveFacts {
+                loss_in: Some(_), ..
+            },
+        ) => "Hmm. Beginner's luck, surely.".to_string(),
+        (Persona::Coach, MoveFacts { blocked: 1, .. }) => {
+            "That blocks your open line - watch for those.".to_string()
+        }
+        (Persona::Coach, MoveFacts { blocked, .. }) if *blocked > 1 => {
+            format!("That blocks {blocked} of your lines at once.")
+        }
+        (Persona::Rival, MoveFacts { blocked, .. }) if *blocked > 0 => {
+            "Nice try. Blocked.".to_string()
+        }
+        (Persona::Coach, MoveFacts { threats, .. }) if *threats > 1 => {
+            format!("Now I threaten to win in {threats} ways - can you stop them all?")
+        }
+        (Persona::Coach, MoveFacts { threats: 1, .. }) => {
+            "I'm threatening to complete a line.".to_string()
+        }
+        (Persona::Rival, MoveFacts { threats, .. }) if *threats > 0 => {
+            "Better look at that line of mine.".to_string()
+        }
+        (Persona::Coach, MoveFacts { swing, .. }) if *swing >= SWING => {
+            "Your last move gave me an opening.".to_string()
+        }
+        (Persona::Rival, MoveFacts { swing, .. }) if *swing >= SWING => {
+            "Thanks for that one.".to_string()
+        }
+        (_, MoveFacts { swing, .. }) if *swing <= -SWING => {
+            "That was a good move of yours.".to_string()
+        }
+        _ => return None,
+    };
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver_core::game::GameState;
+    use solver_core::games::ttt::{TicTacToeMove, TicTacToeState};
+
+    #[test]
+    fn ttt_blocking_move_is_noticed() {
+        // X threatens 0-1-2; O blocks at 2.
+        let before = TicTacToeState::from_str("XX..O....", Player::Player2).unwrap();
+        let after = before.apply_move(&TicTacToeMove { index: 2 });
+        let facts = move_facts(&before, &after, Player::Player2, 3, 0, None, 7);
+        assert_eq!(facts.blocked, 1);
+        assert_eq!((facts.win_in, facts.loss_in), (None, None));
+        assert!(remark(Persona::Coach, &facts).unwrap().contains("blocks"));
+    }
+
+    #[test]
+    fn ttt_forced_results_are_announced() {
+        // X takes the center; O must block 8, and X then forks with 6.
+        let before = TicTacToeState::from_str("XO.......", Player::Player1).unwrap();
+        let after = before.apply_move(&TicTacToeMove { index: 4 });
+        let facts = move_facts(&before, &after, Player::Player1, 3, WIN_SCORE, Some(0), 9);
+        assert_eq!(facts.win_in, Some(2));
+        assert_eq!(facts.threats, 1);
+        assert_eq!(
+            remark(Persona::Coach, &facts).as_deref(),
+            Some("You're losing by force in 2 moves, I'm afraid.")
+        );
+
+        // O answers X's corners with a corner, walking into a fork.
+        let fork = TicTacToeState::from_str("X...O...X", Player::Player2).unwrap();
+        let bad = fork.apply_move(&TicTacToeMove { index: 2 });
+        let facts = move_facts(&fork, &bad, Player::Player2, 3, 0, None, 9);
+        assert_eq!(facts.loss_in, Some(2));
+        assert_eq!(
+            remark(Persona::Coach, &facts).as_deref(),
+            Some("You can force a win in 2 moves - find it!")
+        );
+    }
+
+    #[test]
+    fn quiet_moves_get_no_remark() {
+        assert_eq!(remark(Persona::Rival, &MoveFacts::default()), None);
+        assert!(Persona::parse("coach").is_ok());
+        assert!(Persona::parse("pirate").is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! `completions`: shell completion scripts.
+//!
+//! `completions bash|zsh|fish` prints a script that completes the
+//! subcommands and, after one of them, the `--options` in its usage line,
+//! so the scripts keep up with the usage strings. For example:
+//! `solver_cli completions bash > ~/.local/share/bash-completion/completions/solver_cli`.
+
+use std::fmt::Write as _;
+
+pub const USAGE: &str = "usage: solver_cli completions bash|zsh|fish";
+
+/// The name the scripts complete.
+const PROGRAM: &str = "solver_cli";
+
+/// Every subcommand name (aliases included) with its usage line. `play`
+/// stands for the bare command too: its options are also completed as the
+/// first word.
+fn commands() -> Vec<(&'static str, &'static str)> {
+    #[allow(unused_mut)]
+    let mut commands = vec![
+        ("analyze", crate::analyze::USAGE),
+        ("analyze-file", crate::analyze::USAGE),
+        ("book", crate::book::USAGE),
+        ("completions", USAGE),
+        ("distributed", crate::distributed::USAGE),
+        ("fairness", crate::fairness::USAGE),
+        ("games", crate::games::USAGE),
+        ("match", crate::matches::USAGE),
+        ("play", play_usage()),
+        ("puzzle", crate::puzzle::USAGE),
+        ("replay", crate::replay::USAGE),
+        ("resume", crate::saved_game::USAGE),
+        ("serve", crate::serve::USAGE),
+        ("solve", crate::solve_exact::USAGE),
+        ("solve-exact", crate::solve_exact::USAGE),
+        ("stats", crate::stats::USAGE),
+        ("test-suite", crate::test_suite::USAGE),
+        ("tournament", crate::tournament::USAGE),
+        ("tune", crate::tune::USAGE),
+        ("verify", crate::verify::USAGE),
+    ];
+    #[cfg(feature = "tui")]
+    commands.push(("tui", crate::tui::USAGE));
+    commands
+}
+
+/// The part of the top-level usage line about `play`, before the other
+/// subcommands.
+fn play_usage() -> &'static str {
+    crate::USAGE.split(" | ").next().unwrap_or_default()
+}
+
+/// The `--options` mentioned in a usage line, in order, without repeats.
+fn options(usage: &str) -> Vec<&str> {
+    let mut options = Vec::new();
+    for word in usage.split(|c: char| c.is_whitespace() || "[]|,".contains(c)) {
+        if word.starts_with("--") && word.len() > 2 && !options.contains(&word) {
+            options.push(word);
+        }
+    }
+    options
+}
+
+fn bash() -> String {
+    let commands = commands();
+    let names: Vec<&str> = commands.iter().map(|(name, _)| *name).collect();
+    let mut out = String::new();
+    let _ = writeln!(out, "_{PROGRAM}() {{");
+    out.push_str("    local cur=${COMP_WORDS[COMP_CWORD]} opts\n");
+    out.push_str("    case ${COMP_WORDS[1]} in\n");
+    for (name, usage) in &commands {
+        let _ = writeln!(
+            out,
+            "        {name}) opts=\"{}\" ;;",
+            options(usage).join(" ")
+        );
+    }
+    let _ = writeln!(
+        out,
+        "        *) opts=\"{}\" ;;",
+        options(play_usage()).join(" ")
+    );
+    out.push_str("    esac\n");
+    let _ = writeln!(
+        out,
+        "    if [[ $COMP_CWORD -eq 1 && $cur != -* ]]; then\n        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+        names.join(" ")
+    );
+    out.push_str("    elif [[ $cur == -* ]]; then\n        COMPREPLY=($(compgen -W \"$opts\" -- \"$cur\"))\n");
+    out.push_str("    else\n        COMPREPLY=($(compgen -f -- \"$cur\"))\n    fi\n}\n");
+    let _ = writeln!(out, "complete -F _{PROGRAM} {PROGRAM}");
+    out
+}
+
+fn zsh() -> String {
+    let commands = commands();
+    let names: Vec<&str> = commands.iter().map(|(name, _)| *name).collect();
+    let mut out = String::new();
+    let _ = writeln!(out, "#compdef {PROGRAM}\n");
+    let _ = writeln!(out, "_{PROGRAM}() {{");
+    out.push_str("    local -a opts\n");
+    out.push_str("    if (( CURRENT == 2 )) && [[ $PREFIX != -* ]]; then\n");
+    let _ = writeln!(out, "        compadd -- {}", names.join(" "));
+    out.push_str("        return\n    fi\n");
+    out.push_str("    case $words[2] in\n");
+    for (name, usage) in &commands {
+        let _ = writeln!(
+            out,
+            "        {name}) opts=({}) ;;",
+            options(usage).join(" ")
+        );
+    }
+    let _ = writeln!(
+        out,
+        "        *) opts=({}) ;;",
+        options(play_usage()).join(" ")
+    );
+    out.push_str("    esac\n");
+    out.push_str("    if [[ $PREFIX == -* ]]; then\n        compadd -a opts\n    else\n        _files\n    fi\n}\n\n");
+    let _ = writeln!(out, "_{PROGRAM} \"$@\"");
+    out
+}
+
+fn fish() -> String {
+    let commands = commands();
+    let names: Vec<&str> = commands.iter().map(|(name, _)| *name).collect();
+    let mut out = String::new();
+    let long_options = |usage| -> String {
+        options(usage)
+            .iter()
+            .map(|option| format!(" -l {}", &option[2..]))
+            .collect()
+    };
+    let _ = writeln!(
+        out,
+        "complete -c {PROGRAM} -n __fish_use_subcommand -f -a \"{}\"",
+        names.join(" ")
+    );
+    let _ = writeln!(
+        out,
+        "complete -c {PROGRAM} -n __fish_use_subcommand{}",
+        long_options(play_usage())
+    );
+    for (name, usage) in &commands {
+        let long = long_options(usage);
+        if !long.is_empty() {
+            let _ = writeln!(
+                out,
+                "complete -c {PROGRAM} -n \"__fish_seen_subcommand_from {name}\"{long}"
+            );
+        }
+    }
+    out
+}
+
+/// Runs the `completions` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let script = match args {
+        [shell] if shell == "bash" => bash(),
+        [shell] if shell == "zsh" => zsh(),
+        [shell] if shell == "fish" => fish(),
+        [shell] => return Err(format!("unknown shell '{shell}'")),
+        [] => return Err("missing shell".to_string()),
+        [_, other, ..] => return Err(format!("unexpected argument '{other}'")),
+    };
+    print!("{script}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_options_in_usage_lines() {
+        assert_eq!(
+            options(crate::solve_exact::USAGE),
+            [
+                "--position",
+                "--game",
+                "--tt",
+                "--no-book",
+                "--checkpoint",
+                "--resume"
+            ]
+        );
+        assert_eq!(options(crate::games::USAGE), Vec::<&str>::new());
+        let play = options(play_usage());
+        assert!(play.contains(&"--seed") && play.contains(&"--autosave"));
+        assert!(!play.contains(&"--depth"));
+    }
+
+    #[test]
+    fn scripts_cover_every_command() {
+        for script in [bash(), zsh(), fish()] {
+            for (name, _) in commands() {
+                assert!(script.contains(name), "{name} missing from\n{script}");
+            }
+            assert!(script.contains("sprt-alpha"));
+        }
+        assert!(bash().ends_with("complete -F _solver_cli solver_cli\n"));
+        assert!(fish().contains("-n \"__fish_seen_subcommand_from match\" -l a -l b"));
+        assert!(run(&["tcsh".to_string()]).is_err());
+        assert!(run(&[]).is_err());
+    }
+}
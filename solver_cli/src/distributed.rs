@@ -0,0 +1,154 @@
+//! `distributed`: experimental searches spread over several machines.
+//!
+//! `distributed worker` answers search jobs over TCP (see
+//! `solver_core::distributed`). `distributed search` splits the root moves
+//! of a position among the `--workers` and prints the merged result; list
+//! a worker several times to keep several of its cores busy. A worker that
+//! hasn't answered a move after `--timeout` seconds (ten minutes by
+//! default) is dropped and its move handed to the others.
+
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+use solver_core::distributed::{DEFAULT_JOB_TIMEOUT, distributed_search, serve};
+use solver_core::registry::Registry;
+
+use crate::args;
+
+pub const USAGE: &str = "usage: solver_cli distributed worker [--listen HOST:PORT] | distributed search --workers HOST:PORT,... --depth N [--game NAME] [--position MOVES] [--timeout SECS]";
+
+/// Address a worker listens on without `--listen`.
+const DEFAULT_LISTEN: &str = "127.0.0.1:7878";
+
+#[derive(Debug, PartialEq)]
+struct SearchOptions {
+    workers: Vec<String>,
+    depth: u32,
+    game: String,
+    position: String,
+    /// How long to wait for a worker's answer.
+    timeout: Duration,
+}
+
+fn parse_search_options(args: &[String]) -> Result<SearchOptions, String> {
+    let mut opts = SearchOptions {
+        workers: Vec::new(),
+        depth: 0,
+        game: "c4".to_string(),
+        position: String::new(),
+        timeout: DEFAULT_JOB_TIMEOUT,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| args::value(name, iter.next());
+        match arg.as_str() {
+            "--workers" => {
+                opts.workers = value("--workers")?
+                    .split(',')
+                    .filter(|w| !w.is_empty())
+                    .map(String::from)
+                    .collect()
+            }
+            "--depth" => {
+                let depth = value("--depth")?;
+                opts.depth = depth
+                    .parse()
+                    .ok()
+                    .filter(|&d| d > 0)
+                    .ok_or_else(|| format!("invalid depth '{depth}'"))?;
+            }
+            "--game" => opts.game = value("--game")?,
+            "--position" => opts.position = value("--position")?,
+            "--timeout" => {
+                let secs: u64 = args::parse_number(arg, iter.next())?;
+                opts.timeout = Duration::from_secs(secs.max(1));
+            }
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    if opts.workers.is_empty() {
+        return Err("missing --workers".to_string());
+    }
+    if opts.depth == 0 {
+        return Err("missing --depth".to_string());
+    }
+    Ok(opts)
+}
+
+fn worker(args: &[String]) -> Result<(), String> {
+    let addr = match args {
+        [] => DEFAULT_LISTEN,
+        [flag, addr] if flag == "--listen" => addr,
+        _ => return Err("expected at most --listen HOST:PORT".to_string()),
+    };
+    let listener = TcpListener::bind(addr).map_err(|e| format!("failed to bind {addr}: {e}"))?;
+    println!("Worker listening on {addr}");
+    serve(listener, &Registry::builtin()).map_err(|e| format!("worker stopped: {e}"))
+}
+
+fn search(args: &[String]) -> Result<(), String> {
+    let opts = parse_search_options(args)?;
+    let start = Instant::now();
+    let result = distributed_search(
+        &Registry::builtin(),
+        &opts.game,
+        &opts.position,
+        opts.depth,
+        &opts.workers,
+        opts.timeout,
+    )?;
+    for (mv, value) in &result.lines {
+        println!("{mv:>6}  {value}");
+    }
+    match result.best_move() {
+        Some(mv) => println!("Best move {mv}"),
+        None => println!("The game is over"),
+    }
+    println!(
+        "{} nodes on {} workers in {:.1}s",
+        result.nodes,
+        opts.workers.len(),
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Runs the `distributed` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("worker") => worker(&args[1..]),
+        Some("search") => search(&args[1..]),
+        Some(other) => Err(format!("unknown distributed command '{other}'")),
+        None => Err("missing distributed command".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::split_args;
+
+    #[test]
+    fn parses_search_options() {
+        let opts =
+            parse_search_options(&split_args("--workers a:1,b:2,a:1 --depth 9 --position 33"))
+                .unwrap();
+        assert_eq!(
+            opts,
+            SearchOptions {
+                workers: vec!["a:1".to_string(), "b:2".to_string(), "a:1".to_string()],
+                depth: 9,
+                game: "c4".to_string(),
+                position: "33".to_string(),
+                timeout: DEFAULT_JOB_TIMEOUT,
+            }
+        );
+        let opts = parse_search_options(&split_args("--workers a:1 --depth 1 --timeout 30"));
+        assert_eq!(opts.unwrap().timeout, Duration::from_secs(30));
+        assert!(parse_search_options(&split_args("--depth 9")).is_err());
+        assert!(parse_search_options(&split_args("--workers a:1")).is_err());
+        assert!(parse_search_options(&split_args("--workers a:1 --depth 0")).is_err());
+        assert!(run(&split_args("coordinate")).is_err());
+        assert!(worker(&split_args("--port 1")).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! `fairness`: how much moving first is worth in a game.
+//!
+//! Searches every opening move from the starting position and reports the
+//! value of moving first, the best openings, the second player's best
+//! replies to each opening, and how the game fares under the pie (swap)
+//! rule (see `solver_core::fairness`). Useful when comparing variants:
+//! reports at the same depth are comparable even when not exact.
+//!
+//! Without `--depth`, the game's recommended depth is used (see
+//! `GameState::recommended_depth`); Tic-Tac-Toe is then solved exactly.
+
+use std::fmt::Write as _;
+
+use solver_core::fairness::{FairnessReport, opening_fairness};
+use solver_core::game::{GameState, Outcome};
+use solver_core::games::c4_bitboard::BitboardState;
+use solver_core::games::ttt::TicTacToeState;
+
+use crate::analyze::json_string;
+use crate::args;
+
+pub const USAGE: &str =
+    "usage: solver_cli fairness [--game c4|ttt] [--depth N] [--format text|json]";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    ttt: bool,
+    depth: Option<u32>,
+    json: bool,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        ttt: false,
+        depth: None,
+        json: false,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| args::value(name, iter.next());
+        match arg.as_str() {
+            "--game" => {
+                opts.ttt = match value("--game")?.as_str() {
+                    "c4" => false,
+                    "ttt" => true,
+                    other => return Err(format!("unknown game '{other}'")),
+                }
+            }
+            "--depth" => {
+                let depth = value("--depth")?;
+                opts.depth = Some(
+                    depth
+                        .parse()
+                        .ok()
+                        .filter(|&d| d > 0)
+                        .ok_or_else(|| format!("invalid depth '{depth}'"))?,
+                );
+            }
+            "--format" => {
+                opts.json = match value("--format")?.as_str() {
+                    "text" => false,
+                    "json" => true,
+                    other => return Err(format!("unknown format '{other}'")),
+                }
+            }
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    Ok(opts)
+}
+
+/// A value as a result if it is proven, e.g. "Player1 wins", or as a
+/// signed score.
+fn describe_value<M>(report: &FairnessReport<M>, value: i32) -> String {
+    match report.proven(value) {
+        Some(Outcome::Win(p)) => format!("{p:?} wins"),
+        Some(Outcome::Draw) => "draw".to_string(),
+        None => format!("{value:+}"),
+    }
+}
+
+fn join<M>(moves: impl IntoIterator<Item = M>, fmt_move: impl Fn(M) -> String) -> String {
+    let moves: Vec<String> = moves.into_iter().map(fmt_move).collect();
+    moves.join(" ")
+}
+
+/// The report as a table of openings under a short summary.
+fn report_text<M>(
+    name: &str,
+    report: &FairnessReport<M>,
+    fmt_move: impl Fn(&M) -> String,
+) -> String {
+    let mut out = String::new();
+    let kind = if report.exact { "exact" } else { "estimated" };
+    let _ = writeln!(
+        out,
+        "{name} opening fairness (depth {}, {kind})",
+        report.depth
+    );
+    let _ = writeln!(
+        out,
+        "Start: {} with {:?} to move (first-move advantage {:+})",
+        describe_value(report, report.value),
+        report.first_player,
+        report.first_move_advantage()
+    );
+    let _ = writeln!(
+        out,
+        "Best openings: {}",
+        join(report.best_openings(), &fmt_move)
+    );
+    let _ = writeln!(
+        out,
+        "Pie rule: open with {} (worth {:+} to the opener)",
+        join(report.fairest_openings(), &fmt_move),
+        report.pie_value()
+    );
+    let _ = writeln!(out, "\nopening  value         best replies");
+    for line in &report.openings {
+        let _ = writeln!(
+            out,
+            "{:<7}  {:<12}  {}",
+            fmt_move(&line.opening),
+            describe_value(report, line.value),
+            join(&line.replies, &fmt_move)
+        );
+    }
+    out
+}
+
+/// The report as one JSON object; moves are strings in the game's
+/// notation.
+fn report_json<M>(
+    name: &str,
+    report: &FairnessReport<M>,
+    fmt_move: impl Fn(&M) -> String,
+) -> String {
+    let moves = |moves: Vec<&M>| {
+        let moves: Vec<String> = moves
+            .into_iter()
+            .map(|m| json_string(&fmt_move(m)))
+            .collect();
+        format!("[{}]", moves.join(", "))
+    };
+    let outcome = |value| match report.proven(value) {
+        Some(Outcome::Win(p)) => json_string(&format!("{p:?}")),
+        Some(Outcome::Draw) => json_string("draw"),
+        None => "null".to_string(),
+    };
+    let mut out = String::new();
+    let _ = writeln!(out, "{{");
+    let _ = writeln!(out, "  \"game\": {},", json_string(name));
+    let _ = writeln!(out, "  \"depth\": {},", report.depth);
+    let _ = writeln!(out, "  \"exact\": {},", report.exact);
+    let _ = writeln!(
+        out,
+        "  \"first_player\": {},",
+        json_string(&format!("{:?}", report.first_player))
+    );
+    let _ = writeln!(out, "  \"value\": {},", report.value);
+    let _ = writeln!(out, "  \"outcome\": {},", outcome(report.value));
+    let _ = writeln!(
+        out,
+        "  \"first_move_advantage\": {},",
+        report.first_move_advantage()
+    );
+    let _ = writeln!(
+        out,
+        "  \"best_openings\": {},",
+        moves(report.best_openings())
+    );
+    let _ = writeln!(out, "  \"pie_value\": {},", report.pie_value());
+    let _ = writeln!(
+        out,
+        "  \"fairest_openings\": {},",
+        moves(report.fairest_openings())
+    );
+    let _ = writeln!(out, "  \"openings\": [");
+    for (i, line) in report.openings.iter().enumerate() {
+        let sep = if i + 1 < report.openings.len() {
+            ","
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            out,
+            "    {{\"opening\": {}, \"value\": {}, \"outcome\": {}, \"replies\": {}}}{sep}",
+            json_string(&fmt_move(&line.opening)),
+            line.value,
+            outcome(line.value),
+            moves(line.replies.iter().collect())
+        );
+    }
+    let _ = writeln!(out, "  ]");
+    let _ = writeln!(out, "}}");
+    out
+}
+
+/// Measures `start` and formats the report as asked.
+fn fairness<G: GameState>(
+    name: &str,
+    start: &G,
+    opts: &Options,
+    fmt_move: impl Fn(&G::Move) -> String,
+) -> Result<String, String> {
+    let depth = opts.depth.unwrap_or_else(|| start.recommended_depth());
+    crate::warn_if_impractical(start, depth);
+    let report = opening_fairness(start, depth).ok_or("the game has no opening moves")?;
+    Ok(if opts.json {
+        report_json(name, &report, fmt_move)
+    } else {
+        report_text(name, &report, fmt_move)
+    })
+}
+
+/// Runs the `fairness` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let opts = parse_options(args)?;
+    let out = if opts.ttt {
+        fairness("Tic-Tac-Toe", &TicTacToeState::new(), &opts, |m| {
+            m.index.to_string()
+        })?
+    } else {
+        fairness("Connect Four", &BitboardState::new(), &opts, |c| {
+            c.to_string()
+        })?
+    };
+    print!("{out}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::split_args;
+
+    #[test]
+    fn parses_options() {
+        assert_eq!(
+            parse_options(&split_args("--game ttt --depth 4 --format json")),
+            Ok(Options {
+                ttt: true,
+                depth: Some(4),
+                json: true,
+            })
+        );
+        assert!(parse_options(&split_args("--depth 0")).is_err());
+        assert!(parse_options(&split_args("--format xml")).is_err());
+        assert!(parse_options(&split_args("--game go")).is_err());
+    }
+
+    #[test]
+    fn reports_tic_tac_toe_as_a_fair_draw() {
+        let opts = parse_options(&split_args("--game ttt")).unwrap();
+        let fmt = |m: &solver_core::games::ttt::TicTacToeMove| m.index.to_string();
+        let text = fairness("Tic-Tac-Toe", &TicTacToeState::new(), &opts, fmt).unwrap();
+        assert!(text.starts_with("Tic-Tac-Toe opening fairness (depth 9, exact)\n"));
+        assert!(text.contains("Start: draw with Player1 to move (first-move advantage +0)"));
+        assert!(text.contains("\n0        draw          4\n"), "{text}");
+
+        let opts = Options { json: true, ..opts };
+        let json = fairness("Tic-Tac-Toe", &TicTacToeState::new(), &opts, fmt).unwrap();
+        assert!(json.contains("\"outcome\": \"draw\","));
+        assert!(json.contains(
+            "{\"opening\": \"0\", \"value\": 0, \"outcome\": \"draw\", \"replies\": [\"4\"]},"
+        ));
+    }
+}
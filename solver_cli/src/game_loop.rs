@@ -0,0 +1,335 @@
+//! `play --game NAME`: interactive games of any registered game (see
+//! `games`) against the engine, driven through `DynGameState`.
+//!
+//! Tic-Tac-Toe keeps its own loop in `main`, whose time controls,
+//! commentary, teaching and reviews need the game's concrete type. Other
+//! games are played at their default depth, with the board drawn and
+//! described as in that loop, moves submitted through a `Referee` whose
+//! events a `Narrator` reports, and a rematch offered after each game.
+//! They are saved, autosaved and resumed like Tic-Tac-Toe games (see
+//! `saved_game`), and `--seed` makes the engine pick at random between
+//! equally good moves.
+
+use std::fs;
+use std::time::Duration;
+
+use solver_core::dyn_game::{DynGameState, DynMove};
+use solver_core::game::Player;
+use solver_core::referee::{Referee, Submission, Verdict};
+use solver_core::registry::GameEntry;
+use solver_core::render::{Labels, Layout};
+use solver_core::rng::{Rng, SplitMix64};
+
+use crate::line_editor::LineEditor;
+use crate::messages::{self, Message};
+use crate::narrator::Narrator;
+use crate::profile::{Profile, default_player_name};
+use crate::saved_game::SavedGame;
+use crate::session::{GameRecord, Session, ask_yes_no_stdin};
+use crate::stats::GameResult;
+use crate::theme::Theme;
+use crate::{DEFAULT_AUTOSAVE_MOVES, PlayOptions, TIE_BREAK_STREAM};
+
+/// Returns an error naming the first option of `opts` that only
+/// Tic-Tac-Toe games support.
+fn check_options(opts: &PlayOptions) -> Result<(), String> {
+    let unsupported = [
+        ("--time", opts.time.is_some()),
+        ("--commentary", opts.commentary.is_some()),
+        ("--verbose", opts.verbose),
+        ("--adaptive", opts.adaptive),
+        ("--teach", opts.teach.is_some()),
+    ];
+    match unsupported.iter().find(|(_, set)| *set) {
+        Some((name, _)) => Err(format!("{name} is only available for Tic-Tac-Toe")),
+        None => Ok(()),
+    }
+}
+
+/// The moves of `state`'s game so far, separated by spaces.
+fn record(state: &dyn DynGameState) -> String {
+    let moves: Vec<String> = state
+        .history()
+        .iter()
+        .map(|mv| mv.notation().to_string())
+        .collect();
+    moves.join(" ")
+}
+
+/// Plays one game of `entry` against a depth-`depth` engine, the human
+/// playing `human`, reading moves with `editor`.
+///
+/// Typing `save [FILE]` instead of a move saves the game and ends it, and
+/// the game is autosaved as it goes (see `saved_game`); with `saved`, the
+/// game carries on from such a save. `hint` and `undo` work as in the
+/// Tic-Tac-Toe loop.
+///
+/// Returns how the game went for the human, or `None` if it was abandoned
+/// or saved.
+fn play_game(
+    entry: &GameEntry,
+    human: Player,
+    depth: u32,
+    opts: &PlayOptions,
+    editor: &mut LineEditor,
+    saved: Option<SavedGame>,
+) -> Option<GameRecord> {
+    let mut human_moves = 0;
+    let mut rng = opts
+        .seed
+        .map(|seed| SplitMix64::for_stream(seed, TIE_BREAK_STREAM));
+    let start = match saved {
+        Some(saved) => {
+            human_moves = saved.human_moves;
+            rng = saved.rng.map(SplitMix64::new).or(rng);
+            saved
+                .position()
+                .expect("saved games are checked when loaded")
+        }
+        None => (entry.new_game)(),
+    };
+    let mut referee = Referee::new(start);
+    let name = opts.player.clone().unwrap_or_else(default_player_name);
+    let autosave_path = SavedGame::autosave_path(&name);
+    let snapshot = |state: &dyn DynGameState, rng: &Option<SplitMix64>, human_moves| SavedGame {
+        opts: PlayOptions {
+            game: Some(entry.name.to_string()),
+            ..opts.clone()
+        },
+        human_is_player1: human == Player::Player1,
+        depth,
+        level: None,
+        moves: state
+            .history()
+            .iter()
+            .map(|mv| mv.notation().to_string())
+            .collect(),
+        human_moves,
+        human_seconds: 0.0,
+        clock: None,
+        rng: rng.as_ref().map(SplitMix64::state),
+    };
+    let mut narrator = Narrator::new(human, |mv: &DynMove, value| {
+        messages::text(&Message::AiPlays(mv.notation(), value))
+    });
+    let (theme, warning) = Theme::load_default();
+    if let Some(e) = warning {
+        eprintln!("warning: {e}; using the default theme");
+    }
+    let layout = opts.coords.map_or_else(Layout::default, |labels| {
+        referee.state().coordinates(labels)
+    });
+    let layout = if opts.flip { layout.flip() } else { layout };
+    let show_board = |state: &dyn DynGameState| {
+        print!("{}", theme.render(&state.clone_box(), &layout));
+        if opts.describe {
+            let labels = opts.coords.unwrap_or(Labels::Numbers);
+            print!(
+                "\n{}",
+                state.describe_in(labels, messages::catalog().words())
+            );
+        }
+    };
+
+    println!(
+        "{}",
+        messages::text(&Message::WelcomeTo(entry.display_name))
+    );
+    println!("{}", messages::text(&Message::YouAre(human)));
+    println!();
+    'game: while !referee.is_over() {
+        let state = referee.state().clone_box();
+        show_board(&*state);
+        println!();
+        let current = state.current_player();
+        if current == human {
+            println!("{}", messages::text(&Message::YourTurn(current, None)));
+            let moves = state.legal_moves().join(" ");
+            let prompt = messages::text(&Message::MovePromptFor(&moves));
+            loop {
+                let Some(input) = editor.read_line(&prompt) else {
+                    println!("\n{}", messages::text(&Message::EndOfInput));
+                    let record = record(&*state);
+                    println!("{}", messages::text(&Message::Record(&record)));
+                    return None;
+                };
+                if input.trim().is_empty() {
+                    continue;
+                }
+                if let Some(file) = input
+                    .trim()
+                    .strip_prefix("save")
+                    .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+                {
+                    let path = match file.trim() {
+                        "" => SavedGame::path(&name),
+                        file => file.into(),
+                    };
+                    match snapshot(&*state, &rng, human_moves).save(&path) {
+                        Ok(()) => {
+                            let _ = fs::remove_file(&autosave_path);
+                            let path = path.display().to_string();
+                            println!("{}", messages::text(&Message::Saved(&path)));
+                            return None;
+                        }
+                        Err(e) => {
+                            println!("{e}");
+                            continue;
+                        }
+                    }
+                }
+                match input.trim() {
+                    "hint" => {
+                        let result = state.search(depth, None);
+                        if let (Some(mv), Some(value)) = (result.best_move(), result.value()) {
+                            println!("{}", messages::text(&Message::Hint(mv.notation(), value)));
+                        }
+                        continue;
+                    }
+                    "undo" => {
+                        // Takes back the human's last move and the AI's reply.
+                        let history = state.history();
+                        if history.len() < 2 {
+                            println!("{}", messages::text(&Message::NothingToUndo));
+                            continue;
+                        }
+                        let kept = &history[..history.len() - 2];
+                        let previous = kept
+                            .iter()
+                            .try_fold((entry.new_game)(), |state, mv| state.apply(mv));
+                        referee = Referee::new(previous.expect("moves played replay"));
+                        human_moves = human_moves.saturating_sub(1);
+                        println!("{}", messages::text(&Message::Undone));
+                        continue 'game;
+                    }
+                    _ => {}
+                }
+                let submitted = state.parse_move(input.trim()).map_err(|e| e.to_string());
+                let event = submitted.map(|mv| {
+                    let submission = Submission {
+                        player: current,
+                        ply: referee.ply(),
+                        mv,
+                    };
+                    referee.submit_observed(submission, None, Duration::ZERO, &mut narrator)
+                });
+                match event.map(|event| event.verdict) {
+                    Ok(Verdict::Accepted { .. }) => {
+                        human_moves += 1;
+                        break;
+                    }
+                    Ok(Verdict::Rejected { reason, .. }) => {
+                        let reason = reason.to_string();
+                        println!("{}", messages::text(&Message::InvalidMove(&reason)));
+                    }
+                    Err(reason) => {
+                        println!("{}", messages::text(&Message::InvalidMove(&reason)));
+                    }
+                }
+            }
+        } else {
+            println!("{}", messages::text(&Message::AiThinking(current)));
+            // With a seed, the engine picks at random between its best moves.
+            let best = match &mut rng {
+                Some(rng) => state
+                    .best_moves(depth)
+                    .and_then(|(moves, value)| Some((rng.choose(&moves)?.clone(), value))),
+                None => {
+                    let result = state.search(depth, None);
+                    result.best_move().cloned().zip(result.value())
+                }
+            };
+            let Some((mv, value)) = best else {
+                println!("{}", messages::text(&Message::AiHasNoMoves));
+                break;
+            };
+            let submission = Submission {
+                player: current,
+                ply: referee.ply(),
+                mv,
+            };
+            let event =
+                referee.submit_observed(submission, Some(value), Duration::ZERO, &mut narrator);
+            assert!(event.is_accepted(), "the engine plays legal moves");
+        }
+
+        let every = opts.autosave.unwrap_or(DEFAULT_AUTOSAVE_MOVES) as usize;
+        let moves = referee.state().history().len();
+        if every > 0 && moves % every == 0 && !referee.is_over() {
+            let game = snapshot(referee.state().as_ref(), &rng, human_moves);
+            if let Err(e) = game.save(&autosave_path) {
+                eprintln!("warning: autosave failed: {e}");
+            }
+        }
+    }
+    let _ = fs::remove_file(&autosave_path);
+
+    // The narrator has given the result; show how the game ended.
+    show_board(referee.state().as_ref());
+    let moves = record(referee.state().as_ref());
+    println!("{}", messages::text(&Message::Record(&moves)));
+    let Some(outcome) = referee.outcome() else {
+        println!("{}", messages::text(&Message::Abandoned));
+        return None;
+    };
+    let winner = outcome.winner();
+    Some(GameRecord {
+        result: GameResult::for_player(human, winner),
+        moves: human_moves,
+        seconds: 0.0,
+    })
+}
+
+/// Plays games of `entry` against the engine until the human declines a
+/// rematch, recording each in the player's profile, starting from `saved`
+/// if given.
+///
+/// A saved game brings its own options, but the scripted `opts.moves` are
+/// played in it all the same.
+pub fn play_session(
+    entry: &GameEntry,
+    opts: &PlayOptions,
+    mut saved: Option<SavedGame>,
+) -> Result<(), String> {
+    let mut editor = LineEditor::with_script(opts.moves.clone());
+    let opts = &saved
+        .as_ref()
+        .map_or_else(|| opts.clone(), |saved| saved.opts.clone());
+    check_options(opts)?;
+    let name = opts.player.clone().unwrap_or_else(default_player_name);
+    let mut session = match &saved {
+        Some(saved) => Session::new(saved.human_is_player1, saved.depth, None),
+        None => Session::new(!opts.ai_first, entry.default_depth, None),
+    };
+    loop {
+        let human = if session.human_first {
+            Player::Player1
+        } else {
+            Player::Player2
+        };
+        let depth = session.depth();
+        let saved = saved.take();
+        let Some(game) = play_game(entry, human, depth, opts, &mut editor, saved) else {
+            break;
+        };
+        session.record(entry.name, &game);
+        let path = Profile::path(&name);
+        match Profile::load(&path) {
+            Ok(mut profile) => {
+                profile
+                    .stats
+                    .record(entry.name, depth, game.result, game.moves, game.seconds);
+                if let Err(e) = profile.save(&path) {
+                    eprintln!("warning: {e}");
+                }
+            }
+            Err(e) => eprintln!("warning: {e}; not updating the profile"),
+        }
+        println!("\n{}", session.summary());
+        if !ask_yes_no_stdin(&messages::text(&Message::PlayAgain)) {
+            break;
+        }
+        println!();
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! `games`: lists the games the engine can play, with the names the other
+//! subcommands accept for `--game`.
+
+use std::fmt::Write as _;
+
+use solver_core::registry::Registry;
+
+pub const USAGE: &str = "usage: solver_cli games";
+
+/// One line per registered game: name, display name and default depth.
+fn games_table(registry: &Registry) -> String {
+    let width = registry.names().iter().map(|n| n.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for game in registry.games() {
+        let _ = writeln!(
+            out,
+            "{:<width$}  {} (default depth {})",
+            game.name, game.display_name, game.default_depth
+        );
+    }
+    out
+}
+
+/// Runs the `games` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    if let Some(other) = args.first() {
+        return Err(format!("unexpected argument '{other}'"));
+    }
+    print!("{}", games_table(&Registry::builtin()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_every_builtin_game() {
+        assert_eq!(
+            games_table(&Registry::builtin()),
+            "ttt           Tic-Tac-Toe (default depth 9)\n\
+             c4            Connect Four (default depth 8)\n\
+             c4-8x7        Connect Four 8x7 (default depth 8)\n\
+             score4        Score Four (default depth 6)\n\
+             pentago       Pentago (default depth 2)\n\
+             breakthrough  Breakthrough (default depth 4)\n"
+        );
+        assert!(run(&["--all".to_string()]).is_err());
+    }
+}
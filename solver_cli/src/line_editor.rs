@@ -0,0 +1,299 @@
+//! Reading commands typed during a game.
+//!
+//! `LineEditor` reads one line at a time and keeps a history of them. With
+//! the `line-editing` feature, when both ends are a terminal, the line is
+//! edited in place: Left/Right, Home/End (Ctrl-A/Ctrl-E), Backspace and
+//! Delete work as in a shell, Up/Down walk the history, and Ctrl-D on an
+//! empty line or Ctrl-C ends the input. Otherwise lines are read as they
+//! come, which suits piped input, and `!!` repeats the previous one. Either
+//! way the end of the input is reported as such rather than as an empty
+//! line.
+//!
+//! A script of lines can be given up front, for automated games: they are
+//! read (and echoed after the prompt) before anything is read from the
+//! input.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+
+/// Typed instead of a line to repeat the previous one.
+const REPEAT: &str = "!!";
+
+/// Reads lines, remembering the non-empty ones.
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    /// The lines read so far, oldest first, without repeats in a row.
+    history: Vec<String>,
+    /// Lines still to be read before the input.
+    script: VecDeque<String>,
+}
+
+impl LineEditor {
+    /// An editor that reads `script` before its input.
+    pub fn with_script(script: Vec<String>) -> Self {
+        Self {
+            script: script.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Prints `prompt` and reads a line from the terminal (see the module
+    /// docs), without its line ending. Returns `None` at the end of input.
+    pub fn read_line(&mut self, prompt: &str) -> Option<String> {
+        if !self.script.is_empty() {
+            return self.read_line_from(prompt, &mut io::empty(), &mut io::stdout());
+        }
+        #[cfg(feature = "line-editing")]
+        {
+            use std::io::IsTerminal;
+            if io::stdin().is_terminal() && io::stdout().is_terminal() {
+                // Without raw mode the plain reading below still works.
+                if let Ok(line) = editing::read_line(prompt, &self.history) {
+                    return line.map(|line| self.remember(line));
+                }
+            }
+        }
+        self.read_line_from(prompt, &mut io::stdin().lock(), &mut io::stdout())
+    }
+
+    /// Prints `prompt` to `output` and reads a line from the script or else
+    /// `input`, replacing `!!` by the previous line. Returns `None` at the
+    /// end of input.
+    pub fn read_line_from(
+        &mut self,
+        prompt: &str,
+        input: &mut impl BufRead,
+        output: &mut impl Write,
+    ) -> Option<String> {
+        write!(output, "{prompt}").ok();
+        if let Some(line) = self.script.pop_front() {
+            writeln!(output, "{line}").ok();
+            return Some(self.remember(line));
+        }
+        output.flush().ok();
+        let mut bytes = Vec::new();
+        match input.read_until(b'\n', &mut bytes) {
+            Ok(0) | Err(_) => return None,
+            Ok(_) => {}
+        }
+        let line = String::from_utf8_lossy(&bytes);
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.trim() == REPEAT {
+            let previous = self.history.last().cloned().unwrap_or_default();
+            writeln!(output, "{previous}").ok();
+            return Some(previous);
+        }
+        Some(self.remember(line.to_string()))
+    }
+
+    /// Adds `line` to the history unless it is blank or the same as the
+    /// last one, and returns it.
+    fn remember(&mut self, line: String) -> String {
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        line
+    }
+}
+
+/// In-place editing on a terminal in raw mode.
+#[cfg(feature = "line-editing")]
+mod editing {
+    use std::io::{self, Write};
+
+    use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+    use crossterm::terminal::{self, ClearType};
+    use crossterm::{cursor, queue};
+
+    /// A line being edited, with its cursor and its place in the history.
+    pub(super) struct Line<'a> {
+        chars: Vec<char>,
+        cursor: usize,
+        history: &'a [String],
+        /// The history entry shown, or `history.len()` for the new line.
+        recalled: usize,
+        /// The new line, kept while a history entry is shown.
+        draft: Vec<char>,
+    }
+
+    impl<'a> Line<'a> {
+        pub(super) fn new(history: &'a [String]) -> Self {
+            Self {
+                chars: Vec::new(),
+                cursor: 0,
+                history,
+                recalled: history.len(),
+                draft: Vec::new(),
+            }
+        }
+
+        pub(super) fn text(&self) -> String {
+            self.chars.iter().collect()
+        }
+
+        /// Shows history entry `index`, or the new line at `history.len()`.
+        fn recall(&mut self, index: usize) {
+            if self.recalled == self.history.len() {
+                self.draft = self.chars.clone();
+            }
+            self.recalled = index;
+            self.chars = match self.history.get(index) {
+                Some(entry) => entry.chars().collect(),
+                None => self.draft.clone(),
+            };
+            self.cursor = self.chars.len();
+        }
+
+        /// Applies a key press. Returns `Some` once the line is finished:
+        /// the line on Enter, `None` if the input ended.
+        pub(super) fn key(&mut self, key: KeyEvent) -> Option<Option<String>> {
+            let control = key.modifiers.contains(KeyModifiers::CONTROL);
+            match (key.code, control) {
+                (KeyCode::Enter, _) => return Some(Some(self.text())),
+                (KeyCode::Char('c'), true) => return Some(None),
+                (KeyCode::Char('d'), true) if self.chars.is_empty() => return Some(None),
+                (KeyCode::Char('d'), true) | (KeyCode::Delete, _)
+                    if self.cursor < self.chars.len() =>
+                {
+                    self.chars.remove(self.cursor);
+                }
+                (KeyCode::Char('a'), true) | (KeyCode::Home, _) => self.cursor = 0,
+                (KeyCode::Char('e'), true) | (KeyCode::End, _) => self.cursor = self.chars.len(),
+                (KeyCode::Char('u'), true) => {
+                    self.chars.drain(..self.cursor);
+                    self.cursor = 0;
+                }
+                (KeyCode::Char(c), false) => {
+                    self.chars.insert(self.cursor, c);
+                    self.cursor += 1;
+                }
+                (KeyCode::Backspace, _) if self.cursor > 0 => {
+                    self.cursor -= 1;
+                    self.chars.remove(self.cursor);
+                }
+                (KeyCode::Left, _) => self.cursor = self.cursor.saturating_sub(1),
+                (KeyCode::Right, _) => self.cursor = (self.cursor + 1).min(self.chars.len()),
+                (KeyCode::Up, _) if self.recalled > 0 => self.recall(self.recalled - 1),
+                (KeyCode::Down, _) if self.recalled < self.history.len() => {
+                    self.recall(self.recalled + 1)
+                }
+                _ => {}
+            }
+            None
+        }
+    }
+
+    /// Reads a line in raw mode, redrawing it after every key. Fails if
+    /// the terminal can't enter raw mode.
+    pub(super) fn read_line(prompt: &str, history: &[String]) -> io::Result<Option<String>> {
+        terminal::enable_raw_mode()?;
+        let line = edit(prompt, history);
+        terminal::disable_raw_mode()?;
+        println!();
+        line
+    }
+
+    fn edit(prompt: &str, history: &[String]) -> io::Result<Option<String>> {
+        let mut stdout = io::stdout();
+        let mut line = Line::new(history);
+        let prompt_width = prompt.chars().count();
+        loop {
+            queue!(
+                stdout,
+                cursor::MoveToColumn(0),
+                terminal::Clear(ClearType::UntilNewLine)
+            )?;
+            write!(stdout, "{prompt}{}", line.text())?;
+            let column = u16::try_from(prompt_width + line.cursor).unwrap_or(u16::MAX);
+            queue!(stdout, cursor::MoveToColumn(column))?;
+            stdout.flush()?;
+            if let Event::Key(key) = event::read()?
+                && key.kind != KeyEventKind::Release
+                && let Some(done) = line.key(key)
+            {
+                return Ok(done);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(editor: &mut LineEditor, input: &str) -> Vec<Option<String>> {
+        let mut input = input.as_bytes();
+        let mut output = Vec::new();
+        (0..4)
+            .map(|_| editor.read_line_from("> ", &mut input, &mut output))
+            .collect()
+    }
+
+    #[test]
+    fn reads_lines_until_the_end_of_input() {
+        let mut editor = LineEditor::default();
+        let lines = read_all(&mut editor, "4\r\n\n!!\n");
+        let line = |s: &str| Some(s.to_string());
+        assert_eq!(lines, [line("4"), line(""), line("4"), None]);
+        assert_eq!(editor.history, ["4"]);
+
+        // A last line without a line ending still counts.
+        let lines = read_all(&mut LineEditor::default(), "save game.txt");
+        assert_eq!(lines[..2], [line("save game.txt"), None]);
+        // Bytes that aren't UTF-8 don't stop the reading.
+        let mut input: &[u8] = b"\xff3\n";
+        let mut output = Vec::new();
+        let read = LineEditor::default().read_line_from("> ", &mut input, &mut output);
+        assert_eq!(read, line("\u{fffd}3"));
+        assert_eq!(output, b"> ");
+    }
+
+    #[test]
+    fn scripts_come_before_the_input() {
+        let mut editor = LineEditor::with_script(vec!["4".to_string(), "2".to_string()]);
+        let mut input: &[u8] = b"!!\n";
+        let mut output = Vec::new();
+        let mut read = || editor.read_line_from("> ", &mut input, &mut output);
+        let lines = [read(), read(), read(), read()];
+        assert_eq!(
+            lines,
+            [Some("4"), Some("2"), Some("2"), None].map(|l| l.map(String::from))
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), "> 4\n> 2\n> 2\n> ");
+    }
+
+    #[cfg(feature = "line-editing")]
+    #[test]
+    fn editing_keys_and_history() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        use editing::Line;
+
+        let history = ["1".to_string(), "save".to_string()];
+        let mut line = Line::new(&history);
+        let mut press = |code, modifiers| line.key(KeyEvent::new(code, modifiers));
+        let none = KeyModifiers::NONE;
+        for c in "5x".chars() {
+            assert_eq!(press(KeyCode::Char(c), none), None);
+        }
+        press(KeyCode::Backspace, none);
+        press(KeyCode::Up, none);
+        press(KeyCode::Up, none);
+        press(KeyCode::Up, none);
+        press(KeyCode::Down, none);
+        press(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        press(KeyCode::Char(' '), none);
+        press(KeyCode::End, none);
+        press(KeyCode::Char('s'), none);
+        assert_eq!(
+            press(KeyCode::Enter, none),
+            Some(Some(" saves".to_string()))
+        );
+
+        let mut line = Line::new(&history);
+        line.key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+        line.key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        assert_eq!(line.text(), "");
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(line.key(ctrl_d), Some(None));
+    }
+}
@@ -6,100 +6,1037 @@
 //! - calls solver_core methods,
 //! - prints solver outputs.
 
+mod analyze;
+mod args;
+mod book;
+mod commentary;
+mod completions;
+mod distributed;
+mod fairness;
+mod game_loop;
+mod games;
+mod line_editor;
+mod matches;
+mod messages;
+mod narrator;
+mod profile;
+mod puzzle;
+mod replay;
+mod review;
+mod saved_game;
+mod serve;
+mod session;
+mod solve_exact;
+mod stats;
+mod strength;
+mod teaching;
+mod test_suite;
+mod theme;
+mod tournament;
+#[cfg(feature = "tui")]
+mod tui;
+mod tune;
+mod verify;
+
+use std::fs;
 use std::io::{self, Write};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use solver_core::adaptive::{AdaptiveAgent, level_for_record};
+use solver_core::agent::Agent;
+use solver_core::cancel::CancelToken;
+use solver_core::clock::{GameClock, TimeControl};
 use solver_core::game::{GameState, Player};
-use solver_core::games::ttt::{TicTacToeState, parse_ttt_move, print_ttt_board};
-use solver_core::solvers::minimax::minimax_best_move_ab_depth;
+use solver_core::games::ttt::{TicTacToeMove, TicTacToeState, parse_ttt_move};
+use solver_core::referee::{Referee, Rejection, Submission, Verdict};
+use solver_core::registry::{GameEntry, Registry};
+use solver_core::render::describe::describe_in;
+use solver_core::render::{Labels, Layout};
+use solver_core::rng::{Rng, SplitMix64};
+use solver_core::selection::SelectBy;
+use solver_core::solvers::iterative::{DepthReport, iterative_deepening_cancellable};
+use solver_core::solvers::minimax::{minimax_best_move_ab_depth, minimax_best_moves_ab_depth};
+use solver_core::time_manager::TimeManager;
+
+use crate::commentary::{Persona, move_facts, remark};
+use crate::line_editor::LineEditor;
+use crate::messages::Message;
+use crate::narrator::Narrator;
+use crate::profile::{Profile, default_player_name, parse_player_name};
+use crate::review::{MoveReview, annotated_record, print_review, review_game};
+use crate::saved_game::SavedGame;
+use crate::session::{GameRecord, Session, ask_yes_no_stdin};
+use crate::stats::GameResult;
+use crate::teaching::{Strictness, check_move};
+use crate::theme::Theme;
+
+const USAGE: &str = "usage: solver_cli [[play] [--game NAME] [--seed N] [--time SPEC] [--commentary coach|rival] [--player NAME] [--verbose] [--first human|ai] [--adaptive] [--teach immediate|forced] [--autosave N] [--coords numbers|letters] [--flip] [--describe] [--moves M,M,...] | analyze ... | book ... | completions bash|zsh|fish | distributed ... | fairness ... | games | match ... | puzzle ... | replay ... | resume [FILE] | serve ... | solve ... | stats ... | test-suite ... | tournament ... | tui ... | tune ... | verify ...]\n  Without a command, plays Tic-Tac-Toe against the engine like play; --game picks any game listed by games. analyze and solve are also called analyze-file and solve-exact.\n  SPEC: S (S seconds per game) | S+I (plus I seconds per move) | move:S (S seconds per move)\n  SOLVER_SEED=N in the environment seeds every random choice that --seed or an engine spec doesn't\n  SOLVER_LANG=en|es in the environment sets the language of play and resume (by default, that of LANG)";
+
+/// Environment variable with the default seed for random choices, so a
+/// whole run (games, puzzles, matches) can be reproduced.
+const SEED_VAR: &str = "SOLVER_SEED";
+
+/// The seed in `SOLVER_SEED`, or `None` if it isn't set.
+pub(crate) fn global_seed() -> Result<Option<u64>, String> {
+    match std::env::var(SEED_VAR) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid {SEED_VAR} '{value}'")),
+        Err(_) => Ok(None),
+    }
+}
+
+/// The stream of a run's seed (see `SplitMix64::for_stream`) for the
+/// engine's choice between equally good moves in `play` and `tui`. Every
+/// component drawing random numbers has a stream of its own, so no two
+/// draw the same numbers.
+pub(crate) const TIE_BREAK_STREAM: u64 = 0;
+/// The stream for the adaptive AI's deliberate mistakes.
+pub(crate) const ADAPTIVE_STREAM: u64 = 1;
+/// The stream for puzzle generation.
+pub(crate) const PUZZLE_STREAM: u64 = 2;
+/// The first stream of the random engines of matches and tournaments: the
+/// Nth engine draws from `ENGINE_STREAM + N`.
+pub(crate) const ENGINE_STREAM: u64 = 16;
+
+/// Options for an interactive game.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct PlayOptions {
+    /// The registry name of the game to play (see `games`); Tic-Tac-Toe
+    /// if unset.
+    game: Option<String>,
+    /// If set, the AI picks randomly (but reproducibly for a given seed)
+    /// among equally good moves; otherwise it always plays the first.
+    seed: Option<u64>,
+    /// If set, both players are on this clock. The AI's thinking time is
+    /// allotted by a `TimeManager`, and a player whose clock runs out
+    /// loses.
+    time: Option<TimeControl>,
+    /// If set, the AI comments on its moves in this voice.
+    commentary: Option<Persona>,
+    /// Whose profile the game is recorded in (see `profile`); defaults to
+    /// `default_player_name()`.
+    player: Option<String>,
+    /// If set, the AI prints the order in which each iteration of its
+    /// search tried the root moves, with their scores.
+    verbose: bool,
+    /// If set, the AI moves first in the session's first game.
+    ai_first: bool,
+    /// If set, the AI adapts its strength to the player (see `session`).
+    adaptive: bool,
+    /// If set, the player is warned about losing moves before they are
+    /// played (see `teaching`).
+    teach: Option<Strictness>,
+    /// How many moves apart the game is autosaved (see `saved_game`);
+    /// `None` means every `DEFAULT_AUTOSAVE_MOVES` and zero means never.
+    autosave: Option<u32>,
+    /// If set, the board is drawn with row and column labels of this kind.
+    coords: Option<Labels>,
+    /// If set, the board is drawn upside down (see `Layout::flipped`).
+    flip: bool,
+    /// If set, every board is followed by a description in prose, for
+    /// screen readers (see `render::describe`).
+    describe: bool,
+    /// Moves typed for the human before any are read from the terminal,
+    /// for scripted games (see `LineEditor::with_script`). Not saved with
+    /// the game.
+    moves: Vec<String>,
+}
+
+/// Moves between autosaves unless `--autosave` says otherwise.
+const DEFAULT_AUTOSAVE_MOVES: u32 = 1;
+
+fn parse_seconds(text: &str) -> Result<Duration, String> {
+    text.parse::<f64>()
+        .ok()
+        .filter(|s| *s > 0.0)
+        .and_then(|s| Duration::try_from_secs_f64(s).ok())
+        .ok_or_else(|| format!("invalid number of seconds '{text}'"))
+}
+
+/// Parses a `--time` spec: `S`, `S+I` or `move:S`.
+fn parse_time_control(spec: &str) -> Result<TimeControl, String> {
+    if let Some(limit) = spec.strip_prefix("move:") {
+        Ok(TimeControl::PerMove(parse_seconds(limit)?))
+    } else if let Some((base, increment)) = spec.split_once('+') {
+        Ok(TimeControl::Increment {
+            base: parse_seconds(base)?,
+            increment: parse_seconds(increment)?,
+        })
+    } else {
+        Ok(TimeControl::Absolute(parse_seconds(spec)?))
+    }
+}
+
+/// Formats a time control as a `--time` spec, for `parse_time_control`.
+fn format_time_control(control: TimeControl) -> String {
+    match control {
+        TimeControl::Absolute(base) => base.as_secs_f64().to_string(),
+        TimeControl::Increment { base, increment } => {
+            format!("{}+{}", base.as_secs_f64(), increment.as_secs_f64())
+        }
+        TimeControl::PerMove(limit) => format!("move:{}", limit.as_secs_f64()),
+    }
+}
+
+impl PlayOptions {
+    /// How the board is drawn: Tic-Tac-Toe moves name cells, so both rows
+    /// and columns get labels.
+    fn layout(&self) -> Layout {
+        let layout = self.coords.map_or_else(Layout::default, |labels| {
+            Layout::labelled(labels, SelectBy::Cell)
+        });
+        if self.flip { layout.flip() } else { layout }
+    }
+
+    /// The command-line arguments that `parse_play_options` turns back into
+    /// these options.
+    fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut push = |name: &str, value: Option<String>| {
+            args.push(name.to_string());
+            args.extend(value);
+        };
+        if let Some(game) = &self.game {
+            push("--game", Some(game.clone()));
+        }
+        if let Some(seed) = self.seed {
+            push("--seed", Some(seed.to_string()));
+        }
+        if let Some(control) = self.time {
+            push("--time", Some(format_time_control(control)));
+        }
+        if let Some(persona) = self.commentary {
+            push("--commentary", Some(persona.name().to_string()));
+        }
+        if let Some(player) = &self.player {
+            push("--player", Some(player.clone()));
+        }
+        if self.verbose {
+            push("--verbose", None);
+        }
+        if self.ai_first {
+            push("--first", Some("ai".to_string()));
+        }
+        if self.adaptive {
+            push("--adaptive", None);
+        }
+        if let Some(strictness) = self.teach {
+            push("--teach", Some(strictness.name().to_string()));
+        }
+        if let Some(moves) = self.autosave {
+            push("--autosave", Some(moves.to_string()));
+        }
+        if let Some(labels) = self.coords {
+            let name = match labels {
+                Labels::Numbers => "numbers",
+                Labels::Letters => "letters",
+            };
+            push("--coords", Some(name.to_string()));
+        }
+        if self.flip {
+            push("--flip", None);
+        }
+        if self.describe {
+            push("--describe", None);
+        }
+        // The scripted moves were for the run that started the game.
+        args
+    }
+}
+
+/// Parses the comma-separated moves of `--moves`.
+fn parse_moves(list: &str) -> Result<Vec<String>, String> {
+    let moves: Vec<String> = list.split(',').map(|mv| mv.trim().to_string()).collect();
+    if moves.iter().any(String::is_empty) {
+        return Err(format!("invalid moves '{list}'"));
+    }
+    Ok(moves)
+}
+
+fn parse_play_options(args: &[String]) -> Result<PlayOptions, String> {
+    let mut opts = PlayOptions::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| args::value(name, iter.next());
+        match arg.as_str() {
+            "--game" => opts.game = Some(value("--game")?),
+            "--seed" => {
+                let seed = value("--seed")?;
+                opts.seed = Some(seed.parse().map_err(|_| format!("invalid seed '{seed}'"))?);
+            }
+            "--time" => opts.time = Some(parse_time_control(&value("--time")?)?),
+            "--commentary" => opts.commentary = Some(Persona::parse(&value("--commentary")?)?),
+            "--player" => opts.player = Some(parse_player_name(&value("--player")?)?),
+            "--verbose" => opts.verbose = true,
+            "--first" => {
+                opts.ai_first = match value("--first")?.as_str() {
+                    "human" => false,
+                    "ai" => true,
+                    other => return Err(format!("invalid first player '{other}'")),
+                }
+            }
+            "--adaptive" => opts.adaptive = true,
+            "--teach" => opts.teach = Some(Strictness::parse(&value("--teach")?)?),
+            "--autosave" => {
+                let moves = value("--autosave")?;
+                opts.autosave = Some(
+                    moves
+                        .parse()
+                        .map_err(|_| format!("invalid number of moves '{moves}'"))?,
+                );
+            }
+            "--coords" => opts.coords = Some(args::parse_labels(&value("--coords")?)?),
+            "--flip" => opts.flip = true,
+            "--describe" => opts.describe = true,
+            "--moves" => opts.moves = parse_moves(&value("--moves")?)?,
+            other => return Err(format!("unknown option '{other}'")),
+        }
+    }
+    Ok(opts)
+}
+
+/// Prints the moves played so far, one index per move.
+fn print_record(moves: &[u8]) {
+    let moves: Vec<String> = moves.iter().map(u8::to_string).collect();
+    println!("{}", messages::text(&Message::Record(&moves.join(" "))));
+    io::stdout().flush().ok();
+}
+
+/// What the Ctrl-C handler shares with the games of a session.
+struct Interrupt {
+    cancel: CancelToken,
+    thinking: Arc<AtomicBool>,
+    record: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Interrupt {
+    /// Installs a Ctrl-C handler for a session of interactive games. A
+    /// process can only install one, so it is shared by every game.
+    ///
+    /// While the AI is `thinking`, Ctrl-C cancels its search so it plays
+    /// the best move found so far. At any other time (or on a second Ctrl-C
+    /// while the search winds down) it prints the game record and exits.
+    fn install() -> Self {
+        let interrupt = Self {
+            cancel: CancelToken::new(),
+            thinking: Arc::new(AtomicBool::new(false)),
+            record: Arc::new(Mutex::new(Vec::new())),
+        };
+        let (cancel, thinking, record) = (
+            interrupt.cancel.clone(),
+            interrupt.thinking.clone(),
+            interrupt.record.clone(),
+        );
+        let result = ctrlc::set_handler(move || {
+            if thinking.load(Ordering::SeqCst) && !cancel.is_cancelled() {
+                cancel.cancel();
+            } else {
+                println!("\n{}", messages::text(&Message::Interrupted));
+                print_record(&record.lock().unwrap_or_else(|e| e.into_inner()));
+                process::exit(130);
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("warning: Ctrl-C will not be handled: {e}");
+        }
+        interrupt
+    }
+}
+
+/// One iteration's root moves in the order they were searched, with their
+/// scores, for `--verbose`.
+fn root_order_line(report: &DepthReport<TicTacToeMove>) -> String {
+    let moves: Vec<String> = report
+        .root_moves
+        .iter()
+        .map(|(mv, score)| format!("{} ({score:+})", mv.index))
+        .collect();
+    format!("  depth {}: {}", report.depth, moves.join(", "))
+}
+
+/// Prints a warning if a depth-`depth` search from `state` is expected to
+/// be impractically slow for its game.
+fn warn_if_impractical<G: GameState>(state: &G, depth: u32) {
+    let hint = state.complexity_hint();
+    if hint.is_impractical(depth) {
+        eprintln!(
+            "warning: depth {depth} is likely to be impractically slow (about {:.0e} nodes); recommended depths are {}-{}",
+            hint.estimated_nodes(depth),
+            hint.recommended_depths.start(),
+            hint.recommended_depths.end()
+        );
+    }
+}
 
 /// Plays a human-vs-AI game of Tic-Tac-Toe in the terminal.
 ///
 /// - `human_is_player1`: if true, human plays X (Player1), else O (Player2).
-/// - `ai_depth`: search depth to use for the AI (for TTT, 9 is "perfect").
-pub fn play_ttt_human_vs_ai(human_is_player1: bool, ai_depth: u32) {
+/// - `ai_depth`: search depth to use for the AI (for TTT, 9 is "perfect";
+///   see `GameState::recommended_depth`).
+/// - `opts`: random tie-breaking, time control, commentary and the player's
+///   name (see `PlayOptions`).
+///
+/// Ctrl-C while the AI is thinking makes it move at once; otherwise it
+/// ends the game, printing the moves played (see `Interrupt`). A finished
+/// game ends with a review of the human's moves and the annotated game
+/// record (see `review`), and is recorded in the player's profile (see `profile`).
+///
+/// With `agent`, the AI's moves are the agent's instead of a depth-`ai_depth`
+/// search.
+///
+/// Typing `save [FILE]` instead of a move saves the game and ends it (see
+/// `saved_game`); with `saved`, the game carries on from such a save.
+/// `hint` shows the move the AI would play, and `undo` takes back the
+/// human's last move and the AI's reply.
+///
+/// Moves are read with `editor`, which keeps its history across the games
+/// of a session. Blank lines are ignored, and the end of input abandons
+/// the game, leaving its autosave to recover.
+///
+/// Returns how the game went for the human, or `None` if it was abandoned
+/// or saved.
+fn play_ttt_human_vs_ai(
+    human_is_player1: bool,
+    ai_depth: u32,
+    opts: &PlayOptions,
+    interrupt: &Interrupt,
+    editor: &mut LineEditor,
+    mut agent: Option<&mut AdaptiveAgent>,
+    saved: Option<SavedGame>,
+) -> Option<GameRecord> {
+    let mut rng = opts
+        .seed
+        .map(|seed| SplitMix64::for_stream(seed, TIE_BREAK_STREAM));
+    let mut clock = opts.time.map(GameClock::new);
+    let mut previous_value = None;
+    let mut human_moves = 0;
+    let mut human_time = Duration::ZERO;
     let mut state = TicTacToeState::new();
-
-    println!("Welcome to Tic-Tac-Toe!");
-    println!(
-        "You are {}.",
-        if human_is_player1 {
-            "X (Player1)"
-        } else {
-            "O (Player2)"
+    let Interrupt {
+        cancel,
+        thinking,
+        record,
+    } = interrupt;
+    record.lock().unwrap().clear();
+    if let Some(saved) = saved {
+        state = saved.state().expect("saved games are checked when loaded");
+        clock = saved.clock;
+        rng = saved.rng.map(SplitMix64::new).or(rng);
+        human_moves = saved.human_moves;
+        human_time = Duration::from_secs_f64(saved.human_seconds);
+        *record.lock().unwrap() = saved.cells().expect("saved games are checked when loaded");
+    }
+    let name = opts.player.clone().unwrap_or_else(default_player_name);
+    let autosave_path = SavedGame::autosave_path(&name);
+    let snapshot =
+        |clock, rng: &Option<SplitMix64>, human_moves, human_time: Duration, level| SavedGame {
+            opts: opts.clone(),
+            human_is_player1,
+            depth: ai_depth,
+            level,
+            moves: record.lock().unwrap().iter().map(u8::to_string).collect(),
+            human_moves,
+            human_seconds: human_time.as_secs_f64(),
+            clock,
+            rng: rng.as_ref().map(SplitMix64::state),
+        };
+    let (theme, warning) = Theme::load_default();
+    if let Some(e) = warning {
+        eprintln!("warning: {e}; using the default theme");
+    }
+    let layout = opts.layout();
+    let show_board = |state: &TicTacToeState| {
+        print!("{}", theme.render(state, &layout));
+        if opts.describe {
+            let labels = opts.coords.unwrap_or(Labels::Numbers);
+            print!(
+                "\n{}",
+                describe_in(state, labels, messages::catalog().words())
+            );
         }
-    );
-    println!("Index mapping:");
+    };
+
+    let human = if human_is_player1 {
+        Player::Player1
+    } else {
+        Player::Player2
+    };
+    // The referee's clock runs from the start of play.
+    let epoch = Instant::now();
+    let mut referee = Referee::new(state);
+    if let Some(clock) = clock {
+        referee = referee.with_clock(clock, Duration::ZERO);
+    }
+    let mut narrator = Narrator::new(human, |mv: &TicTacToeMove, value| {
+        messages::text(&Message::AiChooses(mv.index, value))
+    });
+    println!("{}", messages::text(&Message::Welcome));
+    println!("{}", messages::text(&Message::YouAre(human)));
+    println!("{}", messages::text(&Message::IndexMapping));
     println!("0 | 1 | 2");
     println!("3 | 4 | 5");
     println!("6 | 7 | 8");
     println!();
 
     // Main game loop
-    loop {
-        print_ttt_board(&state);
-        println!();
-
-        if state.is_terminal() {
+    'game: loop {
+        if referee.is_over() {
             break;
         }
+        let state = referee.state().clone();
+        show_board(&state);
+        println!();
 
         let current = state.current_player();
-        let human_turn = (current == Player::Player1 && human_is_player1)
-            || (current == Player::Player2 && !human_is_player1);
+        let human_turn = current == human;
 
+        let started = Instant::now();
         if human_turn {
             // Human move
-            println!("Your turn ({:?}).", current);
+            let left = referee
+                .clock()
+                .map(|clock| clock.get(current).remaining().as_secs_f64());
+            println!("{}", messages::text(&Message::YourTurn(current, left)));
             loop {
-                print!("Enter your move (0-8): ");
-                io::stdout().flush().unwrap();
-
-                let mut input = String::new();
-                if io::stdin().read_line(&mut input).is_err() {
-                    println!("Failed to read line. Try again.");
+                let Some(input) = editor.read_line(&messages::text(&Message::MovePrompt)) else {
+                    println!("\n{}", messages::text(&Message::EndOfInput));
+                    print_record(&record.lock().unwrap());
+                    return None;
+                };
+                if input.trim().is_empty() {
                     continue;
                 }
+                if let Some(file) = input
+                    .trim()
+                    .strip_prefix("save")
+                    .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+                {
+                    let level = agent.as_deref().map(AdaptiveAgent::level);
+                    let clock = referee.clock().copied();
+                    let game = snapshot(clock, &rng, human_moves, human_time, level);
+                    let path = match file.trim() {
+                        "" => SavedGame::path(&name),
+                        file => file.into(),
+                    };
+                    match game.save(&path) {
+                        Ok(()) => {
+                            let _ = fs::remove_file(&autosave_path);
+                            let path = path.display().to_string();
+                            println!("{}", messages::text(&Message::Saved(&path)));
+                            return None;
+                        }
+                        Err(e) => {
+                            println!("{e}");
+                            continue;
+                        }
+                    }
+                }
+
+                match input.trim() {
+                    "hint" => {
+                        if let Some((mv, value)) = minimax_best_move_ab_depth(&state, ai_depth) {
+                            let mv = mv.index.to_string();
+                            println!("{}", messages::text(&Message::Hint(&mv, value)));
+                        }
+                        continue;
+                    }
+                    "undo" => {
+                        // Takes back the human's last move and the AI's reply.
+                        let mut moves = record.lock().unwrap();
+                        if moves.len() < 2 {
+                            println!("{}", messages::text(&Message::NothingToUndo));
+                            continue;
+                        }
+                        let kept = moves.len() - 2;
+                        moves.truncate(kept);
+                        let previous = moves.iter().fold(TicTacToeState::new(), |state, &index| {
+                            state.apply_move(&TicTacToeMove { index })
+                        });
+                        drop(moves);
+                        let clock = referee.clock().copied();
+                        referee = Referee::new(previous);
+                        if let Some(clock) = clock {
+                            referee = referee.with_clock(clock, epoch.elapsed());
+                        }
+                        human_moves = human_moves.saturating_sub(1);
+                        println!("{}", messages::text(&Message::Undone));
+                        continue 'game;
+                    }
+                    _ => {}
+                }
 
                 match parse_ttt_move(&input, &state) {
                     Ok(mv) => {
-                        state = state.apply_move(&mv);
+                        if let Some(warning) = opts
+                            .teach
+                            .and_then(|strictness| check_move(&state, &mv, strictness))
+                        {
+                            let warning = warning.message(|mv| mv.index.to_string());
+                            println!("{}", messages::text(&Message::Careful(&warning)));
+                            if !ask_yes_no_stdin(&messages::text(&Message::PlayAnyway)) {
+                                continue;
+                            }
+                        }
+                        let submission = Submission {
+                            player: current,
+                            ply: referee.ply(),
+                            mv,
+                        };
+                        let event = referee.submit_observed(
+                            submission,
+                            None,
+                            epoch.elapsed(),
+                            &mut narrator,
+                        );
+                        match event.verdict {
+                            Verdict::Accepted { .. } => {}
+                            Verdict::Rejected {
+                                reason: Rejection::TimeExpired,
+                                ..
+                            } => break 'game,
+                            Verdict::Rejected { reason, .. } => {
+                                let reason = reason.to_string();
+                                println!("{}", messages::text(&Message::InvalidMove(&reason)));
+                                continue;
+                            }
+                        }
+                        record.lock().unwrap().push(mv.index);
+                        human_moves += 1;
+                        human_time += started.elapsed();
                         break;
                     }
                     Err(msg) => {
-                        println!("Invalid move: {msg}");
+                        println!(
+                            "{}",
+                            messages::text(&Message::InvalidMove(&msg.to_string()))
+                        );
                         continue;
                     }
                 }
             }
         } else {
             // AI move
-            println!("AI ({:?}) is thinking...", current);
+            println!("{}", messages::text(&Message::AiThinking(current)));
 
-            if let Some((mv, value)) = minimax_best_move_ab_depth(&state, ai_depth) {
-                println!("AI chooses index {} (value = {}).", mv.index, value);
-                state = state.apply_move(&mv);
+            let best = if let Some(agent) = agent.as_deref_mut() {
+                agent
+                    .select_move(&state)
+                    .and_then(|d| Some((d.mv, d.value?)))
+            } else {
+                cancel.reset();
+                let mut manager = referee.clock().map(|c| TimeManager::new(c.get(current)));
+                thinking.store(true, Ordering::SeqCst);
+                let on_iteration = |report: &DepthReport<TicTacToeMove>| {
+                    if opts.verbose {
+                        println!("{}", root_order_line(report));
+                    }
+                };
+                let report = match &mut manager {
+                    Some(manager) => {
+                        manager.search_reporting(&state, ai_depth, cancel, on_iteration)
+                    }
+                    None => iterative_deepening_cancellable(&state, ai_depth, cancel, on_iteration),
+                };
+                thinking.store(false, Ordering::SeqCst);
+                let interrupted = cancel.is_cancelled() && !manager.is_some_and(|m| m.stopped());
+                if let (true, Some(report)) = (interrupted, &report) {
+                    println!(
+                        "{}",
+                        messages::text(&Message::SearchInterrupted(report.depth))
+                    );
+                }
+                let full_depth = report.as_ref().is_some_and(|r| r.depth == ai_depth);
+
+                match &mut rng {
+                    Some(rng) if full_depth => minimax_best_moves_ab_depth(&state, ai_depth)
+                        .and_then(|(moves, value)| rng.choose(&moves).map(|&mv| (mv, value))),
+                    _ => report.map(|r| (r.best_move, r.value)),
+                }
+            };
+            if let Some((mv, value)) = best {
+                let submission = Submission {
+                    player: current,
+                    ply: referee.ply(),
+                    mv,
+                };
+                let now = epoch.elapsed();
+                let event = referee.submit_observed(submission, Some(value), now, &mut narrator);
+                if !event.is_accepted() {
+                    break 'game;
+                }
+                record.lock().unwrap().push(mv.index);
+                let after = referee.state();
+                if let Some(persona) = opts.commentary.filter(|_| !after.is_terminal()) {
+                    let facts =
+                        move_facts(&state, after, current, 3, value, previous_value, ai_depth);
+                    if let Some(text) = remark(persona, &facts) {
+                        println!("{}", messages::text(&Message::AiSays(&text)));
+                    }
+                }
+                previous_value = Some(value);
             } else {
                 // No moves: should only happen if state is terminal
-                println!("AI has no legal moves.");
+                println!("{}", messages::text(&Message::AiHasNoMoves));
                 break;
             }
         }
+
+        let every = opts.autosave.unwrap_or(DEFAULT_AUTOSAVE_MOVES) as usize;
+        let moves = record.lock().unwrap().len();
+        if every > 0 && moves % every == 0 && !referee.is_over() {
+            let level = agent.as_deref().map(AdaptiveAgent::level);
+            let clock = referee.clock().copied();
+            let game = snapshot(clock, &rng, human_moves, human_time, level);
+            if let Err(e) = game.save(&autosave_path) {
+                eprintln!("warning: autosave failed: {e}");
+            }
+        }
     }
+    let _ = fs::remove_file(&autosave_path);
+
+    // Game over: the narrator has given the result; print the final board
+    show_board(referee.state());
+    print_record(&record.lock().unwrap());
+    let Some(outcome) = referee.outcome() else {
+        println!("{}", messages::text(&Message::Abandoned));
+        return None;
+    };
+    let winner = outcome.winner();
+    let flagged = narrator.flagged();
 
-    // Game over: print final board and result
-    print_ttt_board(&state);
-    println!("\nGame over!");
+    let path = Profile::path(&name);
+    // A profile that fails to load is left alone rather than replaced, so
+    // whatever is wrong with it can still be fixed by hand.
+    let mut profile = match Profile::load(&path) {
+        Ok(profile) => Some(profile),
+        Err(e) => {
+            eprintln!("warning: {e}; not updating the profile");
+            None
+        }
+    };
+    let game = GameRecord {
+        result: GameResult::for_player(human, winner),
+        moves: human_moves,
+        seconds: human_time.as_secs_f64(),
+    };
+    if let Some(profile) = &mut profile {
+        profile
+            .stats
+            .record("ttt", ai_depth, game.result, game.moves, game.seconds);
+    }
 
-    match state.terminal_value() {
-        Some(1) => println!("Player1 (X) wins!"),
-        Some(-1) => println!("Player2 (O) wins!"),
-        Some(0) => println!("It's a draw!"),
-        None => println!("Non-terminal state at end? (Bug)"),
-        _ => unreachable!("Should only see 1, -1, or 0 values for TTT."),
+    // A game lost on time ends mid-play and says little about the moves.
+    if flagged.is_none() {
+        let moves: Vec<TicTacToeMove> = record
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|&index| TicTacToeMove { index })
+            .collect();
+        let root = TicTacToeState::new();
+        let reviews = review_game(&root, &moves, human, ai_depth);
+        println!();
+        print_review(&reviews, |mv| mv.index.to_string());
+        let annotated = annotated_record(&root, &moves, ai_depth, " ", |mv| mv.index.to_string());
+        println!("{}", messages::text(&Message::AnnotatedRecord(&annotated)));
+        if let Some(profile) = &mut profile {
+            update_strength(profile, &root, &moves, human, &reviews, ai_depth);
+        }
+    }
+    if let Some(Err(e)) = profile.map(|profile| profile.save(&path)) {
+        eprintln!("warning: {e}");
     }
+    Some(game)
+}
+
+/// Plays Tic-Tac-Toe games against the AI until the human declines a
+/// rematch (see `session`), starting from `saved` if given.
+///
+/// A saved game brings its own options, but the scripted `opts.moves` are
+/// played in it all the same.
+fn play_ttt_session(opts: &PlayOptions, mut saved: Option<SavedGame>) {
+    let mut editor = LineEditor::with_script(opts.moves.clone());
+    let opts = &saved
+        .as_ref()
+        .map_or_else(|| opts.clone(), |saved| saved.opts.clone());
+    let interrupt = Interrupt::install();
+    let depth = TicTacToeState::new().recommended_depth();
+    let agent = opts.adaptive.then(|| {
+        let name = opts.player.clone().unwrap_or_else(default_player_name);
+        let stats = Profile::load(Profile::path(&name))
+            .map(|profile| profile.stats)
+            .unwrap_or_default();
+        let (wins, draws, losses) = stats
+            .results
+            .iter()
+            .filter(|counts| counts.game == "ttt")
+            .fold((0, 0, 0), |(w, d, l), c| {
+                (w + c.wins, d + c.draws, l + c.losses)
+            });
+        let level = saved
+            .as_ref()
+            .and_then(|saved| saved.level)
+            .unwrap_or_else(|| level_for_record(wins, draws, losses));
+        let seed = SplitMix64::for_stream(opts.seed.unwrap_or(0), ADAPTIVE_STREAM).next_u64();
+        AdaptiveAgent::new(depth, seed).with_level(level)
+    });
+    let mut session = match &saved {
+        Some(saved) => Session::new(saved.human_is_player1, saved.depth, agent),
+        None => Session::new(!opts.ai_first, depth, agent),
+    };
+    loop {
+        let (human_first, depth) = (session.human_first, session.depth());
+        let agent = session.agent_mut();
+        if let Some(agent) = &agent {
+            let level = Message::AiLevel(agent.level(), depth);
+            println!("{}", messages::text(&level));
+        }
+        let saved = saved.take();
+        let Some(game) = play_ttt_human_vs_ai(
+            human_first,
+            depth,
+            opts,
+            &interrupt,
+            &mut editor,
+            agent,
+            saved,
+        ) else {
+            break;
+        };
+        session.record("ttt", &game);
+        println!("\n{}", session.summary());
+        if !ask_yes_no_stdin(&messages::text(&Message::PlayAgain)) {
+            break;
+        }
+        println!();
+    }
+}
+
+/// Adds a reviewed game to `profile`'s strength record and prints the new
+/// estimate.
+fn update_strength<G: GameState>(
+    profile: &mut Profile,
+    root: &G,
+    moves: &[G::Move],
+    human: Player,
+    reviews: &[MoveReview<G::Move>],
+    depth: u32,
+) {
+    profile
+        .strength
+        .add_game(root, moves, human, reviews, depth);
+    if let Some(estimate) = profile.strength.estimate() {
+        let message = Message::Strength {
+            rating: estimate.rating,
+            depth: estimate.equivalent_depth,
+            blunder_rate: estimate.blunder_rate,
+            average_loss: estimate.average_loss,
+            moves: profile.strength.moves,
+            games: profile.strength.games,
+        };
+        println!("{}", messages::text(&message));
+    }
+}
+
+/// Environment variable holding the log filter (with the `tracing` feature),
+/// in `tracing_subscriber::EnvFilter` syntax.
+#[cfg(feature = "tracing")]
+const LOG_ENV: &str = "SOLVER_LOG";
+
+/// Sends search spans and events to stderr, filtered by `$SOLVER_LOG`.
+#[cfg(feature = "tracing")]
+fn init_logging() {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_env(LOG_ENV))
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_writer(io::stderr)
+        .init();
+}
+
+/// Runs an interactive session of the game picked by `--game` (by default
+/// Tic-Tac-Toe) with the play options in `args`.
+fn play(args: &[String]) -> Result<(), String> {
+    let mut opts = parse_play_options(args)?;
+    messages::init_from_env()?;
+    opts.seed = opts.seed.or(global_seed()?);
+    if let Some(name) = &opts.game {
+        find_game(&Registry::builtin(), name)?;
+    }
+    // An autosave left by a game that never finished is offered first.
+    let saved = SavedGame::recover_autosave(&opts);
+    play_session(&opts, saved)
+}
+
+/// The registered game called `name`.
+fn find_game<'a>(registry: &'a Registry, name: &str) -> Result<&'a GameEntry, String> {
+    registry.get(name).ok_or_else(|| {
+        format!(
+            "unknown game '{name}' (expected one of: {})",
+            registry.names().join(", ")
+        )
+    })
+}
+
+/// Plays the game of `saved`, or else the one `opts` picks, in a session
+/// of rematches (see `play_ttt_session` and `game_loop`).
+fn play_session(opts: &PlayOptions, saved: Option<SavedGame>) -> Result<(), String> {
+    let game = saved
+        .as_ref()
+        .map_or(opts.game.as_deref(), |saved| saved.opts.game.as_deref());
+    match game {
+        None | Some("ttt") => play_ttt_session(opts, saved),
+        Some(name) => {
+            let registry = Registry::builtin();
+            let entry = find_game(&registry, name)?;
+            game_loop::play_session(entry, opts, saved)?;
+        }
+    }
+    Ok(())
 }
 
 fn main() {
-    // Human plays X, AI plays O at depth 9 (perfect play)
-    play_ttt_human_vs_ai(true, 9);
+    #[cfg(feature = "tracing")]
+    init_logging();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        // Human plays X first, AI plays O at the recommended depth (perfect
+        // play), with a rematch offered after each game
+        None
+        | Some(
+            "--game" | "--seed" | "--time" | "--commentary" | "--player" | "--verbose" | "--first"
+            | "--adaptive" | "--teach" | "--autosave" | "--coords" | "--flip" | "--describe"
+            | "--moves",
+        ) => play(&args).map_err(|e| format!("{e}\n{USAGE}")),
+        Some("play") => play(&args[1..]).map_err(|e| format!("{e}\n{USAGE}")),
+        Some("analyze" | "analyze-file") => {
+            analyze::run(&args[1..]).map_err(|e| format!("{e}\n{}", analyze::USAGE))
+        }
+        Some("book") => book::run(&args[1..]).map_err(|e| format!("{e}\n{}", book::USAGE)),
+        Some("completions") => {
+            completions::run(&args[1..]).map_err(|e| format!("{e}\n{}", completions::USAGE))
+        }
+        Some("distributed") => {
+            distributed::run(&args[1..]).map_err(|e| format!("{e}\n{}", distributed::USAGE))
+        }
+        Some("fairness") => {
+            fairness::run(&args[1..]).map_err(|e| format!("{e}\n{}", fairness::USAGE))
+        }
+        Some("games") => games::run(&args[1..]).map_err(|e| format!("{e}\n{}", games::USAGE)),
+        Some("match") => matches::run(&args[1..]).map_err(|e| format!("{e}\n{}", matches::USAGE)),
+        Some("replay") => replay::run(&args[1..]).map_err(|e| format!("{e}\n{}", replay::USAGE)),
+        Some("resume") => {
+            saved_game::run(&args[1..]).map_err(|e| format!("{e}\n{}", saved_game::USAGE))
+        }
+        Some("serve") => serve::run(&args[1..]).map_err(|e| format!("{e}\n{}", serve::USAGE)),
+        Some("solve" | "solve-exact") => {
+            solve_exact::run(&args[1..]).map_err(|e| format!("{e}\n{}", solve_exact::USAGE))
+        }
+        Some("stats") => stats::run(&args[1..]).map_err(|e| format!("{e}\n{}", stats::USAGE)),
+        Some("puzzle") => puzzle::run(&args[1..]).map_err(|e| format!("{e}\n{}", puzzle::USAGE)),
+        Some("test-suite") => {
+            test_suite::run(&args[1..]).map_err(|e| format!("{e}\n{}", test_suite::USAGE))
+        }
+        Some("tournament") => {
+            tournament::run(&args[1..]).map_err(|e| format!("{e}\n{}", tournament::USAGE))
+        }
+        #[cfg(feature = "tui")]
+        Some("tui") => tui::run(&args[1..]).map_err(|e| format!("{e}\n{}", tui::USAGE)),
+        Some("tune") => tune::run(&args[1..]).map_err(|e| format!("{e}\n{}", tune::USAGE)),
+        Some("verify") => verify::run(&args[1..]).map_err(|e| format!("{e}\n{}", verify::USAGE)),
+        Some(other) => Err(format!("unknown command '{other}'\n{USAGE}")),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {e}");
+        process::exit(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::split_args;
+    use solver_core::solvers::iterative::iterative_deepening;
+
+    #[test]
+    fn parses_time_controls() {
+        let secs = Duration::from_secs;
+        assert_eq!(
+            parse_time_control("60"),
+            Ok(TimeControl::Absolute(secs(60)))
+        );
+        assert_eq!(
+            parse_time_control("30+2"),
+            Ok(TimeControl::Increment {
+                base: secs(30),
+                increment: secs(2)
+            })
+        );
+        assert_eq!(
+            parse_time_control("move:0.5"),
+            Ok(TimeControl::PerMove(Duration::from_millis(500)))
+        );
+        for bad in ["", "0", "-1", "move:", "1+x", "inf", "1e300"] {
+            assert!(parse_time_control(bad).is_err(), "{bad}");
+        }
+
+        let opts = parse_play_options(&split_args("--time 10+1 --seed 7")).unwrap();
+        assert_eq!(opts.seed, Some(7));
+        assert!(matches!(opts.time, Some(TimeControl::Increment { .. })));
+        assert!(parse_play_options(&split_args("--seed")).is_err());
+        assert!(parse_play_options(&split_args("--fast")).is_err());
+        assert!(
+            parse_play_options(&split_args("--verbose"))
+                .unwrap()
+                .verbose
+        );
+        let opts = parse_play_options(&split_args("--teach forced")).unwrap();
+        assert_eq!(opts.teach, Some(Strictness::Forced));
+        assert!(parse_play_options(&split_args("--teach")).is_err());
+        let opts = parse_play_options(&split_args("--first ai --adaptive")).unwrap();
+        assert!(opts.ai_first && opts.adaptive);
+        assert!(
+            !parse_play_options(&split_args("--first human"))
+                .unwrap()
+                .ai_first
+        );
+        assert!(parse_play_options(&split_args("--first nobody")).is_err());
+        assert_eq!(
+            parse_play_options(&split_args("--autosave 3"))
+                .unwrap()
+                .autosave,
+            Some(3)
+        );
+        assert!(parse_play_options(&split_args("--autosave often")).is_err());
+        let opts = parse_play_options(&split_args("--moves 4,3,save --autosave 0")).unwrap();
+        assert_eq!(opts.moves, ["4", "3", "save"]);
+        assert_eq!(opts.to_args(), split_args("--autosave 0"));
+        assert!(parse_play_options(&split_args("--moves 4,,3")).is_err());
+        let opts = parse_play_options(&split_args("--coords letters --flip")).unwrap();
+        assert_eq!(opts.to_args(), split_args("--coords letters --flip"));
+        assert_eq!(
+            opts.layout(),
+            Layout::labelled(Labels::Letters, SelectBy::Cell).flip()
+        );
+        assert!(parse_play_options(&split_args("--coords roman")).is_err());
+        assert!(
+            parse_play_options(&split_args("--describe"))
+                .unwrap()
+                .describe
+        );
+    }
+
+    #[test]
+    fn verbose_lines_show_the_root_order() {
+        let state = TicTacToeState::from_str("XX.OO....", Player::Player1).unwrap();
+        let report = iterative_deepening(&state, 2, |_| {}).unwrap();
+        let line = root_order_line(&report);
+        assert!(line.starts_with("  depth 2: 2 (+1000000), "), "{line}");
+        assert_eq!(line.matches(", ").count(), 4);
+    }
 }
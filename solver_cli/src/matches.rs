@@ -0,0 +1,534 @@
+//! `match`: plays two engine configurations against each other.
+//!
+//! Engines are given as specs:
+//! - `ab:N`: depth-N alpha-beta with the game's heuristic.
+//! - `ab:N:flat`: depth-N alpha-beta that only scores won/lost positions,
+//!   useful to measure what the heuristic is worth.
+//! - `id:N`: iterative deepening to depth N with a transposition table.
+//! - `mtdf:N`: like `id:N`, but searching each depth with MTD(f).
+//! - `random[:SEED]`: a uniformly random mover.
+//!
+//! Search engines take an optional last `:contempt=C`, as in
+//! `ab:6:contempt=20`: how many evaluation units a draw is worth less to
+//! them than to their opponent, so a positive contempt presses for a win
+//! against a weaker one.
+//!
+//! Games start from every opening of `--opening-plies` moves in turn, each
+//! played once with each color. With `--sprt ELO0,ELO1` the match stops as
+//! soon as a sequential probability ratio test decides between "A is ELO0
+//! stronger" and "A is ELO1 stronger" (error rates `--sprt-alpha` and
+//! `--sprt-beta`, 0.05 each by default); `--games` is then the maximum.
+//!
+//! Games are played on `--jobs` threads (all cores by default), each with
+//! freshly created engines, so results do not depend on the job count.
+//! For Connect Four, search engines play from the default opening book
+//! (see `solver_cli book build`) when one exists, unless `--no-book` is
+//! given.
+
+use std::sync::Arc;
+use std::thread;
+
+use solver_core::agent::{Agent, MinimaxAgent, SearchAgent};
+use solver_core::arena::{
+    AgentFactory, MatchReport, Sprt, SprtDecision, opening_positions, run_match_parallel,
+};
+use solver_core::book::{BookAgent, OpeningBook};
+use solver_core::eval::FnEvaluator;
+use solver_core::game::{GameState, PositionKey};
+use solver_core::games::c4_bitboard::BitboardState;
+use solver_core::games::ttt::{TicTacToeMove, TicTacToeState};
+use solver_core::rng::{RandomAgent, Rng, SplitMix64};
+use solver_core::solvers::mtdf::SearchAlgorithm;
+
+use crate::args;
+
+/// Transposition table size for `id:` and `mtdf:` engines.
+const TT_MEGABYTES: usize = 16;
+
+pub const USAGE: &str = "usage: solver_cli match --a SPEC --b SPEC [--game c4|ttt] [--games N] [--opening-plies N] [--no-book] [--quiet] [--jobs N] [--sprt ELO0,ELO1 [--sprt-alpha P] [--sprt-beta P]]\n  SPEC: ab:N[:flat] | id:N | mtdf:N | random[:SEED], with search engines taking [:contempt=C]";
+
+/// An engine configuration, as given on the command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EngineSpec {
+    AlphaBeta {
+        depth: u32,
+        flat: bool,
+        contempt: i32,
+    },
+    Search {
+        depth: u32,
+        algorithm: SearchAlgorithm,
+        contempt: i32,
+    },
+    Random {
+        /// The seed of `random:SEED`; plain `random` engines draw from the
+        /// run's seed instead (see `seeded`).
+        seed: Option<u64>,
+    },
+}
+
+impl EngineSpec {
+    /// Parses a spec such as `ab:6` (see the module docs).
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid engine spec '{spec}'");
+        let mut parts: Vec<&str> = spec.split(':').collect();
+        let contempt = match parts.last().and_then(|last| last.strip_prefix("contempt=")) {
+            Some(contempt) if !parts[0].starts_with("random") => {
+                parts.pop();
+                contempt.parse().map_err(|_| invalid())?
+            }
+            _ => 0,
+        };
+        match parts.as_slice() {
+            ["ab", depth, rest @ ..] => {
+                let depth = depth.parse().ok().filter(|&d| d > 0).ok_or_else(invalid)?;
+                let flat = match rest {
+                    [] => false,
+                    ["flat"] => true,
+                    _ => return Err(invalid()),
+                };
+                Ok(EngineSpec::AlphaBeta {
+                    depth,
+                    flat,
+                    contempt,
+                })
+            }
+            [name @ ("id" | "mtdf"), depth] => Ok(EngineSpec::Search {
+                depth: depth.parse().ok().filter(|&d| d > 0).ok_or_else(invalid)?,
+                algorithm: if *name == "id" {
+                    SearchAlgorithm::AlphaBeta
+                } else {
+                    SearchAlgorithm::Mtdf
+                },
+                contempt,
+            }),
+            ["random"] => Ok(EngineSpec::Random { seed: None }),
+            ["random", seed] => Ok(EngineSpec::Random {
+                seed: Some(seed.parse().map_err(|_| invalid())?),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Seeds a `random` engine without a seed of its own from stream
+    /// `engine` of the run's `seed`, so that every engine of a run plays
+    /// differently.
+    pub fn seeded(self, seed: u64, engine: u64) -> Self {
+        match self {
+            EngineSpec::Random { seed: None } => {
+                let mut rng = SplitMix64::for_stream(seed, crate::ENGINE_STREAM + engine);
+                EngineSpec::Random {
+                    seed: Some(rng.next_u64()),
+                }
+            }
+            spec => spec,
+        }
+    }
+
+    /// The search depth, or `None` for engines that don't search.
+    pub fn depth(&self) -> Option<u32> {
+        match *self {
+            EngineSpec::AlphaBeta { depth, .. } | EngineSpec::Search { depth, .. } => Some(depth),
+            EngineSpec::Random { .. } => None,
+        }
+    }
+
+    /// Creates the engine for game number `game` of a match. Random engines
+    /// play differently (but reproducibly) in every game.
+    pub fn build<G>(&self, game: usize) -> Box<dyn Agent<G>>
+    where
+        G: GameState + PositionKey + 'static,
+        G::Move: PartialEq,
+    {
+        match *self {
+            EngineSpec::AlphaBeta {
+                depth,
+                flat: false,
+                contempt,
+            } => Box::new(MinimaxAgent {
+                contempt,
+                ..MinimaxAgent::new(depth)
+            }),
+            EngineSpec::AlphaBeta {
+                depth,
+                flat: true,
+                contempt,
+            } => Box::new(MinimaxAgent {
+                contempt,
+                ..MinimaxAgent::with_evaluator(depth, FnEvaluator::new(|_: &G| 0))
+            }),
+            EngineSpec::Search {
+                depth,
+                algorithm,
+                contempt,
+            } => {
+                let mut agent = SearchAgent::new(depth, algorithm, TT_MEGABYTES);
+                agent.contempt = contempt;
+                Box::new(agent)
+            }
+            EngineSpec::Random { seed } => {
+                let mut rng = SplitMix64::for_stream(seed.unwrap_or(0), game as u64);
+                Box::new(RandomAgent::new(rng.next_u64()))
+            }
+        }
+    }
+
+    /// Like `build`, but search engines consult `book` first if given.
+    pub fn build_c4(
+        &self,
+        book: Option<&Arc<OpeningBook>>,
+        game: usize,
+    ) -> Box<dyn Agent<BitboardState>> {
+        match (self, book) {
+            (EngineSpec::AlphaBeta { .. } | EngineSpec::Search { .. }, Some(book)) => Box::new(
+                BookAgent::new(book.clone(), self.build::<BitboardState>(game)),
+            ),
+            _ => self.build(game),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    a: EngineSpec,
+    b: EngineSpec,
+    ttt: bool,
+    games: u32,
+    opening_plies: u32,
+    use_book: bool,
+    quiet: bool,
+    jobs: usize,
+    sprt: Option<Sprt>,
+}
+
+/// Parses an SPRT error rate, strictly between 0 and 0.5.
+fn parse_error_rate(text: &str) -> Result<f64, String> {
+    text.parse()
+        .ok()
+        .filter(|p| *p > 0.0 && *p < 0.5)
+        .ok_or_else(|| format!("invalid error rate '{text}'"))
+}
+
+/// Parses `ELO0,ELO1` with `ELO0 < ELO1`.
+fn parse_sprt(text: &str) -> Result<Sprt, String> {
+    let invalid = || format!("invalid SPRT bounds '{text}' (expected ELO0,ELO1)");
+    let (elo0, elo1) = text.split_once(',').ok_or_else(invalid)?;
+    let (elo0, elo1): (f64, f64) = (
+        elo0.trim().parse().map_err(|_| invalid())?,
+        elo1.trim().parse().map_err(|_| invalid())?,
+    );
+    if !(elo0.is_finite() && elo1.is_finite() && elo0 < elo1) {
+        return Err(invalid());
+    }
+    Ok(Sprt::new(elo0, elo1))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let (mut a, mut b) = (None, None);
+    let mut opts = Options {
+        a: EngineSpec::Random { seed: None },
+        b: EngineSpec::Random { seed: None },
+        ttt: false,
+        games: 20,
+        opening_plies: 2,
+        use_book: true,
+        quiet: false,
+        jobs: thread::available_parallelism().map_or(1, |n| n.get()),
+        sprt: None,
+    };
+    let (mut alpha, mut beta) = (None, None);
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| args::value(name, iter.next());
+        match arg.as_str() {
+            "--a" => a = Some(EngineSpec::parse(&value("--a")?)?),
+            "--b" => b = Some(EngineSpec::parse(&value("--b")?)?),
+            "--game" => {
+                opts.ttt = match value("--game")?.as_str() {
+                    "c4" => false,
+                    "ttt" => true,
+                    other => return Err(format!("unknown game '{other}'")),
+                }
+            }
+            "--games" => {
+                let games = value("--games")?;
+                opts.games = games
+                    .parse()
+                    .ok()
+                    .filter(|&g| g > 0)
+                    .ok_or_else(|| format!("invalid game count '{games}'"))?;
+            }
+            "--opening-plies" => {
+                let plies = value("--opening-plies")?;
+                opts.opening_plies = plies
+                    .parse()
+                    .map_err(|_| format!("invalid opening plies '{plies}'"))?;
+            }
+            "--no-book" => opts.use_book = false,
+            "--quiet" => opts.quiet = true,
+            "--jobs" => {
+                let jobs = value("--jobs")?;
+                opts.jobs = jobs
+                    .parse()
+                    .ok()
+                    .filter(|&j| j > 0)
+                    .ok_or_else(|| format!("invalid job count '{jobs}'"))?;
+            }
+            "--sprt" => opts.sprt = Some(parse_sprt(&value("--sprt")?)?),
+            "--sprt-alpha" => alpha = Some(parse_error_rate(&value("--sprt-alpha")?)?),
+            "--sprt-beta" => beta = Some(parse_error_rate(&value("--sprt-beta")?)?),
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    match &mut opts.sprt {
+        Some(sprt) => {
+            sprt.alpha = alpha.unwrap_or(sprt.alpha);
+            sprt.beta = beta.unwrap_or(sprt.beta);
+        }
+        None if alpha.is_some() || beta.is_some() => {
+            return Err("--sprt-alpha and --sprt-beta require --sprt".to_string());
+        }
+        None => {}
+    }
+    let seed = crate::global_seed()?.unwrap_or(0);
+    opts.a = a.ok_or("missing --a")?.seeded(seed, 0);
+    opts.b = b.ok_or("missing --b")?.seeded(seed, 1);
+    Ok(opts)
+}
+
+fn play<G>(
+    root: G,
+    opts: &Options,
+    a: &AgentFactory<G>,
+    b: &AgentFactory<G>,
+    fmt_move: impl Fn(&G::Move) -> String,
+) -> Result<(Vec<String>, MatchReport<G::Move>), String>
+where
+    G: GameState + Sync,
+    G::Move: Send,
+{
+    let openings = opening_positions(&root, opts.opening_plies);
+    if openings.is_empty() {
+        return Err(format!("no openings of {} plies", opts.opening_plies));
+    }
+    let (names, starts): (Vec<String>, Vec<G>) = openings
+        .into_iter()
+        .map(|(seq, state)| {
+            (
+                seq.iter().map(&fmt_move).collect::<Vec<_>>().join(" "),
+                state,
+            )
+        })
+        .unzip();
+    let report = run_match_parallel(&starts, a, b, opts.games, opts.jobs, opts.sprt.as_ref());
+    Ok((names, report))
+}
+
+/// Formats an Elo value, spelling out infinities.
+fn fmt_elo(elo: f64) -> String {
+    if elo.is_infinite() {
+        if elo > 0.0 { "+inf" } else { "-inf" }.to_string()
+    } else {
+        format!("{elo:+.0}")
+    }
+}
+
+fn print_report<M>(
+    opts: &Options,
+    openings: &[String],
+    report: &MatchReport<M>,
+    fmt_move: impl Fn(&M) -> String,
+) {
+    if !opts.quiet {
+        for (i, game) in report.games.iter().enumerate() {
+            let result = match game.result {
+                1 => "A wins",
+                -1 => "B wins",
+                _ => "draw",
+            };
+            let moves: Vec<String> = game.moves.iter().map(&fmt_move).collect();
+            println!(
+                "game {:>3}: A plays {}, opening [{}], moves [{}]: {result}",
+                i + 1,
+                if game.a_is_player1 { "first" } else { "second" },
+                openings[game.start],
+                moves.join(" ")
+            );
+        }
+        println!();
+    }
+    let s = report.score;
+    println!("A = {:?}\nB = {:?}", opts.a, opts.b);
+    println!(
+        "A: +{} ={} -{} ({:.1}%)",
+        s.wins,
+        s.draws,
+        s.losses,
+        s.score() * 100.0
+    );
+    if let Some(elo) = s.elo() {
+        println!(
+            "Elo difference (A - B): {} (95% CI {} to {})",
+            fmt_elo(elo.diff),
+            fmt_elo(elo.lower),
+            fmt_elo(elo.upper)
+        );
+    }
+    if let Some(sprt) = &opts.sprt {
+        let (lower, upper) = sprt.bounds();
+        let verdict = match sprt.decision(&s) {
+            SprtDecision::AcceptH0 => {
+                format!("H0 accepted (A is at most {} Elo stronger)", sprt.elo0)
+            }
+            SprtDecision::AcceptH1 => {
+                format!("H1 accepted (A is at least {} Elo stronger)", sprt.elo1)
+            }
+            SprtDecision::Continue => "inconclusive".to_string(),
+        };
+        println!(
+            "SPRT [{}, {}]: LLR {:.2} (bounds {:.2}, {:.2}) after {} games: {verdict}",
+            sprt.elo0,
+            sprt.elo1,
+            sprt.llr(&s),
+            lower,
+            upper,
+            s.games()
+        );
+    }
+}
+
+/// Runs the `match` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let opts = parse_options(args)?;
+    for depth in [opts.a.depth(), opts.b.depth()].into_iter().flatten() {
+        if opts.ttt {
+            crate::warn_if_impractical(&TicTacToeState::new(), depth);
+        } else {
+            crate::warn_if_impractical(&BitboardState::new(), depth);
+        }
+    }
+    if opts.ttt {
+        let fmt = |m: &TicTacToeMove| m.index.to_string();
+        let a = |game| opts.a.build(game);
+        let b = |game| opts.b.build(game);
+        let (openings, report) = play(TicTacToeState::new(), &opts, &a, &b, fmt)?;
+        print_report(&opts, &openings, &report, fmt);
+    } else {
+        let book = if opts.use_book {
+            OpeningBook::load_default()?.map(Arc::new)
+        } else {
+            None
+        };
+        if let Some(book) = &book {
+            let kind = if book.is_exact() { "exact " } else { "" };
+            println!("Using {kind}opening book ({} positions)", book.len());
+        }
+        let a = |game| opts.a.build_c4(book.as_ref(), game);
+        let b = |game| opts.b.build_c4(book.as_ref(), game);
+        let fmt = |c: &u8| c.to_string();
+        let (openings, report) = play(BitboardState::new(), &opts, &a, &b, fmt)?;
+        print_report(&opts, &openings, &report, fmt);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_engine_specs() {
+        assert_eq!(
+            EngineSpec::parse("ab:6").unwrap(),
+            EngineSpec::AlphaBeta {
+                depth: 6,
+                flat: false,
+                contempt: 0
+            }
+        );
+        assert_eq!(
+            EngineSpec::parse("ab:3:flat:contempt=-40").unwrap(),
+            EngineSpec::AlphaBeta {
+                depth: 3,
+                flat: true,
+                contempt: -40
+            }
+        );
+        assert_eq!(
+            EngineSpec::parse("random:9").unwrap(),
+            EngineSpec::Random { seed: Some(9) }
+        );
+        assert_eq!(
+            EngineSpec::parse("mtdf:8:contempt=25").unwrap(),
+            EngineSpec::Search {
+                depth: 8,
+                algorithm: SearchAlgorithm::Mtdf,
+                contempt: 25
+            }
+        );
+        assert_eq!(EngineSpec::parse("id:5").unwrap().depth(), Some(5));
+        assert_eq!(EngineSpec::parse("random").unwrap().depth(), None);
+        let random = EngineSpec::parse("random").unwrap();
+        assert_ne!(random.clone().seeded(5, 0), random.clone().seeded(5, 1));
+        assert_eq!(random.clone().seeded(5, 0), random.seeded(5, 0));
+        let own = EngineSpec::Random { seed: Some(9) };
+        assert_eq!(own.clone().seeded(5, 0), own);
+        for bad in [
+            "ab",
+            "ab:0",
+            "id:0",
+            "mtdf",
+            "ab:2:deep",
+            "mcts:5",
+            "random:x",
+            "ab:2:contempt=high",
+            "ab:2:contempt=5:flat",
+            "random:contempt=5",
+        ] {
+            assert!(EngineSpec::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn parses_sprt_options() {
+        let args = |s: &str| -> Vec<String> { s.split_whitespace().map(String::from).collect() };
+        let opts = parse_options(&args("--a ab:2 --b random --sprt 0,20 --sprt-beta 0.1")).unwrap();
+        assert_eq!(
+            opts.sprt,
+            Some(Sprt {
+                elo0: 0.0,
+                elo1: 20.0,
+                alpha: 0.05,
+                beta: 0.1
+            })
+        );
+        for bad in [
+            "--sprt 20,0",
+            "--sprt 5",
+            "--sprt 0,x",
+            "--sprt 0,5 --sprt-alpha 0.7",
+            "--sprt-alpha 0.1",
+        ] {
+            assert!(
+                parse_options(&args(&format!("--a ab:2 --b random {bad}"))).is_err(),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn c4_deeper_search_beats_random() {
+        let args: Vec<String> = "--a ab:2 --b random:1 --games 4"
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+        let opts = parse_options(&args).unwrap();
+        let a = |game| opts.a.build(game);
+        let b = |game| opts.b.build(game);
+        let (openings, report) =
+            play(BitboardState::new(), &opts, &a, &b, |c| c.to_string()).unwrap();
+        assert_eq!(openings.len(), 49);
+        assert_eq!(report.games.len(), 4);
+        assert!(report.score.score() > 0.5);
+    }
+}
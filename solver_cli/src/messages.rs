@@ -0,0 +1,547 @@
+//! The text of interactive games, in the player's language.
+//!
+//! Everything a game of `play` (or `resume`) says goes through a `Catalog`:
+//! each line is a `Message`, written out by the catalog of the chosen
+//! `Locale`, which also formats the numbers in it and supplies the words
+//! boards are described in (see `render::describe::Vocabulary`). English
+//! and Spanish are built in; another language is another `Catalog` and a
+//! `Locale` variant to pick it.
+//!
+//! The locale comes from `SOLVER_LANG` (`en` or `es`), or else from the
+//! usual `LC_ALL`, `LC_MESSAGES` and `LANG`, defaulting to English. Move
+//! errors from the engine and the other subcommands are in English.
+
+use std::sync::OnceLock;
+
+use solver_core::game::Player;
+use solver_core::render::describe::{self, Vocabulary};
+use solver_core::utils::opposite_player;
+
+/// Environment variable choosing the language of interactive games.
+const LANG_VAR: &str = "SOLVER_LANG";
+
+/// A language the interactive games can be played in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Locale {
+    /// The locale named by a language tag such as `es`, `es_MX.UTF-8` or
+    /// `en-GB`, if it is one of ours.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag.split(['_', '-', '.', '@']).next().unwrap_or_default();
+        match language.to_lowercase().as_str() {
+            "en" | "c" | "posix" => Some(Locale::English),
+            "es" => Some(Locale::Spanish),
+            _ => None,
+        }
+    }
+
+    /// The locale in `SOLVER_LANG`, or else the system's if we have it, or
+    /// else English. An unknown `SOLVER_LANG` is an error; an unknown
+    /// system locale isn't.
+    pub fn from_env() -> Result<Self, String> {
+        if let Ok(value) = std::env::var(LANG_VAR) {
+            return Self::parse(value.trim())
+                .ok_or_else(|| format!("invalid {LANG_VAR} '{value}' (expected en or es)"));
+        }
+        let system = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty());
+        Ok(system.and_then(|tag| Self::parse(&tag)).unwrap_or_default())
+    }
+
+    /// The catalog of the locale's messages.
+    pub fn catalog(self) -> &'static dyn Catalog {
+        match self {
+            Locale::English => &English,
+            Locale::Spanish => &Spanish,
+        }
+    }
+}
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Sets the locale of the process's games from the environment (see
+/// `Locale::from_env`). Only the first call has any effect.
+pub fn init_from_env() -> Result<(), String> {
+    let locale = Locale::from_env()?;
+    LOCALE.get_or_init(|| locale);
+    Ok(())
+}
+
+/// The catalog of the locale set by `init_from_env`, or English.
+pub fn catalog() -> &'static dyn Catalog {
+    LOCALE.get().copied().unwrap_or_default().catalog()
+}
+
+/// `message` in the words of `catalog()`.
+pub fn text(message: &Message) -> String {
+    catalog().text(message)
+}
+
+/// A line (or part of one) of an interactive game.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message<'a> {
+    Welcome,
+    /// Welcome to a game other than Tic-Tac-Toe, by its display name.
+    WelcomeTo(&'a str),
+    /// The human's side.
+    YouAre(Player),
+    /// Above the board of cell indices.
+    IndexMapping,
+    /// The human is to move as the player, with the seconds left on their
+    /// clock if there is one.
+    YourTurn(Player, Option<f64>),
+    MovePrompt,
+    /// The move prompt of games other than Tic-Tac-Toe, with their legal
+    /// moves.
+    MovePromptFor(&'a str),
+    EndOfInput,
+    /// The game was saved to this file.
+    Saved(&'a str),
+    /// `hint`: the engine's move for the human, and its value.
+    Hint(&'a str, i32),
+    /// `undo` took back the human's last move and the AI's reply.
+    Undone,
+    /// `undo` before the human has moved.
+    NothingToUndo,
+    /// A teaching warning: the move lets the opponent win with this reply.
+    AllowsWin(&'a str),
+    /// A teaching warning: the move loses by force.
+    LosesByForce,
+    /// Before a teaching warning.
+    Careful(&'a str),
+    PlayAnyway,
+    /// The move typed was rejected for this reason.
+    InvalidMove(&'a str),
+    AiThinking(Player),
+    /// The search was interrupted after completing this depth.
+    SearchInterrupted(u32),
+    /// The AI plays this cell, with this value.
+    AiChooses(u8, i32),
+    /// The AI plays this move, in the game's notation, with this value.
+    AiPlays(&'a str, i32),
+    /// The AI's commentary.
+    AiSays(&'a str),
+    AiHasNoMoves,
+    GameOver,
+    /// The moves of the game, as typed.
+    Record(&'a str),
+    /// This player's clock ran out.
+    OutOfTime(Player),
+    Wins(Player),
+    Draw,
+    Abandoned,
+    Interrupted,
+    RecoverAutosave,
+    PlayAgain,
+    /// The adaptive AI's level, from 0 to 1, and depth.
+    AiLevel(f64, u32),
+    /// The session's wins, draws, losses and games.
+    Session(u32, u32, u32, u32),
+    ReviewHeading,
+    NoBlunders,
+    /// A blunder in the review: its move number, the move played and its
+    /// value, the better move and its value, and the line after it.
+    Blunder {
+        number: usize,
+        played: &'a str,
+        played_value: i32,
+        best: &'a str,
+        best_value: i32,
+        line: &'a str,
+    },
+    AnnotatedRecord(&'a str),
+    /// The strength estimate: rating, equivalent depth, blunder rate (from
+    /// 0 to 1), average loss, moves and games.
+    Strength {
+        rating: f64,
+        depth: f64,
+        blunder_rate: f64,
+        average_loss: f64,
+        moves: u32,
+        games: u32,
+    },
+    /// The file a game is resumed from.
+    Resuming(&'a str),
+}
+
+/// The messages of one language.
+pub trait Catalog: Sync {
+    /// `message` in words.
+    fn text(&self, message: &Message) -> String;
+
+    /// `value` with `decimals` digits after the decimal point.
+    fn number(&self, value: f64, decimals: usize) -> String {
+        format!("{value:.decimals$}")
+    }
+
+    /// The hint after a yes/no question, and the answers (in lowercase)
+    /// that mean yes.
+    fn yes_no(&self) -> (&'static str, &'static [&'static str]) {
+        ("[y/N]", &["y", "yes"])
+    }
+
+    /// The words boards are described in for `--describe` (see
+    /// `render::describe::describe_in`).
+    fn words(&self) -> &dyn Vocabulary {
+        &describe::English
+    }
+}
+
+/// The built-in English messages.
+pub struct English;
+
+impl Catalog for English {
+    fn text(&self, message: &Message) -> String {
+        match message {
+            Message::Welcome => "Welcome to Tic-Tac-Toe!".to_string(),
+            Message::WelcomeTo(game) => format!("Welcome to {game}!"),
+            Message::YouAre(Player::Player1) => "You are X (Player1).".to_string(),
+            Message::YouAre(Player::Player2) => "You are O (Player2).".to_string(),
+            Message::IndexMapping => "Index mapping:".to_string(),
+            Message::YourTurn(player, Some(seconds)) => {
+                format!(
+                    "Your turn ({player:?}, {}s left).",
+                    self.number(*seconds, 1)
+                )
+            }
+            Message::YourTurn(player, None) => format!("Your turn ({player:?})."),
+            Message::MovePrompt => {
+                "Enter your move (0-8, or undo, hint, save [FILE]): ".to_string()
+            }
+            Message::MovePromptFor(moves) => {
+                format!("Enter your move ({moves}, or undo, hint, save [FILE]): ")
+            }
+            Message::EndOfInput => "End of input; abandoning the game.".to_string(),
+            Message::Saved(path) => {
+                format!("Game saved to {path}; resume it with: solver_cli resume {path}")
+            }
+            Message::Hint(mv, value) => format!("Hint: play {mv} (value = {value})."),
+            Message::Undone => "Took back your last move and the AI's reply.".to_string(),
+            Message::NothingToUndo => "You have no move to take back yet.".to_string(),
+            Message::AllowsWin(reply) => {
+                format!("that move lets your opponent win at once with {reply}")
+            }
+            Message::LosesByForce => "that move lets your opponent force a win".to_string(),
+            Message::Careful(warning) => format!("Careful: {warning}."),
+            Message::PlayAnyway => "Play it anyway?".to_string(),
+            Message::InvalidMove(reason) => format!("Invalid move: {reason}"),
+            Message::AiThinking(player) => format!("AI ({player:?}) is thinking..."),
+            Message::SearchInterrupted(depth) => {
+                format!("Interrupted; playing the best move found at depth {depth}.")
+            }
+            Message::AiChooses(index, value) => {
+                format!("AI chooses index {index} (value = {value}).")
+            }
+            Message::AiPlays(mv, value) => format!("AI plays {mv} (value = {value})."),
+            Message::AiSays(text) => format!("AI: \"{text}\""),
+            Message::AiHasNoMoves => "AI has no legal moves.".to_string(),
+            Message::GameOver => "Game over!".to_string(),
+            Message::Record(moves) => format!("Game record: {moves}"),
+            Message::OutOfTime(loser) => format!(
+                "{loser:?} ran out of time. {:?} wins!",
+                opposite_player(*loser)
+            ),
+            Message::Wins(Player::Player1) => "Player1 (X) wins!".to_string(),
+            Message::Wins(Player::Player2) => "Player2 (O) wins!".to_string(),
+            Message::Draw => "It's a draw!".to_string(),
+            Message::Abandoned => "The game was abandoned.".to_string(),
+            Message::Interrupted => "Interrupted.".to_string(),
+            Message::RecoverAutosave => "An unfinished game was autosaved. Recover it?".to_string(),
+            Message::PlayAgain => "Play again?".to_string(),
+            Message::AiLevel(level, depth) => format!(
+                "The AI plays at level {}% (depth {depth}).",
+                self.number(level * 100.0, 0)
+            ),
+            Message::Session(won, drawn, lost, games) => {
+                format!("Session: {won} won, {drawn} drawn, {lost} lost over {games} game(s)")
+            }
+            Message::ReviewHeading => "Review of your moves:".to_string(),
+            Message::NoBlunders => "  No blunders found.".to_string(),
+            Message::Blunder {
+                number,
+                played,
+                played_value,
+                best,
+                best_value,
+                line,
+            } => format!(
+                "  Move {number}: you played {played} (value {played_value}); {best} was better (value {best_value}): {line}"
+            ),
+            Message::AnnotatedRecord(record) => format!("Annotated record: {record}"),
+            Message::Strength {
+                rating,
+                depth,
+                blunder_rate,
+                average_loss,
+                moves,
+                games,
+            } => format!(
+                "Estimated strength: {} (plays like a depth-{} search; {}% blunders, average loss {} over {moves} moves in {games} games)",
+                self.number(*rating, 0),
+                self.number(*depth, 1),
+                self.number(blunder_rate * 100.0, 0),
+                self.number(*average_loss, 0),
+            ),
+            Message::Resuming(path) => format!("Resuming the game saved in {path}."),
+        }
+    }
+}
+
+/// The built-in Spanish messages.
+pub struct Spanish;
+
+impl Spanish {
+    fn player(player: Player) -> &'static str {
+        match player {
+            Player::Player1 => "Jugador 1 (X)",
+            Player::Player2 => "Jugador 2 (O)",
+        }
+    }
+}
+
+impl Catalog for Spanish {
+    fn text(&self, message: &Message) -> String {
+        let player = |player: &Player| Self::player(*player);
+        match message {
+            Message::Welcome => "¡Bienvenido al tres en raya!".to_string(),
+            Message::WelcomeTo(game) => format!("¡Bienvenido a {game}!"),
+            Message::YouAre(side) => format!("Juegas con {}.", player(side)),
+            Message::IndexMapping => "Números de las casillas:".to_string(),
+            Message::YourTurn(side, Some(seconds)) => format!(
+                "Te toca ({}, quedan {} s).",
+                player(side),
+                self.number(*seconds, 1)
+            ),
+            Message::YourTurn(side, None) => format!("Te toca ({}).", player(side)),
+            Message::MovePrompt => {
+                "Escribe tu jugada (0-8, o undo, hint, save [ARCHIVO]): ".to_string()
+            }
+            Message::MovePromptFor(moves) => {
+                format!("Escribe tu jugada ({moves}, o undo, hint, save [ARCHIVO]): ")
+            }
+            Message::EndOfInput => "Fin de la entrada; se abandona la partida.".to_string(),
+            Message::Saved(path) => {
+                format!("Partida guardada en {path}; para reanudarla: solver_cli resume {path}")
+            }
+            Message::Hint(mv, value) => format!("Pista: juega {mv} (valor = {value})."),
+            Message::Undone => "Se deshacen tu última jugada y la respuesta de la IA.".to_string(),
+            Message::NothingToUndo => "Aún no tienes ninguna jugada que deshacer.".to_string(),
+            Message::AllowsWin(reply) => {
+                format!("esa jugada deja ganar al rival en el acto con {reply}")
+            }
+            Message::LosesByForce => "esa jugada deja al rival forzar la victoria".to_string(),
+            Message::Careful(warning) => format!("Cuidado: {warning}."),
+            Message::PlayAnyway => "¿La juegas de todos modos?".to_string(),
+            Message::InvalidMove(reason) => format!("Jugada no válida: {reason}"),
+            Message::AiThinking(side) => format!("La IA ({}) está pensando...", player(side)),
+            Message::SearchInterrupted(depth) => {
+                format!("Interrumpida; se juega la mejor jugada hallada a profundidad {depth}.")
+            }
+            Message::AiChooses(index, value) => {
+                format!("La IA elige la casilla {index} (valor = {value}).")
+            }
+            Message::AiPlays(mv, value) => format!("La IA juega {mv} (valor = {value})."),
+            Message::AiSays(text) => format!("IA: «{text}»"),
+            Message::AiHasNoMoves => "La IA no tiene jugadas legales.".to_string(),
+            Message::GameOver => "¡Fin de la partida!".to_string(),
+            Message::Record(moves) => format!("Registro de la partida: {moves}"),
+            Message::OutOfTime(loser) => format!(
+                "Al {} se le acabó el tiempo. ¡Gana el {}!",
+                player(loser),
+                player(&opposite_player(*loser))
+            ),
+            Message::Wins(side) => format!("¡Gana el {}!", player(side)),
+            Message::Draw => "¡Empate!".to_string(),
+            Message::Abandoned => "La partida quedó abandonada.".to_string(),
+            Message::Interrupted => "Interrumpido.".to_string(),
+            Message::RecoverAutosave => {
+                "Hay una partida sin terminar guardada automáticamente. ¿Recuperarla?".to_string()
+            }
+            Message::PlayAgain => "¿Otra partida?".to_string(),
+            Message::AiLevel(level, depth) => format!(
+                "La IA juega al nivel {} % (profundidad {depth}).",
+                self.number(level * 100.0, 0)
+            ),
+            Message::Session(won, drawn, lost, games) => format!(
+                "Sesión: {won} ganadas, {drawn} empatadas, {lost} perdidas en {games} partida(s)"
+            ),
+            Message::ReviewHeading => "Revisión de tus jugadas:".to_string(),
+            Message::NoBlunders => "  No se encontraron errores graves.".to_string(),
+            Message::Blunder {
+                number,
+                played,
+                played_value,
+                best,
+                best_value,
+                line,
+            } => format!(
+                "  Jugada {number}: jugaste {played} (valor {played_value}); {best} era mejor (valor {best_value}): {line}"
+            ),
+            Message::AnnotatedRecord(record) => format!("Registro comentado: {record}"),
+            Message::Strength {
+                rating,
+                depth,
+                blunder_rate,
+                average_loss,
+                moves,
+                games,
+            } => format!(
+                "Fuerza estimada: {} (juega como una búsqueda a profundidad {}; {} % de errores graves, pérdida media de {} en {moves} jugadas de {games} partidas)",
+                self.number(*rating, 0),
+                self.number(*depth, 1),
+                self.number(blunder_rate * 100.0, 0),
+                self.number(*average_loss, 0),
+            ),
+            Message::Resuming(path) => format!("Se reanuda la partida guardada en {path}."),
+        }
+    }
+
+    /// With a decimal comma, as in most of the Spanish-speaking world.
+    fn number(&self, value: f64, decimals: usize) -> String {
+        format!("{value:.decimals$}").replace('.', ",")
+    }
+
+    fn yes_no(&self) -> (&'static str, &'static [&'static str]) {
+        ("[s/N]", &["s", "si", "sí", "y", "yes"])
+    }
+
+    fn words(&self) -> &dyn Vocabulary {
+        self
+    }
+}
+
+impl Vocabulary for Spanish {
+    fn empty_cell(&self) -> &str {
+        "vacía"
+    }
+
+    fn player(&self, symbol: &str, number: u8) -> String {
+        format!("{symbol} (jugador {number})")
+    }
+
+    fn list(&self, items: &[String]) -> String {
+        match items {
+            [] => String::new(),
+            [one] => one.clone(),
+            [rest @ .., last] => format!("{} y {last}", rest.join(", ")),
+        }
+    }
+
+    fn empty_board(&self) -> String {
+        "El tablero está vacío.".to_string()
+    }
+
+    fn column(&self, col: &str, pieces: &str, full: bool) -> String {
+        let full = if full { " (llena)" } else { "" };
+        format!("Columna {col}: {pieces} desde abajo{full}.")
+    }
+
+    fn empty_columns(&self, cols: &[String]) -> String {
+        match cols {
+            [col] => format!("La columna {col} está vacía."),
+            cols => format!("Las columnas {} están vacías.", self.list(cols)),
+        }
+    }
+
+    fn row(&self, row: &str, cells: &str) -> String {
+        format!("Fila {row}: {cells}.")
+    }
+
+    fn empty_row(&self, row: &str) -> String {
+        format!("La fila {row} está vacía.")
+    }
+
+    fn won(&self, player: &str) -> String {
+        format!("Ha ganado {player}.")
+    }
+
+    fn drawn(&self) -> String {
+        "La partida está empatada.".to_string()
+    }
+
+    fn to_move(&self, player: &str) -> String {
+        format!("Mueve {player}.")
+    }
+
+    fn column_place(&self, col: &str, height: usize, playable: bool) -> String {
+        let now = if playable { " (se puede jugar ya)" } else { "" };
+        format!("la columna {col}, altura {height}{now}")
+    }
+
+    fn cell_place(&self, row: &str, col: &str) -> String {
+        format!("la fila {row}, columna {col}")
+    }
+
+    fn threat(&self, player: &str, place: &str) -> String {
+        format!("{player} amenaza con ganar en {place}.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver_core::games::c4_bitboard::BitboardState;
+    use solver_core::games::ttt::TicTacToeState;
+    use solver_core::render::Labels;
+    use solver_core::render::describe::{describe, describe_in};
+
+    #[test]
+    fn locales_come_from_language_tags() {
+        assert_eq!(Locale::parse("es"), Some(Locale::Spanish));
+        assert_eq!(Locale::parse("es_MX.UTF-8"), Some(Locale::Spanish));
+        assert_eq!(Locale::parse("en-GB"), Some(Locale::English));
+        assert_eq!(Locale::parse("C.UTF-8"), Some(Locale::English));
+        assert_eq!(Locale::parse("fr_FR"), None);
+    }
+
+    #[test]
+    fn numbers_follow_the_locale() {
+        let (english, spanish) = (Locale::English.catalog(), Locale::Spanish.catalog());
+        assert_eq!(english.number(12.345, 1), "12.3");
+        assert_eq!(spanish.number(12.345, 1), "12,3");
+        let turn = Message::YourTurn(Player::Player2, Some(4.3));
+        assert_eq!(english.text(&turn), "Your turn (Player2, 4.3s left).");
+        assert_eq!(
+            spanish.text(&turn),
+            "Te toca (Jugador 2 (O), quedan 4,3 s)."
+        );
+        assert_eq!(
+            spanish.text(&Message::AiLevel(0.5, 4)),
+            "La IA juega al nivel 50 % (profundidad 4)."
+        );
+    }
+
+    #[test]
+    fn boards_are_described_in_the_locale() {
+        let state = TicTacToeState::from_str("XO..X....", Player::Player2).unwrap();
+        let described =
+            |locale: Locale, labels| describe_in(&state, labels, locale.catalog().words());
+        assert_eq!(
+            described(Locale::English, Labels::Letters),
+            describe(&state, Labels::Letters)
+        );
+        assert_eq!(
+            described(Locale::Spanish, Labels::Letters),
+            "Fila a: ✕, ◯, vacía.\n\
+             Fila b: vacía, ✕, vacía.\n\
+             La fila c está vacía.\n\
+             Mueve ◯ (jugador 2).\n\
+             ✕ amenaza con ganar en la fila c, columna c.\n"
+        );
+        let c4 = BitboardState::from_move_sequence("1122").unwrap();
+        assert_eq!(
+            describe_in(&c4, Labels::Numbers, Locale::Spanish.catalog().words()),
+            "Columna 1: ●, ○ desde abajo.\n\
+             Columna 2: ●, ○ desde abajo.\n\
+             Las columnas 0, 3, 4, 5 y 6 están vacías.\n\
+             Mueve ● (jugador 1).\n"
+        );
+    }
+}
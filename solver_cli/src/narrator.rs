@@ -0,0 +1,83 @@
+//! Prints a game's events at the terminal as a referee reports them: the
+//! engine's moves as it plays them and the result when the game ends.
+//!
+//! The interactive loops (`play`, `play --game`) submit every move through
+//! a `Referee` and let a `Narrator` tell the human what happened, so both
+//! describe games the same way.
+
+use solver_core::events::{GameEvent, GameObserver};
+use solver_core::game::{Outcome, Player};
+
+use crate::messages::{self, Message};
+
+/// Narrates the games of a human playing `human` against the engine.
+pub struct Narrator<F> {
+    human: Player,
+    /// Describes an engine move and its value for the human.
+    describe: F,
+    flagged: Option<Player>,
+}
+
+impl<F> Narrator<F> {
+    /// A narrator for a human playing `human`, with `describe` saying
+    /// what the engine played.
+    pub fn new(human: Player, describe: F) -> Self {
+        Self {
+            human,
+            describe,
+            flagged: None,
+        }
+    }
+
+    /// The player who lost on time, if any.
+    pub fn flagged(&self) -> Option<Player> {
+        self.flagged
+    }
+}
+
+impl<M, F: FnMut(&M, i32) -> String> GameObserver<M> for Narrator<F> {
+    fn on_event(&mut self, event: &GameEvent<M>) {
+        match event {
+            GameEvent::MoveMade {
+                player,
+                mv,
+                eval: Some(value),
+            } if *player != self.human => println!("{}", (self.describe)(mv, *value)),
+            GameEvent::ClockUpdate { player, remaining } if remaining.is_zero() => {
+                self.flagged = Some(*player);
+            }
+            GameEvent::GameEnded { outcome } => {
+                println!("\n{}", messages::text(&Message::GameOver));
+                let result = match (self.flagged, outcome) {
+                    (Some(loser), _) => Message::OutOfTime(loser),
+                    (None, Some(Outcome::Win(player))) => Message::Wins(*player),
+                    (None, Some(Outcome::Draw)) => Message::Draw,
+                    (None, None) => Message::Abandoned,
+                };
+                println!("{}", messages::text(&result));
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn remembers_who_lost_on_time() {
+        let mut narrator = Narrator::new(Player::Player1, |mv: &u8, _| mv.to_string());
+        narrator.on_event(&GameEvent::ClockUpdate {
+            player: Player::Player2,
+            remaining: Duration::from_secs(3),
+        });
+        assert_eq!(narrator.flagged(), None);
+        narrator.on_event(&GameEvent::ClockUpdate {
+            player: Player::Player2,
+            remaining: Duration::ZERO,
+        });
+        assert_eq!(narrator.flagged(), Some(Player::Player2));
+    }
+}
@@ -0,0 +1,253 @@
+//! Local player profiles.
+//!
+//! Each player (`--player NAME`, by default `$USER`) has a profile file in
+//! `$SOLVER_PROFILE_DIR`, or the working directory. A missing file is an
+//! empty profile.
+//!
+//! By default the file is `NAME.profile` and holds `key = value` lines like
+//! a theme file (`#` starts a comment). Keys:
+//! - `strength.games`, `strength.moves`, `strength.loss`,
+//!   `strength.blunders`: review totals (see `StrengthRecord`).
+//! - `strength.reference.D`: blunders of the depth-D reference search.
+//! - `results.GAME.DEPTH`: wins, draws and losses against the depth-DEPTH
+//!   AI, separated by spaces.
+//! - `streak`, `streak.best`: see `Stats`.
+//! - `time.moves`, `time.seconds`: moves made and seconds spent on them.
+//!
+//! With the `json` feature the file is `NAME.json` instead, holding the
+//! serialized `Profile`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::stats::Stats;
+use crate::strength::StrengthRecord;
+
+/// Environment variable naming the directory profiles are kept in.
+pub const PROFILE_DIR_ENV: &str = "SOLVER_PROFILE_DIR";
+/// Player name used when neither `--player` nor `$USER` is set.
+pub const DEFAULT_PLAYER_NAME: &str = "player";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct Profile {
+    pub strength: StrengthRecord,
+    pub stats: Stats,
+}
+
+/// Checks that `name` can be used as a player name: ASCII letters, digits,
+/// `-` and `_` only, so it is safe in a file name.
+pub fn parse_player_name(name: &str) -> Result<String, String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(format!(
+            "invalid player name '{name}' (use letters, digits, '-' and '_')"
+        ))
+    }
+}
+
+/// The player name when none is given: `$USER` if it is a valid name.
+pub fn default_player_name() -> String {
+    std::env::var("USER")
+        .ok()
+        .and_then(|user| parse_player_name(&user).ok())
+        .unwrap_or_else(|| DEFAULT_PLAYER_NAME.to_string())
+}
+
+#[cfg(any(test, not(feature = "json")))]
+fn parse_count<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid count '{value}'"))
+}
+
+impl Profile {
+    /// Parses a profile file in the `key = value` format.
+    #[cfg(any(test, not(feature = "json")))]
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut profile = Self::default();
+        let (strength, stats) = (&mut profile.strength, &mut profile.stats);
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected 'key = value'", n + 1))?;
+            let value = value.trim();
+            let at_line = |e: String| format!("line {}: {e}", n + 1);
+            match key.trim() {
+                "strength.games" => strength.games = parse_count(value).map_err(at_line)?,
+                "strength.moves" => strength.moves = parse_count(value).map_err(at_line)?,
+                "strength.loss" => strength.loss = parse_count(value).map_err(at_line)?,
+                "strength.blunders" => strength.blunders = parse_count(value).map_err(at_line)?,
+                "streak" => stats.streak = parse_count(value).map_err(at_line)?,
+                "streak.best" => stats.best_streak = parse_count(value).map_err(at_line)?,
+                "time.moves" => stats.moves = parse_count(value).map_err(at_line)?,
+                "time.seconds" => stats.seconds = parse_count(value).map_err(at_line)?,
+                other => {
+                    if let Some(depth) = other.strip_prefix("strength.reference.") {
+                        let depth = depth
+                            .parse::<usize>()
+                            .ok()
+                            .filter(|&d| d > 0)
+                            .ok_or_else(|| at_line(format!("unknown key '{other}'")))?;
+                        if strength.reference_blunders.len() < depth {
+                            strength.reference_blunders.resize(depth, 0);
+                        }
+                        strength.reference_blunders[depth - 1] =
+                            parse_count(value).map_err(at_line)?;
+                    } else if let Some((game, depth)) = other
+                        .strip_prefix("results.")
+                        .and_then(|rest| rest.split_once('.'))
+                    {
+                        let depth = parse_count(depth).map_err(at_line)?;
+                        let counts: Vec<u32> = value
+                            .split_whitespace()
+                            .map(parse_count)
+                            .collect::<Result<_, _>>()
+                            .map_err(at_line)?;
+                        let [wins, draws, losses] = counts[..] else {
+                            return Err(at_line(format!(
+                                "expected wins, draws and losses, got '{value}'"
+                            )));
+                        };
+                        *stats.counts_mut(game, depth) = crate::stats::ResultCounts {
+                            game: game.to_string(),
+                            depth,
+                            wins,
+                            draws,
+                            losses,
+                        };
+                    } else {
+                        return Err(at_line(format!("unknown key '{other}'")));
+                    }
+                }
+            }
+        }
+        Ok(profile)
+    }
+
+    /// The profile in the format read by `parse`.
+    #[cfg(any(test, not(feature = "json")))]
+    pub fn to_text(&self) -> String {
+        let (strength, stats) = (&self.strength, &self.stats);
+        let mut text = format!(
+            "strength.games = {}\nstrength.moves = {}\nstrength.loss = {}\nstrength.blunders = {}\n",
+            strength.games, strength.moves, strength.loss, strength.blunders
+        );
+        for (depth, count) in (1..).zip(&strength.reference_blunders) {
+            text.push_str(&format!("strength.reference.{depth} = {count}\n"));
+        }
+        for counts in &stats.results {
+            text.push_str(&format!(
+                "results.{}.{} = {} {} {}\n",
+                counts.game, counts.depth, counts.wins, counts.draws, counts.losses
+            ));
+        }
+        text.push_str(&format!(
+            "streak = {}\nstreak.best = {}\ntime.moves = {}\ntime.seconds = {}\n",
+            stats.streak, stats.best_streak, stats.moves, stats.seconds
+        ));
+        text
+    }
+
+    /// The profile file of player `name`.
+    pub fn path(name: &str) -> PathBuf {
+        let dir =
+            std::env::var_os(PROFILE_DIR_ENV).map_or_else(|| PathBuf::from("."), PathBuf::from);
+        let extension = if cfg!(feature = "json") {
+            "json"
+        } else {
+            "profile"
+        };
+        dir.join(format!("{name}.{extension}"))
+    }
+
+    #[cfg(not(feature = "json"))]
+    fn decode(text: &str) -> Result<Self, String> {
+        Self::parse(text)
+    }
+
+    #[cfg(feature = "json")]
+    fn decode(text: &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|e| e.to_string())
+    }
+
+    #[cfg(not(feature = "json"))]
+    fn encode(&self) -> String {
+        self.to_text()
+    }
+
+    #[cfg(feature = "json")]
+    fn encode(&self) -> String {
+        serde_json::to_string_pretty(self).expect("profiles serialize to JSON")
+    }
+
+    /// Loads the profile at `path`, or an empty profile if there is no file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        Self::decode(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Writes the profile to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        fs::write(path, self.encode())
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::GameResult;
+
+    fn sample() -> Profile {
+        let mut profile = Profile {
+            strength: StrengthRecord {
+                games: 2,
+                moves: 9,
+                loss: 1500,
+                blunders: 2,
+                reference_blunders: vec![5, 3, 0],
+            },
+            stats: Stats::default(),
+        };
+        profile.stats.record("ttt", 9, GameResult::Win, 5, 12.5);
+        profile.stats.record("c4", 8, GameResult::Loss, 10, 40.0);
+        profile
+    }
+
+    #[test]
+    fn profiles_round_trip() {
+        let profile = sample();
+        assert_eq!(Profile::parse(&profile.to_text()), Ok(profile.clone()));
+        assert_eq!(Profile::decode(&profile.encode()), Ok(profile));
+        assert_eq!(Profile::parse("# empty\n"), Ok(Profile::default()));
+        assert!(Profile::parse("strength.games = many").is_err());
+        assert!(Profile::parse("strength.reference.0 = 1").is_err());
+        assert!(Profile::parse("results.ttt.9 = 1 2").is_err());
+        assert!(Profile::parse("rating = 2000").is_err());
+    }
+
+    #[test]
+    fn player_names_are_file_safe() {
+        assert_eq!(parse_player_name("ada_99"), Ok("ada_99".to_string()));
+        for bad in ["", "../etc", "a b", "é"] {
+            assert!(parse_player_name(bad).is_err(), "{bad}");
+        }
+        assert!(Profile::path("ada").ends_with("ada.profile") || cfg!(feature = "json"));
+    }
+}
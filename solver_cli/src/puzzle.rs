@@ -0,0 +1,296 @@
+//! `puzzle`: "find the winning move" puzzles.
+//!
+//! `puzzle generate` plays random games and keeps positions where exactly
+//! one move forces a win within `--depth` plies. A puzzle's difficulty is
+//! the number of the solver's own moves the win takes: a difficulty-1
+//! puzzle wins at once, a difficulty-3 puzzle needs two more accurate
+//! moves after the first, whatever the opponent does.
+//!
+//! Puzzle files hold one puzzle per line, `#` starting a comment:
+//!
+//! ```text
+//! <position> <solution> <difficulty>
+//! ```
+//!
+//! Positions use the `analyze` notation (a Connect Four move sequence,
+//! or a 9-character Tic-Tac-Toe board); the solution is a column or cell
+//! index.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+
+use solver_core::game::{GameState, Outcome, Player};
+use solver_core::games::c4_bitboard::BitboardState;
+use solver_core::games::ttt::TicTacToeState;
+use solver_core::rng::{Rng, SplitMix64};
+use solver_core::solvers::minimax::{WIN_SCORE, minimax_value_ab_depth_root};
+
+use crate::args::{self, parse_number};
+
+pub const USAGE: &str = "usage: solver_cli puzzle generate [--game c4|ttt] [--count N] [--depth N] [--min-difficulty N] [--seed N] [--output FILE]";
+
+/// Random games tried per requested puzzle before giving up.
+const ATTEMPTS_PER_PUZZLE: usize = 500;
+
+/// A position with a single winning move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Puzzle {
+    position: String,
+    solution: String,
+    difficulty: u32,
+}
+
+impl Puzzle {
+    fn to_line(&self) -> String {
+        format!("{} {} {}", self.position, self.solution, self.difficulty)
+    }
+}
+
+/// Returns the only move that wins by force from `state` within `depth`
+/// plies, with the number of the mover's moves the win takes, or `None`
+/// if no move or several moves win.
+fn unique_win<G: GameState>(state: &G, depth: u32) -> Option<(G::Move, u32)> {
+    let mover = state.current_player();
+    let target = if mover == Player::Player1 {
+        WIN_SCORE
+    } else {
+        -WIN_SCORE
+    };
+    let won = |child: &G, d: u32| minimax_value_ab_depth_root(child, d) == target;
+    let mut winner = None;
+    for mv in state.legal_moves() {
+        let child = state.apply_move(&mv);
+        // Plies after this move until the win is certain.
+        let plies = if child.outcome() == Some(Outcome::Win(mover)) {
+            Some(0)
+        } else if child.is_terminal() {
+            None
+        } else {
+            (1..depth).find(|&d| won(&child, d))
+        };
+        if let Some(plies) = plies {
+            if winner.is_some() {
+                return None;
+            }
+            // The opponent replies first, so the mover's n-th further move
+            // is ply 2n.
+            winner = Some((mv, 1 + plies / 2));
+        }
+    }
+    winner
+}
+
+/// Plays a random game of up to `max_plies` moves from `root` and returns
+/// every position along the way (with the moves leading to it), stopping
+/// before the game ends.
+fn random_line<G: GameState>(
+    root: &G,
+    max_plies: u32,
+    rng: &mut impl Rng,
+) -> Vec<(G, Vec<G::Move>)> {
+    let mut line = Vec::new();
+    let mut state = root.clone();
+    let mut moves = Vec::new();
+    for _ in 0..max_plies {
+        let legal = state.legal_moves();
+        let Some(mv) = rng.choose(&legal).cloned() else {
+            break;
+        };
+        let next = state.apply_move(&mv);
+        if next.is_terminal() {
+            break;
+        }
+        moves.push(mv);
+        state = next;
+        line.push((state.clone(), moves.clone()));
+    }
+    line
+}
+
+#[derive(Debug)]
+struct Options {
+    ttt: bool,
+    count: usize,
+    depth: u32,
+    min_difficulty: u32,
+    seed: u64,
+    output: Option<String>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        ttt: false,
+        count: 10,
+        depth: 0,
+        min_difficulty: 2,
+        seed: crate::global_seed()?.unwrap_or(1),
+        output: None,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--game" => {
+                opts.ttt = match iter.next().map(String::as_str) {
+                    Some("c4") => false,
+                    Some("ttt") => true,
+                    Some(other) => return Err(format!("unknown game '{other}'")),
+                    None => return Err("--game requires a value".to_string()),
+                }
+            }
+            "--count" => opts.count = parse_number(arg, iter.next())?,
+            "--depth" => opts.depth = parse_number(arg, iter.next())?,
+            "--min-difficulty" => opts.min_difficulty = parse_number(arg, iter.next())?,
+            "--seed" => opts.seed = parse_number(arg, iter.next())?,
+            "--output" => opts.output = Some(args::value(arg, iter.next())?),
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    if opts.depth == 0 {
+        opts.depth = if opts.ttt {
+            TicTacToeState::new().recommended_depth()
+        } else {
+            BitboardState::new().recommended_depth()
+        };
+    }
+    Ok(opts)
+}
+
+/// Generates up to `opts.count` distinct puzzles from random games of
+/// `root`, writing positions with `notation`.
+fn generate_from<G: GameState>(
+    root: &G,
+    opts: &Options,
+    notation: impl Fn(&G, &[G::Move]) -> String,
+    fmt_move: impl Fn(&G::Move) -> String,
+) -> Vec<Puzzle> {
+    let mut rng = SplitMix64::for_stream(opts.seed, crate::PUZZLE_STREAM);
+    let mut seen = HashSet::new();
+    let mut puzzles = Vec::new();
+    let max_plies = root.complexity_hint().max_plies.unwrap_or(40);
+    for _ in 0..opts.count * ATTEMPTS_PER_PUZZLE {
+        if puzzles.len() >= opts.count {
+            break;
+        }
+        for (state, moves) in random_line(root, max_plies, &mut rng) {
+            let position = notation(&state, &moves);
+            if !seen.insert(position.clone()) {
+                continue;
+            }
+            if let Some((mv, difficulty)) = unique_win(&state, opts.depth)
+                && difficulty >= opts.min_difficulty
+            {
+                puzzles.push(Puzzle {
+                    position,
+                    solution: fmt_move(&mv),
+                    difficulty,
+                });
+                // One puzzle per game keeps the set varied.
+                break;
+            }
+        }
+    }
+    puzzles
+}
+
+fn generate(args: &[String]) -> Result<(), String> {
+    let opts = parse_options(args)?;
+    let puzzles = if opts.ttt {
+        generate_from(
+            &TicTacToeState::new(),
+            &opts,
+            |s, _| s.to_repr(),
+            |m| m.index.to_string(),
+        )
+    } else {
+        generate_from(
+            &BitboardState::new(),
+            &opts,
+            |_, moves| moves.iter().map(u8::to_string).collect(),
+            u8::to_string,
+        )
+    };
+    if puzzles.len() < opts.count {
+        eprintln!(
+            "warning: only found {} of {} puzzles",
+            puzzles.len(),
+            opts.count
+        );
+    }
+
+    let mut out: Box<dyn Write> = match &opts.output {
+        Some(path) => {
+            Box::new(fs::File::create(path).map_err(|e| format!("failed to create {path}: {e}"))?)
+        }
+        None => Box::new(io::stdout().lock()),
+    };
+    let game = if opts.ttt { "ttt" } else { "c4" };
+    let write = |out: &mut dyn Write| -> io::Result<()> {
+        writeln!(
+            out,
+            "# {game} puzzles, depth {}, seed {}",
+            opts.depth, opts.seed
+        )?;
+        writeln!(out, "# position solution difficulty")?;
+        for puzzle in &puzzles {
+            writeln!(out, "{}", puzzle.to_line())?;
+        }
+        Ok(())
+    };
+    write(&mut out).map_err(|e| format!("failed to write puzzles: {e}"))
+}
+
+/// Runs the `puzzle` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("generate") => generate(&args[1..]),
+        Some(other) => Err(format!("unknown puzzle command '{other}'")),
+        None => Err("missing puzzle command".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ttt_unique_wins_are_rated_by_length() {
+        // X wins at once at 2; nothing else wins.
+        let s = TicTacToeState::from_str("XX.OO....", Player::Player1).unwrap();
+        assert_eq!(unique_win(&s, 9).map(|(m, d)| (m.index, d)), Some((2, 1)));
+
+        // Two ways to win at once: not a puzzle.
+        let s = TicTacToeState::from_str("XX.X.O.OO", Player::Player1).unwrap();
+        assert!(unique_win(&s, 9).is_none());
+
+        // The empty board is a draw.
+        assert!(unique_win(&TicTacToeState::new(), 9).is_none());
+    }
+
+    #[test]
+    fn c4_generated_puzzles_are_unique_wins() {
+        let opts = Options {
+            ttt: false,
+            count: 2,
+            depth: 4,
+            min_difficulty: 1,
+            seed: 3,
+            output: None,
+        };
+        let puzzles = generate_from(
+            &BitboardState::new(),
+            &opts,
+            |_, moves| moves.iter().map(u8::to_string).collect(),
+            u8::to_string,
+        );
+        assert_eq!(puzzles.len(), 2);
+        for puzzle in puzzles {
+            let state = BitboardState::from_move_sequence(&puzzle.position).unwrap();
+            let (mv, difficulty) = unique_win(&state, 4).unwrap();
+            assert_eq!(mv.to_string(), puzzle.solution);
+            assert_eq!(difficulty, puzzle.difficulty);
+            let line = puzzle.to_line();
+            assert_eq!(line.split(' ').count(), 3);
+        }
+    }
+}
@@ -0,0 +1,516 @@
+//! `replay`: steps through a recorded game.
+//!
+//! A record file holds the moves of one game in the game's notation, as
+//! printed at the end of an interactive game (`Game record: 4 0 8`), so
+//! the output of a game can be saved and replayed as it is. Blank lines
+//! and lines starting with `#` are ignored, and a `game: NAME` line names
+//! the game (Tic-Tac-Toe, the interactive game, otherwise; `--game`
+//! overrides it):
+//!
+//! ```text
+//! # X wins down the middle column
+//! game: ttt
+//! Game record: 4 0 8 2 1 3 7
+//! ```
+//!
+//! In a file without a `Game record:` line, the first other line holds
+//! the moves. An `Annotated record:` line, as printed after the review of
+//! a game, takes precedence; its markers (`!!`, `!`, `?!`, `?`, `??`) are
+//! shown with the moves they follow.
+//!
+//! The plain viewer reads commands from standard input: Enter or `n` for
+//! the next move, `p` for the previous one, `f` and `l` for the first and
+//! last position, `g N` to go to move N, `e` to toggle the engine's
+//! evaluation, and `q` to quit. With `--tui` (and the `tui` feature) the
+//! arrow keys, Home and End step through the game instead.
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use solver_core::annotate::Annotation;
+use solver_core::dyn_game::DynGameState;
+use solver_core::game::{Outcome, Player};
+use solver_core::registry::{GameEntry, Registry};
+use solver_core::render::{BoardStyle, Labels, Layout};
+use solver_core::solvers::minimax::WIN_SCORE;
+
+use crate::args;
+
+pub const USAGE: &str = "usage: solver_cli replay <game.rec> [--game NAME] [--depth N] [--eval] [--tui] [--coords numbers|letters] [--flip] [--describe]";
+
+/// The game of records without a `game:` line.
+const DEFAULT_GAME: &str = "ttt";
+
+#[derive(Debug)]
+struct Options {
+    path: String,
+    game: Option<String>,
+    depth: Option<u32>,
+    eval: bool,
+    tui: bool,
+    coords: Option<Labels>,
+    flip: bool,
+    describe: bool,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        path: String::new(),
+        game: None,
+        depth: None,
+        eval: false,
+        tui: false,
+        coords: None,
+        flip: false,
+        describe: false,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| args::value(name, iter.next());
+        match arg.as_str() {
+            "--game" => opts.game = Some(value("--game")?),
+            "--depth" => {
+                let depth = value("--depth")?;
+                opts.depth = Some(
+                    depth
+                        .parse()
+                        .ok()
+                        .filter(|&d| d > 0)
+                        .ok_or_else(|| format!("invalid depth '{depth}'"))?,
+                );
+            }
+            "--eval" => opts.eval = true,
+            "--tui" => opts.tui = true,
+            "--coords" => opts.coords = Some(args::parse_labels(&value("--coords")?)?),
+            "--flip" => opts.flip = true,
+            "--describe" => opts.describe = true,
+            other if other.starts_with("--") => {
+                return Err(format!("unknown option '{other}'"));
+            }
+            path if opts.path.is_empty() => opts.path = path.to_string(),
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    if opts.path.is_empty() {
+        return Err("missing record file".to_string());
+    }
+    Ok(opts)
+}
+
+/// The contents of a record file.
+#[derive(Debug, PartialEq, Eq)]
+struct Record {
+    game: Option<String>,
+    moves: String,
+}
+
+fn parse_record(text: &str) -> Result<Record, String> {
+    let mut game = None;
+    let mut annotated = None;
+    let mut recorded = None;
+    let mut first = None;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("game:") {
+            game = Some(name.trim().to_string());
+        } else if let Some(moves) = line.strip_prefix("Annotated record:") {
+            annotated = Some(moves.trim().to_string());
+        } else if let Some(moves) = line.strip_prefix("Game record:") {
+            recorded = Some(moves.trim().to_string());
+        } else if first.is_none() {
+            first = Some(line.to_string());
+        }
+    }
+    let moves = annotated
+        .or(recorded)
+        .or(first)
+        .ok_or_else(|| "the file holds no game record".to_string())?;
+    Ok(Record { game, moves })
+}
+
+/// A recorded game and the position being viewed, with the engine's
+/// evaluations of the positions seen so far.
+pub struct Replay {
+    pub entry: GameEntry,
+    /// The initial position and the position after every move.
+    pub positions: Vec<Box<dyn DynGameState>>,
+    /// Moves played to reach the current position.
+    pub ply: usize,
+    pub show_eval: bool,
+    /// Coordinate labels and orientation of the board.
+    pub layout: Layout,
+    /// If set, the plain viewer follows the board with a description in
+    /// prose, its rows and columns named by these labels.
+    pub describe: Option<Labels>,
+    /// The recorded annotation of every move.
+    pub annotations: Vec<Option<Annotation>>,
+    depth: u32,
+    evaluations: Vec<Option<String>>,
+}
+
+impl Replay {
+    /// Replays `moves` (in the notation of `entry`'s game, optionally
+    /// annotated), starting at the initial position.
+    pub fn new(entry: GameEntry, moves: &str, depth: u32) -> Result<Self, String> {
+        let (moves, notes) = strip_annotations(entry, moves)?;
+        let last = (entry.from_notation)(&moves).map_err(|e| format!("invalid record: {e}"))?;
+        let mut positions = vec![(entry.new_game)()];
+        for mv in last.history() {
+            let next = positions[positions.len() - 1]
+                .apply(&mv)
+                .map_err(|e| e.to_string())?;
+            positions.push(next);
+        }
+        let mut annotations = vec![None; positions.len() - 1];
+        for (ply, note) in notes {
+            annotations[ply] = Some(note);
+        }
+        Ok(Self {
+            entry,
+            evaluations: vec![None; positions.len()],
+            positions,
+            ply: 0,
+            annotations,
+            show_eval: false,
+            layout: Layout::default(),
+            describe: None,
+            depth,
+        })
+    }
+
+    /// The number of moves in the game.
+    pub fn len(&self) -> usize {
+        self.positions.len() - 1
+    }
+
+    pub fn current(&self) -> &dyn DynGameState {
+        self.positions[self.ply].as_ref()
+    }
+
+    /// Moves `by` plies forward (or back, if negative), stopping at either
+    /// end of the game.
+    pub fn step(&mut self, by: isize) {
+        self.go_to(self.ply.saturating_add_signed(by));
+    }
+
+    /// Goes to the position after `ply` moves, or the last position.
+    pub fn go_to(&mut self, ply: usize) {
+        self.ply = ply.min(self.len());
+    }
+
+    /// The engine's view of the current position, searched at most once.
+    pub fn evaluation(&mut self) -> &str {
+        let depth = self.depth;
+        let state = self.positions[self.ply].as_ref();
+        self.evaluations[self.ply].get_or_insert_with(|| evaluate(state, depth))
+    }
+
+    /// The current position's headline: the move number, the move that
+    /// led to it, and who is to move or how the game ended.
+    pub fn caption(&self) -> String {
+        let state = self.current();
+        let note = self
+            .ply
+            .checked_sub(1)
+            .and_then(|ply| self.annotations[ply])
+            .map_or("", Annotation::symbol);
+        let last = state
+            .history()
+            .last()
+            .map_or(String::new(), |mv| format!(", after {mv}{note}"));
+        let status = match state.outcome() {
+            Some(Outcome::Win(player)) => format!("{} wins", mark(player)),
+            Some(Outcome::Draw) => "draw".to_string(),
+            None => format!("{} to move", mark(state.current_player())),
+        };
+        format!("Move {}/{}{last}: {status}", self.ply, self.len())
+    }
+}
+
+/// Removes the annotations from a record, returning the bare moves and
+/// each annotation with the index of the move it follows.
+fn strip_annotations(
+    entry: GameEntry,
+    moves: &str,
+) -> Result<(String, Vec<(usize, Annotation)>), String> {
+    let is_marker = |c: char| c == '!' || c == '?';
+    let mut bare = String::new();
+    let mut notes = Vec::new();
+    let mut rest = moves;
+    while let Some(start) = rest.find(is_marker) {
+        bare.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|c| !is_marker(c)).unwrap_or(rest.len());
+        let symbol = &rest[..end];
+        let note = Annotation::from_symbol(symbol)
+            .ok_or_else(|| format!("invalid record: unknown annotation '{symbol}'"))?;
+        let played = (entry.from_notation)(&bare)
+            .map_err(|e| format!("invalid record: {e}"))?
+            .history()
+            .len();
+        let ply = played
+            .checked_sub(1)
+            .ok_or_else(|| format!("invalid record: '{symbol}' follows no move"))?;
+        notes.push((ply, note));
+        rest = &rest[end..];
+    }
+    bare.push_str(rest);
+    Ok((bare, notes))
+}
+
+fn mark(player: Player) -> &'static str {
+    match player {
+        Player::Player1 => "X",
+        Player::Player2 => "O",
+    }
+}
+
+/// The best move and value of `state` at `depth` plies, in words.
+fn evaluate(state: &dyn DynGameState, depth: u32) -> String {
+    let result = state.search(depth, None);
+    let (Some(best), Some(value)) = (result.best_move(), result.value()) else {
+        return "the game is over".to_string();
+    };
+    let value = if value >= WIN_SCORE {
+        "X wins".to_string()
+    } else if value <= -WIN_SCORE {
+        "O wins".to_string()
+    } else {
+        format!("{value:+}")
+    };
+    format!("best {best}, {value} (depth {})", result.depth)
+}
+
+/// Shows the replay's position, with the evaluation if it is on.
+fn show(replay: &mut Replay, out: &mut impl Write) -> io::Result<()> {
+    let board = replay
+        .current()
+        .render_layout(BoardStyle::Ascii, &replay.layout);
+    write!(out, "\n{board}")?;
+    if let Some(labels) = replay.describe {
+        write!(out, "{}", replay.current().describe(labels))?;
+    }
+    writeln!(out, "{}", replay.caption())?;
+    if replay.show_eval {
+        writeln!(out, "Engine: {}", replay.evaluation())?;
+    }
+    Ok(())
+}
+
+/// Runs the plain viewer, reading commands from `input`.
+fn view(replay: &mut Replay, input: impl BufRead, out: &mut impl Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}, {} moves. Enter steps forward; ? lists the commands.",
+        replay.entry.display_name,
+        replay.len()
+    )?;
+    show(replay, out)?;
+    write!(out, "> ")?;
+    out.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (None | Some("n"), _) => replay.step(1),
+            (Some("p"), _) => replay.step(-1),
+            (Some("f"), _) => replay.go_to(0),
+            (Some("l"), _) => replay.go_to(replay.len()),
+            (Some("g"), Some(n)) if n.parse::<usize>().is_ok() => {
+                replay.go_to(n.parse().unwrap_or_default());
+            }
+            (Some("e"), _) => replay.show_eval = !replay.show_eval,
+            (Some("q"), _) => return Ok(()),
+            _ => {
+                writeln!(
+                    out,
+                    "commands: n (next), p (previous), f (first), l (last), g N (go to move N), e (evaluation), q (quit)"
+                )?;
+                write!(out, "> ")?;
+                out.flush()?;
+                continue;
+            }
+        }
+        show(replay, out)?;
+        write!(out, "> ")?;
+        out.flush()?;
+    }
+    // The input ended after a prompt.
+    writeln!(out)
+}
+
+/// Runs the `replay` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let opts = parse_options(args)?;
+    let text =
+        fs::read_to_string(&opts.path).map_err(|e| format!("cannot read '{}': {e}", opts.path))?;
+    let record = parse_record(&text)?;
+    let game = opts
+        .game
+        .or(record.game)
+        .unwrap_or_else(|| DEFAULT_GAME.to_string());
+    let registry = Registry::builtin();
+    let entry = *registry.get(&game).ok_or_else(|| {
+        format!(
+            "unknown game '{game}' (known: {})",
+            registry.names().join(", ")
+        )
+    })?;
+    let mut replay = Replay::new(
+        entry,
+        &record.moves,
+        opts.depth.unwrap_or(entry.default_depth),
+    )?;
+    replay.show_eval = opts.eval;
+    if let Some(labels) = opts.coords {
+        replay.layout = replay.current().coordinates(labels);
+    }
+    if opts.flip {
+        replay.layout = replay.layout.flip();
+    }
+    if opts.describe {
+        replay.describe = Some(opts.coords.unwrap_or(Labels::Numbers));
+    }
+    if opts.tui {
+        #[cfg(feature = "tui")]
+        return crate::tui::replay(replay);
+        #[cfg(not(feature = "tui"))]
+        return Err("--tui needs solver_cli built with the tui feature".to_string());
+    }
+    view(&mut replay, io::stdin().lock(), &mut io::stdout())
+        .map_err(|e| format!("cannot show the replay: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::split_args;
+
+    fn replay(game: &str, moves: &str) -> Replay {
+        let entry = *Registry::builtin().get(game).unwrap();
+        Replay::new(entry, moves, 4).unwrap()
+    }
+
+    #[test]
+    fn parses_options_and_records() {
+        let opts = parse_options(&split_args("game.rec --game c4 --depth 6 --eval --tui")).unwrap();
+        assert_eq!(opts.path, "game.rec");
+        assert_eq!(opts.game.as_deref(), Some("c4"));
+        assert_eq!(opts.depth, Some(6));
+        assert!(opts.eval && opts.tui);
+        let opts = parse_options(&split_args("game.rec --coords letters --flip")).unwrap();
+        assert_eq!((opts.coords, opts.flip), (Some(Labels::Letters), true));
+        assert!(
+            parse_options(&split_args("game.rec --describe"))
+                .unwrap()
+                .describe
+        );
+        assert!(parse_options(&split_args("game.rec --coords")).is_err());
+        assert!(parse_options(&split_args("--eval")).is_err());
+        assert!(parse_options(&split_args("a.rec b.rec")).is_err());
+
+        let output = "X | O | .\nGame over!\nGame record: 4 0 8\nIt's a draw!\n";
+        assert_eq!(
+            parse_record(output).unwrap(),
+            Record {
+                game: None,
+                moves: "4 0 8".to_string()
+            }
+        );
+        assert_eq!(
+            parse_record("# opening\ngame: c4\n\n3342\n").unwrap(),
+            Record {
+                game: Some("c4".to_string()),
+                moves: "3342".to_string()
+            }
+        );
+        assert!(parse_record("# nothing\n").is_err());
+    }
+
+    #[test]
+    fn steps_through_the_game() {
+        let mut replay = replay("ttt", "4 0 8 2 1 3 7");
+        assert_eq!(replay.len(), 7);
+        assert_eq!(replay.caption(), "Move 0/7: X to move");
+        replay.step(-1);
+        assert_eq!(replay.ply, 0);
+        replay.step(3);
+        assert_eq!(replay.caption(), "Move 3/7, after 8: O to move");
+        replay.go_to(99);
+        assert_eq!(replay.caption(), "Move 7/7, after 7: X wins");
+        assert_eq!(replay.evaluation(), "the game is over");
+
+        replay.go_to(6);
+        assert_eq!(replay.evaluation(), "best 7, X wins (depth 1)");
+
+        let entry = *Registry::builtin().get("c4").unwrap();
+        assert!(Replay::new(entry, "339", 4).is_err());
+    }
+
+    #[test]
+    fn annotations_are_shown_with_their_moves() {
+        let output = "Game record: 0 1 4\nAnnotated record: 0 1?? 4\n";
+        let record = parse_record(output).unwrap();
+        assert_eq!(record.moves, "0 1?? 4");
+        let mut replay = replay("ttt", &record.moves);
+        assert_eq!(replay.len(), 3);
+        assert_eq!(replay.annotations, [None, Some(Annotation::Blunder), None]);
+        replay.go_to(2);
+        assert_eq!(replay.caption(), "Move 2/3, after 1??: X to move");
+        replay.go_to(3);
+        assert_eq!(replay.caption(), "Move 3/3, after 4: O to move");
+
+        // Moves without separators take markers too.
+        let c4 = *Registry::builtin().get("c4").unwrap();
+        assert_eq!(
+            Replay::new(c4, "33!4?!2", 4).unwrap().annotations,
+            [
+                None,
+                Some(Annotation::Good),
+                Some(Annotation::Inaccuracy),
+                None
+            ]
+        );
+
+        let entry = *Registry::builtin().get("ttt").unwrap();
+        assert!(Replay::new(entry, "0 1?!? 4", 4).is_err());
+        assert!(Replay::new(entry, "?? 0", 4).is_err());
+    }
+
+    #[test]
+    fn plain_viewer_follows_commands() {
+        let mut replay = replay("c4", "3342");
+        let mut out = Vec::new();
+        view(
+            &mut replay,
+            "n\n\ng 4\ne\np\nx\nq\nn\n".as_bytes(),
+            &mut out,
+        )
+        .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let captions: Vec<&str> = out
+            .lines()
+            .filter_map(|line| {
+                line.strip_prefix("> ")
+                    .unwrap_or(line)
+                    .strip_prefix("Move ")
+            })
+            .collect();
+        assert_eq!(
+            captions,
+            [
+                "0/4: X to move",
+                "1/4, after 3: O to move",
+                "2/4, after 3: X to move",
+                "4/4, after 2: X to move",
+                "4/4, after 2: X to move",
+                "3/4, after 4: O to move",
+            ]
+        );
+        assert_eq!(out.matches("Engine: best ").count(), 2);
+        assert_eq!(out.matches("commands: ").count(), 1);
+        assert_eq!(replay.ply, 3);
+    }
+}
@@ -0,0 +1,182 @@
+//! Post-game review of the human's moves.
+//!
+//! Every position the human faced is searched again, and moves whose value
+//! (from the human's side) fell more than `BLUNDER_THRESHOLD` short of the
+//! best move are reported with the better move and its line. The whole
+//! game is then written out with every move annotated (`!!` to `??`, see
+//! `solver_core::annotate`), ready for `replay`.
+
+use solver_core::annotate::annotate_game;
+use solver_core::game::{GameState, Player};
+use solver_core::solvers::iterative::principal_variation;
+use solver_core::solvers::minimax::{minimax_best_move_ab_depth, minimax_value_ab_depth_root};
+
+use crate::messages::{self, Message};
+
+/// Value drop (in heuristic units) that makes a move a blunder. Any change
+/// of the forced result is far larger.
+pub const BLUNDER_THRESHOLD: i32 = 50;
+
+/// The evaluation of one human move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MoveReview<M> {
+    /// Index of the move in the game, from 0.
+    pub ply: usize,
+    /// The move played.
+    pub played: M,
+    /// Value of the position after `played`, from Player1's perspective.
+    pub played_value: i32,
+    /// The best move in the position.
+    pub best: M,
+    /// Value of the position after `best`, from Player1's perspective.
+    pub best_value: i32,
+    /// The principal variation starting with `best`.
+    pub line: Vec<M>,
+    /// How much worse `played` is than `best` for the human.
+    pub loss: i32,
+}
+
+impl<M> MoveReview<M> {
+    /// Returns true if the move lost more than `BLUNDER_THRESHOLD`.
+    pub fn is_blunder(&self) -> bool {
+        self.loss > BLUNDER_THRESHOLD
+    }
+}
+
+/// Replays `moves` from `root` and evaluates every move `human` made with
+/// a `depth`-ply search.
+pub fn review_game<G>(
+    root: &G,
+    moves: &[G::Move],
+    human: Player,
+    depth: u32,
+) -> Vec<MoveReview<G::Move>>
+where
+    G: GameState,
+{
+    let sign = if human == Player::Player1 { 1 } else { -1 };
+    let mut reviews = Vec::new();
+    let mut state = root.clone();
+    for (ply, mv) in moves.iter().enumerate() {
+        let after = state.apply_move(mv);
+        if state.current_player() == human
+            && let Some((best, best_value)) = minimax_best_move_ab_depth(&state, depth)
+        {
+            // Searched to the same depth as the best move's value.
+            let played_value = minimax_value_ab_depth_root(&after, depth.saturating_sub(1));
+            let mut line = vec![best.clone()];
+            line.extend(principal_variation(
+                &state.apply_move(&best),
+                depth.saturating_sub(1),
+            ));
+            reviews.push(MoveReview {
+                ply,
+                played: mv.clone(),
+                played_value,
+                best,
+                best_value,
+                line,
+                loss: (best_value - played_value).saturating_mul(sign).max(0),
+            });
+        }
+        state = after;
+    }
+    reviews
+}
+
+/// Prints the blunders in `reviews` in the locale's words (see `messages`),
+/// writing moves with `fmt_move`.
+pub fn print_review<M>(reviews: &[MoveReview<M>], fmt_move: impl Fn(&M) -> String) {
+    println!("{}", messages::text(&Message::ReviewHeading));
+    let mut blunders = reviews.iter().filter(|r| r.is_blunder()).peekable();
+    if blunders.peek().is_none() {
+        println!("{}", messages::text(&Message::NoBlunders));
+    }
+    for review in blunders {
+        let line: Vec<String> = review.line.iter().map(&fmt_move).collect();
+        let blunder = Message::Blunder {
+            number: review.ply + 1,
+            played: &fmt_move(&review.played),
+            played_value: review.played_value,
+            best: &fmt_move(&review.best),
+            best_value: review.best_value,
+            line: &line.join(" "),
+        };
+        println!("{}", messages::text(&blunder));
+    }
+}
+
+/// The moves of the game played from `root`, written by `fmt_move` and
+/// separated by `separator`, each followed by its annotation from a
+/// `depth`-ply search.
+pub fn annotated_record<G>(
+    root: &G,
+    moves: &[G::Move],
+    depth: u32,
+    separator: &str,
+    fmt_move: impl Fn(&G::Move) -> String,
+) -> String
+where
+    G: GameState,
+    G::Move: PartialEq,
+{
+    let annotations = annotate_game(root, moves, depth);
+    let moves: Vec<String> = moves
+        .iter()
+        .zip(annotations)
+        .map(|(mv, annotation)| match annotation {
+            Some(annotation) => format!("{}{annotation}", fmt_move(mv)),
+            None => fmt_move(mv),
+        })
+        .collect();
+    moves.join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver_core::games::ttt::{TicTacToeMove, TicTacToeState};
+
+    fn moves(indices: &[u8]) -> Vec<TicTacToeMove> {
+        indices
+            .iter()
+            .map(|&index| TicTacToeMove { index })
+            .collect()
+    }
+
+    #[test]
+    fn ttt_edge_reply_to_a_corner_is_a_blunder() {
+        // X takes a corner; O's only safe reply is the center. O's edge
+        // reply loses.
+        let game = moves(&[0, 1, 4, 8]);
+        let reviews = review_game(&TicTacToeState::new(), &game, Player::Player2, 9);
+        assert_eq!(reviews.len(), 2);
+        let first = &reviews[0];
+        assert_eq!(first.ply, 1);
+        assert!(first.is_blunder());
+        assert_eq!(first.played_value, 1_000_000);
+        assert_eq!(first.best_value, 0);
+        assert_eq!(first.best.index, 4);
+        assert_eq!(first.line.first(), Some(&first.best));
+
+        // By O's second move the game is already lost: no further blunder.
+        assert!(!reviews[1].is_blunder());
+    }
+
+    #[test]
+    fn ttt_best_play_has_no_blunders() {
+        let game = moves(&[4, 0, 8, 2, 1, 7]);
+        let reviews = review_game(&TicTacToeState::new(), &game, Player::Player1, 9);
+        assert_eq!(reviews.len(), 3);
+        assert!(reviews.iter().all(|r| !r.is_blunder()));
+    }
+
+    #[test]
+    fn records_are_annotated() {
+        let game = moves(&[0, 1, 4]);
+        let record = annotated_record(&TicTacToeState::new(), &game, 9, " ", |mv| {
+            mv.index.to_string()
+        });
+        assert_eq!(record, "0 1?? 4");
+    }
+}
@@ -0,0 +1,426 @@
+//! Saved games of any registered game, to be resumed later.
+//!
+//! Typing `save` (or `save FILE`) instead of a move writes the game in
+//! progress to `NAME.save` next to the player's profile (see `profile`),
+//! or to FILE, and ends it; `tui` saves the same way and plays on.
+//! `solver_cli resume [FILE]` (or `tui --resume FILE`) picks it up again
+//! where it was left, with the same options, clocks and AI.
+//!
+//! While a game is played it is also autosaved every few moves (see
+//! `--autosave`) to `NAME.autosave`, which is removed when the game ends.
+//! If the program dies mid-game, the next game offers to recover it.
+//!
+//! The file holds `key = value` lines like a profile (`#` starts a
+//! comment). Files are written to a temporary file first and then renamed
+//! over the old one, so a crash never leaves half a file. Keys:
+//! - `version`: the format version, `SAVE_VERSION`. Files without one are
+//!   version 1; files from a newer version are refused.
+//! - `options`: the play options, as command-line arguments. Their
+//!   `--game` names the game; without it the game is Tic-Tac-Toe.
+//! - `human`: the side the human plays, `X` or `O`.
+//! - `depth`: the AI's search depth.
+//! - `level`: the level of an adaptive AI.
+//! - `moves`: the moves played so far in the game's notation, separated
+//!   by spaces. Version 1 only saved Tic-Tac-Toe, whose moves are cells.
+//! - `human.moves`, `human.seconds`: the human's moves and the seconds
+//!   spent on them so far.
+//! - `clock.X`, `clock.O`: seconds left and moves made, with a time
+//!   control.
+//! - `rng`: the state of the AI's random tie-breaking, with `--seed`.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use solver_core::clock::{Clock, GameClock};
+use solver_core::dyn_game::DynGameState;
+use solver_core::game::{GameState, Player};
+use solver_core::games::ttt::{TicTacToeMove, TicTacToeState};
+use solver_core::registry::{GameEntry, Registry};
+use solver_core::utils::write_atomic;
+
+use crate::messages::{self, Message};
+use crate::profile::{Profile, default_player_name};
+use crate::session::ask_yes_no_stdin;
+use crate::{PlayOptions, parse_play_options};
+
+pub const USAGE: &str = "usage: solver_cli resume [FILE]";
+
+/// The version of the saved-game format written by `SavedGame::to_text`.
+/// Bump it when the format changes in a way older readers would misread.
+pub const SAVE_VERSION: u32 = 2;
+
+/// A game in progress and everything needed to carry on with it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedGame {
+    pub opts: PlayOptions,
+    pub human_is_player1: bool,
+    pub depth: u32,
+    /// The adaptive AI's level, if the AI adapts.
+    pub level: Option<f64>,
+    /// The moves played, in order, in the game's notation.
+    pub moves: Vec<String>,
+    pub human_moves: u32,
+    pub human_seconds: f64,
+    /// Both clocks, under a time control (`opts.time`).
+    pub clock: Option<GameClock>,
+    /// The state of the AI's tie-breaking `SplitMix64`, with a seed.
+    pub rng: Option<u64>,
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number '{value}'"))
+}
+
+/// Parses `clock.X` / `clock.O` values: seconds left and moves made.
+fn parse_clock(value: &str, opts: &PlayOptions) -> Result<Clock, String> {
+    let control = opts.time.ok_or("clock saved without a time control")?;
+    let (seconds, moves) = value
+        .split_once(' ')
+        .ok_or_else(|| format!("expected seconds and moves, got '{value}'"))?;
+    let remaining = Duration::try_from_secs_f64(parse_number(seconds)?)
+        .map_err(|_| format!("invalid number of seconds '{seconds}'"))?;
+    Ok(Clock::restore(control, remaining, parse_number(moves)?))
+}
+
+impl SavedGame {
+    /// Whether the saved game is Tic-Tac-Toe, which `play` has its own
+    /// loop for.
+    pub fn is_ttt(&self) -> bool {
+        matches!(self.opts.game.as_deref(), None | Some("ttt"))
+    }
+
+    /// The saved game's entry in the registry.
+    pub fn entry(&self) -> Result<GameEntry, String> {
+        let name = self.opts.game.as_deref().unwrap_or("ttt");
+        Registry::builtin()
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("unknown game '{name}'"))
+    }
+
+    /// The Tic-Tac-Toe position after the saved moves.
+    pub fn state(&self) -> Result<TicTacToeState, String> {
+        let mut state = TicTacToeState::new();
+        for index in self.cells()? {
+            state = state
+                .try_apply_move(&TicTacToeMove { index })
+                .map_err(|e| format!("move {index} cannot be played: {e}"))?;
+        }
+        Ok(state)
+    }
+
+    /// The saved Tic-Tac-Toe moves as cell indices.
+    pub fn cells(&self) -> Result<Vec<u8>, String> {
+        self.moves.iter().map(|mv| parse_number(mv)).collect()
+    }
+
+    /// The position after the saved moves, of any game.
+    pub fn position(&self) -> Result<Box<dyn DynGameState>, String> {
+        let mut state = (self.entry()?.new_game)();
+        for mv in &self.moves {
+            state = state
+                .play(mv)
+                .map_err(|e| format!("move {mv} cannot be played: {e}"))?;
+        }
+        Ok(state)
+    }
+
+    /// Parses a saved game in the `key = value` format.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut opts = None;
+        let (mut human, mut depth) = (None, None);
+        let mut game = Self {
+            opts: PlayOptions::default(),
+            human_is_player1: true,
+            depth: 0,
+            level: None,
+            moves: Vec::new(),
+            human_moves: 0,
+            human_seconds: 0.0,
+            clock: None,
+            rng: None,
+        };
+        let mut clocks = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected 'key = value'", n + 1))?;
+            let value = value.trim();
+            let at_line = |e: String| format!("line {}: {e}", n + 1);
+            match key.trim() {
+                "version" => {
+                    let version: u32 = parse_number(value).map_err(at_line)?;
+                    if version > SAVE_VERSION {
+                        return Err(at_line(format!(
+                            "saved in format version {version}, but only versions up to {SAVE_VERSION} can be read"
+                        )));
+                    }
+                }
+                "options" => {
+                    let args: Vec<String> = value.split_whitespace().map(String::from).collect();
+                    opts = Some(parse_play_options(&args).map_err(at_line)?);
+                }
+                "human" => {
+                    human = Some(match value {
+                        "X" => true,
+                        "O" => false,
+                        other => return Err(at_line(format!("invalid side '{other}'"))),
+                    })
+                }
+                "depth" => depth = Some(parse_number(value).map_err(at_line)?),
+                "level" => game.level = Some(parse_number(value).map_err(at_line)?),
+                "moves" => game.moves = value.split_whitespace().map(String::from).collect(),
+                "rng" => game.rng = Some(parse_number(value).map_err(at_line)?),
+                "human.moves" => game.human_moves = parse_number(value).map_err(at_line)?,
+                "human.seconds" => game.human_seconds = parse_number(value).map_err(at_line)?,
+                key @ ("clock.X" | "clock.O") => clocks.push((key == "clock.X", value, n)),
+                other => return Err(at_line(format!("unknown key '{other}'"))),
+            }
+        }
+        game.opts = opts.unwrap_or_default();
+        game.human_is_player1 = human.ok_or("missing key 'human'")?;
+        game.depth = depth.ok_or("missing key 'depth'")?;
+        if let Some(control) = game.opts.time {
+            let mut clock = GameClock::new(control);
+            for (player1, value, n) in clocks {
+                let side = if player1 {
+                    Player::Player1
+                } else {
+                    Player::Player2
+                };
+                *clock.get_mut(side) =
+                    parse_clock(value, &game.opts).map_err(|e| format!("line {}: {e}", n + 1))?;
+            }
+            game.clock = Some(clock);
+        }
+        game.position()?;
+        if game.is_ttt() {
+            game.state()?;
+        }
+        Ok(game)
+    }
+
+    /// The saved game in the format read by `parse`.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "version = {SAVE_VERSION}\noptions = {}\nhuman = {}\ndepth = {}\n",
+            self.opts.to_args().join(" "),
+            if self.human_is_player1 { "X" } else { "O" },
+            self.depth
+        );
+        if let Some(level) = self.level {
+            text.push_str(&format!("level = {level}\n"));
+        }
+        text.push_str(&format!(
+            "moves = {}\nhuman.moves = {}\nhuman.seconds = {}\n",
+            self.moves.join(" "),
+            self.human_moves,
+            self.human_seconds
+        ));
+        if let Some(clock) = &self.clock {
+            for (side, clock) in [("X", &clock.player1), ("O", &clock.player2)] {
+                text.push_str(&format!(
+                    "clock.{side} = {} {}\n",
+                    clock.remaining().as_secs_f64(),
+                    clock.moves()
+                ));
+            }
+        }
+        if let Some(rng) = self.rng {
+            text.push_str(&format!("rng = {rng}\n"));
+        }
+        text
+    }
+
+    /// The default save file of player `name`, next to their profile.
+    pub fn path(name: &str) -> PathBuf {
+        Profile::path(name).with_extension("save")
+    }
+
+    /// The autosave file of player `name`, next to their profile.
+    pub fn autosave_path(name: &str) -> PathBuf {
+        Profile::path(name).with_extension("autosave")
+    }
+
+    /// Offers to recover the autosave of `opts`' player, left by a game
+    /// that never finished, and returns it if the human accepts. A
+    /// declined autosave is removed.
+    pub fn recover_autosave(opts: &PlayOptions) -> Option<Self> {
+        let path = Self::autosave_path(&opts.player.clone().unwrap_or_else(default_player_name));
+        if !path.exists() {
+            return None;
+        }
+        match Self::load(&path) {
+            Ok(game) if ask_yes_no_stdin(&messages::text(&Message::RecoverAutosave)) => Some(game),
+            Ok(_) => {
+                let _ = fs::remove_file(&path);
+                None
+            }
+            Err(e) => {
+                eprintln!("warning: {e}; ignoring the autosave");
+                None
+            }
+        }
+    }
+
+    /// Loads the saved game at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        Self::parse(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Writes the saved game to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        write_atomic(path, |out| out.write_all(self.to_text().as_bytes()))
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))
+    }
+}
+
+/// Runs the `resume` subcommand with the arguments after its name: loads
+/// the saved game and plays on from it.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let path = match args {
+        [] => SavedGame::path(&default_player_name()),
+        [path] => PathBuf::from(path),
+        [_, extra, ..] => return Err(format!("unexpected argument '{extra}'")),
+    };
+    messages::init_from_env()?;
+    let game = SavedGame::load(&path)?;
+    let path = path.display().to_string();
+    println!("{}", messages::text(&Message::Resuming(&path)));
+    crate::play_session(&game.opts.clone(), Some(game))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver_core::clock::TimeControl;
+
+    use crate::commentary::Persona;
+
+    fn sample() -> SavedGame {
+        let control = TimeControl::Increment {
+            base: Duration::from_secs(30),
+            increment: Duration::from_secs(2),
+        };
+        let mut clock = GameClock::new(control);
+        clock
+            .get_mut(Player::Player1)
+            .record_move(Duration::from_millis(4500))
+            .unwrap();
+        SavedGame {
+            opts: PlayOptions {
+                seed: Some(7),
+                time: Some(control),
+                commentary: Some(Persona::Rival),
+                player: Some("alice".to_string()),
+                adaptive: true,
+                ..PlayOptions::default()
+            },
+            human_is_player1: false,
+            depth: 5,
+            level: Some(0.25),
+            moves: vec!["4".to_string(), "0".to_string()],
+            human_moves: 1,
+            human_seconds: 3.5,
+            clock: Some(clock),
+            rng: Some(u64::MAX - 1),
+        }
+    }
+
+    #[test]
+    fn saved_games_round_trip() {
+        let game = sample();
+        let text = game.to_text();
+        assert!(text.contains("moves = 4 0\n"), "{text}");
+        assert!(text.contains("clock.X = 27.5 1\n"), "{text}");
+        assert!(text.contains("rng = 18446744073709551614\n"), "{text}");
+        assert_eq!(SavedGame::parse(&text), Ok(game.clone()));
+        assert_eq!(game.state().unwrap().ply(), Some(2));
+
+        let untimed = SavedGame {
+            opts: PlayOptions::default(),
+            level: None,
+            clock: None,
+            rng: None,
+            ..game
+        };
+        assert_eq!(SavedGame::parse(&untimed.to_text()), Ok(untimed));
+    }
+
+    #[test]
+    fn saves_any_registered_game() {
+        let game = SavedGame {
+            opts: PlayOptions {
+                game: Some("c4".to_string()),
+                ..PlayOptions::default()
+            },
+            human_is_player1: true,
+            depth: 6,
+            level: None,
+            moves: ["3", "3", "2"].map(String::from).to_vec(),
+            human_moves: 2,
+            human_seconds: 0.0,
+            clock: None,
+            rng: None,
+        };
+        let text = game.to_text();
+        assert!(text.contains("options = --game c4\n"), "{text}");
+        assert_eq!(SavedGame::parse(&text), Ok(game.clone()));
+        assert!(!game.is_ttt());
+        assert_eq!(game.position().unwrap().notation(), "332");
+        let full = text.replace("moves = 3 3 2", "moves = 3 3 3 3 3 3 3");
+        assert!(SavedGame::parse(&full).unwrap_err().contains("move 3"));
+        let unknown = text.replace("--game c4", "--game chess");
+        assert!(SavedGame::parse(&unknown).is_err());
+    }
+
+    #[test]
+    fn saves_replace_the_old_file_whole() {
+        let path = std::env::temp_dir().join(format!("saved-game-{}.save", std::process::id()));
+        let game = sample();
+        game.save(&path).unwrap();
+        let more = SavedGame {
+            moves: ["4", "0", "8"].map(String::from).to_vec(),
+            ..game
+        };
+        more.save(&path).unwrap();
+        assert_eq!(SavedGame::load(&path), Ok(more));
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        assert!(!Path::new(&tmp).exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_broken_saves() {
+        let text = sample().to_text();
+        for (from, to) in [
+            ("moves = 4 0", "moves = 4 4"),
+            ("human = O", "human = Z"),
+            ("depth = 5\n", ""),
+            ("clock.X = 27.5 1", "clock.X = soon"),
+            ("options = ", "options = --fast "),
+        ] {
+            let broken = text.replace(from, to);
+            assert!(SavedGame::parse(&broken).is_err(), "{broken}");
+        }
+        assert!(SavedGame::parse("colour = blue").is_err());
+        let newer = text.replace("version = 2", "version = 3");
+        assert!(SavedGame::parse(&newer).unwrap_err().contains("version 3"));
+        let unversioned = text.replace("version = 2\n", "");
+        assert_eq!(SavedGame::parse(&unversioned), Ok(sample()));
+        assert!(run(&["a".to_string(), "b".to_string()]).is_err());
+    }
+}
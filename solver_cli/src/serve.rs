@@ -0,0 +1,40 @@
+//! `serve`: runs the HTTP and WebSocket server.
+//!
+//! The server is its own binary, `solver_server`. This looks for it next
+//! to `solver_cli` (where `cargo build` and `cargo install` put both), then
+//! on `PATH`, and runs it with the arguments after `serve`.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+pub const USAGE: &str =
+    "usage: solver_cli serve [--addr HOST:PORT] [--ws-addr HOST:PORT] [--max-depth N]";
+
+/// The server binary's name, without the platform's executable suffix.
+const SERVER: &str = "solver_server";
+
+/// Where to run the server from: next to this binary if it is there,
+/// otherwise just its name, for the shell's search path.
+fn server_path() -> PathBuf {
+    let name = format!("{SERVER}{}", env::consts::EXE_SUFFIX);
+    env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join(&name)))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Runs the `serve` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let path = server_path();
+    let status = Command::new(&path)
+        .args(args)
+        .status()
+        .map_err(|e| format!("failed to start {}: {e}", path.display()))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{SERVER} stopped: {status}"))
+    }
+}
@@ -0,0 +1,179 @@
+//! A session of interactive games.
+//!
+//! After each game the player is offered a rematch. Colors alternate from
+//! game to game, and the session's results are kept across games. With
+//! `--adaptive` the AI is an `AdaptiveAgent`, starting at a level set by
+//! the player's past results and adapting to them from move to move and
+//! game to game.
+
+use std::io::{self, BufRead, Write};
+
+use solver_core::adaptive::AdaptiveAgent;
+use solver_core::game::Player;
+use solver_core::utils::opposite_player;
+
+use crate::messages::{self, Message};
+use crate::stats::{GameResult, ResultCounts, Stats};
+
+/// How a finished game went for the human.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameRecord {
+    pub result: GameResult,
+    /// Moves the human made.
+    pub moves: u32,
+    /// Seconds the human spent on them.
+    pub seconds: f64,
+}
+
+/// The state carried from one game of a session to the next.
+#[derive(Clone, Debug)]
+pub struct Session {
+    /// Whether the human moves first in the next game.
+    pub human_first: bool,
+    /// The AI's depth, unless it adapts.
+    depth: u32,
+    /// With adaptive difficulty, the AI.
+    agent: Option<AdaptiveAgent>,
+    /// The results of the session's games.
+    pub stats: Stats,
+}
+
+impl Session {
+    /// A session whose first game has the human moving first if
+    /// `human_first`, against a depth-`depth` AI, or against `agent` if
+    /// given.
+    pub fn new(human_first: bool, depth: u32, agent: Option<AdaptiveAgent>) -> Self {
+        Self {
+            human_first,
+            depth,
+            agent,
+            stats: Stats::default(),
+        }
+    }
+
+    /// The depth the AI searches at now.
+    pub fn depth(&self) -> u32 {
+        self.agent.as_ref().map_or(self.depth, AdaptiveAgent::depth)
+    }
+
+    /// The adaptive AI, if the session has one.
+    pub fn agent_mut(&mut self) -> Option<&mut AdaptiveAgent> {
+        self.agent.as_mut()
+    }
+
+    /// Records a finished game of `game`, then swaps colors and lets an
+    /// adaptive AI adjust to the result.
+    pub fn record(&mut self, game: &str, record: &GameRecord) {
+        self.stats.record(
+            game,
+            self.depth(),
+            record.result,
+            record.moves,
+            record.seconds,
+        );
+        let human = if self.human_first {
+            Player::Player1
+        } else {
+            Player::Player2
+        };
+        let ai = opposite_player(human);
+        if let Some(agent) = &mut self.agent {
+            agent.record_game(
+                ai,
+                match record.result {
+                    GameResult::Win => Some(human),
+                    GameResult::Draw => None,
+                    GameResult::Loss => Some(ai),
+                },
+            );
+        }
+        self.human_first = !self.human_first;
+    }
+
+    /// A one-line summary of the session so far, in the locale's words.
+    pub fn summary(&self) -> String {
+        let total = |f: fn(&ResultCounts) -> u32| -> u32 { self.stats.results.iter().map(f).sum() };
+        messages::text(&Message::Session(
+            total(|c| c.wins),
+            total(|c| c.draws),
+            total(|c| c.losses),
+            self.stats.games(),
+        ))
+    }
+}
+
+/// Asks `question`; anything but `y` or `yes`, or their equivalents in the
+/// locale (see `messages`), means no, as does the end of the input.
+pub fn ask_yes_no(question: &str, input: &mut impl BufRead, output: &mut impl Write) -> bool {
+    let (hint, yes) = messages::catalog().yes_no();
+    write!(output, "{question} {hint} ").ok();
+    output.flush().ok();
+    let mut answer = String::new();
+    match input.read_line(&mut answer) {
+        Ok(0) | Err(_) => {
+            // End the prompt's line, as typing an answer would have.
+            writeln!(output).ok();
+            false
+        }
+        Ok(_) => yes.contains(&answer.trim().to_lowercase().as_str()),
+    }
+}
+
+/// `ask_yes_no` on the terminal.
+pub fn ask_yes_no_stdin(question: &str) -> bool {
+    ask_yes_no(question, &mut io::stdin().lock(), &mut io::stdout())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(result: GameResult) -> GameRecord {
+        GameRecord {
+            result,
+            moves: 4,
+            seconds: 2.0,
+        }
+    }
+
+    #[test]
+    fn colors_alternate_and_results_add_up() {
+        let mut session = Session::new(true, 9, None);
+        session.record("ttt", &game(GameResult::Loss));
+        assert!(!session.human_first);
+        session.record("ttt", &game(GameResult::Draw));
+        assert!(session.human_first);
+        assert_eq!(session.depth(), 9);
+        assert_eq!(
+            session.summary(),
+            "Session: 0 won, 1 drawn, 1 lost over 2 game(s)"
+        );
+        assert_eq!(session.stats.moves, 8);
+    }
+
+    #[test]
+    fn an_adaptive_ai_follows_the_results() {
+        let agent = AdaptiveAgent::new(9, 0).with_level(0.0);
+        let mut session = Session::new(false, 9, Some(agent));
+        assert_eq!(session.depth(), 1);
+        session.record("ttt", &game(GameResult::Win));
+        session.record("ttt", &game(GameResult::Win));
+        let level = session.agent_mut().unwrap().level();
+        assert!(level > 0.0);
+        session.record("ttt", &game(GameResult::Draw));
+        assert_eq!(session.agent_mut().unwrap().level(), level);
+        session.record("ttt", &game(GameResult::Loss));
+        assert!(session.agent_mut().unwrap().level() < level);
+        assert_eq!(session.stats.games(), 4);
+    }
+
+    #[test]
+    fn only_yes_means_yes() {
+        let ask = |text: &str| ask_yes_no("Again?", &mut text.as_bytes(), &mut Vec::new());
+        assert!(ask("y\n"));
+        assert!(ask(" YES \n"));
+        assert!(!ask("n\n"));
+        assert!(!ask("\n"));
+        assert!(!ask(""));
+    }
+}
@@ -0,0 +1,426 @@
+//! `solve` (or `solve-exact`): the exact result of one position with best play.
+//!
+//! Unlike the depth-limited searches elsewhere, this searches until the
+//! result is proven (see `solver_core::solvers::exact::solve_exact`), so
+//! it reports ground truth together with how many plies the game lasts.
+//! Early Connect Four positions can take a very long time; each finished
+//! depth is reported as it completes.
+//!
+//! Connect Four positions in the default exact opening book are answered
+//! from the book at once, without a distance, unless `--no-book` is given.
+//!
+//! With `--checkpoint FILE`, the progress is saved to `FILE` after each
+//! root move as `key = value` lines: `game`, `position`, `depth` (the
+//! depth being searched), `searched` (the root moves it has finished),
+//! `best` (the best of them and its value), `nodes` and `seconds`.
+//! `--resume` carries on from there instead of starting over, and the
+//! checkpoint is removed once the position is solved.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use solver_core::book::OpeningBook;
+use solver_core::game::{GameState, Outcome, PositionKey};
+use solver_core::games::c4_bitboard::BitboardState;
+use solver_core::games::ttt::TicTacToeState;
+use solver_core::solvers::exact::{SolveProgress, solve_exact_from};
+use solver_core::solvers::tt::{ReplacementPolicy, TranspositionTable};
+use solver_core::utils::write_atomic;
+
+use crate::args;
+
+pub const USAGE: &str = "usage: solver_cli solve --position POS [--game c4|ttt] [--tt MB] [--no-book] [--checkpoint FILE [--resume]]";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    ttt: bool,
+    position: String,
+    tt_mb: usize,
+    use_book: bool,
+    checkpoint: Option<String>,
+    resume: bool,
+}
+
+/// How far an unfinished solve got, as saved in the checkpoint file.
+#[derive(Clone, Debug, PartialEq)]
+struct Progress {
+    game: String,
+    position: String,
+    /// The depth being searched; no shallower one settles the result.
+    depth: u32,
+    /// The root moves finished at `depth`.
+    searched: Vec<String>,
+    /// The best of them and its value.
+    best: Option<(String, i32)>,
+    nodes: u64,
+    seconds: f64,
+}
+
+impl Progress {
+    fn to_text(&self) -> String {
+        let mut text = format!(
+            "game = {}\nposition = {}\ndepth = {}\nsearched = {}\n",
+            self.game,
+            self.position,
+            self.depth,
+            self.searched.join(" ")
+        );
+        if let Some((mv, value)) = &self.best {
+            text += &format!("best = {mv} {value}\n");
+        }
+        text + &format!("nodes = {}\nseconds = {:.1}\n", self.nodes, self.seconds)
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let (mut game, mut position, mut depth, mut nodes, mut seconds) =
+            (None, None, None, None, None);
+        let (mut searched, mut best) = (Vec::new(), None);
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected 'key = value'", n + 1))?;
+            let value = value.trim();
+            let invalid = || format!("line {}: invalid value '{value}'", n + 1);
+            match key.trim() {
+                "game" => game = Some(value.to_string()),
+                "position" => position = Some(value.to_string()),
+                "depth" => depth = Some(value.parse().map_err(|_| invalid())?),
+                "searched" => searched = value.split_whitespace().map(String::from).collect(),
+                "best" => {
+                    let (mv, value) = value.split_once(' ').ok_or_else(invalid)?;
+                    let value = value.trim().parse().map_err(|_| invalid())?;
+                    best = Some((mv.to_string(), value));
+                }
+                "nodes" => nodes = Some(value.parse().map_err(|_| invalid())?),
+                "seconds" => seconds = Some(value.parse().map_err(|_| invalid())?),
+                other => return Err(format!("line {}: unknown key '{other}'", n + 1)),
+            }
+        }
+        Ok(Self {
+            game: game.ok_or("missing key 'game'")?,
+            // An empty position is the initial one.
+            position: position.unwrap_or_default(),
+            depth: depth.ok_or("missing key 'depth'")?,
+            searched,
+            best,
+            nodes: nodes.unwrap_or(0),
+            seconds: seconds.unwrap_or(0.0),
+        })
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        ttt: false,
+        position: String::new(),
+        tt_mb: 64,
+        use_book: true,
+        checkpoint: None,
+        resume: false,
+    };
+    let mut position = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| args::value(name, iter.next());
+        match arg.as_str() {
+            "--game" => {
+                opts.ttt = match value("--game")?.as_str() {
+                    "c4" => false,
+                    "ttt" => true,
+                    other => return Err(format!("unknown game '{other}'")),
+                }
+            }
+            "--position" => position = Some(value("--position")?),
+            "--tt" => {
+                let mb = value("--tt")?;
+                opts.tt_mb = mb
+                    .parse()
+                    .ok()
+                    .filter(|&mb| mb > 0)
+                    .ok_or_else(|| format!("invalid table size '{mb}'"))?;
+            }
+            "--no-book" => opts.use_book = false,
+            "--checkpoint" => opts.checkpoint = Some(value("--checkpoint")?),
+            "--resume" => opts.resume = true,
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    opts.position = position.ok_or("missing --position")?;
+    if opts.resume && opts.checkpoint.is_none() {
+        return Err("--resume needs --checkpoint".to_string());
+    }
+    Ok(opts)
+}
+
+/// One line stating the result, e.g. "Player1 wins in 17 plies with best
+/// move 3". `plies` is `None` when only the result is known.
+fn describe(outcome: Outcome, plies: Option<u32>, best_move: Option<String>) -> String {
+    let Some(mv) = best_move else {
+        return match outcome {
+            Outcome::Win(p) => format!("The game is over: {p:?} has won"),
+            Outcome::Draw => "The game is over: it is a draw".to_string(),
+        };
+    };
+    let plies = plies.map(|n| format!("{n} {}", if n == 1 { "ply" } else { "plies" }));
+    match (outcome, plies) {
+        (Outcome::Win(p), Some(plies)) => format!("{p:?} wins in {plies} with best move {mv}"),
+        (Outcome::Win(p), None) => format!("{p:?} wins with best move {mv}"),
+        (Outcome::Draw, Some(plies)) => format!("Draw after {plies} with best move {mv}"),
+        (Outcome::Draw, None) => format!("Draw with best move {mv}"),
+    }
+}
+
+/// Solves `state`, reporting its progress on one updating line and saving
+/// it to the checkpoint, if there is one.
+fn solve<G>(state: &G, opts: &Options, fmt_move: impl Fn(&G::Move) -> String) -> Result<(), String>
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    let checkpoint = opts.checkpoint.as_deref().map(Path::new);
+    let mut progress = Progress {
+        game: if opts.ttt { "ttt" } else { "c4" }.to_string(),
+        position: opts.position.clone(),
+        depth: 1,
+        searched: Vec::new(),
+        best: None,
+        nodes: 0,
+        seconds: 0.0,
+    };
+    if let Some(checkpoint) = checkpoint
+        && opts.resume
+        && checkpoint.exists()
+    {
+        let text = fs::read_to_string(checkpoint)
+            .map_err(|e| format!("failed to read {}: {e}", checkpoint.display()))?;
+        let saved = Progress::parse(&text).map_err(|e| format!("{}: {e}", checkpoint.display()))?;
+        if (&saved.game, &saved.position) != (&progress.game, &progress.position) {
+            return Err(format!(
+                "{} is for {} position '{}'",
+                checkpoint.display(),
+                saved.game,
+                saved.position
+            ));
+        }
+        println!(
+            "Resuming depth {} after {} moves",
+            saved.depth,
+            saved.searched.len()
+        );
+        progress = saved;
+    }
+
+    // Saved moves are matched against the legal ones by their text.
+    let moves = state.legal_moves();
+    let find = |text: &str| {
+        moves
+            .iter()
+            .find(|mv| fmt_move(mv) == text)
+            .cloned()
+            .ok_or_else(|| format!("the checkpoint has an illegal move '{text}'"))
+    };
+    let from = SolveProgress {
+        depth: progress.depth,
+        searched: progress
+            .searched
+            .iter()
+            .map(|mv| find(mv))
+            .collect::<Result<_, _>>()?,
+        best: match &progress.best {
+            Some((mv, value)) => Some((find(mv)?, *value)),
+            None => None,
+        },
+    };
+
+    let mut tt = TranspositionTable::with_megabytes(opts.tt_mb, ReplacementPolicy::DepthPreferred);
+    let start = Instant::now();
+    let (earlier_nodes, earlier_seconds) = (progress.nodes, progress.seconds);
+    let mut warned = false;
+    let solution = solve_exact_from(state, &mut tt, None, from, |now, stats| {
+        progress.depth = now.depth;
+        progress.searched = now.searched.iter().map(&fmt_move).collect();
+        progress.best = now.best.as_ref().map(|(mv, value)| (fmt_move(mv), *value));
+        progress.nodes = earlier_nodes + stats.nodes;
+        progress.seconds = earlier_seconds + start.elapsed().as_secs_f64();
+        print!(
+            "\rdepth {}: {}/{} moves ({} nodes, {:.1}s)",
+            now.depth,
+            now.searched.len(),
+            moves.len(),
+            progress.nodes,
+            progress.seconds
+        );
+        let _ = io::stdout().flush();
+        if let Some(checkpoint) = checkpoint
+            && let Err(e) = write_atomic(checkpoint, |out| {
+                out.write_all(progress.to_text().as_bytes())
+            })
+            && !warned
+        {
+            eprintln!("\nwarning: failed to save {}: {e}", checkpoint.display());
+            warned = true;
+        }
+    });
+    let solution = solution.expect("the search is never cancelled");
+    if solution.plies > 1 {
+        println!();
+    }
+    if let Some(checkpoint) = checkpoint
+        && checkpoint.exists()
+    {
+        fs::remove_file(checkpoint)
+            .map_err(|e| format!("failed to remove {}: {e}", checkpoint.display()))?;
+    }
+    let best_move = solution.best_move.as_ref().map(&fmt_move);
+    println!(
+        "{}",
+        describe(solution.outcome, Some(solution.plies), best_move)
+    );
+    println!(
+        "{} nodes in {:.1}s",
+        earlier_nodes + solution.nodes,
+        earlier_seconds + start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Runs the `solve` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let opts = parse_options(args)?;
+    if opts.ttt {
+        let state = TicTacToeState::from_board_str(&opts.position).map_err(|e| e.to_string())?;
+        return solve(&state, &opts, |m| m.index.to_string());
+    }
+    let state = BitboardState::from_move_sequence(&opts.position).map_err(|e| e.to_string())?;
+    let book = if opts.use_book {
+        OpeningBook::load_default()?
+    } else {
+        None
+    };
+    if let Some(book) = book
+        && let Some(outcome) = book.lookup_exact(&state)
+    {
+        let entry = book.lookup(&state).expect("exact hits are book entries");
+        // The book knows the result but not how long the game lasts.
+        let best_move = Some(entry.best_move.to_string());
+        println!("{}", describe(outcome, None, best_move));
+        println!("From the exact opening book; use --no-book for the distance");
+        return Ok(());
+    }
+    solve(&state, &opts, |c| c.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::split_args;
+    use solver_core::game::Player;
+
+    #[test]
+    fn parses_options() {
+        let opts = parse_options(&split_args(
+            "--position 3344 --tt 16 --no-book --checkpoint long.ckpt --resume",
+        ))
+        .unwrap();
+        assert_eq!(
+            opts,
+            Options {
+                ttt: false,
+                position: "3344".to_string(),
+                tt_mb: 16,
+                use_book: false,
+                checkpoint: Some("long.ckpt".to_string()),
+                resume: true,
+            }
+        );
+        assert!(
+            parse_options(&split_args("--game ttt --position X........"))
+                .unwrap()
+                .ttt
+        );
+        assert!(parse_options(&split_args("--game c4")).is_err());
+        assert!(parse_options(&split_args("--position 3 --tt 0")).is_err());
+        assert!(parse_options(&split_args("--position 3 --game go")).is_err());
+        assert!(parse_options(&split_args("--position 3 --resume")).is_err());
+    }
+
+    #[test]
+    fn checkpoints_round_trip() {
+        let progress = Progress {
+            game: "c4".to_string(),
+            position: "3344".to_string(),
+            depth: 12,
+            searched: vec!["3".to_string(), "2".to_string()],
+            best: Some(("2".to_string(), -7)),
+            nodes: 123_456,
+            seconds: 81.5,
+        };
+        assert_eq!(Progress::parse(&progress.to_text()), Ok(progress));
+        assert!(Progress::parse("game = c4").is_err());
+        assert!(Progress::parse("game = c4\ndepth = deep").is_err());
+        assert!(Progress::parse("game = c4\ndepth = 3\nbest = 3").is_err());
+    }
+
+    #[test]
+    fn resumed_solves_finish_and_remove_the_checkpoint() {
+        let path = std::env::temp_dir().join(format!("solve-{}.checkpoint", std::process::id()));
+        let progress = Progress {
+            game: "ttt".to_string(),
+            position: "XO..X...O".to_string(),
+            depth: 3,
+            searched: vec!["2".to_string()],
+            best: Some(("2".to_string(), 0)),
+            nodes: 10,
+            seconds: 1.0,
+        };
+        fs::write(&path, progress.to_text()).unwrap();
+        let checkpoint = path.to_str().unwrap();
+        let resume = |position: &str| {
+            run(&split_args(&format!(
+                "--game ttt --position {position} --checkpoint {checkpoint} --resume"
+            )))
+        };
+        // The checkpoint is for another position.
+        assert!(resume("XO..X..O.").is_err());
+        assert!(path.exists());
+        // Cell 1 is taken, so it can't have been searched.
+        let illegal = Progress {
+            searched: vec!["1".to_string()],
+            ..progress.clone()
+        };
+        fs::write(&path, illegal.to_text()).unwrap();
+        assert!(resume("XO..X...O").is_err());
+        fs::write(&path, progress.to_text()).unwrap();
+        assert_eq!(resume("XO..X...O"), Ok(()));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn describes_results() {
+        let win = Outcome::Win(Player::Player1);
+        let mv = || Some("3".to_string());
+        assert_eq!(
+            describe(win, Some(17), mv()),
+            "Player1 wins in 17 plies with best move 3"
+        );
+        assert_eq!(describe(win, None, mv()), "Player1 wins with best move 3");
+        assert_eq!(
+            describe(win, Some(1), mv()),
+            "Player1 wins in 1 ply with best move 3"
+        );
+        assert_eq!(
+            describe(Outcome::Draw, Some(9), mv()),
+            "Draw after 9 plies with best move 3"
+        );
+        assert_eq!(
+            describe(Outcome::Draw, Some(0), None),
+            "The game is over: it is a draw"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! `stats`: the human player's game statistics.
+//!
+//! Every finished interactive game is recorded in the player's profile
+//! (see `profile`): the result per game and AI depth, win and loss streaks,
+//! and the time the player spent on their moves.
+
+use solver_core::game::Player;
+
+use crate::profile::Profile;
+
+pub const USAGE: &str = "usage: solver_cli stats [--player NAME]";
+
+/// The result of a game for the human.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl GameResult {
+    /// The result for `human` of a game won by `winner` (`None` = draw).
+    pub fn for_player(human: Player, winner: Option<Player>) -> Self {
+        match winner {
+            Some(p) if p == human => GameResult::Win,
+            Some(_) => GameResult::Loss,
+            None => GameResult::Draw,
+        }
+    }
+}
+
+/// Results against one AI depth in one game.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResultCounts {
+    /// Game name, as in `--game` options (`ttt`, `c4`).
+    pub game: String,
+    /// The AI's search depth.
+    pub depth: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl ResultCounts {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+/// Totals over all recorded games.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats {
+    /// Results by game and depth, sorted.
+    pub results: Vec<ResultCounts>,
+    /// The current streak: positive for wins in a row, negative for losses
+    /// in a row, 0 after a draw.
+    pub streak: i32,
+    /// The longest winning streak.
+    pub best_streak: u32,
+    /// Moves the player made.
+    pub moves: u32,
+    /// Seconds the player spent on those moves.
+    pub seconds: f64,
+}
+
+impl Stats {
+    /// The counts for `game` at `depth`, created if missing.
+    pub fn counts_mut(&mut self, game: &str, depth: u32) -> &mut ResultCounts {
+        let index = match self
+            .results
+            .binary_search_by(|c| (c.game.as_str(), c.depth).cmp(&(game, depth)))
+        {
+            Ok(index) => index,
+            Err(index) => {
+                let counts = ResultCounts {
+                    game: game.to_string(),
+                    depth,
+                    ..ResultCounts::default()
+                };
+                self.results.insert(index, counts);
+                index
+            }
+        };
+        &mut self.results[index]
+    }
+
+    /// Records a finished game of `game` against a depth-`depth` AI, in
+    /// which the player made `moves` moves in `seconds`.
+    pub fn record(&mut self, game: &str, depth: u32, result: GameResult, moves: u32, seconds: f64) {
+        let counts = self.counts_mut(game, depth);
+        match result {
+            GameResult::Win => counts.wins += 1,
+            GameResult::Draw => counts.draws += 1,
+            GameResult::Loss => counts.losses += 1,
+        }
+        self.streak = match result {
+            GameResult::Win => self.streak.max(0) + 1,
+            GameResult::Draw => 0,
+            GameResult::Loss => self.streak.min(0) - 1,
+        };
+        self.best_streak = self.best_streak.max(self.streak.max(0) as u32);
+        self.moves += moves;
+        self.seconds += seconds;
+    }
+
+    /// Games played in total.
+    pub fn games(&self) -> u32 {
+        self.results.iter().map(ResultCounts::games).sum()
+    }
+}
+
+/// Prints `profile`'s statistics and strength estimate.
+fn print_stats(name: &str, profile: &Profile) {
+    let stats = &profile.stats;
+    println!("Statistics for {name}: {} games", stats.games());
+    if stats.games() == 0 {
+        return;
+    }
+    println!("  game  depth  games   wins  draws  losses");
+    for counts in &stats.results {
+        println!(
+            "  {:<4}  {:>5}  {:>5}  {:>5}  {:>5}  {:>6}",
+            counts.game,
+            counts.depth,
+            counts.games(),
+            counts.wins,
+            counts.draws,
+            counts.losses
+        );
+    }
+    let streak = match stats.streak {
+        0 => "none".to_string(),
+        n if n > 0 => format!("{n} won"),
+        n => format!("{} lost", -n),
+    };
+    println!(
+        "  Current streak: {streak}; longest winning streak: {}",
+        stats.best_streak
+    );
+    if stats.moves > 0 {
+        println!(
+            "  Time: {:.0}s over {} moves ({:.1}s per move)",
+            stats.seconds,
+            stats.moves,
+            stats.seconds / f64::from(stats.moves)
+        );
+    }
+    if let Some(estimate) = profile.strength.estimate() {
+        println!(
+            "  Estimated strength: {:.0} (plays like a depth-{:.1} search)",
+            estimate.rating, estimate.equivalent_depth
+        );
+    }
+}
+
+/// Runs the `stats` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut name = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--player" => {
+                let value = iter.next().ok_or("--player requires a value")?;
+                name = Some(crate::profile::parse_player_name(value)?);
+            }
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    let name = name.unwrap_or_else(crate::profile::default_player_name);
+    let profile = Profile::load(Profile::path(&name))?;
+    print_stats(&name, &profile);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_and_streaks_are_tracked() {
+        let mut stats = Stats::default();
+        stats.record("ttt", 9, GameResult::Win, 5, 10.0);
+        stats.record("ttt", 9, GameResult::Win, 4, 6.0);
+        stats.record("c4", 8, GameResult::Loss, 12, 30.0);
+        assert_eq!(stats.streak, -1);
+        assert_eq!(stats.best_streak, 2);
+        stats.record("ttt", 3, GameResult::Draw, 4, 2.0);
+        assert_eq!(stats.streak, 0);
+
+        assert_eq!(stats.games(), 4);
+        let keys: Vec<(&str, u32)> = stats
+            .results
+            .iter()
+            .map(|c| (c.game.as_str(), c.depth))
+            .collect();
+        assert_eq!(keys, [("c4", 8), ("ttt", 3), ("ttt", 9)]);
+        assert_eq!(stats.counts_mut("ttt", 9).wins, 2);
+        assert_eq!((stats.moves, stats.seconds), (25, 48.0));
+
+        assert_eq!(
+            GameResult::for_player(Player::Player2, Some(Player::Player1)),
+            GameResult::Loss
+        );
+        assert_eq!(
+            GameResult::for_player(Player::Player2, None),
+            GameResult::Draw
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Playing-strength estimate for the human, from post-game reviews.
+//!
+//! Every reviewed move adds its value loss (capped at `LOSS_CAP`, so a
+//! single lost game does not swamp the average) and whether it was a
+//! blunder. The same positions are also played by reference searches of
+//! depth 1 to `REFERENCE_DEPTHS`, counting their blunders. The human's
+//! blunder rate is then placed on that ladder: someone who blunders less
+//! than a depth-2 search but more than a depth-3 one "plays like depth 2.x".
+//!
+//! The rating is `RATING_BASE + RATING_PER_PLY` times that depth. It is an
+//! arbitrary progression metric, not an Elo rating.
+
+use solver_core::game::{GameState, Player};
+use solver_core::solvers::minimax::{minimax_best_move_ab_depth, minimax_value_ab_depth_root};
+
+use crate::review::{BLUNDER_THRESHOLD, MoveReview};
+
+/// Largest value loss counted for a single move.
+pub const LOSS_CAP: i32 = 1000;
+/// Reference searches run at depths 1 to this.
+pub const REFERENCE_DEPTHS: u32 = 6;
+/// Rating of a player who blunders every move.
+pub const RATING_BASE: f64 = 800.0;
+/// Rating points per ply of equivalent search depth.
+pub const RATING_PER_PLY: f64 = 200.0;
+
+/// Review totals over all recorded games.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct StrengthRecord {
+    /// Games reviewed.
+    pub games: u32,
+    /// Human moves reviewed.
+    pub moves: u32,
+    /// Sum of the capped value losses.
+    pub loss: u64,
+    /// Human blunders.
+    pub blunders: u32,
+    /// Blunders the depth-`d` reference search would have made in the same
+    /// positions, at index `d - 1`.
+    pub reference_blunders: Vec<u32>,
+}
+
+/// A summary of a `StrengthRecord`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrengthEstimate {
+    /// Average capped value loss per move.
+    pub average_loss: f64,
+    /// Fraction of moves that were blunders.
+    pub blunder_rate: f64,
+    /// The reference search depth the human plays like, from 0 (blunders
+    /// every move) to `REFERENCE_DEPTHS`.
+    pub equivalent_depth: f64,
+    /// `RATING_BASE + RATING_PER_PLY * equivalent_depth`.
+    pub rating: f64,
+}
+
+impl StrengthRecord {
+    /// Adds one reviewed game: `reviews` as returned by `review_game` for
+    /// `human`'s moves in `moves` played from `root`, searched to `depth`.
+    pub fn add_game<G: GameState>(
+        &mut self,
+        root: &G,
+        moves: &[G::Move],
+        human: Player,
+        reviews: &[MoveReview<G::Move>],
+        depth: u32,
+    ) {
+        let sign = if human == Player::Player1 { 1 } else { -1 };
+        self.reference_blunders.resize(REFERENCE_DEPTHS as usize, 0);
+        self.games += 1;
+        let mut state = root.clone();
+        let mut reviews = reviews.iter().peekable();
+        for (ply, mv) in moves.iter().enumerate() {
+            if let Some(review) = reviews.next_if(|r| r.ply == ply) {
+                self.moves += 1;
+                self.loss += review.loss.min(LOSS_CAP) as u64;
+                self.blunders += u32::from(review.is_blunder());
+                for (reference, count) in (1..).zip(&mut self.reference_blunders) {
+                    let Some((choice, _)) = minimax_best_move_ab_depth(&state, reference) else {
+                        continue;
+                    };
+                    let value = minimax_value_ab_depth_root(
+                        &state.apply_move(&choice),
+                        depth.saturating_sub(1),
+                    );
+                    let loss = (review.best_value - value).saturating_mul(sign);
+                    *count += u32::from(loss > BLUNDER_THRESHOLD);
+                }
+            }
+            state = state.apply_move(mv);
+        }
+    }
+
+    /// The strength estimate, or `None` before any move was reviewed.
+    pub fn estimate(&self) -> Option<StrengthEstimate> {
+        if self.moves == 0 {
+            return None;
+        }
+        let moves = f64::from(self.moves);
+        let blunder_rate = f64::from(self.blunders) / moves;
+        // Rates of the ladder, from a notional depth 0 that always blunders.
+        let rates: Vec<f64> = std::iter::once(1.0)
+            .chain(
+                self.reference_blunders
+                    .iter()
+                    .map(|&b| f64::from(b) / moves),
+            )
+            .collect();
+        let mut equivalent_depth = (rates.len() - 1) as f64;
+        for (depth, pair) in rates.windows(2).enumerate() {
+            let (weaker, stronger) = (pair[0], pair[1]);
+            if blunder_rate >= stronger {
+                let fraction = if weaker > stronger {
+                    ((weaker - blunder_rate) / (weaker - stronger)).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                equivalent_depth = depth as f64 + fraction;
+                break;
+            }
+        }
+        Some(StrengthEstimate {
+            average_loss: self.loss as f64 / moves,
+            blunder_rate,
+            equivalent_depth,
+            rating: RATING_BASE + RATING_PER_PLY * equivalent_depth,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::review_game;
+    use solver_core::games::ttt::{TicTacToeMove, TicTacToeState};
+
+    fn moves(indices: &[u8]) -> Vec<TicTacToeMove> {
+        indices
+            .iter()
+            .map(|&index| TicTacToeMove { index })
+            .collect()
+    }
+
+    #[test]
+    fn ttt_blunders_lower_the_estimate() {
+        let root = TicTacToeState::new();
+        let mut careless = StrengthRecord::default();
+        let game = moves(&[0, 1, 4, 8]);
+        let reviews = review_game(&root, &game, Player::Player2, 9);
+        careless.add_game(&root, &game, Player::Player2, &reviews, 9);
+        assert_eq!(
+            (careless.games, careless.moves, careless.blunders),
+            (1, 2, 1)
+        );
+        assert_eq!(careless.loss, LOSS_CAP as u64);
+        assert_eq!(careless.reference_blunders.len(), REFERENCE_DEPTHS as usize);
+
+        let mut careful = StrengthRecord::default();
+        let game = moves(&[4, 0, 8, 2, 1, 7]);
+        let reviews = review_game(&root, &game, Player::Player1, 9);
+        careful.add_game(&root, &game, Player::Player1, &reviews, 9);
+        let careful = careful.estimate().unwrap();
+        let careless = careless.estimate().unwrap();
+        assert_eq!(careful.blunder_rate, 0.0);
+        assert!(careless.equivalent_depth < careful.equivalent_depth);
+        assert!(careless.rating < careful.rating);
+        assert_eq!(careless.blunder_rate, 0.5);
+
+        assert_eq!(StrengthRecord::default().estimate(), None);
+    }
+
+    #[test]
+    fn equivalent_depth_interpolates_between_references() {
+        let record = StrengthRecord {
+            games: 1,
+            moves: 10,
+            loss: 0,
+            blunders: 3,
+            reference_blunders: vec![6, 2, 0],
+        };
+        // 1.0 (depth 0) > 0.6 (depth 1) > 0.3 > 0.2 (depth 2).
+        let estimate = record.estimate().unwrap();
+        assert!((estimate.equivalent_depth - 1.75).abs() < 1e-9);
+        assert!((estimate.rating - (RATING_BASE + 1.75 * RATING_PER_PLY)).abs() < 1e-9);
+    }
+}
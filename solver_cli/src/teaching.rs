@@ -0,0 +1,143 @@
+//! Teaching mode for interactive games.
+//!
+//! Before a human move is played, a quick search checks it. If the move
+//! hands the opponent an immediate win, or (when strict) loses by force
+//! although the position did not, the player is warned and may take the
+//! move back.
+
+use solver_core::game::{GameState, Player};
+use solver_core::solvers::minimax::{WIN_SCORE, minimax_value_ab_depth_root};
+
+use crate::messages::{self, Message};
+
+/// Plies the forced-loss check searches after the move.
+pub const CHECK_DEPTH: u32 = 6;
+
+/// Which moves teaching mode warns about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strictness {
+    /// Moves after which the opponent can win at once.
+    Immediate,
+    /// Those, and moves that lose by force within `CHECK_DEPTH` plies of a
+    /// position that didn't.
+    Forced,
+}
+
+impl Strictness {
+    /// Parses a `--teach` value: `immediate` or `forced`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "immediate" => Ok(Strictness::Immediate),
+            "forced" => Ok(Strictness::Forced),
+            other => Err(format!(
+                "invalid teaching strictness '{other}' (use immediate or forced)"
+            )),
+        }
+    }
+
+    /// The value `parse` accepts.
+    pub fn name(self) -> &'static str {
+        match self {
+            Strictness::Immediate => "immediate",
+            Strictness::Forced => "forced",
+        }
+    }
+}
+
+/// What is wrong with a move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning<M> {
+    /// The opponent wins at once with `reply`.
+    AllowsWin { reply: M },
+    /// The opponent can force a win.
+    LosesByForce,
+}
+
+impl<M> Warning<M> {
+    /// The warning in the locale's words (see `messages`), with moves
+    /// written by `format_move`.
+    pub fn message(&self, format_move: impl Fn(&M) -> String) -> String {
+        match self {
+            Warning::AllowsWin { reply } => {
+                messages::text(&Message::AllowsWin(&format_move(reply)))
+            }
+            Warning::LosesByForce => messages::text(&Message::LosesByForce),
+        }
+    }
+}
+
+/// Checks `mv` for the side to move in `state`, returning a warning if
+/// `strictness` calls for one.
+pub fn check_move<G: GameState>(
+    state: &G,
+    mv: &G::Move,
+    strictness: Strictness,
+) -> Option<Warning<G::Move>> {
+    let mover = state.current_player();
+    let after = state.apply_move(mv);
+    if after.is_terminal() {
+        return None;
+    }
+    let lost = |value: i32| match mover {
+        Player::Player1 => value <= -WIN_SCORE,
+        Player::Player2 => value >= WIN_SCORE,
+    };
+    for reply in after.legal_moves() {
+        if after
+            .apply_move(&reply)
+            .terminal_value()
+            .is_some_and(|value| lost(value * WIN_SCORE))
+        {
+            return Some(Warning::AllowsWin { reply });
+        }
+    }
+    let forced = strictness == Strictness::Forced
+        && lost(minimax_value_ab_depth_root(&after, CHECK_DEPTH))
+        && !lost(minimax_value_ab_depth_root(state, CHECK_DEPTH + 1));
+    forced.then_some(Warning::LosesByForce)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver_core::games::c4_bitboard::BitboardState;
+    use solver_core::games::ttt::{TicTacToeMove, TicTacToeState};
+
+    #[test]
+    fn moves_that_allow_a_win_are_flagged() {
+        // O threatens 3-4-5; only 5 stops it.
+        let s = TicTacToeState::from_str("XX.OO...X", Player::Player1).unwrap();
+        let check = |index| check_move(&s, &TicTacToeMove { index }, Strictness::Immediate);
+        assert_eq!(check(2), None);
+        assert_eq!(
+            check(6),
+            Some(Warning::AllowsWin {
+                reply: TicTacToeMove { index: 5 }
+            })
+        );
+        let warning = check(6).unwrap();
+        assert_eq!(
+            warning.message(|mv| mv.index.to_string()),
+            "that move lets your opponent win at once with 5"
+        );
+    }
+
+    #[test]
+    fn strict_mode_also_flags_forced_losses() {
+        // Player2 has the bottom row's columns 1 and 2; unless Player1
+        // takes 0, 3 or 4, Player2 plays 3 and threatens both 0 and 4.
+        let s = BitboardState::from_move_sequence("6152").unwrap();
+        assert_eq!(check_move(&s, &6, Strictness::Immediate), None);
+        assert_eq!(
+            check_move(&s, &6, Strictness::Forced),
+            Some(Warning::LosesByForce)
+        );
+        assert_eq!(check_move(&s, &3, Strictness::Forced), None);
+    }
+
+    #[test]
+    fn strictness_parses() {
+        assert_eq!(Strictness::parse("forced"), Ok(Strictness::Forced));
+        assert!(Strictness::parse("lenient").is_err());
+    }
+}
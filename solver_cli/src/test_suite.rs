@@ -0,0 +1,174 @@
+//! `test-suite`: solves Connect Four test positions with known values and
+//! reports the solver's accuracy, node counts and speed.
+//!
+//! Without `--suite` or `--file`, every bundled suite is run (see
+//! `solver_core::suites`). `--file` reads positions in the format of
+//! Pascal Pons' test sets, `<moves> <score>` with 1-based columns, so his
+//! published sets can be run as well. Positions the solver gets wrong are
+//! always listed, and make the command fail; `--verbose` lists every
+//! position.
+
+use std::fs;
+use std::time::Instant;
+
+use solver_core::suites::{self, SUITES, SuitePosition, SuiteReport, parse_suite, run_suite};
+
+use crate::args;
+
+pub const USAGE: &str =
+    "usage: solver_cli test-suite [--suite NAME ...] [--file FILE ...] [--tt MB] [--verbose]";
+
+/// Default transposition table size, in megabytes.
+const DEFAULT_TT_MB: usize = 64;
+
+#[derive(Debug)]
+struct Options {
+    suites: Vec<String>,
+    files: Vec<String>,
+    tt_mb: usize,
+    verbose: bool,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        suites: Vec::new(),
+        files: Vec::new(),
+        tt_mb: DEFAULT_TT_MB,
+        verbose: false,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| args::value(name, iter.next());
+        match arg.as_str() {
+            "--suite" => {
+                let name = value("--suite")?;
+                if suites::suite(&name).is_none() {
+                    let names: Vec<&str> = SUITES.iter().map(|s| s.name).collect();
+                    return Err(format!(
+                        "unknown suite '{name}' (known: {})",
+                        names.join(", ")
+                    ));
+                }
+                opts.suites.push(name);
+            }
+            "--file" => opts.files.push(value("--file")?),
+            "--tt" => {
+                let mb = value("--tt")?;
+                opts.tt_mb = mb
+                    .parse()
+                    .ok()
+                    .filter(|&mb| mb > 0)
+                    .ok_or_else(|| format!("invalid table size '{mb}'"))?;
+            }
+            "--verbose" => opts.verbose = true,
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    if opts.suites.is_empty() && opts.files.is_empty() {
+        opts.suites = SUITES.iter().map(|s| s.name.to_string()).collect();
+    }
+    Ok(opts)
+}
+
+/// The lines printed for one suite: the positions (failures only unless
+/// `verbose`) and a summary.
+fn report_lines(
+    name: &str,
+    positions: &[SuitePosition],
+    report: &SuiteReport,
+    seconds: f64,
+    verbose: bool,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (i, (position, result)) in positions.iter().zip(&report.results).enumerate() {
+        if verbose || !result.is_correct() {
+            lines.push(format!(
+                "  {:>3} {:<42} expected {:+} found {:+} nodes {}{}",
+                i + 1,
+                position.moves,
+                result.expected,
+                result.found,
+                result.nodes,
+                if result.is_correct() { "" } else { "  WRONG" }
+            ));
+        }
+    }
+    let count = report.results.len().max(1) as u64;
+    lines.push(format!(
+        "{name}: {}/{} correct ({:.1}%), {} nodes ({} per position), {seconds:.2}s, {:.0} nodes/s",
+        report.correct(),
+        report.results.len(),
+        100.0 * report.accuracy(),
+        report.nodes(),
+        report.nodes() / count,
+        report.nodes() as f64 / seconds.max(1e-9)
+    ));
+    lines
+}
+
+/// Runs the `test-suite` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let opts = parse_options(args)?;
+    let mut runs: Vec<(String, Vec<SuitePosition>)> = opts
+        .suites
+        .iter()
+        .filter_map(|name| suites::suite(name))
+        .map(|suite| (suite.name.to_string(), suite.positions()))
+        .collect();
+    for path in &opts.files {
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read '{path}': {e}"))?;
+        let positions = parse_suite(&text).map_err(|e| format!("{path}: {e}"))?;
+        runs.push((path.clone(), positions));
+    }
+
+    let mut wrong = 0;
+    for (name, positions) in &runs {
+        let started = Instant::now();
+        let report = run_suite(positions, opts.tt_mb);
+        let seconds = started.elapsed().as_secs_f64();
+        for line in report_lines(name, positions, &report, seconds, opts.verbose) {
+            println!("{line}");
+        }
+        wrong += report.results.len() - report.correct();
+    }
+    match wrong {
+        0 => Ok(()),
+        n => Err(format!("{n} position(s) solved wrongly")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::split_args;
+
+    #[test]
+    fn parses_options() {
+        let opts = parse_options(&[]).unwrap();
+        assert_eq!(opts.suites, ["end", "middle"]);
+        let opts = parse_options(&split_args("--file a.txt --tt 8 --verbose")).unwrap();
+        assert!(opts.suites.is_empty());
+        assert_eq!(opts.files, ["a.txt"]);
+        assert_eq!(opts.tt_mb, 8);
+        assert!(opts.verbose);
+        assert!(parse_options(&split_args("--suite opening")).is_err());
+        assert!(parse_options(&split_args("--tt 0")).is_err());
+    }
+
+    #[test]
+    fn reports_list_wrong_positions_and_a_summary() {
+        let mut positions =
+            parse_suite("312675152616667736551153125232 -1\n541566711474452477646275512113 -1\n")
+                .unwrap();
+        positions[1].expected = 1;
+        let report = run_suite(&positions, 1);
+        let lines = report_lines("mine", &positions, &report, 0.5, false);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("    2 541566711474452477646275512113"));
+        assert!(lines[0].ends_with("WRONG"));
+        assert!(lines[1].starts_with("mine: 1/2 correct (50.0%), "));
+
+        let lines = report_lines("mine", &positions, &report, 0.5, true);
+        assert_eq!(lines.len(), 3);
+    }
+}
@@ -0,0 +1,327 @@
+//! Board colors and symbols for terminal output.
+//!
+//! A theme file holds `key = value` lines (`#` starts a comment):
+//! - `player1.symbol`, `player2.symbol`, `empty.symbol`: one character each.
+//! - `player1.color`, `player2.color`, `background`: a color name (see
+//!   `parse_color`), an xterm 256-color index, or `none`.
+//!
+//! The theme is read from `$SOLVER_THEME`, or `theme.conf` in the working
+//! directory. Without one, players are drawn in blue and orange, which stay
+//! distinguishable under the common forms of color blindness (unlike red
+//! and yellow). When stdout is not a terminal, or `NO_COLOR` is set, colors
+//! are dropped and symbols fall back to plain ASCII.
+
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+
+use solver_core::game::{BoardGame, Player};
+use solver_core::render::{Frame, Layout, draw_grid};
+
+/// File name `Theme::load_default` looks for in the working directory.
+pub const DEFAULT_THEME_PATH: &str = "theme.conf";
+/// Environment variable that overrides `DEFAULT_THEME_PATH`.
+pub const THEME_PATH_ENV: &str = "SOLVER_THEME";
+
+/// How one kind of cell is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellStyle {
+    pub symbol: char,
+    /// xterm 256-color foreground, or `None` for the terminal default.
+    pub color: Option<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub player1: CellStyle,
+    pub player2: CellStyle,
+    pub empty: CellStyle,
+    /// xterm 256-color background behind the cells, if any.
+    pub background: Option<u8>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::colorblind()
+    }
+}
+
+/// Maps a color name or 256-color index to an xterm 256-color index.
+///
+/// The Okabe-Ito names (`blue`, `orange`, `sky-blue`, `vermillion`,
+/// `bluish-green`, `yellow`, `reddish-purple`) are colorblind-safe;
+/// `red`, `green`, `black`, `white` and `grey` are also accepted.
+fn parse_color(value: &str) -> Result<Option<u8>, String> {
+    let color = match value {
+        "none" => return Ok(None),
+        "blue" => 25,
+        "orange" => 172,
+        "sky-blue" => 74,
+        "vermillion" => 166,
+        "bluish-green" => 36,
+        "yellow" => 227,
+        "reddish-purple" => 175,
+        "red" => 160,
+        "green" => 34,
+        "black" => 16,
+        "white" => 15,
+        "grey" | "gray" => 244,
+        other => other
+            .parse()
+            .map_err(|_| format!("unknown color '{other}'"))?,
+    };
+    Ok(Some(color))
+}
+
+fn parse_symbol(value: &str) -> Result<char, String> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!("symbol must be one character, got '{value}'")),
+    }
+}
+
+impl Theme {
+    /// Blue X and orange O on the terminal's background.
+    pub fn colorblind() -> Self {
+        Self {
+            player1: CellStyle {
+                symbol: 'X',
+                color: Some(25),
+            },
+            player2: CellStyle {
+                symbol: 'O',
+                color: Some(172),
+            },
+            empty: CellStyle {
+                symbol: '.',
+                color: None,
+            },
+            background: None,
+        }
+    }
+
+    /// ASCII symbols without any escape codes.
+    pub fn plain() -> Self {
+        Self::colorblind().without_colors()
+    }
+
+    /// This theme with every color removed.
+    pub fn without_colors(mut self) -> Self {
+        self.player1.color = None;
+        self.player2.color = None;
+        self.empty.color = None;
+        self.background = None;
+        self
+    }
+
+    /// This theme with colors removed and non-ASCII symbols replaced, for
+    /// output that is not a terminal.
+    pub fn plain_fallback(self) -> Self {
+        let plain = Self::plain();
+        let ascii = |style: CellStyle, fallback: CellStyle| {
+            if style.symbol.is_ascii() {
+                style.symbol
+            } else {
+                fallback.symbol
+            }
+        };
+        Self {
+            player1: CellStyle {
+                symbol: ascii(self.player1, plain.player1),
+                color: None,
+            },
+            player2: CellStyle {
+                symbol: ascii(self.player2, plain.player2),
+                color: None,
+            },
+            empty: CellStyle {
+                symbol: ascii(self.empty, plain.empty),
+                color: None,
+            },
+            background: None,
+        }
+    }
+
+    /// Parses a theme file, starting from the colorblind default.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut theme = Self::colorblind();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected 'key = value'", n + 1))?;
+            let value = value.trim();
+            let at_line = |e: String| format!("line {}: {e}", n + 1);
+            match key.trim() {
+                "player1.symbol" => theme.player1.symbol = parse_symbol(value).map_err(at_line)?,
+                "player2.symbol" => theme.player2.symbol = parse_symbol(value).map_err(at_line)?,
+                "empty.symbol" => theme.empty.symbol = parse_symbol(value).map_err(at_line)?,
+                "player1.color" => theme.player1.color = parse_color(value).map_err(at_line)?,
+                "player2.color" => theme.player2.color = parse_color(value).map_err(at_line)?,
+                "empty.color" => theme.empty.color = parse_color(value).map_err(at_line)?,
+                "background" => theme.background = parse_color(value).map_err(at_line)?,
+                other => return Err(at_line(format!("unknown key '{other}'"))),
+            }
+        }
+        Ok(theme)
+    }
+
+    /// Loads a theme file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        Self::parse(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Loads the theme for terminal output: the configured theme file (or
+    /// the default theme if there is none), made plain if stdout is not a
+    /// terminal or `NO_COLOR` is set (see `for_stdout`).
+    ///
+    /// A theme file that can't be read falls back to the default theme,
+    /// made plain all the same, and the error is returned with it as a
+    /// warning.
+    pub fn load_default() -> (Self, Option<String>) {
+        let path = std::env::var(THEME_PATH_ENV).unwrap_or_else(|_| DEFAULT_THEME_PATH.to_string());
+        let (theme, warning) = Self::load_or_default(&path);
+        (theme.for_stdout(), warning)
+    }
+
+    /// The theme in `path` if there is one, or else the default theme with
+    /// the reason it wasn't loaded, if any.
+    fn load_or_default(path: &str) -> (Self, Option<String>) {
+        if !Path::new(path).exists() {
+            return (Self::default(), None);
+        }
+        match Self::load(path) {
+            Ok(theme) => (theme, None),
+            Err(e) => (Self::default(), Some(e)),
+        }
+    }
+
+    /// `self` as it should be drawn on stdout: plain if stdout is not a
+    /// terminal or `NO_COLOR` is set.
+    pub fn for_stdout(self) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        if no_color || !io::stdout().is_terminal() {
+            self.plain_fallback()
+        } else {
+            self
+        }
+    }
+
+    /// Returns the style for a cell owned by `owner` (`None` = empty).
+    pub fn style(&self, owner: Option<Player>) -> CellStyle {
+        match owner {
+            Some(Player::Player1) => self.player1,
+            Some(Player::Player2) => self.player2,
+            None => self.empty,
+        }
+    }
+
+    /// Draws one cell, with escape codes if the theme has colors.
+    pub fn paint(&self, owner: Option<Player>) -> String {
+        let style = self.style(owner);
+        let mut codes = Vec::new();
+        if let Some(fg) = style.color {
+            codes.push(format!("38;5;{fg}"));
+        }
+        if let Some(bg) = self.background {
+            codes.push(format!("48;5;{bg}"));
+        }
+        if codes.is_empty() {
+            style.symbol.to_string()
+        } else {
+            format!("\x1b[{}m{}\x1b[0m", codes.join(";"), style.symbol)
+        }
+    }
+
+    /// Renders any grid game in the layout of `print_ttt_board`: cells
+    /// separated by ` | `, rows by `---+---...`, labelled and oriented as
+    /// `layout` says.
+    pub fn render<G: BoardGame>(&self, state: &G, layout: &Layout) -> String {
+        draw_grid(
+            state.rows(),
+            state.cols(),
+            |row, col| self.paint(state.cell(row, col).owner()),
+            &Frame::ASCII,
+            layout,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver_core::game::GameState;
+    use solver_core::games::c4_bitboard::BitboardState;
+    use solver_core::games::ttt::TicTacToeState;
+    use solver_core::render::Labels;
+    use solver_core::selection::SelectBy;
+
+    #[test]
+    fn parses_theme_files() {
+        let theme = Theme::parse(
+            "# my colors\nplayer1.symbol = ●\nplayer1.color = sky-blue\nplayer2.color = 208\n\nbackground = none # default\n",
+        )
+        .unwrap();
+        assert_eq!(theme.player1.symbol, '●');
+        assert_eq!(theme.player1.color, Some(74));
+        assert_eq!(theme.player2.color, Some(208));
+        assert_eq!(theme.player2.symbol, 'O');
+
+        assert!(Theme::parse("player1.color = mauve").is_err());
+        assert!(Theme::parse("player1.symbol = XX").is_err());
+        assert!(Theme::parse("tint = blue").is_err());
+        assert!(Theme::parse("player1.color").is_err());
+    }
+
+    #[test]
+    fn unreadable_theme_files_fall_back_with_a_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("theme.conf");
+        fs::write(&path, "tint = blue\n").unwrap();
+        let (theme, warning) = Theme::load_or_default(path.to_str().unwrap());
+        assert_eq!(theme, Theme::default());
+        assert!(warning.unwrap().contains("tint"));
+        // Tests don't run on a terminal, so the fallback is made plain too.
+        let state = TicTacToeState::new().apply_move(&TicTacToeState::new().legal_moves()[4]);
+        assert!(
+            !theme
+                .for_stdout()
+                .render(&state, &Layout::default())
+                .contains('\x1b')
+        );
+
+        let missing = dir.path().join("missing.conf");
+        assert_eq!(
+            Theme::load_or_default(missing.to_str().unwrap()),
+            (Theme::default(), None)
+        );
+    }
+
+    #[test]
+    fn plain_fallback_is_ascii_without_escape_codes() {
+        let fancy = Theme::parse("player1.symbol = ●\nbackground = grey").unwrap();
+        let state = TicTacToeState::new().apply_move(&TicTacToeState::new().legal_moves()[4]);
+        let bare = Layout::default();
+        assert!(fancy.render(&state, &bare).contains('\x1b'));
+        let plain = fancy.plain_fallback().render(&state, &bare);
+        assert_eq!(
+            plain,
+            ". | . | .\n---+---+---\n. | X | .\n---+---+---\n. | . | .\n"
+        );
+
+        let c4 = BitboardState::from_move_sequence("34").unwrap();
+        let board = Theme::plain().render(&c4, &bare);
+        assert_eq!(board.lines().count(), 11);
+        assert_eq!(board.lines().last(), Some(". | . | . | X | O | . | ."));
+        let labelled = Layout::labelled(Labels::Numbers, SelectBy::Column);
+        let board = Theme::plain().render(&c4, &labelled);
+        assert_eq!(board.lines().last(), Some("0   1   2   3   4   5   6"));
+    }
+}
@@ -0,0 +1,364 @@
+//! `tournament`: plays several engine configurations against each other.
+//!
+//! Engines are given as `match` specs (see `matches`), one `--engine` each.
+//! Every pairing is a mini-match of `--games` games over the openings of
+//! `--opening-plies` moves, alternating colors. Games run on `--jobs` threads
+//! (all cores by default), with fresh engines for every game. The format is
+//! a round robin (everyone meets everyone) or `--swiss ROUNDS` rounds of a Swiss system
+//! (players with equal scores meet, without rematches). `--seed N` (by
+//! default `SOLVER_SEED`) seeds the `random` engines without a seed of
+//! their own, each from its own stream.
+//!
+//! The standings are ranked by points, then Sonneborn-Berger score. With
+//! `--crosstable FILE`, the crosstable is also written as CSV: one row per
+//! player in standings order, with their points against each opponent.
+//! With `--html FILE`, a self-contained HTML report with replays of every
+//! game is written as well; `--annotate DEPTH` marks its moves (`!!` to
+//! `??`, see `solver_core::annotate`) by searches of DEPTH plies.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::sync::Arc;
+use std::thread;
+
+use solver_core::arena::{AgentFactory, opening_positions};
+use solver_core::book::OpeningBook;
+use solver_core::games::c4_bitboard::BitboardState;
+use solver_core::games::ttt::TicTacToeState;
+use solver_core::registry::RegisteredGame;
+use solver_core::tournament::{
+    Tournament, html_report, html_report_annotated, run_round_robin_parallel, run_swiss_parallel,
+};
+
+use crate::args;
+use crate::matches::EngineSpec;
+
+pub const USAGE: &str = "usage: solver_cli tournament --engine SPEC --engine SPEC [--engine SPEC ...] [--swiss ROUNDS] [--game c4|ttt] [--games N] [--opening-plies N] [--no-book] [--jobs N] [--seed N] [--crosstable FILE] [--html FILE [--annotate DEPTH]]\n  SPEC: as for match";
+
+#[derive(Debug)]
+struct Options {
+    engines: Vec<EngineSpec>,
+    /// The specs as given, naming the engines.
+    names: Vec<String>,
+    /// Swiss rounds, or `None` for a round robin.
+    swiss: Option<u32>,
+    ttt: bool,
+    games: u32,
+    opening_plies: u32,
+    use_book: bool,
+    jobs: usize,
+    /// The run's seed, for `random` engines without one of their own.
+    seed: Option<u64>,
+    crosstable: Option<String>,
+    html: Option<String>,
+    /// Depth of the searches annotating the HTML report's moves.
+    annotate: Option<u32>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        engines: Vec::new(),
+        names: Vec::new(),
+        swiss: None,
+        ttt: false,
+        games: 2,
+        opening_plies: 2,
+        use_book: true,
+        jobs: thread::available_parallelism().map_or(1, |n| n.get()),
+        seed: None,
+        crosstable: None,
+        html: None,
+        annotate: None,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| args::value(name, iter.next());
+        let positive = |name: &str, text: String| {
+            text.parse()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("invalid value '{text}' for {name}"))
+        };
+        match arg.as_str() {
+            "--engine" => {
+                let spec = value("--engine")?;
+                opts.engines.push(EngineSpec::parse(&spec)?);
+                // Number repeated specs to keep names unique.
+                let repeats = opts
+                    .names
+                    .iter()
+                    .filter(|n| n.split('#').next() == Some(&spec))
+                    .count();
+                opts.names.push(if repeats == 0 {
+                    spec
+                } else {
+                    format!("{spec}#{}", repeats + 1)
+                });
+            }
+            "--swiss" => opts.swiss = Some(positive("--swiss", value("--swiss")?)?),
+            "--game" => {
+                opts.ttt = match value("--game")?.as_str() {
+                    "c4" => false,
+                    "ttt" => true,
+                    other => return Err(format!("unknown game '{other}'")),
+                }
+            }
+            "--games" => opts.games = positive("--games", value("--games")?)?,
+            "--opening-plies" => {
+                let plies = value("--opening-plies")?;
+                opts.opening_plies = plies
+                    .parse()
+                    .map_err(|_| format!("invalid opening plies '{plies}'"))?;
+            }
+            "--no-book" => opts.use_book = false,
+            "--jobs" => {
+                let jobs = value("--jobs")?;
+                opts.jobs = jobs
+                    .parse()
+                    .ok()
+                    .filter(|&j| j > 0)
+                    .ok_or_else(|| format!("invalid job count '{jobs}'"))?;
+            }
+            "--seed" => {
+                let seed = value("--seed")?;
+                opts.seed = Some(seed.parse().map_err(|_| format!("invalid seed '{seed}'"))?);
+            }
+            "--crosstable" => opts.crosstable = Some(value("--crosstable")?),
+            "--html" => opts.html = Some(value("--html")?),
+            "--annotate" => opts.annotate = Some(positive("--annotate", value("--annotate")?)?),
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    if opts.engines.len() < 2 {
+        return Err("a tournament needs at least two --engine specs".to_string());
+    }
+    let seed = match opts.seed {
+        Some(seed) => seed,
+        None => crate::global_seed()?.unwrap_or(0),
+    };
+    opts.engines = (0..)
+        .zip(opts.engines)
+        .map(|(engine, spec)| spec.seeded(seed, engine))
+        .collect();
+    if opts.annotate.is_some() && opts.html.is_none() {
+        return Err("--annotate needs --html".to_string());
+    }
+    Ok(opts)
+}
+
+/// Plays the tournament from the openings of `root`, writing the HTML
+/// report if one was asked for.
+fn play<G>(root: G, opts: &Options, factories: &[&AgentFactory<G>]) -> Result<Tournament, String>
+where
+    G: RegisteredGame,
+    G::Move: PartialEq,
+{
+    let starts: Vec<G> = opening_positions(&root, opts.opening_plies)
+        .into_iter()
+        .map(|(_, state)| state)
+        .collect();
+    if starts.is_empty() {
+        return Err(format!("no openings of {} plies", opts.opening_plies));
+    }
+    let report = match opts.swiss {
+        Some(rounds) => run_swiss_parallel(&starts, factories, rounds, opts.games, opts.jobs),
+        None => run_round_robin_parallel(&starts, factories, opts.games, opts.jobs),
+    };
+    if let Some(path) = &opts.html {
+        let html = match opts.annotate {
+            Some(depth) => html_report_annotated(&report, &starts, &opts.names, depth),
+            None => html_report(&report, &starts, &opts.names),
+        };
+        fs::write(path, html).map_err(|e| format!("failed to write {path}: {e}"))?;
+    }
+    Ok(report.tournament)
+}
+
+/// Formats a point total without a trailing `.0`.
+fn fmt_points(points: f64) -> String {
+    if points.fract() == 0.0 {
+        format!("{points:.0}")
+    } else {
+        format!("{points:.1}")
+    }
+}
+
+/// The standings table, with one crosstable column per rank.
+fn standings_table(tournament: &Tournament, names: &[String]) -> String {
+    let standings = tournament.standings();
+    let mut out = String::new();
+    let width = names.iter().map(String::len).max().unwrap_or(0);
+    let _ = write!(out, "rank  {:<width$}  points     SB ", "engine");
+    for rank in 1..=standings.len() {
+        let _ = write!(out, " {rank:>4}");
+    }
+    out.push('\n');
+    for (rank, row) in standings.iter().enumerate() {
+        let _ = write!(
+            out,
+            "{:>4}  {:<width$}  {:>6}  {:>5.2} ",
+            rank + 1,
+            names[row.player],
+            fmt_points(row.points),
+            row.sonneborn_berger
+        );
+        for column in &standings {
+            let cell = if column.player == row.player {
+                "x".to_string()
+            } else if tournament.have_met(row.player, column.player) {
+                fmt_points(tournament.points_against(row.player, column.player))
+            } else {
+                String::new()
+            };
+            let _ = write!(out, " {cell:>4}");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The crosstable as CSV, in standings order.
+fn crosstable_csv(tournament: &Tournament, names: &[String]) -> String {
+    let standings = tournament.standings();
+    let mut out = String::from("rank,engine,points,sonneborn_berger,byes");
+    for rank in 1..=standings.len() {
+        let _ = write!(out, ",{rank}");
+    }
+    out.push('\n');
+    for (rank, row) in standings.iter().enumerate() {
+        let _ = write!(
+            out,
+            "{},{},{},{},{}",
+            rank + 1,
+            names[row.player],
+            row.points,
+            row.sonneborn_berger,
+            tournament.byes(row.player)
+        );
+        for column in &standings {
+            out.push(',');
+            if column.player != row.player && tournament.have_met(row.player, column.player) {
+                let _ = write!(
+                    out,
+                    "{}",
+                    tournament.points_against(row.player, column.player)
+                );
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Runs the `tournament` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let opts = parse_options(args)?;
+    for depth in opts.engines.iter().filter_map(EngineSpec::depth) {
+        if opts.ttt {
+            crate::warn_if_impractical(&TicTacToeState::new(), depth);
+        } else {
+            crate::warn_if_impractical(&BitboardState::new(), depth);
+        }
+    }
+    let tournament = if opts.ttt {
+        let engines: Vec<_> = opts
+            .engines
+            .iter()
+            .map(|spec| move |game| spec.build(game))
+            .collect();
+        let factories: Vec<&AgentFactory<_>> = engines.iter().map(|e| e as _).collect();
+        play(TicTacToeState::new(), &opts, &factories)?
+    } else {
+        let book = if opts.use_book {
+            OpeningBook::load_default()?.map(Arc::new)
+        } else {
+            None
+        };
+        if let Some(book) = &book {
+            let kind = if book.is_exact() { "exact " } else { "" };
+            println!("Using {kind}opening book ({} positions)", book.len());
+        }
+        let engines: Vec<_> = opts
+            .engines
+            .iter()
+            .map(|spec| {
+                let book = book.as_ref();
+                move |game| spec.build_c4(book, game)
+            })
+            .collect();
+        let factories: Vec<&AgentFactory<_>> = engines.iter().map(|e| e as _).collect();
+        play(BitboardState::new(), &opts, &factories)?
+    };
+
+    print!("{}", standings_table(&tournament, &opts.names));
+    if let Some(path) = &opts.crosstable {
+        fs::write(path, crosstable_csv(&tournament, &opts.names))
+            .map_err(|e| format!("failed to write {path}: {e}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::split_args;
+
+    #[test]
+    fn parses_options() {
+        let opts = parse_options(&split_args(
+            "--engine ab:2 --engine random --engine id:3 --swiss 3",
+        ))
+        .unwrap();
+        assert_eq!(opts.engines.len(), 3);
+        assert_eq!(opts.swiss, Some(3));
+        let opts = parse_options(&split_args(
+            "--engine random --engine random --html report.html",
+        ))
+        .unwrap();
+        assert_eq!(opts.names, ["random", "random#2"]);
+        // Repeated random engines still play differently.
+        assert_ne!(opts.engines[0], opts.engines[1]);
+        let seeded =
+            parse_options(&split_args("--engine random --engine random --seed 4")).unwrap();
+        assert_eq!(seeded.seed, Some(4));
+        assert_ne!(seeded.engines, opts.engines);
+        assert_eq!(opts.html.as_deref(), Some("report.html"));
+        assert_eq!(opts.annotate, None);
+        let opts = parse_options(&split_args(
+            "--engine random --engine random --html report.html --annotate 4",
+        ))
+        .unwrap();
+        assert_eq!(opts.annotate, Some(4));
+        assert!(
+            parse_options(&split_args("--engine random --engine random --annotate 4")).is_err()
+        );
+        assert!(parse_options(&split_args("--engine ab:2")).is_err());
+        assert!(parse_options(&split_args("--engine ab:2 --engine random --swiss 0")).is_err());
+    }
+
+    #[test]
+    fn ttt_crosstable_lists_every_pairing() {
+        let opts = parse_options(&split_args(
+            "--game ttt --engine ab:9 --engine random:1 --engine random:2 --games 2 --opening-plies 1",
+        ))
+        .unwrap();
+        let engines: Vec<_> = opts
+            .engines
+            .iter()
+            .map(|spec| move |game| spec.build(game))
+            .collect();
+        let factories: Vec<&AgentFactory<_>> = engines.iter().map(|e| e as _).collect();
+        let tournament = play(TicTacToeState::new(), &opts, &factories).unwrap();
+        let csv = crosstable_csv(&tournament, &opts.names);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "rank,engine,points,sonneborn_berger,byes,1,2,3");
+        assert!(lines[1].starts_with("1,ab:9,"));
+        // Every player has two filled cells and an empty diagonal.
+        for line in &lines[1..] {
+            let cells: Vec<&str> = line.split(',').skip(5).collect();
+            assert_eq!(cells.iter().filter(|c| c.is_empty()).count(), 1);
+        }
+        assert!(standings_table(&tournament, &opts.names).contains("random:2"));
+    }
+}
@@ -0,0 +1,911 @@
+//! `tui` (with the `tui` feature): a full-screen game against the engine.
+//!
+//! The screen shows the board, the moves so far, an evaluation bar, and the
+//! engine's search as it deepens. Pick a move with the arrow keys, which
+//! move a cursor over the board with the legal moves highlighted, or type
+//! it in the game's notation, and press Enter to play it. Enter makes a
+//! thinking engine move at once, and starts a new game once one is over.
+//! Esc or Ctrl-C quits.
+//!
+//! Any registered game can be played (see `games`), at `--depth` plies
+//! (the game's default depth otherwise). With `--second`, the engine
+//! moves first. With `--seed N` (by default `SOLVER_SEED`), it picks at
+//! random between equally good moves, as in `play`.
+//!
+//! Typing `save [FILE]` saves the game in progress like `play` does (see
+//! `saved_game`) and plays on; `--resume FILE` carries on with a saved
+//! game, whose game, side and depth override the other options.
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, Paragraph, Wrap};
+use solver_core::cancel::CancelToken;
+use solver_core::dyn_game::{DynGameState, DynMove};
+use solver_core::events::{GameEvent, GameObserver};
+use solver_core::game::{Outcome, Player};
+use solver_core::referee::{Referee, Rejection, Submission, Verdict};
+use solver_core::registry::{GameEntry, Registry};
+use solver_core::render::BoardStyle;
+use solver_core::rng::{Rng, SplitMix64};
+use solver_core::selection::{Direction, Selection};
+use solver_core::solvers::minimax::WIN_SCORE;
+
+use crate::PlayOptions;
+use crate::args;
+use crate::messages::{self, Message};
+use crate::profile::default_player_name;
+use crate::replay::Replay;
+use crate::saved_game::SavedGame;
+
+pub const USAGE: &str =
+    "usage: solver_cli tui [--game NAME] [--depth N] [--second] [--seed N] [--resume FILE]";
+
+const PICK_A_MOVE: &str = "Pick a move with the arrow keys or type it, then press Enter.";
+
+/// Values at which the evaluation bar is three quarters full; proven wins
+/// fill it completely.
+const EVAL_SCALE: f64 = 100.0;
+
+#[derive(Debug)]
+struct Options {
+    game: String,
+    depth: Option<u32>,
+    human: Player,
+    seed: Option<u64>,
+    resume: Option<PathBuf>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        game: "c4".to_string(),
+        depth: None,
+        human: Player::Player1,
+        seed: None,
+        resume: None,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| args::value(name, iter.next());
+        match arg.as_str() {
+            "--game" => opts.game = value("--game")?,
+            "--depth" => {
+                let depth = value("--depth")?;
+                opts.depth = Some(
+                    depth
+                        .parse()
+                        .ok()
+                        .filter(|&d| d > 0)
+                        .ok_or_else(|| format!("invalid depth '{depth}'"))?,
+                );
+            }
+            "--second" => opts.human = Player::Player2,
+            "--seed" => {
+                let seed = value("--seed")?;
+                opts.seed = Some(seed.parse().map_err(|_| format!("invalid seed '{seed}'"))?);
+            }
+            "--resume" => opts.resume = Some(value("--resume")?.into()),
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    Ok(opts)
+}
+
+/// One completed depth of the engine's search.
+#[derive(Clone, Debug)]
+struct SearchInfo {
+    depth: u32,
+    best: DynMove,
+    value: i32,
+    /// Nodes of all depths so far.
+    nodes: u64,
+    elapsed: Duration,
+}
+
+/// A search running on its own thread, reporting each completed depth.
+struct Thinking {
+    updates: Receiver<SearchInfo>,
+    cancel: CancelToken,
+}
+
+impl Thinking {
+    /// Searches `state` to `depth` plies, deepening iteratively. With
+    /// `tie_break`, a completed search ends with a random pick, seeded by
+    /// it, between the equally good moves.
+    fn start(state: Box<dyn DynGameState>, depth: u32, tie_break: Option<u64>) -> Self {
+        let (tx, updates) = mpsc::channel();
+        let cancel = CancelToken::new();
+        let token = cancel.clone();
+        thread::spawn(move || {
+            let started = Instant::now();
+            let result = state.search_reporting(depth, Some(&token), &mut |result| {
+                if let (Some(best), Some(value)) = (result.best_move(), result.value()) {
+                    let _ = tx.send(SearchInfo {
+                        depth: result.depth,
+                        best: best.clone(),
+                        value,
+                        nodes: result.stats.nodes,
+                        elapsed: started.elapsed(),
+                    });
+                }
+            });
+            if let Some(seed) = tie_break
+                && result.depth == depth
+                && !token.is_cancelled()
+                && let Some((moves, value)) = state.best_moves(depth)
+                && let Some(best) = SplitMix64::new(seed).choose(&moves)
+            {
+                let _ = tx.send(SearchInfo {
+                    depth,
+                    best: best.clone(),
+                    value,
+                    nodes: result.stats.nodes,
+                    elapsed: started.elapsed(),
+                });
+            }
+        });
+        Self { updates, cancel }
+    }
+}
+
+/// The state of the screen between key presses.
+struct App {
+    entry: GameEntry,
+    /// The game, whose events set `message` (see `on_event`).
+    game: Referee<Box<dyn DynGameState>>,
+    /// The legal moves of the position on the board, with the cursor.
+    selection: Selection<DynMove>,
+    human: Player,
+    depth: u32,
+    /// Seeds the engine's picks between equally good moves, with a seed.
+    rng: Option<SplitMix64>,
+    input: String,
+    message: String,
+    /// The engine's latest completed search, kept after it moves.
+    info: Option<SearchInfo>,
+    thinking: Option<Thinking>,
+    quit: bool,
+}
+
+impl App {
+    fn new(entry: GameEntry, depth: u32, human: Player, rng: Option<SplitMix64>) -> Self {
+        Self::resume(entry, depth, human, rng, (entry.new_game)())
+    }
+
+    /// Plays on from `state`, a position of `entry`'s game.
+    fn resume(
+        entry: GameEntry,
+        depth: u32,
+        human: Player,
+        rng: Option<SplitMix64>,
+        state: Box<dyn DynGameState>,
+    ) -> Self {
+        let mut app = Self {
+            entry,
+            selection: state.selection(),
+            game: Referee::new(state),
+            human,
+            depth,
+            rng,
+            input: String::new(),
+            message: PICK_A_MOVE.to_string(),
+            info: None,
+            thinking: None,
+            quit: false,
+        };
+        app.think_if_engine_to_move();
+        app
+    }
+
+    fn new_game(&mut self) {
+        self.game = Referee::new((self.entry.new_game)());
+        self.reselect();
+        self.info = None;
+        self.message = PICK_A_MOVE.to_string();
+        self.think_if_engine_to_move();
+    }
+
+    /// The current position.
+    fn state(&self) -> &dyn DynGameState {
+        self.game.state().as_ref()
+    }
+
+    /// Offers the moves of the current position, keeping the cursor where
+    /// it was.
+    fn reselect(&mut self) {
+        let (row, col) = self.selection.cursor();
+        self.selection = self.state().selection();
+        self.selection.set_cursor(row, col);
+    }
+
+    /// Whether the human is to pick a move.
+    fn choosing(&self) -> bool {
+        self.thinking.is_none() && !self.state().is_terminal()
+    }
+
+    fn think_if_engine_to_move(&mut self) {
+        if !self.state().is_terminal() && self.state().current_player() != self.human {
+            let tie_break = self.rng.as_mut().map(Rng::next_u64);
+            let state = self.state().clone_box();
+            self.thinking = Some(Thinking::start(state, self.depth, tie_break));
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter => self.submit(),
+            KeyCode::Up => self.move_cursor(Direction::Up),
+            KeyCode::Down => self.move_cursor(Direction::Down),
+            KeyCode::Left => self.move_cursor(Direction::Left),
+            KeyCode::Right => self.move_cursor(Direction::Right),
+            _ => {}
+        }
+    }
+
+    fn move_cursor(&mut self, direction: Direction) {
+        if !self.choosing() {
+            return;
+        }
+        self.selection.move_cursor(direction);
+        self.message = match self.selection.selected() {
+            Some(mv) => format!("Enter plays {mv}."),
+            None => "No legal move there.".to_string(),
+        };
+    }
+
+    /// Acts on Enter: plays the typed or selected move, hurries the engine,
+    /// or starts a new game.
+    fn submit(&mut self) {
+        let text = std::mem::take(&mut self.input);
+        let text = text.trim();
+        if let Some(thinking) = &self.thinking {
+            if text.is_empty() {
+                thinking.cancel.cancel();
+            } else {
+                self.message = "The engine is thinking; Enter makes it move now.".to_string();
+            }
+        } else if self.state().is_terminal() {
+            self.new_game();
+        } else if let Some(file) = text
+            .strip_prefix("save")
+            .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+        {
+            self.save(file.trim());
+        } else if !text.is_empty() {
+            match self.state().parse_move(text) {
+                Ok(mv) => self.play(mv),
+                Err(e) => self.message = format!("'{text}': {e}"),
+            }
+        } else {
+            match self.selection.selected().cloned() {
+                Some(mv) => self.play(mv),
+                None => self.message = "No legal move there; pick a highlighted cell.".to_string(),
+            }
+        }
+    }
+
+    /// Saves the game to `file`, or by default next to the player's
+    /// profile, to be resumed with `resume` or `--resume`.
+    fn save(&mut self, file: &str) {
+        let path = match file {
+            "" => SavedGame::path(&default_player_name()),
+            file => file.into(),
+        };
+        let history = self.state().history();
+        let first = usize::from(self.human == Player::Player2);
+        let game = SavedGame {
+            opts: PlayOptions {
+                game: Some(self.entry.name.to_string()),
+                ai_first: self.human == Player::Player2,
+                ..PlayOptions::default()
+            },
+            human_is_player1: self.human == Player::Player1,
+            depth: self.depth,
+            level: None,
+            moves: history.iter().map(|mv| mv.notation().to_string()).collect(),
+            human_moves: history.iter().skip(first).step_by(2).count() as u32,
+            human_seconds: 0.0,
+            clock: None,
+            rng: self.rng.as_ref().map(SplitMix64::state),
+        };
+        self.message = match game.save(&path) {
+            Ok(()) => messages::text(&Message::Saved(&path.display().to_string())),
+            Err(e) => e,
+        };
+    }
+
+    /// Plays the human's move `mv`, then lets the engine answer.
+    fn play(&mut self, mv: DynMove) {
+        self.message.clear();
+        if let Err(reason) = self.submit_move(mv, None) {
+            self.message = reason.to_string();
+            return;
+        }
+        self.think_if_engine_to_move();
+    }
+
+    /// Submits `mv`, valued `eval` by the engine if it is the engine's, to
+    /// the referee, and takes in the events of the ruling.
+    fn submit_move(&mut self, mv: DynMove, eval: Option<i32>) -> Result<(), Rejection> {
+        let submission = Submission {
+            player: self.state().current_player(),
+            ply: self.game.ply(),
+            mv,
+        };
+        // Games in the TUI have no clocks, so the time is irrelevant.
+        let mut events = Vec::new();
+        let event = self
+            .game
+            .submit_observed(submission, eval, Duration::ZERO, &mut events);
+        if let Verdict::Rejected { reason, .. } = event.verdict {
+            return Err(reason);
+        }
+        self.reselect();
+        for event in &events {
+            self.on_event(event);
+        }
+        Ok(())
+    }
+
+    /// Takes the engine's progress, and plays its move once it is done.
+    fn poll_engine(&mut self) {
+        let Some(thinking) = &self.thinking else {
+            return;
+        };
+        loop {
+            match thinking.updates.try_recv() {
+                Ok(info) => self.info = Some(info),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        self.thinking = None;
+        let Some(info) = self.info.clone() else {
+            self.message = "The engine found no move.".to_string();
+            return;
+        };
+        if let Err(reason) = self.submit_move(info.best, Some(info.value)) {
+            self.message = format!("The engine's move failed: {reason}");
+        }
+    }
+
+    fn status(&self) -> String {
+        match self.state().outcome() {
+            Some(Outcome::Win(player)) => format!("{} wins", mark(player)),
+            Some(Outcome::Draw) => "Draw".to_string(),
+            None if self.thinking.is_some() => {
+                format!(
+                    "{} to move (engine thinking)",
+                    mark(self.state().current_player())
+                )
+            }
+            None => format!("{} to move (you)", mark(self.state().current_player())),
+        }
+    }
+}
+
+/// Tells the human what the engine played and when the game is over.
+impl GameObserver<DynMove> for App {
+    fn on_event(&mut self, event: &GameEvent<DynMove>) {
+        match event {
+            GameEvent::MoveMade { player, mv, .. } if *player != self.human => {
+                self.message = format!("The engine played {mv}.");
+            }
+            GameEvent::GameEnded { .. } => self.message.push_str(" Press Enter for a new game."),
+            _ => {}
+        }
+    }
+}
+
+fn mark(player: Player) -> &'static str {
+    match player {
+        Player::Player1 => "X",
+        Player::Player2 => "O",
+    }
+}
+
+/// The share of the evaluation bar for Player1 at `value`, and its label.
+fn eval_bar(value: i32) -> (f64, String) {
+    if value >= WIN_SCORE {
+        (1.0, "X wins".to_string())
+    } else if value <= -WIN_SCORE {
+        (0.0, "O wins".to_string())
+    } else {
+        let v = f64::from(value);
+        (0.5 + 0.5 * v / (v.abs() + EVAL_SCALE), format!("{value:+}"))
+    }
+}
+
+/// The board in the layout of `unicode_board`. While the human is to move,
+/// the legal moves are highlighted, and the cursor is shown green on a
+/// legal move and red elsewhere.
+fn board_lines(app: &App) -> Vec<Line<'static>> {
+    let glyphs = app.state().symbols();
+    let choosing = app.choosing();
+    let (rows, cols) = (app.state().rows(), app.state().cols());
+    let mut lines = Vec::new();
+    for row in 0..rows {
+        if row > 0 {
+            lines.push(Line::raw(vec!["───"; cols].join("┼")));
+        }
+        let mut spans = Vec::new();
+        for col in 0..cols {
+            if col > 0 {
+                spans.push(Span::raw(" │ "));
+            }
+            let target = choosing && app.selection.is_target(row, col);
+            let style = if choosing && app.selection.cursor() == (row, col) {
+                let color = if target { Color::Green } else { Color::Red };
+                Style::new().fg(Color::Black).bg(color)
+            } else if target {
+                Style::new().fg(Color::Green).add_modifier(Modifier::BOLD)
+            } else {
+                Style::new()
+            };
+            spans.push(Span::styled(glyphs.get(app.state().cell(row, col)), style));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let [top, eval, engine, input] = Layout::vertical([
+        Constraint::Min(8),
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Length(4),
+    ])
+    .areas(frame.area());
+    let [board, moves] =
+        Layout::horizontal([Constraint::Min(30), Constraint::Length(24)]).areas(top);
+
+    frame.render_widget(
+        Paragraph::new(board_lines(app)).block(
+            Block::bordered()
+                .title(app.entry.display_name)
+                .title_bottom(app.status()),
+        ),
+        board,
+    );
+
+    let history: Vec<String> = app
+        .state()
+        .history()
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            let pair: Vec<String> = pair.iter().map(ToString::to_string).collect();
+            format!("{:>3}. {}", i + 1, pair.join("  "))
+        })
+        .collect();
+    // Keep the latest moves in view.
+    let shown = history
+        .len()
+        .saturating_sub(usize::from(moves.height.saturating_sub(2)));
+    frame.render_widget(
+        Paragraph::new(history[shown..].join("\n")).block(Block::bordered().title("Moves")),
+        moves,
+    );
+
+    let (ratio, label) = app
+        .info
+        .as_ref()
+        .map_or((0.5, "?".to_string()), |info| eval_bar(info.value));
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title("Evaluation (X)"))
+            .gauge_style(Style::new().fg(Color::Blue).bg(Color::Yellow))
+            .ratio(ratio.clamp(0.0, 1.0))
+            .label(label),
+        eval,
+    );
+
+    let search = app.info.as_ref().map_or(String::new(), |info| {
+        format!(
+            "depth {}  best {}  value {:+}  nodes {}  {:.2}s",
+            info.depth,
+            info.best,
+            info.value,
+            info.nodes,
+            info.elapsed.as_secs_f64()
+        )
+    });
+    frame.render_widget(
+        Paragraph::new(search).block(Block::bordered().title("Engine")),
+        engine,
+    );
+
+    frame.render_widget(
+        Paragraph::new(format!("> {}\n{}", app.input, app.message))
+            .wrap(Wrap { trim: true })
+            .block(Block::bordered().title("Your move (Esc quits)")),
+        input,
+    );
+}
+
+/// Runs the `tui` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let opts = parse_options(args)?;
+    let mut app = match &opts.resume {
+        Some(path) => {
+            let saved = SavedGame::load(path)?;
+            let human = if saved.human_is_player1 {
+                Player::Player1
+            } else {
+                Player::Player2
+            };
+            let rng = saved.rng.map(SplitMix64::new);
+            App::resume(saved.entry()?, saved.depth, human, rng, saved.position()?)
+        }
+        None => {
+            let registry = Registry::builtin();
+            let entry = *registry.get(&opts.game).ok_or_else(|| {
+                format!(
+                    "unknown game '{}' (known: {})",
+                    opts.game,
+                    registry.names().join(", ")
+                )
+            })?;
+            let depth = opts.depth.unwrap_or(entry.default_depth);
+            let seed = match opts.seed {
+                Some(seed) => Some(seed),
+                None => crate::global_seed()?,
+            };
+            let rng = seed.map(|seed| SplitMix64::for_stream(seed, crate::TIE_BREAK_STREAM));
+            App::new(entry, depth, opts.human, rng)
+        }
+    };
+
+    let mut terminal = ratatui::init();
+    let result = (|| -> io::Result<()> {
+        while !app.quit {
+            terminal.draw(|frame| draw(frame, &app))?;
+            if event::poll(Duration::from_millis(50))?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                app.handle_key(key);
+            }
+            app.poll_engine();
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+    if let Some(thinking) = &app.thinking {
+        thinking.cancel.cancel();
+    }
+    result.map_err(|e| format!("terminal error: {e}"))
+}
+
+/// Draws a replay: the board, the moves with the current one marked, and
+/// the engine's evaluation if it is on.
+fn draw_replay(frame: &mut Frame, replay: &Replay, evaluation: Option<&str>) {
+    let [top, engine, help] = Layout::vertical([
+        Constraint::Min(8),
+        Constraint::Length(3),
+        Constraint::Length(3),
+    ])
+    .areas(frame.area());
+    let [board, moves] =
+        Layout::horizontal([Constraint::Min(30), Constraint::Length(24)]).areas(top);
+
+    let state = replay.current();
+    frame.render_widget(
+        Paragraph::new(state.render_layout(BoardStyle::Unicode, &replay.layout)).block(
+            Block::bordered()
+                .title(replay.entry.display_name)
+                .title_bottom(replay.caption()),
+        ),
+        board,
+    );
+
+    let last = replay.positions[replay.len()].history();
+    let lines: Vec<Line> = last
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            let mut spans = vec![Span::raw(format!("{:>3}.", i + 1))];
+            for (j, mv) in pair.iter().enumerate() {
+                let style = if 2 * i + j + 1 == replay.ply {
+                    Style::new().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::new()
+                };
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled(format!("{:>2}", mv.to_string()), style));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    // Keep the current move in view.
+    let height = usize::from(moves.height.saturating_sub(2)).max(1);
+    let shown = (replay.ply.saturating_sub(1) / 2).saturating_sub(height - 1);
+    frame.render_widget(
+        Paragraph::new(lines[shown.min(lines.len())..].to_vec())
+            .block(Block::bordered().title("Moves")),
+        moves,
+    );
+
+    frame.render_widget(
+        Paragraph::new(evaluation.unwrap_or("off (press e)"))
+            .block(Block::bordered().title("Engine")),
+        engine,
+    );
+    frame.render_widget(
+        Paragraph::new("←/→ step  Home/End first/last  e evaluation  Esc quit")
+            .block(Block::bordered()),
+        help,
+    );
+}
+
+/// Steps through `replay` in the full-screen view.
+pub fn replay(mut replay: Replay) -> Result<(), String> {
+    let mut terminal = ratatui::init();
+    let result = (|| -> io::Result<()> {
+        loop {
+            let evaluation = replay.show_eval.then(|| replay.evaluation().to_string());
+            terminal.draw(|frame| draw_replay(frame, &replay, evaluation.as_deref()))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(());
+                }
+                KeyCode::Left | KeyCode::Up => replay.step(-1),
+                KeyCode::Right | KeyCode::Down | KeyCode::Enter => replay.step(1),
+                KeyCode::Home => replay.go_to(0),
+                KeyCode::End => replay.go_to(replay.len()),
+                KeyCode::Char('e') => replay.show_eval = !replay.show_eval,
+                _ => {}
+            }
+        }
+    })();
+    ratatui::restore();
+    result.map_err(|e| format!("terminal error: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::args::split_args;
+
+    fn app(game: &str, human: Player) -> App {
+        let entry = *Registry::builtin().get(game).unwrap();
+        App::new(entry, 2, human, None)
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn type_line(app: &mut App, text: &str) {
+        for c in text.chars() {
+            app.handle_key(key(KeyCode::Char(c)));
+        }
+        app.handle_key(key(KeyCode::Enter));
+    }
+
+    /// Polls the engine until it has moved.
+    fn wait_for_engine(app: &mut App) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while app.thinking.is_some() {
+            assert!(Instant::now() < deadline, "the engine did not move");
+            thread::sleep(Duration::from_millis(5));
+            app.poll_engine();
+        }
+    }
+
+    fn screen(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(60, 24)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn parses_options() {
+        let opts = parse_options(&split_args(
+            "--game ttt --depth 4 --second --seed 7 --resume a.save",
+        ))
+        .unwrap();
+        assert_eq!(opts.game, "ttt");
+        assert_eq!(opts.seed, Some(7));
+        assert_eq!(opts.resume, Some(PathBuf::from("a.save")));
+        assert_eq!(opts.depth, Some(4));
+        assert_eq!(opts.human, Player::Player2);
+        assert!(parse_options(&split_args("--depth 0")).is_err());
+        assert!(parse_options(&split_args("--fast")).is_err());
+    }
+
+    #[test]
+    fn moves_are_typed_and_answered() {
+        let mut app = app("c4", Player::Player1);
+        assert!(app.thinking.is_none());
+        type_line(&mut app, "9");
+        assert!(app.message.contains("'9'"));
+        assert_eq!(app.state().notation(), "");
+
+        type_line(&mut app, "3");
+        assert!(app.thinking.is_some());
+        wait_for_engine(&mut app);
+        assert_eq!(app.state().history().len(), 2);
+        let info = app.info.as_ref().unwrap();
+        assert_eq!(info.depth, 2);
+        assert!(app.message.starts_with("The engine played"));
+
+        let screen = screen(&app);
+        assert!(screen.contains("Connect Four"));
+        assert!(screen.contains("1. 3  "));
+        assert!(screen.contains("depth 2  best"));
+        assert!(screen.contains("X to move (you)"));
+
+        app.handle_key(key(KeyCode::Esc));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn engine_opens_when_human_is_second_and_games_restart() {
+        let mut app = app("ttt", Player::Player2);
+        wait_for_engine(&mut app);
+        assert_eq!(app.state().history().len(), 1);
+        while !app.state().is_terminal() {
+            let mv = app.state().legal_moves()[0].clone();
+            type_line(&mut app, &mv);
+            wait_for_engine(&mut app);
+        }
+        assert!(app.message.ends_with("Press Enter for a new game."));
+        app.handle_key(key(KeyCode::Enter));
+        wait_for_engine(&mut app);
+        assert_eq!(app.state().history().len(), 1);
+    }
+
+    #[test]
+    fn arrow_keys_pick_highlighted_moves() {
+        let mut app = app("ttt", Player::Player1);
+        type_line(&mut app, "4");
+        wait_for_engine(&mut app);
+        let reply = app.state().history()[1].to_string();
+
+        // The cursor stays on the cell just played, which is now taken.
+        assert_eq!(app.selection.cursor(), (1, 1));
+        app.handle_key(key(KeyCode::Enter));
+        assert!(app.message.starts_with("No legal move there"));
+        assert_eq!(app.state().history().len(), 2);
+
+        app.handle_key(key(KeyCode::Up));
+        app.handle_key(key(KeyCode::Left));
+        let cell = app.selection.cursor();
+        let legal = app.selection.selected().map(ToString::to_string);
+        assert_eq!(legal.is_none(), reply == "0");
+        match &legal {
+            Some(mv) => assert_eq!(app.message, format!("Enter plays {mv}.")),
+            None => assert_eq!(app.message, "No legal move there."),
+        }
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 24)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        // The board starts inside the border; cells are 4 columns apart and
+        // rows 2 lines apart.
+        let at = |(row, col): (usize, usize)| &buffer[(1 + 4 * col as u16, 1 + 2 * row as u16)];
+        let expected = if legal.is_some() {
+            Color::Green
+        } else {
+            Color::Red
+        };
+        assert_eq!(at(cell).bg, expected);
+        let other = (0..3)
+            .flat_map(|row| (0..3).map(move |col| (row, col)))
+            .find(|&c| c != cell && app.selection.is_target(c.0, c.1))
+            .unwrap();
+        assert_eq!(at(other).fg, Color::Green);
+        assert_eq!(at((1, 1)).fg, Color::Reset);
+
+        if let Some(mv) = legal {
+            app.handle_key(key(KeyCode::Enter));
+            assert_eq!(app.state().history()[2].to_string(), mv);
+            assert!(app.thinking.is_some());
+            app.handle_key(key(KeyCode::Right));
+            assert_eq!(app.selection.cursor(), cell);
+        }
+    }
+
+    #[test]
+    fn replays_mark_the_current_move() {
+        let entry = *Registry::builtin().get("ttt").unwrap();
+        let mut replay = Replay::new(entry, "4 0 8", 4).unwrap();
+        replay.go_to(2);
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal
+            .draw(|frame| draw_replay(frame, &replay, Some("best 8, +0 (depth 4)")))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("Move 2/3, after 0: X to move"));
+        assert!(text.contains("1.  4  0"));
+        assert!(text.contains("best 8, +0 (depth 4)"));
+        let reversed: String = buffer
+            .content()
+            .iter()
+            .filter(|cell| cell.modifier.contains(Modifier::REVERSED))
+            .map(|cell| cell.symbol())
+            .collect();
+        assert_eq!(reversed, " 0");
+    }
+
+    #[test]
+    fn games_are_saved_and_resumed() {
+        let path = std::env::temp_dir().join(format!("tui-{}.save", std::process::id()));
+        let mut app = app("c4", Player::Player1);
+        type_line(&mut app, "3");
+        wait_for_engine(&mut app);
+        type_line(&mut app, &format!("save {}", path.display()));
+        assert!(app.message.starts_with("Game saved to"), "{}", app.message);
+        assert_eq!(app.state().history().len(), 2);
+
+        let saved = SavedGame::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(saved.opts.game.as_deref(), Some("c4"));
+        assert_eq!(saved.human_moves, 1);
+        let resumed = App::resume(
+            saved.entry().unwrap(),
+            2,
+            Player::Player1,
+            None,
+            saved.position().unwrap(),
+        );
+        assert_eq!(resumed.state().notation(), app.state().notation());
+        assert!(resumed.thinking.is_none());
+    }
+
+    #[test]
+    fn seeds_pick_between_equal_moves() {
+        let entry = *Registry::builtin().get("ttt").unwrap();
+        let opening = |seed| {
+            let mut app = App::new(entry, 9, Player::Player2, Some(SplitMix64::new(seed)));
+            wait_for_engine(&mut app);
+            app.state().notation()
+        };
+        // Every opening move draws, so the seed decides.
+        let openings: Vec<String> = (0..8).map(opening).collect();
+        assert_eq!(openings[3], opening(3));
+        assert!(openings.iter().any(|mv| *mv != openings[0]));
+    }
+
+    #[test]
+    fn eval_bar_squashes_values_and_fills_for_wins() {
+        assert_eq!(eval_bar(0), (0.5, "+0".to_string()));
+        assert_eq!(eval_bar(100).0, 0.75);
+        assert_eq!(eval_bar(-WIN_SCORE), (0.0, "O wins".to_string()));
+    }
+}
@@ -0,0 +1,48 @@
+//! `tune`: automated tuning of the Connect Four evaluation weights.
+
+use solver_core::games::c4_bitboard::C4EvalParams;
+use solver_core::tuning::{TuningConfig, openings, tune};
+
+use crate::args::parse_number;
+
+pub const USAGE: &str =
+    "usage: solver_cli tune [--depth N] [--rounds N] [--step-percent N] [--opening-plies N]";
+
+/// Runs the `tune` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut config = TuningConfig::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--depth" => config.depth = parse_number(arg, iter.next())?,
+            "--rounds" => config.rounds = parse_number(arg, iter.next())?,
+            "--step-percent" => config.step_percent = parse_number(arg, iter.next())?,
+            "--opening-plies" => config.openings = openings(parse_number(arg, iter.next())?),
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    if config.depth == 0 {
+        return Err("depth must be at least 1".to_string());
+    }
+
+    let start = C4EvalParams::default();
+    println!(
+        "Tuning from {start:?}\n{} rounds, depth {}, {} games per trial",
+        config.rounds,
+        config.depth,
+        config.openings.len() * 2
+    );
+    let best = tune(start, &config, |step| {
+        println!(
+            "round {}: {} -> W/D/L {}/{}/{} ({:.1}%)",
+            step.round,
+            step.param,
+            step.score.wins,
+            step.score.draws,
+            step.score.losses,
+            step.score.score() * 100.0
+        );
+    });
+    println!("Best parameters: {best:?}");
+    Ok(())
+}
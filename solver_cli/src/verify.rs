@@ -0,0 +1,38 @@
+//! `verify`: exhaustive self-checks of the solvers.
+//!
+//! `verify ttt` enumerates and solves every reachable Tic-Tac-Toe position
+//! and checks the invariants listed on `solver_core::verify::verify_ttt`.
+
+use solver_core::verify::{TTT_CLASSES, TTT_POSITIONS, verify_ttt};
+
+pub const USAGE: &str = "usage: solver_cli verify ttt";
+
+fn ttt() -> Result<(), String> {
+    let report = verify_ttt();
+    println!(
+        "Positions: {} (expected {TTT_POSITIONS}), {} up to symmetry (expected {TTT_CLASSES})",
+        report.positions, report.classes
+    );
+    let [x, o, draws] = report.finished;
+    println!("Finished: {x} won by X, {o} won by O, {draws} drawn");
+    println!("Empty board value: {}", report.root_value);
+    for violation in &report.violations {
+        println!("FAIL: {violation}");
+    }
+    if report.is_ok() {
+        println!("All invariants hold");
+        Ok(())
+    } else {
+        Err(format!("{} invariant(s) broken", report.violations.len()))
+    }
+}
+
+/// Runs the `verify` subcommand with the arguments after its name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args {
+        [game] if game == "ttt" => ttt(),
+        [other] => Err(format!("unknown verify target '{other}'")),
+        [] => Err("missing verify target".to_string()),
+        _ => Err(format!("unexpected argument '{}'", args[1])),
+    }
+}
@@ -0,0 +1,423 @@
+//! End-to-end tests: the compiled `solver_cli` run with scripted input.
+//!
+//! Every test runs in a fresh directory that also holds the profiles and
+//! saves (`SOLVER_PROFILE_DIR`), so nothing leaks between tests or into
+//! the user's files.
+
+use std::fs;
+use std::path::Path;
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use predicates::str::contains;
+use tempfile::TempDir;
+
+/// `solver_cli` in `dir`, with no seed or language from the environment.
+fn cli(dir: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("solver_cli").unwrap();
+    cmd.current_dir(dir)
+        .env("SOLVER_PROFILE_DIR", dir)
+        .env_remove("SOLVER_SEED")
+        .env_remove("SOLVER_LANG")
+        .env_remove("LC_ALL")
+        .env_remove("LC_MESSAGES")
+        .env("LANG", "C");
+    cmd
+}
+
+fn temp_dir() -> TempDir {
+    tempfile::tempdir().unwrap()
+}
+
+#[test]
+fn plays_a_full_tic_tac_toe_game() {
+    let dir = temp_dir();
+    // 2 is taken by the time it is played, so it is rejected and 3 follows.
+    cli(dir.path())
+        .args(["play", "--moves", "4,8,2,3,5", "--autosave", "0"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "Enter your move (0-8, or undo, hint, save [FILE]): 4\n",
+        ))
+        .stdout(contains(
+            "Invalid move: illegal move: the cell is not empty",
+        ))
+        .stdout(contains("Game record: 4 0 8 2 3 1"))
+        .stdout(contains("Player2 (O) wins!"))
+        .stdout(contains("Annotated record: 4 0 8 2 3?? 1!!"))
+        .stdout(contains("Session: 0 won, 0 drawn, 1 lost over 1 game(s)"))
+        .stdout(contains("Play again? [y/N] \n"));
+    let profile = if cfg!(feature = "json") {
+        "player.json"
+    } else {
+        "player.profile"
+    };
+    assert!(dir.path().join(profile).is_file());
+}
+
+#[test]
+fn plays_a_full_connect_four_game() {
+    let dir = temp_dir();
+    // The human fills the columns from the left, asking for a hint and
+    // taking the first move back on the way; moves into full columns are
+    // rejected and the next one follows.
+    let filler: Vec<String> = (0..7)
+        .flat_map(|col| std::iter::repeat_n(col.to_string(), 7))
+        .collect();
+    let moves = format!("hint,0,undo,{}", filler.join(","));
+    cli(dir.path())
+        .args(["play", "--game", "c4", "--autosave", "0", "--moves", &moves])
+        .assert()
+        .success()
+        .stdout(contains("Welcome to Connect Four!"))
+        .stdout(contains("Hint: play 3 (value = "))
+        .stdout(contains("Took back your last move and the AI's reply."))
+        .stdout(contains("AI plays 3 (value = "))
+        .stdout(contains("Game over!\nPlayer2 (O) wins!"))
+        .stdout(contains("Game record: 0 3 0 4 0 0 0 2 0 1"))
+        .stdout(contains("Session: 0 won, 0 drawn, 1 lost over 1 game(s)"));
+}
+
+#[test]
+fn hints_and_takes_back_moves() {
+    let dir = temp_dir();
+    cli(dir.path())
+        .args(["play", "--moves", "undo,4,hint,undo,4", "--autosave", "0"])
+        .assert()
+        .success()
+        .stdout(contains("You have no move to take back yet."))
+        .stdout(contains("Hint: play 2 (value = 0)."))
+        .stdout(contains("Took back your last move and the AI's reply."))
+        .stdout(contains("End of input"))
+        .stdout(contains("Game record: 4 0"));
+}
+
+#[test]
+fn a_broken_profile_is_left_as_it_was() {
+    let dir = temp_dir();
+    let profile = dir.path().join(if cfg!(feature = "json") {
+        "player.json"
+    } else {
+        "player.profile"
+    });
+    fs::write(&profile, "not a profile\n").unwrap();
+    cli(dir.path())
+        .args(["play", "--moves", "4,8,2,3,5", "--autosave", "0"])
+        .assert()
+        .success()
+        .stderr(contains("not updating the profile"));
+    assert_eq!(fs::read_to_string(&profile).unwrap(), "not a profile\n");
+}
+
+#[test]
+fn plays_again_and_switches_sides() {
+    let dir = temp_dir();
+    cli(dir.path())
+        .args(["--moves", "4,8,3,5,1,2,6", "--autosave", "0"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(contains("Session: 0 won, 0 drawn, 1 lost over 1 game(s)"))
+        .stdout(contains("Your turn (Player2)."))
+        .stdout(contains("AI (Player1) is thinking..."));
+}
+
+#[test]
+fn end_of_input_abandons_the_game_and_keeps_the_autosave() {
+    let dir = temp_dir();
+    cli(dir.path())
+        .args(["play", "--moves", "4"])
+        .write_stdin("\n\n")
+        .assert()
+        .success()
+        .stdout(contains("End of input; abandoning the game."))
+        .stdout(contains("Game record: 4 0"))
+        .stdout(contains("Game over!").not());
+    assert!(dir.path().join("player.autosave").is_file());
+
+    // The next session offers to recover it and carries on.
+    cli(dir.path())
+        .args(["play", "--moves", "8"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(contains(
+            "An unfinished game was autosaved. Recover it? [y/N]",
+        ))
+        .stdout(contains("Game record: 4 0 8 2"));
+}
+
+#[test]
+fn saves_and_resumes_a_game() {
+    let dir = temp_dir();
+    cli(dir.path())
+        .args(["play", "--moves", "4,save game.save"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "Game saved to game.save; resume it with: solver_cli resume game.save",
+        ));
+    let saved = fs::read_to_string(dir.path().join("game.save")).unwrap();
+    assert!(saved.contains("moves = 4 0"), "{saved}");
+
+    cli(dir.path())
+        .args(["resume", "game.save"])
+        .write_stdin("8\n")
+        .assert()
+        .success()
+        .stdout(contains("Game record: 4 0 8 2"));
+}
+
+#[test]
+fn saves_and_resumes_other_games() {
+    let dir = temp_dir();
+    cli(dir.path())
+        .args(["play", "--game", "c4", "--moves", "3,save game.save"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "Enter your move (0 1 2 3 4 5 6, or undo, hint, save [FILE]): 3\n",
+        ))
+        .stdout(contains("Game saved to game.save"));
+    let saved = fs::read_to_string(dir.path().join("game.save")).unwrap();
+    assert!(saved.contains("options = --game c4"), "{saved}");
+    assert!(saved.contains("moves = 3 "), "{saved}");
+
+    cli(dir.path())
+        .args(["resume", "game.save"])
+        .assert()
+        .success()
+        .stdout(contains("Resuming the game saved in game.save."))
+        .stdout(contains("Welcome to Connect Four!"))
+        .stdout(contains("Game record: 3 "));
+
+    // Unfinished games are autosaved and recovered whatever the game.
+    cli(dir.path())
+        .args(["play", "--game", "c4", "--moves", "3", "--autosave", "1"])
+        .assert()
+        .success()
+        .stdout(contains("End of input"));
+    assert!(dir.path().join("player.autosave").is_file());
+    cli(dir.path())
+        .args(["play", "--moves", "3"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(contains(
+            "An unfinished game was autosaved. Recover it? [y/N]",
+        ))
+        .stdout(contains("Welcome to Connect Four!"))
+        .stdout(contains(
+            "Enter your move (0 1 2 3 4 5 6, or undo, hint, save [FILE]): 3\n",
+        ));
+}
+
+#[test]
+fn rejects_invalid_input() {
+    let dir = temp_dir();
+    cli(dir.path())
+        .arg("frobnicate")
+        .assert()
+        .code(2)
+        .stderr(contains("error: unknown command 'frobnicate'"))
+        .stderr(contains("usage: solver_cli"));
+    cli(dir.path())
+        .args(["play", "--seed", "x"])
+        .assert()
+        .code(2)
+        .stderr(contains("invalid seed 'x'"));
+    cli(dir.path())
+        .args(["play", "--moves", "4,,8"])
+        .assert()
+        .code(2)
+        .stderr(contains("invalid moves '4,,8'"));
+    cli(dir.path())
+        .args(["play", "--game", "chess"])
+        .assert()
+        .code(2)
+        .stderr(contains("unknown game 'chess' (expected one of: ttt, c4,"));
+    cli(dir.path())
+        .args(["play", "--game", "c4", "--teach", "forced"])
+        .assert()
+        .code(2)
+        .stderr(contains("--teach is only available for Tic-Tac-Toe"));
+    cli(dir.path())
+        .arg("play")
+        .env("SOLVER_SEED", "soon")
+        .assert()
+        .code(2)
+        .stderr(contains("invalid SOLVER_SEED 'soon'"));
+    cli(dir.path())
+        .args(["--moves", "9,x,4", "--autosave", "0"])
+        .assert()
+        .success()
+        .stdout(contains("Invalid move: "))
+        .stdout(contains("Invalid move: 'x' is not a number"))
+        .stdout(contains("Game record: 4 0"));
+    cli(dir.path())
+        .args(["solve", "--game", "ttt", "--position", "XO"])
+        .assert()
+        .code(2)
+        .stderr(contains("expected 9 cells, got 2"))
+        .stderr(contains("usage: solver_cli solve"));
+}
+
+#[test]
+fn plays_any_registered_game() {
+    let dir = temp_dir();
+    cli(dir.path())
+        .args(["play", "--game", "pentago", "--first", "ai"])
+        .assert()
+        .success()
+        .stdout(contains("Welcome to Pentago!"))
+        .stdout(contains("You are O (Player2)."))
+        .stdout(contains("AI plays "))
+        .stdout(contains("Enter your move ("))
+        .stdout(contains("End of input; abandoning the game."));
+}
+
+#[test]
+fn steps_through_a_connect_four_game() {
+    let dir = temp_dir();
+    fs::write(dir.path().join("game.rec"), "game: c4\n3 3 4 4 5 5 6\n").unwrap();
+    // Forward, back, to the end, an unknown command, then end of input.
+    cli(dir.path())
+        .args(["replay", "game.rec", "--eval", "--depth", "4"])
+        .write_stdin("n\np\nl\nbogus\n")
+        .assert()
+        .success()
+        .stdout(contains("Connect Four, 7 moves."))
+        .stdout(contains("Move 1/7, after 3: O to move"))
+        .stdout(contains("Move 0/7: X to move"))
+        .stdout(contains("Move 7/7, after 6: X wins"))
+        .stdout(contains("Engine: the game is over"))
+        .stdout(contains("commands: n (next), p (previous)"))
+        .stdout(predicate::str::ends_with("> \n"));
+
+    // Column numbers under the board, upside down, and a description.
+    cli(dir.path())
+        .args([
+            "replay",
+            "game.rec",
+            "--coords",
+            "numbers",
+            "--flip",
+            "--describe",
+        ])
+        .write_stdin("l\n")
+        .assert()
+        .success()
+        .stdout(contains("Move 7/7, after 6: X wins"))
+        .stdout(contains(
+            "\n. | . | . | X | X | X | X\n---+---+---+---+---+---+---\n. | . | . | O | O | O | .\n",
+        ))
+        .stdout(contains("0   1   2   3   4   5   6\n"))
+        .stdout(contains(
+            "Column 6: ● from the bottom.\nColumns 0, 1 and 2 are empty.\n● (player 1) has won.\n",
+        ));
+}
+
+#[test]
+fn plays_connect_four_matches() {
+    let dir = temp_dir();
+    cli(dir.path())
+        .args([
+            "match", "--game", "c4", "--a", "ab:3", "--b", "random:1", "--games", "2", "--quiet",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("A: +2 =0 -0 (100.0%)"));
+}
+
+#[test]
+fn solves_positions_without_interaction() {
+    let dir = temp_dir();
+    cli(dir.path())
+        .args([
+            "solve",
+            "--game",
+            "ttt",
+            "--position",
+            ".........",
+            "--no-book",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Draw after 9 plies with best move 4"));
+    cli(dir.path())
+        .args([
+            "solve-exact",
+            "--game",
+            "c4",
+            "--position",
+            "332211",
+            "--no-book",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Player1 wins in 1 ply with best move 0"));
+    cli(dir.path())
+        .args(["verify", "ttt"])
+        .assert()
+        .success()
+        .stdout(contains("All invariants hold"));
+    cli(dir.path())
+        .arg("games")
+        .assert()
+        .success()
+        .stdout(contains("c4            Connect Four (default depth 8)"));
+    cli(dir.path())
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(contains("complete -F _solver_cli solver_cli"));
+}
+
+#[test]
+fn plays_in_spanish() {
+    let dir = temp_dir();
+    cli(dir.path())
+        .args([
+            "play",
+            "--moves",
+            "4,8,2,3,5",
+            "--autosave",
+            "0",
+            "--describe",
+        ])
+        .env("SOLVER_LANG", "es")
+        .assert()
+        .success()
+        .stdout(contains("¡Bienvenido al tres en raya!"))
+        .stdout(contains(
+            "Escribe tu jugada (0-8, o undo, hint, save [ARCHIVO]): 4\n",
+        ))
+        .stdout(contains("La IA elige la casilla 0 (valor = 0)."))
+        .stdout(contains("Fila 1: vacía, ✕, vacía.\n"))
+        .stdout(contains("¡Gana el Jugador 2 (O)!"))
+        .stdout(contains("Registro comentado: 4 0 8 2 3?? 1!!"))
+        .stdout(contains("¿Otra partida? [s/N] \n"));
+    cli(dir.path())
+        .arg("play")
+        .env("SOLVER_LANG", "klingon")
+        .assert()
+        .code(2)
+        .stderr(contains("invalid SOLVER_LANG 'klingon'"));
+}
+
+#[test]
+fn a_broken_theme_file_still_gives_plain_piped_output() {
+    let dir = temp_dir();
+    let theme = dir.path().join("broken.conf");
+    fs::write(&theme, "tint = blue\n").unwrap();
+    cli(dir.path())
+        .args(["play", "--moves", "4"])
+        .env("SOLVER_THEME", &theme)
+        .assert()
+        .success()
+        .stderr(contains("warning: "))
+        .stderr(contains("using the default theme"))
+        .stdout(contains("\x1b").not())
+        .stdout(contains(". | X | ."));
+}
@@ -0,0 +1,136 @@
+//! Connect Four bitboard micro-benchmarks.
+//!
+//! Run with `cargo bench -p solver_core --bench bitboard`.
+//!
+//! Compares `BitboardState`, which derives drop positions from `mask_bb`,
+//! against a copy of the previous layout that carried a per-column
+//! `heights` array, on the operations that dominate deep searches:
+//! applying a move (which clones the state) and walking the move tree.
+//! Also compares search with full and incremental leaf evaluation.
+
+use std::hint::black_box;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+use solver_core::game::Player;
+use solver_core::games::c4_bitboard::{BitboardState, IncrementalBitboard};
+use solver_core::solvers::minimax::minimax_value_ab_depth;
+use solver_core::utils::opposite_player;
+
+/// The previous layout: bitboards plus an explicit height per column.
+#[derive(Clone)]
+struct WithHeights {
+    player_bb: u64,
+    mask_bb: u64,
+    heights: [u8; 7],
+    current_player: Player,
+}
+
+impl WithHeights {
+    fn new() -> Self {
+        Self {
+            player_bb: 0,
+            mask_bb: 0,
+            heights: [0; 7],
+            current_player: Player::Player1,
+        }
+    }
+
+    fn can_play(&self, col: u8) -> bool {
+        self.heights[col as usize] < 6
+    }
+
+    fn apply_column_move(&self, col: u8) -> Self {
+        let bit = 1u64 << (col * 7 + self.heights[col as usize]);
+        let mut heights = self.heights;
+        heights[col as usize] += 1;
+        Self {
+            player_bb: match self.current_player {
+                Player::Player1 => self.player_bb | bit,
+                Player::Player2 => self.player_bb,
+            },
+            mask_bb: self.mask_bb | bit,
+            heights,
+            current_player: opposite_player(self.current_player),
+        }
+    }
+}
+
+fn perft_bitboard(state: &BitboardState, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    (0..7)
+        .filter(|&c| state.can_play(c))
+        .map(|c| perft_bitboard(&state.apply_column_move(c), depth - 1))
+        .sum()
+}
+
+fn perft_with_heights(state: &WithHeights, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    (0..7)
+        .filter(|&c| state.can_play(c))
+        .map(|c| perft_with_heights(&state.apply_column_move(c), depth - 1))
+        .sum()
+}
+
+/// Runs `f` `iters` times per sample and prints the fastest sample.
+fn bench(name: &str, iters: u32, mut f: impl FnMut()) {
+    let mut best = Duration::MAX;
+    for _ in 0..5 {
+        let start = Instant::now();
+        for _ in 0..iters {
+            f();
+        }
+        best = best.min(start.elapsed());
+    }
+    println!(
+        "{name:<32} {:>10.1} ns/iter",
+        best.as_nanos() as f64 / iters as f64
+    );
+}
+
+fn main() {
+    println!(
+        "size_of: BitboardState = {} bytes, with heights = {} bytes\n",
+        size_of::<BitboardState>(),
+        size_of::<WithHeights>()
+    );
+
+    let state = BitboardState::from_move_sequence("332344").unwrap();
+    let legacy = "332344"
+        .bytes()
+        .fold(WithHeights::new(), |s, c| s.apply_column_move(c - b'0'));
+
+    bench("apply_column_move (mask)", 1_000_000, || {
+        black_box(black_box(&state).apply_column_move(black_box(2)));
+    });
+    bench("apply_column_move (heights)", 1_000_000, || {
+        black_box(black_box(&legacy).apply_column_move(black_box(2)));
+    });
+    bench("perft 6 (mask)", 5, || {
+        black_box(perft_bitboard(black_box(&state), 6));
+    });
+    bench("perft 6 (heights)", 5, || {
+        black_box(perft_with_heights(black_box(&legacy), 6));
+    });
+    let incremental = IncrementalBitboard::from(state.clone());
+    bench("alpha-beta depth 7 (incremental)", 3, || {
+        black_box(minimax_value_ab_depth(
+            black_box(&incremental),
+            7,
+            i32::MIN,
+            i32::MAX,
+        ));
+    });
+    bench("alpha-beta depth 7", 3, || {
+        black_box(minimax_value_ab_depth(
+            black_box(&state),
+            7,
+            i32::MIN,
+            i32::MAX,
+        ));
+    });
+}
@@ -0,0 +1,26 @@
+//! `ConnectFourState::from_str` and `BitboardState::from_move_sequence`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solver_core::game::{GameState, Player};
+use solver_core::games::c4::ConnectFourState;
+use solver_core::games::c4_bitboard::BitboardState;
+
+fuzz_target!(|input: &str| {
+    if let Ok(state) = ConnectFourState::from_str(input, Player::Player2) {
+        assert_eq!(state.to_repr(), input);
+        let _ = state.terminal_value();
+        for mv in state.legal_moves() {
+            let _ = state.apply_move(&mv);
+        }
+    }
+    if let Ok(state) = BitboardState::from_move_sequence(input) {
+        let discs: usize = state.heights().iter().map(|&h| usize::from(h)).sum();
+        assert_eq!(discs, input.len());
+        let _ = state.terminal_value();
+        for mv in state.legal_moves() {
+            let _ = state.apply_move(&mv);
+        }
+    }
+});
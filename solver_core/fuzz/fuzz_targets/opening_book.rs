@@ -0,0 +1,23 @@
+//! `OpeningBook::read_from` on arbitrary bytes.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solver_core::book::OpeningBook;
+use solver_core::games::c4_bitboard::BitboardState;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(book) = OpeningBook::read_from(data) else {
+        return;
+    };
+    // Look up both members of some mirror-image pairs.
+    for seq in ["", "0", "6", "01", "65", "33"] {
+        let state = BitboardState::from_move_sequence(seq).unwrap();
+        if let Some(entry) = book.lookup(&state) {
+            assert!(entry.best_move < 7);
+        }
+    }
+    let mut bytes = Vec::new();
+    book.write_to(&mut bytes).unwrap();
+    assert_eq!(OpeningBook::read_from(&bytes[..]), Ok(book));
+});
@@ -0,0 +1,28 @@
+//! JSON deserialization of game states (the `serde` feature).
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solver_core::game::GameState;
+use solver_core::games::c4::ConnectFourState;
+use solver_core::games::c4_bitboard::BitboardState;
+use solver_core::games::ttt::TicTacToeState;
+
+fn exercise<G: GameState>(state: G) {
+    let _ = state.terminal_value();
+    for mv in state.legal_moves() {
+        let _ = state.apply_move(&mv);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(state) = serde_json::from_slice::<TicTacToeState>(data) {
+        exercise(state);
+    }
+    if let Ok(state) = serde_json::from_slice::<ConnectFourState>(data) {
+        exercise(state);
+    }
+    if let Ok(state) = serde_json::from_slice::<BitboardState>(data) {
+        exercise(state);
+    }
+});
@@ -0,0 +1,25 @@
+//! `parse_ttt_move`, `TicTacToeState::from_str` and `from_board_str`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solver_core::game::{GameState, Player};
+use solver_core::games::ttt::{TicTacToeState, parse_ttt_move};
+
+fuzz_target!(|input: &str| {
+    let empty = TicTacToeState::new();
+    if let Ok(mv) = parse_ttt_move(input, &empty) {
+        assert!(empty.legal_moves().contains(&mv));
+    }
+    if let Ok(state) = TicTacToeState::from_str(input, Player::Player1) {
+        assert_eq!(state.to_repr(), input);
+        let _ = state.terminal_value();
+        for mv in state.legal_moves() {
+            assert_eq!(parse_ttt_move(&mv.index.to_string(), &state), Ok(mv));
+        }
+    }
+    if let Ok(state) = TicTacToeState::from_board_str(input) {
+        let _ = state.legal_moves();
+        let _ = state.heuristic_value();
+    }
+});
@@ -0,0 +1,214 @@
+use alloc::vec::Vec;
+
+use crate::agent::{Agent, Decision};
+use crate::game::{GameState, Player};
+use crate::rng::{Rng, SplitMix64};
+use crate::solvers::minimax::{minimax_best_move_ab_depth, minimax_value_ab_depth_root};
+
+/// Most often the weakest agent (level 0) plays a random move instead of
+/// its search's choice.
+pub const MAX_BLUNDER_RATE: f64 = 0.3;
+/// How much the level moves after each of the agent's moves, against the
+/// trend of its evaluation.
+pub const MOVE_STEP: f64 = 0.02;
+/// How much the level moves after each game, against its result.
+pub const GAME_STEP: f64 = 0.15;
+
+/// An agent whose strength adapts to its opponent, aiming to win about
+/// half of its games.
+///
+/// Its strength is a level from 0 (weakest) to 1 (strongest), which sets
+/// both its search depth (1 to `max_depth` plies) and how often it
+/// blunders: with probability `(1 - level) * MAX_BLUNDER_RATE` it plays a
+/// random other move instead of its search's choice.
+///
+/// The level eases off by `MOVE_STEP` after each move that improved the
+/// agent's evaluation, and tightens by as much after each move that
+/// worsened it. Between games, `record_game` lowers it by `GAME_STEP` after
+/// a win and raises it after a loss. `level_for_record` gives a starting
+/// level from the opponent's past results.
+#[derive(Clone, Debug)]
+pub struct AdaptiveAgent<R = SplitMix64> {
+    level: f64,
+    max_depth: u32,
+    rng: R,
+    /// The agent's last evaluation, from its own perspective.
+    last_value: Option<i32>,
+}
+
+impl AdaptiveAgent {
+    /// An agent of middling level searching at most `max_depth` plies,
+    /// whose blunders are determined by `seed`.
+    pub fn new(max_depth: u32, seed: u64) -> Self {
+        Self::with_rng(max_depth, SplitMix64::new(seed))
+    }
+}
+
+impl<R> AdaptiveAgent<R> {
+    /// Like `new`, drawing the blunders from `rng`.
+    pub fn with_rng(max_depth: u32, rng: R) -> Self {
+        Self {
+            level: 0.5,
+            max_depth: max_depth.max(1),
+            rng,
+            last_value: None,
+        }
+    }
+
+    /// The same agent starting at `level` (clamped to 0..=1).
+    pub fn with_level(mut self, level: f64) -> Self {
+        self.level = level.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The current level, from 0 (weakest) to 1 (strongest).
+    pub fn level(&self) -> f64 {
+        self.level
+    }
+
+    /// The depth the agent searches at its current level.
+    pub fn depth(&self) -> u32 {
+        1 + (f64::from(self.max_depth - 1) * self.level + 0.5) as u32
+    }
+
+    /// How often the agent blunders at its current level.
+    pub fn blunder_rate(&self) -> f64 {
+        (1.0 - self.level) * MAX_BLUNDER_RATE
+    }
+
+    /// Adjusts the level after a game the agent played as `side`, won by
+    /// `winner` (`None` for a draw), and forgets the game's evaluations.
+    pub fn record_game(&mut self, side: Player, winner: Option<Player>) {
+        if let Some(winner) = winner {
+            let step = if side == winner {
+                -GAME_STEP
+            } else {
+                GAME_STEP
+            };
+            self.adjust(step);
+        }
+        self.last_value = None;
+    }
+
+    fn adjust(&mut self, step: f64) {
+        self.level = (self.level + step).clamp(0.0, 1.0);
+    }
+
+    /// Follows the trend of the agent's evaluation `value` (Player1's
+    /// perspective) of the position it just moved from.
+    fn follow_trend(&mut self, side: Player, value: i32) {
+        let own = match side {
+            Player::Player1 => value,
+            Player::Player2 => -value,
+        };
+        if let Some(last) = self.last_value {
+            if own > last {
+                self.adjust(-MOVE_STEP);
+            } else if own < last {
+                self.adjust(MOVE_STEP);
+            }
+        }
+        self.last_value = Some(own);
+    }
+}
+
+/// A starting level for an opponent with the given results against the
+/// agent's game: their score (a draw counting half) as a fraction of their
+/// games, or 0.5 without any.
+pub fn level_for_record(wins: u32, draws: u32, losses: u32) -> f64 {
+    let games = wins + draws + losses;
+    if games == 0 {
+        return 0.5;
+    }
+    (f64::from(wins) + f64::from(draws) / 2.0) / f64::from(games)
+}
+
+impl<G, R> Agent<G> for AdaptiveAgent<R>
+where
+    G: GameState,
+    R: Rng,
+    G::Move: PartialEq,
+{
+    fn select_move(&mut self, state: &G) -> Option<Decision<G::Move>> {
+        if state.is_terminal() {
+            return None;
+        }
+        let depth = self.depth();
+        let blunder = self.rng.next_f64();
+        let (best, value) = minimax_best_move_ab_depth(state, depth)?;
+        self.follow_trend(state.current_player(), value);
+        if blunder < self.blunder_rate() {
+            let mut others: Vec<G::Move> = state.legal_moves();
+            others.retain(|mv| *mv != best);
+            if let Some(mv) = self.rng.choose(&others) {
+                let value = minimax_value_ab_depth_root(&state.apply_move(mv), depth - 1);
+                return Some(Decision {
+                    mv: mv.clone(),
+                    value: Some(value),
+                });
+            }
+        }
+        Some(Decision {
+            mv: best,
+            value: Some(value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::play_game;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+    use crate::rng::RandomAgent;
+
+    #[test]
+    fn the_level_sets_depth_and_blunder_rate() {
+        let weakest = AdaptiveAgent::new(9, 0).with_level(-1.0);
+        assert_eq!(
+            (weakest.depth(), weakest.blunder_rate()),
+            (1, MAX_BLUNDER_RATE)
+        );
+        assert_eq!(AdaptiveAgent::new(9, 0).depth(), 5);
+        let strongest = AdaptiveAgent::new(9, 0).with_level(2.0);
+        assert_eq!((strongest.depth(), strongest.blunder_rate()), (9, 0.0));
+
+        // At full strength the agent plays the search's move.
+        let s = BitboardState::from_move_sequence("3344").unwrap();
+        let mut agent = AdaptiveAgent::new(4, 0).with_level(1.0);
+        let (best, value) = minimax_best_move_ab_depth(&s, 4).unwrap();
+        assert_eq!(
+            agent.select_move(&s),
+            Some(Decision {
+                mv: best,
+                value: Some(value)
+            })
+        );
+    }
+
+    #[test]
+    fn results_move_the_level_against_them() {
+        let mut agent = AdaptiveAgent::new(9, 0);
+        agent.record_game(Player::Player1, Some(Player::Player1));
+        assert_eq!(agent.level(), 0.5 - GAME_STEP);
+        agent.record_game(Player::Player1, None);
+        assert_eq!(agent.level(), 0.5 - GAME_STEP);
+        agent.record_game(Player::Player2, Some(Player::Player1));
+        assert_eq!(agent.level(), 0.5);
+
+        assert_eq!(level_for_record(0, 0, 0), 0.5);
+        assert_eq!(level_for_record(3, 2, 5), 0.4);
+    }
+
+    #[test]
+    fn winning_positions_make_the_agent_ease_off() {
+        // A strong agent beats a random one, its evaluation climbing as it
+        // does, so it ends the game weaker than it started.
+        let mut agent = AdaptiveAgent::new(9, 1).with_level(1.0);
+        let mut random = RandomAgent::new(7);
+        let (end, _) = play_game(&TicTacToeState::new(), &mut agent, &mut random);
+        assert_eq!(end.terminal_value(), Some(1));
+        assert!(agent.level() < 1.0);
+    }
+}
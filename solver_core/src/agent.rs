@@ -0,0 +1,291 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::eval::{Evaluator, HeuristicEvaluator};
+use crate::events::{GameEvent, GameObserver};
+use crate::game::{GameState, Player, PositionKey};
+use crate::solvers::minimax::{SearchParams, alpha_beta};
+use crate::solvers::mtdf::{SearchAlgorithm, iterative_search_with_contempt};
+use crate::solvers::tt::{ReplacementPolicy, TranspositionTable, TtStats};
+
+/// A move chosen by an agent, with the agent's evaluation if it has one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Decision<M> {
+    /// The chosen move.
+    pub mv: M,
+    /// The agent's evaluation of the move from Player1's perspective, if any.
+    pub value: Option<i32>,
+}
+
+/// Something that picks moves: a search-based engine, a random mover, a
+/// scripted player, etc.
+///
+/// Game loops, servers, and match runners drive agents through this trait
+/// so they don't depend on any particular solver.
+pub trait Agent<G: GameState> {
+    /// Chooses a move for the side to move in `state`.
+    ///
+    /// Returns `None` if there is no legal move (e.g., the state is terminal).
+    fn select_move(&mut self, state: &G) -> Option<Decision<G::Move>>;
+}
+
+/// Boxed agents (e.g. chosen at runtime) are agents too.
+impl<G: GameState, A: Agent<G> + ?Sized> Agent<G> for Box<A> {
+    fn select_move(&mut self, state: &G) -> Option<Decision<G::Move>> {
+        (**self).select_move(state)
+    }
+}
+
+/// Plays a game from `start` until it ends, asking `p1` for Player1's moves
+/// and `p2` for Player2's.
+///
+/// Returns the final state and the moves played. The game also stops if an
+/// agent has no move to offer.
+pub fn play_game<G: GameState>(
+    start: &G,
+    p1: &mut dyn Agent<G>,
+    p2: &mut dyn Agent<G>,
+) -> (G, Vec<G::Move>) {
+    let (state, decisions) = play_game_decisions(start, p1, p2);
+    (state, decisions.into_iter().map(|d| d.mv).collect())
+}
+
+/// Like `play_game`, but returns the agents' decisions, so their
+/// evaluations are kept along with the moves.
+pub fn play_game_decisions<G: GameState>(
+    start: &G,
+    p1: &mut dyn Agent<G>,
+    p2: &mut dyn Agent<G>,
+) -> (G, Vec<Decision<G::Move>>) {
+    play_game_observed(start, p1, p2, &mut ())
+}
+
+/// Like `play_game_decisions`, reporting each move and the end of the game
+/// to `observer` as they happen.
+pub fn play_game_observed<G: GameState>(
+    start: &G,
+    p1: &mut dyn Agent<G>,
+    p2: &mut dyn Agent<G>,
+    observer: &mut dyn GameObserver<G::Move>,
+) -> (G, Vec<Decision<G::Move>>) {
+    let mut state = start.clone();
+    let mut decisions = Vec::new();
+    while !state.is_terminal() {
+        let player = state.current_player();
+        let decision = match player {
+            Player::Player1 => p1.select_move(&state),
+            Player::Player2 => p2.select_move(&state),
+        };
+        let Some(decision) = decision else {
+            break;
+        };
+        state = state.apply_move(&decision.mv);
+        observer.on_event(&GameEvent::MoveMade {
+            player,
+            mv: decision.mv.clone(),
+            eval: decision.value,
+        });
+        decisions.push(decision);
+    }
+    observer.on_event(&GameEvent::GameEnded {
+        outcome: state.outcome(),
+    });
+    (state, decisions)
+}
+
+/// An agent that plays the depth-limited alpha-beta best move, scoring
+/// leaves with `evaluator` (the game's own heuristic by default).
+///
+/// A depth of 0 is treated as 1: the agent always looks at least one ply
+/// ahead so it can compare its moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MinimaxAgent<E = HeuristicEvaluator> {
+    /// Search depth in plies.
+    pub depth: u32,
+    /// Leaf evaluator.
+    pub evaluator: E,
+    /// How much the agent dislikes a draw (see `SearchParams::contempt`).
+    pub contempt: i32,
+}
+
+impl MinimaxAgent {
+    /// Creates an agent searching `depth` plies with the game's heuristic.
+    pub fn new(depth: u32) -> Self {
+        Self {
+            depth,
+            evaluator: HeuristicEvaluator,
+            contempt: 0,
+        }
+    }
+}
+
+impl<E> MinimaxAgent<E> {
+    /// Creates an agent searching `depth` plies with a custom evaluator.
+    pub fn with_evaluator(depth: u32, evaluator: E) -> Self {
+        Self {
+            depth,
+            evaluator,
+            contempt: 0,
+        }
+    }
+}
+
+impl<G: GameState, E: Evaluator<G>> Agent<G> for MinimaxAgent<E> {
+    fn select_move(&mut self, state: &G) -> Option<Decision<G::Move>> {
+        if state.is_terminal() {
+            return None;
+        }
+        let params = SearchParams {
+            contempt: self.contempt,
+            ..SearchParams::depth(self.depth.max(1))
+        };
+        let (best, _) = alpha_beta(state, params, &self.evaluator);
+        best.map(|(mv, value)| Decision {
+            mv,
+            value: Some(value),
+        })
+    }
+}
+
+/// How much of a `SearchAgent`'s latest search was started warm, from
+/// the table it kept from earlier moves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WarmStart {
+    /// Table entries kept from earlier moves when the search started.
+    pub kept_entries: usize,
+    /// Positions the search visited.
+    pub nodes: u64,
+    /// How the search used the table.
+    pub tt: TtStats,
+}
+
+/// An agent that deepens iteratively with `algorithm`, keeping its
+/// transposition table between moves unless `reuse_table` is off.
+#[derive(Clone, Debug)]
+pub struct SearchAgent<M> {
+    /// Search depth in plies.
+    pub depth: u32,
+    pub algorithm: SearchAlgorithm,
+    /// Keep the table's results for the next move; turn off to start every
+    /// move cold and measure what keeping them saves.
+    pub reuse_table: bool,
+    /// How much the agent dislikes a draw (see `SearchParams::contempt`).
+    pub contempt: i32,
+    tt: TranspositionTable<M>,
+    warm_start: Option<WarmStart>,
+    /// The draw score (from Player1's perspective) the table's results
+    /// were searched with, as the stored values include it.
+    draw_score: i32,
+}
+
+impl<M: Clone> SearchAgent<M> {
+    /// Creates an agent searching `depth` plies with a two-tier table of
+    /// `tt_megabytes` MiB.
+    pub fn new(depth: u32, algorithm: SearchAlgorithm, tt_megabytes: usize) -> Self {
+        Self {
+            depth,
+            algorithm,
+            reuse_table: true,
+            contempt: 0,
+            tt: TranspositionTable::with_megabytes(tt_megabytes, ReplacementPolicy::TwoTier),
+            warm_start: None,
+            draw_score: 0,
+        }
+    }
+
+    /// The agent's transposition table, e.g. to report its statistics.
+    pub fn table(&self) -> &TranspositionTable<M> {
+        &self.tt
+    }
+
+    /// What the latest search started from and how it used the table, or
+    /// `None` before the first move.
+    pub fn warm_start(&self) -> Option<WarmStart> {
+        self.warm_start
+    }
+}
+
+impl<G> Agent<G> for SearchAgent<G::Move>
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    fn select_move(&mut self, state: &G) -> Option<Decision<G::Move>> {
+        let params = SearchParams {
+            contempt: self.contempt,
+            ..SearchParams::default()
+        };
+        let draw_score = params.draw_score(state.current_player());
+        if !self.reuse_table || draw_score != self.draw_score {
+            self.tt.clear();
+        }
+        self.draw_score = draw_score;
+        let kept_entries = self.tt.len();
+        let before = self.tt.stats();
+        let (best, stats) = iterative_search_with_contempt(
+            state,
+            self.depth,
+            self.algorithm,
+            self.contempt,
+            &mut self.tt,
+        );
+        self.warm_start = Some(WarmStart {
+            kept_entries,
+            nodes: stats.nodes,
+            tt: self.tt.stats().since(before),
+        });
+        best.map(|(mv, value)| Decision {
+            mv,
+            value: Some(value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+
+    #[test]
+    fn agents_score_draws_by_their_contempt() {
+        // Tic-tac-toe is a draw, which the agents see to the end.
+        let start = TicTacToeState::new();
+        let reply = start.apply_move(&start.legal_moves()[4]);
+        for contempt in [0, 30, -30] {
+            let mut minimax = MinimaxAgent::new(9);
+            minimax.contempt = contempt;
+            assert_eq!(minimax.select_move(&start).unwrap().value, Some(-contempt));
+            for algorithm in [SearchAlgorithm::AlphaBeta, SearchAlgorithm::Mtdf] {
+                let mut agent = SearchAgent::new(9, algorithm, 1);
+                agent.contempt = contempt;
+                assert_eq!(agent.select_move(&start).unwrap().value, Some(-contempt));
+                // The other side's draws score the other way, whatever the
+                // table kept from the last move.
+                assert_eq!(agent.select_move(&reply).unwrap().value, Some(contempt));
+            }
+        }
+    }
+
+    #[test]
+    fn search_agents_report_their_warm_starts() {
+        let mut warm = SearchAgent::new(8, SearchAlgorithm::AlphaBeta, 4);
+        let mut cold = warm.clone();
+        cold.reuse_table = false;
+        assert_eq!(warm.warm_start(), None);
+
+        let start = BitboardState::new();
+        let later = start.apply_move(&3).apply_move(&3);
+        for agent in [&mut warm, &mut cold] {
+            agent.select_move(&start);
+            assert_eq!(agent.warm_start().unwrap().kept_entries, 0);
+            agent.select_move(&later);
+        }
+        let (warm, cold) = (warm.warm_start().unwrap(), cold.warm_start().unwrap());
+        assert!(warm.kept_entries > 0);
+        assert_eq!(cold.kept_entries, 0);
+        assert!(warm.tt.hit_rate() > cold.tt.hit_rate());
+        assert!(warm.nodes < cold.nodes, "{} vs {}", warm.nodes, cold.nodes);
+    }
+}
@@ -0,0 +1,190 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::game::{GameState, Player};
+use crate::solvers::minimax::{WIN_SCORE, minimax_value_ab_depth_root};
+
+/// Value lost (in evaluation units, for the side that moved) from which a
+/// move is an inaccuracy.
+pub const INACCURACY_LOSS: i32 = 20;
+/// Value lost from which a move is a mistake.
+pub const MISTAKE_LOSS: i32 = 50;
+/// Value lost from which a move is a blunder. Giving away a forced result
+/// is always one.
+pub const BLUNDER_LOSS: i32 = 150;
+
+/// A quality marker for a move, as in chess notation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Annotation {
+    /// `!!`: the only move that keeps the forced result.
+    Brilliant,
+    /// `!`: the only move that isn't a mistake.
+    Good,
+    /// `?!`: loses more than `INACCURACY_LOSS`.
+    Inaccuracy,
+    /// `?`: loses more than `MISTAKE_LOSS`.
+    Mistake,
+    /// `??`: loses more than `BLUNDER_LOSS`, or gives away a forced
+    /// result.
+    Blunder,
+}
+
+impl Annotation {
+    /// Every annotation, best first.
+    pub const ALL: [Annotation; 5] = [
+        Annotation::Brilliant,
+        Annotation::Good,
+        Annotation::Inaccuracy,
+        Annotation::Mistake,
+        Annotation::Blunder,
+    ];
+
+    /// The marker written after the move: `!!`, `!`, `?!`, `?` or `??`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Annotation::Brilliant => "!!",
+            Annotation::Good => "!",
+            Annotation::Inaccuracy => "?!",
+            Annotation::Mistake => "?",
+            Annotation::Blunder => "??",
+        }
+    }
+
+    /// The annotation in words: `"brilliant"`, `"good"`, `"inaccuracy"`,
+    /// `"mistake"` or `"blunder"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Annotation::Brilliant => "brilliant",
+            Annotation::Good => "good",
+            Annotation::Inaccuracy => "inaccuracy",
+            Annotation::Mistake => "mistake",
+            Annotation::Blunder => "blunder",
+        }
+    }
+
+    /// The annotation whose marker is `symbol`.
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.symbol() == symbol)
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+/// The forced result a depth-limited value shows for the side it is
+/// taken for: 1 won, -1 lost, 0 not proven either way.
+fn result(value: i32) -> i32 {
+    if value >= WIN_SCORE {
+        1
+    } else if value <= -WIN_SCORE {
+        -1
+    } else {
+        0
+    }
+}
+
+/// Annotates `mv`, played in `state`, by comparing it with every other
+/// legal move, each searched `depth` plies deep (counting the move).
+///
+/// Returns `None` for moves that deserve no marker, including every move
+/// of a position with only one.
+pub fn annotate_move<G>(state: &G, mv: &G::Move, depth: u32) -> Option<Annotation>
+where
+    G: GameState,
+    G::Move: PartialEq,
+{
+    let sign = match state.current_player() {
+        Player::Player1 => 1,
+        Player::Player2 => -1,
+    };
+    let mut played = None;
+    let mut others = Vec::new();
+    for candidate in state.legal_moves() {
+        let child = state.apply_move(&candidate);
+        let value = sign * minimax_value_ab_depth_root(&child, depth.saturating_sub(1));
+        if candidate == *mv && played.is_none() {
+            played = Some(value);
+        } else {
+            others.push(value);
+        }
+    }
+    let played = played?;
+    let best = others.iter().copied().fold(played, i32::max);
+    let loss = best.saturating_sub(played);
+    if result(played) < result(best) || loss > BLUNDER_LOSS {
+        Some(Annotation::Blunder)
+    } else if loss > MISTAKE_LOSS {
+        Some(Annotation::Mistake)
+    } else if loss > INACCURACY_LOSS {
+        Some(Annotation::Inaccuracy)
+    } else if others.is_empty() || loss > 0 {
+        None
+    } else if others.iter().all(|&v| result(v) < result(played)) {
+        Some(Annotation::Brilliant)
+    } else if others
+        .iter()
+        .all(|&v| played.saturating_sub(v) > MISTAKE_LOSS)
+    {
+        Some(Annotation::Good)
+    } else {
+        None
+    }
+}
+
+/// Annotates every move of the game played from `start` (see
+/// `annotate_move`).
+pub fn annotate_game<G>(start: &G, moves: &[G::Move], depth: u32) -> Vec<Option<Annotation>>
+where
+    G: GameState,
+    G::Move: PartialEq,
+{
+    let mut state = start.clone();
+    let mut annotations = Vec::with_capacity(moves.len());
+    for mv in moves {
+        annotations.push(annotate_move(&state, mv, depth));
+        state = state.apply_move(mv);
+    }
+    annotations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::ttt::{TicTacToeMove, TicTacToeState};
+
+    fn ttt(indices: &[u8]) -> Vec<TicTacToeMove> {
+        indices
+            .iter()
+            .map(|&index| TicTacToeMove { index })
+            .collect()
+    }
+
+    #[test]
+    fn symbols_round_trip() {
+        for annotation in Annotation::ALL {
+            assert_eq!(
+                Annotation::from_symbol(annotation.symbol()),
+                Some(annotation)
+            );
+        }
+        assert_eq!(Annotation::from_symbol("!?!"), None);
+        assert_eq!(alloc::format!("{}", Annotation::Blunder), "??");
+    }
+
+    #[test]
+    fn ttt_games_are_annotated() {
+        // Every opening draws; against a corner, only the centre does.
+        let annotations = annotate_game(&TicTacToeState::new(), &ttt(&[0, 1]), 9);
+        assert_eq!(annotations, [None, Some(Annotation::Blunder)]);
+        let annotations = annotate_game(&TicTacToeState::new(), &ttt(&[0, 4]), 9);
+        assert_eq!(annotations, [None, Some(Annotation::Brilliant)]);
+
+        // A move that doesn't appear among the legal ones gets no marker.
+        let s = TicTacToeState::from_str("X........", Player::Player2).unwrap();
+        assert_eq!(annotate_move(&s, &TicTacToeMove { index: 0 }, 9), None);
+    }
+}
@@ -0,0 +1,550 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+use crate::agent::{Agent, play_game_decisions};
+use crate::game::{GameState, Outcome, Player};
+
+/// Creates a fresh agent for the game with the given index, for matches
+/// that play games in parallel (see `run_match_parallel`). The index lets
+/// random agents vary their seed per game while staying reproducible.
+pub type AgentFactory<'a, G> = dyn Fn(usize) -> Box<dyn Agent<G>> + Sync + 'a;
+
+/// Win/draw/loss counts from one side's perspective.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    /// Total number of games.
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Fraction of available points scored (win = 1, draw = 0.5).
+    pub fn score(&self) -> f64 {
+        let games = self.games();
+        if games == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + 0.5 * self.draws as f64) / games as f64
+    }
+
+    /// Records one game result (+1 win, 0 draw, -1 loss).
+    pub fn record(&mut self, result: i32) {
+        match result.signum() {
+            1 => self.wins += 1,
+            -1 => self.losses += 1,
+            _ => self.draws += 1,
+        }
+    }
+
+    /// The variance of a single game's result (1, 0.5 or 0) around the
+    /// mean score.
+    fn variance(&self) -> f64 {
+        let p = self.score();
+        (self.wins as f64 * (1.0 - p).powi(2)
+            + self.draws as f64 * (0.5 - p).powi(2)
+            + self.losses as f64 * p.powi(2))
+            / self.games() as f64
+    }
+
+    /// Estimates the Elo difference implied by this score, with a 95%
+    /// confidence interval. Returns `None` if no games were played.
+    pub fn elo(&self) -> Option<EloEstimate> {
+        let n = self.games() as f64;
+        if n == 0.0 {
+            return None;
+        }
+        let p = self.score();
+        let margin = 1.96 * (self.variance() / n).sqrt();
+        Some(EloEstimate {
+            diff: elo_from_score(p),
+            lower: elo_from_score(p - margin),
+            upper: elo_from_score(p + margin),
+        })
+    }
+}
+
+/// An Elo difference and its 95% confidence interval.
+///
+/// Scores of 0% or 100% (or interval bounds beyond them) map to infinite
+/// differences.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EloEstimate {
+    pub diff: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Converts an expected score in `0.0..=1.0` to an Elo difference.
+pub fn elo_from_score(score: f64) -> f64 {
+    if score <= 0.0 {
+        f64::NEG_INFINITY
+    } else if score >= 1.0 {
+        f64::INFINITY
+    } else {
+        -400.0 * (1.0 / score - 1.0).log10()
+    }
+}
+
+/// Sequential probability ratio test parameters for a match.
+///
+/// The test weighs H0 "A is `elo0` stronger than B" against H1 "A is `elo1`
+/// stronger" (`elo1 > elo0`) after every game, and stops as soon as one is
+/// accepted: H1 with false-positive rate `alpha`, H0 with false-negative
+/// rate `beta`. Usually far fewer games are needed than for a fixed-length
+/// match of the same confidence.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+/// The state of an SPRT.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SprtDecision {
+    /// H0 accepted: A is at most `elo0` stronger.
+    AcceptH0,
+    /// H1 accepted: A is at least `elo1` stronger.
+    AcceptH1,
+    /// Not enough evidence yet.
+    Continue,
+}
+
+/// Games added to each of the win, draw and loss tallies when `Sprt::llr`
+/// estimates the variance of a game's result.
+const SPRT_PSEUDO_COUNT: f64 = 0.5;
+
+/// Converts an Elo difference to an expected score.
+pub fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+impl Sprt {
+    /// A test of `elo0` against `elo1` with the customary 5% error rates.
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Self {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// The log-likelihood ratio of H1 to H0 given `score`.
+    ///
+    /// Uses the normal approximation of the trinomial (win/draw/loss)
+    /// model, with `SPRT_PSEUDO_COUNT` added to each tally so that
+    /// one-sided results, which have no variance of their own, still
+    /// reach a decision.
+    pub fn llr(&self, score: &MatchScore) -> f64 {
+        let n = score.games() as f64;
+        if n == 0.0 {
+            return 0.0;
+        }
+        let (wins, draws, losses) = (
+            score.wins as f64 + SPRT_PSEUDO_COUNT,
+            score.draws as f64 + SPRT_PSEUDO_COUNT,
+            score.losses as f64 + SPRT_PSEUDO_COUNT,
+        );
+        let total = wins + draws + losses;
+        let p = (wins + 0.5 * draws) / total;
+        let variance =
+            (wins * (1.0 - p).powi(2) + draws * (0.5 - p).powi(2) + losses * p.powi(2)) / total;
+        let (s0, s1) = (score_from_elo(self.elo0), score_from_elo(self.elo1));
+        (s1 - s0) * (2.0 * p - s0 - s1) * n / (2.0 * variance)
+    }
+
+    /// The LLR bounds: H0 is accepted below the first, H1 above the second.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// The decision after the games in `score`.
+    pub fn decision(&self, score: &MatchScore) -> SprtDecision {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtDecision::AcceptH1
+        } else if llr <= lower {
+            SprtDecision::AcceptH0
+        } else {
+            SprtDecision::Continue
+        }
+    }
+}
+
+/// Returns every line of `plies` moves from `root` that does not end the
+/// game, with the resulting position, in lexicographic move order.
+pub fn opening_positions<G: GameState>(root: &G, plies: u32) -> Vec<(Vec<G::Move>, G)> {
+    let mut result = Vec::new();
+    let mut stack = vec![(root.clone(), Vec::new())];
+    while let Some((state, seq)) = stack.pop() {
+        if seq.len() == plies as usize {
+            result.push((seq, state));
+            continue;
+        }
+        for mv in state.legal_moves().into_iter().rev() {
+            let child = state.apply_move(&mv);
+            if !child.is_terminal() {
+                let mut next = seq.clone();
+                next.push(mv);
+                stack.push((child, next));
+            }
+        }
+    }
+    result
+}
+
+/// One game of a match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameRecord<M> {
+    /// Index into the match's start positions.
+    pub start: usize,
+    /// Whether agent A played Player1.
+    pub a_is_player1: bool,
+    /// Moves played from the start position.
+    pub moves: Vec<M>,
+    /// The evaluation each move was chosen with, from Player1's
+    /// perspective, if its agent gave one (see `Decision::value`).
+    pub values: Vec<Option<i32>>,
+    /// How the game ended, or `None` if an agent stopped offering moves.
+    pub outcome: Option<Outcome>,
+    /// Result from A's perspective: +1 win, 0 draw, -1 loss (unfinished
+    /// games count as draws).
+    pub result: i32,
+}
+
+/// The outcome of `run_match`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchReport<M> {
+    /// Agent A's score.
+    pub score: MatchScore,
+    /// Every game in the order played.
+    pub games: Vec<GameRecord<M>>,
+}
+
+/// Plays `games` games between agents `a` and `b`, alternating colors.
+///
+/// Game `i` starts from `starts[(i / 2) % starts.len()]` with A playing
+/// Player1 when `i` is even, so each start position is played once with
+/// each color before moving on. Deterministic engines need several start
+/// positions for a match to be more than two distinct games.
+///
+/// Panics if `starts` is empty.
+pub fn run_match<G: GameState>(
+    starts: &[G],
+    a: &mut dyn Agent<G>,
+    b: &mut dyn Agent<G>,
+    games: u32,
+) -> MatchReport<G::Move> {
+    play_match(starts, a, b, games, |_| false)
+}
+
+/// Like `run_match`, but stops as soon as `sprt` reaches a decision, with
+/// `games` as the maximum. The test is only checked after even-numbered
+/// games, so both colors are played equally often.
+///
+/// Panics if `starts` is empty.
+pub fn run_match_sprt<G: GameState>(
+    starts: &[G],
+    a: &mut dyn Agent<G>,
+    b: &mut dyn Agent<G>,
+    games: u32,
+    sprt: &Sprt,
+) -> (MatchReport<G::Move>, SprtDecision) {
+    let report = play_match(starts, a, b, games, |score| {
+        score.games() % 2 == 0 && sprt.decision(score) != SprtDecision::Continue
+    });
+    let decision = sprt.decision(&report.score);
+    (report, decision)
+}
+
+/// Like `run_match` (or `run_match_sprt`, given `sprt`), but plays the games
+/// on `jobs` threads.
+///
+/// Every game gets fresh agents from `make_a` and `make_b`, so no state
+/// such as a transposition table or random generator is shared between
+/// games, and the result does not depend on `jobs`. With `sprt`, the
+/// test is applied to games in order, and the report ends at the game
+/// where it decided. Games already running at that point are discarded.
+///
+/// Panics if `starts` is empty.
+pub fn run_match_parallel<G>(
+    starts: &[G],
+    make_a: &AgentFactory<G>,
+    make_b: &AgentFactory<G>,
+    games: u32,
+    jobs: usize,
+    sprt: Option<&Sprt>,
+) -> MatchReport<G::Move>
+where
+    G: GameState + Sync,
+    G::Move: Send,
+{
+    assert!(
+        !starts.is_empty(),
+        "a match needs at least one start position"
+    );
+    let games = games as usize;
+    let next = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+    // Finished games, and the report over the in-order prefix of them.
+    let results = Mutex::new((
+        (0..games).map(|_| None).collect::<Vec<_>>(),
+        MatchReport {
+            score: MatchScore::default(),
+            games: Vec::new(),
+        },
+    ));
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, games.max(1)) {
+            scope.spawn(|| {
+                while !stopped.load(Ordering::Relaxed) {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= games {
+                        break;
+                    }
+                    let record = play_one(starts, &mut *make_a(i), &mut *make_b(i), i);
+                    let mut guard = results.lock().unwrap();
+                    let (pending, report) = &mut *guard;
+                    pending[i] = Some(record);
+                    while !stopped.load(Ordering::Relaxed) {
+                        let Some(record) =
+                            pending.get_mut(report.games.len()).and_then(Option::take)
+                        else {
+                            break;
+                        };
+                        report.score.record(record.result);
+                        report.games.push(record);
+                        let decided = sprt.is_some_and(|sprt| {
+                            report.score.games() % 2 == 0
+                                && sprt.decision(&report.score) != SprtDecision::Continue
+                        });
+                        if decided {
+                            stopped.store(true, Ordering::Relaxed);
+                        }
+                    }
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap().1
+}
+
+/// Plays game `i` of a match as described for `run_match`.
+fn play_one<G: GameState>(
+    starts: &[G],
+    a: &mut dyn Agent<G>,
+    b: &mut dyn Agent<G>,
+    i: usize,
+) -> GameRecord<G::Move> {
+    let start = (i / 2) % starts.len();
+    let a_is_player1 = i.is_multiple_of(2);
+    let (end, decisions) = if a_is_player1 {
+        play_game_decisions(&starts[start], a, b)
+    } else {
+        play_game_decisions(&starts[start], b, a)
+    };
+    let values = decisions.iter().map(|d| d.value).collect();
+    let moves = decisions.into_iter().map(|d| d.mv).collect();
+    let outcome = end.outcome();
+    let a = if a_is_player1 {
+        Player::Player1
+    } else {
+        Player::Player2
+    };
+    GameRecord {
+        start,
+        a_is_player1,
+        moves,
+        values,
+        outcome,
+        result: outcome.map_or(0, |o| o.value_for(a)),
+    }
+}
+
+/// Plays up to `games` games as described for `run_match`, stopping early
+/// once `stop` returns true for the score so far.
+fn play_match<G: GameState>(
+    starts: &[G],
+    a: &mut dyn Agent<G>,
+    b: &mut dyn Agent<G>,
+    games: u32,
+    mut stop: impl FnMut(&MatchScore) -> bool,
+) -> MatchReport<G::Move> {
+    assert!(
+        !starts.is_empty(),
+        "a match needs at least one start position"
+    );
+    let mut report = MatchReport {
+        score: MatchScore::default(),
+        games: Vec::new(),
+    };
+    for i in 0..games as usize {
+        let record = play_one(starts, a, b, i);
+        report.score.record(record.result);
+        report.games.push(record);
+        if stop(&report.score) {
+            break;
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::MinimaxAgent;
+    use crate::games::ttt::TicTacToeState;
+    use crate::rng::RandomAgent;
+
+    #[test]
+    fn elo_of_even_score_is_zero() {
+        let even = MatchScore {
+            wins: 10,
+            draws: 5,
+            losses: 10,
+        };
+        let elo = even.elo().unwrap();
+        assert_eq!(elo.diff, 0.0);
+        assert!(elo.lower < 0.0 && elo.upper > 0.0);
+        assert!((elo_from_score(0.75) - 190.85).abs() < 0.01);
+        assert_eq!(MatchScore::default().elo(), None);
+    }
+
+    #[test]
+    fn ttt_perfect_play_never_loses_to_random() {
+        let starts = vec![TicTacToeState::new()];
+        let report = run_match(
+            &starts,
+            &mut MinimaxAgent::new(9),
+            &mut RandomAgent::new(3),
+            10,
+        );
+        assert_eq!(report.games.len(), 10);
+        assert_eq!(report.score.losses, 0);
+        assert!(report.score.wins > 0);
+        assert!(report.score.elo().unwrap().diff > 0.0);
+        assert!(report.games.iter().step_by(2).all(|g| g.a_is_player1));
+        assert!(report.games.iter().all(|g| g.outcome.is_some()));
+    }
+
+    #[test]
+    fn sprt_decides_clear_results_and_waits_on_close_ones() {
+        let sprt = Sprt::new(0.0, 10.0);
+        let (lower, upper) = sprt.bounds();
+        assert!((upper - 2.944).abs() < 0.001);
+        assert!((lower + 2.944).abs() < 0.001);
+
+        let close = MatchScore {
+            wins: 30,
+            draws: 40,
+            losses: 30,
+        };
+        assert_eq!(sprt.decision(&close), SprtDecision::Continue);
+        let strong = MatchScore {
+            wins: 300,
+            draws: 400,
+            losses: 200,
+        };
+        assert_eq!(sprt.decision(&strong), SprtDecision::AcceptH1);
+        let weak = MatchScore {
+            wins: 200,
+            draws: 400,
+            losses: 300,
+        };
+        assert_eq!(sprt.decision(&weak), SprtDecision::AcceptH0);
+        assert_eq!(sprt.llr(&MatchScore::default()), 0.0);
+
+        // Results all one way have no variance of their own.
+        let sweep = MatchScore {
+            wins: 20,
+            draws: 0,
+            losses: 0,
+        };
+        assert_eq!(sprt.decision(&sweep), SprtDecision::AcceptH1);
+        let swept = MatchScore {
+            wins: 0,
+            draws: 0,
+            losses: 20,
+        };
+        assert_eq!(sprt.decision(&swept), SprtDecision::AcceptH0);
+        let drawn = MatchScore {
+            wins: 0,
+            draws: 200,
+            losses: 0,
+        };
+        assert_eq!(sprt.decision(&drawn), SprtDecision::AcceptH0);
+        assert_eq!(
+            sprt.decision(&MatchScore {
+                wins: 2,
+                draws: 0,
+                losses: 0
+            }),
+            SprtDecision::Continue
+        );
+        assert!((score_from_elo(0.0) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ttt_sprt_match_stops_early() {
+        let starts = vec![TicTacToeState::new()];
+        let (report, decision) = run_match_sprt(
+            &starts,
+            &mut MinimaxAgent::new(9),
+            &mut RandomAgent::new(3),
+            1000,
+            &Sprt::new(0.0, 50.0),
+        );
+        assert_eq!(decision, SprtDecision::AcceptH1);
+        assert!(report.games.len() < 1000);
+        assert_eq!(report.games.len() % 2, 0);
+    }
+
+    #[test]
+    fn ttt_parallel_matches_do_not_depend_on_the_job_count() {
+        let starts: Vec<TicTacToeState> = opening_positions(&TicTacToeState::new(), 1)
+            .into_iter()
+            .map(|(_, s)| s)
+            .collect();
+        let make_a =
+            |i: usize| -> Box<dyn Agent<TicTacToeState>> { Box::new(RandomAgent::new(i as u64)) };
+        let make_b = |i: usize| -> Box<dyn Agent<TicTacToeState>> {
+            Box::new(RandomAgent::new(1000 + i as u64))
+        };
+        let serial = run_match_parallel(&starts, &make_a, &make_b, 40, 1, None);
+        let parallel = run_match_parallel(&starts, &make_a, &make_b, 40, 4, None);
+        assert_eq!(serial, parallel);
+        assert_eq!(serial.games.len(), 40);
+        assert_eq!(serial.score.games(), 40);
+
+        let perfect =
+            |_: usize| -> Box<dyn Agent<TicTacToeState>> { Box::new(MinimaxAgent::new(9)) };
+        let sprt = Sprt::new(0.0, 50.0);
+        let stopped = run_match_parallel(&starts, &perfect, &make_b, 1000, 4, Some(&sprt));
+        assert!(stopped.games.len() < 1000);
+        assert_eq!(sprt.decision(&stopped.score), SprtDecision::AcceptH1);
+    }
+
+    #[test]
+    fn ttt_opening_positions_skip_nothing_at_two_plies() {
+        let openings = opening_positions(&TicTacToeState::new(), 2);
+        assert_eq!(openings.len(), 72);
+        assert_eq!(openings[0].0.len(), 2);
+    }
+}
@@ -0,0 +1,603 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::agent::{Agent, Decision};
+use crate::game::{GameState, Outcome};
+use crate::games::c4_bitboard::BitboardState;
+use crate::solvers::minimax::minimax_best_move_ab_depth;
+use crate::solvers::tt::{ReplacementPolicy, TranspositionTable, minimax_best_move_ab_depth_tt};
+use crate::utils::write_atomic;
+
+/// File name `OpeningBook::load_default` looks for in the working directory.
+pub const DEFAULT_BOOK_PATH: &str = "c4.book";
+/// Environment variable that overrides `DEFAULT_BOOK_PATH`.
+pub const BOOK_PATH_ENV: &str = "SOLVER_BOOK";
+
+const MAGIC: &[u8; 4] = b"C4BK";
+/// Version 2 added the flags byte; version 1 books are still read.
+const VERSION: u8 = 2;
+const ENTRY_BYTES: usize = 13;
+/// Flag set in books whose values are exact (see `OpeningBook::build_exact`).
+const EXACT_FLAG: u8 = 1;
+/// Transposition table size for `OpeningBook::build_exact`.
+const EXACT_TT_MEGABYTES: usize = 64;
+/// Cells on the board: a search this deep from the initial position only
+/// reaches finished games.
+const CELLS: u32 = 42;
+/// How often `OpeningBook::build_exact_resumable` saves its progress by
+/// default.
+pub const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The stored answer for one book position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookEntry {
+    /// The column to play.
+    pub best_move: u8,
+    /// The search value of the position from Player1's perspective.
+    pub value: i32,
+}
+
+/// Precomputed best moves for the first plies of standard Connect Four.
+///
+/// Only one of each mirror-image pair of positions is stored; lookups
+/// mirror the position and the answer as needed.
+///
+/// A book is either searched to a fixed depth (`build`) or solved
+/// (`build_exact`), in which case its values are the positions' exact
+/// results and `lookup_exact` answers from it.
+///
+/// On disk the book is the header `C4BK`, a version byte, the board rows,
+/// columns and book plies, a flags byte (bit 0: the book is exact), and a
+/// little-endian `u32` entry count, followed by the entries sorted by key,
+/// 13 bytes each: the position key (`u64`), the best column (`u8`), and the
+/// value (`i32`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OpeningBook {
+    /// Positions with fewer than this many discs are covered.
+    plies: u8,
+    /// Whether the values are exact results rather than search values.
+    exact: bool,
+    entries: HashMap<u64, BookEntry>,
+}
+
+/// Where and how often a long book build saves its progress, and whether
+/// it picks up saved progress (see `OpeningBook::build_exact_resumable`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub path: PathBuf,
+    /// Progress is saved after the first position solved this long after
+    /// the last save.
+    pub interval: Duration,
+    /// Start from the progress saved in `path`, if there is any.
+    pub resume: bool,
+}
+
+impl Checkpoint {
+    /// Saves to `path` every `DEFAULT_CHECKPOINT_INTERVAL`, without
+    /// resuming.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            interval: DEFAULT_CHECKPOINT_INTERVAL,
+            resume: false,
+        }
+    }
+}
+
+/// Returns the key under which `state` is stored and whether it had to be
+/// mirrored to get there.
+fn canonical(state: &BitboardState) -> (u64, bool) {
+    let key = state.key();
+    let mirrored = state.mirrored().key();
+    if mirrored < key {
+        (mirrored, true)
+    } else {
+        (key, false)
+    }
+}
+
+impl OpeningBook {
+    /// Builds a book covering every position reachable in fewer than
+    /// `plies` moves, searching each one to `depth` plies.
+    ///
+    /// `on_progress(done, total)` is called after each position is solved.
+    pub fn build(plies: u8, depth: u32, on_progress: impl FnMut(usize, usize)) -> Self {
+        Self::build_with(
+            plies,
+            |state| minimax_best_move_ab_depth(state, depth.max(1)),
+            on_progress,
+        )
+    }
+
+    /// Builds a book covering every position reachable in fewer than
+    /// `plies` moves, solving each one exactly: its value is `WIN_SCORE`
+    /// or more if Player1 wins with best play, as much below zero if
+    /// Player2 does, and zero for a draw.
+    ///
+    /// Solving is a search to the end of the game, which is slow until
+    /// late in the game: expect even a few plies to take a long time.
+    pub fn build_exact(plies: u8, on_progress: impl FnMut(usize, usize)) -> Self {
+        let mut tt = TranspositionTable::with_megabytes(
+            EXACT_TT_MEGABYTES,
+            ReplacementPolicy::DepthPreferred,
+        );
+        let mut book = Self::build_with(plies, |state| solve(state, &mut tt), on_progress);
+        book.exact = true;
+        book
+    }
+
+    /// Like `build_exact`, but saves the positions solved so far to
+    /// `checkpoint.path` as it goes, as an exact book that only holds some
+    /// of its positions. With `checkpoint.resume`, a book saved there by an
+    /// interrupted build is completed instead of starting over: the
+    /// positions it is missing are the ones still to solve.
+    ///
+    /// The checkpoint is removed once the book is complete. Fails if the
+    /// checkpoint can't be read or written, or is for another number of
+    /// plies.
+    pub fn build_exact_resumable(
+        plies: u8,
+        checkpoint: &Checkpoint,
+        on_progress: impl FnMut(usize, usize),
+    ) -> Result<Self, String> {
+        let mut tt = TranspositionTable::with_megabytes(
+            EXACT_TT_MEGABYTES,
+            ReplacementPolicy::DepthPreferred,
+        );
+        Self::build_resumable_with(
+            plies,
+            checkpoint,
+            |state| solve(state, &mut tt),
+            on_progress,
+        )
+    }
+
+    /// Builds an exact book of the positions before `plies`, answered by
+    /// `search` and saved to `checkpoint`.
+    fn build_resumable_with(
+        plies: u8,
+        checkpoint: &Checkpoint,
+        search: impl FnMut(&BitboardState) -> Option<(u8, i32)>,
+        on_progress: impl FnMut(usize, usize),
+    ) -> Result<Self, String> {
+        let path = &checkpoint.path;
+        let mut book = if checkpoint.resume && path.exists() {
+            let book = Self::load(path)?;
+            if !book.exact || book.plies != plies {
+                return Err(format!(
+                    "{} is not a checkpoint of an exact book of {plies} plies",
+                    path.display()
+                ));
+            }
+            book
+        } else {
+            Self {
+                plies,
+                exact: true,
+                entries: HashMap::new(),
+            }
+        };
+        let mut last_save = Instant::now();
+        book.extend_with(search, on_progress, |book| {
+            if last_save.elapsed() < checkpoint.interval {
+                return Ok(());
+            }
+            last_save = Instant::now();
+            write_atomic(path, |out| book.write_to(out))
+                .map_err(|e| format!("failed to write {}: {e}", path.display()))
+        })?;
+        if path.exists() {
+            fs::remove_file(path)
+                .map_err(|e| format!("failed to remove {}: {e}", path.display()))?;
+        }
+        Ok(book)
+    }
+
+    /// Builds a book of the positions before `plies`, answered by `search`.
+    fn build_with(
+        plies: u8,
+        search: impl FnMut(&BitboardState) -> Option<(u8, i32)>,
+        on_progress: impl FnMut(usize, usize),
+    ) -> Self {
+        let mut book = Self {
+            plies,
+            exact: false,
+            entries: HashMap::new(),
+        };
+        book.extend_with(search, on_progress, |_| Ok(()))
+            .expect("nothing to save");
+        book
+    }
+
+    /// Answers with `search` the positions before `self.plies` that the
+    /// book doesn't hold yet, calling `on_solved` with the book after each.
+    ///
+    /// `on_progress(done, total)` counts the positions already held as
+    /// done. Stops at the first error from `on_solved`.
+    fn extend_with(
+        &mut self,
+        mut search: impl FnMut(&BitboardState) -> Option<(u8, i32)>,
+        mut on_progress: impl FnMut(usize, usize),
+        mut on_solved: impl FnMut(&Self) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut positions = Vec::new();
+        let mut seen = HashSet::new();
+        let mut frontier = vec![BitboardState::new()];
+        for _ in 0..self.plies {
+            let mut next = Vec::new();
+            for state in frontier {
+                if state.is_terminal() || !seen.insert(canonical(&state).0) {
+                    continue;
+                }
+                for col in state.legal_moves() {
+                    next.push(state.apply_column_move(col));
+                }
+                positions.push(state);
+            }
+            frontier = next;
+        }
+
+        let total = positions.len();
+        for (i, state) in positions.iter().enumerate() {
+            let (key, mirrored) = canonical(state);
+            if let Entry::Vacant(vacant) = self.entries.entry(key) {
+                let state = if mirrored {
+                    state.mirrored()
+                } else {
+                    state.clone()
+                };
+                if let Some((best_move, value)) = search(&state) {
+                    vacant.insert(BookEntry { best_move, value });
+                }
+                on_solved(self)?;
+            }
+            on_progress(i + 1, total);
+        }
+        Ok(())
+    }
+
+    /// Number of stored positions.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the book has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Positions with fewer than this many discs are covered.
+    pub fn plies(&self) -> u8 {
+        self.plies
+    }
+
+    /// Returns true if the book was solved exactly (see `build_exact`).
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+
+    /// Returns the exact result of `state` with best play, if the book is
+    /// exact and holds the position.
+    ///
+    /// Consulted before searching, this gives perfect play through the
+    /// opening without any search.
+    pub fn lookup_exact(&self, state: &BitboardState) -> Option<Outcome> {
+        if !self.exact {
+            return None;
+        }
+        self.lookup(state)
+            .map(|entry| Outcome::from_value(entry.value))
+    }
+
+    /// Returns the book move for `state`, if the position is in the book.
+    pub fn lookup(&self, state: &BitboardState) -> Option<BookEntry> {
+        if state.ply()? >= u32::from(self.plies) {
+            return None;
+        }
+        let (key, mirrored) = canonical(state);
+        let entry = *self.entries.get(&key)?;
+        Some(BookEntry {
+            best_move: if mirrored {
+                6 - entry.best_move
+            } else {
+                entry.best_move
+            },
+            value: entry.value,
+        })
+    }
+
+    /// Writes the book in its on-disk format.
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        let flags = if self.exact { EXACT_FLAG } else { 0 };
+        w.write_all(&[VERSION, 6, 7, self.plies, flags])?;
+        w.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        let mut keys: Vec<&u64> = self.entries.keys().collect();
+        keys.sort();
+        for key in keys {
+            let entry = self.entries[key];
+            w.write_all(&key.to_le_bytes())?;
+            w.write_all(&[entry.best_move])?;
+            w.write_all(&entry.value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads a book written by `write_to`.
+    pub fn read_from<R: Read>(mut r: R) -> Result<Self, String> {
+        let mut bytes = Vec::new();
+        r.read_to_end(&mut bytes)
+            .map_err(|e| format!("failed to read book: {e}"))?;
+        if bytes.len() < 5 {
+            return Err("book is truncated".to_string());
+        }
+        if &bytes[..4] != MAGIC {
+            return Err("not an opening book".to_string());
+        }
+        let header_len = match bytes[4] {
+            1 => 12,
+            VERSION => 13,
+            version => return Err(format!("unsupported book version {version}")),
+        };
+        let (header, body) = bytes
+            .split_at_checked(header_len)
+            .ok_or("book is truncated")?;
+        let flags = if header_len == 13 { header[8] } else { 0 };
+        if header[5..7] != [6, 7] {
+            return Err(format!(
+                "book is for a {}x{} board, expected 6x7",
+                header[5], header[6]
+            ));
+        }
+        let count = u32::from_le_bytes(header[header_len - 4..].try_into().unwrap()) as usize;
+        if count.checked_mul(ENTRY_BYTES) != Some(body.len()) {
+            return Err(format!(
+                "book declares {count} entries but holds {} bytes of entries",
+                body.len()
+            ));
+        }
+        let entries = body
+            .chunks_exact(ENTRY_BYTES)
+            .enumerate()
+            .map(|(i, e)| {
+                let key = u64::from_le_bytes(e[..8].try_into().unwrap());
+                let value = i32::from_le_bytes(e[9..].try_into().unwrap());
+                let best_move = e[8];
+                // Mirroring in `lookup` relies on moves being on the board.
+                if best_move > 6 {
+                    return Err(format!("book entry {i} has invalid move {best_move}"));
+                }
+                Ok((key, BookEntry { best_move, value }))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            plies: header[7],
+            exact: flags & EXACT_FLAG != 0,
+            entries,
+        })
+    }
+
+    /// Saves the book to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = io::BufWriter::new(fs::File::create(path)?);
+        self.write_to(&mut out)?;
+        out.flush()
+    }
+
+    /// Loads a book from `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let file =
+            fs::File::open(path).map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+        Self::read_from(io::BufReader::new(file))
+    }
+
+    /// Loads the book named by `$SOLVER_BOOK`, or `c4.book` in the working
+    /// directory if the variable is unset.
+    ///
+    /// Returns `Ok(None)` if there is no book file, so callers can use a book
+    /// whenever one has been built without requiring it.
+    pub fn load_default() -> Result<Option<Self>, String> {
+        let path = std::env::var(BOOK_PATH_ENV).unwrap_or_else(|_| DEFAULT_BOOK_PATH.to_string());
+        if !Path::new(&path).exists() {
+            return Ok(None);
+        }
+        Self::load(&path).map(Some)
+    }
+}
+
+/// The best move and exact value of `state`, by a search to the end of
+/// the game.
+fn solve(state: &BitboardState, tt: &mut TranspositionTable<u8>) -> Option<(u8, i32)> {
+    let remaining = CELLS - state.ply().unwrap_or(0);
+    minimax_best_move_ab_depth_tt(state, remaining, tt).0
+}
+
+/// Plays book moves while the position is in the book and defers to
+/// `fallback` afterwards.
+#[derive(Clone, Debug)]
+pub struct BookAgent<A> {
+    pub book: Arc<OpeningBook>,
+    /// Agent used once the game leaves the book.
+    pub fallback: A,
+}
+
+impl<A> BookAgent<A> {
+    /// Wraps `fallback` so it plays from `book` while it can.
+    pub fn new(book: Arc<OpeningBook>, fallback: A) -> Self {
+        Self { book, fallback }
+    }
+}
+
+impl<A: Agent<BitboardState>> Agent<BitboardState> for BookAgent<A> {
+    fn select_move(&mut self, state: &BitboardState) -> Option<Decision<u8>> {
+        match self.book.lookup(state) {
+            Some(entry) if !state.is_terminal() => Some(Decision {
+                mv: entry.best_move,
+                value: Some(entry.value),
+            }),
+            _ => self.fallback.select_move(state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::MinimaxAgent;
+    use crate::game::Player;
+    use crate::solvers::minimax::WIN_SCORE;
+
+    #[test]
+    fn c4_book_covers_requested_plies_and_round_trips() {
+        let mut calls = 0;
+        let book = OpeningBook::build(3, 2, |_, _| calls += 1);
+        // 1 + 4 one-ply + 25 two-ply positions, up to mirroring.
+        assert_eq!(book.len(), 30);
+        assert_eq!(calls, 30);
+
+        let mut bytes = Vec::new();
+        book.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 13 + 30 * ENTRY_BYTES);
+        assert_eq!(OpeningBook::read_from(&bytes[..]).unwrap(), book);
+        assert!(OpeningBook::read_from(&bytes[..20]).is_err());
+
+        // A corrupt move would underflow when mirrored by `lookup`.
+        bytes[13 + 8] = 7;
+        assert!(OpeningBook::read_from(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn version_1_books_are_still_read() {
+        let book = OpeningBook::build(2, 2, |_, _| {});
+        let mut bytes = Vec::new();
+        book.write_to(&mut bytes).unwrap();
+        bytes[4] = 1;
+        bytes.remove(8);
+        assert_eq!(OpeningBook::read_from(&bytes[..]).unwrap(), book);
+    }
+
+    #[test]
+    fn exact_books_answer_lookup_exact() {
+        // Stand-in values: Player2 wins after a first move in column 0,
+        // and everything else is a draw.
+        let mut book = OpeningBook::build_with(
+            2,
+            |state| {
+                let value = if state.key() == BitboardState::from_move_sequence("0").unwrap().key()
+                {
+                    -WIN_SCORE
+                } else {
+                    0
+                };
+                Some((3, value))
+            },
+            |_, _| {},
+        );
+        let searched = BitboardState::from_move_sequence("6").unwrap();
+        assert_eq!(book.lookup_exact(&searched), None);
+
+        book.exact = true;
+        let mut bytes = Vec::new();
+        book.write_to(&mut bytes).unwrap();
+        let book = OpeningBook::read_from(&bytes[..]).unwrap();
+        assert!(book.is_exact());
+        // Column 6 mirrors column 0.
+        assert_eq!(
+            book.lookup_exact(&searched),
+            Some(Outcome::Win(Player::Player2))
+        );
+        assert_eq!(
+            book.lookup_exact(&BitboardState::new()),
+            Some(Outcome::Draw)
+        );
+        assert_eq!(
+            book.lookup_exact(&BitboardState::from_move_sequence("33").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn resumed_builds_only_solve_the_missing_positions() {
+        let path = std::env::temp_dir().join(format!("resume-{}.book", std::process::id()));
+        let checkpoint = Checkpoint {
+            path: path.clone(),
+            interval: Duration::ZERO,
+            resume: true,
+        };
+        let search = |state: &BitboardState| Some((3, state.key() as i32));
+        let full = OpeningBook::build_resumable_with(3, &checkpoint, search, |_, _| {}).unwrap();
+        assert_eq!(full.len(), 30);
+        assert!(!path.exists());
+
+        // An interrupted build left the first ten positions behind.
+        let mut partial = full.clone();
+        let mut keys: Vec<u64> = partial.entries.keys().copied().collect();
+        keys.sort();
+        for key in &keys[10..] {
+            partial.entries.remove(key);
+        }
+        partial.save(&path).unwrap();
+        let mut searched = 0;
+        let mut last = (0, 0);
+        let resumed = OpeningBook::build_resumable_with(
+            3,
+            &checkpoint,
+            |state| {
+                searched += 1;
+                search(state)
+            },
+            |done, total| last = (done, total),
+        )
+        .unwrap();
+        assert_eq!(resumed, full);
+        assert_eq!((searched, last), (20, (30, 30)));
+
+        partial.save(&path).unwrap();
+        assert!(OpeningBook::build_resumable_with(2, &checkpoint, search, |_, _| {}).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn late_positions_are_solved_exactly() {
+        let state = BitboardState::from_move_sequence("6426123360244453").unwrap();
+        let mut tt = TranspositionTable::with_entries(1 << 16, ReplacementPolicy::DepthPreferred);
+        let (best, value) = solve(&state, &mut tt).unwrap();
+        assert!(value <= -WIN_SCORE);
+        assert!(state.can_play(best));
+    }
+
+    #[test]
+    fn c4_book_moves_match_search_including_mirrored_positions() {
+        let book = OpeningBook::build(3, 4, |_, _| {});
+        for seq in ["", "0", "6", "25", "41"] {
+            let state = BitboardState::from_move_sequence(seq).unwrap();
+            let entry = book.lookup(&state).unwrap();
+            let (_, value) = minimax_best_move_ab_depth(&state, 4).unwrap();
+            assert_eq!(entry.value, value, "position {seq:?}");
+            assert!(state.can_play(entry.best_move));
+            let after = minimax_best_move_ab_depth(&state.apply_column_move(entry.best_move), 3);
+            assert_eq!(after.map(|(_, v)| v), Some(value), "position {seq:?}");
+        }
+        let beyond = BitboardState::from_move_sequence("333").unwrap();
+        assert_eq!(book.lookup(&beyond), None);
+    }
+
+    #[test]
+    fn c4_book_agent_falls_back_outside_the_book() {
+        let book = Arc::new(OpeningBook::build(1, 2, |_, _| {}));
+        let mut agent = BookAgent::new(book.clone(), MinimaxAgent::new(2));
+        let first = agent.select_move(&BitboardState::new()).unwrap();
+        assert_eq!(
+            Some(first.mv),
+            book.lookup(&BitboardState::new()).map(|e| e.best_move)
+        );
+        let later = BitboardState::from_move_sequence("33").unwrap();
+        assert!(agent.select_move(&later).is_some());
+    }
+}
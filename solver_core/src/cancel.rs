@@ -0,0 +1,82 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+#[cfg(feature = "std")]
+use std::thread;
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+/// A flag for stopping a running search from another thread (e.g. a
+/// Ctrl-C handler or a server timeout).
+///
+/// Clones share the same flag. Searches poll it cooperatively and return
+/// early once it is set; the token stays cancelled until `reset`.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every search polling this token to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true once `cancel` has been called (and not reset since).
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clears the flag so the token can be used for another search.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    /// Cancels the token after `timeout` on a background thread, unless the
+    /// returned timer is dropped first.
+    ///
+    /// Used to bound a search by time: start the timer, run the search, and
+    /// drop the timer once the search returns.
+    #[cfg(feature = "std")]
+    pub fn cancel_after(&self, timeout: Duration) -> CancelTimer {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let token = self.clone();
+        thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(timeout) {
+                token.cancel();
+            }
+        });
+        CancelTimer { _stop: stop }
+    }
+}
+
+/// A pending `CancelToken::cancel_after`; dropping it disarms the timer.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct CancelTimer {
+    // Dropping the sender wakes the timer thread, which then exits.
+    _stop: Sender<()>,
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timer_cancels_unless_dropped() {
+        let token = CancelToken::new();
+        let timer = token.cancel_after(Duration::from_millis(10));
+        thread::sleep(Duration::from_millis(200));
+        assert!(token.is_cancelled());
+        drop(timer);
+
+        token.reset();
+        drop(token.cancel_after(Duration::from_millis(10)));
+        thread::sleep(Duration::from_millis(100));
+        assert!(!token.is_cancelled());
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::dyn_game::DynGameState;
+use crate::game::{Outcome, Player};
+use crate::registry::Registry;
+use crate::render::BoardStyle;
+use crate::sessions::{SessionError, SessionId, SessionManager};
+use crate::solvers::minimax::WIN_SCORE;
+use crate::time_manager::{InstantTimeSource, TimeSource};
+
+/// Chat commands for games of a registry, one game per channel and game,
+/// so a chat bot only needs to pass messages in and replies out.
+///
+/// Commands are `!GAME COMMAND [ARGS]`, e.g. `!c4 play 3`:
+///
+/// - `new [MOVES]` starts a game, optionally after a game record such as
+///   `0314`;
+/// - `play MOVE` plays a move (starting a game if none is in progress), to
+///   which the engine replies unless the game is over;
+/// - `board` shows the position;
+/// - `hint` shows the engine's choice without playing it;
+/// - `resign` ends the game;
+/// - `help` lists the commands.
+///
+/// `!games` lists the games.
+pub struct ChatBot<T = InstantTimeSource> {
+    sessions: SessionManager<T>,
+    channels: Mutex<HashMap<(String, &'static str), SessionId>>,
+    prefix: String,
+    style: BoardStyle,
+    engine_replies: bool,
+}
+
+const COMMANDS: &str = "new [MOVES], play MOVE, board, hint, resign, help";
+
+impl ChatBot<InstantTimeSource> {
+    /// A bot for the games of `registry`, forgetting games idle for
+    /// `idle_timeout`.
+    pub fn new(registry: Registry, idle_timeout: Duration) -> Self {
+        Self::with_sessions(SessionManager::new(registry, idle_timeout))
+    }
+}
+
+impl<T: TimeSource> ChatBot<T> {
+    /// A bot keeping its games in `sessions`.
+    pub fn with_sessions(sessions: SessionManager<T>) -> Self {
+        Self {
+            sessions,
+            channels: Mutex::new(HashMap::new()),
+            prefix: "!".to_string(),
+            style: BoardStyle::default(),
+            engine_replies: true,
+        }
+    }
+
+    /// Sets the text commands start with (`!` by default).
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Sets how boards are drawn.
+    pub fn style(mut self, style: BoardStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets whether the engine answers each move (the default), or the
+    /// channel plays both sides.
+    pub fn engine_replies(mut self, on: bool) -> Self {
+        self.engine_replies = on;
+        self
+    }
+
+    /// The sessions behind the bot, e.g. for `expire_idle`.
+    pub fn sessions(&self) -> &SessionManager<T> {
+        &self.sessions
+    }
+
+    fn channels(&self) -> MutexGuard<'_, HashMap<(String, &'static str), SessionId>> {
+        self.channels.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Handles `message`, sent in `channel`. Returns the reply, or `None`
+    /// if the message is not a command for the bot.
+    pub fn handle(&self, channel: &str, message: &str) -> Option<String> {
+        let command = message.trim().strip_prefix(self.prefix.as_str())?;
+        let mut words = command.split_whitespace();
+        let game = words.next()?;
+        if game == "games" {
+            let names: Vec<String> = self
+                .sessions
+                .registry()
+                .games()
+                .iter()
+                .map(|g| format!("{} ({})", g.name, g.display_name))
+                .collect();
+            return Some(format!("games: {}", names.join(", ")));
+        }
+        let name = self.sessions.registry().get(game)?.name;
+        let args: Vec<&str> = words.collect();
+        let key = (channel.to_string(), name);
+        Some(match self.run(&key, &args) {
+            Ok(reply) => reply,
+            Err(SessionError::UnknownSession(_)) => {
+                self.channels().remove(&key);
+                format!(
+                    "no game in progress; start one with {}{name} new",
+                    self.prefix
+                )
+            }
+            Err(e) => e.to_string(),
+        })
+    }
+
+    fn run(&self, key: &(String, &'static str), args: &[&str]) -> Result<String, SessionError> {
+        let (_, game) = *key;
+        let current = self.channels().get(key).copied();
+        let session = || current.ok_or(SessionError::UnknownSession(0));
+        match args {
+            ["new"] | ["new", _] => {
+                let moves = args.get(1).copied().unwrap_or("");
+                let id = self.sessions.create_from(game, moves)?;
+                if let Some(old) = self.channels().insert(key.clone(), id) {
+                    self.sessions.remove(old);
+                }
+                Ok(self.board(self.sessions.state(id)?.as_ref()))
+            }
+            ["play", mv] => {
+                let id = match current {
+                    Some(id) => id,
+                    None => {
+                        let id = self.sessions.create(game)?;
+                        self.channels().insert(key.clone(), id);
+                        id
+                    }
+                };
+                let mut state = self.sessions.play(id, mv)?;
+                let mut reply = String::new();
+                if self.engine_replies && !state.is_terminal() {
+                    state = self.reply(id, state.as_ref(), &mut reply)?;
+                }
+                Ok(reply + &self.board(state.as_ref()))
+            }
+            ["board"] => Ok(self.board(self.sessions.state(session()?)?.as_ref())),
+            ["hint"] => {
+                let state = self.sessions.state(session()?)?;
+                let entry = self.sessions.registry().get(game);
+                let depth = entry.map_or(1, |e| e.default_depth);
+                let result = state.search(depth, None);
+                Ok(match (result.best_move(), result.value()) {
+                    (Some(mv), Some(value)) => format!("hint: {mv} ({})", describe_value(value)),
+                    _ => "the game is over".to_string(),
+                })
+            }
+            ["resign"] => {
+                let id = session()?;
+                self.channels().remove(key);
+                self.sessions.remove(id);
+                Ok("game over: resigned".to_string())
+            }
+            ["help"] | [] => Ok(format!("commands: {COMMANDS}")),
+            _ => Ok(format!(
+                "unknown command '{}'; commands: {COMMANDS}",
+                args.join(" ")
+            )),
+        }
+    }
+
+    /// Plays the engine's move in session `id`, at `state`, noting it in
+    /// `reply`.
+    fn reply(
+        &self,
+        id: SessionId,
+        state: &dyn DynGameState,
+        reply: &mut String,
+    ) -> Result<Box<dyn DynGameState>, SessionError> {
+        let depth = self
+            .sessions
+            .registry()
+            .get(state.game_name())
+            .map_or(1, |e| e.default_depth);
+        let result = state.search(depth, None);
+        let mv = result.best_move().expect("a game in progress has moves");
+        reply.push_str(&format!("I play {mv}\n"));
+        self.sessions.play(id, mv.notation())
+    }
+
+    /// The position drawn in the bot's style, followed by its status.
+    fn board(&self, state: &dyn DynGameState) -> String {
+        let mut out = state.render_as(self.style);
+        out.push_str(&match state.outcome() {
+            None => format!("{} to move", player_mark(state.current_player())),
+            Some(Outcome::Win(player)) => format!("game over: {} wins", player_mark(player)),
+            Some(Outcome::Draw) => "game over: draw".to_string(),
+        });
+        out
+    }
+}
+
+fn player_mark(player: Player) -> &'static str {
+    match player {
+        Player::Player1 => "X",
+        Player::Player2 => "O",
+    }
+}
+
+/// A search value (Player1's perspective) in words.
+fn describe_value(value: i32) -> String {
+    if value >= WIN_SCORE {
+        "X wins".to_string()
+    } else if value <= -WIN_SCORE {
+        "O wins".to_string()
+    } else {
+        format!("eval {value:+}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bot() -> ChatBot {
+        ChatBot::new(Registry::builtin(), Duration::from_secs(600))
+    }
+
+    #[test]
+    fn commands_drive_a_game_per_channel() {
+        let bot = bot().engine_replies(false);
+        assert_eq!(bot.handle("#a", "hello"), None);
+        assert_eq!(bot.handle("#a", "!chess play e4"), None);
+        assert_eq!(
+            bot.handle("#a", "!c4 board").unwrap(),
+            "no game in progress; start one with !c4 new"
+        );
+
+        let reply = bot.handle("#a", "!c4 play 3").unwrap();
+        assert!(reply.ends_with(". | . | . | X | . | . | .\nO to move"));
+        let reply = bot.handle("#b", "!ttt new 0314").unwrap();
+        assert!(reply.ends_with("X to move"));
+        let reply = bot.handle("#b", "!ttt hint").unwrap();
+        assert_eq!(reply, "hint: 2 (X wins)");
+        let reply = bot.handle("#b", "!ttt play 2").unwrap();
+        assert!(reply.ends_with("game over: X wins"));
+
+        assert_eq!(
+            bot.handle("#a", "!c4 play 9").unwrap(),
+            "illegal move: the move is off the board"
+        );
+        assert!(
+            bot.handle("#a", "!c4 dance")
+                .unwrap()
+                .starts_with("unknown command 'dance'")
+        );
+        assert_eq!(
+            bot.handle("#a", "!c4 resign").unwrap(),
+            "game over: resigned"
+        );
+        assert!(
+            bot.handle("#a", "!c4 hint")
+                .unwrap()
+                .starts_with("no game in progress")
+        );
+        assert_eq!(
+            bot.handle("#a", "!games").unwrap(),
+            "games: ttt (Tic-Tac-Toe), c4 (Connect Four), c4-8x7 (Connect Four 8x7), score4 (Score Four), pentago (Pentago), breakthrough (Breakthrough)"
+        );
+    }
+
+    #[test]
+    fn engine_answers_moves_and_boards_can_be_emoji() {
+        let bot = bot().style(BoardStyle::Emoji).prefix("?");
+        let reply = bot.handle("#a", "?ttt play 0").unwrap();
+        let lines: Vec<&str> = reply.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("I play "));
+        assert_eq!(lines[1].chars().count(), 3);
+        assert_eq!(lines[1].chars().next(), Some('❌'));
+        assert_eq!(reply.matches('⭕').count(), 1);
+        assert_eq!(lines[4], "X to move");
+    }
+}
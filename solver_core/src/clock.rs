@@ -0,0 +1,211 @@
+use core::time::Duration;
+
+use crate::game::Player;
+
+/// How much time each player gets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeControl {
+    /// A fixed amount of time for the whole game.
+    Absolute(Duration),
+    /// `base` for the whole game, plus `increment` after every move.
+    Increment { base: Duration, increment: Duration },
+    /// A fixed limit for each move; unused time is not carried over.
+    PerMove(Duration),
+}
+
+/// A move took longer than the time left on the clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeExpired;
+
+impl core::fmt::Display for TimeExpired {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("time expired")
+    }
+}
+
+impl core::error::Error for TimeExpired {}
+
+/// One player's clock.
+///
+/// The game loop measures how long each move took and passes it to
+/// `record_move`; the clock never reads the system time itself, which keeps
+/// it deterministic in tests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Clock {
+    control: TimeControl,
+    remaining: Duration,
+    moves: u32,
+}
+
+/// Under `Absolute` and `Increment` controls, a move gets the remaining
+/// time divided by this: roughly the moves left in a typical game.
+const MOVES_TO_GO: u32 = 20;
+/// Fraction of a per-move limit the AI plans to use, leaving a margin for
+/// the search to notice it was cancelled.
+const PER_MOVE_SAFETY: f64 = 0.9;
+
+impl Clock {
+    /// A fresh clock for `control`.
+    pub fn new(control: TimeControl) -> Self {
+        let remaining = match control {
+            TimeControl::Absolute(total) => total,
+            TimeControl::Increment { base, .. } => base,
+            TimeControl::PerMove(limit) => limit,
+        };
+        Self {
+            control,
+            remaining,
+            moves: 0,
+        }
+    }
+
+    /// A clock on `control` with `remaining` left after `moves` moves, e.g.
+    /// as saved from `remaining` and `moves` to resume a game later.
+    pub fn restore(control: TimeControl, remaining: Duration, moves: u32) -> Self {
+        Self {
+            control,
+            remaining,
+            moves,
+        }
+    }
+
+    /// The clock's time control.
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// Time left for the current move before the player loses on time.
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    /// Moves recorded so far.
+    pub fn moves(&self) -> u32 {
+        self.moves
+    }
+
+    /// Returns true once the clock has run out.
+    pub fn is_expired(&self) -> bool {
+        self.remaining.is_zero()
+    }
+
+    /// Charges a move that took `elapsed`, then adds any increment (or
+    /// resets a per-move limit).
+    ///
+    /// Fails, leaving the clock at zero, if the move took longer than the
+    /// time remaining.
+    pub fn record_move(&mut self, elapsed: Duration) -> Result<(), TimeExpired> {
+        if elapsed > self.remaining {
+            self.remaining = Duration::ZERO;
+            return Err(TimeExpired);
+        }
+        self.moves += 1;
+        self.remaining = match self.control {
+            TimeControl::Absolute(_) => self.remaining - elapsed,
+            TimeControl::Increment { increment, .. } => self.remaining - elapsed + increment,
+            TimeControl::PerMove(limit) => limit,
+        };
+        Ok(())
+    }
+
+    /// How long an engine should think about its next move: a fixed share
+    /// of the remaining time, plus most of the increment, or most of a
+    /// per-move limit.
+    pub fn budget(&self) -> Duration {
+        match self.control {
+            TimeControl::Absolute(_) => self.remaining / MOVES_TO_GO,
+            TimeControl::Increment { increment, .. } => {
+                let planned = self.remaining / MOVES_TO_GO + increment.mul_f64(0.75);
+                planned.min(self.remaining.mul_f64(PER_MOVE_SAFETY))
+            }
+            TimeControl::PerMove(limit) => limit.mul_f64(PER_MOVE_SAFETY),
+        }
+    }
+}
+
+/// The clocks of both players.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameClock {
+    pub player1: Clock,
+    pub player2: Clock,
+}
+
+impl GameClock {
+    /// Both players on the same time control.
+    pub fn new(control: TimeControl) -> Self {
+        Self {
+            player1: Clock::new(control),
+            player2: Clock::new(control),
+        }
+    }
+
+    /// The clock of `player`.
+    pub fn get(&self, player: Player) -> &Clock {
+        match player {
+            Player::Player1 => &self.player1,
+            Player::Player2 => &self.player2,
+        }
+    }
+
+    /// The clock of `player`, for recording a move.
+    pub fn get_mut(&mut self, player: Player) -> &mut Clock {
+        match player {
+            Player::Player1 => &mut self.player1,
+            Player::Player2 => &mut self.player2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn increment_is_added_after_each_move() {
+        let mut clock = Clock::new(TimeControl::Increment {
+            base: 10 * SEC,
+            increment: 2 * SEC,
+        });
+        clock.record_move(3 * SEC).unwrap();
+        assert_eq!(clock.remaining(), 9 * SEC);
+        assert_eq!(clock.moves(), 1);
+        let resumed = Clock::restore(clock.control(), clock.remaining(), clock.moves());
+        assert_eq!(resumed, clock);
+        assert_eq!(clock.record_move(10 * SEC), Err(TimeExpired));
+        assert!(clock.is_expired());
+    }
+
+    #[test]
+    fn per_move_limit_does_not_carry_over() {
+        let mut clock = Clock::new(TimeControl::PerMove(5 * SEC));
+        clock.record_move(SEC).unwrap();
+        assert_eq!(clock.remaining(), 5 * SEC);
+        assert!(clock.record_move(5 * SEC).is_ok());
+        assert!(clock.record_move(6 * SEC).is_err());
+    }
+
+    #[test]
+    fn budget_stays_within_the_remaining_time() {
+        let absolute = Clock::new(TimeControl::Absolute(60 * SEC));
+        assert_eq!(absolute.budget(), 3 * SEC);
+
+        let mut low = Clock::new(TimeControl::Increment {
+            base: 10 * SEC,
+            increment: SEC,
+        });
+        low.record_move(10 * SEC).unwrap();
+        assert_eq!(low.remaining(), SEC);
+        assert!(low.budget() < low.remaining());
+
+        let per_move = Clock::new(TimeControl::PerMove(10 * SEC));
+        assert_eq!(per_move.budget(), 9 * SEC);
+
+        let mut game = GameClock::new(TimeControl::Absolute(SEC));
+        game.get_mut(Player::Player2).record_move(SEC / 2).unwrap();
+        assert_eq!(game.get(Player::Player1).remaining(), SEC);
+        assert_eq!(game.player2.remaining(), SEC / 2);
+    }
+}
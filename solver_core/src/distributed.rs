@@ -0,0 +1,420 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::game::Player;
+use crate::registry::Registry;
+use crate::solvers::minimax::WIN_SCORE;
+
+/// How often an idle coordinator connection checks for jobs handed back.
+const IDLE_POLL: Duration = Duration::from_millis(10);
+
+/// How long `distributed_search` waits for a worker's answer unless told
+/// otherwise.
+pub const DEFAULT_JOB_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// One root move to search: the position the game `game` reaches after
+/// the moves `position` (see `GameEntry::from_notation`), then `root_move`,
+/// searched to `depth` plies in all.
+///
+/// On the wire a job is one line of tab-separated fields:
+/// `SEARCH`, the game, the depth, the position and the root move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Job {
+    pub game: String,
+    pub position: String,
+    pub root_move: String,
+    /// Plies searched, the root move included.
+    pub depth: u32,
+}
+
+/// A worker's answer to a `Job`.
+///
+/// On the wire: `RESULT`, the value and the node count, tab-separated, or
+/// `ERROR` and a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JobResult {
+    /// The value of the position after the root move from Player1's
+    /// perspective, on the `PvLine::value` scale.
+    pub value: i32,
+    /// Positions the worker visited.
+    pub nodes: u64,
+}
+
+/// The merged result of a distributed search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DistributedResult {
+    /// Every root move with its value, best first for the player to move.
+    pub lines: Vec<(String, i32)>,
+    /// Positions visited by all workers.
+    pub nodes: u64,
+}
+
+impl DistributedResult {
+    /// The best root move, if there is one.
+    pub fn best_move(&self) -> Option<&str> {
+        self.lines.first().map(|(mv, _)| mv.as_str())
+    }
+
+    /// The value of the best root move, if there is one.
+    pub fn value(&self) -> Option<i32> {
+        self.lines.first().map(|&(_, value)| value)
+    }
+}
+
+impl Job {
+    fn to_line(&self) -> String {
+        format!(
+            "SEARCH\t{}\t{}\t{}\t{}\n",
+            self.game, self.depth, self.position, self.root_move
+        )
+    }
+
+    fn parse(line: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split('\t').collect();
+        let ["SEARCH", game, depth, position, root_move] = fields[..] else {
+            return Err(format!("malformed job '{}'", line.trim_end()));
+        };
+        Ok(Self {
+            game: game.to_string(),
+            position: position.to_string(),
+            root_move: root_move.to_string(),
+            depth: depth
+                .parse()
+                .ok()
+                .filter(|&d| d > 0)
+                .ok_or_else(|| format!("invalid depth '{depth}'"))?,
+        })
+    }
+
+    /// Searches the job on this machine.
+    pub fn run(&self, registry: &Registry) -> Result<JobResult, String> {
+        let entry = registry
+            .get(&self.game)
+            .ok_or_else(|| format!("unknown game '{}'", self.game))?;
+        let state = (entry.from_notation)(&self.position)
+            .and_then(|root| root.play(&self.root_move))
+            .map_err(|e| e.to_string())?;
+        if let Some(outcome) = state.outcome() {
+            return Ok(JobResult {
+                value: outcome.value() * WIN_SCORE,
+                nodes: 1,
+            });
+        }
+        // The root move was the whole search: score the position it leads
+        // to as a search would score a leaf.
+        if self.depth == 1 {
+            return Ok(JobResult {
+                value: state.heuristic_value(),
+                nodes: 1,
+            });
+        }
+        let result = state.search(self.depth - 1, None);
+        Ok(JobResult {
+            value: result.value().ok_or("the position has no moves")?,
+            nodes: result.stats.nodes,
+        })
+    }
+}
+
+fn parse_reply(line: &str) -> Result<JobResult, String> {
+    let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split('\t').collect();
+    match fields[..] {
+        ["RESULT", value, nodes] => Ok(JobResult {
+            value: value
+                .parse()
+                .map_err(|_| format!("invalid value '{value}'"))?,
+            nodes: nodes
+                .parse()
+                .map_err(|_| format!("invalid node count '{nodes}'"))?,
+        }),
+        ["ERROR", message] => Err(message.to_string()),
+        _ => Err(format!("malformed reply '{}'", line.trim_end())),
+    }
+}
+
+/// Answers the jobs sent over `stream`, one line each, until the other
+/// end closes it.
+pub fn serve_connection(stream: TcpStream, registry: &Registry) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let reply = match Job::parse(&line?).and_then(|job| job.run(registry)) {
+            Ok(result) => format!("RESULT\t{}\t{}\n", result.value, result.nodes),
+            Err(e) => format!("ERROR\t{}\n", e.replace(['\t', '\n'], " ")),
+        };
+        writer.write_all(reply.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Runs a worker: answers jobs from every connection to `listener`, each
+/// connection on its own thread, until accepting fails.
+///
+/// A coordinator sends one job at a time per connection, so it can keep
+/// several of a machine's cores busy by connecting to it several times.
+pub fn serve(listener: TcpListener, registry: &Registry) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept()?;
+        let registry = registry.clone();
+        thread::spawn(move || serve_connection(stream, &registry));
+    }
+}
+
+/// Searches the position after `position` in `game` to `depth` plies by
+/// splitting its root moves among `workers`, and merges their answers.
+///
+/// Each worker address gets its own connection, which takes the next
+/// unsearched root move whenever it finishes one, so faster machines
+/// search more of them. A worker that can't be reached, drops its
+/// connection or takes longer than `timeout` to answer hands its move back
+/// to the others; the search fails only if every worker does, or if a
+/// worker reports an error (such as an illegal position).
+pub fn distributed_search<A: ToSocketAddrs + Sync>(
+    registry: &Registry,
+    game: &str,
+    position: &str,
+    depth: u32,
+    workers: &[A],
+    timeout: Duration,
+) -> Result<DistributedResult, String> {
+    let entry = registry
+        .get(game)
+        .ok_or_else(|| format!("unknown game '{game}'"))?;
+    let root = (entry.from_notation)(position).map_err(|e| e.to_string())?;
+    if depth == 0 {
+        return Err("depth must be at least 1".to_string());
+    }
+    if workers.is_empty() {
+        return Err("no workers".to_string());
+    }
+    let queue: Mutex<VecDeque<Job>> = Mutex::new(
+        root.legal_moves()
+            .into_iter()
+            .map(|root_move| Job {
+                game: game.to_string(),
+                position: position.to_string(),
+                root_move,
+                depth,
+            })
+            .collect(),
+    );
+    // Jobs without a result, queued or being searched.
+    let unfinished = AtomicUsize::new(queue.lock().unwrap().len());
+    let results = Mutex::new(Vec::new());
+    let failure = Mutex::new(None);
+    let (queue_ref, results_ref, failure_ref, unfinished_ref) =
+        (&queue, &results, &failure, &unfinished);
+    let worker_errors: Vec<String> = thread::scope(|scope| {
+        let handles: Vec<_> = workers
+            .iter()
+            .map(|addr| {
+                let (queue, results, failure, unfinished) =
+                    (queue_ref, results_ref, failure_ref, unfinished_ref);
+                scope.spawn(move || {
+                    let stream = TcpStream::connect(addr).map_err(|e| e.to_string())?;
+                    stream
+                        .set_read_timeout(Some(timeout))
+                        .map_err(|e| e.to_string())?;
+                    let mut writer = stream.try_clone().map_err(|e| e.to_string())?;
+                    let mut reader = BufReader::new(stream);
+                    loop {
+                        if failure.lock().unwrap().is_some() {
+                            return Ok(());
+                        }
+                        let Some(job) = queue.lock().unwrap().pop_front() else {
+                            if unfinished.load(Ordering::SeqCst) == 0 {
+                                return Ok(());
+                            }
+                            // Another worker may drop its connection and
+                            // hand its job back.
+                            thread::sleep(IDLE_POLL);
+                            continue;
+                        };
+                        let mut reply = String::new();
+                        let sent = writer
+                            .write_all(job.to_line().as_bytes())
+                            .and_then(|()| reader.read_line(&mut reply));
+                        match sent {
+                            Ok(n) if n > 0 => match parse_reply(&reply) {
+                                Ok(result) => {
+                                    results.lock().unwrap().push((job.root_move, result));
+                                    unfinished.fetch_sub(1, Ordering::SeqCst);
+                                }
+                                Err(e) => {
+                                    failure.lock().unwrap().get_or_insert(format!(
+                                        "worker failed on move {}: {e}",
+                                        job.root_move
+                                    ));
+                                    return Ok(());
+                                }
+                            },
+                            lost => {
+                                queue.lock().unwrap().push_back(job);
+                                return Err(match lost {
+                                    Err(e) => e.to_string(),
+                                    Ok(_) => "connection closed".to_string(),
+                                });
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().expect("worker thread panicked").err())
+            .collect()
+    });
+    if let Some(e) = failure.into_inner().unwrap() {
+        return Err(e);
+    }
+    if !queue.into_inner().unwrap().is_empty() {
+        return Err(format!("every worker failed: {}", worker_errors.join("; ")));
+    }
+
+    let mut results = results.into_inner().unwrap();
+    let maximizing = root.current_player() == Player::Player1;
+    // Best first, ties in the order the moves were generated.
+    let order = root.legal_moves();
+    let position_of = |mv: &String| order.iter().position(|m| m == mv);
+    results.sort_by_key(|(mv, result)| {
+        let value = if maximizing {
+            -i64::from(result.value)
+        } else {
+            i64::from(result.value)
+        };
+        (value, position_of(mv))
+    });
+    Ok(DistributedResult {
+        nodes: results.iter().map(|(_, result)| result.nodes).sum(),
+        lines: results
+            .into_iter()
+            .map(|(mv, result)| (mv, result.value))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4_bitboard::{BitboardState, BitboardState8x7};
+    use crate::games::ttt::TicTacToeState;
+    use crate::solvers::search::Search;
+
+    /// Starts a worker on a free local port and returns its address.
+    fn start_worker() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || serve(listener, &Registry::builtin()));
+        addr
+    }
+
+    #[test]
+    fn jobs_and_replies_round_trip() {
+        let job = Job {
+            game: "pentago".to_string(),
+            position: "14/2R 3/1L".to_string(),
+            root_move: "0/0R".to_string(),
+            depth: 3,
+        };
+        assert_eq!(Job::parse(&job.to_line()), Ok(job));
+        assert!(Job::parse("SEARCH\tc4\t0\t33\t4\n").is_err());
+        assert!(Job::parse("SOLVE\tc4\t3\t33\t4\n").is_err());
+        assert_eq!(
+            parse_reply("RESULT\t-12\t345\n"),
+            Ok(JobResult {
+                value: -12,
+                nodes: 345
+            })
+        );
+        assert_eq!(
+            parse_reply("ERROR\tbad move\n"),
+            Err("bad move".to_string())
+        );
+    }
+
+    /// Searches with the builtin games and the default timeout.
+    fn search(
+        game: &str,
+        position: &str,
+        depth: u32,
+        workers: &[&str],
+    ) -> Result<DistributedResult, String> {
+        let registry = Registry::builtin();
+        distributed_search(
+            &registry,
+            game,
+            position,
+            depth,
+            workers,
+            DEFAULT_JOB_TIMEOUT,
+        )
+    }
+
+    #[test]
+    fn split_searches_agree_with_local_ones() {
+        let worker = start_worker();
+        let workers = [worker.as_str(), worker.as_str()];
+        let result = search("c4", "3342", 5, &workers).unwrap();
+        let state = BitboardState::from_move_sequence("3342").unwrap();
+        let local = Search::new(&state).depth(5).run();
+        assert_eq!(result.value(), local.value());
+        assert_eq!(result.lines.len(), 7);
+        assert!(result.nodes > 0);
+
+        // A root move that ends the game is scored without searching.
+        let result = search("c4", "001122", 3, &workers).unwrap();
+        assert_eq!(result.best_move(), Some("3"));
+        assert_eq!(result.value(), Some(WIN_SCORE));
+
+        // At depth 1 the root move is the whole search.
+        let result = search("ttt", "", 1, &workers).unwrap();
+        let local = Search::new(&TicTacToeState::new()).depth(1).run();
+        assert_eq!(result.lines.len(), 9);
+        assert_eq!(result.value(), local.value());
+        assert_eq!(result.nodes, 9);
+    }
+
+    #[test]
+    fn searches_connect_four_on_eight_columns() {
+        let worker = start_worker();
+        let result = search("c4-8x7", "34", 3, &[worker.as_str(), &worker]).unwrap();
+        assert_eq!(result.lines.len(), 8);
+        let state = BitboardState8x7::from_move_sequence("34").unwrap();
+        assert_eq!(result.value(), Search::new(&state).depth(3).run().value());
+    }
+
+    #[test]
+    fn unreachable_workers_leave_their_moves_to_the_others() {
+        let dead = TcpListener::bind("127.0.0.1:0").unwrap();
+        let dead_addr = dead.local_addr().unwrap().to_string();
+        drop(dead);
+        let worker = start_worker();
+        let result = search("ttt", "", 2, &[dead_addr.as_str(), &worker]).unwrap();
+        assert_eq!(result.lines.len(), 9);
+        assert!(search("ttt", "", 2, &[dead_addr.as_str()]).is_err());
+        assert!(search("go", "", 2, &[worker.as_str()]).is_err());
+    }
+
+    #[test]
+    fn hung_workers_time_out_and_leave_their_moves_to_the_others() {
+        // Accepts connections and never answers.
+        let hung = TcpListener::bind("127.0.0.1:0").unwrap();
+        let hung_addr = hung.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let _connections: Vec<_> = hung.incoming().collect();
+        });
+        let registry = Registry::builtin();
+        let worker = start_worker();
+        let timeout = Duration::from_millis(200);
+        let workers = [hung_addr.as_str(), &worker];
+        let result = distributed_search(&registry, "ttt", "", 2, &workers, timeout).unwrap();
+        assert_eq!(result.lines.len(), 9);
+        let err = distributed_search(&registry, "ttt", "", 2, &workers[..1], timeout).unwrap_err();
+        assert!(err.starts_with("every worker failed"), "{err}");
+    }
+}
@@ -0,0 +1,712 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::fmt;
+
+use crate::cancel::CancelToken;
+use crate::error::{MoveError, ParseError};
+use crate::game::{BoardGame, CellContent, GameState, Outcome, Player};
+use crate::registry::RegisteredGame;
+use crate::render::describe::{Vocabulary, describe, describe_in};
+use crate::render::svg::{SvgBoard, changed_cell, winning_line};
+use crate::render::{
+    BoardStyle, Glyphs, Labels, Layout, emoji_board_with, render_board, render_board_with,
+    unicode_board_with,
+};
+use crate::selection::Selection;
+use crate::solvers::iterative::{DepthReport, iterative_deepening};
+use crate::solvers::minimax::minimax_best_moves_ab_depth;
+use crate::solvers::search::{PvLine, Search, SearchResult};
+
+/// A move of some registered game, with its type erased.
+///
+/// It is played with `DynGameState::apply` in positions of the same game,
+/// and shows as its notation. Two moves are equal if they belong to the
+/// same game and have the same notation.
+pub struct DynMove {
+    game: &'static str,
+    text: String,
+    mv: Box<dyn ErasedMove>,
+}
+
+impl DynMove {
+    /// Erases `mv`, a move of game `G`.
+    pub fn new<G: RegisteredGame>(mv: G::Move) -> Self {
+        Self {
+            game: G::NAME,
+            text: G::format_move(&mv),
+            mv: Box::new(mv),
+        }
+    }
+
+    /// The registry name of the game the move belongs to.
+    pub fn game_name(&self) -> &'static str {
+        self.game
+    }
+
+    /// The move in its game's notation.
+    pub fn notation(&self) -> &str {
+        &self.text
+    }
+
+    /// The move as a `G::Move`, if it is a move of `G`.
+    pub fn downcast_ref<G: RegisteredGame>(&self) -> Option<&G::Move> {
+        if self.game != G::NAME {
+            return None;
+        }
+        self.mv.move_any().downcast_ref()
+    }
+}
+
+impl Clone for DynMove {
+    fn clone(&self) -> Self {
+        Self {
+            game: self.game,
+            text: self.text.clone(),
+            mv: self.mv.clone_move(),
+        }
+    }
+}
+
+impl PartialEq for DynMove {
+    fn eq(&self, other: &Self) -> bool {
+        self.game == other.game && self.text == other.text
+    }
+}
+
+impl Eq for DynMove {}
+
+impl fmt::Debug for DynMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DynMove({}: {})", self.game, self.text)
+    }
+}
+
+impl fmt::Display for DynMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// The boxed move inside a `DynMove`.
+trait ErasedMove: Send + Sync {
+    fn move_any(&self) -> &dyn Any;
+    fn clone_move(&self) -> Box<dyn ErasedMove>;
+}
+
+impl<M: Clone + Send + Sync + 'static> ErasedMove for M {
+    fn move_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_move(&self) -> Box<dyn ErasedMove> {
+        Box::new(self.clone())
+    }
+}
+
+/// A position of some registered game behind a trait object, so games of
+/// different types can be held and driven alike.
+///
+/// Moves are exchanged either as text in the game's notation (see
+/// `RegisteredGame::format_move`) or as `DynMove`s, and the position
+/// remembers the moves that led to it.
+pub trait DynGameState: Send + Sync {
+    /// The registry name of the game, e.g. `"c4"`.
+    fn game_name(&self) -> &'static str;
+
+    /// Returns the player whose turn it is.
+    fn current_player(&self) -> Player;
+
+    /// Returns true if the game is over.
+    fn is_terminal(&self) -> bool;
+
+    /// Returns how the game ended, or `None` while it is in progress.
+    fn outcome(&self) -> Option<Outcome>;
+
+    /// Returns the plies played from the start of the game, if the position
+    /// tells (see `GameState::ply`).
+    fn ply(&self) -> Option<u32>;
+
+    /// The game's static evaluation of the position, from Player1's
+    /// perspective (see `GameState::heuristic_value`).
+    fn heuristic_value(&self) -> i32;
+
+    /// The legal moves, in the game's move notation.
+    fn legal_moves(&self) -> Vec<String>;
+
+    /// The position after the move `mv`, given in the game's notation.
+    fn play(&self, mv: &str) -> Result<Box<dyn DynGameState>, ParseError>;
+
+    /// The legal moves.
+    fn moves(&self) -> Vec<DynMove>;
+
+    /// Parses a move in the game's notation and checks that it is legal.
+    fn parse_move(&self, text: &str) -> Result<DynMove, ParseError>;
+
+    /// The position after `mv`; `MoveError::ForeignMove` if it is a move of
+    /// another game.
+    fn apply(&self, mv: &DynMove) -> Result<Box<dyn DynGameState>, MoveError>;
+
+    /// The moves played from the initial position.
+    fn history(&self) -> Vec<DynMove>;
+
+    /// Searches the position to `depth` plies, as `Search::run`, stopping
+    /// early if `cancel` is cancelled.
+    fn search(&self, depth: u32, cancel: Option<&CancelToken>) -> SearchResult<DynMove>;
+
+    /// `search`, passing the result so far to `on_depth` after each
+    /// completed depth (see `Search::run_reporting`).
+    fn search_reporting(
+        &self,
+        depth: u32,
+        cancel: Option<&CancelToken>,
+        on_depth: &mut dyn FnMut(&SearchResult<DynMove>),
+    ) -> SearchResult<DynMove>;
+
+    /// Runs iterative deepening to `depth` plies, passing each completed
+    /// iteration to `on_iteration` (see
+    /// `solvers::iterative::iterative_deepening`).
+    fn iterative_deepening(
+        &self,
+        depth: u32,
+        on_iteration: &mut dyn FnMut(&DepthReport<DynMove>),
+    ) -> Option<DepthReport<DynMove>>;
+
+    /// Every move with the best value at `depth` plies, and that value, so
+    /// callers can break ties themselves (see
+    /// `solvers::minimax::minimax_best_moves_ab_depth`).
+    fn best_moves(&self, depth: u32) -> Option<(Vec<DynMove>, i32)>;
+
+    /// The moves played from the initial position, as accepted by
+    /// `GameEntry::from_notation`.
+    fn notation(&self) -> String;
+
+    /// Number of rows of the board.
+    fn rows(&self) -> usize;
+
+    /// Number of columns of the board.
+    fn cols(&self) -> usize;
+
+    /// The content of the cell at (`row`, `col`); see `BoardGame::cell`.
+    fn cell(&self, row: usize, col: usize) -> CellContent;
+
+    /// The board as plain text (see `render_board`).
+    fn render(&self) -> String;
+
+    /// The board in `style`, with the game's symbols or emoji.
+    fn render_as(&self, style: BoardStyle) -> String;
+
+    /// `render_as` with the coordinate labels and orientation of `layout`.
+    fn render_layout(&self, style: BoardStyle, layout: &Layout) -> String;
+
+    /// The game's usual coordinates drawn with `labels` (see
+    /// `Layout::labelled`).
+    fn coordinates(&self, labels: Labels) -> Layout;
+
+    /// The position in prose for screen readers, with rows and columns
+    /// named by `labels` (see `render::describe::describe`).
+    fn describe(&self, labels: Labels) -> String;
+
+    /// `describe` in the words of `words` (see
+    /// `render::describe::describe_in`).
+    fn describe_in(&self, labels: Labels, words: &dyn Vocabulary) -> String;
+
+    /// The board as an SVG document (see `SvgBoard`), with the last move
+    /// and any winning line highlighted.
+    fn render_svg(&self) -> String;
+
+    /// The game's symbols for `BoardStyle::Unicode`, for drawing boards
+    /// cell by cell.
+    fn symbols(&self) -> Glyphs;
+
+    /// The legal moves placed on the board, for picking one by position.
+    fn selection(&self) -> Selection<DynMove>;
+
+    /// Returns a boxed copy of this position.
+    fn clone_box(&self) -> Box<dyn DynGameState>;
+
+    /// The position as `Any`, for `downcast_ref`.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl Clone for Box<dyn DynGameState> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl dyn DynGameState + '_ {
+    /// The position as a `G`, if it is a position of `G`.
+    pub fn downcast_ref<G: RegisteredGame>(&self) -> Option<&G> {
+        self.as_any()
+            .downcast_ref::<Played<G>>()
+            .map(|played| &played.state)
+    }
+}
+
+impl fmt::Debug for dyn DynGameState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DynGameState({}: {:?})",
+            self.game_name(),
+            self.notation()
+        )
+    }
+}
+
+/// A position of `G` together with the moves that led to it.
+#[derive(Clone)]
+pub(crate) struct Played<G: GameState> {
+    state: G,
+    moves: Vec<G::Move>,
+}
+
+impl<G: RegisteredGame> Played<G> {
+    /// The initial position.
+    pub(crate) fn initial() -> Self {
+        Self {
+            state: G::initial(),
+            moves: Vec::new(),
+        }
+    }
+
+    /// Plays the moves of `notation` from the initial position. Games
+    /// without a move separator ignore whitespace between moves.
+    pub(crate) fn from_notation(notation: &str) -> Result<Self, ParseError> {
+        let mut played = Self::initial();
+        let notation = notation.trim();
+        let moves: Vec<String> = if G::MOVE_SEPARATOR.is_empty() {
+            notation
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .map(String::from)
+                .collect()
+        } else {
+            notation
+                .split(G::MOVE_SEPARATOR)
+                .filter(|mv| !mv.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        for (ply, mv) in moves.iter().enumerate() {
+            played = played.play_move(mv).map_err(|e| match e {
+                ParseError::IllegalMove(error) => ParseError::IllegalMoveAt {
+                    move_number: ply + 1,
+                    error,
+                },
+                e => e,
+            })?;
+        }
+        Ok(played)
+    }
+
+    /// The position, without its moves.
+    pub(crate) fn into_state(self) -> G {
+        self.state
+    }
+
+    fn play_move(&self, mv: &str) -> Result<Self, ParseError> {
+        let mv = self.state.parse_move(mv)?;
+        Ok(self.apply_move(mv)?)
+    }
+
+    fn apply_move(&self, mv: G::Move) -> Result<Self, MoveError> {
+        let state = self.state.try_apply_move(&mv)?;
+        debug_assert!(
+            state
+                .last_move()
+                .is_none_or(|last| G::format_move(&last) == G::format_move(&mv)),
+            "the state's last move disagrees with the move history"
+        );
+        let mut moves = self.moves.clone();
+        moves.push(mv);
+        Ok(Self { state, moves })
+    }
+}
+
+/// `result` with its moves erased.
+fn erase_result<G: RegisteredGame>(result: &SearchResult<G::Move>) -> SearchResult<DynMove> {
+    SearchResult {
+        lines: result
+            .lines
+            .iter()
+            .map(|line| PvLine {
+                value: line.value,
+                moves: line.moves.iter().cloned().map(DynMove::new::<G>).collect(),
+            })
+            .collect(),
+        depth: result.depth,
+        stats: result.stats,
+        stopped: result.stopped,
+    }
+}
+
+/// `report` with its moves erased.
+fn erase_report<G: RegisteredGame>(report: &DepthReport<G::Move>) -> DepthReport<DynMove> {
+    let erase = |mv: &G::Move| DynMove::new::<G>(mv.clone());
+    DepthReport {
+        depth: report.depth,
+        best_move: erase(&report.best_move),
+        value: report.value,
+        pv: report.pv.iter().map(erase).collect(),
+        root_moves: report
+            .root_moves
+            .iter()
+            .map(|(mv, score)| (erase(mv), *score))
+            .collect(),
+    }
+}
+
+impl<G: RegisteredGame> DynGameState for Played<G> {
+    fn game_name(&self) -> &'static str {
+        G::NAME
+    }
+
+    fn current_player(&self) -> Player {
+        self.state.current_player()
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.state.is_terminal()
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.state.outcome()
+    }
+
+    fn ply(&self) -> Option<u32> {
+        self.state.ply()
+    }
+
+    fn heuristic_value(&self) -> i32 {
+        self.state.heuristic_value()
+    }
+
+    fn legal_moves(&self) -> Vec<String> {
+        self.state
+            .legal_moves()
+            .iter()
+            .map(G::format_move)
+            .collect()
+    }
+
+    fn play(&self, mv: &str) -> Result<Box<dyn DynGameState>, ParseError> {
+        Ok(Box::new(self.play_move(mv)?))
+    }
+
+    fn moves(&self) -> Vec<DynMove> {
+        self.state
+            .legal_moves()
+            .into_iter()
+            .map(DynMove::new::<G>)
+            .collect()
+    }
+
+    fn parse_move(&self, text: &str) -> Result<DynMove, ParseError> {
+        Ok(DynMove::new::<G>(self.state.parse_move(text)?))
+    }
+
+    fn apply(&self, mv: &DynMove) -> Result<Box<dyn DynGameState>, MoveError> {
+        let mv = mv.downcast_ref::<G>().ok_or(MoveError::ForeignMove)?;
+        Ok(Box::new(self.apply_move(mv.clone())?))
+    }
+
+    fn history(&self) -> Vec<DynMove> {
+        self.moves.iter().cloned().map(DynMove::new::<G>).collect()
+    }
+
+    fn search(&self, depth: u32, cancel: Option<&CancelToken>) -> SearchResult<DynMove> {
+        self.search_reporting(depth, cancel, &mut |_| {})
+    }
+
+    fn search_reporting(
+        &self,
+        depth: u32,
+        cancel: Option<&CancelToken>,
+        on_depth: &mut dyn FnMut(&SearchResult<DynMove>),
+    ) -> SearchResult<DynMove> {
+        let mut search = Search::new(&self.state).depth(depth);
+        if let Some(cancel) = cancel {
+            search = search.cancel(cancel);
+        }
+        let result = search.run_reporting(|result| on_depth(&erase_result::<G>(result)));
+        erase_result::<G>(&result)
+    }
+
+    fn iterative_deepening(
+        &self,
+        depth: u32,
+        on_iteration: &mut dyn FnMut(&DepthReport<DynMove>),
+    ) -> Option<DepthReport<DynMove>> {
+        iterative_deepening(&self.state, depth, |report| {
+            on_iteration(&erase_report::<G>(report))
+        })
+        .map(|report| erase_report::<G>(&report))
+    }
+
+    fn best_moves(&self, depth: u32) -> Option<(Vec<DynMove>, i32)> {
+        let (moves, value) = minimax_best_moves_ab_depth(&self.state, depth)?;
+        Some((moves.into_iter().map(DynMove::new::<G>).collect(), value))
+    }
+
+    fn notation(&self) -> String {
+        let moves: Vec<String> = self.moves.iter().map(G::format_move).collect();
+        moves.join(G::MOVE_SEPARATOR)
+    }
+
+    fn rows(&self) -> usize {
+        self.state.rows()
+    }
+
+    fn cols(&self) -> usize {
+        self.state.cols()
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        self.state.cell(row, col)
+    }
+
+    fn render(&self) -> String {
+        render_board(&self.state)
+    }
+
+    fn render_as(&self, style: BoardStyle) -> String {
+        self.render_layout(style, &Layout::default())
+    }
+
+    fn render_layout(&self, style: BoardStyle, layout: &Layout) -> String {
+        match style {
+            BoardStyle::Ascii => render_board_with(&self.state, layout),
+            BoardStyle::Unicode => unicode_board_with(&self.state, &G::SYMBOLS, layout),
+            BoardStyle::Emoji => emoji_board_with(&self.state, &G::EMOJI, layout),
+        }
+    }
+
+    fn coordinates(&self, labels: Labels) -> Layout {
+        Layout::labelled(labels, G::SELECT_BY)
+    }
+
+    fn describe(&self, labels: Labels) -> String {
+        describe(&self.state, labels)
+    }
+
+    fn describe_in(&self, labels: Labels, words: &dyn Vocabulary) -> String {
+        describe_in(&self.state, labels, words)
+    }
+
+    fn render_svg(&self) -> String {
+        let mut board = SvgBoard::new().style(G::SVG_STYLE);
+        if let Some((_, earlier)) = self.moves.split_last() {
+            let before = earlier
+                .iter()
+                .fold(G::initial(), |state, mv| state.apply_move(mv));
+            if let Some(cell) = changed_cell(&before, &self.state) {
+                board = board.last_move(cell);
+            }
+        }
+        if let Some(length) = G::WIN_LENGTH {
+            board = board.winning_line(winning_line(&self.state, length));
+        }
+        board.render(&self.state)
+    }
+
+    fn symbols(&self) -> Glyphs {
+        G::SYMBOLS
+    }
+
+    fn selection(&self) -> Selection<DynMove> {
+        Selection::of(&self.state, G::SELECT_BY).map(DynMove::new::<G>)
+    }
+
+    fn clone_box(&self) -> Box<dyn DynGameState> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Positions of any registered game can be driven by the generic
+/// machinery, e.g. refereed by a `Referee`, with `DynMove`s as moves.
+impl GameState for Box<dyn DynGameState> {
+    type Move = DynMove;
+
+    fn current_player(&self) -> Player {
+        (**self).current_player()
+    }
+
+    fn legal_moves(&self) -> Vec<DynMove> {
+        (**self).moves()
+    }
+
+    fn apply_move(&self, mv: &DynMove) -> Self {
+        (**self).apply(mv).expect("apply_move is given legal moves")
+    }
+
+    fn check_move(&self, mv: &DynMove) -> Result<(), MoveError> {
+        (**self).apply(mv).map(|_| ())
+    }
+
+    fn try_apply_move(&self, mv: &DynMove) -> Result<Self, MoveError> {
+        (**self).apply(mv)
+    }
+
+    fn is_terminal(&self) -> bool {
+        (**self).is_terminal()
+    }
+
+    fn terminal_value(&self) -> Option<i32> {
+        (**self).outcome().map(Outcome::value)
+    }
+
+    fn last_move(&self) -> Option<DynMove> {
+        (**self).history().pop()
+    }
+
+    fn ply(&self) -> Option<u32> {
+        (**self).ply()
+    }
+
+    fn heuristic_value(&self) -> i32 {
+        (**self).heuristic_value()
+    }
+}
+
+impl BoardGame for Box<dyn DynGameState> {
+    fn rows(&self) -> usize {
+        (**self).rows()
+    }
+
+    fn cols(&self) -> usize {
+        (**self).cols()
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        (**self).cell(row, col)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::{TicTacToeMove, TicTacToeState};
+    use crate::registry::Registry;
+
+    #[test]
+    fn different_games_are_driven_through_one_interface() {
+        let registry = Registry::builtin();
+        let mut games: Vec<Box<dyn DynGameState>> = registry
+            .games()
+            .iter()
+            .map(|entry| (entry.new_game)())
+            .collect();
+        for game in &mut games {
+            while !game.is_terminal() {
+                let result = game.search(2, None);
+                let mv = result.best_move().unwrap();
+                assert_eq!(mv.game_name(), game.game_name());
+                *game = game.apply(mv).unwrap();
+            }
+            let replayed = game
+                .history()
+                .iter()
+                .try_fold(
+                    (registry.get(game.game_name()).unwrap().new_game)(),
+                    |g, mv| g.apply(mv),
+                )
+                .unwrap();
+            assert_eq!(replayed.notation(), game.notation());
+        }
+        assert!(games[0].downcast_ref::<TicTacToeState>().is_some());
+        assert!(games[1].downcast_ref::<TicTacToeState>().is_none());
+        assert!(
+            games[1]
+                .downcast_ref::<BitboardState>()
+                .unwrap()
+                .is_terminal()
+        );
+    }
+
+    #[test]
+    fn moves_only_apply_to_their_own_game() {
+        let registry = Registry::builtin();
+        let ttt = (registry.get("ttt").unwrap().new_game)();
+        let c4 = (registry.get("c4").unwrap().new_game)();
+        let centre = ttt.parse_move("4").unwrap();
+        assert_eq!(
+            centre.downcast_ref::<TicTacToeState>(),
+            Some(&TicTacToeMove { index: 4 })
+        );
+        assert!(centre.downcast_ref::<BitboardState>().is_none());
+        assert_eq!(c4.apply(&centre).err(), Some(MoveError::ForeignMove));
+        assert_eq!(ttt.moves().len(), 9);
+        assert!(ttt.moves().contains(&centre));
+
+        let after = ttt.apply(&centre).unwrap();
+        assert_eq!(after.apply(&centre).err(), Some(MoveError::CellOccupied));
+        assert_eq!(after.history(), [centre]);
+        assert!(after.render_svg().contains(r#"<rect x="60" y="60""#));
+
+        // Connect Four's rows go unlabelled: moves pick a column.
+        let c4_labels = c4.coordinates(Labels::Numbers);
+        assert_eq!(
+            (c4_labels.columns, c4_labels.rows),
+            (Some(Labels::Numbers), None)
+        );
+        let ttt_labels = ttt.coordinates(Labels::Letters).flip();
+        assert_eq!(
+            after
+                .render_layout(BoardStyle::Ascii, &ttt_labels)
+                .lines()
+                .next(),
+            Some("c . | . | .")
+        );
+        assert!(
+            after
+                .describe(Labels::Numbers)
+                .contains("Row 1: empty, ✕, empty.\n")
+        );
+    }
+
+    #[test]
+    fn boxed_positions_are_game_states() {
+        use crate::referee::{Referee, Submission, Verdict};
+
+        let c4 = (Registry::builtin().get("c4").unwrap().from_notation)("061626").unwrap();
+        let report = c4.iterative_deepening(3, &mut |_| {}).unwrap();
+        let typed = iterative_deepening(c4.downcast_ref::<BitboardState>().unwrap(), 3, |_| {});
+        assert_eq!(report.best_move.notation(), "3");
+        assert_eq!(report.value, typed.unwrap().value);
+        let (best, _) = c4.best_moves(3).unwrap();
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].notation(), "3");
+
+        let mut referee = Referee::new(c4.clone());
+        assert_eq!(referee.ply(), 6);
+        let submit = |referee: &mut Referee<Box<dyn DynGameState>>, mv: &str| {
+            let mv = referee.state().parse_move(mv).unwrap();
+            let submission = Submission {
+                player: referee.state().current_player(),
+                ply: referee.ply(),
+                mv,
+            };
+            referee
+                .submit(submission, core::time::Duration::ZERO)
+                .verdict
+        };
+        assert!(matches!(
+            submit(&mut referee, "3"),
+            Verdict::Accepted { .. }
+        ));
+        assert_eq!(referee.state().notation(), "0616263");
+        assert_eq!(
+            GameState::last_move(referee.state()).unwrap().notation(),
+            "3"
+        );
+        assert_eq!(referee.outcome(), Some(Outcome::Win(Player::Player1)));
+    }
+}
@@ -0,0 +1,118 @@
+use alloc::string::String;
+use core::fmt;
+
+/// Why a move cannot be played in a position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveError {
+    /// The game is already over.
+    GameOver,
+    /// The move names a cell or column that is not on the board.
+    OutOfRange,
+    /// The move's column has no empty cell left.
+    ColumnFull,
+    /// The move's cell is already taken.
+    CellOccupied,
+    /// A swap (see `WithSwapRule`) was requested other than as the second
+    /// move of the game.
+    SwapUnavailable,
+    /// A `DynMove` was played in a position of a different game.
+    ForeignMove,
+    /// A pop (see `C4Rules::pop_out`) was requested where the rules don't
+    /// allow it or the bottom disc is not the mover's.
+    PopUnavailable,
+    /// The move starts from a cell without one of the mover's pieces.
+    NotYourPiece,
+    /// The piece can't move to the move's target cell.
+    IllegalStep,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MoveError::GameOver => "the game is over",
+            MoveError::OutOfRange => "the move is off the board",
+            MoveError::ColumnFull => "the column is full",
+            MoveError::CellOccupied => "the cell is not empty",
+            MoveError::SwapUnavailable => "swapping is only allowed as the second move",
+            MoveError::ForeignMove => "the move belongs to a different game",
+            MoveError::PopUnavailable => "only your own bottom disc can be popped, in Pop Out",
+            MoveError::NotYourPiece => "there is none of your pieces on that cell",
+            MoveError::IllegalStep => "the piece can't move there",
+        })
+    }
+}
+
+impl core::error::Error for MoveError {}
+
+/// Why text could not be parsed into a position or a move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The text has the wrong number of cells.
+    WrongLength { expected: usize, found: usize },
+    /// A character that doesn't denote a cell or column, at a 0-based
+    /// character index.
+    InvalidChar { ch: char, index: usize },
+    /// The text should have been a number.
+    NotANumber(String),
+    /// The numbers of marks can't occur in a game.
+    ImpossibleMarkCounts { player1: usize, player2: usize },
+    /// A disc sits above an empty cell.
+    FloatingDisc { row: usize, col: usize },
+    /// The winning lines on the board can't occur in a game: both players
+    /// have one, or no last move of the winner completes them.
+    ImpossibleWin,
+    /// The move can't be played in the position.
+    IllegalMove(MoveError),
+    /// The game's rules can't be played on its board, e.g. lines longer
+    /// than the board is wide.
+    UnsupportedRules,
+    /// Move `move_number` (1-based) of a sequence can't be played.
+    IllegalMoveAt {
+        move_number: usize,
+        error: MoveError,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::WrongLength { expected, found } => {
+                write!(f, "expected {expected} cells, got {found}")
+            }
+            ParseError::InvalidChar { ch, index } => {
+                write!(f, "invalid character '{ch}' at index {index}")
+            }
+            ParseError::NotANumber(text) => write!(f, "'{text}' is not a number"),
+            ParseError::ImpossibleMarkCounts { player1, player2 } => {
+                write!(f, "impossible mark counts: {player1} X and {player2} O")
+            }
+            ParseError::FloatingDisc { row, col } => {
+                write!(f, "disc at ({row}, {col}) is floating above an empty cell")
+            }
+            ParseError::ImpossibleWin => {
+                write!(f, "the winning lines on the board can't occur in a game")
+            }
+            ParseError::UnsupportedRules => write!(f, "the rules can't be played on this board"),
+            ParseError::IllegalMove(e) => write!(f, "illegal move: {e}"),
+            ParseError::IllegalMoveAt { move_number, error } => {
+                write!(f, "move {move_number} is illegal: {error}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            ParseError::IllegalMove(e) | ParseError::IllegalMoveAt { error: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<MoveError> for ParseError {
+    fn from(e: MoveError) -> Self {
+        ParseError::IllegalMove(e)
+    }
+}
@@ -0,0 +1,60 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::game::GameState;
+
+/// Scores non-terminal positions for depth-limited search.
+///
+/// Values follow the same convention as `GameState::heuristic_value`:
+/// positive is good for Player1, negative is good for Player2. Searches
+/// score terminal positions themselves (±1_000_000), so evaluators only see
+/// non-terminal leaves and should stay well inside that range.
+pub trait Evaluator<G: GameState> {
+    /// Returns the evaluation of `state` from Player1's perspective.
+    fn evaluate(&self, state: &G) -> i32;
+
+    /// Returns the evaluations of `states` from Player1's perspective, one
+    /// per position and in the same order (see `solvers::batched`).
+    ///
+    /// Scores the positions one by one unless overridden by evaluators
+    /// whose cost per call is better spread over a batch, such as a neural
+    /// network on a GPU.
+    fn evaluate_batch(&self, states: &[G]) -> Vec<i32> {
+        states.iter().map(|state| self.evaluate(state)).collect()
+    }
+}
+
+/// The game's built-in heuristic (`GameState::heuristic_value`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HeuristicEvaluator;
+
+impl<G: GameState> Evaluator<G> for HeuristicEvaluator {
+    fn evaluate(&self, state: &G) -> i32 {
+        state.heuristic_value()
+    }
+}
+
+/// An evaluator backed by a user-provided function, e.g. a neural network
+/// running in the host application.
+pub struct FnEvaluator<G> {
+    f: Box<dyn Fn(&G) -> i32 + Send + Sync>,
+}
+
+impl<G> FnEvaluator<G> {
+    /// Wraps `f` as an evaluator.
+    pub fn new(f: impl Fn(&G) -> i32 + Send + Sync + 'static) -> Self {
+        Self { f: Box::new(f) }
+    }
+}
+
+impl<G: GameState> Evaluator<G> for FnEvaluator<G> {
+    fn evaluate(&self, state: &G) -> i32 {
+        (self.f)(state)
+    }
+}
+
+impl<G> core::fmt::Debug for FnEvaluator<G> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("FnEvaluator")
+    }
+}
@@ -0,0 +1,106 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::game::{Outcome, Player};
+
+/// A change in a game, as seen by spectators.
+///
+/// Game loops report what happens as a stream of these (see
+/// `play_game_observed` and `Referee::game_events`), so user interfaces,
+/// loggers, bots and network layers can all follow a game without
+/// working out for themselves what changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameEvent<M> {
+    /// `player` played `mv`. `eval` is the mover's evaluation from
+    /// Player1's perspective, if it had one.
+    MoveMade {
+        player: Player,
+        mv: M,
+        eval: Option<i32>,
+    },
+    /// `player`'s clock now shows `remaining`; zero means they ran out of
+    /// time.
+    ClockUpdate { player: Player, remaining: Duration },
+    /// The game is over. `outcome` is `None` if it was abandoned before a
+    /// result.
+    GameEnded { outcome: Option<Outcome> },
+}
+
+/// Receives the events of a game as they happen.
+pub trait GameObserver<M> {
+    fn on_event(&mut self, event: &GameEvent<M>);
+}
+
+/// Ignores every event.
+impl<M> GameObserver<M> for () {
+    fn on_event(&mut self, _event: &GameEvent<M>) {}
+}
+
+/// Collects the events, e.g. to check them in tests or log them later.
+impl<M: Clone> GameObserver<M> for Vec<GameEvent<M>> {
+    fn on_event(&mut self, event: &GameEvent<M>) {
+        self.push(event.clone());
+    }
+}
+
+/// Sends the events to another thread. Events are dropped once the
+/// receiver hangs up.
+#[cfg(feature = "std")]
+impl<M: Clone> GameObserver<M> for std::sync::mpsc::Sender<GameEvent<M>> {
+    fn on_event(&mut self, event: &GameEvent<M>) {
+        let _ = self.send(event.clone());
+    }
+}
+
+impl<M, O: GameObserver<M> + ?Sized> GameObserver<M> for &mut O {
+    fn on_event(&mut self, event: &GameEvent<M>) {
+        (**self).on_event(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{MinimaxAgent, play_game_decisions, play_game_observed};
+    use crate::games::ttt::TicTacToeState;
+
+    #[test]
+    fn the_game_loop_reports_every_move_and_the_end() {
+        let start = TicTacToeState::new();
+        let (mut a, mut b) = (MinimaxAgent::new(9), MinimaxAgent::new(9));
+        let mut log = Vec::new();
+        let (end, decisions) = play_game_observed(&start, &mut a, &mut b, &mut log);
+        assert_eq!(
+            (end, decisions.clone()),
+            play_game_decisions(&start, &mut a, &mut b)
+        );
+
+        #[cfg(feature = "std")]
+        {
+            let (mut tx, rx) = std::sync::mpsc::channel();
+            play_game_observed(&start, &mut a, &mut b, &mut tx);
+            assert_eq!(rx.try_iter().collect::<Vec<_>>(), log);
+        }
+        assert_eq!(log.len(), decisions.len() + 1);
+        for (event, decision) in log.iter().zip(&decisions) {
+            let GameEvent::MoveMade { mv, eval, .. } = event else {
+                panic!("expected a move, got {event:?}");
+            };
+            assert_eq!((mv, eval), (&decision.mv, &decision.value));
+        }
+        assert!(matches!(
+            log[0],
+            GameEvent::MoveMade {
+                player: Player::Player1,
+                ..
+            }
+        ));
+        assert_eq!(
+            log.last(),
+            Some(&GameEvent::GameEnded {
+                outcome: Some(Outcome::Draw)
+            })
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use alloc::vec::Vec;
+
+use crate::game::{GameState, Outcome, Player};
+use crate::solvers::minimax::{
+    WIN_SCORE, minimax_best_moves_ab_depth, minimax_value_ab_depth_root,
+};
+
+/// How one opening move fares: its value and the replies that hold the
+/// opener to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpeningLine<M> {
+    pub opening: M,
+    /// The value after `opening`, from Player1's perspective.
+    pub value: i32,
+    /// The replies that are best for the second player, in search order;
+    /// empty if `opening` ends the game.
+    pub replies: Vec<M>,
+}
+
+/// How big the first-move advantage of a game is, as found by
+/// `opening_fairness`.
+///
+/// Values are from Player1's perspective like every search value. With
+/// `exact` they are proven results; otherwise they are depth-limited
+/// estimates, useful for comparing variants searched to the same depth.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FairnessReport<M> {
+    /// The player making the first move.
+    pub first_player: Player,
+    pub depth: u32,
+    /// Whether the search reached the end of every game, so the values
+    /// are exact.
+    pub exact: bool,
+    /// The value of the starting position.
+    pub value: i32,
+    /// Every opening move, in search order.
+    pub openings: Vec<OpeningLine<M>>,
+}
+
+impl<M> FairnessReport<M> {
+    /// Converts a value from Player1's perspective to the first player's.
+    fn for_first_player(&self, value: i32) -> i32 {
+        match self.first_player {
+            Player::Player1 => value,
+            Player::Player2 => -value,
+        }
+    }
+
+    /// The result `value` proves, if any: a win if it is a forced win, a
+    /// draw if it is exact and neither side wins.
+    pub fn proven(&self, value: i32) -> Option<Outcome> {
+        if value.abs() >= WIN_SCORE {
+            Some(Outcome::from_value(value / WIN_SCORE))
+        } else {
+            self.exact.then_some(Outcome::Draw)
+        }
+    }
+
+    /// The proven result of the starting position, if any.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.proven(self.value)
+    }
+
+    /// The value of moving first, from the first player's perspective:
+    /// positive if moving first is an advantage.
+    pub fn first_move_advantage(&self) -> i32 {
+        self.for_first_player(self.value)
+    }
+
+    /// The openings that keep the whole advantage.
+    pub fn best_openings(&self) -> Vec<&M> {
+        self.openings
+            .iter()
+            .filter(|line| line.value == self.value)
+            .map(|line| &line.opening)
+            .collect()
+    }
+
+    /// The value of moving first under the pie (swap) rule, from the first
+    /// player's perspective.
+    ///
+    /// The second player swaps whenever the opening favours the opener, so
+    /// an opening worth `v` is only worth `-|v|`; the opener does best
+    /// with the most balanced opening (see `fairest_openings`). A value of
+    /// zero means the pie rule makes the game fair.
+    pub fn pie_value(&self) -> i32 {
+        self.openings
+            .iter()
+            .map(|line| -line.value.abs())
+            .max()
+            .unwrap_or_else(|| self.first_move_advantage())
+    }
+
+    /// The openings closest to balanced: the ones to play under the pie
+    /// rule.
+    pub fn fairest_openings(&self) -> Vec<&M> {
+        let pie = self.pie_value();
+        self.openings
+            .iter()
+            .filter(|line| -line.value.abs() == pie)
+            .map(|line| &line.opening)
+            .collect()
+    }
+}
+
+/// Measures the first-move advantage of the game starting at `start`:
+/// searches each opening move `depth` plies deep (counting the opening)
+/// and records its value with the second player's best replies.
+///
+/// Searching at least as deep as the game can last gives exact values;
+/// for larger games, compare variants at the same depth. Returns `None` if
+/// `start` has no legal moves.
+pub fn opening_fairness<G: GameState>(start: &G, depth: u32) -> Option<FairnessReport<G::Move>> {
+    let depth = depth.max(1);
+    let mut openings = Vec::new();
+    for opening in start.legal_moves() {
+        let after = start.apply_move(&opening);
+        let (replies, value) = match minimax_best_moves_ab_depth(&after, depth - 1) {
+            Some(best) if !after.is_terminal() => best,
+            _ => (Vec::new(), minimax_value_ab_depth_root(&after, 0)),
+        };
+        openings.push(OpeningLine {
+            opening,
+            value,
+            replies,
+        });
+    }
+    let first_player = start.current_player();
+    let values = openings.iter().map(|line| line.value);
+    let value = match first_player {
+        Player::Player1 => values.max()?,
+        Player::Player2 => values.min()?,
+    };
+    Some(FairnessReport {
+        first_player,
+        depth,
+        exact: start
+            .complexity_hint()
+            .max_plies
+            .is_some_and(|plies| depth >= plies),
+        value,
+        openings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::ttt::{TicTacToeMove, TicTacToeState};
+
+    fn cells(moves: Vec<&TicTacToeMove>) -> Vec<u8> {
+        moves.into_iter().map(|mv| mv.index).collect()
+    }
+
+    #[test]
+    fn tic_tac_toe_is_a_fair_draw() {
+        let report = opening_fairness(&TicTacToeState::new(), 9).unwrap();
+        assert!(report.exact);
+        assert_eq!(report.outcome(), Some(Outcome::Draw));
+        assert_eq!(report.first_move_advantage(), 0);
+        assert_eq!(report.openings.len(), 9);
+        assert_eq!(cells(report.best_openings()).len(), 9);
+        assert_eq!(report.pie_value(), 0);
+
+        // Only the centre holds a corner opening to a draw.
+        let corner = report.openings.iter().find(|l| l.opening.index == 0);
+        assert_eq!(corner.unwrap().replies, [TicTacToeMove { index: 4 }]);
+    }
+
+    #[test]
+    fn the_pie_rule_prefers_balanced_openings() {
+        // X to move can win at once on 2, so that opening would be swapped.
+        let start = TicTacToeState::from_board_str("XX.OO....").unwrap();
+        let report = opening_fairness(&start, 9).unwrap();
+        assert_eq!(report.outcome(), Some(Outcome::Win(Player::Player1)));
+        assert!(report.first_move_advantage() >= WIN_SCORE);
+        assert_eq!(cells(report.best_openings()), [2]);
+        let win = report.openings.iter().find(|l| l.opening.index == 2);
+        assert!(win.unwrap().replies.is_empty());
+        assert_eq!(report.pie_value(), 0);
+        assert!(!cells(report.fairest_openings()).contains(&2));
+
+        let shallow = opening_fairness(&TicTacToeState::new(), 2).unwrap();
+        assert!(!shallow.exact);
+        assert_eq!(shallow.proven(0), None);
+    }
+}
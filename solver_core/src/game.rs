@@ -1,10 +1,144 @@
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+
+use crate::error::MoveError;
+use crate::utils::opposite_player;
+
 /// Represents the players in a two-player deterministic game.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Player {
     Player1,
     Player2,
 }
 
+/// How a finished game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Outcome {
+    /// The given player won.
+    Win(Player),
+    /// Nobody won.
+    Draw,
+}
+
+impl Outcome {
+    /// Converts a terminal value (see `GameState::terminal_value`) by its
+    /// sign: positive is a Player1 win, negative a Player2 win.
+    pub fn from_value(value: i32) -> Self {
+        match value.signum() {
+            1 => Outcome::Win(Player::Player1),
+            -1 => Outcome::Win(Player::Player2),
+            _ => Outcome::Draw,
+        }
+    }
+
+    /// The outcome as a terminal value: +1, 0 or -1 from Player1's
+    /// perspective.
+    pub fn value(self) -> i32 {
+        match self {
+            Outcome::Win(Player::Player1) => 1,
+            Outcome::Win(Player::Player2) => -1,
+            Outcome::Draw => 0,
+        }
+    }
+
+    /// The winner, or `None` for a draw.
+    pub fn winner(self) -> Option<Player> {
+        match self {
+            Outcome::Win(p) => Some(p),
+            Outcome::Draw => None,
+        }
+    }
+
+    /// The outcome as a value from `player`'s perspective: +1 win, 0 draw,
+    /// -1 loss.
+    pub fn value_for(self, player: Player) -> i32 {
+        match player {
+            Player::Player1 => self.value(),
+            Player::Player2 => -self.value(),
+        }
+    }
+}
+
+impl From<Outcome> for i32 {
+    fn from(outcome: Outcome) -> Self {
+        outcome.value()
+    }
+}
+
+/// What it means for the side to move to have no legal moves in a position
+/// that isn't terminal (see `GameState::no_moves_rule`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoMovesRule {
+    /// The game is drawn, like stalemate in chess.
+    Draw,
+    /// The side to move loses, as in Domineering or checkers.
+    LossForMover,
+    /// It can't happen: every position without legal moves is terminal.
+    /// The solvers panic if it does.
+    #[default]
+    Impossible,
+}
+
+impl NoMovesRule {
+    /// The value, on the `GameState::terminal_value` scale, of a position
+    /// in which `mover` has no legal moves.
+    ///
+    /// Panics for `NoMovesRule::Impossible`.
+    pub fn value(self, mover: Player) -> i32 {
+        match self {
+            NoMovesRule::Draw => 0,
+            NoMovesRule::LossForMover => Outcome::Win(opposite_player(mover)).value(),
+            NoMovesRule::Impossible => {
+                panic!("a position that isn't terminal has no legal moves")
+            }
+        }
+    }
+}
+
+/// A rough description of how expensive a game is to search, used to pick
+/// default depths and to warn before hopeless searches.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComplexityHint {
+    /// How many times more nodes alpha-beta visits per extra ply, measured
+    /// from typical positions.
+    pub branching_factor: f64,
+    /// The most plies the game can still last, if known. Searching deeper
+    /// than this costs nothing extra.
+    pub max_plies: Option<u32>,
+    /// Depths that usually finish within about a second.
+    pub recommended_depths: RangeInclusive<u32>,
+}
+
+impl ComplexityHint {
+    /// Estimated node count above which a search is considered
+    /// impractically slow (minutes rather than seconds).
+    pub const IMPRACTICAL_NODES: f64 = 1e8;
+
+    /// Estimates the nodes a depth-`depth` search visits.
+    pub fn estimated_nodes(&self, depth: u32) -> f64 {
+        let plies = self.max_plies.map_or(depth, |max| depth.min(max));
+        // Square-and-multiply, as `f64::powi` needs `std`.
+        let (mut factor, mut exponent, mut nodes) = (self.branching_factor, plies, 1.0);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                nodes *= factor;
+            }
+            factor *= factor;
+            exponent >>= 1;
+        }
+        nodes
+    }
+
+    /// Returns `true` if a depth-`depth` search is expected to take too long
+    /// to be useful interactively.
+    pub fn is_impractical(&self, depth: u32) -> bool {
+        self.estimated_nodes(depth) > Self::IMPRACTICAL_NODES
+    }
+}
+
 /// A trait representing a deterministic, perfect-information,
 /// two-player, turn-based game state.
 pub trait GameState: Clone {
@@ -17,10 +151,46 @@ pub trait GameState: Clone {
     /// Returns a list of all legal moves from this state.
     fn legal_moves(&self) -> Vec<Self::Move>;
 
+    /// Calls `f` once for every legal move, in the same order as
+    /// `legal_moves`.
+    ///
+    /// The solvers use this instead of `legal_moves` so that generating moves
+    /// doesn't allocate at every node. The default implementation collects
+    /// `legal_moves`; games should override it with an allocation-free loop.
+    fn for_each_legal_move(&self, mut f: impl FnMut(Self::Move)) {
+        for mv in self.legal_moves() {
+            f(mv);
+        }
+    }
+
     /// Applies a move to the state and returns the resulting state.
     /// This should *not* mutate `self`; instead, return a new value.
     fn apply_move(&self, mv: &Self::Move) -> Self;
 
+    /// Returns why `mv` cannot be played in this state, if it can't.
+    ///
+    /// The default implementation only rejects moves once the game is over;
+    /// games should override it to reject every move `legal_moves` would
+    /// not return.
+    fn check_move(&self, mv: &Self::Move) -> Result<(), MoveError> {
+        let _ = mv;
+        if self.is_terminal() {
+            Err(MoveError::GameOver)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Applies `mv` if `check_move` accepts it.
+    ///
+    /// `apply_move` assumes the move is legal and may panic or corrupt the
+    /// state otherwise, so moves from outside the program (user input,
+    /// network requests) should go through this instead.
+    fn try_apply_move(&self, mv: &Self::Move) -> Result<Self, MoveError> {
+        self.check_move(mv)?;
+        Ok(self.apply_move(mv))
+    }
+
     /// Returns true if the state is terminal (win/loss/draw).
     fn is_terminal(&self) -> bool;
 
@@ -35,6 +205,51 @@ pub trait GameState: Clone {
     /// If state is non-terminal, return None.
     fn terminal_value(&self) -> Option<i32>;
 
+    /// Returns how the game ended, or `None` if it hasn't.
+    ///
+    /// Derived from `terminal_value`; prefer this over matching on the raw
+    /// numbers.
+    fn outcome(&self) -> Option<Outcome> {
+        self.terminal_value().map(Outcome::from_value)
+    }
+
+    /// Returns the move that led to this position, or `None` at the start
+    /// of the game or if the state doesn't track it.
+    ///
+    /// The built-in games set it in `apply_move`; positions parsed from a
+    /// board have none. Defaults to `None`.
+    fn last_move(&self) -> Option<Self::Move> {
+        None
+    }
+
+    /// Returns how many plies (moves by either player) were played from the
+    /// start of the game to reach this position, or `None` if the position
+    /// doesn't tell.
+    ///
+    /// Games where every move places one piece count the pieces on the
+    /// board. Defaults to `None`.
+    fn ply(&self) -> Option<u32> {
+        None
+    }
+
+    /// Returns the number of the current full move, counting from 1 and
+    /// going up after each Player2 move, as in game records.
+    ///
+    /// Derived from `ply`.
+    fn move_number(&self) -> Option<u32> {
+        self.ply().map(|ply| ply / 2 + 1)
+    }
+
+    /// Returns what it means when the side to move has no legal moves but
+    /// the position isn't terminal.
+    ///
+    /// The solvers score such positions by this rule, so games where running
+    /// out of moves decides the game don't have to fold it into
+    /// `terminal_value`. Defaults to `NoMovesRule::Impossible`.
+    fn no_moves_rule(&self) -> NoMovesRule {
+        NoMovesRule::Impossible
+    }
+
     /// Returns a heuristic evaluation of the position from Player1's perspective.
     ///
     /// By convention:
@@ -56,4 +271,167 @@ pub trait GameState: Clone {
     fn move_ordering_key(&self, mv: &Self::Move) -> i32 {
         0
     }
+
+    /// Returns how expensive the game is to search from this position.
+    ///
+    /// The default is a conservative guess that treats every legal move as
+    /// a full branch; games should override it with measured numbers.
+    fn complexity_hint(&self) -> ComplexityHint {
+        ComplexityHint {
+            branching_factor: self.legal_moves().len().max(1) as f64,
+            max_plies: None,
+            recommended_depths: 1..=4,
+        }
+    }
+
+    /// Returns the search depth front ends should use when none is given.
+    ///
+    /// Defaults to the top of `complexity_hint().recommended_depths`.
+    fn recommended_depth(&self) -> u32 {
+        *self.complexity_hint().recommended_depths.end()
+    }
+}
+
+/// What occupies one cell of a `BoardGame`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CellContent {
+    Empty,
+    /// A mark or disc belonging to the given player.
+    Piece(Player),
+}
+
+impl CellContent {
+    /// The player owning the cell, or `None` if it is empty.
+    pub fn owner(self) -> Option<Player> {
+        match self {
+            CellContent::Empty => None,
+            CellContent::Piece(p) => Some(p),
+        }
+    }
+}
+
+/// A game played on a rectangular grid, exposing its cells so renderers,
+/// frontends and generic heuristics can work with any such game.
+///
+/// Rows are numbered from the top as the board is usually drawn, so for
+/// Connect Four row 0 is the row discs reach last.
+pub trait BoardGame: GameState {
+    /// Number of rows.
+    fn rows(&self) -> usize;
+
+    /// Number of columns.
+    fn cols(&self) -> usize;
+
+    /// Returns the content of the cell at (`row`, `col`).
+    ///
+    /// Row 0 is the top row as boards are drawn: for games with gravity,
+    /// like Connect Four, the row filled last, whatever the internal
+    /// layout. `render::Layout::flipped` draws it at the bottom instead.
+    ///
+    /// Panics if the cell is outside the board.
+    fn cell(&self, row: usize, col: usize) -> CellContent;
+}
+
+/// A game state that can be summarized by a 64-bit key, e.g. for
+/// transposition tables.
+///
+/// Equal positions must have equal keys. Distinct positions should rarely
+/// collide, but may: most built-in games encode positions exactly, while
+/// those whose boards don't fit in 64 bits (Score Four, Breakthrough)
+/// hash them, so users of keys must tolerate the odd collision.
+pub trait PositionKey {
+    /// Returns the key of this position.
+    fn position_key(&self) -> u64;
+}
+
+/// A game state whose players can trade places: every piece changes hands
+/// and the other player is to move. Useful for exploiting colour symmetry
+/// in solvers, checking heuristics and augmenting training data.
+///
+/// The swapped position may not occur in a game (e.g. Player2 may have
+/// more pieces), but it is played by the same rules, so its terminal and
+/// heuristic values are the negations of the original's.
+///
+/// Games whose side to move follows from the piece counts, such as Score
+/// Four and Pentago, can't swap players this way.
+pub trait SwapPlayers {
+    /// The position with the players' pieces and turn exchanged; the last
+    /// move is kept.
+    fn swap_players(&self) -> Self;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::ttt::TicTacToeState;
+
+    #[test]
+    fn outcome_round_trips_through_values() {
+        for outcome in [
+            Outcome::Win(Player::Player1),
+            Outcome::Win(Player::Player2),
+            Outcome::Draw,
+        ] {
+            assert_eq!(Outcome::from_value(outcome.value()), outcome);
+            assert_eq!(i32::from(outcome), outcome.value());
+        }
+        assert_eq!(
+            Outcome::from_value(1_000_000),
+            Outcome::Win(Player::Player1)
+        );
+        assert_eq!(Outcome::Win(Player::Player1).value_for(Player::Player2), -1);
+    }
+
+    #[test]
+    fn ttt_outcome_matches_terminal_value() {
+        let won = TicTacToeState::from_str("XXXOO....", Player::Player2).unwrap();
+        assert_eq!(won.outcome(), Some(Outcome::Win(Player::Player1)));
+        let drawn = TicTacToeState::from_str("XOXXOOOXX", Player::Player2).unwrap();
+        assert_eq!(drawn.outcome(), Some(Outcome::Draw));
+        assert_eq!(TicTacToeState::new().outcome(), None);
+    }
+
+    #[test]
+    fn plies_and_move_numbers_are_counted_from_the_board() {
+        use crate::games::c4::ConnectFourState;
+        use crate::games::c4_bitboard::BitboardState;
+        use crate::games::swap::{SwapMove, WithSwapRule};
+
+        let ttt = TicTacToeState::from_str("X...O.X..", Player::Player2).unwrap();
+        assert_eq!(ttt.ply(), Some(3));
+        assert_eq!(ttt.move_number(), Some(2));
+        assert_eq!(TicTacToeState::new().move_number(), Some(1));
+
+        let bitboard = BitboardState::from_move_sequence("33425").unwrap();
+        assert_eq!(bitboard.ply(), Some(5));
+        assert_eq!(bitboard.move_number(), Some(3));
+        let mut array = ConnectFourState::new();
+        for mv in array.legal_moves().into_iter().take(2) {
+            array = array.apply_move(&mv);
+        }
+        assert_eq!(array.ply(), Some(2));
+
+        let swap = WithSwapRule::new(TicTacToeState::new());
+        let opened = swap.apply_move(&swap.legal_moves()[4]);
+        assert_eq!(opened.ply(), Some(1));
+        assert_eq!(opened.apply_move(&SwapMove::Swap).ply(), Some(2));
+    }
+
+    #[test]
+    fn complexity_hint_caps_estimates_at_max_plies() {
+        let hint = ComplexityHint {
+            branching_factor: 3.0,
+            max_plies: Some(4),
+            recommended_depths: 1..=4,
+        };
+        assert_eq!(hint.estimated_nodes(2), 9.0);
+        assert_eq!(hint.estimated_nodes(40), 81.0);
+        assert!(!hint.is_impractical(40));
+        let open = ComplexityHint {
+            max_plies: None,
+            ..hint
+        };
+        assert!(open.is_impractical(40));
+    }
 }
@@ -0,0 +1,551 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::error::{MoveError, ParseError};
+use crate::game::{
+    BoardGame, CellContent, ComplexityHint, GameState, Player, PositionKey, SwapPlayers,
+};
+use crate::registry::RegisteredGame;
+use crate::rng::mix;
+use crate::utils::opposite_player;
+
+/// Rows and columns of the board.
+const SIZE: usize = 8;
+/// Number of cells.
+const CELLS: usize = SIZE * SIZE;
+/// The top row, which Player1 races to.
+const TOP_ROW: u64 = 0xFF;
+/// The bottom row, which Player2 races to.
+const BOTTOM_ROW: u64 = 0xFF << (CELLS - SIZE);
+/// Every cell but those in the leftmost column.
+const NOT_LEFT: u64 = !0x0101_0101_0101_0101;
+/// Every cell but those in the rightmost column.
+const NOT_RIGHT: u64 = !0x8080_8080_8080_8080;
+
+/// Heuristic value of a pawn, whatever its row.
+const PAWN_VALUE: i32 = 100;
+/// Heuristic bonus for a pawn by the rows it has advanced from the back
+/// row, up to one step from winning.
+const ADVANCE_BONUS: [i32; SIZE - 1] = [0, 2, 4, 8, 16, 32, 64];
+
+/// A Breakthrough move: a pawn steps from `from` to `to`, one row towards
+/// the far side, straight ahead onto an empty cell or diagonally onto an
+/// empty or enemy cell, capturing the enemy pawn there.
+///
+/// Cells are numbered `row * 8 + col`, row 0 at the top. Moves are
+/// written in algebraic notation as `from-to`, e.g. `b2-b3`: files `a` to
+/// `h` from the left, ranks 1 to 8 from the bottom.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BreakthroughMove {
+    pub from: u8,
+    pub to: u8,
+}
+
+/// Parses an algebraic cell name such as `b2` into a cell index.
+fn parse_cell(text: &str, offset: usize) -> Result<u8, ParseError> {
+    let mut chars = text.chars();
+    let (file, rank) = (chars.next(), chars.next());
+    let col = match file {
+        Some(ch @ ('a'..='h' | 'A'..='H')) => ch.to_ascii_lowercase() as u8 - b'a',
+        Some(ch) => return Err(ParseError::InvalidChar { ch, index: offset }),
+        None => return Err(ParseError::NotANumber(String::from(text))),
+    };
+    let row = match rank {
+        Some(ch @ '1'..='8') => b'8' - ch as u8,
+        Some(ch) => {
+            return Err(ParseError::InvalidChar {
+                ch,
+                index: offset + 1,
+            });
+        }
+        None => return Err(ParseError::NotANumber(String::from(text))),
+    };
+    if let Some(ch) = chars.next() {
+        return Err(ParseError::InvalidChar {
+            ch,
+            index: offset + 2,
+        });
+    }
+    Ok(row * SIZE as u8 + col)
+}
+
+impl BreakthroughMove {
+    /// Parses a move as written by `Display`, e.g. `b2-b3`; `x` may stand
+    /// for `-` in captures, and letters may be upper case.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let text = text.trim();
+        let (from, to) = text
+            .split_once(['-', 'x', 'X'])
+            .ok_or_else(|| ParseError::NotANumber(String::from(text)))?;
+        Ok(Self {
+            from: parse_cell(from, 0)?,
+            to: parse_cell(to, from.len() + 1)?,
+        })
+    }
+}
+
+impl fmt::Display for BreakthroughMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cell = |f: &mut fmt::Formatter<'_>, cell: u8| {
+            let (row, col) = (cell / SIZE as u8, cell % SIZE as u8);
+            write!(f, "{}{}", (b'a' + col) as char, SIZE as u8 - row)
+        };
+        cell(f, self.from)?;
+        f.write_str("-")?;
+        cell(f, self.to)
+    }
+}
+
+/// A position of Breakthrough: each player starts with two rows of pawns
+/// on an 8x8 board, Player1 at the bottom and Player2 at the top. Pawns
+/// step one row forward, straight onto an empty cell or diagonally onto a
+/// cell not holding one of the mover's pawns, capturing any enemy pawn
+/// there. The first to reach the far row wins.
+///
+/// There are no draws: a player who loses every pawn loses, and a player
+/// with pawns always has a move (their most advanced pawn can always
+/// step diagonally). Games are also short, since every move brings a pawn
+/// closer to the far row (see `complexity_hint`).
+///
+/// Each player's pawns are a bitboard with bit `row * 8 + col` for each
+/// cell, row 0 at the top.
+///
+/// Two states are equal when their positions and players to move are,
+/// whatever moves led to them.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BreakthroughState {
+    player1: u64,
+    player2: u64,
+    current_player: Player,
+    /// Moves played so far.
+    ply: u32,
+    /// The move that led here (see `GameState::last_move`).
+    #[cfg_attr(feature = "serde", serde(skip))]
+    last_move: Option<BreakthroughMove>,
+}
+
+impl PartialEq for BreakthroughState {
+    fn eq(&self, other: &Self) -> bool {
+        self.player1 == other.player1
+            && self.player2 == other.player2
+            && self.current_player == other.current_player
+    }
+}
+
+impl Eq for BreakthroughState {}
+
+impl Default for BreakthroughState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BreakthroughState {
+    /// The starting position, with Player1 to move.
+    pub fn new() -> Self {
+        Self {
+            player1: 0xFFFF << (CELLS - 2 * SIZE),
+            player2: 0xFFFF,
+            current_player: Player::Player1,
+            ply: 0,
+            last_move: None,
+        }
+    }
+
+    /// Builds a position by playing moves separated by spaces from the
+    /// starting position, e.g. `"b2-b3 g7-g6"`.
+    pub fn from_move_sequence(seq: &str) -> Result<Self, ParseError> {
+        let mut state = Self::new();
+        for (ply, text) in seq.split_whitespace().enumerate() {
+            let mv = BreakthroughMove::parse(text)?;
+            state = state
+                .try_apply_move(&mv)
+                .map_err(|error| ParseError::IllegalMoveAt {
+                    move_number: ply + 1,
+                    error,
+                })?;
+        }
+        Ok(state)
+    }
+
+    /// The cells holding `player`'s pawns, one bit per cell.
+    pub fn pawns(&self, player: Player) -> u64 {
+        match player {
+            Player::Player1 => self.player1,
+            Player::Player2 => self.player2,
+        }
+    }
+
+    /// The cells `player`'s pawns can step to: straight ahead onto empty
+    /// cells, or diagonally onto cells without their own pawns. Bit `i`
+    /// of the three masks is the target of a step straight, to the left
+    /// or to the right.
+    fn targets(&self, player: Player) -> [u64; 3] {
+        let pawns = self.pawns(player);
+        let empty = !(self.player1 | self.player2);
+        match player {
+            Player::Player1 => [
+                pawns >> SIZE & empty,
+                pawns >> (SIZE + 1) & NOT_RIGHT & !pawns,
+                pawns >> (SIZE - 1) & NOT_LEFT & !pawns,
+            ],
+            Player::Player2 => [
+                pawns << SIZE & empty,
+                pawns << (SIZE - 1) & NOT_RIGHT & !pawns,
+                pawns << (SIZE + 1) & NOT_LEFT & !pawns,
+            ],
+        }
+    }
+
+    /// The cell a step from `to` came from, for a step that went
+    /// straight, left or right (`kind` 0, 1 or 2, as in `targets`).
+    fn source(player: Player, to: u8, kind: usize) -> u8 {
+        let offset = [SIZE, SIZE + 1, SIZE - 1][kind] as u8;
+        match player {
+            Player::Player1 => to + offset,
+            // Player2 steps down the board, so its offsets are reversed.
+            Player::Player2 => to - [SIZE, SIZE - 1, SIZE + 1][kind] as u8,
+        }
+    }
+
+    /// The winner, if a pawn has reached the far row or a player has no
+    /// pawns left.
+    fn winner(&self) -> Option<Player> {
+        if self.player1 & TOP_ROW != 0 || self.player2 == 0 {
+            Some(Player::Player1)
+        } else if self.player2 & BOTTOM_ROW != 0 || self.player1 == 0 {
+            Some(Player::Player2)
+        } else {
+            None
+        }
+    }
+
+    /// How many rows a pawn of `player` on `cell` has advanced from its
+    /// back row.
+    fn advance(player: Player, cell: usize) -> usize {
+        match player {
+            Player::Player1 => SIZE - 1 - cell / SIZE,
+            Player::Player2 => cell / SIZE,
+        }
+    }
+
+    /// Material plus a bonus for each pawn's advance, for `player`.
+    fn pawn_score(&self, player: Player) -> i32 {
+        let mut score = 0;
+        let mut pawns = self.pawns(player);
+        while pawns != 0 {
+            let advance = Self::advance(player, pawns.trailing_zeros() as usize);
+            score += PAWN_VALUE + ADVANCE_BONUS.get(advance).copied().unwrap_or(0);
+            pawns &= pawns - 1;
+        }
+        score
+    }
+}
+
+/// Pawns only move forward, so the board is also turned upside down: each
+/// player's pawns keep heading for the far row.
+impl SwapPlayers for BreakthroughState {
+    fn swap_players(&self) -> Self {
+        // Reversing the bytes reverses the order of the rows.
+        let flip_cell = |cell: u8| cell ^ (CELLS - SIZE) as u8;
+        Self {
+            player1: self.player2.swap_bytes(),
+            player2: self.player1.swap_bytes(),
+            current_player: opposite_player(self.current_player),
+            ply: self.ply,
+            last_move: self.last_move.map(|mv| BreakthroughMove {
+                from: flip_cell(mv.from),
+                to: flip_cell(mv.to),
+            }),
+        }
+    }
+}
+
+/// Mixes both bitboards and the player to move; unlike most built-in
+/// games, distinct positions may (very rarely) share a key, as the board
+/// doesn't fit in 64 bits.
+impl PositionKey for BreakthroughState {
+    fn position_key(&self) -> u64 {
+        let side = match self.current_player {
+            Player::Player1 => 0,
+            Player::Player2 => 0x9e37_79b9_7f4a_7c15,
+        };
+        mix(self.player1 ^ mix(self.player2 ^ side))
+    }
+}
+
+impl GameState for BreakthroughState {
+    type Move = BreakthroughMove;
+
+    fn current_player(&self) -> Player {
+        self.current_player
+    }
+
+    fn legal_moves(&self) -> Vec<BreakthroughMove> {
+        let mut moves = Vec::new();
+        self.for_each_legal_move(|mv| moves.push(mv));
+        moves
+    }
+
+    fn for_each_legal_move(&self, mut f: impl FnMut(BreakthroughMove)) {
+        let player = self.current_player;
+        for (kind, mut targets) in self.targets(player).into_iter().enumerate() {
+            while targets != 0 {
+                let to = targets.trailing_zeros() as u8;
+                f(BreakthroughMove {
+                    from: Self::source(player, to, kind),
+                    to,
+                });
+                targets &= targets - 1;
+            }
+        }
+    }
+
+    fn check_move(&self, mv: &BreakthroughMove) -> Result<(), MoveError> {
+        if self.is_terminal() {
+            return Err(MoveError::GameOver);
+        }
+        if mv.from as usize >= CELLS || mv.to as usize >= CELLS {
+            return Err(MoveError::OutOfRange);
+        }
+        let player = self.current_player;
+        if self.pawns(player) & (1 << mv.from) == 0 {
+            return Err(MoveError::NotYourPiece);
+        }
+        let legal = self
+            .targets(player)
+            .into_iter()
+            .enumerate()
+            .any(|(kind, targets)| {
+                targets & (1 << mv.to) != 0 && Self::source(player, mv.to, kind) == mv.from
+            });
+        if legal {
+            Ok(())
+        } else {
+            Err(MoveError::IllegalStep)
+        }
+    }
+
+    fn apply_move(&self, mv: &BreakthroughMove) -> Self {
+        let (from, to) = (1u64 << mv.from, 1u64 << mv.to);
+        let (mut player1, mut player2) = (self.player1, self.player2);
+        match self.current_player {
+            Player::Player1 => {
+                player1 = player1 & !from | to;
+                player2 &= !to;
+            }
+            Player::Player2 => {
+                player2 = player2 & !from | to;
+                player1 &= !to;
+            }
+        }
+        Self {
+            player1,
+            player2,
+            current_player: opposite_player(self.current_player),
+            ply: self.ply + 1,
+            last_move: Some(*mv),
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.winner().is_some()
+    }
+
+    fn terminal_value(&self) -> Option<i32> {
+        self.winner().map(|winner| match winner {
+            Player::Player1 => 1,
+            Player::Player2 => -1,
+        })
+    }
+
+    /// Material and race: each pawn is worth `PAWN_VALUE` plus a bonus
+    /// that doubles with every row it advances.
+    fn heuristic_value(&self) -> i32 {
+        self.pawn_score(Player::Player1) - self.pawn_score(Player::Player2)
+    }
+
+    /// Captures first, then steps by how far they advance the pawn.
+    fn move_ordering_key(&self, mv: &BreakthroughMove) -> i32 {
+        let opponent = self.pawns(opposite_player(self.current_player));
+        let capture = i32::from(opponent & (1 << mv.to) != 0);
+        capture * SIZE as i32 + Self::advance(self.current_player, mv.to as usize) as i32
+    }
+
+    fn last_move(&self) -> Option<BreakthroughMove> {
+        self.last_move
+    }
+
+    fn ply(&self) -> Option<u32> {
+        Some(self.ply)
+    }
+
+    /// Every move but the last takes a pawn a row closer to the far row
+    /// without reaching it, which bounds the plies left.
+    fn complexity_hint(&self) -> ComplexityHint {
+        let steps_left = |player: Player| {
+            let mut steps = 0;
+            let mut pawns = self.pawns(player);
+            while pawns != 0 {
+                let advance = Self::advance(player, pawns.trailing_zeros() as usize);
+                steps += (SIZE - 2).saturating_sub(advance) as u32;
+                pawns &= pawns - 1;
+            }
+            steps
+        };
+        ComplexityHint {
+            branching_factor: 8.0,
+            max_plies: Some(steps_left(Player::Player1) + steps_left(Player::Player2) + 1),
+            recommended_depths: 1..=5,
+        }
+    }
+
+    fn recommended_depth(&self) -> u32 {
+        4
+    }
+}
+
+impl BoardGame for BreakthroughState {
+    fn rows(&self) -> usize {
+        SIZE
+    }
+
+    fn cols(&self) -> usize {
+        SIZE
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        assert!(
+            row < SIZE && col < SIZE,
+            "cell ({row}, {col}) is off the board"
+        );
+        let bit = 1 << (row * SIZE + col);
+        if self.player1 & bit != 0 {
+            CellContent::Piece(Player::Player1)
+        } else if self.player2 & bit != 0 {
+            CellContent::Piece(Player::Player2)
+        } else {
+            CellContent::Empty
+        }
+    }
+}
+
+impl RegisteredGame for BreakthroughState {
+    const NAME: &'static str = "breakthrough";
+    const DISPLAY_NAME: &'static str = "Breakthrough";
+    const MOVE_SEPARATOR: &'static str = " ";
+
+    fn initial() -> Self {
+        Self::new()
+    }
+
+    fn format_move(mv: &BreakthroughMove) -> String {
+        alloc::format!("{mv}")
+    }
+
+    fn parse_move(&self, text: &str) -> Result<BreakthroughMove, ParseError> {
+        let mv = BreakthroughMove::parse(text)?;
+        self.check_move(&mv)?;
+        Ok(mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Outcome;
+    use crate::solvers::minimax::minimax_best_move_ab_depth;
+    use alloc::string::ToString;
+
+    fn mv(text: &str) -> BreakthroughMove {
+        BreakthroughMove::parse(text).unwrap()
+    }
+
+    #[test]
+    fn pawns_step_forward_and_capture_diagonally() {
+        let start = BreakthroughState::new();
+        // Each front pawn steps straight or diagonally; edge pawns have
+        // one diagonal fewer.
+        assert_eq!(start.legal_moves().len(), 22);
+        assert_eq!(mv("b2-c3"), BreakthroughMove { from: 49, to: 42 });
+        assert_eq!(mv("B2xC3").to_string(), "b2-c3");
+        for bad in ["b2", "b2-", "i2-i3", "b9-b8", "b2-b3c"] {
+            assert!(BreakthroughMove::parse(bad).is_err(), "{bad}");
+        }
+        assert_eq!(start.check_move(&mv("b1-b2")), Err(MoveError::IllegalStep));
+        assert_eq!(start.check_move(&mv("b3-b4")), Err(MoveError::NotYourPiece));
+        assert_eq!(start.check_move(&mv("b2-b4")), Err(MoveError::IllegalStep));
+        assert_eq!(start.check_move(&mv("a2-h3")), Err(MoveError::IllegalStep));
+
+        let state = BreakthroughState::from_move_sequence("d2-d3 e7-e6 d3-d4 e6-e5").unwrap();
+        // d4 and e5 face each other diagonally: either may take the other.
+        assert_eq!(state.check_move(&mv("d4-e5")), Ok(()));
+        let taken = state.apply_move(&mv("d4xe5"));
+        assert_eq!(taken.pawns(Player::Player2).count_ones(), 15);
+        assert_eq!(taken.current_player(), Player::Player2);
+        assert_eq!(taken.ply(), Some(5));
+        let blocked = BreakthroughState::from_move_sequence("e2-e3 e7-e6 e3-e4 e6-e5").unwrap();
+        assert_eq!(
+            blocked.check_move(&mv("e4-e5")),
+            Err(MoveError::IllegalStep)
+        );
+        assert!(
+            blocked
+                .legal_moves()
+                .iter()
+                .all(|m| m.to_string() != "e4-e5")
+        );
+    }
+
+    #[test]
+    fn reaching_the_far_row_or_capturing_everything_wins() {
+        let state = |player1: u64, player2: u64, current_player| BreakthroughState {
+            player1,
+            player2,
+            current_player,
+            ply: 0,
+            last_move: None,
+        };
+        // Player1 on a7 steps to a8; Player2's pawn on h5 is far away.
+        let racing = state(1 << 8, 1 << 31, Player::Player1);
+        let won = racing.apply_move(&mv("a7-a8"));
+        assert_eq!(won.outcome(), Some(Outcome::Win(Player::Player1)));
+        let (best, _) = minimax_best_move_ab_depth(&racing, 1).unwrap();
+        assert_eq!(best.to_string(), "a7-a8");
+
+        let wiped = state(1 << 40, 0, Player::Player2);
+        assert_eq!(wiped.outcome(), Some(Outcome::Win(Player::Player1)));
+    }
+
+    #[test]
+    fn swapping_players_turns_the_board_over() {
+        let state = BreakthroughState::from_move_sequence("d2-d3").unwrap();
+        let swapped = state.swap_players();
+        // Player1's pawn on d3 becomes Player2's on d6, and the start
+        // position swaps onto itself.
+        assert_eq!(swapped.cell(2, 3), CellContent::Piece(Player::Player2));
+        assert_eq!(swapped.current_player(), Player::Player1);
+        assert_eq!(swapped.last_move().unwrap().to_string(), "d7-d6");
+        assert_eq!(swapped.heuristic_value(), -state.heuristic_value());
+        let start = BreakthroughState::new();
+        let mut turned = start.swap_players();
+        turned.current_player = Player::Player1;
+        assert_eq!(turned, start);
+    }
+
+    #[test]
+    fn the_plies_left_are_bounded() {
+        let start = BreakthroughState::new();
+        assert_eq!(
+            start.complexity_hint().max_plies,
+            Some(2 * (8 * 5 + 8 * 6) + 1)
+        );
+        assert!(start.heuristic_value() == 0);
+        let ahead = start.apply_move(&mv("d2-d3"));
+        assert!(ahead.heuristic_value() > 0);
+        assert!(ahead.complexity_hint().max_plies < start.complexity_hint().max_plies);
+    }
+}
@@ -1,12 +1,33 @@
-use crate::game::{GameState, Player};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::{MoveError, ParseError};
+use crate::game::{BoardGame, CellContent, ComplexityHint, GameState, Player, SwapPlayers};
+use crate::games::c4_bitboard::C4EvalParams;
+use crate::heuristics::{line_score, line_winner};
+use crate::transforms::{Symmetric, Transform};
 use crate::utils::opposite_player;
 
 const ROWS: u8 = 6;
 const COLS: u8 = 7;
 const WIN_LENGTH: u8 = 4;
 
+/// Line scores shared with `C4Bitboard::evaluate` (without its column
+/// weights), for 0 to 4 discs of one player.
+fn line_weights() -> [i32; 5] {
+    let params = C4EvalParams::default();
+    [
+        0,
+        0,
+        params.window_two,
+        params.window_three,
+        params.window_four,
+    ]
+}
+
 /// Represents the contents of a single Connect Four board cell.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum C4Cell {
     /// The cell is empty (no disc has been played here yet).
     Empty,
@@ -20,7 +41,8 @@ pub enum C4Cell {
 ///
 /// A move is "drop a disc into this column".
 /// The row is determined by gravity (the lowest empty cell in that column).
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConnectFourMove {
     /// The column index (0..=6) where the current player will drop a disc.
     pub column: u8,
@@ -31,7 +53,18 @@ pub struct ConnectFourMove {
 /// The board is 7 columns by 6 rows, flattened into a 1D array of length 42.
 /// We also track the current player and the height of each column
 /// (i.e., how many cells are already filled in that column).
+///
+/// With the `serde` feature, the state is serialized as its `to_repr` string
+/// plus the current player, and deserialization goes through `from_str` so
+/// heights are recomputed and gravity is validated.
+///
+/// Two states are equal when their positions are, whatever move led to them.
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "ConnectFourRepr", try_from = "ConnectFourRepr")
+)]
 pub struct ConnectFourState {
     /// The board cells, stored in row-major order, 6 rows × 7 columns = 42 cells.
     ///
@@ -48,6 +81,46 @@ pub struct ConnectFourState {
 
     /// The player whose turn it is to move in this position.
     pub current_player: Player,
+
+    /// The move that led here (see `GameState::last_move`).
+    last_move: Option<ConnectFourMove>,
+}
+
+impl PartialEq for ConnectFourState {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board
+            && self.heights == other.heights
+            && self.current_player == other.current_player
+    }
+}
+
+impl Eq for ConnectFourState {}
+
+/// Serialized form of `ConnectFourState` (see the `serde` feature).
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ConnectFourRepr {
+    board: String,
+    current_player: Player,
+}
+
+#[cfg(feature = "serde")]
+impl From<ConnectFourState> for ConnectFourRepr {
+    fn from(state: ConnectFourState) -> Self {
+        Self {
+            board: state.to_repr(),
+            current_player: state.current_player,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ConnectFourRepr> for ConnectFourState {
+    type Error = ParseError;
+
+    fn try_from(repr: ConnectFourRepr) -> Result<Self, Self::Error> {
+        Self::from_str(&repr.board, repr.current_player)
+    }
 }
 
 impl Default for ConnectFourState {
@@ -66,6 +139,7 @@ impl ConnectFourState {
             board: [C4Cell::Empty; 42],
             heights: [0; 7],
             current_player: Player::Player1,
+            last_move: None,
         }
     }
 
@@ -81,106 +155,7 @@ impl ConnectFourState {
     ///
     /// This does not check for draws; only for wins.
     fn winner(&self) -> Option<Player> {
-        if let Some(p) = self.check_horizontal() {
-            return Some(p);
-        }
-        if let Some(p) = self.check_vertical() {
-            return Some(p);
-        }
-        if let Some(p) = self.check_diag_down() {
-            return Some(p);
-        }
-        if let Some(p) = self.check_diag_up() {
-            return Some(p);
-        }
-        None
-    }
-
-    /// Checks all horizontal lines for a 4-in-a-row.
-    /// Returns the winning player if found.
-    fn check_horizontal(&self) -> Option<Player> {
-        for col in 0..=(COLS - WIN_LENGTH) {
-            for row in 0..ROWS {
-                let idxs = [
-                    Self::idx(row, col),
-                    Self::idx(row, col + 1),
-                    Self::idx(row, col + 2),
-                    Self::idx(row, col + 3),
-                ];
-                if let Some(p) = self.check_line(idxs) {
-                    return Some(p);
-                }
-            }
-        }
-        None
-    }
-
-    /// Checks vertical lines for 4-in-a-row.
-    fn check_vertical(&self) -> Option<Player> {
-        for col in 0..COLS {
-            for row in 0..=(ROWS - WIN_LENGTH) {
-                let idxs = [
-                    Self::idx(row, col),
-                    Self::idx(row + 1, col),
-                    Self::idx(row + 2, col),
-                    Self::idx(row + 3, col),
-                ];
-                if let Some(p) = self.check_line(idxs) {
-                    return Some(p);
-                }
-            }
-        }
-        None
-    }
-
-    /// Checks diagonal down-right lines (↘).
-    fn check_diag_down(&self) -> Option<Player> {
-        for col in 0..=(COLS - WIN_LENGTH) {
-            for row in 0..=(ROWS - WIN_LENGTH) {
-                let idxs = [
-                    Self::idx(row, col),
-                    Self::idx(row + 1, col + 1),
-                    Self::idx(row + 2, col + 2),
-                    Self::idx(row + 3, col + 3),
-                ];
-                if let Some(p) = self.check_line(idxs) {
-                    return Some(p);
-                }
-            }
-        }
-        None
-    }
-
-    /// Checks diagonal up-right lines (↗).
-    fn check_diag_up(&self) -> Option<Player> {
-        for col in 0..=(COLS - WIN_LENGTH) {
-            for row in (WIN_LENGTH - 1)..ROWS {
-                let idxs = [
-                    Self::idx(row, col),
-                    Self::idx(row - 1, col + 1),
-                    Self::idx(row - 2, col + 2),
-                    Self::idx(row - 3, col + 3),
-                ];
-                if let Some(p) = self.check_line(idxs) {
-                    return Some(p);
-                }
-            }
-        }
-        None
-    }
-
-    /// Given four board indices, returns Some(Player) if all four cells belong
-    /// to the same player (and are not Empty), otherwise None.
-    fn check_line(&self, idxs: [usize; 4]) -> Option<Player> {
-        let is_p1 = idxs.iter().all(|&i| self.board[i] == C4Cell::P1);
-        if is_p1 {
-            return Some(Player::Player1);
-        }
-        let is_p2 = idxs.iter().all(|&i| self.board[i] == C4Cell::P2);
-        if is_p2 {
-            return Some(Player::Player2);
-        }
-        None
+        line_winner(self, WIN_LENGTH as usize)
     }
 
     /// Attempts to construct a ConnectFourState from a string representation.
@@ -203,13 +178,14 @@ impl ConnectFourState {
     ///
     /// You may ignore gravity correctness here for now; later, we can add validation
     /// that the board is "physically legal" (no discs floating above empties).
-    pub fn from_str(repr: &str, current_player: Player) -> Result<Self, String> {
-        // 1) Validate length = 42 exactly.
-        if repr.len() != 42 {
-            return Err(format!(
-                "Connect Four board string must have length 42, got {}",
-                repr.len()
-            ));
+    pub fn from_str(repr: &str, current_player: Player) -> Result<Self, ParseError> {
+        // 1) Validate length = 42 exactly (in characters, not bytes).
+        let len = repr.chars().count();
+        if len != 42 {
+            return Err(ParseError::WrongLength {
+                expected: 42,
+                found: len,
+            });
         }
 
         // 2) Parse characters into cells.
@@ -219,7 +195,7 @@ impl ConnectFourState {
                 '.' => C4Cell::Empty,
                 'X' => C4Cell::P1,
                 'O' => C4Cell::P2,
-                other => return Err(format!("Invalid character '{}' at index {}", other, i)),
+                ch => return Err(ParseError::InvalidChar { ch, index: i }),
             };
         }
 
@@ -244,10 +220,10 @@ impl ConnectFourState {
             for row in 0..(6 - h as usize) {
                 let idx = row * 7 + col as usize;
                 if board[idx] != C4Cell::Empty {
-                    return Err(format!(
-                        "Illegal board: disc at ({}, {}) is floating above an empty cell",
-                        row, col
-                    ));
+                    return Err(ParseError::FloatingDisc {
+                        row,
+                        col: col as usize,
+                    });
                 }
             }
 
@@ -258,8 +234,69 @@ impl ConnectFourState {
             board,
             heights,
             current_player,
+            last_move: None,
         })
     }
+
+    /// Returns the 42-character string representation accepted by `from_str`.
+    ///
+    /// The current player is not part of the string and must be passed
+    /// back in separately when parsing.
+    pub fn to_repr(&self) -> String {
+        self.board
+            .iter()
+            .map(|c| match c {
+                C4Cell::Empty => '.',
+                C4Cell::P1 => 'X',
+                C4Cell::P2 => 'O',
+            })
+            .collect()
+    }
+}
+
+impl SwapPlayers for ConnectFourState {
+    fn swap_players(&self) -> Self {
+        Self {
+            board: self.board.map(|cell| match cell {
+                C4Cell::P1 => C4Cell::P2,
+                C4Cell::P2 => C4Cell::P1,
+                C4Cell::Empty => C4Cell::Empty,
+            }),
+            heights: self.heights,
+            current_player: opposite_player(self.current_player),
+            last_move: self.last_move,
+        }
+    }
+}
+
+/// Gravity rules out turning the board, so only the mirror applies.
+impl Symmetric for ConnectFourState {
+    fn transforms() -> &'static [Transform] {
+        &[Transform::IDENTITY, Transform::MIRROR]
+    }
+
+    fn transformed(&self, t: Transform) -> Self {
+        let mut board = [C4Cell::Empty; 42];
+        for (i, &cell) in self.board.iter().enumerate() {
+            board[t.map_index(6, 7, i)] = cell;
+        }
+        let mut heights = self.heights;
+        if t.mirror {
+            heights.reverse();
+        }
+        Self {
+            board,
+            heights,
+            current_player: self.current_player,
+            last_move: self.last_move.map(|mv| Self::transform_move(&mv, t)),
+        }
+    }
+
+    fn transform_move(mv: &ConnectFourMove, t: Transform) -> ConnectFourMove {
+        ConnectFourMove {
+            column: t.map_cell(6, 7, 0, usize::from(mv.column)).1 as u8,
+        }
+    }
 }
 
 impl GameState for ConnectFourState {
@@ -282,6 +319,25 @@ impl GameState for ConnectFourState {
             .collect()
     }
 
+    fn for_each_legal_move(&self, mut f: impl FnMut(Self::Move)) {
+        for (col, height) in self.heights.iter().enumerate() {
+            if *height < ROWS {
+                f(Self::Move { column: col as u8 });
+            }
+        }
+    }
+
+    fn check_move(&self, mv: &Self::Move) -> Result<(), MoveError> {
+        if self.is_terminal() {
+            return Err(MoveError::GameOver);
+        }
+        match self.heights.get(mv.column as usize) {
+            None => Err(MoveError::OutOfRange),
+            Some(&height) if height >= ROWS => Err(MoveError::ColumnFull),
+            Some(_) => Ok(()),
+        }
+    }
+
     /// Applies a move by the current player and returns the resulting new state.
     ///
     /// The move is "drop a disc into column mv.column", where the disc falls
@@ -313,6 +369,7 @@ impl GameState for ConnectFourState {
             board: new_board,
             heights: new_heights,
             current_player: new_player,
+            last_move: Some(*mv),
         }
     }
 
@@ -340,4 +397,87 @@ impl GameState for ConnectFourState {
 
         None
     }
+
+    /// Scores every line of four cells (see `KInARow`).
+    fn heuristic_value(&self) -> i32 {
+        line_score(self, WIN_LENGTH as usize, &line_weights())
+    }
+
+    fn last_move(&self) -> Option<ConnectFourMove> {
+        self.last_move
+    }
+
+    fn ply(&self) -> Option<u32> {
+        Some(self.board.iter().filter(|c| **c != C4Cell::Empty).count() as u32)
+    }
+
+    /// Same estimates as `C4Bitboard`, though this representation searches
+    /// several times slower per node.
+    fn complexity_hint(&self) -> ComplexityHint {
+        let empty = self.board.len() as u32 - self.ply().unwrap_or(0);
+        ComplexityHint {
+            branching_factor: 3.2,
+            max_plies: Some(empty),
+            recommended_depths: 1..=10,
+        }
+    }
+
+    fn recommended_depth(&self) -> u32 {
+        8
+    }
+}
+
+impl BoardGame for ConnectFourState {
+    fn rows(&self) -> usize {
+        ROWS as usize
+    }
+
+    fn cols(&self) -> usize {
+        COLS as usize
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        assert!(
+            row < ROWS as usize && col < COLS as usize,
+            "cell ({row}, {col}) is off the board"
+        );
+        match self.board[Self::idx(row as u8, col as u8)] {
+            C4Cell::Empty => CellContent::Empty,
+            C4Cell::P1 => CellContent::Piece(Player::Player1),
+            C4Cell::P2 => CellContent::Piece(Player::Player2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    #[test]
+    fn c4_tracks_the_last_move_without_affecting_equality() {
+        let s = ConnectFourState::new().apply_move(&ConnectFourMove { column: 5 });
+        assert_eq!(s.last_move(), Some(ConnectFourMove { column: 5 }));
+        let parsed = ConnectFourState::from_str(&s.to_repr(), Player::Player2).unwrap();
+        assert_eq!(parsed.last_move(), None);
+        assert_eq!(parsed, s);
+    }
+
+    #[test]
+    fn c4_from_str_counts_characters_not_bytes() {
+        let empty = ".".repeat(42);
+        assert_eq!(
+            ConnectFourState::from_str(&empty, Player::Player1),
+            Ok(ConnectFourState::new())
+        );
+        // 42 bytes, but only 41 characters.
+        let accented = format!("é{}", ".".repeat(40));
+        assert_eq!(
+            ConnectFourState::from_str(&accented, Player::Player1),
+            Err(ParseError::WrongLength {
+                expected: 42,
+                found: 41
+            })
+        );
+    }
 }
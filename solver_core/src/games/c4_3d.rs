@@ -0,0 +1,561 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::error::{MoveError, ParseError};
+use crate::game::{BoardGame, CellContent, ComplexityHint, GameState, Player, PositionKey};
+use crate::registry::RegisteredGame;
+use crate::rng::mix;
+
+/// Pegs along each side of the board, and levels on each peg.
+const SIZE: usize = 4;
+/// Number of pegs.
+const PEGS: usize = SIZE * SIZE;
+/// Number of cells.
+const CELLS: usize = PEGS * SIZE;
+/// The bits of peg 0 on every level; shift by a peg to get that peg's.
+const PEG_MASK: u64 = 0x0001_0001_0001_0001;
+
+/// Number of winning lines on the 4x4x4 board.
+pub const LINE_COUNT: usize = 76;
+
+/// Every winning line, as the bits of its four cells (see
+/// `ScoreFourState` for the layout).
+pub const LINES: [u64; LINE_COUNT] = winning_lines();
+
+/// How many winning lines pass through each cell: 7 for the corners and
+/// the eight central cells, 4 for the rest.
+const CELL_LINES: [u8; CELLS] = cell_lines();
+
+/// Heuristic score of a line holding only one player's discs, by their
+/// number.
+const LINE_WEIGHTS: [i32; SIZE + 1] = [0, 1, 8, 64, 100_000];
+
+/// The bit of the cell at (`x`, `y`) on level `z` (0 at the bottom).
+const fn bit(x: usize, y: usize, z: usize) -> u64 {
+    1 << (z * PEGS + y * SIZE + x)
+}
+
+/// Finds the 76 lines: 48 along the axes, 24 diagonals within planes and
+/// 4 through the centre of the cube.
+const fn winning_lines() -> [u64; LINE_COUNT] {
+    let mut lines = [0; LINE_COUNT];
+    let mut n = 0;
+    // Each direction (dx, dy, dz) in -1..=1, counted once: the first
+    // non-zero step must be positive.
+    let mut d: i32 = 0;
+    while d < 27 {
+        let step = [(d % 3) - 1, (d / 3 % 3) - 1, (d / 9) - 1];
+        let first = if step[0] != 0 {
+            step[0]
+        } else if step[1] != 0 {
+            step[1]
+        } else {
+            step[2]
+        };
+        if first > 0 {
+            let mut start = 0;
+            while start < CELLS {
+                let from = [
+                    (start % SIZE) as i32,
+                    (start / SIZE % SIZE) as i32,
+                    (start / PEGS) as i32,
+                ];
+                let mut line = 0;
+                let mut i = 0;
+                while i < SIZE as i32 {
+                    let (x, y, z) = (
+                        from[0] + i * step[0],
+                        from[1] + i * step[1],
+                        from[2] + i * step[2],
+                    );
+                    let size = SIZE as i32;
+                    if x < 0 || x >= size || y < 0 || y >= size || z < 0 || z >= size {
+                        line = 0;
+                        break;
+                    }
+                    line |= bit(x as usize, y as usize, z as usize);
+                    i += 1;
+                }
+                if line != 0 {
+                    lines[n] = line;
+                    n += 1;
+                }
+                start += 1;
+            }
+        }
+        d += 1;
+    }
+    assert!(n == LINE_COUNT);
+    lines
+}
+
+const fn cell_lines() -> [u8; CELLS] {
+    let mut counts = [0; CELLS];
+    let mut i = 0;
+    while i < LINE_COUNT {
+        let mut cell = 0;
+        while cell < CELLS {
+            if LINES[i] & (1 << cell) != 0 {
+                counts[cell] += 1;
+            }
+            cell += 1;
+        }
+        i += 1;
+    }
+    counts
+}
+
+/// A position of Score Four: three-dimensional Connect Four on 16 pegs in
+/// a 4x4 square, each holding up to 4 beads. Four in a row wins in any of
+/// 76 lines: along the axes, along the diagonals of any plane, or through
+/// the centre of the cube. The game is a win for the first player.
+///
+/// Moves are peg numbers `y * 4 + x` from 0 to 15, written as one
+/// hexadecimal digit in game records (`0` to `f`).
+///
+/// Each player's beads are a bitboard with bit `z * 16 + y * 4 + x` for
+/// the cell at (`x`, `y`) on level `z`, 0 at the bottom.
+///
+/// As a `BoardGame` the four levels are drawn side by side, from the
+/// bottom level on the left: row `y`, column `z * 4 + x`. `to_layers`
+/// draws them with gaps between the levels.
+///
+/// Two states are equal when their positions are, whatever move led to
+/// them.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "ScoreFourRepr", try_from = "ScoreFourRepr")
+)]
+pub struct ScoreFourState {
+    player1: u64,
+    player2: u64,
+    /// The peg played last (see `GameState::last_move`).
+    last_move: Option<u8>,
+}
+
+impl PartialEq for ScoreFourState {
+    fn eq(&self, other: &Self) -> bool {
+        self.player1 == other.player1 && self.player2 == other.player2
+    }
+}
+
+impl Eq for ScoreFourState {}
+
+/// Serialized form of `ScoreFourState` (see the `serde` feature).
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ScoreFourRepr {
+    player1: u64,
+    player2: u64,
+}
+
+#[cfg(feature = "serde")]
+impl From<ScoreFourState> for ScoreFourRepr {
+    fn from(state: ScoreFourState) -> Self {
+        Self {
+            player1: state.player1,
+            player2: state.player2,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ScoreFourRepr> for ScoreFourState {
+    type Error = ParseError;
+
+    fn try_from(repr: ScoreFourRepr) -> Result<Self, Self::Error> {
+        Self::from_bits(repr.player1, repr.player2)
+    }
+}
+
+impl Default for ScoreFourState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScoreFourState {
+    /// The empty board, with Player1 to move.
+    pub fn new() -> Self {
+        Self {
+            player1: 0,
+            player2: 0,
+            last_move: None,
+        }
+    }
+
+    /// Builds a position from each player's bitboard, checking that it can
+    /// occur in a game: no cell taken twice, no bead above an empty cell,
+    /// Player1 at most one bead ahead, and at most one player with a line.
+    pub fn from_bits(player1: u64, player2: u64) -> Result<Self, ParseError> {
+        let counts = (player1.count_ones(), player2.count_ones());
+        if player1 & player2 != 0 || !(counts.0 == counts.1 || counts.0 == counts.1 + 1) {
+            return Err(ParseError::ImpossibleMarkCounts {
+                player1: counts.0 as usize,
+                player2: counts.1 as usize,
+            });
+        }
+        let state = Self {
+            player1,
+            player2,
+            last_move: None,
+        };
+        let occupied = state.occupied();
+        // Every bead above level 0 needs one below it.
+        let floating = occupied & !(occupied << PEGS) & !0xffff;
+        if floating != 0 {
+            let cell = floating.trailing_zeros() as usize;
+            let z = cell / PEGS;
+            return Err(ParseError::FloatingDisc {
+                row: cell / SIZE % SIZE,
+                col: z * SIZE + cell % SIZE,
+            });
+        }
+        if has_line(player1) && has_line(player2) {
+            return Err(ParseError::ImpossibleWin);
+        }
+        Ok(state)
+    }
+
+    /// Builds a position by playing pegs written as hexadecimal digits
+    /// from the empty board, e.g. `"05fa"`.
+    pub fn from_move_sequence(seq: &str) -> Result<Self, ParseError> {
+        let mut state = Self::new();
+        for (ply, ch) in seq.chars().enumerate() {
+            let peg = ch
+                .to_digit(16)
+                .ok_or(ParseError::InvalidChar { ch, index: ply })? as u8;
+            state = state
+                .try_apply_move(&peg)
+                .map_err(|error| ParseError::IllegalMoveAt {
+                    move_number: ply + 1,
+                    error,
+                })?;
+        }
+        Ok(state)
+    }
+
+    /// Player1's beads (see the layout above).
+    pub fn player1_bits(&self) -> u64 {
+        self.player1
+    }
+
+    /// Player2's beads.
+    pub fn player2_bits(&self) -> u64 {
+        self.player2
+    }
+
+    fn occupied(&self) -> u64 {
+        self.player1 | self.player2
+    }
+
+    /// Number of beads on `peg`.
+    pub fn height(&self, peg: u8) -> usize {
+        (self.occupied() & (PEG_MASK << peg)).count_ones() as usize
+    }
+
+    /// The player with four in a row, if any.
+    fn winner(&self) -> Option<Player> {
+        if has_line(self.player1) {
+            Some(Player::Player1)
+        } else if has_line(self.player2) {
+            Some(Player::Player2)
+        } else {
+            None
+        }
+    }
+
+    /// The content of the cell at (`x`, `y`) on level `z`.
+    pub fn cell_at(&self, x: usize, y: usize, z: usize) -> CellContent {
+        let bit = bit(x, y, z);
+        if self.player1 & bit != 0 {
+            CellContent::Piece(Player::Player1)
+        } else if self.player2 & bit != 0 {
+            CellContent::Piece(Player::Player2)
+        } else {
+            CellContent::Empty
+        }
+    }
+
+    /// Draws the levels side by side from the bottom one, with `X`, `O` and
+    /// `.` cells, under a header naming the levels.
+    pub fn to_layers(&self) -> String {
+        let mut out = String::new();
+        let header: Vec<String> = (1..=SIZE).map(|z| alloc::format!("level {z}")).collect();
+        out.push_str(&header.join("  "));
+        out.push('\n');
+        for y in 0..SIZE {
+            let levels: Vec<String> = (0..SIZE)
+                .map(|z| {
+                    let cells: Vec<&str> = (0..SIZE)
+                        .map(|x| match self.cell_at(x, y, z) {
+                            CellContent::Empty => ".",
+                            CellContent::Piece(Player::Player1) => "X",
+                            CellContent::Piece(Player::Player2) => "O",
+                        })
+                        .collect();
+                    cells.join(" ")
+                })
+                .collect();
+            out.push_str(&levels.join("  "));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Whether `beads` complete one of the lines.
+fn has_line(beads: u64) -> bool {
+    LINES.iter().any(|&line| line & !beads == 0)
+}
+
+impl GameState for ScoreFourState {
+    type Move = u8;
+
+    fn current_player(&self) -> Player {
+        if self.occupied().count_ones().is_multiple_of(2) {
+            Player::Player1
+        } else {
+            Player::Player2
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<u8> {
+        let mut moves = Vec::with_capacity(PEGS);
+        self.for_each_legal_move(|mv| moves.push(mv));
+        moves
+    }
+
+    fn for_each_legal_move(&self, mut f: impl FnMut(u8)) {
+        for peg in 0..PEGS as u8 {
+            if self.height(peg) < SIZE {
+                f(peg);
+            }
+        }
+    }
+
+    fn check_move(&self, mv: &u8) -> Result<(), MoveError> {
+        if self.is_terminal() {
+            Err(MoveError::GameOver)
+        } else if *mv as usize >= PEGS {
+            Err(MoveError::OutOfRange)
+        } else if self.height(*mv) >= SIZE {
+            Err(MoveError::ColumnFull)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn apply_move(&self, mv: &u8) -> Self {
+        let bead = 1 << (self.height(*mv) * PEGS + *mv as usize);
+        let mut next = self.clone();
+        match self.current_player() {
+            Player::Player1 => next.player1 |= bead,
+            Player::Player2 => next.player2 |= bead,
+        }
+        next.last_move = Some(*mv);
+        next
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.terminal_value().is_some()
+    }
+
+    fn terminal_value(&self) -> Option<i32> {
+        match self.winner() {
+            Some(Player::Player1) => Some(1),
+            Some(Player::Player2) => Some(-1),
+            None => (self.occupied().count_ones() as usize == CELLS).then_some(0),
+        }
+    }
+
+    /// Scores every line that only one player has beads in, by
+    /// `LINE_WEIGHTS` of their number: positive for Player1, negative for
+    /// Player2.
+    fn heuristic_value(&self) -> i32 {
+        LINES
+            .iter()
+            .map(|&line| {
+                let p1 = (self.player1 & line).count_ones() as usize;
+                let p2 = (self.player2 & line).count_ones() as usize;
+                match (p1, p2) {
+                    (n, 0) => LINE_WEIGHTS[n],
+                    (0, n) => -LINE_WEIGHTS[n],
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+
+    /// Pegs whose next cell lies on more lines first: corners and centre.
+    fn move_ordering_key(&self, mv: &u8) -> i32 {
+        let cell = self.height(*mv) * PEGS + *mv as usize;
+        CELL_LINES.get(cell).map_or(0, |&n| n as i32)
+    }
+
+    fn last_move(&self) -> Option<u8> {
+        self.last_move
+    }
+
+    fn ply(&self) -> Option<u32> {
+        Some(self.occupied().count_ones())
+    }
+
+    fn complexity_hint(&self) -> ComplexityHint {
+        ComplexityHint {
+            branching_factor: 5.0,
+            max_plies: Some(CELLS as u32 - self.occupied().count_ones()),
+            recommended_depths: 1..=7,
+        }
+    }
+
+    fn recommended_depth(&self) -> u32 {
+        6
+    }
+}
+
+impl PositionKey for ScoreFourState {
+    /// Mixes both bitboards; unlike the other built-in games, distinct
+    /// positions may (very rarely) share a key, as 64 cells with three
+    /// states each don't fit in 64 bits.
+    fn position_key(&self) -> u64 {
+        mix(self.player1 ^ mix(self.player2))
+    }
+}
+
+impl BoardGame for ScoreFourState {
+    fn rows(&self) -> usize {
+        SIZE
+    }
+
+    fn cols(&self) -> usize {
+        SIZE * SIZE
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        assert!(
+            row < SIZE && col < SIZE * SIZE,
+            "cell ({row}, {col}) is off the board"
+        );
+        self.cell_at(col % SIZE, row, col / SIZE)
+    }
+}
+
+impl RegisteredGame for ScoreFourState {
+    const NAME: &'static str = "score4";
+    const DISPLAY_NAME: &'static str = "Score Four";
+    const MOVE_SEPARATOR: &'static str = "";
+
+    fn initial() -> Self {
+        Self::new()
+    }
+
+    fn format_move(mv: &u8) -> String {
+        char::from_digit(u32::from(*mv), 16).map_or_else(|| mv.to_string(), String::from)
+    }
+
+    /// Accepts a hexadecimal digit as written by `format_move`, or the peg
+    /// number in decimal.
+    fn parse_move(&self, text: &str) -> Result<u8, ParseError> {
+        let text = text.trim();
+        let peg = text
+            .parse()
+            .ok()
+            .or_else(|| u8::from_str_radix(text, 16).ok())
+            .ok_or_else(|| ParseError::NotANumber(text.to_string()))?;
+        self.check_move(&peg)?;
+        Ok(peg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Outcome;
+    use crate::render::render_board;
+    use crate::solvers::minimax::minimax_best_move_ab_depth;
+
+    #[test]
+    fn the_lines_are_all_distinct_fours() {
+        assert!(LINES.iter().all(|line| line.count_ones() == 4));
+        let mut sorted = LINES;
+        sorted.sort_unstable();
+        assert!(sorted.windows(2).all(|w| w[0] != w[1]));
+        // The cube's corners and centre lie on 7 lines, the rest on 4.
+        assert_eq!(CELL_LINES[0], 7);
+        assert_eq!(CELL_LINES[bit(1, 1, 1).trailing_zeros() as usize], 7);
+        assert_eq!(CELL_LINES[1], 4);
+        let total: usize = CELL_LINES.iter().map(|&n| n as usize).sum();
+        assert_eq!(total, LINE_COUNT * 4);
+    }
+
+    #[test]
+    fn wins_along_every_kind_of_line() {
+        let win = Some(Outcome::Win(Player::Player1));
+        // Straight up peg 0, with Player2 stacking peg 1.
+        let up = ScoreFourState::from_move_sequence("0101010").unwrap();
+        assert_eq!(up.outcome(), win);
+        // Along the bottom row.
+        let row = ScoreFourState::from_move_sequence("0415263").unwrap();
+        assert_eq!(row.outcome(), win);
+        // Through the centre of the cube, from peg 0 on the bottom level to
+        // peg 15 on the top one.
+        let diagonal = ScoreFourState::from_move_sequence("055afaaf3ff").unwrap();
+        assert_eq!(diagonal.outcome(), win, "{}", diagonal.to_layers());
+        let before = ScoreFourState::from_move_sequence("055afaaf3f").unwrap();
+        assert_eq!(before.outcome(), None);
+        assert_eq!(diagonal.check_move(&0), Err(MoveError::GameOver));
+        assert_eq!(before.check_move(&16), Err(MoveError::OutOfRange));
+        assert_eq!(up.ply(), Some(7));
+        assert_eq!(
+            ScoreFourState::from_move_sequence("00000"),
+            Err(ParseError::IllegalMoveAt {
+                move_number: 5,
+                error: MoveError::ColumnFull,
+            })
+        );
+    }
+
+    #[test]
+    fn the_search_takes_and_blocks_lines() {
+        // Player1 has three on the bottom row: take the fourth...
+        let state = ScoreFourState::from_move_sequence("041526").unwrap();
+        let best = minimax_best_move_ab_depth(&state, 2).map(|(mv, _)| mv);
+        assert_eq!(best, Some(3));
+        // ...or, as Player2, block it.
+        let state = ScoreFourState::from_move_sequence("04152").unwrap();
+        let best = minimax_best_move_ab_depth(&state, 2).map(|(mv, _)| mv);
+        assert_eq!(best, Some(3));
+        assert!(state.heuristic_value() > 0);
+    }
+
+    #[test]
+    fn draws_levels_side_by_side() {
+        let state = ScoreFourState::from_move_sequence("005").unwrap();
+        assert_eq!(
+            state.to_layers(),
+            "level 1  level 2  level 3  level 4\n\
+             X . . .  O . . .  . . . .  . . . .\n\
+             . X . .  . . . .  . . . .  . . . .\n\
+             . . . .  . . . .  . . . .  . . . .\n\
+             . . . .  . . . .  . . . .  . . . .\n"
+        );
+        let grid = render_board(&state);
+        assert!(grid.starts_with("X | . | . | . | O | . "), "{grid}");
+        assert_eq!(state.cell(1, 1), CellContent::Piece(Player::Player1));
+        assert_eq!(
+            ScoreFourState::from_bits(state.player1_bits(), state.player2_bits()),
+            Ok(state)
+        );
+        assert_eq!(
+            ScoreFourState::from_bits(bit(0, 0, 1), 0),
+            Err(ParseError::FloatingDisc { row: 0, col: 4 })
+        );
+        assert!(ScoreFourState::from_bits(1, 1).is_err());
+        assert_eq!(ScoreFourState::format_move(&15), "f");
+        assert_eq!(ScoreFourState::new().parse_move("12"), Ok(12));
+        assert_eq!(ScoreFourState::new().parse_move("c"), Ok(12));
+    }
+}
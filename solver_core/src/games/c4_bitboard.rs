@@ -1,59 +1,400 @@
-use crate::game::{GameState, Player};
+#[cfg(feature = "serde")]
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::error::{MoveError, ParseError};
+use crate::eval::Evaluator;
+use crate::game::{
+    BoardGame, CellContent, ComplexityHint, GameState, Player, PositionKey, SwapPlayers,
+};
+use crate::registry::RegisteredGame;
+use crate::render::describe::Threat;
+use crate::selection::SelectBy;
+use crate::tablebase::PositionIndex;
+use crate::transforms::{Symmetric, Transform};
 use crate::utils::opposite_player;
 
-/// Board geometry for bitboard layout:
-/// - 6 playable rows
-/// - 7 columns
-/// - 1 sentinel bit per column
-const ROWS: u8 = 6;
-const COLS: u8 = 7;
-const BITS_PER_COL: u8 = ROWS + 1; // = 7
-const WIN_LENGTH: u8 = 4;
+/// Number of aligned discs needed to win.
+const WIN_LENGTH: usize = 4;
+/// Column weights for the standard 7-column board; other widths are mapped
+/// onto this table proportionally (see `C4Bitboard::col_weight`).
 const COL_WEIGHTS: [i32; 7] = [3, 4, 5, 7, 5, 4, 3];
 
-/// Efficient bitboard-based representation of Connect Four.
+/// Weights used by the Connect Four heuristic (`C4Bitboard::evaluate_with`).
+///
+/// A 4-cell window containing only one player's discs scores `window_two`,
+/// `window_three`, or `window_four` for 2, 3, or 4 discs (positive for
+/// Player1, negated for Player2). Each disc additionally scores the weight
+/// of its column. `Default` gives the hand-tuned values used by `evaluate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct C4EvalParams {
+    pub window_two: i32,
+    pub window_three: i32,
+    pub window_four: i32,
+    pub col_weights: [i32; 7],
+}
+
+impl Default for C4EvalParams {
+    fn default() -> Self {
+        Self {
+            window_two: 10,
+            window_three: 100,
+            window_four: 100000,
+            col_weights: COL_WEIGHTS,
+        }
+    }
+}
+
+/// The standard 7-column, 6-row Connect Four board.
+pub type BitboardState = C4Bitboard<6, 7>;
+
+/// Connect Four on 8 columns of 7 rows, a size still open to solving.
+pub type BitboardState8x7 = C4Bitboard<7, 8>;
+
+/// The empty cells where one player would complete four in a row, as
+/// found by `C4Bitboard::threats`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThreatMap {
+    /// The cells as a bitboard in `C4Bitboard`'s layout, e.g. to combine
+    /// with `C4Bitboard::playable_cells`.
+    pub bits: u64,
+    /// The same cells as `(row, col)` with row 0 at the top, as in
+    /// `BoardGame::cell`, top row first.
+    pub cells: Vec<(usize, usize)>,
+}
+
+/// Both players' threats in a Connect Four position.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Threats {
+    pub player1: ThreatMap,
+    pub player2: ThreatMap,
+}
+
+impl Threats {
+    /// The threats of `player`.
+    pub fn of(&self, player: Player) -> &ThreatMap {
+        match player {
+            Player::Player1 => &self.player1,
+            Player::Player2 => &self.player2,
+        }
+    }
+}
+
+/// Efficient bitboard-based representation of Connect Four on a
+/// `ROWS` x `COLS` board.
+///
+/// Layout:
+/// - each column occupies `ROWS + 1` bits (`ROWS` playable + 1 sentinel)
+/// - bit index = col * (ROWS + 1) + row, with row 0 at the bottom
+///
+/// The whole board must fit in a `u64`, i.e. `(ROWS + 1) * COLS <= 64`.
+/// This is checked at compile time when a board size is first used:
+///
+/// ```compile_fail
+/// use solver_core::games::c4_bitboard::C4Bitboard;
 ///
-/// Uses the canonical 7x6+padding layout:
-/// - each column occupies 7 bits (6 playable + 1 sentinel)
-/// - bit index = col * 7 + row
+/// // 9 * 8 = 72 bits does not fit in a u64.
+/// let _ = C4Bitboard::<8, 8>::new();
+/// ```
 ///
 /// player_bb: bits for Player1's discs
 /// mask_bb:  bits for all discs (P1 + P2)
-/// heights: next free bit index for each column  
+///
+/// Column heights are not stored; they are derived from `mask_bb` (see
+/// `height` and `heights`), which keeps the state at two words plus the
+/// side to move.
+///
+/// Two states are equal when their positions are, whatever move led to them.
 #[derive(Clone, Debug)]
-pub struct BitboardState {
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "BitboardRepr", try_from = "BitboardRepr")
+)]
+pub struct C4Bitboard<const ROWS: usize, const COLS: usize> {
     pub player_bb: u64,
     pub mask_bb: u64,
-    pub heights: [u8; COLS as usize],
     pub current_player: Player,
+    /// The column played last (see `GameState::last_move`).
+    last_move: Option<u8>,
+}
+
+impl<const ROWS: usize, const COLS: usize> PartialEq for C4Bitboard<ROWS, COLS> {
+    fn eq(&self, other: &Self) -> bool {
+        self.player_bb == other.player_bb
+            && self.mask_bb == other.mask_bb
+            && self.current_player == other.current_player
+    }
 }
 
-impl Default for BitboardState {
+impl<const ROWS: usize, const COLS: usize> Eq for C4Bitboard<ROWS, COLS> {}
+
+impl<const ROWS: usize, const COLS: usize> Default for C4Bitboard<ROWS, COLS> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl BitboardState {
+impl<const ROWS: usize, const COLS: usize> C4Bitboard<ROWS, COLS> {
+    /// Bits per column, including the sentinel bit.
+    const BITS_PER_COL: usize = ROWS + 1;
+
+    /// Evaluated in `new`, so an oversized board fails to compile.
+    const FITS_IN_U64: () = assert!(
+        ROWS > 0 && COLS > 0 && (ROWS + 1) * COLS <= 64,
+        "board must be non-empty and (ROWS + 1) * COLS must not exceed 64 bits"
+    );
+
+    /// One bit at the bottom of every column.
+    const BOTTOM_MASK: u64 = {
+        let mut mask = 0u64;
+        let mut col = 0;
+        while col < COLS {
+            mask |= 1u64 << (col * (ROWS + 1));
+            col += 1;
+        }
+        mask
+    };
+
+    /// Number of different stacks of discs a column can hold, which is also
+    /// a mask of the column's bits in `key`.
+    const COLUMN_CODES: u64 = (1u64 << (ROWS + 1)) - 1;
+
+    /// Every playable cell; `mask_bb` equals this when the board is full.
+    const BOARD_MASK: u64 = Self::BOTTOM_MASK * ((1u64 << ROWS) - 1);
+
     /// Creates a new empty bitboard state.
     pub fn new() -> Self {
+        let () = Self::FITS_IN_U64;
         Self {
             player_bb: 0,
             mask_bb: 0,
-            heights: [0; COLS as usize],
             current_player: Player::Player1,
+            last_move: None,
+        }
+    }
+
+    /// Builds a position by playing a sequence of 0-based column digits from
+    /// the empty board, e.g. `"3342"`.
+    ///
+    /// Returns an error for non-digit characters, columns outside the board,
+    /// moves into full columns, and moves played after the game has ended.
+    pub fn from_move_sequence(seq: &str) -> Result<Self, ParseError> {
+        Self::from_columns(seq, 0)
+    }
+
+    /// Builds a position from a move sequence in the format of Pascal Pons'
+    /// solver and test sets: 1-based column digits, e.g. `"4453"` for the
+    /// `from_move_sequence` sequence `"3342"`.
+    ///
+    /// Returns the same errors as `from_move_sequence`.
+    pub fn from_pons_sequence(seq: &str) -> Result<Self, ParseError> {
+        Self::from_columns(seq, 1)
+    }
+
+    /// Plays a sequence of column digits numbered from `first`.
+    fn from_columns(seq: &str, first: u32) -> Result<Self, ParseError> {
+        let mut state = Self::new();
+        for (ply, ch) in seq.chars().enumerate() {
+            let col = ch
+                .to_digit(10)
+                .and_then(|d| d.checked_sub(first))
+                .filter(|&d| (d as usize) < COLS)
+                .ok_or(ParseError::InvalidChar { ch, index: ply })? as u8;
+            state = state
+                .try_apply_move(&col)
+                .map_err(|error| ParseError::IllegalMoveAt {
+                    move_number: ply + 1,
+                    error,
+                })?;
+        }
+        Ok(state)
+    }
+
+    /// Builds a position from a grid of `ROWS` rows of `COLS` cells, top
+    /// row first: `X`, `x` or `1` for Player1's discs, `O`, `o` or `2` for
+    /// Player2's, and `.`, `-` or `0` for empty cells. Whitespace (such as
+    /// line breaks between rows) is ignored, so `to_grid` output and
+    /// one-line grids are both accepted.
+    ///
+    /// The position is checked to be reachable in a game: the side to move
+    /// follows from the disc counts, which must be equal or one more for
+    /// Player1; no disc may float above an empty cell; and if a player has
+    /// won, the last disc they played must have completed every winning
+    /// line, with nobody else having won.
+    pub fn from_grid(grid: &str) -> Result<Self, ParseError> {
+        let cells: Vec<(usize, char)> = grid
+            .chars()
+            .filter(|ch| !ch.is_whitespace())
+            .enumerate()
+            .collect();
+        if cells.len() != ROWS * COLS {
+            return Err(ParseError::WrongLength {
+                expected: ROWS * COLS,
+                found: cells.len(),
+            });
+        }
+        let mut state = Self::new();
+        for (index, ch) in cells {
+            let (row, col) = (index / COLS, index % COLS);
+            let bit = 1u64 << Self::idx(ROWS - 1 - row, col);
+            match ch {
+                'X' | 'x' | '1' => {
+                    state.player_bb |= bit;
+                    state.mask_bb |= bit;
+                }
+                'O' | 'o' | '2' => state.mask_bb |= bit,
+                '.' | '-' | '0' => {}
+                ch => return Err(ParseError::InvalidChar { ch, index }),
+            }
+        }
+        for col in 0..COLS {
+            let discs = state.mask_bb & Self::column_mask(col as u8);
+            let height = discs.count_ones() as usize;
+            if discs != ((1u64 << height) - 1) << Self::idx(0, col) {
+                // The lowest empty cell with a disc above it.
+                let gap = (discs >> Self::idx(0, col)).trailing_ones() as usize;
+                return Err(ParseError::FloatingDisc {
+                    row: ROWS - 1 - gap,
+                    col,
+                });
+            }
+        }
+
+        let player1 = state.player_bb.count_ones() as usize;
+        let player2 = state.p2_bb().count_ones() as usize;
+        state.current_player = if player1 == player2 {
+            Player::Player1
+        } else if player1 == player2 + 1 {
+            Player::Player2
+        } else {
+            return Err(ParseError::ImpossibleMarkCounts { player1, player2 });
+        };
+
+        let winner = match (
+            state.check_win(state.player_bb),
+            state.check_win(state.p2_bb()),
+        ) {
+            (false, false) => return Ok(state),
+            (true, false) => Player::Player1,
+            (false, true) => Player::Player2,
+            (true, true) => return Err(ParseError::ImpossibleWin),
+        };
+        // The winner moved last, with a disc that is now on top of its
+        // column and without which nobody had won.
+        let last_move_won = winner != state.current_player
+            && (0..COLS as u8).any(|col| {
+                let height = state.height(col);
+                if height == 0 {
+                    return false;
+                }
+                let top = 1u64 << Self::idx(height as usize - 1, col as usize);
+                let winners_disc = (state.player_bb & top != 0) == (winner == Player::Player1);
+                let before = Self {
+                    player_bb: state.player_bb & !top,
+                    mask_bb: state.mask_bb & !top,
+                    current_player: winner,
+                    last_move: None,
+                };
+                winners_disc && before.terminal_value().is_none()
+            });
+        if last_move_won {
+            Ok(state)
+        } else {
+            Err(ParseError::ImpossibleWin)
         }
     }
 
+    /// The position as `ROWS` lines of `COLS` cells (`X`, `O` or `.`), top
+    /// row first, as accepted by `from_grid`.
+    pub fn to_grid(&self) -> String {
+        let mut out = String::with_capacity(ROWS * (COLS + 1));
+        for row in 0..ROWS {
+            for col in 0..COLS {
+                out.push(match self.cell(row, col) {
+                    CellContent::Empty => '.',
+                    CellContent::Piece(Player::Player1) => 'X',
+                    CellContent::Piece(Player::Player2) => 'O',
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
     /// Computes the bit mask for the next empty cell in the given column.
+    ///
+    /// Adding the column's bottom bit to the mask carries through the discs
+    /// already played and lands on the first empty cell. Returns 0 if the
+    /// column is full.
     pub fn next_bit(&self, col: u8) -> u64 {
-        let bit_index = (col * BITS_PER_COL) + self.heights[col as usize];
-        1u64 << (bit_index as u64)
+        (self.mask_bb + Self::bottom_mask_col(col)) & Self::column_mask(col)
+    }
+
+    /// Returns true if `col` still has an empty cell.
+    #[inline]
+    pub fn can_play(&self, col: u8) -> bool {
+        self.mask_bb & Self::top_mask_col(col) == 0
     }
 
+    /// Number of discs in `col`.
     #[inline]
-    fn idx(row: u8, col: u8) -> u8 {
-        col * BITS_PER_COL + row
+    pub fn height(&self, col: u8) -> u8 {
+        (self.mask_bb & Self::column_mask(col)).count_ones() as u8
+    }
+
+    /// Number of discs in each column.
+    pub fn heights(&self) -> [u8; COLS] {
+        core::array::from_fn(|col| self.height(col as u8))
+    }
+
+    /// A number that uniquely identifies the position.
+    ///
+    /// Adding `mask_bb` to Player1's discs sets one bit just above each
+    /// column's top disc, so no two positions share a key. The side to move
+    /// follows from the disc count.
+    pub fn key(&self) -> u64 {
+        self.player_bb + self.mask_bb
+    }
+
+    /// Returns the position reflected left to right.
+    pub fn mirrored(&self) -> Self {
+        let flip = |bb: u64| {
+            (0..COLS as u8).fold(0u64, |acc, col| {
+                let bits = (bb & Self::column_mask(col)) >> Self::idx(0, col as usize);
+                acc | bits << Self::idx(0, COLS - 1 - col as usize)
+            })
+        };
+        let mut state = Self::new();
+        state.player_bb = flip(self.player_bb);
+        state.mask_bb = flip(self.mask_bb);
+        state.current_player = self.current_player;
+        state.last_move = self.last_move.map(|col| COLS as u8 - 1 - col);
+        state
+    }
+
+    #[inline]
+    fn bottom_mask_col(col: u8) -> u64 {
+        1u64 << Self::idx(0, col as usize)
+    }
+
+    #[inline]
+    fn top_mask_col(col: u8) -> u64 {
+        1u64 << Self::idx(ROWS - 1, col as usize)
+    }
+
+    #[inline]
+    fn column_mask(col: u8) -> u64 {
+        ((1u64 << ROWS) - 1) << Self::idx(0, col as usize)
+    }
+
+    #[inline]
+    fn idx(row: usize, col: usize) -> usize {
+        col * Self::BITS_PER_COL + row
     }
 
     /// Applies a move in the given column and returns the resulting new state.
@@ -61,13 +402,10 @@ impl BitboardState {
     /// This:
     /// - sets the bit for the current player
     /// - updates the mask_bb
-    /// - increments heights[col]
     /// - swaps the current player
     pub fn apply_column_move(&self, col: u8) -> Self {
         let next_move = self.next_bit(col);
         let new_mask_bb = self.mask_bb | next_move;
-        let mut new_heights = self.heights;
-        new_heights[col as usize] += 1;
         let new_player_bb = match self.current_player {
             Player::Player1 => self.player_bb | next_move,
             Player::Player2 => self.player_bb,
@@ -76,8 +414,8 @@ impl BitboardState {
         Self {
             player_bb: new_player_bb,
             mask_bb: new_mask_bb,
-            heights: new_heights,
             current_player: new_player,
+            last_move: Some(col),
         }
     }
 
@@ -87,96 +425,198 @@ impl BitboardState {
     }
 
     #[inline]
-    fn has_run(bb: u64, shift: u8) -> bool {
-        let s = shift as u64;
-        let x = bb & (bb >> s);
-        (x & (x >> (2 * s))) != 0
+    fn has_run(bb: u64, shift: usize) -> bool {
+        let x = bb & (bb >> shift);
+        (x & (x >> (2 * shift))) != 0
     }
 
     /// Checks whether the bitboard `bb` contains a 4-in-a-row.
     ///
-    /// Win directions are detected using bit shifts:
-    /// - Horizontal: shift by 1
-    /// - Vertical:   shift by BITS_PER_COL (7)
-    /// - Diag ↘:     shift by BITS_PER_COL + 1 (8)
-    /// - Diag ↗:     shift by BITS_PER_COL - 1 (6)
+    /// Win directions are detected using bit shifts (H = ROWS + 1):
+    /// - Vertical:   shift by 1
+    /// - Horizontal: shift by H
+    /// - Diag ↗:     shift by H + 1
+    /// - Diag ↘:     shift by H - 1
     ///
     /// Returns true if any direction yields 4 aligned bits.
     pub fn check_win(&self, bb: u64) -> bool {
+        let h = Self::BITS_PER_COL;
         Self::has_run(bb, 1)
-            || Self::has_run(bb, BITS_PER_COL)
-            || Self::has_run(bb, BITS_PER_COL + 1)
-            || Self::has_run(bb, BITS_PER_COL - 1)
+            || Self::has_run(bb, h)
+            || Self::has_run(bb, h + 1)
+            || Self::has_run(bb, h - 1)
+    }
+
+    /// The cells where a disc can be dropped now: the lowest empty cell of
+    /// each column that isn't full.
+    pub fn playable_cells(&self) -> u64 {
+        (self.mask_bb + Self::BOTTOM_MASK) & Self::BOARD_MASK
+    }
+
+    /// The empty cells that would complete four in a row for `bb`'s
+    /// owner, whether or not a disc can be dropped there yet.
+    ///
+    /// For each direction, a cell completes a line if the three cells on
+    /// one side of it (`XXX.`), or two on one side and one on the other
+    /// (`XX.X`), are in `bb`. Shifts that run off a column land in its
+    /// empty sentinel bit, so lines never wrap around the board.
+    fn winning_cells(&self, bb: u64) -> u64 {
+        let h = Self::BITS_PER_COL;
+        // Vertical: only three discs below can complete a line.
+        let mut cells = (bb << 1) & (bb << 2) & (bb << 3);
+        for shift in [h, h + 1, h - 1] {
+            let pair = (bb << shift) & (bb << (2 * shift));
+            cells |= pair & (bb << (3 * shift));
+            cells |= pair & (bb >> shift);
+            let pair = (bb >> shift) & (bb >> (2 * shift));
+            cells |= pair & (bb << shift);
+            cells |= pair & (bb >> (3 * shift));
+        }
+        cells & Self::BOARD_MASK & !self.mask_bb
+    }
+
+    /// Each player's threats: the empty cells where a disc of theirs would
+    /// complete four in a row, both as bitboards and as board coordinates.
+    ///
+    /// Threats in `playable_cells` can be played at once; the others
+    /// matter for the endgame, where the parity of a threat's row decides
+    /// who is forced to fill the cell below it.
+    pub fn threats(&self) -> Threats {
+        let map = |bits: u64| ThreatMap {
+            bits,
+            cells: (0..ROWS)
+                .flat_map(|row| (0..COLS).map(move |col| (row, col)))
+                .filter(|&(row, col)| bits & 1u64 << Self::idx(ROWS - 1 - row, col) != 0)
+                .collect(),
+        };
+        Threats {
+            player1: map(self.winning_cells(self.player_bb)),
+            player2: map(self.winning_cells(self.p2_bb())),
+        }
     }
 
     /// Returns true if the board is full (mask_bb contains all playable cells).
     pub fn is_full(&self) -> bool {
-        self.heights.iter().all(|&h| h == ROWS)
+        self.mask_bb == Self::BOARD_MASK
     }
 
     /// Computes a heuristic evaluation based on:
     /// - all horizontal, vertical, and diagonal windows of length 4
     /// - center column occupancy
     pub fn evaluate(&self) -> i32 {
-        let p1_board = self.player_bb;
-        let p2_board = self.p2_bb();
+        self.evaluate_with(&C4EvalParams::default())
+    }
+
+    /// Like `evaluate`, but with custom weights.
+    pub fn evaluate_with(&self, params: &C4EvalParams) -> i32 {
         if let Some(v) = self.terminal_value() {
             // Scale terminal values so they dominate heuristic noise
             return v * 1000000;
         }
-        self.score_all_windows(p1_board, p2_board) + self.center_control_score(p1_board, p2_board)
+        self.static_score(params)
+    }
+
+    /// The window and center-control terms of the heuristic, ignoring
+    /// whether the position is terminal.
+    fn static_score(&self, params: &C4EvalParams) -> i32 {
+        let p1_board = self.player_bb;
+        let p2_board = self.p2_bb();
+        self.score_all_windows(p1_board, p2_board, params)
+            + self.center_control_score(p1_board, p2_board, params)
+    }
+
+    /// Applies a move in `col` and returns the new state together with the
+    /// change in `static_score`.
+    ///
+    /// Only the (at most 16) windows through the new disc and the weight of
+    /// its column can change, so this is much cheaper than rescoring the
+    /// whole board.
+    fn apply_with_delta(&self, col: u8, params: &C4EvalParams) -> (Self, i32) {
+        let row = self.height(col) as isize;
+        let next = self.apply_column_move(col);
+        let in_bounds =
+            |r: isize, c: isize| (0..ROWS as isize).contains(&r) && (0..COLS as isize).contains(&c);
+
+        let mut delta = 0;
+        for (dr, dc) in [(0, 1), (1, 0), (1, 1), (-1, 1)] {
+            for k in 0..WIN_LENGTH as isize {
+                let (r0, c0) = (row - k * dr, col as isize - k * dc);
+                let last = WIN_LENGTH as isize - 1;
+                if !in_bounds(r0, c0) || !in_bounds(r0 + last * dr, c0 + last * dc) {
+                    continue;
+                }
+                let mask = (0..WIN_LENGTH as isize).fold(0u64, |acc, i| {
+                    acc | 1u64 << Self::idx((r0 + i * dr) as usize, (c0 + i * dc) as usize)
+                });
+                delta += next.score_window(next.player_bb, next.p2_bb(), mask, params)
+                    - self.score_window(self.player_bb, self.p2_bb(), mask, params);
+            }
+        }
+        let weight = Self::col_weight(&params.col_weights, col as usize);
+        delta += match self.current_player {
+            Player::Player1 => weight,
+            Player::Player2 => -weight,
+        };
+        (next, delta)
     }
 
     /// Scores all windows of 4 cells on the board.
     ///
     /// This function iterates over all possible 4-cell segments (horiz, vert, diag)
     /// and aggregates their contributions to the heuristic.
-    fn score_all_windows(&self, p1_board: u64, p2_board: u64) -> i32 {
-        self.check_horizontal(p1_board, p2_board)
-            + self.check_vertical(p1_board, p2_board)
-            + self.check_diag_down(p1_board, p2_board)
-            + self.check_diag_up(p1_board, p2_board)
+    fn score_all_windows(&self, p1_board: u64, p2_board: u64, params: &C4EvalParams) -> i32 {
+        self.check_horizontal(p1_board, p2_board, params)
+            + self.check_vertical(p1_board, p2_board, params)
+            + self.check_diag_down(p1_board, p2_board, params)
+            + self.check_diag_up(p1_board, p2_board, params)
     }
 
     #[inline]
-    fn window_mask(coords: &[(u8, u8); 4]) -> u64 {
+    fn window_mask(coords: &[(usize, usize); 4]) -> u64 {
         coords
             .iter()
-            .fold(0u64, |acc, &(r, c)| acc | (1u64 << Self::idx(r, c) as u64))
+            .fold(0u64, |acc, &(r, c)| acc | (1u64 << Self::idx(r, c)))
+    }
+
+    /// Number of window start positions along a line of `len` cells
+    /// (zero if the line is shorter than a window).
+    #[inline]
+    const fn window_starts(len: usize) -> usize {
+        (len + 1).saturating_sub(WIN_LENGTH)
     }
 
     /// Checks all horizontal lines for a 4-in-a-row.
     /// Returns the heuristic score.
-    fn check_horizontal(&self, p1_board: u64, p2_board: u64) -> i32 {
+    fn check_horizontal(&self, p1_board: u64, p2_board: u64, params: &C4EvalParams) -> i32 {
         let mut score: i32 = 0;
-        for col in 0..=(COLS - WIN_LENGTH) {
+        for col in 0..Self::window_starts(COLS) {
             for row in 0..ROWS {
                 let coords = [(row, col), (row, col + 1), (row, col + 2), (row, col + 3)];
                 let mask = Self::window_mask(&coords);
-                score += self.score_window(p1_board, p2_board, mask);
+                score += self.score_window(p1_board, p2_board, mask, params);
             }
         }
         score
     }
 
     /// Checks vertical lines for 4-in-a-row.
-    fn check_vertical(&self, p1_board: u64, p2_board: u64) -> i32 {
+    fn check_vertical(&self, p1_board: u64, p2_board: u64, params: &C4EvalParams) -> i32 {
         let mut score: i32 = 0;
         for col in 0..COLS {
-            for row in 0..=(ROWS - WIN_LENGTH) {
+            for row in 0..Self::window_starts(ROWS) {
                 let coords = [(row, col), (row + 1, col), (row + 2, col), (row + 3, col)];
                 let mask = Self::window_mask(&coords);
-                score += self.score_window(p1_board, p2_board, mask);
+                score += self.score_window(p1_board, p2_board, mask, params);
             }
         }
         score
     }
 
     /// Checks diagonal down-right lines (↘).
-    fn check_diag_down(&self, p1_board: u64, p2_board: u64) -> i32 {
+    fn check_diag_down(&self, p1_board: u64, p2_board: u64, params: &C4EvalParams) -> i32 {
         let mut score: i32 = 0;
-        for col in 0..=(COLS - WIN_LENGTH) {
-            for row in 0..=(ROWS - WIN_LENGTH) {
+        for col in 0..Self::window_starts(COLS) {
+            for row in 0..Self::window_starts(ROWS) {
                 let coords = [
                     (row, col),
                     (row + 1, col + 1),
@@ -184,16 +624,16 @@ impl BitboardState {
                     (row + 3, col + 3),
                 ];
                 let mask = Self::window_mask(&coords);
-                score += self.score_window(p1_board, p2_board, mask);
+                score += self.score_window(p1_board, p2_board, mask, params);
             }
         }
         score
     }
 
     /// Checks diagonal up-right lines (↗).
-    fn check_diag_up(&self, p1_board: u64, p2_board: u64) -> i32 {
+    fn check_diag_up(&self, p1_board: u64, p2_board: u64, params: &C4EvalParams) -> i32 {
         let mut score: i32 = 0;
-        for col in 0..=(COLS - WIN_LENGTH) {
+        for col in 0..Self::window_starts(COLS) {
             for row in (WIN_LENGTH - 1)..ROWS {
                 let coords = [
                     (row, col),
@@ -202,7 +642,7 @@ impl BitboardState {
                     (row - 3, col + 3),
                 ];
                 let mask = Self::window_mask(&coords);
-                score += self.score_window(p1_board, p2_board, mask);
+                score += self.score_window(p1_board, p2_board, mask, params);
             }
         }
         score
@@ -217,29 +657,53 @@ impl BitboardState {
     /// `window_mask` selects the 4 cells.
     /// This method counts how many belong to Player1, how many to Player2,
     /// and returns a signed score contribution.
-    fn score_window(&self, p1_board: u64, p2_board: u64, window_mask: u64) -> i32 {
+    fn score_window(
+        &self,
+        p1_board: u64,
+        p2_board: u64,
+        window_mask: u64,
+        params: &C4EvalParams,
+    ) -> i32 {
         let num_p1_chips = self.count_player_chips(p1_board, window_mask);
         let num_p2_chips = self.count_player_chips(p2_board, window_mask);
         match (num_p1_chips, num_p2_chips) {
-            (4, 0) => 100000,
-            (3, 0) => 100,
-            (2, 0) => 10,
-            (0, 2) => -10,
-            (0, 3) => -100,
-            (0, 4) => -100000,
+            (4, 0) => params.window_four,
+            (3, 0) => params.window_three,
+            (2, 0) => params.window_two,
+            (0, 2) => -params.window_two,
+            (0, 3) => -params.window_three,
+            (0, 4) => -params.window_four,
             _ => 0,
         }
     }
 
-    fn score_column(&self, p1_board: u64, p2_board: u64, column: u8) -> i32 {
+    /// Looks up the weight of `column` in a 7-entry weight table, stretching
+    /// the table over boards that are narrower or wider than 7 columns.
+    #[inline]
+    fn col_weight(weights: &[i32; 7], column: usize) -> i32 {
+        if COLS == 7 {
+            weights[column]
+        } else if COLS == 1 {
+            weights[3]
+        } else {
+            weights[column * 6 / (COLS - 1)]
+        }
+    }
+
+    fn score_column(
+        &self,
+        p1_board: u64,
+        p2_board: u64,
+        column: usize,
+        params: &C4EvalParams,
+    ) -> i32 {
         let mut col_mask = 0u64;
         for row in 0..ROWS {
-            let idx = Self::idx(row, column);
-            col_mask |= 1u64 << idx as u64;
+            col_mask |= 1u64 << Self::idx(row, column);
         }
         let num_p1_chips = self.count_player_chips(p1_board, col_mask) as i32;
         let num_p2_chips = self.count_player_chips(p2_board, col_mask) as i32;
-        let w = COL_WEIGHTS[column as usize];
+        let w = Self::col_weight(&params.col_weights, column);
         w * (num_p1_chips - num_p2_chips)
     }
 
@@ -250,9 +714,9 @@ impl BitboardState {
     /// - near-center columns (2,4) next
     /// - then (1,5)
     /// - then outer (0,6)
-    fn center_control_score(&self, p1_board: u64, p2_board: u64) -> i32 {
+    fn center_control_score(&self, p1_board: u64, p2_board: u64, params: &C4EvalParams) -> i32 {
         (0..COLS)
-            .map(|col| self.score_column(p1_board, p2_board, col))
+            .map(|col| self.score_column(p1_board, p2_board, col, params))
             .sum()
     }
 
@@ -281,28 +745,45 @@ impl BitboardState {
             10_000
         } else {
             // Otherwise prefer central columns
-            COL_WEIGHTS[col as usize]
+            Self::col_weight(&COL_WEIGHTS, col as usize)
         }
     }
 }
 
-impl GameState for BitboardState {
-    type Move = u8; // column index (0..=6)
+impl<const ROWS: usize, const COLS: usize> GameState for C4Bitboard<ROWS, COLS> {
+    type Move = u8; // column index (0..COLS)
 
     /// Return legal moves (any column that is not full).
     fn legal_moves(&self) -> Vec<Self::Move> {
-        (0..COLS)
-            .filter(|&c| self.heights[c as usize] < ROWS)
-            .collect()
+        (0..COLS as u8).filter(|&c| self.can_play(c)).collect()
+    }
+
+    fn for_each_legal_move(&self, mut f: impl FnMut(Self::Move)) {
+        for col in 0..COLS as u8 {
+            if self.can_play(col) {
+                f(col);
+            }
+        }
+    }
+
+    fn check_move(&self, mv: &Self::Move) -> Result<(), MoveError> {
+        if self.is_terminal() {
+            Err(MoveError::GameOver)
+        } else if *mv as usize >= COLS {
+            Err(MoveError::OutOfRange)
+        } else if !self.can_play(*mv) {
+            Err(MoveError::ColumnFull)
+        } else {
+            Ok(())
+        }
     }
 
     /// Applies a move by dropping a disc into the given column `mv`.
     ///
-    /// This delegates to BitboardState::apply_move(col), which performs:
+    /// This delegates to C4Bitboard::apply_column_move(col), which performs:
     /// - bit placement
     /// - mask update
     /// - player1 bitboard update (if needed)
-    /// - height increment
     /// - current player swap
     ///
     /// Does not mutate `self`; returns a fresh state.
@@ -349,11 +830,414 @@ impl GameState for BitboardState {
     fn move_ordering_key(&self, mv: &Self::Move) -> i32 {
         self.move_ordering_key_connect4(*mv)
     }
+
+    fn last_move(&self) -> Option<u8> {
+        self.last_move
+    }
+
+    fn ply(&self) -> Option<u32> {
+        Some(self.mask_bb.count_ones())
+    }
+
+    /// Measured on the standard board from the empty position, where
+    /// depth 12 takes about a second in a release build.
+    fn complexity_hint(&self) -> ComplexityHint {
+        ComplexityHint {
+            branching_factor: 3.2,
+            max_plies: Some((ROWS * COLS) as u32 - self.mask_bb.count_ones()),
+            recommended_depths: 1..=12,
+        }
+    }
+
+    fn recommended_depth(&self) -> u32 {
+        8
+    }
+}
+
+impl<const ROWS: usize, const COLS: usize> BoardGame for C4Bitboard<ROWS, COLS> {
+    fn rows(&self) -> usize {
+        ROWS
+    }
+
+    fn cols(&self) -> usize {
+        COLS
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        assert!(
+            row < ROWS && col < COLS,
+            "cell ({row}, {col}) is off the board"
+        );
+        // Bitboard rows count from the bottom.
+        let bit = 1u64 << Self::idx(ROWS - 1 - row, col);
+        if self.mask_bb & bit == 0 {
+            CellContent::Empty
+        } else if self.player_bb & bit != 0 {
+            CellContent::Piece(Player::Player1)
+        } else {
+            CellContent::Piece(Player::Player2)
+        }
+    }
+}
+
+/// Moves are column indices `0`..`6`, so `"3342"` is a game record (as
+/// for `from_move_sequence`).
+impl RegisteredGame for BitboardState {
+    const NAME: &'static str = "c4";
+    const DISPLAY_NAME: &'static str = "Connect Four";
+    const MOVE_SEPARATOR: &'static str = "";
+    const WIN_LENGTH: Option<usize> = Some(WIN_LENGTH);
+    const SELECT_BY: SelectBy = SelectBy::Column;
+
+    fn initial() -> Self {
+        Self::new()
+    }
+
+    fn format_move(mv: &u8) -> String {
+        mv.to_string()
+    }
+
+    fn parse_move(&self, text: &str) -> Result<u8, ParseError> {
+        self.parse_column(text)
+    }
+
+    fn threat_cells(&self) -> Vec<Threat> {
+        self.threat_list()
+    }
+}
+
+/// Moves are column indices `0`..`7`, as for `BitboardState`.
+impl RegisteredGame for BitboardState8x7 {
+    const NAME: &'static str = "c4-8x7";
+    const DISPLAY_NAME: &'static str = "Connect Four 8x7";
+    const MOVE_SEPARATOR: &'static str = "";
+    const WIN_LENGTH: Option<usize> = Some(WIN_LENGTH);
+    const SELECT_BY: SelectBy = SelectBy::Column;
+
+    fn initial() -> Self {
+        Self::new()
+    }
+
+    fn format_move(mv: &u8) -> String {
+        mv.to_string()
+    }
+
+    fn parse_move(&self, text: &str) -> Result<u8, ParseError> {
+        self.parse_column(text)
+    }
+
+    fn threat_cells(&self) -> Vec<Threat> {
+        self.threat_list()
+    }
+}
+
+impl<const ROWS: usize, const COLS: usize> C4Bitboard<ROWS, COLS> {
+    /// `threats` as `RegisteredGame::threat_cells`: Player1's, then
+    /// Player2's, each marked playable if it is in `playable_cells`.
+    fn threat_list(&self) -> Vec<Threat> {
+        let threats = self.threats();
+        let playable = self.playable_cells();
+        [Player::Player1, Player::Player2]
+            .into_iter()
+            .flat_map(|player| {
+                threats
+                    .of(player)
+                    .cells
+                    .iter()
+                    .map(move |&(row, col)| Threat {
+                        player,
+                        cell: (row, col),
+                        playable: playable & 1u64 << Self::idx(ROWS - 1 - row, col) != 0,
+                    })
+            })
+            .collect()
+    }
+
+    /// Parses a column index and checks that it can be played.
+    fn parse_column(&self, text: &str) -> Result<u8, ParseError> {
+        let text = text.trim();
+        let col = text
+            .parse()
+            .map_err(|_| ParseError::NotANumber(text.to_string()))?;
+        self.check_move(&col)?;
+        Ok(col)
+    }
+}
+
+impl<const ROWS: usize, const COLS: usize> SwapPlayers for C4Bitboard<ROWS, COLS> {
+    fn swap_players(&self) -> Self {
+        Self {
+            player_bb: self.p2_bb(),
+            mask_bb: self.mask_bb,
+            current_player: opposite_player(self.current_player),
+            last_move: self.last_move,
+        }
+    }
+}
+
+/// Gravity rules out turning the board, so only the mirror applies.
+impl<const ROWS: usize, const COLS: usize> Symmetric for C4Bitboard<ROWS, COLS> {
+    fn transforms() -> &'static [Transform] {
+        &[Transform::IDENTITY, Transform::MIRROR]
+    }
+
+    fn transformed(&self, t: Transform) -> Self {
+        if t.mirror {
+            self.mirrored()
+        } else {
+            self.clone()
+        }
+    }
+
+    fn transform_move(mv: &u8, t: Transform) -> u8 {
+        if t.mirror { COLS as u8 - 1 - mv } else { *mv }
+    }
+}
+
+/// Positions are numbered by the columns of `key`, read as the digits of a
+/// base `2^(ROWS + 1) - 1` number: a column holds one of that many stacks
+/// of discs.
+impl<const ROWS: usize, const COLS: usize> PositionIndex for C4Bitboard<ROWS, COLS> {
+    fn index_count() -> u64 {
+        Self::COLUMN_CODES.pow(COLS as u32)
+    }
+
+    fn rank(&self) -> u64 {
+        let key = self.key();
+        (0..COLS).rev().fold(0, |acc, col| {
+            acc * Self::COLUMN_CODES + (key >> Self::idx(0, col) & Self::COLUMN_CODES)
+        })
+    }
+
+    fn unrank(index: u64) -> Option<Self> {
+        if index >= Self::index_count() {
+            return None;
+        }
+        let mut state = Self::new();
+        let mut rest = index;
+        for col in 0..COLS {
+            // A column's code is its discs plus Player1's discs in it.
+            let code = rest % Self::COLUMN_CODES;
+            rest /= Self::COLUMN_CODES;
+            let discs = (1u64 << (code + 1).ilog2()) - 1;
+            state.mask_bb |= discs << Self::idx(0, col);
+            state.player_bb |= (code - discs) << Self::idx(0, col);
+        }
+        let player1 = state.player_bb.count_ones();
+        let player2 = state.p2_bb().count_ones();
+        state.current_player = match player1.checked_sub(player2) {
+            Some(0) => Player::Player1,
+            Some(1) => Player::Player2,
+            _ => return None,
+        };
+        Some(state)
+    }
+}
+
+impl<const ROWS: usize, const COLS: usize> PositionKey for C4Bitboard<ROWS, COLS> {
+    fn position_key(&self) -> u64 {
+        self.key()
+    }
+}
+
+/// Custom weights can be plugged into any evaluator-aware search or agent.
+impl<const ROWS: usize, const COLS: usize> Evaluator<C4Bitboard<ROWS, COLS>> for C4EvalParams {
+    fn evaluate(&self, state: &C4Bitboard<ROWS, COLS>) -> i32 {
+        state.evaluate_with(self)
+    }
+}
+
+/// A `C4Bitboard` that keeps its heuristic score up to date as moves are
+/// applied.
+///
+/// `heuristic_value` returns the cached score instead of rescanning every
+/// window, which makes leaf evaluation in depth-limited search nearly free
+/// at the cost of a small update in `apply_move`. Values always agree with
+/// `C4Bitboard::evaluate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IncrementalBitboard<const ROWS: usize, const COLS: usize> {
+    board: C4Bitboard<ROWS, COLS>,
+    /// `static_score` of `board` under the default weights.
+    score: i32,
+}
+
+impl<const ROWS: usize, const COLS: usize> Default for IncrementalBitboard<ROWS, COLS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const ROWS: usize, const COLS: usize> From<C4Bitboard<ROWS, COLS>>
+    for IncrementalBitboard<ROWS, COLS>
+{
+    /// Scores `board` once; later moves update the score incrementally.
+    fn from(board: C4Bitboard<ROWS, COLS>) -> Self {
+        let score = board.static_score(&C4EvalParams::default());
+        Self { board, score }
+    }
+}
+
+impl<const ROWS: usize, const COLS: usize> IncrementalBitboard<ROWS, COLS> {
+    /// Creates a new empty board.
+    pub fn new() -> Self {
+        Self {
+            board: C4Bitboard::new(),
+            score: 0,
+        }
+    }
+
+    /// The underlying bitboard.
+    pub fn board(&self) -> &C4Bitboard<ROWS, COLS> {
+        &self.board
+    }
+
+    /// Same as `heuristic_value`: the cached evaluation, or the scaled
+    /// terminal value if the game is over.
+    pub fn heuristic_value_cached(&self) -> i32 {
+        match self.board.terminal_value() {
+            Some(v) => v * 1000000,
+            None => self.score,
+        }
+    }
+}
+
+impl<const ROWS: usize, const COLS: usize> GameState for IncrementalBitboard<ROWS, COLS> {
+    type Move = u8;
+
+    fn legal_moves(&self) -> Vec<Self::Move> {
+        self.board.legal_moves()
+    }
+
+    fn for_each_legal_move(&self, f: impl FnMut(Self::Move)) {
+        self.board.for_each_legal_move(f);
+    }
+
+    fn check_move(&self, mv: &Self::Move) -> Result<(), MoveError> {
+        self.board.check_move(mv)
+    }
+
+    /// Applies the move and updates the cached score from the windows
+    /// touching the new disc.
+    fn apply_move(&self, mv: &Self::Move) -> Self {
+        let (board, delta) = self.board.apply_with_delta(*mv, &C4EvalParams::default());
+        Self {
+            board,
+            score: self.score + delta,
+        }
+    }
+
+    fn current_player(&self) -> Player {
+        self.board.current_player
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.board.is_terminal()
+    }
+
+    fn terminal_value(&self) -> Option<i32> {
+        self.board.terminal_value()
+    }
+
+    fn heuristic_value(&self) -> i32 {
+        self.heuristic_value_cached()
+    }
+
+    fn move_ordering_key(&self, mv: &Self::Move) -> i32 {
+        self.board.move_ordering_key_connect4(*mv)
+    }
+
+    fn last_move(&self) -> Option<u8> {
+        self.board.last_move()
+    }
+
+    fn ply(&self) -> Option<u32> {
+        self.board.ply()
+    }
+
+    fn complexity_hint(&self) -> ComplexityHint {
+        self.board.complexity_hint()
+    }
+
+    fn recommended_depth(&self) -> u32 {
+        self.board.recommended_depth()
+    }
+}
+
+impl<const ROWS: usize, const COLS: usize> BoardGame for IncrementalBitboard<ROWS, COLS> {
+    fn rows(&self) -> usize {
+        ROWS
+    }
+
+    fn cols(&self) -> usize {
+        COLS
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        self.board.cell(row, col)
+    }
+}
+
+impl<const ROWS: usize, const COLS: usize> PositionKey for IncrementalBitboard<ROWS, COLS> {
+    fn position_key(&self) -> u64 {
+        self.board.key()
+    }
+}
+
+/// Serialized form of `C4Bitboard`: the two bitboards and the side to move.
+/// Deserializing goes through this type so the bitboards can be validated.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BitboardRepr {
+    player_bb: u64,
+    mask_bb: u64,
+    current_player: Player,
+}
+
+#[cfg(feature = "serde")]
+impl<const ROWS: usize, const COLS: usize> From<C4Bitboard<ROWS, COLS>> for BitboardRepr {
+    fn from(state: C4Bitboard<ROWS, COLS>) -> Self {
+        Self {
+            player_bb: state.player_bb,
+            mask_bb: state.mask_bb,
+            current_player: state.current_player,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<const ROWS: usize, const COLS: usize> TryFrom<BitboardRepr> for C4Bitboard<ROWS, COLS> {
+    type Error = String;
+
+    /// Rejects discs outside the board, discs of Player1 not in `mask_bb`,
+    /// and columns with gaps below a disc.
+    fn try_from(repr: BitboardRepr) -> Result<Self, String> {
+        if repr.player_bb & !repr.mask_bb != 0 {
+            return Err("player_bb has discs outside mask_bb".to_string());
+        }
+        if repr.mask_bb & !Self::BOARD_MASK != 0 {
+            return Err("mask_bb has discs outside the board".to_string());
+        }
+        for col in 0..COLS as u8 {
+            let discs = repr.mask_bb & Self::column_mask(col);
+            let height = discs.count_ones();
+            if discs != ((1u64 << height) - 1) << Self::idx(0, col as usize) {
+                return Err(format!("column {col} has a gap below a disc"));
+            }
+        }
+        let mut state = Self::new();
+        state.player_bb = repr.player_bb;
+        state.mask_bb = repr.mask_bb;
+        state.current_player = repr.current_player;
+        Ok(state)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::render::describe::line_threats;
+    use crate::rng::{Rng, SplitMix64};
+    use alloc::vec;
 
     fn play_sequence(cols: &[u8]) -> BitboardState {
         let mut s = BitboardState::new();
@@ -376,6 +1260,92 @@ mod tests {
         assert_eq!(bit1, 1u64 << 1);
     }
 
+    #[test]
+    fn c4_heights_are_derived_from_mask() {
+        let s = play_sequence(&[3, 3, 3, 0, 6, 6, 6, 6, 6, 6]);
+        assert_eq!(s.heights(), [1, 0, 0, 3, 0, 0, 6]);
+        assert!(!s.can_play(6));
+        assert_eq!(s.next_bit(6), 0);
+        assert_eq!(s.legal_moves(), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn c4_threats_find_open_and_hidden_wins() {
+        let s = BitboardState::from_grid(
+            ".......\
+             .......\
+             .......\
+             .......\
+             O.O....\
+             XXX.O..",
+        )
+        .unwrap();
+        let threats = s.threats();
+        assert_eq!(threats.player1.cells, [(5, 3)]);
+        assert_eq!(
+            threats.player1.bits & s.playable_cells(),
+            threats.player1.bits
+        );
+        assert_eq!(threats.of(Player::Player2), &ThreatMap::default());
+
+        // X needs (3, 2) for the diagonal, two discs above the empty
+        // column 2.
+        let s = BitboardState::from_grid(
+            ".......\
+             .......\
+             ...X...\
+             ...O...\
+             .X.O...\
+             XO.XO..",
+        )
+        .unwrap();
+        let threats = s.threats();
+        assert!(threats.player1.cells.contains(&(3, 2)));
+        assert_eq!(threats.player1.bits & s.playable_cells(), 0);
+    }
+
+    #[test]
+    fn c4_threats_match_trying_every_empty_cell() {
+        let mut rng = SplitMix64::new(7);
+        for _ in 0..50 {
+            let mut s = BitboardState::new();
+            while !s.is_terminal() {
+                let threats = s.threats();
+                for (player, bb) in [(Player::Player1, s.player_bb), (Player::Player2, s.p2_bb())] {
+                    let expected = (0..6)
+                        .flat_map(|row| (0..7).map(move |col| (row, col)))
+                        .filter(|&(row, col)| {
+                            let bit = 1u64 << BitboardState::idx(5 - row, col);
+                            s.mask_bb & bit == 0 && s.check_win(bb | bit)
+                        })
+                        .collect::<Vec<_>>();
+                    assert_eq!(threats.of(player).cells, expected, "{}", s.to_grid());
+                }
+                assert_eq!(
+                    s.threat_cells(),
+                    line_threats(&s, WIN_LENGTH, SelectBy::Column)
+                );
+                let moves = s.legal_moves();
+                s = s.apply_move(rng.choose(&moves).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn c4_keys_are_unique_and_mirroring_reverses_columns() {
+        let a = BitboardState::from_move_sequence("0011").unwrap();
+        let b = BitboardState::from_move_sequence("0110").unwrap();
+        assert_ne!(a.key(), b.key());
+        assert_ne!(BitboardState::new().key(), a.key());
+
+        let s = BitboardState::from_move_sequence("01234").unwrap();
+        assert_eq!(
+            s.mirrored(),
+            BitboardState::from_move_sequence("65432").unwrap()
+        );
+        assert_eq!(s.mirrored().mirrored(), s);
+    }
+
     #[test]
     fn c4_horizontal_win_detection_for_player1() {
         // P1: 0,1,2,3 (bottom row)
@@ -423,8 +1393,8 @@ mod tests {
     #[test]
     fn c4_is_full_when_all_columns_full() {
         let mut s = BitboardState::new();
-        for col in 0..COLS {
-            for _ in 0..ROWS {
+        for col in 0..7 {
+            for _ in 0..6 {
                 s = s.apply_column_move(col);
             }
         }
@@ -452,9 +1422,247 @@ mod tests {
         assert!(value > 0);
     }
 
+    #[test]
+    fn c4_from_move_sequence_matches_manual_play() {
+        let s = BitboardState::from_move_sequence("0616263").unwrap();
+        assert_eq!(s, play_sequence(&[0, 6, 1, 6, 2, 6, 3]));
+        assert_eq!(s.terminal_value(), Some(1));
+    }
+
+    #[test]
+    fn c4_from_move_sequence_rejects_bad_input() {
+        assert!(BitboardState::from_move_sequence("7").is_err());
+        assert!(BitboardState::from_move_sequence("3a").is_err());
+        assert_eq!(
+            BitboardState::from_move_sequence("3é"),
+            Err(ParseError::InvalidChar { ch: 'é', index: 1 })
+        );
+        assert_eq!(
+            BitboardState::from_move_sequence("0000000"),
+            Err(ParseError::IllegalMoveAt {
+                move_number: 7,
+                error: MoveError::ColumnFull
+            })
+        );
+        assert!(BitboardState::from_move_sequence("06162635").is_err()); // after the win
+    }
+
+    #[test]
+    fn c4_tracks_the_last_move_without_affecting_equality() {
+        let s = BitboardState::from_move_sequence("3342").unwrap();
+        assert_eq!(s.last_move(), Some(2));
+        assert_eq!(s.mirrored().last_move(), Some(4));
+        assert_eq!(BitboardState::new().last_move(), None);
+        let grid = BitboardState::from_grid(&s.to_grid()).unwrap();
+        assert_eq!(grid.last_move(), None);
+        assert_eq!(grid, s);
+        let incremental = IncrementalBitboard::from(s.clone()).apply_move(&6);
+        assert_eq!(incremental.last_move(), Some(6));
+    }
+
+    #[test]
+    fn c4_from_pons_sequence_counts_columns_from_one() {
+        assert_eq!(
+            BitboardState::from_pons_sequence("4453"),
+            BitboardState::from_move_sequence("3342")
+        );
+        assert_eq!(
+            BitboardState::from_pons_sequence("40"),
+            Err(ParseError::InvalidChar { ch: '0', index: 1 })
+        );
+        assert_eq!(
+            BitboardState::from_pons_sequence("18"),
+            Err(ParseError::InvalidChar { ch: '8', index: 1 })
+        );
+        assert_eq!(
+            BitboardState::from_pons_sequence("1111111"),
+            Err(ParseError::IllegalMoveAt {
+                move_number: 7,
+                error: MoveError::ColumnFull
+            })
+        );
+    }
+
+    #[test]
+    fn c4_grids_round_trip_and_infer_the_side_to_move() {
+        let s = BitboardState::from_move_sequence("33425").unwrap();
+        let grid = s.to_grid();
+        assert_eq!(
+            grid,
+            ".......\n.......\n.......\n.......\n...O...\n..OXXX.\n"
+        );
+        assert_eq!(BitboardState::from_grid(&grid), Ok(s.clone()));
+        let one_line = grid.replace('\n', "").replace('X', "1").replace('O', "2");
+        assert_eq!(BitboardState::from_grid(&one_line), Ok(s.clone()));
+        assert_eq!(
+            BitboardState::from_grid(&grid.replace('.', "0")),
+            Ok(s.clone())
+        );
+        assert_eq!(s.current_player(), Player::Player2);
+        let empty = BitboardState::from_grid(&".".repeat(42)).unwrap();
+        assert_eq!(empty, BitboardState::new());
+
+        let won = BitboardState::from_move_sequence("0616263").unwrap();
+        assert_eq!(BitboardState::from_grid(&won.to_grid()), Ok(won));
+    }
+
+    #[test]
+    fn c4_from_grid_rejects_unreachable_positions() {
+        assert_eq!(
+            BitboardState::from_grid("X"),
+            Err(ParseError::WrongLength {
+                expected: 42,
+                found: 1
+            })
+        );
+        let grid =
+            |bottom: &str, above: &str| alloc::format!("{}{above:.<7}{bottom}", ".".repeat(28));
+        assert_eq!(
+            BitboardState::from_grid(&grid("X?.....", "")),
+            Err(ParseError::InvalidChar { ch: '?', index: 36 })
+        );
+        assert_eq!(
+            BitboardState::from_grid(&grid("XO.....", "..X")),
+            Err(ParseError::FloatingDisc { row: 5, col: 2 })
+        );
+        assert_eq!(
+            BitboardState::from_grid(&grid("XX.....", "")),
+            Err(ParseError::ImpossibleMarkCounts {
+                player1: 2,
+                player2: 0
+            })
+        );
+        // Both players have four in a row.
+        assert_eq!(
+            BitboardState::from_grid(&grid("XXXX...", "OOOO")),
+            Err(ParseError::ImpossibleWin)
+        );
+        // Player1 won, but Player2 moved last.
+        assert_eq!(
+            BitboardState::from_grid(&grid("XXXXOOO", "O")),
+            Err(ParseError::ImpossibleWin)
+        );
+        // Two lines that no single disc of Player1's completes.
+        let two_lines = "\
+            .......\
+            .......\
+            X......\
+            XO.....\
+            XO..O..\
+            XXXXOOO";
+        assert_eq!(
+            BitboardState::from_grid(two_lines),
+            Err(ParseError::ImpossibleWin)
+        );
+    }
+
+    #[test]
+    fn c4_try_apply_move_reports_why_a_move_is_illegal() {
+        let s = BitboardState::from_move_sequence("000000").unwrap();
+        assert_eq!(s.try_apply_move(&0), Err(MoveError::ColumnFull));
+        assert_eq!(s.try_apply_move(&7), Err(MoveError::OutOfRange));
+        assert_eq!(s.try_apply_move(&1), Ok(s.apply_column_move(1)));
+        let won = BitboardState::from_move_sequence("0616263").unwrap();
+        assert_eq!(won.check_move(&4), Err(MoveError::GameOver));
+        let incremental = IncrementalBitboard::from(s);
+        assert_eq!(incremental.check_move(&0), Err(MoveError::ColumnFull));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn c4_incremental_eval_matches_full_eval(choices in proptest::collection::vec(0u32..1000, 0..42)) {
+            let mut inc = IncrementalBitboard::<6, 7>::new();
+            for choice in choices {
+                let moves = inc.legal_moves();
+                if inc.is_terminal() || moves.is_empty() {
+                    break;
+                }
+                inc = inc.apply_move(&moves[choice as usize % moves.len()]);
+                proptest::prop_assert_eq!(inc.heuristic_value(), inc.board().evaluate());
+            }
+        }
+
+        #[test]
+        fn c4_incremental_eval_matches_on_small_boards(choices in proptest::collection::vec(0u32..1000, 0..20)) {
+            let mut inc = IncrementalBitboard::<4, 5>::new();
+            for choice in choices {
+                let moves = inc.legal_moves();
+                if inc.is_terminal() || moves.is_empty() {
+                    break;
+                }
+                inc = inc.apply_move(&moves[choice as usize % moves.len()]);
+                proptest::prop_assert_eq!(inc.heuristic_value(), inc.board().evaluate());
+            }
+        }
+    }
+
+    #[test]
+    fn c4_incremental_from_existing_position() {
+        let board = BitboardState::from_move_sequence("33424").unwrap();
+        let inc = IncrementalBitboard::from(board.clone()).apply_move(&1);
+        assert_eq!(inc.heuristic_value(), board.apply_column_move(1).evaluate());
+    }
+
     #[test]
     fn c4_heuristic_is_symmetric_for_empty_board() {
         let s = BitboardState::new();
         assert_eq!(s.heuristic_value(), 0);
     }
+
+    #[test]
+    fn c4_small_board_detects_wins_and_draws() {
+        type Small = C4Bitboard<4, 5>;
+        let s = Small::from_move_sequence("0101010").unwrap();
+        assert_eq!(s.terminal_value(), Some(1));
+        assert!(Small::from_move_sequence("5").is_err());
+
+        // Horizontal win across the bottom row.
+        let s = Small::from_move_sequence("0010203").unwrap();
+        assert_eq!(s.terminal_value(), Some(1));
+
+        let mut s = Small::new();
+        assert_eq!(s.legal_moves(), vec![0, 1, 2, 3, 4]);
+        for _ in 0..4 {
+            s = s.apply_column_move(2);
+        }
+        assert_eq!(s.legal_moves(), vec![0, 1, 3, 4]);
+    }
+
+    #[test]
+    fn c4_board_too_short_for_vertical_wins() {
+        type Wide = C4Bitboard<3, 8>;
+        let s = Wide::from_move_sequence("010101").unwrap();
+        assert_eq!(s.legal_moves().len(), 6);
+        assert_eq!(s.terminal_value(), None);
+        assert_eq!(Wide::new().heuristic_value(), 0);
+
+        // Filling the board column by column never lines up four.
+        let mut s = Wide::new();
+        for col in 0..8 {
+            for _ in 0..3 {
+                s = s.apply_column_move(col);
+            }
+        }
+        assert!(s.is_full());
+    }
+
+    #[test]
+    fn c4_complexity_hint_shrinks_as_the_board_fills() {
+        let s = BitboardState::new();
+        let hint = s.complexity_hint();
+        assert_eq!(hint.max_plies, Some(42));
+        assert!(hint.recommended_depths.contains(&s.recommended_depth()));
+        assert!(!hint.is_impractical(12));
+        assert!(hint.is_impractical(20));
+
+        // Seven of eight columns filled on a board without vertical wins.
+        let mut late = C4Bitboard::<3, 8>::new();
+        for col in 0..7 {
+            for _ in 0..3 {
+                late = late.apply_column_move(col);
+            }
+        }
+        assert_eq!(late.complexity_hint().max_plies, Some(3));
+        assert!(!late.complexity_hint().is_impractical(20));
+    }
 }
@@ -0,0 +1,589 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::error::{MoveError, ParseError};
+use crate::game::{BoardGame, CellContent, ComplexityHint, GameState, Player, SwapPlayers};
+use crate::games::c4::C4Cell;
+use crate::games::c4_bitboard::C4EvalParams;
+use crate::heuristics::{count_pieces, line_score, windows};
+use crate::utils::opposite_player;
+
+const ROWS: usize = 6;
+const COLS: usize = 7;
+
+/// Which Connect Four rules a `VariantState` is played by.
+///
+/// Variants combine freely, e.g. Pop Out with five in a row:
+/// `C4Rules { pop_out: true, win_length: 5, ..C4Rules::STANDARD }`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct C4Rules {
+    /// Pop Out: instead of dropping a disc, a player may pop one of their
+    /// own discs out of the bottom row, and the discs above it fall down.
+    /// If that completes lines for both players, the one who popped wins.
+    /// A full board is not a draw as long as the player to move can pop.
+    pub pop_out: bool,
+    /// How many discs in a row win, from 1 to 7.
+    pub win_length: u8,
+    /// Horizontal lines may wrap around from the rightmost column to the
+    /// leftmost, as if the board were a cylinder.
+    pub wrap_around: bool,
+}
+
+impl C4Rules {
+    /// The standard rules: four in a row, no popping, no wrapping.
+    pub const STANDARD: Self = Self {
+        pop_out: false,
+        win_length: 4,
+        wrap_around: false,
+    };
+
+    /// Whether a line of `win_length` fits on the board.
+    pub fn fit_the_board(&self) -> bool {
+        (1..=COLS as u8).contains(&self.win_length)
+    }
+}
+
+impl Default for C4Rules {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// A move in a Connect Four variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VariantMove {
+    /// Drop a disc into a column, as in standard Connect Four.
+    Drop(u8),
+    /// Pop the mover's own disc out of the bottom of a column (Pop Out).
+    Pop(u8),
+}
+
+impl VariantMove {
+    /// The column the move is played in.
+    pub fn column(self) -> u8 {
+        match self {
+            VariantMove::Drop(col) | VariantMove::Pop(col) => col,
+        }
+    }
+
+    /// Parses a move as written by `Display`: a column digit for a drop,
+    /// or `p` and a column digit for a pop, e.g. `3` or `p3`.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let text = text.trim();
+        let (pop, digits) = match text.strip_prefix(['p', 'P']) {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        let column = digits
+            .parse()
+            .map_err(|_| ParseError::NotANumber(String::from(text)))?;
+        Ok(if pop {
+            VariantMove::Pop(column)
+        } else {
+            VariantMove::Drop(column)
+        })
+    }
+}
+
+impl fmt::Display for VariantMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariantMove::Drop(col) => write!(f, "{col}"),
+            VariantMove::Pop(col) => write!(f, "p{col}"),
+        }
+    }
+}
+
+/// A Connect Four position under a choice of `C4Rules`, on the standard
+/// 7-column, 6-row board.
+///
+/// Cells are stored like `ConnectFourState`'s: row-major, row 0 at the
+/// top. Under the standard rules the two play the same game; the
+/// variants' moves and wins need this more general (and slower) state.
+///
+/// With the `serde` feature, the board is serialized as a
+/// `ConnectFourState::to_repr` string and checked for gravity when
+/// deserialized.
+///
+/// Two states are equal when their rules and positions are, whatever
+/// moves led to them.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "VariantRepr", try_from = "VariantRepr")
+)]
+pub struct VariantState {
+    rules: C4Rules,
+    board: [C4Cell; ROWS * COLS],
+    heights: [u8; COLS],
+    current_player: Player,
+    /// Moves played so far; with pops this is not the number of discs.
+    ply: u32,
+    last_move: Option<VariantMove>,
+}
+
+impl PartialEq for VariantState {
+    fn eq(&self, other: &Self) -> bool {
+        self.rules == other.rules
+            && self.board == other.board
+            && self.current_player == other.current_player
+    }
+}
+
+impl Eq for VariantState {}
+
+/// Serialized form of `VariantState` (see the `serde` feature).
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct VariantRepr {
+    rules: C4Rules,
+    board: String,
+    current_player: Player,
+    ply: u32,
+}
+
+#[cfg(feature = "serde")]
+impl From<VariantState> for VariantRepr {
+    fn from(state: VariantState) -> Self {
+        let mut board = crate::games::c4::ConnectFourState::new();
+        board.board = state.board;
+        Self {
+            rules: state.rules,
+            board: board.to_repr(),
+            current_player: state.current_player,
+            ply: state.ply,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<VariantRepr> for VariantState {
+    type Error = ParseError;
+
+    fn try_from(repr: VariantRepr) -> Result<Self, Self::Error> {
+        if !repr.rules.fit_the_board() {
+            return Err(ParseError::UnsupportedRules);
+        }
+        let board = crate::games::c4::ConnectFourState::from_str(&repr.board, repr.current_player)?;
+        Ok(Self {
+            board: board.board,
+            heights: board.heights,
+            current_player: repr.current_player,
+            ply: repr.ply,
+            last_move: None,
+            ..Self::new(repr.rules)
+        })
+    }
+}
+
+impl Default for VariantState {
+    fn default() -> Self {
+        Self::new(C4Rules::STANDARD)
+    }
+}
+
+/// The cell of `player`'s discs.
+fn disc(player: Player) -> C4Cell {
+    match player {
+        Player::Player1 => C4Cell::P1,
+        Player::Player2 => C4Cell::P2,
+    }
+}
+
+impl VariantState {
+    /// The empty board under `rules`, with Player1 to move.
+    ///
+    /// Panics if `rules.win_length` is not between 1 and 7.
+    pub fn new(rules: C4Rules) -> Self {
+        assert!(
+            rules.fit_the_board(),
+            "win length {} does not fit the board",
+            rules.win_length
+        );
+        Self {
+            rules,
+            board: [C4Cell::Empty; ROWS * COLS],
+            heights: [0; COLS],
+            current_player: Player::Player1,
+            ply: 0,
+            last_move: None,
+        }
+    }
+
+    /// Plays `moves` (separated by spaces, see `VariantMove::parse`) from
+    /// the empty board under `rules`.
+    pub fn from_moves(rules: C4Rules, moves: &str) -> Result<Self, ParseError> {
+        let mut state = Self::new(rules);
+        for (i, text) in moves.split_whitespace().enumerate() {
+            let mv = VariantMove::parse(text)?;
+            state = state
+                .try_apply_move(&mv)
+                .map_err(|error| ParseError::IllegalMoveAt {
+                    move_number: i + 1,
+                    error,
+                })?;
+        }
+        Ok(state)
+    }
+
+    /// The rules of the game.
+    pub fn rules(&self) -> C4Rules {
+        self.rules
+    }
+
+    #[inline]
+    fn idx(row: usize, col: usize) -> usize {
+        row * COLS + col
+    }
+
+    /// Whether the player to move may pop the bottom disc of `col`.
+    fn can_pop(&self, col: usize) -> bool {
+        self.rules.pop_out && self.board[Self::idx(ROWS - 1, col)] == disc(self.current_player)
+    }
+
+    /// Which players have a line of `win_length` discs: (Player1, Player2).
+    fn lines(&self) -> (bool, bool) {
+        let k = self.rules.win_length as usize;
+        let mut found = (false, false);
+        let mut check = |(p1, p2): (usize, usize)| {
+            found.0 |= p1 == k;
+            found.1 |= p2 == k;
+        };
+        for window in windows(ROWS, COLS, k) {
+            check(count_pieces(self, window));
+        }
+        if self.rules.wrap_around {
+            // The horizontal lines `windows` leaves out: those that run off
+            // the right edge and carry on from the left.
+            for row in 0..ROWS {
+                for start in (COLS + 1 - k)..COLS {
+                    let mut counts = (0, 0);
+                    for i in 0..k {
+                        match self.board[Self::idx(row, (start + i) % COLS)] {
+                            C4Cell::P1 => counts.0 += 1,
+                            C4Cell::P2 => counts.1 += 1,
+                            C4Cell::Empty => {}
+                        }
+                    }
+                    check(counts);
+                }
+            }
+        }
+        found
+    }
+
+    /// The winner, if any. A pop can complete lines for both players at
+    /// once; then the player who popped, the one who just moved, wins.
+    fn winner(&self) -> Option<Player> {
+        match self.lines() {
+            (true, true) => Some(opposite_player(self.current_player)),
+            (true, false) => Some(Player::Player1),
+            (false, true) => Some(Player::Player2),
+            (false, false) => None,
+        }
+    }
+
+    /// Line scores as in `ConnectFourState`, for lines of `win_length`.
+    fn line_weights(&self) -> Vec<i32> {
+        let params = C4EvalParams::default();
+        let k = self.rules.win_length as usize;
+        let mut weights = alloc::vec![0; k + 1];
+        for (missing, weight) in [
+            (0, params.window_four),
+            (1, params.window_three),
+            (2, params.window_two),
+        ] {
+            if missing < k {
+                weights[k - missing] = weight;
+            }
+        }
+        weights
+    }
+}
+
+impl SwapPlayers for VariantState {
+    fn swap_players(&self) -> Self {
+        Self {
+            board: self.board.map(|cell| match cell {
+                C4Cell::P1 => C4Cell::P2,
+                C4Cell::P2 => C4Cell::P1,
+                C4Cell::Empty => C4Cell::Empty,
+            }),
+            current_player: opposite_player(self.current_player),
+            ..self.clone()
+        }
+    }
+}
+
+impl GameState for VariantState {
+    type Move = VariantMove;
+
+    fn current_player(&self) -> Player {
+        self.current_player
+    }
+
+    /// Drops into every column with room, then (in Pop Out) pops from every
+    /// column with the mover's disc at the bottom.
+    fn legal_moves(&self) -> Vec<Self::Move> {
+        let mut moves = Vec::new();
+        self.for_each_legal_move(|mv| moves.push(mv));
+        moves
+    }
+
+    fn for_each_legal_move(&self, mut f: impl FnMut(Self::Move)) {
+        for col in 0..COLS {
+            if (self.heights[col] as usize) < ROWS {
+                f(VariantMove::Drop(col as u8));
+            }
+        }
+        for col in 0..COLS {
+            if self.can_pop(col) {
+                f(VariantMove::Pop(col as u8));
+            }
+        }
+    }
+
+    fn check_move(&self, mv: &Self::Move) -> Result<(), MoveError> {
+        if self.is_terminal() {
+            return Err(MoveError::GameOver);
+        }
+        let col = mv.column() as usize;
+        if col >= COLS {
+            return Err(MoveError::OutOfRange);
+        }
+        match mv {
+            VariantMove::Drop(_) if self.heights[col] as usize >= ROWS => {
+                Err(MoveError::ColumnFull)
+            }
+            VariantMove::Pop(_) if !self.can_pop(col) => Err(MoveError::PopUnavailable),
+            _ => Ok(()),
+        }
+    }
+
+    fn apply_move(&self, mv: &Self::Move) -> Self {
+        let mut next = self.clone();
+        match *mv {
+            VariantMove::Drop(col) => {
+                let col = col as usize;
+                let row = ROWS - 1 - self.heights[col] as usize;
+                next.board[Self::idx(row, col)] = disc(self.current_player);
+                next.heights[col] += 1;
+            }
+            VariantMove::Pop(col) => {
+                let col = col as usize;
+                for row in (1..ROWS).rev() {
+                    next.board[Self::idx(row, col)] = next.board[Self::idx(row - 1, col)];
+                }
+                next.board[Self::idx(0, col)] = C4Cell::Empty;
+                next.heights[col] -= 1;
+            }
+        }
+        next.current_player = opposite_player(self.current_player);
+        next.ply += 1;
+        next.last_move = Some(*mv);
+        next
+    }
+
+    /// Over once someone has a line, or when the player to move can
+    /// neither drop nor pop.
+    fn is_terminal(&self) -> bool {
+        self.terminal_value().is_some()
+    }
+
+    fn terminal_value(&self) -> Option<i32> {
+        if let Some(p) = self.winner() {
+            return Some(match p {
+                Player::Player1 => 1,
+                Player::Player2 => -1,
+            });
+        }
+        let full = self.heights.iter().all(|&h| h as usize == ROWS);
+        (full && !(0..COLS).any(|col| self.can_pop(col))).then_some(0)
+    }
+
+    /// Scores every window of `win_length` cells like `ConnectFourState`;
+    /// lines that wrap around are not scored.
+    fn heuristic_value(&self) -> i32 {
+        line_score(self, self.rules.win_length as usize, &self.line_weights())
+    }
+
+    /// Central drops first, pops last.
+    fn move_ordering_key(&self, mv: &Self::Move) -> i32 {
+        match mv {
+            VariantMove::Drop(col) => -(*col as i32 - COLS as i32 / 2).abs(),
+            VariantMove::Pop(_) => -(COLS as i32),
+        }
+    }
+
+    fn last_move(&self) -> Option<VariantMove> {
+        self.last_move
+    }
+
+    fn ply(&self) -> Option<u32> {
+        Some(self.ply)
+    }
+
+    /// Standard Connect Four's estimates; pops add branches and can go on
+    /// forever, so Pop Out games have no bound on their length.
+    fn complexity_hint(&self) -> ComplexityHint {
+        let empty = self.board.iter().filter(|c| **c == C4Cell::Empty).count() as u32;
+        if self.rules.pop_out {
+            ComplexityHint {
+                branching_factor: 4.5,
+                max_plies: None,
+                recommended_depths: 1..=8,
+            }
+        } else {
+            ComplexityHint {
+                branching_factor: 3.2,
+                max_plies: Some(empty),
+                recommended_depths: 1..=10,
+            }
+        }
+    }
+
+    fn recommended_depth(&self) -> u32 {
+        if self.rules.pop_out { 6 } else { 8 }
+    }
+}
+
+impl BoardGame for VariantState {
+    fn rows(&self) -> usize {
+        ROWS
+    }
+
+    fn cols(&self) -> usize {
+        COLS
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        assert!(
+            row < ROWS && col < COLS,
+            "cell ({row}, {col}) is off the board"
+        );
+        match self.board[Self::idx(row, col)] {
+            C4Cell::Empty => CellContent::Empty,
+            C4Cell::P1 => CellContent::Piece(Player::Player1),
+            C4Cell::P2 => CellContent::Piece(Player::Player2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Outcome;
+    use crate::games::c4::{ConnectFourMove, ConnectFourState};
+    use crate::solvers::minimax::minimax_best_move_ab_depth;
+    use alloc::string::ToString;
+
+    fn rules(pop_out: bool, win_length: u8, wrap_around: bool) -> C4Rules {
+        C4Rules {
+            pop_out,
+            win_length,
+            wrap_around,
+        }
+    }
+
+    #[test]
+    fn standard_rules_play_standard_connect_four() {
+        let columns = [3, 3, 2, 4, 1, 5, 0];
+        let mut classic = ConnectFourState::new();
+        let mut variant = VariantState::default();
+        for col in columns {
+            assert_eq!(variant.is_terminal(), classic.is_terminal());
+            assert_eq!(variant.legal_moves().len(), classic.legal_moves().len());
+            assert_eq!(variant.heuristic_value(), classic.heuristic_value());
+            classic = classic.apply_move(&ConnectFourMove { column: col });
+            variant = variant.apply_move(&VariantMove::Drop(col));
+        }
+        assert_eq!(variant.outcome(), Some(Outcome::Win(Player::Player1)));
+        assert_eq!(variant.outcome(), classic.outcome());
+        assert!(variant.check_move(&VariantMove::Drop(6)).is_err());
+    }
+
+    #[test]
+    fn pop_out_pops_your_own_bottom_disc() {
+        let pop_out = rules(true, 4, false);
+        let state = VariantState::from_moves(pop_out, "3 3 4").unwrap();
+        // Player2 owns no bottom disc yet.
+        assert!(!state.legal_moves().contains(&VariantMove::Pop(3)));
+        assert_eq!(
+            state.check_move(&VariantMove::Pop(3)),
+            Err(MoveError::PopUnavailable)
+        );
+        let state = state.apply_move(&VariantMove::Drop(0));
+        assert!(state.legal_moves().contains(&VariantMove::Pop(3)));
+        let popped = state.apply_move(&VariantMove::Pop(3));
+        // Player2's disc fell to the bottom of column 3.
+        assert_eq!(popped.cell(5, 3), CellContent::Piece(Player::Player2));
+        assert_eq!(popped.cell(4, 3), CellContent::Empty);
+        assert_eq!(
+            (popped.ply(), popped.last_move()),
+            (Some(5), Some(VariantMove::Pop(3)))
+        );
+        assert_eq!(VariantMove::Pop(3).to_string(), "p3");
+
+        let standard = VariantState::from_moves(C4Rules::STANDARD, "3 3 4 0").unwrap();
+        assert_eq!(
+            standard.check_move(&VariantMove::Pop(3)),
+            Err(MoveError::PopUnavailable)
+        );
+    }
+
+    #[test]
+    fn a_pop_completing_both_lines_wins_for_the_popper() {
+        // Column 0 holds X, O, X from the bottom, next to O O O on the
+        // bottom row and X X X above. Popping X's bottom disc lets O and X
+        // fall into line with both rows.
+        let pop_out = rules(true, 4, false);
+        let state = VariantState::from_moves(pop_out, "0 1 1 0 0 2 2 3 3 6").unwrap();
+        assert!(!state.is_terminal());
+        let end = state.apply_move(&VariantMove::Pop(0));
+        assert_eq!(end.lines(), (true, true));
+        assert_eq!(end.outcome(), Some(Outcome::Win(Player::Player1)));
+    }
+
+    #[test]
+    fn longer_lines_and_wrapping() {
+        let five = rules(false, 5, false);
+        let four = VariantState::from_moves(five, "0 0 1 1 2 2 3 3").unwrap();
+        assert!(!four.is_terminal());
+        assert_eq!(
+            four.apply_move(&VariantMove::Drop(4)).outcome(),
+            Some(Outcome::Win(Player::Player1))
+        );
+
+        // X on 5, 6, 0 and 1 of the bottom row: four in a row around the edge.
+        let wrap = rules(false, 4, true);
+        let moves = "5 5 6 6 0 0 1";
+        let state = VariantState::from_moves(wrap, moves).unwrap();
+        assert_eq!(state.outcome(), Some(Outcome::Win(Player::Player1)));
+        let flat = VariantState::from_moves(C4Rules::STANDARD, moves).unwrap();
+        assert!(!flat.is_terminal());
+
+        // The search finds the wrapping win.
+        let state = VariantState::from_moves(wrap, "5 5 6 6 0 0").unwrap();
+        let (mv, value) = minimax_best_move_ab_depth(&state, 1).unwrap();
+        assert!(value > 0);
+        assert!(matches!(mv, VariantMove::Drop(1 | 4)), "{mv:?}");
+    }
+
+    #[test]
+    fn parses_moves() {
+        assert_eq!(VariantMove::parse("3"), Ok(VariantMove::Drop(3)));
+        assert_eq!(VariantMove::parse("P2"), Ok(VariantMove::Pop(2)));
+        assert!(VariantMove::parse("x").is_err());
+        assert_eq!(
+            VariantState::from_moves(C4Rules::STANDARD, "0 0 0 0 0 0 0"),
+            Err(ParseError::IllegalMoveAt {
+                move_number: 7,
+                error: MoveError::ColumnFull
+            })
+        );
+    }
+}
@@ -1,3 +1,9 @@
+pub mod breakthrough;
 pub mod c4;
+pub mod c4_3d;
 pub mod c4_bitboard;
+pub mod c4_variants;
+pub mod pentago;
+pub mod swap;
 pub mod ttt;
+pub mod ttt_bitboard;
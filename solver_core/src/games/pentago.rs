@@ -0,0 +1,661 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::error::{MoveError, ParseError};
+use crate::game::{BoardGame, CellContent, ComplexityHint, GameState, Player, PositionKey};
+use crate::registry::RegisteredGame;
+use crate::transforms::{Symmetric, Transform, canonical};
+
+/// Rows and columns of the board.
+const SIZE: usize = 6;
+/// Number of cells.
+const CELLS: usize = SIZE * SIZE;
+/// Every cell of the board.
+const FULL: u64 = (1 << CELLS) - 1;
+/// Marbles in a row needed to win.
+const WIN_LENGTH: usize = 5;
+/// Number of rows of five on the board: 12 across, 12 down and 8
+/// diagonal.
+const LINE_COUNT: usize = 32;
+
+/// Every row of five, as a mask of cells (bit `row * 6 + col`).
+const LINES: [u64; LINE_COUNT] = {
+    let mut lines = [0; LINE_COUNT];
+    let mut n = 0;
+    // Steps right, down, down-right and down-left.
+    let steps: [(i32, i32); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+    let mut d = 0;
+    while d < 4 {
+        let (dr, dc) = steps[d];
+        let mut start = 0;
+        while start < CELLS {
+            let (row, col) = ((start / SIZE) as i32, (start % SIZE) as i32);
+            let (end_row, end_col) = (row + 4 * dr, col + 4 * dc);
+            if end_row < SIZE as i32 && end_col >= 0 && end_col < SIZE as i32 {
+                let mut line = 0;
+                let mut i = 0;
+                while i < WIN_LENGTH as i32 {
+                    line |= 1 << ((row + i * dr) * SIZE as i32 + col + i * dc);
+                    i += 1;
+                }
+                lines[n] = line;
+                n += 1;
+            }
+            start += 1;
+        }
+        d += 1;
+    }
+    assert!(n == LINE_COUNT);
+    lines
+};
+
+/// How many rows of five pass through each cell.
+const CELL_LINES: [u8; CELLS] = {
+    let mut counts = [0; CELLS];
+    let mut i = 0;
+    while i < LINE_COUNT {
+        let mut cell = 0;
+        while cell < CELLS {
+            if LINES[i] & (1 << cell) != 0 {
+                counts[cell] += 1;
+            }
+            cell += 1;
+        }
+        i += 1;
+    }
+    counts
+};
+
+/// Heuristic score of a row of five holding only one player's marbles,
+/// by their number.
+const LINE_WEIGHTS: [i32; WIN_LENGTH + 1] = [0, 1, 4, 16, 64, 100_000];
+
+/// `TERNARY[marbles]`: the sum of `3^i` over the cells `i` in a 9-cell
+/// chunk of the board, for base-3 position keys.
+const TERNARY: [u64; 512] = {
+    let mut ternary = [0; 512];
+    let mut marbles = 0;
+    while marbles < 512 {
+        let (mut cell, mut power, mut sum) = (0, 1, 0);
+        while cell < 9 {
+            if marbles & (1 << cell) != 0 {
+                sum += power;
+            }
+            power *= 3;
+            cell += 1;
+        }
+        ternary[marbles] = sum;
+        marbles += 1;
+    }
+    ternary
+};
+
+/// The cell in row `row` and column `col` of `quadrant`.
+const fn quadrant_cell(quadrant: u8, row: usize, col: usize) -> usize {
+    (quadrant as usize / 2 * 3 + row) * SIZE + quadrant as usize % 2 * 3 + col
+}
+
+/// The quadrant holding `cell`.
+const fn quadrant_of(cell: usize) -> u8 {
+    ((cell / SIZE / 3) * 2 + cell % SIZE / 3) as u8
+}
+
+/// A Pentago move: place a marble on an empty cell, then turn one of the
+/// four 3x3 quadrants a quarter turn.
+///
+/// Written `CELL/QD`, e.g. `14/2R`: the cell index (0 to 35, row by row
+/// from the top left), the quadrant (0 top left, 1 top right, 2 bottom
+/// left, 3 bottom right) and `R` to turn it clockwise or `L`
+/// anticlockwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PentagoMove {
+    /// Where the marble is placed.
+    pub cell: u8,
+    /// The quadrant turned afterwards.
+    pub quadrant: u8,
+    pub clockwise: bool,
+}
+
+impl PentagoMove {
+    /// Parses a move as written by `Display`, e.g. `14/2R`; the direction
+    /// may be lower case.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let text = text.trim();
+        let not_a_move = || ParseError::NotANumber(String::from(text));
+        let (cell, turn) = text.split_once('/').ok_or_else(not_a_move)?;
+        let cell = cell.parse().map_err(|_| not_a_move())?;
+        let mut chars = turn.chars();
+        let quadrant = chars
+            .next()
+            .and_then(|ch| ch.to_digit(10))
+            .ok_or_else(not_a_move)? as u8;
+        let clockwise = match chars.next() {
+            Some('R' | 'r') => true,
+            Some('L' | 'l') => false,
+            Some(ch) => {
+                return Err(ParseError::InvalidChar {
+                    ch,
+                    index: text.len() - turn.len() + 1,
+                });
+            }
+            None => return Err(not_a_move()),
+        };
+        if let Some(ch) = chars.next() {
+            return Err(ParseError::InvalidChar {
+                ch,
+                index: text.len() - turn.len() + 2,
+            });
+        }
+        Ok(Self {
+            cell,
+            quadrant,
+            clockwise,
+        })
+    }
+}
+
+impl fmt::Display for PentagoMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = if self.clockwise { 'R' } else { 'L' };
+        write!(f, "{}/{}{direction}", self.cell, self.quadrant)
+    }
+}
+
+/// Turns `quadrant` of `marbles` (one bit per cell) a quarter turn.
+fn rotate(marbles: u64, quadrant: u8, clockwise: bool) -> u64 {
+    let mut rotated = marbles;
+    for row in 0..3 {
+        for col in 0..3 {
+            let from = quadrant_cell(quadrant, row, col);
+            let to = if clockwise {
+                quadrant_cell(quadrant, col, 2 - row)
+            } else {
+                quadrant_cell(quadrant, 2 - col, row)
+            };
+            rotated &= !(1 << to);
+            rotated |= (marbles >> from & 1) << to;
+        }
+    }
+    rotated
+}
+
+/// `marbles` under `t`.
+fn map_marbles(marbles: u64, t: Transform) -> u64 {
+    let mut mapped = 0;
+    let mut rest = marbles;
+    while rest != 0 {
+        let cell = rest.trailing_zeros() as usize;
+        mapped |= 1 << t.map_index(SIZE, SIZE, cell);
+        rest &= rest - 1;
+    }
+    mapped
+}
+
+/// Whether `marbles` hold a row of five.
+fn has_line(marbles: u64) -> bool {
+    LINES.iter().any(|&line| line & !marbles == 0)
+}
+
+/// A position of Pentago: two players take turns placing a marble on a
+/// 6x6 board and then turning one of its four 3x3 quadrants a quarter
+/// turn either way. Five in a row wins, whether made by the placement or
+/// by the turn.
+///
+/// A placement that makes five in a row wins at once, without the turn.
+/// If a turn makes rows of five for both players, the game is drawn, as it
+/// is when the board fills up with no row of five.
+///
+/// Each player's marbles are a bitboard with bit `row * 6 + col` for each
+/// cell, row 0 at the top.
+///
+/// `position_key` tells apart positions that are rotations or reflections
+/// of each other, since a transposition table keeps their best moves;
+/// `canonical` and `canonical_key` identify them instead.
+///
+/// Two states are equal when their positions are, whatever move led to
+/// them.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "PentagoRepr", try_from = "PentagoRepr")
+)]
+pub struct PentagoState {
+    player1: u64,
+    player2: u64,
+    /// The move that led here (see `GameState::last_move`).
+    last_move: Option<PentagoMove>,
+}
+
+impl PartialEq for PentagoState {
+    fn eq(&self, other: &Self) -> bool {
+        self.player1 == other.player1 && self.player2 == other.player2
+    }
+}
+
+impl Eq for PentagoState {}
+
+/// Serialized form of `PentagoState` (see the `serde` feature).
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PentagoRepr {
+    player1: u64,
+    player2: u64,
+}
+
+#[cfg(feature = "serde")]
+impl From<PentagoState> for PentagoRepr {
+    fn from(state: PentagoState) -> Self {
+        Self {
+            player1: state.player1,
+            player2: state.player2,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<PentagoRepr> for PentagoState {
+    type Error = ParseError;
+
+    fn try_from(repr: PentagoRepr) -> Result<Self, Self::Error> {
+        Self::from_bits(repr.player1, repr.player2)
+    }
+}
+
+impl Default for PentagoState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PentagoState {
+    /// The empty board with Player1 to move.
+    pub fn new() -> Self {
+        Self {
+            player1: 0,
+            player2: 0,
+            last_move: None,
+        }
+    }
+
+    /// Builds a position from each player's marbles (bit `row * 6 + col`),
+    /// checking that it can occur in a game: every marble on the board, no
+    /// cell taken twice, and Player1 at most one marble ahead.
+    pub fn from_bits(player1: u64, player2: u64) -> Result<Self, ParseError> {
+        let off_board = (player1 | player2) & !FULL;
+        if off_board != 0 {
+            return Err(ParseError::WrongLength {
+                expected: CELLS,
+                found: 64 - off_board.leading_zeros() as usize,
+            });
+        }
+        let counts = (player1.count_ones(), player2.count_ones());
+        if player1 & player2 != 0 || !(counts.0 == counts.1 || counts.0 == counts.1 + 1) {
+            return Err(ParseError::ImpossibleMarkCounts {
+                player1: counts.0 as usize,
+                player2: counts.1 as usize,
+            });
+        }
+        Ok(Self {
+            player1,
+            player2,
+            last_move: None,
+        })
+    }
+
+    /// Builds a position by playing moves separated by spaces from the
+    /// empty board, e.g. `"14/2R 0/0L"`.
+    pub fn from_move_sequence(seq: &str) -> Result<Self, ParseError> {
+        let mut state = Self::new();
+        for (ply, text) in seq.split_whitespace().enumerate() {
+            let mv = PentagoMove::parse(text)?;
+            state = state
+                .try_apply_move(&mv)
+                .map_err(|error| ParseError::IllegalMoveAt {
+                    move_number: ply + 1,
+                    error,
+                })?;
+        }
+        Ok(state)
+    }
+
+    /// The cells holding `player`'s marbles, one bit per cell.
+    pub fn marbles(&self, player: Player) -> u64 {
+        match player {
+            Player::Player1 => self.player1,
+            Player::Player2 => self.player2,
+        }
+    }
+
+    /// The empty cells, one bit per cell.
+    pub fn empty(&self) -> u64 {
+        FULL & !(self.player1 | self.player2)
+    }
+
+    /// The position under each of the board's eight symmetries (rotations
+    /// and reflections, in the order of `Transform::ALL`), starting with
+    /// the position itself. The last move is mapped along.
+    pub fn symmetries(&self) -> [Self; 8] {
+        Transform::ALL.map(|t| self.transformed(t))
+    }
+
+    /// The symmetric image of the position with the smallest position key,
+    /// shared by all positions that differ only by a symmetry (see
+    /// `transforms::canonical`).
+    pub fn canonical(&self) -> Self {
+        canonical(self).0
+    }
+
+    /// The position key of `canonical`: equal for positions that are
+    /// rotations or reflections of each other, distinct otherwise.
+    pub fn canonical_key(&self) -> u64 {
+        self.canonical().position_key()
+    }
+
+    fn cell_at(&self, cell: usize) -> CellContent {
+        if self.player1 & (1 << cell) != 0 {
+            CellContent::Piece(Player::Player1)
+        } else if self.player2 & (1 << cell) != 0 {
+            CellContent::Piece(Player::Player2)
+        } else {
+            CellContent::Empty
+        }
+    }
+}
+
+/// Every symmetry of the square board maps quadrants onto quadrants, so it
+/// maps moves onto moves.
+impl Symmetric for PentagoState {
+    fn transforms() -> &'static [Transform] {
+        &Transform::ALL
+    }
+
+    fn transformed(&self, t: Transform) -> Self {
+        Self {
+            player1: map_marbles(self.player1, t),
+            player2: map_marbles(self.player2, t),
+            last_move: self.last_move.map(|mv| Self::transform_move(&mv, t)),
+        }
+    }
+
+    fn transform_move(mv: &PentagoMove, t: Transform) -> PentagoMove {
+        // A quadrant goes where its centre does; mirroring reverses the
+        // turn.
+        let centre = quadrant_cell(mv.quadrant, 1, 1);
+        PentagoMove {
+            cell: t.map_index(SIZE, SIZE, mv.cell as usize) as u8,
+            quadrant: quadrant_of(t.map_index(SIZE, SIZE, centre)),
+            clockwise: mv.clockwise != t.mirror,
+        }
+    }
+}
+
+/// Base-3 encoding of the board, nine cells at a time; 3^36 fits in 64
+/// bits, so it is collision-free.
+impl PositionKey for PentagoState {
+    fn position_key(&self) -> u64 {
+        let chunk = |marbles: u64, i: usize| TERNARY[(marbles >> (9 * i) & 0x1FF) as usize];
+        (0..4).rev().fold(0, |acc, i| {
+            acc * 3u64.pow(9) + chunk(self.player1, i) + 2 * chunk(self.player2, i)
+        })
+    }
+}
+
+impl GameState for PentagoState {
+    type Move = PentagoMove;
+
+    fn current_player(&self) -> Player {
+        if (self.player1 | self.player2).count_ones().is_multiple_of(2) {
+            Player::Player1
+        } else {
+            Player::Player2
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<PentagoMove> {
+        let mut moves = Vec::with_capacity(self.empty().count_ones() as usize * 8);
+        self.for_each_legal_move(|mv| moves.push(mv));
+        moves
+    }
+
+    fn for_each_legal_move(&self, mut f: impl FnMut(PentagoMove)) {
+        let mut empty = self.empty();
+        while empty != 0 {
+            let cell = empty.trailing_zeros() as u8;
+            for quadrant in 0..4 {
+                for clockwise in [true, false] {
+                    f(PentagoMove {
+                        cell,
+                        quadrant,
+                        clockwise,
+                    });
+                }
+            }
+            empty &= empty - 1;
+        }
+    }
+
+    fn check_move(&self, mv: &PentagoMove) -> Result<(), MoveError> {
+        if self.is_terminal() {
+            return Err(MoveError::GameOver);
+        }
+        if mv.cell as usize >= CELLS || mv.quadrant > 3 {
+            return Err(MoveError::OutOfRange);
+        }
+        if self.empty() & (1 << mv.cell) == 0 {
+            return Err(MoveError::CellOccupied);
+        }
+        Ok(())
+    }
+
+    fn apply_move(&self, mv: &PentagoMove) -> Self {
+        let mut next = *self;
+        let mover = match self.current_player() {
+            Player::Player1 => &mut next.player1,
+            Player::Player2 => &mut next.player2,
+        };
+        *mover |= 1 << mv.cell;
+        if !has_line(*mover) {
+            next.player1 = rotate(next.player1, mv.quadrant, mv.clockwise);
+            next.player2 = rotate(next.player2, mv.quadrant, mv.clockwise);
+        }
+        next.last_move = Some(*mv);
+        next
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.terminal_value().is_some()
+    }
+
+    fn terminal_value(&self) -> Option<i32> {
+        match (has_line(self.player1), has_line(self.player2)) {
+            (true, true) => Some(0),
+            (true, false) => Some(1),
+            (false, true) => Some(-1),
+            (false, false) => (self.empty() == 0).then_some(0),
+        }
+    }
+
+    /// Scores every row of five that only one player has marbles in, by
+    /// `LINE_WEIGHTS` of their number: positive for Player1, negative for
+    /// Player2.
+    fn heuristic_value(&self) -> i32 {
+        LINES
+            .iter()
+            .map(|&line| {
+                let p1 = (self.player1 & line).count_ones() as usize;
+                let p2 = (self.player2 & line).count_ones() as usize;
+                match (p1, p2) {
+                    (n, 0) => LINE_WEIGHTS[n],
+                    (0, n) => -LINE_WEIGHTS[n],
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+
+    /// Cells on more rows of five first: the quadrants' centres.
+    fn move_ordering_key(&self, mv: &PentagoMove) -> i32 {
+        CELL_LINES.get(mv.cell as usize).map_or(0, |&n| n as i32)
+    }
+
+    fn last_move(&self) -> Option<PentagoMove> {
+        self.last_move
+    }
+
+    fn ply(&self) -> Option<u32> {
+        Some((self.player1 | self.player2).count_ones())
+    }
+
+    fn complexity_hint(&self) -> ComplexityHint {
+        ComplexityHint {
+            branching_factor: 40.0,
+            max_plies: Some(self.empty().count_ones()),
+            recommended_depths: 1..=3,
+        }
+    }
+
+    fn recommended_depth(&self) -> u32 {
+        2
+    }
+}
+
+impl BoardGame for PentagoState {
+    fn rows(&self) -> usize {
+        SIZE
+    }
+
+    fn cols(&self) -> usize {
+        SIZE
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        assert!(
+            row < SIZE && col < SIZE,
+            "cell ({row}, {col}) is off the board"
+        );
+        self.cell_at(row * SIZE + col)
+    }
+}
+
+impl RegisteredGame for PentagoState {
+    const NAME: &'static str = "pentago";
+    const DISPLAY_NAME: &'static str = "Pentago";
+    const MOVE_SEPARATOR: &'static str = " ";
+    const WIN_LENGTH: Option<usize> = Some(WIN_LENGTH);
+
+    fn initial() -> Self {
+        Self::new()
+    }
+
+    fn format_move(mv: &PentagoMove) -> String {
+        alloc::format!("{mv}")
+    }
+
+    fn parse_move(&self, text: &str) -> Result<PentagoMove, ParseError> {
+        let mv = PentagoMove::parse(text)?;
+        self.check_move(&mv)?;
+        Ok(mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Outcome;
+    use crate::solvers::minimax::minimax_best_move_ab_depth;
+    use alloc::string::ToString;
+
+    fn state(player1: u64, player2: u64) -> PentagoState {
+        PentagoState {
+            player1,
+            player2,
+            last_move: None,
+        }
+    }
+
+    #[test]
+    fn moves_place_then_turn_a_quadrant() {
+        let mv = PentagoMove::parse("0/0R").unwrap();
+        assert_eq!(mv.to_string(), "0/0R");
+        let after = PentagoState::new().apply_move(&mv);
+        // The top left corner turns to the quadrant's top right.
+        assert_eq!(after.marbles(Player::Player1), 1 << 2);
+        let back = after.apply_move(&PentagoMove::parse("35/0l").unwrap());
+        assert_eq!(back.marbles(Player::Player1), 1);
+        assert_eq!(back.marbles(Player::Player2), 1 << 35);
+        assert_eq!(back.current_player(), Player::Player1);
+        assert_eq!(back.legal_moves().len(), 34 * 8);
+        assert_eq!(back.check_move(&mv), Err(MoveError::CellOccupied));
+        for bad in ["0", "0/4", "0/0X", "0/0RR", "x/0R"] {
+            assert!(PentagoMove::parse(bad).is_err(), "{bad}");
+        }
+        let quadrant = PentagoMove { quadrant: 4, ..mv };
+        assert_eq!(
+            PentagoState::new().check_move(&quadrant),
+            Err(MoveError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn wins_come_from_placements_and_turns() {
+        assert!(LINES.iter().all(|line| line.count_ones() == 5));
+        // Four across the top; the fifth marble wins before any turn.
+        let four = state(0b1111, 1 << 35 | 1 << 34 | 1 << 33 | 1 << 32);
+        let won = four.apply_move(&PentagoMove::parse("4/0R").unwrap());
+        assert_eq!(won.outcome(), Some(Outcome::Win(Player::Player1)));
+        assert_eq!(won.marbles(Player::Player1), 0b11111);
+
+        // Turning the top left quadrant anticlockwise brings its right
+        // column round to the top row, completing cells 0 to 4.
+        let turn = state(
+            1 << 3 | 1 << 4 | 1 << 8 | 1 << 14,
+            1 << 30 | 1 << 31 | 1 << 33 | 1 << 35,
+        );
+        let won = turn.apply_move(&PentagoMove::parse("2/0L").unwrap());
+        assert_eq!(won.marbles(Player::Player1), 0b11111);
+        assert_eq!(won.outcome(), Some(Outcome::Win(Player::Player1)));
+
+        // The same turn also brings Player2's left column round to row 2,
+        // making rows for both players: a draw.
+        let both = state(
+            1 << 2 | 1 << 3 | 1 << 4 | 1 << 8 | 1 << 14,
+            1 | 1 << 6 | 1 << 15 | 1 << 16,
+        );
+        let drawn = both.apply_move(&PentagoMove::parse("12/0L").unwrap());
+        assert_eq!(drawn.marbles(Player::Player2), 0b11111 << 12);
+        assert_eq!(drawn.outcome(), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn symmetric_positions_share_a_canonical_key() {
+        let start = PentagoState::from_move_sequence("0/3R 14/1L").unwrap();
+        let images = start.symmetries();
+        assert_eq!(images[0], start);
+        for image in images {
+            assert_eq!(image.canonical_key(), start.canonical_key());
+            assert_eq!(image.heuristic_value(), start.heuristic_value());
+            assert_eq!(image.ply(), Some(2));
+        }
+        // Mapping the last move along maps the whole game: replaying the
+        // image of the second move from the image of the first position
+        // gives the image of the second.
+        let first = PentagoState::from_move_sequence("0/3R").unwrap();
+        for (s, image) in images.iter().enumerate() {
+            let replayed = first.symmetries()[s].apply_move(&image.last_move.unwrap());
+            assert_eq!(replayed, *image, "symmetry {s}");
+        }
+        let keys: Vec<u64> = images.iter().map(|i| i.position_key()).collect();
+        assert!(keys.iter().skip(1).any(|&k| k != keys[0]));
+    }
+
+    #[test]
+    fn the_search_finds_a_win_in_one() {
+        let four = state(0b1111, 1 << 35 | 1 << 34 | 1 << 33 | 1 << 32);
+        let (mv, value) = minimax_best_move_ab_depth(&four, 1).unwrap();
+        assert!(value > 0);
+        assert!(four.apply_move(&mv).is_terminal());
+    }
+}
@@ -0,0 +1,289 @@
+use alloc::vec::Vec;
+
+use crate::{
+    error::MoveError,
+    game::{ComplexityHint, GameState, Player, PositionKey},
+    utils::opposite_player,
+};
+
+/// A move in a game played with the swap rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SwapMove<M> {
+    /// An ordinary move of the underlying game.
+    Play(M),
+    /// Player2 takes over the side that made the first move.
+    Swap,
+}
+
+/// Where the game is with respect to the swap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum SwapPhase {
+    /// No move has been played yet.
+    FirstMove,
+    /// One move has been played; Player2 may swap.
+    SwapOffered,
+    /// The chance to swap has passed.
+    Closed,
+}
+
+/// A game played with the swap (pie) rule: after Player1's first move,
+/// Player2 may either reply normally or swap, taking over the side that
+/// moved first. Player1 then moves next, playing the second side.
+///
+/// This neutralizes the first-move advantage in games like Connect Four:
+/// Player1 has to open with a move that is not too strong, or it will be
+/// taken over.
+///
+/// `Player1` and `Player2` here are the seats, not the sides of the
+/// underlying game. Values (`terminal_value`, `heuristic_value`) are from
+/// Player1's seat, so every solver works on the wrapped game unchanged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WithSwapRule<G> {
+    inner: G,
+    /// Whether Player2 swapped, so each seat plays the other side.
+    swapped: bool,
+    phase: SwapPhase,
+}
+
+impl<G: GameState + Default> Default for WithSwapRule<G> {
+    fn default() -> Self {
+        Self::new(G::default())
+    }
+}
+
+impl<G: GameState> WithSwapRule<G> {
+    /// Wraps the starting position of a game.
+    pub fn new(start: G) -> Self {
+        Self {
+            inner: start,
+            swapped: false,
+            phase: SwapPhase::FirstMove,
+        }
+    }
+
+    /// The position of the underlying game.
+    pub fn inner(&self) -> &G {
+        &self.inner
+    }
+
+    /// Returns `true` if Player2 swapped sides.
+    pub fn swapped(&self) -> bool {
+        self.swapped
+    }
+
+    /// Returns `true` if the side to move may swap now.
+    pub fn can_swap(&self) -> bool {
+        self.phase == SwapPhase::SwapOffered && !self.inner.is_terminal()
+    }
+
+    /// The seat playing `side` of the underlying game.
+    pub fn seat_of(&self, side: Player) -> Player {
+        if self.swapped {
+            opposite_player(side)
+        } else {
+            side
+        }
+    }
+
+    /// Converts a value from the underlying game's Player1 side to
+    /// Player1's seat.
+    fn to_seat_value(&self, value: i32) -> i32 {
+        if self.swapped { -value } else { value }
+    }
+}
+
+impl<G: GameState> GameState for WithSwapRule<G> {
+    type Move = SwapMove<G::Move>;
+
+    fn current_player(&self) -> Player {
+        self.seat_of(self.inner.current_player())
+    }
+
+    /// The underlying game's moves, followed by `Swap` when it is allowed.
+    fn legal_moves(&self) -> Vec<Self::Move> {
+        let mut moves = Vec::new();
+        self.for_each_legal_move(|mv| moves.push(mv));
+        moves
+    }
+
+    fn for_each_legal_move(&self, mut f: impl FnMut(Self::Move)) {
+        self.inner.for_each_legal_move(|mv| f(SwapMove::Play(mv)));
+        if self.can_swap() {
+            f(SwapMove::Swap);
+        }
+    }
+
+    fn apply_move(&self, mv: &Self::Move) -> Self {
+        match mv {
+            SwapMove::Play(mv) => Self {
+                inner: self.inner.apply_move(mv),
+                swapped: self.swapped,
+                phase: match self.phase {
+                    SwapPhase::FirstMove => SwapPhase::SwapOffered,
+                    _ => SwapPhase::Closed,
+                },
+            },
+            SwapMove::Swap => Self {
+                inner: self.inner.clone(),
+                swapped: !self.swapped,
+                phase: SwapPhase::Closed,
+            },
+        }
+    }
+
+    fn check_move(&self, mv: &Self::Move) -> Result<(), MoveError> {
+        match mv {
+            SwapMove::Play(mv) => self.inner.check_move(mv),
+            SwapMove::Swap if self.is_terminal() => Err(MoveError::GameOver),
+            SwapMove::Swap if !self.can_swap() => Err(MoveError::SwapUnavailable),
+            SwapMove::Swap => Ok(()),
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.inner.is_terminal()
+    }
+
+    fn terminal_value(&self) -> Option<i32> {
+        self.inner.terminal_value().map(|v| self.to_seat_value(v))
+    }
+
+    fn heuristic_value(&self) -> i32 {
+        self.to_seat_value(self.inner.heuristic_value())
+    }
+
+    /// Ordinary moves keep the underlying game's ordering; the swap is
+    /// tried last.
+    fn move_ordering_key(&self, mv: &Self::Move) -> i32 {
+        match mv {
+            SwapMove::Play(mv) => self.inner.move_ordering_key(mv),
+            SwapMove::Swap => i32::MIN,
+        }
+    }
+
+    /// The swap counts as a ply of its own.
+    fn ply(&self) -> Option<u32> {
+        self.inner.ply().map(|ply| ply + u32::from(self.swapped))
+    }
+
+    /// The swap adds at most one ply to the underlying game.
+    fn complexity_hint(&self) -> ComplexityHint {
+        let hint = self.inner.complexity_hint();
+        ComplexityHint {
+            max_plies: hint.max_plies.map(|p| p + u32::from(self.can_swap())),
+            ..hint
+        }
+    }
+
+    fn recommended_depth(&self) -> u32 {
+        self.inner.recommended_depth()
+    }
+}
+
+/// Packs the swap state into the two low bits; keys stay collision-free as
+/// long as the underlying key fits in 62 bits, as the built-in games' do.
+impl<G: GameState + PositionKey> PositionKey for WithSwapRule<G> {
+    fn position_key(&self) -> u64 {
+        let offered = self.phase == SwapPhase::SwapOffered;
+        (self.inner.position_key() << 2) | (u64::from(offered) << 1) | u64::from(self.swapped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::{TicTacToeMove, TicTacToeState};
+    use crate::solvers::minimax::{minimax_best_move_ab_depth, minimax_value_ab_root};
+    #[cfg(feature = "std")]
+    use crate::testing::{check_state_properties, walk_game_tree};
+
+    type SwapTtt = WithSwapRule<TicTacToeState>;
+
+    fn play(index: u8) -> SwapMove<TicTacToeMove> {
+        SwapMove::Play(TicTacToeMove { index })
+    }
+
+    #[test]
+    fn ttt_swap_is_only_offered_as_the_second_move() {
+        let start = SwapTtt::default();
+        assert!(!start.legal_moves().contains(&SwapMove::Swap));
+        assert_eq!(
+            start.check_move(&SwapMove::Swap),
+            Err(MoveError::SwapUnavailable)
+        );
+
+        let opened = start.apply_move(&play(4));
+        assert_eq!(opened.legal_moves().last(), Some(&SwapMove::Swap));
+        assert_eq!(opened.current_player(), Player::Player2);
+
+        let replied = opened.apply_move(&play(0));
+        assert!(!replied.legal_moves().contains(&SwapMove::Swap));
+        assert_eq!(
+            replied.try_apply_move(&SwapMove::Swap),
+            Err(MoveError::SwapUnavailable)
+        );
+    }
+
+    #[test]
+    fn ttt_swap_hands_the_first_side_to_player2() {
+        let swapped = SwapTtt::default()
+            .apply_move(&play(4))
+            .apply_move(&SwapMove::Swap);
+        assert!(swapped.swapped());
+        assert_eq!(
+            swapped.inner(),
+            &TicTacToeState::new().apply_move(&TicTacToeMove { index: 4 })
+        );
+        // Player1 now plays O, the side to move.
+        assert_eq!(swapped.current_player(), Player::Player1);
+        assert_eq!(swapped.seat_of(Player::Player1), Player::Player2);
+
+        // X (Player2's seat) completes the middle row.
+        let won = [0, 3, 1, 5]
+            .into_iter()
+            .fold(swapped, |s, i| s.apply_move(&play(i)));
+        assert_eq!(won.terminal_value(), Some(-1));
+    }
+
+    #[test]
+    fn ttt_swap_game_is_still_a_draw() {
+        assert_eq!(minimax_value_ab_root(&SwapTtt::default()), 0);
+        #[cfg(feature = "std")]
+        {
+            let visited = walk_game_tree(&SwapTtt::default(), 3, &mut |s| {
+                check_state_properties(s, true);
+            });
+            assert!(visited > 1);
+        }
+    }
+
+    #[test]
+    fn c4_swap_takes_over_a_strong_opening() {
+        type SwapC4 = WithSwapRule<BitboardState>;
+        let keys = [
+            SwapC4::default().position_key(),
+            SwapC4::default()
+                .apply_move(&SwapMove::Play(3))
+                .position_key(),
+            SwapC4::default()
+                .apply_move(&SwapMove::Play(3))
+                .apply_move(&SwapMove::Swap)
+                .position_key(),
+        ];
+        assert_ne!(keys[0], keys[1]);
+        assert_ne!(keys[1], keys[2]);
+
+        // The center opening is strong enough that Player2 takes it over.
+        let opened = SwapC4::default().apply_move(&SwapMove::Play(3));
+        let best = minimax_best_move_ab_depth(&opened, 4).unwrap();
+        assert_eq!(best.0, SwapMove::Swap);
+        // An edge opening is not worth taking.
+        let edge = SwapC4::default().apply_move(&SwapMove::Play(0));
+        let best = minimax_best_move_ab_depth(&edge, 4).unwrap();
+        assert_ne!(best.0, SwapMove::Swap);
+    }
+}
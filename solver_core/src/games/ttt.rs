@@ -1,9 +1,20 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 use crate::{
-    game::{GameState, Player},
+    error::{MoveError, ParseError},
+    game::{BoardGame, CellContent, ComplexityHint, GameState, Player, PositionKey, SwapPlayers},
+    heuristics::line_score,
+    registry::RegisteredGame,
+    render::{Glyphs, svg::SvgStyle},
+    transforms::{Symmetric, Transform},
     utils::opposite_player,
 };
 
-const WIN_LINES: [[usize; 3]; 8] = [
+/// Weights of open lines holding 0 to 3 marks of one player.
+pub(crate) const LINE_WEIGHTS: [i32; 4] = [0, 1, 10, 100];
+
+pub(crate) const WIN_LINES: [[usize; 3]; 8] = [
     [0, 1, 2],
     [3, 4, 5],
     [6, 7, 8],
@@ -16,6 +27,7 @@ const WIN_LINES: [[usize; 3]; 8] = [
 
 /// Represents the contents of a single Tic-Tac-Toe board cell.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Cell {
     /// The cell is empty (no player has played here yet).
     Empty,
@@ -37,14 +49,28 @@ pub enum Cell {
 ///  3 | 4 | 5
 /// ---+---+---
 ///  6 | 7 | 8
+///
+/// Two states are equal when their positions are, whatever move led to them.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TicTacToeState {
     /// The 3x3 board flattened into a fixed-size array of 9 cells.
     pub board: [Cell; 9],
     /// The player whose turn it is to move in this position.
     pub current_player: Player,
+    /// The move that led here (see `GameState::last_move`).
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) last_move: Option<TicTacToeMove>,
 }
 
+impl PartialEq for TicTacToeState {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board && self.current_player == other.current_player
+    }
+}
+
+impl Eq for TicTacToeState {}
+
 impl Default for TicTacToeState {
     fn default() -> Self {
         Self::new()
@@ -59,6 +85,7 @@ impl TicTacToeState {
         Self {
             board: [Cell::Empty; 9],
             current_player: Player::Player1,
+            last_move: None,
         }
     }
 
@@ -75,26 +102,62 @@ impl TicTacToeState {
     /// The current player could be inferred (e.g., X if #X == #O, else O),
     /// or you can decide to keep it simple and pass the current player in
     /// as an argument in a later version.
-    pub fn from_str(repr: &str, current_player: Player) -> Result<Self, String> {
+    pub fn from_str(repr: &str, current_player: Player) -> Result<Self, ParseError> {
         let cells: Vec<Cell> = repr
             .chars()
-            .map(|n| match n {
+            .enumerate()
+            .map(|(index, ch)| match ch {
                 'X' => Ok(Cell::X),
                 'O' => Ok(Cell::O),
                 '.' => Ok(Cell::Empty),
-                _ => Err(format!("Invalid character: {}", n)),
+                _ => Err(ParseError::InvalidChar { ch, index }),
             })
             .collect::<Result<_, _>>()?;
 
         let board: [Cell; 9] = cells
             .try_into()
-            .map_err(|v: Vec<_>| format!("Expected 9 cells, got {}", v.len()))?;
+            .map_err(|v: Vec<_>| ParseError::WrongLength {
+                expected: 9,
+                found: v.len(),
+            })?;
 
         Ok(Self {
             board,
             current_player,
+            last_move: None,
         })
     }
+
+    /// Like `from_str`, but infers the side to move from the mark counts:
+    /// X (Player1) moves when both sides have the same number of marks, and
+    /// O (Player2) moves when X has one more. Any other count is an error.
+    pub fn from_board_str(repr: &str) -> Result<Self, ParseError> {
+        let state = Self::from_str(repr, Player::Player1)?;
+        let xs = state.board.iter().filter(|&&c| c == Cell::X).count();
+        let os = state.board.iter().filter(|&&c| c == Cell::O).count();
+        let current_player = if xs == os {
+            Player::Player1
+        } else if xs == os + 1 {
+            Player::Player2
+        } else {
+            return Err(ParseError::ImpossibleMarkCounts {
+                player1: xs,
+                player2: os,
+            });
+        };
+        Ok(Self {
+            current_player,
+            ..state
+        })
+    }
+
+    /// Returns the string representation accepted by `from_str`.
+    ///
+    /// The current player is not part of the string and must be passed
+    /// back in separately when parsing.
+    pub fn to_repr(&self) -> String {
+        self.board.iter().map(|&c| cell_to_char(c)).collect()
+    }
 }
 
 /// Represents a single move in Tic-Tac-Toe.
@@ -102,12 +165,65 @@ impl TicTacToeState {
 /// For simplicity, a move is just "play in this cell index".
 /// The index should be in the range 0..=8, using the same
 /// indexing convention as `TicTacToeState`.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TicTacToeMove {
     /// The index (0..=8) of the cell where the current player plays.
     pub index: u8,
 }
 
+/// Base-3 encoding of the board (the side to move follows from the marks).
+impl PositionKey for TicTacToeState {
+    fn position_key(&self) -> u64 {
+        self.board.iter().rev().fold(0, |acc, cell| {
+            acc * 3
+                + match cell {
+                    Cell::Empty => 0,
+                    Cell::X => 1,
+                    Cell::O => 2,
+                }
+        })
+    }
+}
+
+impl SwapPlayers for TicTacToeState {
+    fn swap_players(&self) -> Self {
+        Self {
+            board: self.board.map(|cell| match cell {
+                Cell::X => Cell::O,
+                Cell::O => Cell::X,
+                Cell::Empty => Cell::Empty,
+            }),
+            current_player: opposite_player(self.current_player),
+            last_move: self.last_move,
+        }
+    }
+}
+
+impl Symmetric for TicTacToeState {
+    fn transforms() -> &'static [Transform] {
+        &Transform::ALL
+    }
+
+    fn transformed(&self, t: Transform) -> Self {
+        let mut board = [Cell::Empty; 9];
+        for (i, &cell) in self.board.iter().enumerate() {
+            board[t.map_index(3, 3, i)] = cell;
+        }
+        Self {
+            board,
+            current_player: self.current_player,
+            last_move: self.last_move.map(|mv| Self::transform_move(&mv, t)),
+        }
+    }
+
+    fn transform_move(mv: &TicTacToeMove, t: Transform) -> TicTacToeMove {
+        TicTacToeMove {
+            index: t.map_index(3, 3, usize::from(mv.index)) as u8,
+        }
+    }
+}
+
 impl GameState for TicTacToeState {
     /// The move type for Tic-Tac-Toe is just a cell index (0..=8).
     type Move = TicTacToeMove;
@@ -138,6 +254,25 @@ impl GameState for TicTacToeState {
             .collect()
     }
 
+    fn for_each_legal_move(&self, mut f: impl FnMut(Self::Move)) {
+        for (i, cell) in self.board.iter().enumerate() {
+            if *cell == Cell::Empty {
+                f(Self::Move { index: i as u8 });
+            }
+        }
+    }
+
+    fn check_move(&self, mv: &Self::Move) -> Result<(), MoveError> {
+        if self.is_terminal() {
+            return Err(MoveError::GameOver);
+        }
+        match self.board.get(mv.index as usize) {
+            None => Err(MoveError::OutOfRange),
+            Some(Cell::Empty) => Ok(()),
+            Some(_) => Err(MoveError::CellOccupied),
+        }
+    }
+
     /// Applies the given move and returns the resulting game state.
     ///
     /// This method MUST:
@@ -163,6 +298,7 @@ impl GameState for TicTacToeState {
         Self {
             board: new_board,
             current_player: new_player,
+            last_move: Some(*mv),
         }
     }
 
@@ -206,15 +342,86 @@ impl GameState for TicTacToeState {
         None
     }
 
+    /// Counts open lines, weighted by how many marks they hold (see
+    /// `KInARow`).
+    fn heuristic_value(&self) -> i32 {
+        line_score(self, 3, &LINE_WEIGHTS)
+    }
+
     fn move_ordering_key(&self, mv: &TicTacToeMove) -> i32 {
-        match mv.index {
-            4 => 3,             // center
-            0 | 2 | 6 | 8 => 2, // corners
-            _ => 1,             // edges
+        ordering_key(mv.index)
+    }
+
+    fn last_move(&self) -> Option<TicTacToeMove> {
+        self.last_move
+    }
+
+    fn ply(&self) -> Option<u32> {
+        Some(self.board.iter().filter(|c| **c != Cell::Empty).count() as u32)
+    }
+
+    /// The whole game tree is tiny: a full-depth search visits a few
+    /// thousand nodes.
+    fn complexity_hint(&self) -> ComplexityHint {
+        ComplexityHint {
+            branching_factor: 2.7,
+            max_plies: self.ply().map(|ply| 9 - ply),
+            recommended_depths: 1..=9,
         }
     }
 }
 
+impl BoardGame for TicTacToeState {
+    fn rows(&self) -> usize {
+        3
+    }
+
+    fn cols(&self) -> usize {
+        3
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        assert!(row < 3 && col < 3, "cell ({row}, {col}) is off the board");
+        match self.board[row * 3 + col] {
+            Cell::Empty => CellContent::Empty,
+            Cell::X => CellContent::Piece(Player::Player1),
+            Cell::O => CellContent::Piece(Player::Player2),
+        }
+    }
+}
+
+/// Moves are cell indices `0`..`8`, so `"404"` is a game record.
+impl RegisteredGame for TicTacToeState {
+    const NAME: &'static str = "ttt";
+    const DISPLAY_NAME: &'static str = "Tic-Tac-Toe";
+    const MOVE_SEPARATOR: &'static str = "";
+    const SYMBOLS: Glyphs = Glyphs::MARKS;
+    const EMOJI: Glyphs = Glyphs::MARK_EMOJI;
+    const SVG_STYLE: SvgStyle = SvgStyle::Marks;
+    const WIN_LENGTH: Option<usize> = Some(3);
+
+    fn initial() -> Self {
+        Self::new()
+    }
+
+    fn format_move(mv: &TicTacToeMove) -> String {
+        mv.index.to_string()
+    }
+
+    fn parse_move(&self, text: &str) -> Result<TicTacToeMove, ParseError> {
+        parse_ttt_move(text, self)
+    }
+}
+
+/// Centre first, then corners, then edges.
+pub(crate) fn ordering_key(index: u8) -> i32 {
+    match index {
+        4 => 3,             // center
+        0 | 2 | 6 | 8 => 2, // corners
+        _ => 1,             // edges
+    }
+}
+
 fn cell_to_char(c: Cell) -> char {
     match c {
         Cell::Empty => '.',
@@ -238,6 +445,7 @@ fn cell_to_char(c: Cell) -> char {
 ///   . | X | .
 ///  ---+---+---
 ///   O | . | .
+#[cfg(feature = "std")]
 pub fn print_ttt_board(state: &TicTacToeState) {
     for row in 0..3 {
         let base = row * 3;
@@ -259,21 +467,20 @@ pub fn print_ttt_board(state: &TicTacToeState) {
 ///
 /// This function will:
 /// - Trim whitespace
-/// - Return Err(...) on malformed input
-/// - Return Err(...) if the chosen cell is not empty in `state`
-pub fn parse_ttt_move(input: &str, state: &TicTacToeState) -> Result<TicTacToeMove, String> {
+/// - Return `ParseError::NotANumber` on malformed input
+/// - Return `ParseError::IllegalMove` if the index is off the board, the
+///   cell is not empty in `state`, or the game is over
+pub fn parse_ttt_move(input: &str, state: &TicTacToeState) -> Result<TicTacToeMove, ParseError> {
     let clean = input.trim();
-    // Try to parse as usize
     let idx: usize = clean
         .parse()
-        .map_err(|_| "Could not parse input as a number in 0..=8".to_string())?;
+        .map_err(|_| ParseError::NotANumber(clean.to_string()))?;
     if idx > 8 {
-        return Err("Index must be between 0 and 8".to_string());
-    }
-    if state.board[idx] != Cell::Empty {
-        return Err("Cell is not empty.".to_string());
+        return Err(MoveError::OutOfRange.into());
     }
-    Ok(TicTacToeMove { index: idx as u8 })
+    let mv = TicTacToeMove { index: idx as u8 };
+    state.check_move(&mv)?;
+    Ok(mv)
 }
 
 #[cfg(test)]
@@ -281,6 +488,18 @@ mod tests {
     use super::*;
     use crate::solvers::minimax::{minimax_value, minimax_value_ab_root};
 
+    #[test]
+    fn ttt_tracks_the_last_move_without_affecting_equality() {
+        let s = TicTacToeState::new();
+        assert_eq!(s.last_move(), None);
+        let s = s.apply_move(&TicTacToeMove { index: 4 });
+        let s = s.apply_move(&TicTacToeMove { index: 0 });
+        assert_eq!(s.last_move(), Some(TicTacToeMove { index: 0 }));
+        let parsed = TicTacToeState::from_str("O...X....", Player::Player1).unwrap();
+        assert_eq!(parsed.last_move(), None);
+        assert_eq!(parsed, s);
+    }
+
     #[test]
     fn ttt_from_str_parses_correctly() {
         let s = TicTacToeState::from_str("X.O...O..", Player::Player1).unwrap();
@@ -297,15 +516,75 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    fn ttt_parse_errors_say_what_went_wrong() {
+        let s = TicTacToeState::from_str("X........", Player::Player2).unwrap();
+        assert_eq!(
+            parse_ttt_move(" x ", &s),
+            Err(ParseError::NotANumber("x".to_string()))
+        );
+        assert_eq!(
+            parse_ttt_move("12", &s),
+            Err(ParseError::IllegalMove(MoveError::OutOfRange))
+        );
+        assert_eq!(
+            TicTacToeState::from_str("X.Z......", Player::Player1),
+            Err(ParseError::InvalidChar { ch: 'Z', index: 2 })
+        );
+        assert_eq!(
+            TicTacToeState::from_board_str("XX......."),
+            Err(ParseError::ImpossibleMarkCounts {
+                player1: 2,
+                player2: 0
+            })
+        );
+    }
+
     #[test]
     fn ttt_from_str_rejects_wrong_length() {
         let too_short = TicTacToeState::from_str("XOX.....", Player::Player1);
-        assert!(too_short.is_err());
+        assert_eq!(
+            too_short,
+            Err(ParseError::WrongLength {
+                expected: 9,
+                found: 8
+            })
+        );
 
         let too_long = TicTacToeState::from_str("XOX...O..X", Player::Player1);
         assert!(too_long.is_err());
     }
 
+    #[test]
+    fn ttt_from_board_str_infers_side_to_move() {
+        let s = TicTacToeState::from_board_str("X........").unwrap();
+        assert_eq!(s.current_player, Player::Player2);
+        let s = TicTacToeState::from_board_str("XO.......").unwrap();
+        assert_eq!(s.current_player, Player::Player1);
+        assert!(TicTacToeState::from_board_str("XX.......").is_err());
+        assert!(TicTacToeState::from_board_str("O........").is_err());
+    }
+
+    #[test]
+    fn ttt_try_apply_move_reports_why_a_move_is_illegal() {
+        let s = TicTacToeState::from_str("X........", Player::Player2).unwrap();
+        assert_eq!(
+            s.try_apply_move(&TicTacToeMove { index: 0 }),
+            Err(MoveError::CellOccupied)
+        );
+        assert_eq!(
+            s.try_apply_move(&TicTacToeMove { index: 9 }),
+            Err(MoveError::OutOfRange)
+        );
+        assert!(s.try_apply_move(&TicTacToeMove { index: 4 }).is_ok());
+        let won = TicTacToeState::from_str("XXXOO....", Player::Player2).unwrap();
+        assert_eq!(
+            won.check_move(&TicTacToeMove { index: 8 }),
+            Err(MoveError::GameOver)
+        );
+        assert!(parse_ttt_move("0", &s).is_err());
+    }
+
     #[test]
     fn ttt_terminal_value_x_win_row() {
         // X X X
@@ -369,4 +648,22 @@ mod tests {
         let corner = TicTacToeMove { index: 0 };
         assert!(s.move_ordering_key(&center) > s.move_ordering_key(&corner));
     }
+
+    #[test]
+    fn ttt_board_cells_follow_the_index_layout() {
+        let s = TicTacToeState::from_str("X...O...X", Player::Player2).unwrap();
+        assert_eq!((s.rows(), s.cols()), (3, 3));
+        assert_eq!(s.cell(0, 0), CellContent::Piece(Player::Player1));
+        assert_eq!(s.cell(1, 1).owner(), Some(Player::Player2));
+        assert_eq!(s.cell(2, 2), CellContent::Piece(Player::Player1));
+        assert_eq!(s.cell(0, 2), CellContent::Empty);
+    }
+
+    #[test]
+    fn ttt_recommends_a_full_depth_search() {
+        let s = TicTacToeState::new();
+        assert_eq!(s.recommended_depth(), 9);
+        assert_eq!(s.complexity_hint().max_plies, Some(9));
+        assert!(!s.complexity_hint().is_impractical(u32::MAX));
+    }
 }
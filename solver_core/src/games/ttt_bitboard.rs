@@ -0,0 +1,573 @@
+use alloc::string::String;
+#[cfg(feature = "serde")]
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::error::{MoveError, ParseError};
+use crate::game::{
+    BoardGame, CellContent, ComplexityHint, GameState, Player, PositionKey, SwapPlayers,
+};
+use crate::games::ttt::{
+    Cell, LINE_WEIGHTS, TicTacToeMove, TicTacToeState, WIN_LINES, ordering_key,
+};
+use crate::tablebase::PositionIndex;
+use crate::transforms::{Symmetric, Transform};
+use crate::utils::opposite_player;
+
+/// Every cell of the board.
+const FULL: u16 = 0x1FF;
+
+/// The winning lines as masks of cells.
+const LINES: [u16; 8] = {
+    let mut lines = [0; 8];
+    let mut i = 0;
+    while i < 8 {
+        let [a, b, c] = WIN_LINES[i];
+        lines[i] = (1 << a) | (1 << b) | (1 << c);
+        i += 1;
+    }
+    lines
+};
+
+/// `WINS[marks]`: whether one player's `marks` complete a line.
+const WINS: [bool; 512] = {
+    let mut wins = [false; 512];
+    let mut marks = 0;
+    while marks < 512 {
+        let mut i = 0;
+        while i < 8 {
+            if marks as u16 & LINES[i] == LINES[i] {
+                wins[marks] = true;
+            }
+            i += 1;
+        }
+        marks += 1;
+    }
+    wins
+};
+
+/// `TERNARY[marks]`: the sum of `3^i` over the cells `i` in `marks`, so a
+/// position's base-3 key is `TERNARY[x] + 2 * TERNARY[o]`.
+const TERNARY: [u32; 512] = {
+    let mut ternary = [0; 512];
+    let mut marks = 0;
+    while marks < 512 {
+        let (mut cell, mut power, mut sum) = (0, 1, 0);
+        while cell < 9 {
+            if marks & (1 << cell) != 0 {
+                sum += power;
+            }
+            power *= 3;
+            cell += 1;
+        }
+        ternary[marks] = sum;
+        marks += 1;
+    }
+    ternary
+};
+
+/// `CELL_MAPS[s][i]`: where symmetry `s` takes cell `i`. Symmetries 0 to 3
+/// rotate the board a quarter turn clockwise that many times; 4 to 7
+/// mirror it left to right first.
+const CELL_MAPS: [[usize; 9]; 8] = {
+    let mut maps = [[0; 9]; 8];
+    let mut s = 0;
+    while s < 8 {
+        let mut i = 0;
+        while i < 9 {
+            let (mut row, mut col) = (i / 3, i % 3);
+            if s >= 4 {
+                col = 2 - col;
+            }
+            let mut turns = 0;
+            while turns < s % 4 {
+                (row, col) = (col, 2 - row);
+                turns += 1;
+            }
+            maps[s][i] = row * 3 + col;
+            i += 1;
+        }
+        s += 1;
+    }
+    maps
+};
+
+/// `MAPPED_MARKS[s][marks]`: `marks` under symmetry `s`.
+const MAPPED_MARKS: [[u16; 512]; 8] = {
+    let mut mapped = [[0; 512]; 8];
+    let mut s = 0;
+    while s < 8 {
+        let mut marks = 0;
+        while marks < 512 {
+            let mut cell = 0;
+            while cell < 9 {
+                if marks & (1 << cell) != 0 {
+                    mapped[s][marks] |= 1 << CELL_MAPS[s][cell];
+                }
+                cell += 1;
+            }
+            marks += 1;
+        }
+        s += 1;
+    }
+    mapped
+};
+
+/// Tic-Tac-Toe with each player's marks in a `u16` (bit `i` for cell `i`,
+/// indexed as in `TicTacToeState`).
+///
+/// It plays exactly like `TicTacToeState`: the same moves in the same
+/// order, terminal values, heuristic values, move ordering and position
+/// keys, so any solver result carries over. Wins are looked up in a table
+/// of all 512 sets of marks instead of checking the lines one by one,
+/// which makes it the better choice for exhaustive work over the whole
+/// game. `From` converts between the two.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "TicTacToeBitboardRepr", try_from = "TicTacToeBitboardRepr")
+)]
+pub struct TicTacToeBitboard {
+    /// Player1's (X's) marks.
+    x: u16,
+    /// Player2's (O's) marks.
+    o: u16,
+    current_player: Player,
+    /// The move that led here (see `GameState::last_move`).
+    last_move: Option<TicTacToeMove>,
+}
+
+/// Deserializing goes through this type so the marks can be validated.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TicTacToeBitboardRepr {
+    x: u16,
+    o: u16,
+    current_player: Player,
+}
+
+#[cfg(feature = "serde")]
+impl From<TicTacToeBitboard> for TicTacToeBitboardRepr {
+    fn from(state: TicTacToeBitboard) -> Self {
+        Self {
+            x: state.x,
+            o: state.o,
+            current_player: state.current_player,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TicTacToeBitboardRepr> for TicTacToeBitboard {
+    type Error = String;
+
+    /// Rejects marks outside the nine cells and cells marked by both
+    /// players, which the win tables can't look up.
+    fn try_from(repr: TicTacToeBitboardRepr) -> Result<Self, String> {
+        if (repr.x | repr.o) & !FULL != 0 {
+            return Err("marks outside the board".to_string());
+        }
+        if repr.x & repr.o != 0 {
+            return Err("a cell is marked by both players".to_string());
+        }
+        Ok(Self {
+            x: repr.x,
+            o: repr.o,
+            current_player: repr.current_player,
+            last_move: None,
+        })
+    }
+}
+
+impl PartialEq for TicTacToeBitboard {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x && self.o == other.o && self.current_player == other.current_player
+    }
+}
+
+impl Eq for TicTacToeBitboard {}
+
+impl Default for TicTacToeBitboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TicTacToeBitboard {
+    /// The empty board with Player1 to move.
+    pub fn new() -> Self {
+        Self {
+            x: 0,
+            o: 0,
+            current_player: Player::Player1,
+            last_move: None,
+        }
+    }
+
+    /// Parses the format of `TicTacToeState::from_str`.
+    pub fn from_str(repr: &str, current_player: Player) -> Result<Self, ParseError> {
+        TicTacToeState::from_str(repr, current_player).map(|state| Self::from(&state))
+    }
+
+    /// Returns the string representation accepted by `from_str`.
+    pub fn to_repr(&self) -> String {
+        (0..9)
+            .map(|i| match self.cell_at(i) {
+                Cell::X => 'X',
+                Cell::O => 'O',
+                Cell::Empty => '.',
+            })
+            .collect()
+    }
+
+    /// The cells marked by `player`, one bit per cell.
+    pub fn marks(&self, player: Player) -> u16 {
+        match player {
+            Player::Player1 => self.x,
+            Player::Player2 => self.o,
+        }
+    }
+
+    /// The empty cells, one bit per cell.
+    pub fn empty(&self) -> u16 {
+        FULL & !(self.x | self.o)
+    }
+
+    /// The position under each of the board's eight symmetries (rotations
+    /// and reflections), starting with the position itself. The last move
+    /// is mapped along.
+    pub fn symmetries(&self) -> [Self; 8] {
+        core::array::from_fn(|s| Self {
+            x: MAPPED_MARKS[s][self.x as usize],
+            o: MAPPED_MARKS[s][self.o as usize],
+            current_player: self.current_player,
+            last_move: self.last_move.map(|mv| TicTacToeMove {
+                index: CELL_MAPS[s][mv.index as usize] as u8,
+            }),
+        })
+    }
+
+    /// The symmetric image of the position with the smallest position key,
+    /// shared by all positions that differ only by a symmetry.
+    pub fn canonical(&self) -> Self {
+        self.symmetries()
+            .into_iter()
+            .min_by_key(|image| image.position_key())
+            .unwrap_or(*self)
+    }
+
+    fn cell_at(&self, index: usize) -> Cell {
+        if self.x & (1 << index) != 0 {
+            Cell::X
+        } else if self.o & (1 << index) != 0 {
+            Cell::O
+        } else {
+            Cell::Empty
+        }
+    }
+}
+
+impl From<&TicTacToeState> for TicTacToeBitboard {
+    fn from(state: &TicTacToeState) -> Self {
+        let marks = |mark: Cell| {
+            (0..9)
+                .filter(|&i| state.board[i] == mark)
+                .fold(0, |acc, i| acc | (1 << i))
+        };
+        Self {
+            x: marks(Cell::X),
+            o: marks(Cell::O),
+            current_player: state.current_player,
+            last_move: state.last_move,
+        }
+    }
+}
+
+impl From<&TicTacToeBitboard> for TicTacToeState {
+    fn from(state: &TicTacToeBitboard) -> Self {
+        Self {
+            board: core::array::from_fn(|i| state.cell_at(i)),
+            current_player: state.current_player,
+            last_move: state.last_move,
+        }
+    }
+}
+
+/// The same base-3 key as `TicTacToeState`.
+impl PositionKey for TicTacToeBitboard {
+    fn position_key(&self) -> u64 {
+        u64::from(TERNARY[self.x as usize] + 2 * TERNARY[self.o as usize])
+    }
+}
+
+impl SwapPlayers for TicTacToeBitboard {
+    fn swap_players(&self) -> Self {
+        Self {
+            x: self.o,
+            o: self.x,
+            current_player: opposite_player(self.current_player),
+            last_move: self.last_move,
+        }
+    }
+}
+
+impl Symmetric for TicTacToeBitboard {
+    fn transforms() -> &'static [Transform] {
+        &Transform::ALL
+    }
+
+    fn transformed(&self, t: Transform) -> Self {
+        self.symmetries()[t.index()]
+    }
+
+    fn transform_move(mv: &TicTacToeMove, t: Transform) -> TicTacToeMove {
+        TicTacToeMove {
+            index: CELL_MAPS[t.index()][usize::from(mv.index)] as u8,
+        }
+    }
+}
+
+/// Positions are numbered by their base-3 position key.
+impl PositionIndex for TicTacToeBitboard {
+    fn index_count() -> u64 {
+        3u64.pow(9)
+    }
+
+    fn rank(&self) -> u64 {
+        self.position_key()
+    }
+
+    fn unrank(index: u64) -> Option<Self> {
+        if index >= Self::index_count() {
+            return None;
+        }
+        let (mut x, mut o, mut rest) = (0u16, 0u16, index);
+        for cell in 0..9 {
+            match rest % 3 {
+                1 => x |= 1 << cell,
+                2 => o |= 1 << cell,
+                _ => {}
+            }
+            rest /= 3;
+        }
+        let current_player = match x.count_ones().checked_sub(o.count_ones()) {
+            Some(0) => Player::Player1,
+            Some(1) => Player::Player2,
+            _ => return None,
+        };
+        Some(Self {
+            x,
+            o,
+            current_player,
+            last_move: None,
+        })
+    }
+}
+
+impl GameState for TicTacToeBitboard {
+    type Move = TicTacToeMove;
+
+    fn current_player(&self) -> Player {
+        self.current_player
+    }
+
+    fn legal_moves(&self) -> Vec<TicTacToeMove> {
+        let mut moves = Vec::with_capacity(self.empty().count_ones() as usize);
+        self.for_each_legal_move(|mv| moves.push(mv));
+        moves
+    }
+
+    fn for_each_legal_move(&self, mut f: impl FnMut(TicTacToeMove)) {
+        let mut empty = self.empty();
+        while empty != 0 {
+            f(TicTacToeMove {
+                index: empty.trailing_zeros() as u8,
+            });
+            empty &= empty - 1;
+        }
+    }
+
+    fn check_move(&self, mv: &TicTacToeMove) -> Result<(), MoveError> {
+        if self.is_terminal() {
+            return Err(MoveError::GameOver);
+        }
+        if mv.index > 8 {
+            return Err(MoveError::OutOfRange);
+        }
+        if self.empty() & (1 << mv.index) == 0 {
+            return Err(MoveError::CellOccupied);
+        }
+        Ok(())
+    }
+
+    fn apply_move(&self, mv: &TicTacToeMove) -> Self {
+        let bit = 1 << mv.index;
+        let (x, o) = match self.current_player {
+            Player::Player1 => (self.x | bit, self.o),
+            Player::Player2 => (self.x, self.o | bit),
+        };
+        Self {
+            x,
+            o,
+            current_player: opposite_player(self.current_player),
+            last_move: Some(*mv),
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        WINS[self.x as usize] || WINS[self.o as usize] || self.empty() == 0
+    }
+
+    fn terminal_value(&self) -> Option<i32> {
+        // Reachable positions never have lines for both players.
+        if WINS[self.x as usize] {
+            Some(1)
+        } else if WINS[self.o as usize] {
+            Some(-1)
+        } else if self.empty() == 0 {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    /// `TicTacToeState`'s open-line count, from the line masks.
+    fn heuristic_value(&self) -> i32 {
+        LINES
+            .iter()
+            .map(|&line| {
+                let xs = (self.x & line).count_ones() as usize;
+                let os = (self.o & line).count_ones() as usize;
+                match (xs, os) {
+                    (n, 0) => LINE_WEIGHTS[n],
+                    (0, n) => -LINE_WEIGHTS[n],
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+
+    fn move_ordering_key(&self, mv: &TicTacToeMove) -> i32 {
+        ordering_key(mv.index)
+    }
+
+    fn last_move(&self) -> Option<TicTacToeMove> {
+        self.last_move
+    }
+
+    fn ply(&self) -> Option<u32> {
+        Some((self.x | self.o).count_ones())
+    }
+
+    fn complexity_hint(&self) -> ComplexityHint {
+        ComplexityHint {
+            branching_factor: 2.7,
+            max_plies: self.ply().map(|ply| 9 - ply),
+            recommended_depths: 1..=9,
+        }
+    }
+}
+
+impl BoardGame for TicTacToeBitboard {
+    fn rows(&self) -> usize {
+        3
+    }
+
+    fn cols(&self) -> usize {
+        3
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        assert!(row < 3 && col < 3, "cell ({row}, {col}) is off the board");
+        match self.cell_at(row * 3 + col) {
+            Cell::Empty => CellContent::Empty,
+            Cell::X => CellContent::Piece(Player::Player1),
+            Cell::O => CellContent::Piece(Player::Player2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solvers::minimax::minimax_value_ab_root;
+
+    #[test]
+    fn ttt_bitboard_round_trips_through_the_array_state() {
+        let array = TicTacToeState::from_str("XO..X...O", Player::Player1).unwrap();
+        let bitboard = TicTacToeBitboard::from(&array);
+        assert_eq!(bitboard.marks(Player::Player1), 0b000_010_001);
+        assert_eq!(bitboard.marks(Player::Player2), 0b100_000_010);
+        assert_eq!(bitboard.to_repr(), "XO..X...O");
+        assert_eq!(TicTacToeState::from(&bitboard), array);
+        assert_eq!(
+            TicTacToeBitboard::from_str("XO..X...O", Player::Player1),
+            Ok(bitboard)
+        );
+        assert!(TicTacToeBitboard::from_str("XO..X...", Player::Player1).is_err());
+    }
+
+    #[test]
+    fn ttt_bitboard_detects_every_line() {
+        for line in LINES {
+            let won = TicTacToeBitboard {
+                x: line,
+                ..TicTacToeBitboard::new()
+            };
+            assert_eq!(won.terminal_value(), Some(1));
+            let lost = TicTacToeBitboard {
+                x: 0,
+                o: line,
+                ..won
+            };
+            assert_eq!(lost.terminal_value(), Some(-1));
+        }
+        let draw = TicTacToeBitboard::from_str("XOXXOOOXX", Player::Player1).unwrap();
+        assert_eq!(draw.terminal_value(), Some(0));
+        assert_eq!(minimax_value_ab_root(&TicTacToeBitboard::new()), 0);
+    }
+
+    #[test]
+    fn ttt_bitboard_symmetries_rotate_and_reflect() {
+        let s = TicTacToeBitboard::from_str("XO.......", Player::Player1).unwrap();
+        let images: Vec<String> = s.symmetries().iter().map(|i| i.to_repr()).collect();
+        assert_eq!(
+            images,
+            [
+                "XO.......",
+                "..X..O...",
+                ".......OX",
+                "...O..X..",
+                ".OX......",
+                ".....O..X",
+                "......XO.",
+                "X..O.....",
+            ]
+        );
+        for image in s.symmetries() {
+            assert_eq!(image.canonical(), s.canonical());
+        }
+        let moved = TicTacToeBitboard::new().apply_move(&TicTacToeMove { index: 1 });
+        assert_eq!(
+            moved.symmetries()[1].last_move(),
+            Some(TicTacToeMove { index: 5 })
+        );
+    }
+
+    #[test]
+    fn ttt_bitboard_rejects_illegal_moves() {
+        let s = TicTacToeBitboard::from_str("X........", Player::Player2).unwrap();
+        assert_eq!(
+            s.try_apply_move(&TicTacToeMove { index: 0 }),
+            Err(MoveError::CellOccupied)
+        );
+        assert_eq!(
+            s.check_move(&TicTacToeMove { index: 9 }),
+            Err(MoveError::OutOfRange)
+        );
+        let after = s.apply_move(&TicTacToeMove { index: 4 });
+        assert_eq!(after.last_move(), Some(TicTacToeMove { index: 4 }));
+        assert_eq!(after.ply(), Some(2));
+    }
+}
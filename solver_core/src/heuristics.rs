@@ -0,0 +1,223 @@
+use alloc::vec::Vec;
+
+use crate::eval::Evaluator;
+use crate::game::{BoardGame, CellContent, Player};
+
+/// A straight line of cells on a grid: horizontal, vertical or diagonal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Window {
+    /// The first cell, as (row, column).
+    pub start: (usize, usize),
+    /// Row and column offset from one cell to the next.
+    pub step: (isize, isize),
+    /// Number of cells.
+    pub len: usize,
+}
+
+impl Window {
+    /// The cells of the window, from `start`.
+    pub fn cells(self) -> impl Iterator<Item = (usize, usize)> {
+        (0..self.len as isize).map(move |i| {
+            (
+                (self.start.0 as isize + i * self.step.0) as usize,
+                (self.start.1 as isize + i * self.step.1) as usize,
+            )
+        })
+    }
+}
+
+/// Every window of `k` consecutive cells on a `rows` x `cols` grid, in all
+/// four directions.
+pub fn windows(rows: usize, cols: usize, k: usize) -> impl Iterator<Item = Window> {
+    // (step, valid start rows, valid start columns) for each direction.
+    let fits = |n: usize| (n + 1).saturating_sub(k);
+    let directions = [
+        ((0, 1), 0..rows, 0..fits(cols)),
+        ((1, 0), 0..fits(rows), 0..cols),
+        ((1, 1), 0..fits(rows), 0..fits(cols)),
+        ((-1, 1), (k.max(1) - 1).min(rows)..rows, 0..fits(cols)),
+    ];
+    directions
+        .into_iter()
+        .filter(move |_| k > 0)
+        .flat_map(move |(step, rows, cols)| {
+            rows.flat_map(move |row| {
+                cols.clone().map(move |col| Window {
+                    start: (row, col),
+                    step,
+                    len: k,
+                })
+            })
+        })
+}
+
+/// Counts the pieces of each player in `window`: (Player1, Player2).
+pub fn count_pieces<G: BoardGame>(state: &G, window: Window) -> (usize, usize) {
+    window
+        .cells()
+        .fold((0, 0), |(p1, p2), (row, col)| match state.cell(row, col) {
+            CellContent::Piece(Player::Player1) => (p1 + 1, p2),
+            CellContent::Piece(Player::Player2) => (p1, p2 + 1),
+            CellContent::Empty => (p1, p2),
+        })
+}
+
+/// Returns the player owning a full line of `k` cells, if any.
+///
+/// If both players own a line (which no legal game reaches), Player1 is
+/// returned.
+pub fn line_winner<G: BoardGame>(state: &G, k: usize) -> Option<Player> {
+    windows(state.rows(), state.cols(), k).find_map(|window| match count_pieces(state, window) {
+        (n, 0) if n == k => Some(Player::Player1),
+        (0, n) if n == k => Some(Player::Player2),
+        _ => None,
+    })
+}
+
+/// The windows of `k` cells holding exactly `pieces` of `player`'s pieces
+/// and none of the opponent's, e.g. open threes in Connect Four with
+/// `k = 4, pieces = 3`.
+pub fn open_lines<G: BoardGame>(state: &G, k: usize, player: Player, pieces: usize) -> Vec<Window> {
+    windows(state.rows(), state.cols(), k)
+        .filter(|&window| {
+            let (p1, p2) = count_pieces(state, window);
+            match player {
+                Player::Player1 => (p1, p2) == (pieces, 0),
+                Player::Player2 => (p1, p2) == (0, pieces),
+            }
+        })
+        .collect()
+}
+
+/// Scores `state` by its windows of `k` cells from Player1's perspective,
+/// as described for `KInARow`, with `weights[n]` for `n` pieces.
+///
+/// Lets games keep their weights in a constant instead of building a
+/// `KInARow`.
+pub fn line_score<G: BoardGame>(state: &G, k: usize, weights: &[i32]) -> i32 {
+    windows(state.rows(), state.cols(), k)
+        .map(|window| match count_pieces(state, window) {
+            (n, 0) => weights[n],
+            (0, n) => -weights[n],
+            _ => 0,
+        })
+        .sum()
+}
+
+/// The classic k-in-a-row heuristic: every window of `k` cells that only
+/// one player has pieces in scores `weights[n]` for that player's `n`
+/// pieces, positive for Player1 and negated for Player2.
+///
+/// Works for any grid game where lines decide the game: Tic-Tac-Toe,
+/// Connect Four, and m,n,k-games such as Gomoku.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KInARow {
+    k: usize,
+    weights: Vec<i32>,
+}
+
+impl KInARow {
+    /// A heuristic for lines of `k` cells. `weights` holds one entry per
+    /// piece count, from 0 to `k`.
+    ///
+    /// Panics if `weights` does not have `k + 1` entries.
+    pub fn new(k: usize, weights: Vec<i32>) -> Self {
+        assert_eq!(
+            weights.len(),
+            k + 1,
+            "need one weight for each count from 0 to {k}"
+        );
+        Self { k, weights }
+    }
+
+    /// The line length.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The score of a window holding `n` pieces of a single player.
+    pub fn weight(&self, n: usize) -> i32 {
+        self.weights[n]
+    }
+
+    /// Scores `state` from Player1's perspective.
+    pub fn score<G: BoardGame>(&self, state: &G) -> i32 {
+        line_score(state, self.k, &self.weights)
+    }
+}
+
+impl<G: BoardGame> Evaluator<G> for KInARow {
+    fn evaluate(&self, state: &G) -> i32 {
+        self.score(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::game::{GameState, Outcome};
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+    #[cfg(feature = "std")]
+    use crate::rng::{Rng, SplitMix64};
+    #[cfg(feature = "std")]
+    use crate::testing::playout;
+    use alloc::vec;
+
+    #[test]
+    fn window_counts_match_the_standard_boards() {
+        // 3 rows + 3 columns + 2 diagonals.
+        assert_eq!(windows(3, 3, 3).count(), 8);
+        // The well-known 69 lines of Connect Four.
+        assert_eq!(windows(6, 7, 4).count(), 69);
+        assert_eq!(windows(2, 2, 3).count(), 0);
+        for window in windows(6, 7, 4) {
+            assert!(window.cells().all(|(r, c)| r < 6 && c < 7));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn c4_line_winner_agrees_with_terminal_value() {
+        let mut rng = SplitMix64::new(7);
+        for _ in 0..200 {
+            let choices: Vec<u32> = (0..42).map(|_| rng.next_u64() as u32).collect();
+            let end = playout(&BitboardState::new(), &choices);
+            let expected = match end.outcome() {
+                Some(Outcome::Win(p)) => Some(p),
+                _ => None,
+            };
+            assert_eq!(line_winner(&end, 4), expected, "{end:?}");
+        }
+    }
+
+    #[test]
+    fn c4_open_lines_find_threes() {
+        // Player1 has 0, 1, 2 on the bottom row; only 0-3 is still open.
+        let s = BitboardState::from_move_sequence("051525").unwrap();
+        let threes = open_lines(&s, 4, Player::Player1, 3);
+        assert_eq!(threes.len(), 1);
+        assert_eq!(threes[0].start, (5, 0));
+        // Player2's stack in column 5 is open upwards.
+        let stack = open_lines(&s, 4, Player::Player2, 3);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack[0].step, (1, 0));
+    }
+
+    #[test]
+    fn ttt_k_in_a_row_prefers_open_lines() {
+        let heuristic = KInARow::new(3, vec![0, 1, 10, 100]);
+        assert_eq!(heuristic.score(&TicTacToeState::new()), 0);
+        // The center lies on four lines, a corner on three.
+        let center = TicTacToeState::from_str("....X....", Player::Player2).unwrap();
+        let corner = TicTacToeState::from_str("X........", Player::Player2).unwrap();
+        assert_eq!(heuristic.score(&center), 4);
+        assert_eq!(heuristic.score(&corner), 3);
+        // Blocked lines are worth nothing to either side.
+        let blocked = TicTacToeState::from_str("XO.......", Player::Player1).unwrap();
+        // X keeps its column and diagonal, O its column.
+        assert_eq!(heuristic.score(&blocked), 1);
+    }
+}
@@ -1,10 +1,67 @@
 //! Core library for generic game solving,
 //! including game state abstractions and solver algorithms.
+//!
+//! Without the default `std` feature the crate is `no_std` (it still needs
+//! `alloc`), so the engines can run on microcontrollers. Matches,
+//! tournaments, tuning, opening books, game sessions, Monte Carlo tree
+//! search and speculative search need `std`, as does
+//! `CancelToken::cancel_after`; time management takes its time from a
+//! `TimeSource` instead of `std::time::Instant`.
+//!
+//! `prelude` gathers the names most programs need, and `quick` answers the
+//! common questions (the best move after a game record, the exact result of
+//! a position) in one call.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod adaptive;
+pub mod agent;
+pub mod annotate;
+#[cfg(feature = "std")]
+pub mod arena;
+#[cfg(feature = "std")]
+pub mod book;
+pub mod cancel;
+#[cfg(feature = "chat")]
+pub mod chat;
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod distributed;
+pub mod dyn_game;
+pub mod error;
+pub mod eval;
+pub mod events;
+pub mod fairness;
 pub mod game;
 pub mod games;
+pub mod heuristics;
+pub mod prelude;
+pub mod quick;
+pub mod referee;
+pub mod registry;
+pub mod render;
+pub mod rng;
+pub mod selection;
+#[cfg(feature = "std")]
+pub mod sessions;
 pub mod solvers;
+#[cfg(feature = "std")]
+pub mod speculative;
+pub mod suites;
+pub mod tablebase;
+#[cfg(all(feature = "std", any(test, feature = "testing")))]
+pub mod testing;
+pub mod time_manager;
+#[cfg(feature = "std")]
+pub mod tournament;
+mod trace;
+pub mod transforms;
+#[cfg(feature = "std")]
+pub mod tuning;
 pub mod utils;
+pub mod verify;
 
 // Will add:
 // pub mod c4;
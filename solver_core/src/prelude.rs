@@ -0,0 +1,51 @@
+//! The names most programs using the crate need, in one `use`.
+//!
+//! `use solver_core::prelude::*;` brings in the game traits and the
+//! built-in games, the searches and what they return, the registry of games
+//! by name, and the facade in `quick` with its `Depth`. Each solver still
+//! lives in its own module under `solvers`:
+//!
+//! ```
+//! use solver_core::prelude::*;
+//! use solver_core::solvers::iterative::iterative_deepening;
+//! use solver_core::solvers::minimax::minimax_best_move_ab_depth;
+//! use solver_core::solvers::mtdf::{SearchAlgorithm, iterative_search};
+//!
+//! let state: BitboardState = quick::position("001122").unwrap();
+//!
+//! // Plain alpha-beta, iterative deepening and MTD(f) all find the win.
+//! assert_eq!(minimax_best_move_ab_depth(&state, 3).map(|(mv, _)| mv), Some(3));
+//! let report = iterative_deepening(&state, 3, |_| {}).unwrap();
+//! assert_eq!(report.best_move, 3);
+//! let mut tt = TranspositionTable::with_megabytes(1, ReplacementPolicy::TwoTier);
+//! let (best, _) = iterative_search(&state, 3, SearchAlgorithm::Mtdf, &mut tt);
+//! assert_eq!(best.map(|(mv, _)| mv), Some(3));
+//!
+//! // The configurable search, with two lines.
+//! let result = Search::new(&state).depth(3).multipv(2).run();
+//! assert_eq!(result.lines.len(), 2);
+//! assert_eq!(result.best_move(), Some(&3));
+//!
+//! // Any built-in game by name.
+//! let entry = Registry::builtin().get("ttt").copied().unwrap();
+//! let game = (entry.from_notation)("40").unwrap();
+//! assert_eq!(game.current_player(), Player::Player1);
+//! ```
+
+pub use crate::agent::{Agent, Decision};
+pub use crate::cancel::CancelToken;
+pub use crate::dyn_game::{DynGameState, DynMove};
+pub use crate::error::{MoveError, ParseError};
+pub use crate::game::{BoardGame, GameState, Outcome, Player, PositionKey};
+pub use crate::games::breakthrough::{BreakthroughMove, BreakthroughState};
+pub use crate::games::c4_3d::ScoreFourState;
+pub use crate::games::c4_bitboard::BitboardState;
+pub use crate::games::pentago::{PentagoMove, PentagoState};
+pub use crate::games::ttt::{TicTacToeMove, TicTacToeState};
+pub use crate::quick::{self, Depth};
+pub use crate::registry::{GameEntry, RegisteredGame, Registry};
+pub use crate::solvers::exact::{ExactSolution, solve_exact};
+#[cfg(feature = "std")]
+pub use crate::solvers::mcts::{MctsAgent, MctsTree};
+pub use crate::solvers::search::{PvLine, Search, SearchResult};
+pub use crate::solvers::tt::{ReplacementPolicy, TranspositionTable};
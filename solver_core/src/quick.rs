@@ -0,0 +1,183 @@
+//! One-call answers to the common questions about a position, for
+//! programs that want a best move rather than a search to put together.
+//!
+//! Positions are given as game records, the moves from the initial
+//! position in the game's notation (as `DynGameState::notation` writes
+//! them): column digits for Connect Four, cell indices for Tic-Tac-Toe.
+//! The searches behind these functions are the ones in `solvers`, with a
+//! transposition table of `TT_MEGABYTES`; use them directly for anything
+//! more (time limits, cancellation, several lines, threads).
+//!
+//! ```
+//! use solver_core::prelude::*;
+//!
+//! // Connect Four after 4, 4, 5, 3, 3 (0-based columns): O replies at 4.
+//! let result = quick::solve_c4("44533", Depth(8)).unwrap();
+//! assert_eq!(result.best_move(), Some(&4));
+//! assert_eq!(result.pv().len(), 8);
+//!
+//! // Tic-Tac-Toe is solved outright: the empty board is a draw.
+//! let solution = quick::solve_exact(&TicTacToeState::new());
+//! assert_eq!(solution.outcome, Outcome::Draw);
+//! assert_eq!(solution.plies, 9);
+//! ```
+
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use crate::dyn_game::{DynMove, Played};
+use crate::error::ParseError;
+use crate::game::{GameState, PositionKey};
+use crate::games::c4_bitboard::BitboardState;
+use crate::games::ttt::{TicTacToeMove, TicTacToeState};
+use crate::registry::{RegisteredGame, Registry};
+use crate::solvers::exact::{ExactSolution, solve_exact as solve_exact_with};
+use crate::solvers::search::{Search, SearchResult};
+use crate::solvers::tt::{ReplacementPolicy, TranspositionTable};
+
+/// Size of the transposition table of every search here, in MiB.
+pub const TT_MEGABYTES: usize = 16;
+
+/// A search depth in plies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Depth(pub u32);
+
+/// The position of `G` after the moves of `record`, as accepted by
+/// `GameEntry::from_notation`.
+///
+/// ```
+/// use solver_core::prelude::*;
+///
+/// let state: BitboardState = quick::position("3342").unwrap();
+/// assert_eq!(state.current_player(), Player::Player1);
+/// assert!(quick::position::<TicTacToeState>("44").is_err());
+/// ```
+pub fn position<G: RegisteredGame>(record: &str) -> Result<G, ParseError> {
+    Played::<G>::from_notation(record).map(Played::into_state)
+}
+
+/// Searches `state` `depth` plies deep through a transposition table.
+///
+/// ```
+/// use solver_core::prelude::*;
+///
+/// // X to move completes the top row.
+/// let state = TicTacToeState::from_str("XX.OO....", Player::Player1).unwrap();
+/// let result = quick::solve(&state, Depth(3));
+/// assert_eq!(result.best_move(), Some(&TicTacToeMove { index: 2 }));
+/// assert!(result.value().unwrap() > 0);
+/// ```
+pub fn solve<G>(state: &G, depth: Depth) -> SearchResult<G::Move>
+where
+    G: GameState + PositionKey + Sync,
+    G::Move: PartialEq + Send + Sync,
+{
+    Search::new(state)
+        .depth(depth.0)
+        .with_tt(TT_MEGABYTES)
+        .run()
+}
+
+/// Searches the Connect Four position after `record` (0-based column
+/// digits, e.g. `"44533"`) `depth` plies deep.
+///
+/// ```
+/// use solver_core::prelude::*;
+///
+/// let result = quick::solve_c4("", Depth(6)).unwrap();
+/// assert_eq!(result.best_move(), Some(&3));
+/// assert!(quick::solve_c4("9", Depth(6)).is_err());
+/// ```
+pub fn solve_c4(record: &str, depth: Depth) -> Result<SearchResult<u8>, ParseError> {
+    Ok(solve(&position::<BitboardState>(record)?, depth))
+}
+
+/// Searches the Tic-Tac-Toe position after `record` (cell indices 0-8,
+/// row by row, e.g. `"40"`) `depth` plies deep.
+///
+/// ```
+/// use solver_core::prelude::*;
+///
+/// // X in the center, O in a corner, X on the opposite corner: O must not
+/// // let X take a third corner.
+/// let result = quick::solve_ttt("408", Depth(9)).unwrap();
+/// assert_eq!(result.value(), Some(0));
+/// assert!(matches!(result.best_move(), Some(mv) if [2, 6].contains(&mv.index)));
+/// ```
+pub fn solve_ttt(record: &str, depth: Depth) -> Result<SearchResult<TicTacToeMove>, ParseError> {
+    Ok(solve(&position::<TicTacToeState>(record)?, depth))
+}
+
+/// Searches the position after `record` of the built-in game called `game`
+/// (see `Registry::builtin`), to `depth` or else the game's default depth.
+/// Moves come back as `DynMove`s, which print in the game's notation.
+///
+/// ```
+/// use solver_core::prelude::*;
+///
+/// let result = quick::solve_game("score4", "", Some(Depth(2))).unwrap();
+/// assert_eq!(result.depth, 2);
+/// let result = quick::solve_game("breakthrough", "a2-a3 h7-h6", Some(Depth(1))).unwrap();
+/// assert!(result.best_move().is_some());
+/// let result = quick::solve_game("pentago", "0/0R", Some(Depth(1))).unwrap();
+/// assert!(result.best_move().unwrap().to_string().contains('/'));
+/// assert_eq!(
+///     quick::solve_game("chess", "", None).err(),
+///     Some("unknown game 'chess'".to_string())
+/// );
+/// ```
+pub fn solve_game(
+    game: &str,
+    record: &str,
+    depth: Option<Depth>,
+) -> Result<SearchResult<DynMove>, String> {
+    let registry = Registry::builtin();
+    let entry = registry
+        .get(game)
+        .ok_or_else(|| format!("unknown game '{game}'"))?;
+    let state = (entry.from_notation)(record).map_err(|e| e.to_string())?;
+    let depth = depth.map_or(entry.default_depth, |depth| depth.0);
+    Ok(state.search(depth, None))
+}
+
+/// Solves `state` exactly: its result with best play, how many plies that
+/// takes, and a best move (see `solvers::exact::solve_exact`).
+///
+/// ```
+/// use solver_core::prelude::*;
+///
+/// let state: BitboardState = quick::position("332211").unwrap();
+/// let solution = quick::solve_exact(&state);
+/// assert_eq!(solution.outcome, Outcome::Win(Player::Player1));
+/// assert_eq!((solution.plies, solution.best_move), (1, Some(0)));
+/// ```
+pub fn solve_exact<G>(state: &G) -> ExactSolution<G::Move>
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    let mut tt =
+        TranspositionTable::with_megabytes(TT_MEGABYTES, ReplacementPolicy::DepthPreferred);
+    solve_exact_with(state, &mut tt, None, |_, _| {}).expect("the search has no cancel token")
+}
+
+/// Picks a move for the side to move in `state` by Monte Carlo tree
+/// search: `iterations` playouts, chosen reproducibly from `seed`.
+///
+/// ```
+/// use solver_core::prelude::*;
+///
+/// // Three in the bottom row: X wins at column 3.
+/// let state: BitboardState = quick::position("001122").unwrap();
+/// assert_eq!(quick::mcts_move(&state, 2000, 1), Some(3));
+/// ```
+#[cfg(feature = "std")]
+pub fn mcts_move<G: GameState + PositionKey>(
+    state: &G,
+    iterations: u32,
+    seed: u64,
+) -> Option<G::Move> {
+    crate::solvers::mcts::MctsTree::new(seed)
+        .search(state, iterations)
+        .map(|(mv, _)| mv)
+}
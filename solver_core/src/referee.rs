@@ -0,0 +1,703 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::time::Duration;
+
+use crate::clock::{GameClock, TimeControl};
+use crate::error::MoveError;
+use crate::events::{GameEvent, GameObserver};
+use crate::game::{GameState, Outcome, Player};
+use crate::utils::opposite_player;
+
+/// A move sent by a remote player, who cannot be trusted to follow the
+/// rules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Submission<M> {
+    /// Who claims to be moving.
+    pub player: Player,
+    /// The number of moves the player saw played before this one. A
+    /// submission made against an older position (a resend, or a reply to
+    /// a move that has since been taken back) no longer matches.
+    pub ply: u32,
+    pub mv: M,
+}
+
+/// Why a `Referee` refused a submission.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rejection {
+    /// The game has ended.
+    GameOver,
+    /// The other player is to move.
+    NotYourTurn { to_move: Player },
+    /// The submission was made against another position.
+    WrongPly { expected: u32, found: u32 },
+    /// The move can't be played in the position.
+    Illegal(MoveError),
+    /// The player's clock ran out before the move arrived; they lose on
+    /// time.
+    TimeExpired,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::GameOver => f.write_str("the game is over"),
+            Rejection::NotYourTurn { .. } => f.write_str("it is not your turn"),
+            Rejection::WrongPly { expected, found } => write!(
+                f,
+                "the move was made after {found} moves, but {expected} have been played"
+            ),
+            Rejection::Illegal(e) => write!(f, "illegal move: {e}"),
+            Rejection::TimeExpired => f.write_str("time expired"),
+        }
+    }
+}
+
+impl core::error::Error for Rejection {}
+
+/// What became of a submission.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Verdict<M> {
+    /// The move was played. `remaining` is the mover's time left after
+    /// it, under a time control.
+    Accepted {
+        mv: M,
+        elapsed: Duration,
+        remaining: Option<Duration>,
+    },
+    /// The move was refused and the game is unchanged, unless the reason
+    /// is `TimeExpired`.
+    Rejected { mv: M, reason: Rejection },
+}
+
+/// One ruling of a `Referee`, for the transport to relay and log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RefereeEvent<M> {
+    /// Counts the referee's rulings from 0, so gaps and replays show.
+    pub seq: u64,
+    /// Moves played before the submission.
+    pub ply: u32,
+    /// The player the submission came from.
+    pub player: Player,
+    pub verdict: Verdict<M>,
+    /// Set by the referee's `EventSigner`, if it has one, over the event
+    /// with this field `None`.
+    pub signature: Option<String>,
+}
+
+impl<M> RefereeEvent<M> {
+    /// Returns true if the move was played.
+    pub fn is_accepted(&self) -> bool {
+        matches!(self.verdict, Verdict::Accepted { .. })
+    }
+}
+
+/// Signs `RefereeEvent`s so clients and logs can check they came from the
+/// referee, e.g. with an HMAC over a serialized event.
+pub trait EventSigner<M>: Send + Sync {
+    /// The signature of `event`, whose `signature` is `None`.
+    fn sign(&self, event: &RefereeEvent<M>) -> String;
+}
+
+/// A submission that passed every check, to be played by
+/// `Referee::commit`.
+#[derive(Clone, Debug)]
+pub struct Approved<G: GameState> {
+    submission: Submission<G::Move>,
+    elapsed: Duration,
+    next: G,
+}
+
+impl<G: GameState> Approved<G> {
+    /// The submission that was approved.
+    pub fn submission(&self) -> &Submission<G::Move> {
+        &self.submission
+    }
+
+    /// The position after the move.
+    pub fn state(&self) -> &G {
+        &self.next
+    }
+}
+
+/// Validates and plays the moves of remote players, so each transport
+/// (WebSocket, chat bot, ...) doesn't need its own checks.
+///
+/// Moves go through two steps. `check` validates a submission against
+/// the turn order, the position it was made in, the rules and the clock,
+/// without changing anything; `commit` then plays an approved move. A
+/// transport can do its own work in between (persist the move, or ask the
+/// other side to confirm) and drop the approval to abandon it. `submit`
+/// does both steps at once. Either way every ruling is returned as a
+/// `RefereeEvent`.
+///
+/// Like `Clock`, the referee never reads the system time itself: callers
+/// pass the current time from their `TimeSource`.
+#[derive(Clone)]
+pub struct Referee<G: GameState> {
+    state: G,
+    ply: u32,
+    clock: Option<GameClock>,
+    /// When the player to move got the turn.
+    turn_started: Duration,
+    /// The player who lost on time, if one did.
+    flagged: Option<Player>,
+    next_seq: u64,
+    signer: Option<Arc<dyn EventSigner<G::Move>>>,
+}
+
+impl<G: GameState> fmt::Debug for Referee<G>
+where
+    G: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Referee")
+            .field("state", &self.state)
+            .field("ply", &self.ply)
+            .field("clock", &self.clock)
+            .field("flagged", &self.flagged)
+            .field("next_seq", &self.next_seq)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<G: GameState> Referee<G> {
+    /// A referee for a game starting from `state`, without a time control.
+    pub fn new(state: G) -> Self {
+        Self {
+            ply: state.ply().unwrap_or(0),
+            state,
+            clock: None,
+            turn_started: Duration::ZERO,
+            flagged: None,
+            next_seq: 0,
+            signer: None,
+        }
+    }
+
+    /// Puts both players on `control`, with the player to move's clock
+    /// starting at `now`.
+    pub fn with_time_control(self, control: TimeControl, now: Duration) -> Self {
+        self.with_clock(GameClock::new(control), now)
+    }
+
+    /// Plays on `clock`, e.g. one saved part-way through a game, the player
+    /// to move's turn starting at `now`.
+    pub fn with_clock(mut self, clock: GameClock, now: Duration) -> Self {
+        self.clock = Some(clock);
+        self.turn_started = now;
+        self
+    }
+
+    /// Signs every event with `signer`.
+    pub fn with_signer(mut self, signer: impl EventSigner<G::Move> + 'static) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// The current position.
+    pub fn state(&self) -> &G {
+        &self.state
+    }
+
+    /// Moves played so far, counting any before the starting position.
+    pub fn ply(&self) -> u32 {
+        self.ply
+    }
+
+    /// Both players' clocks, under a time control.
+    pub fn clock(&self) -> Option<&GameClock> {
+        self.clock.as_ref()
+    }
+
+    /// The result, once the game is over on the board or on time.
+    pub fn outcome(&self) -> Option<Outcome> {
+        match self.flagged {
+            Some(loser) => Some(Outcome::Win(opposite_player(loser))),
+            None => self.state.outcome(),
+        }
+    }
+
+    /// Returns true once no more moves will be accepted.
+    pub fn is_over(&self) -> bool {
+        self.outcome().is_some()
+    }
+
+    /// Step one: checks `submission`, arriving at `now`, without changing
+    /// the game.
+    pub fn check(
+        &self,
+        submission: Submission<G::Move>,
+        now: Duration,
+    ) -> Result<Approved<G>, (Submission<G::Move>, Rejection)> {
+        match self.validate(&submission, now) {
+            Ok((next, elapsed)) => Ok(Approved {
+                submission,
+                elapsed,
+                next,
+            }),
+            Err(reason) => Err((submission, reason)),
+        }
+    }
+
+    fn validate(
+        &self,
+        submission: &Submission<G::Move>,
+        now: Duration,
+    ) -> Result<(G, Duration), Rejection> {
+        if self.is_over() {
+            return Err(Rejection::GameOver);
+        }
+        let to_move = self.state.current_player();
+        if submission.player != to_move {
+            return Err(Rejection::NotYourTurn { to_move });
+        }
+        if submission.ply != self.ply {
+            return Err(Rejection::WrongPly {
+                expected: self.ply,
+                found: submission.ply,
+            });
+        }
+        let elapsed = now.saturating_sub(self.turn_started);
+        if let Some(clock) = &self.clock
+            && elapsed > clock.get(to_move).remaining()
+        {
+            return Err(Rejection::TimeExpired);
+        }
+        let next = self
+            .state
+            .try_apply_move(&submission.mv)
+            .map_err(Rejection::Illegal)?;
+        Ok((next, elapsed))
+    }
+
+    /// Step two: plays an approved move, starting the opponent's clock at
+    /// `now`.
+    ///
+    /// The move is rejected after all if the game moved on since it was
+    /// approved (another approval was committed first).
+    pub fn commit(&mut self, approved: Approved<G>, now: Duration) -> RefereeEvent<G::Move> {
+        let Approved {
+            submission,
+            elapsed,
+            next,
+        } = approved;
+        if let Err(reason) = self.revalidate(&submission) {
+            return self.reject(submission, reason);
+        }
+        let mut remaining = None;
+        if let Some(clock) = &mut self.clock {
+            let clock = clock.get_mut(submission.player);
+            clock
+                .record_move(elapsed)
+                .expect("approved moves were made in time");
+            remaining = Some(clock.remaining());
+        }
+        self.state = next;
+        self.ply += 1;
+        self.turn_started = now;
+        self.event(
+            submission.ply,
+            submission.player,
+            Verdict::Accepted {
+                mv: submission.mv,
+                elapsed,
+                remaining,
+            },
+        )
+    }
+
+    /// The checks of `check` that can change between approving and
+    /// committing.
+    fn revalidate(&self, submission: &Submission<G::Move>) -> Result<(), Rejection> {
+        if self.is_over() {
+            Err(Rejection::GameOver)
+        } else if submission.ply != self.ply {
+            Err(Rejection::WrongPly {
+                expected: self.ply,
+                found: submission.ply,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Records the rejection of `submission` for `reason`. A
+    /// `TimeExpired` rejection ends the game: the submitting player loses
+    /// on time.
+    pub fn reject(
+        &mut self,
+        submission: Submission<G::Move>,
+        reason: Rejection,
+    ) -> RefereeEvent<G::Move> {
+        if reason == Rejection::TimeExpired {
+            self.flag(submission.player);
+        }
+        self.event(
+            submission.ply,
+            submission.player,
+            Verdict::Rejected {
+                mv: submission.mv,
+                reason,
+            },
+        )
+    }
+
+    /// Checks and, if it passes, plays `submission` arriving at `now`.
+    pub fn submit(
+        &mut self,
+        submission: Submission<G::Move>,
+        now: Duration,
+    ) -> RefereeEvent<G::Move> {
+        match self.check(submission, now) {
+            Ok(approved) => self.commit(approved, now),
+            Err((submission, reason)) => self.reject(submission, reason),
+        }
+    }
+
+    /// Submits `submission` as `submit` does and tells `observer` its
+    /// `game_events`, with `eval` as the mover's evaluation of the move.
+    pub fn submit_observed(
+        &mut self,
+        submission: Submission<G::Move>,
+        eval: Option<i32>,
+        now: Duration,
+        observer: &mut impl GameObserver<G::Move>,
+    ) -> RefereeEvent<G::Move> {
+        let event = self.submit(submission, now);
+        for mut game_event in self.game_events(&event) {
+            if let GameEvent::MoveMade { eval: e, .. } = &mut game_event {
+                *e = eval;
+            }
+            observer.on_event(&game_event);
+        }
+        event
+    }
+
+    /// Ends the game if the player to move has run out of time by `now`,
+    /// even though they never submitted a move, and returns them.
+    pub fn check_time(&mut self, now: Duration) -> Option<Player> {
+        let clock = self.clock.as_ref()?;
+        let to_move = self.state.current_player();
+        if self.is_over() || now.saturating_sub(self.turn_started) <= clock.get(to_move).remaining()
+        {
+            return None;
+        }
+        self.flag(to_move);
+        Some(to_move)
+    }
+
+    fn flag(&mut self, player: Player) {
+        if let Some(clock) = &mut self.clock {
+            let _ = clock.get_mut(player).record_move(Duration::MAX);
+        }
+        self.flagged = Some(player);
+    }
+
+    fn event(
+        &mut self,
+        ply: u32,
+        player: Player,
+        verdict: Verdict<G::Move>,
+    ) -> RefereeEvent<G::Move> {
+        let mut event = RefereeEvent {
+            seq: self.next_seq,
+            ply,
+            player,
+            verdict,
+            signature: None,
+        };
+        self.next_seq += 1;
+        event.signature = self.signer.as_ref().map(|signer| signer.sign(&event));
+        event
+    }
+
+    /// The `GameEvent`s for spectators of `event`, which must be the
+    /// referee's latest ruling: the move and the mover's clock if a move
+    /// was played, a clock at zero if a player lost on time, and the end of
+    /// the game if it is over. Rejected moves change nothing and have none.
+    pub fn game_events(&self, event: &RefereeEvent<G::Move>) -> Vec<GameEvent<G::Move>> {
+        let mut events = Vec::new();
+        match &event.verdict {
+            Verdict::Accepted { mv, remaining, .. } => {
+                events.push(GameEvent::MoveMade {
+                    player: event.player,
+                    mv: mv.clone(),
+                    eval: None,
+                });
+                if let Some(remaining) = *remaining {
+                    events.push(GameEvent::ClockUpdate {
+                        player: event.player,
+                        remaining,
+                    });
+                }
+            }
+            Verdict::Rejected {
+                reason: Rejection::TimeExpired,
+                ..
+            } => events.push(GameEvent::ClockUpdate {
+                player: event.player,
+                remaining: Duration::ZERO,
+            }),
+            Verdict::Rejected { .. } => return events,
+        }
+        if let Some(outcome) = self.outcome() {
+            events.push(GameEvent::GameEnded {
+                outcome: Some(outcome),
+            });
+        }
+        events
+    }
+
+    /// Returns true if `event` carries the signature this referee's signer
+    /// gives it. Without a signer, only unsigned events verify.
+    pub fn verify(&self, event: &RefereeEvent<G::Move>) -> bool
+    where
+        G::Move: Clone,
+    {
+        let unsigned = RefereeEvent {
+            signature: None,
+            ..event.clone()
+        };
+        let expected = self.signer.as_ref().map(|signer| signer.sign(&unsigned));
+        event.signature == expected
+    }
+}
+
+/// Boxed signers sign like the signer they hold.
+impl<M> EventSigner<M> for Box<dyn EventSigner<M>> {
+    fn sign(&self, event: &RefereeEvent<M>) -> String {
+        (**self).sign(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::{TicTacToeMove, TicTacToeState};
+    use alloc::format;
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    fn submission(player: Player, ply: u32, index: u8) -> Submission<TicTacToeMove> {
+        Submission {
+            player,
+            ply,
+            mv: TicTacToeMove { index },
+        }
+    }
+
+    fn rejection<M>(event: &RefereeEvent<M>) -> Option<Rejection> {
+        match event.verdict {
+            Verdict::Rejected { reason, .. } => Some(reason),
+            Verdict::Accepted { .. } => None,
+        }
+    }
+
+    #[test]
+    fn enforces_turns_plies_and_legality() {
+        let mut referee = Referee::new(TicTacToeState::new());
+        let event = referee.submit(submission(Player::Player1, 0, 4), SEC);
+        assert!(event.is_accepted());
+        assert_eq!((event.seq, event.ply), (0, 0));
+        assert_eq!(referee.ply(), 1);
+
+        let cases = [
+            (
+                submission(Player::Player1, 1, 0),
+                Rejection::NotYourTurn {
+                    to_move: Player::Player2,
+                },
+            ),
+            (
+                submission(Player::Player2, 0, 0),
+                Rejection::WrongPly {
+                    expected: 1,
+                    found: 0,
+                },
+            ),
+            (
+                submission(Player::Player2, 1, 4),
+                Rejection::Illegal(MoveError::CellOccupied),
+            ),
+        ];
+        for (i, (submission, reason)) in cases.into_iter().enumerate() {
+            let event = referee.submit(submission, SEC);
+            assert_eq!(rejection(&event), Some(reason));
+            assert_eq!(event.seq, i as u64 + 1);
+        }
+        assert_eq!(referee.ply(), 1);
+        assert!(
+            referee
+                .submit(submission(Player::Player2, 1, 0), SEC)
+                .is_accepted()
+        );
+    }
+
+    #[test]
+    fn approvals_go_stale_once_another_move_is_committed() {
+        let mut referee = Referee::new(BitboardState::new());
+        let first = Submission {
+            player: Player::Player1,
+            ply: 0,
+            mv: 3,
+        };
+        let a = referee.check(first.clone(), SEC).unwrap();
+        let b = referee.check(first, SEC).unwrap();
+        assert_eq!(a.state().ply(), Some(1));
+        assert!(referee.commit(a, SEC).is_accepted());
+        let event = referee.commit(b, SEC);
+        assert_eq!(
+            rejection(&event),
+            Some(Rejection::WrongPly {
+                expected: 1,
+                found: 0,
+            })
+        );
+        assert_eq!(referee.state().ply(), Some(1));
+    }
+
+    #[test]
+    fn late_moves_lose_on_time() {
+        let control = TimeControl::Increment {
+            base: 10 * SEC,
+            increment: SEC,
+        };
+        let mut referee =
+            Referee::new(TicTacToeState::new()).with_time_control(control, Duration::ZERO);
+        let event = referee.submit(submission(Player::Player1, 0, 4), 3 * SEC);
+        assert_eq!(
+            event.verdict,
+            Verdict::Accepted {
+                mv: TicTacToeMove { index: 4 },
+                elapsed: 3 * SEC,
+                remaining: Some(8 * SEC),
+            }
+        );
+        assert_eq!(
+            referee.game_events(&event),
+            [
+                GameEvent::MoveMade {
+                    player: Player::Player1,
+                    mv: TicTacToeMove { index: 4 },
+                    eval: None,
+                },
+                GameEvent::ClockUpdate {
+                    player: Player::Player1,
+                    remaining: 8 * SEC,
+                },
+            ]
+        );
+        assert_eq!(referee.check_time(12 * SEC), None);
+
+        let event = referee.submit(submission(Player::Player2, 1, 0), 14 * SEC);
+        assert_eq!(rejection(&event), Some(Rejection::TimeExpired));
+        assert_eq!(referee.outcome(), Some(Outcome::Win(Player::Player1)));
+        assert_eq!(
+            referee.game_events(&event),
+            [
+                GameEvent::ClockUpdate {
+                    player: Player::Player2,
+                    remaining: Duration::ZERO,
+                },
+                GameEvent::GameEnded {
+                    outcome: Some(Outcome::Win(Player::Player1)),
+                },
+            ]
+        );
+        let event = referee.submit(submission(Player::Player2, 1, 0), 14 * SEC);
+        assert_eq!(rejection(&event), Some(Rejection::GameOver));
+        assert_eq!(referee.game_events(&event), []);
+
+        let mut idle = Referee::new(TicTacToeState::new())
+            .with_time_control(TimeControl::PerMove(SEC), Duration::ZERO);
+        assert_eq!(idle.check_time(2 * SEC), Some(Player::Player1));
+        assert_eq!(idle.outcome(), Some(Outcome::Win(Player::Player2)));
+    }
+
+    #[test]
+    fn observers_hear_each_ruling() {
+        let mut referee = Referee::new(TicTacToeState::new());
+        let mut events = Vec::new();
+        for (ply, index) in [0, 3, 1, 4, 2].into_iter().enumerate() {
+            let player = referee.state().current_player();
+            let event = referee.submit_observed(
+                submission(player, ply as u32, index),
+                Some(ply as i32),
+                Duration::ZERO,
+                &mut events,
+            );
+            assert!(event.is_accepted());
+        }
+        let rejected = referee.submit_observed(
+            submission(Player::Player2, 5, 5),
+            None,
+            Duration::ZERO,
+            &mut events,
+        );
+        assert_eq!(rejection(&rejected), Some(Rejection::GameOver));
+        assert_eq!(events.len(), 6);
+        assert_eq!(
+            events[4],
+            GameEvent::MoveMade {
+                player: Player::Player1,
+                mv: TicTacToeMove { index: 2 },
+                eval: Some(4),
+            }
+        );
+        assert_eq!(
+            events[5],
+            GameEvent::GameEnded {
+                outcome: Some(Outcome::Win(Player::Player1)),
+            }
+        );
+    }
+
+    #[test]
+    fn saved_clocks_carry_on() {
+        let mut clock = GameClock::new(TimeControl::Absolute(10 * SEC));
+        clock.get_mut(Player::Player1).record_move(4 * SEC).unwrap();
+        let mut referee = Referee::new(TicTacToeState::new()).with_clock(clock, SEC);
+        let event = referee.submit(submission(Player::Player1, 0, 4), 3 * SEC);
+        assert_eq!(
+            event.verdict,
+            Verdict::Accepted {
+                mv: TicTacToeMove { index: 4 },
+                elapsed: 2 * SEC,
+                remaining: Some(4 * SEC),
+            }
+        );
+    }
+
+    struct Checksum;
+
+    impl EventSigner<TicTacToeMove> for Checksum {
+        fn sign(&self, event: &RefereeEvent<TicTacToeMove>) -> String {
+            format!("{}:{}:{}", event.seq, event.ply, event.is_accepted())
+        }
+    }
+
+    #[test]
+    fn events_are_signed_and_verified() {
+        let mut referee = Referee::new(TicTacToeState::new()).with_signer(Checksum);
+        let mut event = referee.submit(submission(Player::Player1, 0, 4), SEC);
+        assert_eq!(event.signature.as_deref(), Some("0:0:true"));
+        assert!(referee.verify(&event));
+        event.seq = 7;
+        assert!(!referee.verify(&event));
+
+        let unsigned = Referee::new(TicTacToeState::new());
+        assert!(!unsigned.verify(&event));
+        assert_eq!(
+            format!("{}", Rejection::Illegal(MoveError::CellOccupied)),
+            "illegal move: the cell is not empty"
+        );
+    }
+}
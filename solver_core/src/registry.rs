@@ -0,0 +1,196 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::dyn_game::{DynGameState, Played};
+use crate::error::ParseError;
+use crate::game::BoardGame;
+use crate::games::breakthrough::BreakthroughState;
+use crate::games::c4_3d::ScoreFourState;
+use crate::games::c4_bitboard::{BitboardState, BitboardState8x7};
+use crate::games::pentago::PentagoState;
+use crate::games::ttt::TicTacToeState;
+use crate::render::Glyphs;
+use crate::render::describe::{Threat, line_threats};
+use crate::render::svg::SvgStyle;
+use crate::selection::SelectBy;
+
+/// A game that can be listed in a `Registry` and played through
+/// `DynGameState`.
+pub trait RegisteredGame: BoardGame<Move: Send + Sync> + Send + Sync + 'static {
+    /// Short name used on the command line and in requests, e.g. `"c4"`.
+    const NAME: &'static str;
+    /// Human-readable name, e.g. `"Connect Four"`.
+    const DISPLAY_NAME: &'static str;
+    /// Written between the moves of a game record; empty for games whose
+    /// moves are single characters.
+    const MOVE_SEPARATOR: &'static str;
+    /// Cells drawn by `BoardStyle::Unicode`.
+    const SYMBOLS: Glyphs = Glyphs::DISCS;
+    /// Cells drawn by `BoardStyle::Emoji`.
+    const EMOJI: Glyphs = Glyphs::DISC_EMOJI;
+    /// How `DynGameState::render_svg` draws pieces.
+    const SVG_STYLE: SvgStyle = SvgStyle::Discs;
+    /// How many pieces in a row win, if the game is won that way;
+    /// `render_svg` highlights such a line.
+    const WIN_LENGTH: Option<usize> = None;
+    /// How `DynGameState::selection` picks moves from board cells.
+    const SELECT_BY: SelectBy = SelectBy::Cell;
+
+    /// The initial position.
+    fn initial() -> Self;
+
+    /// Writes `mv` in the game's move notation.
+    fn format_move(mv: &Self::Move) -> String;
+
+    /// Parses a move in the game's notation and checks that it is legal in
+    /// this position.
+    fn parse_move(&self, text: &str) -> Result<Self::Move, ParseError>;
+
+    /// The empty cells where a player would complete a winning line, as
+    /// `render::describe` reads them out: by default, the lines of
+    /// `WIN_LENGTH` found by `line_threats`, and none without one.
+    fn threat_cells(&self) -> Vec<Threat> {
+        Self::WIN_LENGTH.map_or_else(Vec::new, |length| {
+            line_threats(self, length, Self::SELECT_BY)
+        })
+    }
+}
+
+/// A registered game: its names, default depth, and how to start or
+/// restore a position.
+#[derive(Clone, Copy, Debug)]
+pub struct GameEntry {
+    /// Short name used on the command line and in requests, e.g. `"c4"`.
+    pub name: &'static str,
+    /// Human-readable name, e.g. `"Connect Four"`.
+    pub display_name: &'static str,
+    /// Search depth used when none is given (the initial position's
+    /// `recommended_depth`).
+    pub default_depth: u32,
+    /// Creates the initial position.
+    pub new_game: fn() -> Box<dyn DynGameState>,
+    /// Plays a game record (see `DynGameState::notation`) from the initial
+    /// position.
+    pub from_notation: fn(&str) -> Result<Box<dyn DynGameState>, ParseError>,
+}
+
+impl GameEntry {
+    /// The entry of game `G`.
+    pub fn of<G: RegisteredGame>() -> Self {
+        Self {
+            name: G::NAME,
+            display_name: G::DISPLAY_NAME,
+            default_depth: G::initial().recommended_depth(),
+            new_game: || Box::new(Played::<G>::initial()),
+            from_notation: |notation| Ok(Box::new(Played::<G>::from_notation(notation)?)),
+        }
+    }
+}
+
+/// Games looked up by name, so front ends can offer whatever games are
+/// registered instead of hard-coding them.
+#[derive(Clone, Debug, Default)]
+pub struct Registry {
+    entries: Vec<GameEntry>,
+}
+
+impl Registry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry of the games in this crate: Tic-Tac-Toe, Connect Four
+    /// (on the standard board and on 8 columns of 7 rows), Score Four,
+    /// Pentago and Breakthrough.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register::<TicTacToeState>();
+        registry.register::<BitboardState>();
+        registry.register::<BitboardState8x7>();
+        registry.register::<ScoreFourState>();
+        registry.register::<PentagoState>();
+        registry.register::<BreakthroughState>();
+        registry
+    }
+
+    /// Adds game `G`, replacing any game registered under the same name.
+    pub fn register<G: RegisteredGame>(&mut self) {
+        self.register_entry(GameEntry::of::<G>());
+    }
+
+    /// Adds `entry`, replacing any game registered under the same name.
+    pub fn register_entry(&mut self, entry: GameEntry) {
+        match self.entries.iter_mut().find(|e| e.name == entry.name) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// The game registered as `name`.
+    pub fn get(&self, name: &str) -> Option<&GameEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Every registered game, in registration order.
+    pub fn games(&self) -> &[GameEntry] {
+        &self.entries
+    }
+
+    /// The names of the registered games, in registration order.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.entries.iter().map(|e| e.name).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Outcome, Player};
+
+    #[test]
+    fn builtin_games_are_listed_and_playable_by_name() {
+        let registry = Registry::builtin();
+        assert_eq!(
+            registry.names(),
+            ["ttt", "c4", "c4-8x7", "score4", "pentago", "breakthrough"]
+        );
+        let c4 = registry.get("c4").unwrap();
+        assert_eq!(c4.display_name, "Connect Four");
+        assert_eq!(c4.default_depth, 8);
+        assert!(registry.get("chess").is_none());
+
+        let game = (c4.new_game)();
+        assert_eq!(game.legal_moves().len(), 7);
+        let game = game.play("3").unwrap().play("4").unwrap();
+        assert_eq!(game.notation(), "34");
+        assert_eq!(
+            game.render().lines().last(),
+            Some(". | . | . | X | O | . | .")
+        );
+        assert!(game.play("9").is_err());
+
+        let ttt = registry.get("ttt").unwrap();
+        let won = (ttt.from_notation)("03142").unwrap();
+        assert_eq!(won.outcome(), Some(Outcome::Win(Player::Player1)));
+        assert_eq!(won.clone().notation(), "03142");
+        assert_eq!(
+            (ttt.from_notation)("004").err(),
+            Some(ParseError::IllegalMoveAt {
+                move_number: 2,
+                error: crate::error::MoveError::CellOccupied,
+            })
+        );
+    }
+
+    #[test]
+    fn registering_a_name_again_replaces_the_game() {
+        let mut registry = Registry::builtin();
+        let mut entry = GameEntry::of::<TicTacToeState>();
+        entry.default_depth = 5;
+        registry.register_entry(entry);
+        assert_eq!(registry.games().len(), 6);
+        assert_eq!(registry.get("ttt").unwrap().default_depth, 5);
+    }
+}
@@ -0,0 +1,344 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::game::{CellContent, Outcome, Player};
+use crate::registry::RegisteredGame;
+use crate::render::{Grid, Labels};
+use crate::selection::SelectBy;
+
+/// The words `describe_in` puts together, so a description can be given
+/// in another language without repeating how it is worked out.
+///
+/// Players, rows and columns come in already named: players by the game's
+/// symbol for them, rows and columns by their labels. Each sentence ends
+/// with a full stop but no newline.
+pub trait Vocabulary {
+    /// A cell without a piece, in a list of cells.
+    fn empty_cell(&self) -> &str;
+
+    /// A player's first mention: their `symbol` and number, 1 or 2.
+    fn player(&self, symbol: &str, number: u8) -> String;
+
+    /// `items` joined as in prose: "a", "a and b", "a, b and c".
+    fn list(&self, items: &[String]) -> String;
+
+    fn empty_board(&self) -> String;
+
+    /// A column's `pieces` from the bottom, listed; `full` if no more fit.
+    fn column(&self, col: &str, pieces: &str, full: bool) -> String;
+
+    /// The empty columns, listed with `list` if there are several.
+    fn empty_columns(&self, cols: &[String]) -> String;
+
+    /// A row's cells from left to right, listed.
+    fn row(&self, row: &str, cells: &str) -> String;
+
+    fn empty_row(&self, row: &str) -> String;
+
+    fn won(&self, player: &str) -> String;
+
+    fn drawn(&self) -> String;
+
+    fn to_move(&self, player: &str) -> String;
+
+    /// A cell of a game with gravity: its column and height from 1 at the
+    /// bottom, and whether a piece can be dropped there now.
+    fn column_place(&self, col: &str, height: usize, playable: bool) -> String;
+
+    /// A cell of a game without gravity.
+    fn cell_place(&self, row: &str, col: &str) -> String;
+
+    /// `player` would complete a winning line at `place`.
+    fn threat(&self, player: &str, place: &str) -> String;
+}
+
+/// The words of `describe`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct English;
+
+impl Vocabulary for English {
+    fn empty_cell(&self) -> &str {
+        "empty"
+    }
+
+    fn player(&self, symbol: &str, number: u8) -> String {
+        format!("{symbol} (player {number})")
+    }
+
+    fn list(&self, items: &[String]) -> String {
+        match items {
+            [] => String::new(),
+            [one] => one.clone(),
+            [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+        }
+    }
+
+    fn empty_board(&self) -> String {
+        String::from("The board is empty.")
+    }
+
+    fn column(&self, col: &str, pieces: &str, full: bool) -> String {
+        let full = if full { " (full)" } else { "" };
+        format!("Column {col}: {pieces} from the bottom{full}.")
+    }
+
+    fn empty_columns(&self, cols: &[String]) -> String {
+        match cols {
+            [col] => format!("Column {col} is empty."),
+            cols => format!("Columns {} are empty.", self.list(cols)),
+        }
+    }
+
+    fn row(&self, row: &str, cells: &str) -> String {
+        format!("Row {row}: {cells}.")
+    }
+
+    fn empty_row(&self, row: &str) -> String {
+        format!("Row {row} is empty.")
+    }
+
+    fn won(&self, player: &str) -> String {
+        format!("{player} has won.")
+    }
+
+    fn drawn(&self) -> String {
+        String::from("The game is drawn.")
+    }
+
+    fn to_move(&self, player: &str) -> String {
+        format!("{player} to move.")
+    }
+
+    fn column_place(&self, col: &str, height: usize, playable: bool) -> String {
+        let now = if playable { " (playable now)" } else { "" };
+        format!("column {col}, height {height}{now}")
+    }
+
+    fn cell_place(&self, row: &str, col: &str) -> String {
+        format!("row {row}, column {col}")
+    }
+
+    fn threat(&self, player: &str, place: &str) -> String {
+        format!("{player} threatens to win at {place}.")
+    }
+}
+
+/// The discs of column `col` from the bottom, for games with gravity.
+fn stack<G: Grid + ?Sized>(grid: &G, col: usize) -> Vec<Player> {
+    (0..grid.rows())
+        .rev()
+        .map_while(|row| match grid.cell(row, col) {
+            CellContent::Piece(player) => Some(player),
+            CellContent::Empty => None,
+        })
+        .collect()
+}
+
+/// An empty cell where a player would complete a winning line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Threat {
+    pub player: Player,
+    /// The cell, as `(row, col)` with row 0 at the top.
+    pub cell: (usize, usize),
+    /// Whether the player could take the cell with their next move: always
+    /// for games that place pieces anywhere, and only on top of its column
+    /// for games with gravity.
+    pub playable: bool,
+}
+
+/// The empty cells that would complete `length` in a row for one player,
+/// found by scanning every line of the board: Player1's, then Player2's,
+/// each in board order. A cell threatened by both players is listed for
+/// each. `select_by` tells whether pieces fall down their column, for
+/// `Threat::playable`.
+pub fn line_threats<G: Grid + ?Sized>(grid: &G, length: usize, select_by: SelectBy) -> Vec<Threat> {
+    let (rows, cols) = (grid.rows() as isize, grid.cols() as isize);
+    let length = length.max(2) as isize;
+    let mut found = Vec::new();
+    for row in 0..rows {
+        for col in 0..cols {
+            for (dr, dc) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+                let (end_row, end_col) = (row + (length - 1) * dr, col + (length - 1) * dc);
+                if !(0..rows).contains(&end_row) || !(0..cols).contains(&end_col) {
+                    continue;
+                }
+                let mut owner = None;
+                let mut empty = Vec::new();
+                let mut mixed = false;
+                for i in 0..length {
+                    let cell = ((row + i * dr) as usize, (col + i * dc) as usize);
+                    match grid.cell(cell.0, cell.1) {
+                        CellContent::Empty => empty.push(cell),
+                        CellContent::Piece(player) => {
+                            mixed |= owner.is_some_and(|owner| owner != player);
+                            owner = Some(player);
+                        }
+                    }
+                }
+                if let (Some(player), [cell], false) = (owner, empty.as_slice(), mixed)
+                    && !found.contains(&(player, *cell))
+                {
+                    found.push((player, *cell));
+                }
+            }
+        }
+    }
+    found.sort_by_key(|&(player, cell)| (player == Player::Player2, cell));
+    found
+        .into_iter()
+        .map(|(player, (row, col))| Threat {
+            player,
+            cell: (row, col),
+            playable: select_by != SelectBy::Column
+                || row + 1 == grid.rows()
+                || grid.cell(row + 1, col) != CellContent::Empty,
+        })
+        .collect()
+}
+
+/// Describes `state` in prose, one sentence per line, for screen readers.
+///
+/// The pieces are listed row by row, or, where moves pick a column (see
+/// `SelectBy::Column`), column by column from the bottom. Then comes whose
+/// turn it is or how the game ended and every empty cell where a player
+/// would complete a winning line (see `RegisteredGame::threat_cells`).
+/// Players are named by the game's `SYMBOLS`. Rows and columns
+/// are named with `labels`, as on a board drawn with `Layout::labelled`;
+/// with gravity, cells are named by column and height instead, counting
+/// from 1 at the bottom.
+pub fn describe<G: RegisteredGame>(state: &G, labels: Labels) -> String {
+    describe_in(state, labels, &English)
+}
+
+/// `describe` in the words of `words`.
+pub fn describe_in<G: RegisteredGame>(state: &G, labels: Labels, words: &dyn Vocabulary) -> String {
+    let symbols = G::SYMBOLS;
+    let piece = |player| symbols.get(CellContent::Piece(player));
+    let full_name = |player| {
+        let number = match player {
+            Player::Player1 => 1,
+            Player::Player2 => 2,
+        };
+        words.player(piece(player), number)
+    };
+    let mut lines = Vec::new();
+    let (rows, cols) = (Grid::rows(state), Grid::cols(state));
+    let occupied = (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (row, col)))
+        .any(|(row, col)| Grid::cell(state, row, col) != CellContent::Empty);
+    if !occupied {
+        lines.push(words.empty_board());
+    } else if G::SELECT_BY == SelectBy::Column {
+        let mut empty = Vec::new();
+        for col in 0..cols {
+            let discs = stack(state, col);
+            if discs.is_empty() {
+                empty.push(labels.label(col));
+                continue;
+            }
+            let names: Vec<&str> = discs.iter().map(|&player| piece(player)).collect();
+            let label = labels.label(col);
+            lines.push(words.column(&label, &names.join(", "), discs.len() == rows));
+        }
+        if !empty.is_empty() {
+            lines.push(words.empty_columns(&empty));
+        }
+    } else {
+        for row in 0..rows {
+            let cells: Vec<CellContent> =
+                (0..cols).map(|col| Grid::cell(state, row, col)).collect();
+            let label = labels.label(row);
+            if cells.iter().all(|&cell| cell == CellContent::Empty) {
+                lines.push(words.empty_row(&label));
+            } else {
+                let names: Vec<&str> = cells
+                    .into_iter()
+                    .map(|cell| match cell {
+                        CellContent::Empty => words.empty_cell(),
+                        CellContent::Piece(player) => piece(player),
+                    })
+                    .collect();
+                lines.push(words.row(&label, &names.join(", ")));
+            }
+        }
+    }
+
+    match state.outcome() {
+        Some(Outcome::Win(player)) => lines.push(words.won(&full_name(player))),
+        Some(Outcome::Draw) => lines.push(words.drawn()),
+        None => {
+            lines.push(words.to_move(&full_name(state.current_player())));
+            for threat in state.threat_cells() {
+                let (row, col) = threat.cell;
+                let place = if G::SELECT_BY == SelectBy::Column {
+                    words.column_place(&labels.label(col), rows - row, threat.playable)
+                } else {
+                    words.cell_place(&labels.label(row), &labels.label(col))
+                };
+                lines.push(words.threat(piece(threat.player), &place));
+            }
+        }
+    }
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+
+    #[test]
+    fn connect_four_is_described_by_column() {
+        let state = BitboardState::new();
+        assert_eq!(
+            describe(&state, Labels::Numbers),
+            "The board is empty.\n● (player 1) to move.\n"
+        );
+
+        // Player1 has three along the bottom, open at both ends.
+        let state = BitboardState::from_move_sequence("112233").unwrap();
+        let text = describe(&state, Labels::Numbers);
+        assert_eq!(
+            text,
+            "Column 1: ●, ○ from the bottom.\n\
+             Column 2: ●, ○ from the bottom.\n\
+             Column 3: ●, ○ from the bottom.\n\
+             Columns 0, 4, 5 and 6 are empty.\n\
+             ● (player 1) to move.\n\
+             ● threatens to win at column 0, height 1 (playable now).\n\
+             ● threatens to win at column 4, height 1 (playable now).\n\
+             ○ threatens to win at column 0, height 2.\n\
+             ○ threatens to win at column 4, height 2.\n"
+        );
+        let won = BitboardState::from_move_sequence("0101010").unwrap();
+        let text = describe(&won, Labels::Letters);
+        assert!(text.starts_with(
+            "Column a: ●, ●, ●, ● from the bottom.\nColumn b: ○, ○, ○ from the bottom.\n"
+        ));
+        assert!(text.ends_with("● (player 1) has won.\n"), "{text}");
+    }
+
+    #[test]
+    fn tic_tac_toe_is_described_by_row() {
+        let state = TicTacToeState::from_str("XO..X....", Player::Player2).unwrap();
+        assert_eq!(
+            describe(&state, Labels::Letters),
+            "Row a: ✕, ◯, empty.\n\
+             Row b: empty, ✕, empty.\n\
+             Row c is empty.\n\
+             ◯ (player 2) to move.\n\
+             ✕ threatens to win at row c, column c.\n"
+        );
+        let drawn = TicTacToeState::from_str("XOXXOOOXX", Player::Player2).unwrap();
+        let text = describe(&drawn, Labels::Numbers);
+        assert!(text.ends_with("The game is drawn.\n"));
+        assert_eq!(line_threats(&drawn, 3, SelectBy::Cell), []);
+    }
+}
@@ -0,0 +1,387 @@
+pub mod describe;
+pub mod svg;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+
+use crate::dyn_game::DynGameState;
+use crate::game::{BoardGame, CellContent, Player};
+use crate::selection::SelectBy;
+
+/// A board that can be drawn: every `BoardGame`, and `DynGameState`s.
+pub trait Grid {
+    /// Number of rows.
+    fn rows(&self) -> usize;
+
+    /// Number of columns.
+    fn cols(&self) -> usize;
+
+    /// The content of the cell at (`row`, `col`).
+    fn cell(&self, row: usize, col: usize) -> CellContent;
+}
+
+impl<G: BoardGame> Grid for G {
+    fn rows(&self) -> usize {
+        BoardGame::rows(self)
+    }
+
+    fn cols(&self) -> usize {
+        BoardGame::cols(self)
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        BoardGame::cell(self, row, col)
+    }
+}
+
+impl Grid for dyn DynGameState {
+    fn rows(&self) -> usize {
+        DynGameState::rows(self)
+    }
+
+    fn cols(&self) -> usize {
+        DynGameState::cols(self)
+    }
+
+    fn cell(&self, row: usize, col: usize) -> CellContent {
+        DynGameState::cell(self, row, col)
+    }
+}
+
+/// What each kind of cell is drawn as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Glyphs {
+    pub player1: &'static str,
+    pub player2: &'static str,
+    pub empty: &'static str,
+}
+
+impl Glyphs {
+    /// `X`, `O` and `.`.
+    pub const ASCII: Glyphs = Glyphs {
+        player1: "X",
+        player2: "O",
+        empty: ".",
+    };
+    /// Discs: `●`, `○` and `·`.
+    pub const DISCS: Glyphs = Glyphs {
+        player1: "●",
+        player2: "○",
+        empty: "·",
+    };
+    /// Pen marks: `✕`, `◯` and `·`.
+    pub const MARKS: Glyphs = Glyphs {
+        player1: "✕",
+        player2: "◯",
+        empty: "·",
+    };
+    /// Red and yellow discs in white holes.
+    pub const DISC_EMOJI: Glyphs = Glyphs {
+        player1: "🔴",
+        player2: "🟡",
+        empty: "⚪",
+    };
+    /// Crosses and noughts on white squares.
+    pub const MARK_EMOJI: Glyphs = Glyphs {
+        player1: "❌",
+        player2: "⭕",
+        empty: "⬜",
+    };
+
+    /// The glyph of `cell`.
+    pub fn get(&self, cell: CellContent) -> &'static str {
+        match cell {
+            CellContent::Empty => self.empty,
+            CellContent::Piece(Player::Player1) => self.player1,
+            CellContent::Piece(Player::Player2) => self.player2,
+        }
+    }
+}
+
+/// How `DynGameState::render_as` draws a board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BoardStyle {
+    /// `render_board`: plain ASCII, aligned in any monospaced font.
+    #[default]
+    Ascii,
+    /// `unicode_board` with the game's symbols. Box-drawing characters and
+    /// some symbols are double width in East Asian fonts; use `Ascii` where
+    /// that matters.
+    Unicode,
+    /// `emoji_board` with the game's emoji, for chat messages.
+    Emoji,
+}
+
+/// How rows or columns are labelled around a board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Labels {
+    /// `0`, `1`, `2`, ...: the indices moves are given by.
+    Numbers,
+    /// `a`, `b`, `c`, ..., then numbers past `z`.
+    Letters,
+}
+
+impl Labels {
+    /// The label of row or column `index`.
+    pub fn label(self, index: usize) -> String {
+        match self {
+            Labels::Letters if index < 26 => char::from(b'a' + index as u8).to_string(),
+            _ => index.to_string(),
+        }
+    }
+}
+
+/// Coordinate labels and orientation for a drawn board. The default is a
+/// bare board with row 0 (see `BoardGame::cell`) at the top.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Layout {
+    /// Labels under the columns.
+    pub columns: Option<Labels>,
+    /// Labels left of the rows.
+    pub rows: Option<Labels>,
+    /// Draws row 0 at the bottom instead, with the rows keeping their
+    /// labels.
+    pub flipped: bool,
+}
+
+impl Layout {
+    /// `labels` under the columns and, unless moves are picked by column
+    /// (as in Connect Four, where rows never need naming), left of the rows.
+    pub fn labelled(labels: Labels, select_by: SelectBy) -> Self {
+        Self {
+            columns: Some(labels),
+            rows: (select_by == SelectBy::Cell).then_some(labels),
+            flipped: false,
+        }
+    }
+
+    /// The same layout, with row 0 at the bottom.
+    pub fn flip(self) -> Self {
+        Self {
+            flipped: true,
+            ..self
+        }
+    }
+}
+
+/// The lines of a drawn board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// Between the cells of a row.
+    pub column: &'static str,
+    /// A segment of the line between rows, one per column; empty for no
+    /// such lines.
+    pub row: &'static str,
+    /// Where the segments between rows meet.
+    pub cross: &'static str,
+    /// How many terminal columns a cell takes.
+    pub cell_width: usize,
+}
+
+impl Frame {
+    /// `X | O`, rows separated by `---+---`.
+    pub const ASCII: Frame = Frame {
+        column: " | ",
+        row: "---",
+        cross: "+",
+        cell_width: 1,
+    };
+    /// `ASCII` with box-drawing characters.
+    pub const UNICODE: Frame = Frame {
+        column: " │ ",
+        row: "───",
+        cross: "┼",
+        cell_width: 1,
+    };
+    /// Double-width cells side by side, without lines.
+    pub const EMOJI: Frame = Frame {
+        column: "",
+        row: "",
+        cross: "",
+        cell_width: 2,
+    };
+}
+
+/// Draws a `rows` by `cols` board with the cells drawn by `cell` (given
+/// row and column), in `frame`, labelled and oriented as `layout` says.
+pub fn draw_grid(
+    rows: usize,
+    cols: usize,
+    cell: impl Fn(usize, usize) -> String,
+    frame: &Frame,
+    layout: &Layout,
+) -> String {
+    let row_labels: Vec<String> = layout.rows.map_or_else(Vec::new, |labels| {
+        (0..rows).map(|r| labels.label(r)).collect()
+    });
+    let margin = row_labels
+        .iter()
+        .map(|label| label.chars().count() + 1)
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    let order: Vec<usize> = if layout.flipped {
+        (0..rows).rev().collect()
+    } else {
+        (0..rows).collect()
+    };
+    for (i, &r) in order.iter().enumerate() {
+        if i > 0 && !frame.row.is_empty() {
+            let separator: Vec<&str> = (0..cols).map(|_| frame.row).collect();
+            let _ = writeln!(out, "{:margin$}{}", "", separator.join(frame.cross));
+        }
+        if let Some(label) = row_labels.get(r) {
+            let _ = write!(out, "{label:>width$} ", width = margin - 1);
+        }
+        let cells: Vec<String> = (0..cols).map(|c| cell(r, c)).collect();
+        out.push_str(&cells.join(frame.column));
+        out.push('\n');
+    }
+    if let Some(labels) = layout.columns {
+        let step = frame.cell_width + frame.column.chars().count();
+        let mut line = String::new();
+        let mut width = 0;
+        for c in 0..cols {
+            let label = labels.label(c);
+            let start = (margin + c * step).max(width + usize::from(c > 0));
+            let _ = write!(line, "{:pad$}{label}", "", pad = start - width);
+            width = start + label.chars().count();
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Draws `grid` with `glyphs` in `frame`, as `layout` says.
+fn grid_lines<G: Grid + ?Sized>(
+    grid: &G,
+    glyphs: &Glyphs,
+    frame: &Frame,
+    layout: &Layout,
+) -> String {
+    draw_grid(
+        grid.rows(),
+        grid.cols(),
+        |r, c| glyphs.get(grid.cell(r, c)).to_string(),
+        frame,
+        layout,
+    )
+}
+
+/// Renders a board as text: `X`, `O` and `.` cells separated by ` | `,
+/// rows by `---+---...`.
+pub fn render_board<G: Grid + ?Sized>(grid: &G) -> String {
+    render_board_with(grid, &Layout::default())
+}
+
+/// `render_board` with the labels and orientation of `layout`.
+pub fn render_board_with<G: Grid + ?Sized>(grid: &G, layout: &Layout) -> String {
+    grid_lines(grid, &Glyphs::ASCII, &Frame::ASCII, layout)
+}
+
+/// Renders a board in the layout of `render_board`, with `glyphs` for the
+/// cells and box-drawing lines between them.
+pub fn unicode_board<G: Grid + ?Sized>(grid: &G, glyphs: &Glyphs) -> String {
+    unicode_board_with(grid, glyphs, &Layout::default())
+}
+
+/// `unicode_board` with the labels and orientation of `layout`.
+pub fn unicode_board_with<G: Grid + ?Sized>(grid: &G, glyphs: &Glyphs, layout: &Layout) -> String {
+    grid_lines(grid, glyphs, &Frame::UNICODE, layout)
+}
+
+/// Renders a board as rows of `glyphs` without separators, e.g. for chat
+/// messages.
+///
+/// The built-in emoji all have the emoji presentation by default, so they
+/// are drawn two columns wide without variation selectors. Where that
+/// can't be relied on, `Glyphs::ASCII` gives the same layout in plain
+/// text.
+pub fn emoji_board<G: Grid + ?Sized>(grid: &G, glyphs: &Glyphs) -> String {
+    emoji_board_with(grid, glyphs, &Layout::default())
+}
+
+/// `emoji_board` with the labels and orientation of `layout`; column
+/// labels assume two-column-wide glyphs.
+pub fn emoji_board_with<G: Grid + ?Sized>(grid: &G, glyphs: &Glyphs, layout: &Layout) -> String {
+    grid_lines(grid, glyphs, &Frame::EMOJI, layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+
+    #[test]
+    fn boards_render_in_every_style() {
+        let ttt = TicTacToeState::from_str("XO.......", Player::Player1).unwrap();
+        assert_eq!(
+            render_board(&ttt),
+            "X | O | .\n---+---+---\n. | . | .\n---+---+---\n. | . | .\n"
+        );
+        assert_eq!(
+            unicode_board(&ttt, &Glyphs::MARKS)
+                .lines()
+                .take(2)
+                .collect::<Vec<_>>(),
+            ["✕ │ ◯ │ ·", "───┼───┼───"]
+        );
+        assert_eq!(
+            emoji_board(&ttt, &Glyphs::MARK_EMOJI),
+            "❌⭕⬜\n⬜⬜⬜\n⬜⬜⬜\n"
+        );
+
+        let c4 = BitboardState::from_move_sequence("34").unwrap();
+        let emoji = emoji_board(&c4, &Glyphs::DISC_EMOJI);
+        assert_eq!(emoji.lines().count(), 6);
+        assert_eq!(emoji.lines().last(), Some("⚪⚪⚪🔴🟡⚪⚪"));
+        assert_eq!(
+            emoji_board(&c4, &Glyphs::ASCII).lines().last(),
+            Some("...XO..")
+        );
+    }
+
+    #[test]
+    fn boards_are_labelled_and_flipped() {
+        let ttt = TicTacToeState::from_str("XO.......", Player::Player1).unwrap();
+        let layout = Layout::labelled(Labels::Numbers, SelectBy::Cell);
+        assert_eq!(
+            render_board_with(&ttt, &layout),
+            "0 X | O | .\n  ---+---+---\n1 . | . | .\n  ---+---+---\n2 . | . | .\n  0   1   2\n"
+        );
+        let letters = Layout::labelled(Labels::Letters, SelectBy::Cell).flip();
+        assert_eq!(
+            render_board_with(&ttt, &letters)
+                .lines()
+                .rev()
+                .take(2)
+                .collect::<Vec<_>>(),
+            ["  a   b   c", "a X | O | ."]
+        );
+
+        let c4 = BitboardState::from_move_sequence("34").unwrap();
+        let columns = Layout::labelled(Labels::Numbers, SelectBy::Column);
+        let board = render_board_with(&c4, &columns);
+        assert_eq!(
+            board.lines().rev().take(2).collect::<Vec<_>>(),
+            ["0   1   2   3   4   5   6", ". | . | . | X | O | . | ."]
+        );
+        // Upside down, the first pieces are at the top.
+        let flipped = render_board_with(&c4, &columns.flip());
+        assert_eq!(flipped.lines().next(), Some(". | . | . | X | O | . | ."));
+        assert_eq!(
+            emoji_board_with(&c4, &Glyphs::DISC_EMOJI, &columns)
+                .lines()
+                .last(),
+            Some("0 1 2 3 4 5 6")
+        );
+        assert_eq!(
+            render_board_with(&c4, &Layout::default()),
+            render_board(&c4)
+        );
+        assert_eq!(Labels::Letters.label(27), "27");
+    }
+}
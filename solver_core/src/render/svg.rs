@@ -0,0 +1,339 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+
+use crate::game::{CellContent, Player};
+use crate::render::Grid;
+
+/// How `SvgBoard` draws pieces.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SvgStyle {
+    /// Red and yellow discs in the holes of a blue frame, as in Connect
+    /// Four.
+    #[default]
+    Discs,
+    /// Crosses and noughts on a ruled grid, as in Tic-Tac-Toe.
+    Marks,
+}
+
+const FRAME: &str = "#1d4e9e";
+const HOLE: &str = "#ffffff";
+const PLAYER1_DISC: &str = "#d62828";
+const PLAYER2_DISC: &str = "#f7c948";
+const PAPER: &str = "#ffffff";
+const RULE: &str = "#333333";
+const PLAYER1_MARK: &str = "#1d4e9e";
+const PLAYER2_MARK: &str = "#c2410c";
+const LAST_MOVE: &str = "#fff1a8";
+const WIN_LINE: &str = "#2a9d4a";
+
+/// An SVG picture of a position, with the last move and a winning line
+/// highlighted.
+///
+/// ```
+/// use solver_core::games::c4_bitboard::BitboardState;
+/// use solver_core::render::svg::{SvgBoard, winning_line};
+///
+/// let state = BitboardState::from_move_sequence("0101010").unwrap();
+/// let svg = SvgBoard::new()
+///     .last_move((2, 0))
+///     .winning_line(winning_line(&state, 4))
+///     .render(&state);
+/// assert!(svg.starts_with("<svg"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SvgBoard {
+    style: SvgStyle,
+    cell_size: u32,
+    last_move: Option<(usize, usize)>,
+    winning_line: Vec<(usize, usize)>,
+}
+
+impl Default for SvgBoard {
+    fn default() -> Self {
+        Self {
+            style: SvgStyle::default(),
+            cell_size: 60,
+            last_move: None,
+            winning_line: Vec::new(),
+        }
+    }
+}
+
+impl SvgBoard {
+    /// Discs, 60 pixels per cell, nothing highlighted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how pieces are drawn.
+    pub fn style(mut self, style: SvgStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets the width and height of a cell, in pixels.
+    pub fn cell_size(mut self, pixels: u32) -> Self {
+        self.cell_size = pixels.max(1);
+        self
+    }
+
+    /// Highlights the cell (`row`, `col`) as the last move.
+    pub fn last_move(mut self, cell: (usize, usize)) -> Self {
+        self.last_move = Some(cell);
+        self
+    }
+
+    /// Draws a line through `cells` (e.g. from `winning_line`), from the
+    /// first to the last; fewer than two cells draw nothing.
+    pub fn winning_line(mut self, cells: Vec<(usize, usize)>) -> Self {
+        self.winning_line = cells;
+        self
+    }
+
+    /// The width and height of the picture of `grid`, in pixels.
+    pub fn size<G: Grid + ?Sized>(&self, grid: &G) -> (u32, u32) {
+        (
+            grid.cols() as u32 * self.cell_size,
+            grid.rows() as u32 * self.cell_size,
+        )
+    }
+
+    /// The centre of cell (`row`, `col`).
+    fn centre(&self, (row, col): (usize, usize)) -> (f64, f64) {
+        let size = f64::from(self.cell_size);
+        ((col as f64 + 0.5) * size, (row as f64 + 0.5) * size)
+    }
+
+    /// Draws `grid` as a standalone SVG document.
+    pub fn render<G: Grid + ?Sized>(&self, grid: &G) -> String {
+        let (width, height) = self.size(grid);
+        let size = f64::from(self.cell_size);
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        );
+        let background = match self.style {
+            SvgStyle::Discs => FRAME,
+            SvgStyle::Marks => PAPER,
+        };
+        let _ = write!(
+            out,
+            r#"<rect width="{width}" height="{height}" fill="{background}"/>"#
+        );
+        if let (Some((row, col)), SvgStyle::Marks) = (self.last_move, self.style) {
+            let _ = write!(
+                out,
+                r#"<rect x="{}" y="{}" width="{size}" height="{size}" fill="{LAST_MOVE}"/>"#,
+                col as f64 * size,
+                row as f64 * size
+            );
+        }
+        if self.style == SvgStyle::Marks {
+            for col in 1..grid.cols() {
+                let x = col as f64 * size;
+                let _ = write!(
+                    out,
+                    r#"<line x1="{x}" y1="0" x2="{x}" y2="{height}" stroke="{RULE}" stroke-width="2"/>"#
+                );
+            }
+            for row in 1..grid.rows() {
+                let y = row as f64 * size;
+                let _ = write!(
+                    out,
+                    r#"<line x1="0" y1="{y}" x2="{width}" y2="{y}" stroke="{RULE}" stroke-width="2"/>"#
+                );
+            }
+        }
+        for row in 0..grid.rows() {
+            for col in 0..grid.cols() {
+                let cell = grid.cell(row, col);
+                let last = self.last_move == Some((row, col));
+                match self.style {
+                    SvgStyle::Discs => self.disc(&mut out, (row, col), cell, last),
+                    SvgStyle::Marks => self.mark(&mut out, (row, col), cell),
+                }
+            }
+        }
+        if let [first, .., last] = self.winning_line[..] {
+            let (x1, y1) = self.centre(first);
+            let (x2, y2) = self.centre(last);
+            let _ = write!(
+                out,
+                r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{WIN_LINE}" stroke-width="{}" stroke-linecap="round" opacity="0.8"/>"#,
+                size * 0.15
+            );
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+
+    fn disc(&self, out: &mut String, cell: (usize, usize), content: CellContent, last: bool) {
+        let (cx, cy) = self.centre(cell);
+        let r = f64::from(self.cell_size) * 0.4;
+        let fill = match content {
+            CellContent::Empty => HOLE,
+            CellContent::Piece(Player::Player1) => PLAYER1_DISC,
+            CellContent::Piece(Player::Player2) => PLAYER2_DISC,
+        };
+        let _ = write!(out, r#"<circle cx="{cx}" cy="{cy}" r="{r}" fill="{fill}""#);
+        if last {
+            let _ = write!(
+                out,
+                r#" stroke="{LAST_MOVE}" stroke-width="{}""#,
+                f64::from(self.cell_size) * 0.08
+            );
+        }
+        out.push_str("/>");
+    }
+
+    fn mark(&self, out: &mut String, cell: (usize, usize), content: CellContent) {
+        let (cx, cy) = self.centre(cell);
+        let d = f64::from(self.cell_size) * 0.3;
+        let width = f64::from(self.cell_size) * 0.1;
+        match content {
+            CellContent::Empty => {}
+            CellContent::Piece(Player::Player1) => {
+                let _ = write!(
+                    out,
+                    r#"<path d="M{} {}L{} {}M{} {}L{} {}" stroke="{PLAYER1_MARK}" stroke-width="{width}" stroke-linecap="round"/>"#,
+                    cx - d,
+                    cy - d,
+                    cx + d,
+                    cy + d,
+                    cx + d,
+                    cy - d,
+                    cx - d,
+                    cy + d
+                );
+            }
+            CellContent::Piece(Player::Player2) => {
+                let _ = write!(
+                    out,
+                    r#"<circle cx="{cx}" cy="{cy}" r="{d}" fill="none" stroke="{PLAYER2_MARK}" stroke-width="{width}"/>"#
+                );
+            }
+        }
+    }
+
+    /// Draws `grid` as a PNG image.
+    #[cfg(feature = "png")]
+    pub fn render_png<G: Grid + ?Sized>(&self, grid: &G) -> Result<Vec<u8>, String> {
+        use alloc::string::ToString;
+
+        let (width, height) = self.size(grid);
+        let tree = resvg::usvg::Tree::from_str(&self.render(grid), &Default::default())
+            .map_err(|e| e.to_string())?;
+        let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height)
+            .ok_or_else(|| "the board has no cells".to_string())?;
+        resvg::render(
+            &tree,
+            resvg::tiny_skia::Transform::default(),
+            &mut pixmap.as_mut(),
+        );
+        pixmap.encode_png().map_err(|e| e.to_string())
+    }
+}
+
+/// The cells of a line of at least `length` pieces of one player, along a
+/// row, column or diagonal of `grid`, in order; empty if there is none.
+pub fn winning_line<G: Grid + ?Sized>(grid: &G, length: usize) -> Vec<(usize, usize)> {
+    let (rows, cols) = (grid.rows() as isize, grid.cols() as isize);
+    let length = length.max(1) as isize;
+    for row in 0..rows {
+        for col in 0..cols {
+            let CellContent::Piece(player) = grid.cell(row as usize, col as usize) else {
+                continue;
+            };
+            for (dr, dc) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+                let line: Vec<(usize, usize)> = (0..length)
+                    .map(|i| (row + i * dr, col + i * dc))
+                    .take_while(|&(r, c)| {
+                        (0..rows).contains(&r)
+                            && (0..cols).contains(&c)
+                            && grid.cell(r as usize, c as usize) == CellContent::Piece(player)
+                    })
+                    .map(|(r, c)| (r as usize, c as usize))
+                    .collect();
+                if line.len() == length as usize {
+                    return line;
+                }
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// The first cell (in row-major order) that differs between `before` and
+/// `after`, e.g. the cell of the move between two positions.
+pub fn changed_cell<A: Grid + ?Sized, B: Grid + ?Sized>(
+    before: &A,
+    after: &B,
+) -> Option<(usize, usize)> {
+    (0..after.rows())
+        .flat_map(|row| (0..after.cols()).map(move |col| (row, col)))
+        .find(|&(row, col)| before.cell(row, col) != after.cell(row, col))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+
+    #[test]
+    fn winning_lines_and_last_moves_are_found() {
+        let before = BitboardState::from_move_sequence("010101").unwrap();
+        let after = before.apply_move(&0);
+        assert_eq!(winning_line(&before, 4), []);
+        assert_eq!(winning_line(&after, 4), [(2, 0), (3, 0), (4, 0), (5, 0)]);
+        assert_eq!(changed_cell(&before, &after), Some((2, 0)));
+
+        let ttt = TicTacToeState::from_str("X.O.XO..X", Player::Player2).unwrap();
+        assert_eq!(winning_line(&ttt, 3), [(0, 0), (1, 1), (2, 2)]);
+        assert_eq!(winning_line(&ttt, 4), []);
+    }
+
+    #[test]
+    fn svg_draws_every_piece_and_the_highlights() {
+        let state = BitboardState::from_move_sequence("0101010").unwrap();
+        let svg = SvgBoard::new()
+            .cell_size(10)
+            .last_move((2, 0))
+            .winning_line(winning_line(&state, 4))
+            .render(&state);
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="70" height="60""#)
+        );
+        assert_eq!(svg.matches("<circle").count(), 42);
+        assert_eq!(svg.matches(PLAYER1_DISC).count(), 4);
+        assert_eq!(svg.matches(PLAYER2_DISC).count(), 3);
+        assert_eq!(svg.matches(LAST_MOVE).count(), 1);
+        assert!(svg.contains(r##"<line x1="5" y1="25" x2="5" y2="55" stroke="#2a9d4a""##));
+
+        let ttt = TicTacToeState::from_str("X...O....", Player::Player1).unwrap();
+        let svg = SvgBoard::new()
+            .style(SvgStyle::Marks)
+            .last_move((1, 1))
+            .render(&ttt);
+        assert_eq!(svg.matches("<path").count(), 1);
+        assert_eq!(svg.matches("<circle").count(), 1);
+        assert_eq!(svg.matches(r##"stroke="#333333""##).count(), 4);
+        assert!(svg.contains(r##"<rect x="60" y="60" width="60" height="60" fill="#fff1a8"/>"##));
+        assert!(!svg.contains(WIN_LINE));
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png_has_the_board_size() {
+        let png = SvgBoard::new()
+            .cell_size(20)
+            .render_png(&BitboardState::new())
+            .unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+        // The IHDR chunk holds the width and height.
+        assert_eq!(png[16..24], [0, 0, 0, 140, 0, 0, 0, 120]);
+    }
+}
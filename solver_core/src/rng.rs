@@ -0,0 +1,203 @@
+use crate::agent::{Agent, Decision};
+use crate::game::GameState;
+
+/// A source of random bits, with the draws every randomized component in
+/// the crate needs built on top.
+///
+/// Components take any `Rng` (defaulting to `SplitMix64`) or a seed for
+/// one, never a global or OS source, so a run is reproducible from its
+/// seed. With the `rand` feature, `RandCore` adapts generators from the
+/// `rand` ecosystem.
+pub trait Rng {
+    /// Returns the next 64 random bits.
+    fn next_u64(&mut self) -> u64;
+
+    /// Returns a uniformly distributed value in `0..n`.
+    ///
+    /// Panics if `n` is 0.
+    fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "below(0) has no valid result");
+        // Reject the top partial range so every residue is equally likely.
+        let zone = u64::MAX - (u64::MAX % n);
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+
+    /// Returns a uniformly distributed value in `0.0..1.0`.
+    fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill an f64's mantissa exactly.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a uniformly chosen element of `items`, or `None` if empty.
+    fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.below(items.len() as u64) as usize)
+        }
+    }
+}
+
+impl<R: Rng + ?Sized> Rng for &mut R {
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+}
+
+/// A small seedable pseudo-random generator (SplitMix64), the crate's
+/// default `Rng`.
+///
+/// The output depends only on the seed and uses wrapping `u64` arithmetic,
+/// so it is identical on every platform.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// Creates a generator from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// A generator for one of several components sharing a run's `seed`,
+    /// told apart by `stream`: the same pair always gives the same
+    /// generator, and different streams give unrelated ones.
+    pub fn for_stream(seed: u64, stream: u64) -> Self {
+        Self::new(SplitMix64::new(seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15)).next_u64())
+    }
+
+    /// The generator's state: `SplitMix64::new(state)` carries on where
+    /// this one is, e.g. after saving it.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+}
+
+impl Rng for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.state)
+    }
+}
+
+/// The SplitMix64 finalizer: a bijection that spreads every input bit over
+/// the output, e.g. for hashing bitboards into keys.
+pub fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Any `rand_core` generator as an `Rng`, e.g. a ChaCha generator for
+/// runs that need statistically stronger randomness.
+#[cfg(feature = "rand")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RandCore<R>(pub R);
+
+#[cfg(feature = "rand")]
+impl<R: rand_core::RngCore> Rng for RandCore<R> {
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+}
+
+/// An agent that plays a uniformly random legal move.
+///
+/// Two agents created with the same seed play the same moves.
+#[derive(Clone, Debug)]
+pub struct RandomAgent<R = SplitMix64> {
+    rng: R,
+}
+
+impl RandomAgent {
+    /// Creates a random agent whose choices are determined by `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self::with_rng(SplitMix64::new(seed))
+    }
+}
+
+impl<R: Rng> RandomAgent<R> {
+    /// Creates a random agent drawing its choices from `rng`.
+    pub fn with_rng(rng: R) -> Self {
+        Self { rng }
+    }
+}
+
+impl<G: GameState, R: Rng> Agent<G> for RandomAgent<R> {
+    fn select_move(&mut self, state: &G) -> Option<Decision<G::Move>> {
+        if state.is_terminal() {
+            return None;
+        }
+        let moves = state.legal_moves();
+        self.rng.choose(&moves).map(|mv| Decision {
+            mv: mv.clone(),
+            value: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::play_game;
+    use crate::games::c4_bitboard::BitboardState;
+
+    #[test]
+    fn splitmix_matches_reference_sequence() {
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+        assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+    }
+
+    #[test]
+    fn generators_resume_from_their_state() {
+        let mut rng = SplitMix64::new(11);
+        rng.next_u64();
+        let mut resumed = SplitMix64::new(rng.state());
+        assert_eq!(resumed.next_u64(), rng.next_u64());
+    }
+
+    #[test]
+    fn below_stays_in_range() {
+        let mut rng = SplitMix64::new(7);
+        assert!((0..1000).all(|_| rng.below(7) < 7));
+        assert_eq!(rng.choose::<u8>(&[]), None);
+    }
+
+    #[test]
+    fn streams_are_reproducible_and_distinct() {
+        assert_eq!(
+            SplitMix64::for_stream(5, 1).next_u64(),
+            SplitMix64::for_stream(5, 1).next_u64()
+        );
+        assert_ne!(
+            SplitMix64::for_stream(5, 1).next_u64(),
+            SplitMix64::for_stream(5, 2).next_u64()
+        );
+        // Generators can be lent out without giving them up.
+        let mut rng = SplitMix64::new(3);
+        let mut copy = rng.clone();
+        let mut agent = RandomAgent::with_rng(&mut rng);
+        let start = BitboardState::new();
+        let first = agent.select_move(&start).unwrap().mv;
+        let moves = start.legal_moves();
+        assert_eq!(first, moves[copy.below(moves.len() as u64) as usize]);
+        assert_eq!(rng.next_u64(), copy.next_u64());
+    }
+
+    #[test]
+    fn c4_random_games_are_reproducible_from_seed() {
+        let start = BitboardState::new();
+        let (_, a) = play_game(&start, &mut RandomAgent::new(1), &mut RandomAgent::new(2));
+        let (_, b) = play_game(&start, &mut RandomAgent::new(1), &mut RandomAgent::new(2));
+        let (_, c) = play_game(&start, &mut RandomAgent::new(3), &mut RandomAgent::new(2));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+}
@@ -0,0 +1,187 @@
+use alloc::vec::Vec;
+
+use crate::game::BoardGame;
+use crate::render::svg::changed_cell;
+
+/// How a board cell picks a move in a `Selection`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectBy {
+    /// Each move is played on its own cell, as in Tic-Tac-Toe.
+    #[default]
+    Cell,
+    /// Moves are dropped into columns, as in Connect Four: any cell of a
+    /// column picks the column's move, and the cursor only moves sideways.
+    Column,
+}
+
+/// A direction to move a `Selection`'s cursor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// A cursor over a board, for picking moves by position (e.g. with arrow
+/// keys) instead of by notation.
+///
+/// The legal moves are placed on the board by the cell they fill, so the
+/// selection works for any `BoardGame` whose moves each fill one cell.
+#[derive(Clone, Debug)]
+pub struct Selection<M> {
+    rows: usize,
+    cols: usize,
+    by: SelectBy,
+    cursor: (usize, usize),
+    targets: Vec<((usize, usize), M)>,
+}
+
+impl<M> Selection<M> {
+    /// A selection over a `rows` by `cols` board with the given moves and
+    /// the cells they fill, and the cursor in the middle of the board.
+    pub fn new(rows: usize, cols: usize, by: SelectBy, targets: Vec<((usize, usize), M)>) -> Self {
+        let mut selection = Self {
+            rows,
+            cols,
+            by,
+            cursor: (0, 0),
+            targets,
+        };
+        selection.set_cursor(rows / 2, cols / 2);
+        selection
+    }
+
+    /// A selection of the legal moves of `state`.
+    pub fn of<G: BoardGame<Move = M>>(state: &G, by: SelectBy) -> Self {
+        let targets = state
+            .legal_moves()
+            .into_iter()
+            .filter_map(|mv| Some((changed_cell(state, &state.apply_move(&mv))?, mv)))
+            .collect();
+        Self::new(state.rows(), state.cols(), by, targets)
+    }
+
+    /// The same selection with each move replaced by `f` of it.
+    pub fn map<N>(self, mut f: impl FnMut(M) -> N) -> Selection<N> {
+        Selection {
+            rows: self.rows,
+            cols: self.cols,
+            by: self.by,
+            cursor: self.cursor,
+            targets: self
+                .targets
+                .into_iter()
+                .map(|(cell, mv)| (cell, f(mv)))
+                .collect(),
+        }
+    }
+
+    /// The cell under the cursor. In `SelectBy::Column` selections this is
+    /// the cell the column's move fills, or the top of a full column.
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    /// Moves the cursor to (`row`, `col`), kept on the board.
+    pub fn set_cursor(&mut self, row: usize, col: usize) {
+        let col = col.min(self.cols.saturating_sub(1));
+        let row = match self.by {
+            SelectBy::Cell => row.min(self.rows.saturating_sub(1)),
+            SelectBy::Column => self
+                .targets
+                .iter()
+                .find(|((_, c), _)| *c == col)
+                .map_or(0, |((r, _), _)| *r),
+        };
+        self.cursor = (row, col);
+    }
+
+    /// Moves the cursor one cell in `direction`, stopping at the edge of the
+    /// board.
+    pub fn move_cursor(&mut self, direction: Direction) {
+        let (row, col) = self.cursor;
+        match direction {
+            Direction::Up => self.set_cursor(row.saturating_sub(1), col),
+            Direction::Down => self.set_cursor(row + 1, col),
+            Direction::Left => self.set_cursor(row, col.saturating_sub(1)),
+            Direction::Right => self.set_cursor(row, col + 1),
+        }
+    }
+
+    /// The move under the cursor, or `None` if no legal move fills that
+    /// cell.
+    pub fn selected(&self) -> Option<&M> {
+        self.move_at(self.cursor.0, self.cursor.1)
+    }
+
+    /// The legal move filling (`row`, `col`), if any.
+    pub fn move_at(&self, row: usize, col: usize) -> Option<&M> {
+        self.targets
+            .iter()
+            .find(|(cell, _)| *cell == (row, col))
+            .map(|(_, mv)| mv)
+    }
+
+    /// Whether a legal move fills (`row`, `col`), e.g. for highlighting.
+    pub fn is_target(&self, row: usize, col: usize) -> bool {
+        self.move_at(row, col).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameState, Player};
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+
+    #[test]
+    fn cells_pick_moves_and_occupied_cells_pick_none() {
+        let state = TicTacToeState::from_str("X...O....", Player::Player1).unwrap();
+        let mut selection = Selection::of(&state, SelectBy::Cell);
+        assert_eq!(selection.cursor(), (1, 1));
+        assert_eq!(selection.selected(), None);
+
+        selection.move_cursor(Direction::Up);
+        selection.move_cursor(Direction::Up);
+        selection.move_cursor(Direction::Right);
+        selection.move_cursor(Direction::Right);
+        assert_eq!(selection.cursor(), (0, 2));
+        let mv = *selection.selected().unwrap();
+        assert_eq!(changed_cell(&state, &state.apply_move(&mv)), Some((0, 2)));
+        assert!(!selection.is_target(0, 0));
+        assert_eq!(
+            (0..3)
+                .flat_map(|r| (0..3).map(move |c| (r, c)))
+                .filter(|&(r, c)| selection.is_target(r, c))
+                .count(),
+            7
+        );
+    }
+
+    #[test]
+    fn columns_follow_the_drop_cell() {
+        let state = BitboardState::from_move_sequence("3334").unwrap();
+        let mut selection = Selection::of(&state, SelectBy::Column);
+        assert_eq!(selection.cursor(), (2, 3));
+        assert_eq!(selection.selected(), Some(&3));
+
+        selection.move_cursor(Direction::Up);
+        assert_eq!(selection.cursor(), (2, 3));
+        selection.move_cursor(Direction::Right);
+        assert_eq!(selection.cursor(), (4, 4));
+        assert_eq!(selection.selected(), Some(&4));
+        for _ in 0..10 {
+            selection.move_cursor(Direction::Right);
+        }
+        assert_eq!(selection.cursor(), (5, 6));
+
+        let full = BitboardState::from_move_sequence("333333").unwrap();
+        let mut selection = Selection::of(&full, SelectBy::Column).map(|mv| mv + 1);
+        assert_eq!(selection.cursor(), (0, 3));
+        assert_eq!(selection.selected(), None);
+        selection.move_cursor(Direction::Left);
+        assert_eq!(selection.selected(), Some(&3));
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::cancel::CancelToken;
+use crate::dyn_game::{DynGameState, DynMove};
+use crate::error::ParseError;
+use crate::registry::Registry;
+use crate::solvers::search::SearchResult;
+use crate::time_manager::{InstantTimeSource, TimeSource};
+
+/// Identifies a session of a `SessionManager`.
+pub type SessionId = u64;
+
+/// Why a `SessionManager` request failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SessionError {
+    /// No game is registered under the name.
+    UnknownGame(String),
+    /// No session has the ID, or it expired.
+    UnknownSession(SessionId),
+    /// The move or game record could not be played.
+    InvalidMove(ParseError),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::UnknownGame(name) => write!(f, "unknown game '{name}'"),
+            SessionError::UnknownSession(id) => write!(f, "no session {id}"),
+            SessionError::InvalidMove(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<ParseError> for SessionError {
+    fn from(e: ParseError) -> Self {
+        SessionError::InvalidMove(e)
+    }
+}
+
+/// A session's contents and when it was last used.
+struct Session<S> {
+    state: S,
+    last_active: Duration,
+}
+
+/// Games in progress, keyed by `SessionId`, shared between threads.
+///
+/// Any game of the registry can be started. Sessions that go unused for
+/// longer than the idle timeout expire: they are dropped by
+/// `expire_idle`, and behave as removed before then.
+///
+/// By default a session is just its position, a `Box<dyn DynGameState>`.
+/// Front ends that keep more per game (players, subscribers, a `Referee`)
+/// store their own session type `S` with `insert` and reach it with
+/// `with_session`, and get the same IDs and expiry.
+pub struct SessionManager<T = InstantTimeSource, S = Box<dyn DynGameState>> {
+    registry: Registry,
+    idle_timeout: Duration,
+    time: T,
+    next_id: AtomicU64,
+    sessions: Mutex<HashMap<SessionId, Session<S>>>,
+}
+
+impl<S> SessionManager<InstantTimeSource, S> {
+    /// A manager of games from `registry`, expiring sessions idle for
+    /// `idle_timeout`.
+    pub fn new(registry: Registry, idle_timeout: Duration) -> Self {
+        Self::with_time_source(registry, idle_timeout, InstantTimeSource::default())
+    }
+}
+
+impl<T: TimeSource, S> SessionManager<T, S> {
+    /// Like `new`, but reads the time from `time`.
+    pub fn with_time_source(registry: Registry, idle_timeout: Duration, time: T) -> Self {
+        Self {
+            registry,
+            idle_timeout,
+            time,
+            next_id: AtomicU64::new(1),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The games sessions can be started for.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<SessionId, Session<S>>> {
+        // A panic while holding the lock leaves no session half-updated.
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Runs `f` on the live session `id`, marking it as used.
+    ///
+    /// The sessions are locked while `f` runs, so it should not search.
+    pub fn with_session<R>(
+        &self,
+        id: SessionId,
+        f: impl FnOnce(&mut S) -> Result<R, SessionError>,
+    ) -> Result<R, SessionError> {
+        let now = self.time.now();
+        let mut sessions = self.lock();
+        let session = sessions
+            .get_mut(&id)
+            .ok_or(SessionError::UnknownSession(id))?;
+        if now.saturating_sub(session.last_active) > self.idle_timeout {
+            sessions.remove(&id);
+            return Err(SessionError::UnknownSession(id));
+        }
+        session.last_active = now;
+        f(&mut session.state)
+    }
+
+    /// Adds a session holding `state` and returns its ID.
+    pub fn insert(&self, state: S) -> SessionId {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let last_active = self.time.now();
+        self.lock().insert(id, Session { state, last_active });
+        id
+    }
+
+    /// Ends session `id`. Returns whether it existed.
+    pub fn remove(&self, id: SessionId) -> bool {
+        self.lock().remove(&id).is_some()
+    }
+
+    /// Keeps only the sessions for which `keep` returns true, without
+    /// marking any as used.
+    pub fn retain(&self, mut keep: impl FnMut(SessionId, &mut S) -> bool) {
+        self.lock()
+            .retain(|&id, session| keep(id, &mut session.state));
+    }
+
+    /// Drops the sessions idle for longer than the timeout and returns
+    /// them, in increasing order of ID, e.g. to tell their players.
+    pub fn take_idle(&self) -> Vec<(SessionId, S)> {
+        let now = self.time.now();
+        let mut sessions = self.lock();
+        let idle: Vec<SessionId> = sessions
+            .iter()
+            .filter(|(_, session)| now.saturating_sub(session.last_active) > self.idle_timeout)
+            .map(|(&id, _)| id)
+            .collect();
+        let mut expired: Vec<(SessionId, S)> = idle
+            .into_iter()
+            .filter_map(|id| sessions.remove(&id).map(|session| (id, session.state)))
+            .collect();
+        expired.sort_unstable_by_key(|&(id, _)| id);
+        expired
+    }
+
+    /// Drops the sessions idle for longer than the timeout and returns
+    /// their IDs, in increasing order.
+    pub fn expire_idle(&self) -> Vec<SessionId> {
+        self.take_idle().into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// The number of sessions, including expired ones not yet dropped.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns true if there are no sessions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: TimeSource> SessionManager<T> {
+    /// Starts a game of `game` (a registry name) from its initial position.
+    pub fn create(&self, game: &str) -> Result<SessionId, SessionError> {
+        self.create_from(game, "")
+    }
+
+    /// Starts a game of `game` after the moves of `notation` (see
+    /// `DynGameState::notation`).
+    pub fn create_from(&self, game: &str, notation: &str) -> Result<SessionId, SessionError> {
+        let entry = self
+            .registry
+            .get(game)
+            .ok_or_else(|| SessionError::UnknownGame(game.to_string()))?;
+        Ok(self.insert((entry.from_notation)(notation)?))
+    }
+
+    /// Plays `mv`, in the game's notation, in session `id` and returns the
+    /// new position. Illegal moves leave the session unchanged.
+    pub fn play(&self, id: SessionId, mv: &str) -> Result<Box<dyn DynGameState>, SessionError> {
+        self.with_session(id, |state| {
+            *state = state.play(mv)?;
+            Ok(state.clone())
+        })
+    }
+
+    /// The current position of session `id`.
+    pub fn state(&self, id: SessionId) -> Result<Box<dyn DynGameState>, SessionError> {
+        self.with_session(id, |state| Ok(state.clone()))
+    }
+
+    /// Searches the current position of session `id` to `depth` plies (see
+    /// `DynGameState::search`).
+    ///
+    /// The search runs on a copy, so other requests (even for the same
+    /// session) are not blocked while it runs.
+    pub fn evaluate(
+        &self,
+        id: SessionId,
+        depth: u32,
+        cancel: Option<&CancelToken>,
+    ) -> Result<SearchResult<DynMove>, SessionError> {
+        let state = self.state(id)?;
+        Ok(state.search(depth, cancel))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+    use crate::error::MoveError;
+    use crate::game::{Outcome, Player};
+
+    /// A clock advanced by hand, in seconds.
+    #[derive(Clone, Default)]
+    struct ManualTime(Arc<AtomicU64>);
+
+    impl TimeSource for ManualTime {
+        fn now(&self) -> Duration {
+            Duration::from_secs(self.0.load(Ordering::Relaxed))
+        }
+    }
+
+    fn manager() -> (SessionManager<ManualTime>, ManualTime) {
+        let time = ManualTime::default();
+        let sessions = SessionManager::with_time_source(
+            Registry::builtin(),
+            Duration::from_secs(60),
+            time.clone(),
+        );
+        (sessions, time)
+    }
+
+    #[test]
+    fn moves_are_validated_and_applied_per_session() {
+        let (sessions, _) = manager();
+        let ttt = sessions.create("ttt").unwrap();
+        let c4 = sessions.create_from("c4", "3").unwrap();
+        assert_ne!(ttt, c4);
+        assert_eq!(
+            sessions.create("chess"),
+            Err(SessionError::UnknownGame("chess".to_string()))
+        );
+
+        for mv in ["0", "3", "1", "4"] {
+            sessions.play(ttt, mv).unwrap();
+        }
+        assert_eq!(
+            sessions.play(ttt, "4").err(),
+            Some(SessionError::InvalidMove(ParseError::IllegalMove(
+                MoveError::CellOccupied
+            )))
+        );
+        let won = sessions.play(ttt, "2").unwrap();
+        assert_eq!(won.outcome(), Some(Outcome::Win(Player::Player1)));
+        assert_eq!(sessions.state(ttt).unwrap().notation(), "03142");
+
+        assert_eq!(
+            sessions.state(c4).unwrap().current_player(),
+            Player::Player2
+        );
+        let result = sessions.evaluate(c4, 3, None).unwrap();
+        assert_eq!(result.depth, 3);
+        assert_eq!(result.best_move().unwrap().game_name(), "c4");
+
+        assert!(sessions.remove(ttt));
+        assert_eq!(
+            sessions.state(ttt).err(),
+            Some(SessionError::UnknownSession(ttt))
+        );
+    }
+
+    #[test]
+    fn idle_sessions_expire() {
+        let (sessions, time) = manager();
+        let old = sessions.create("ttt").unwrap();
+        let used = sessions.create("ttt").unwrap();
+        time.0.store(50, Ordering::Relaxed);
+        sessions.play(used, "4").unwrap();
+        let young = sessions.create("c4").unwrap();
+
+        time.0.store(100, Ordering::Relaxed);
+        assert_eq!(sessions.expire_idle(), [old]);
+        assert_eq!(sessions.len(), 2);
+
+        // Past the timeout a session is gone even before it is dropped.
+        time.0.store(200, Ordering::Relaxed);
+        assert_eq!(
+            sessions.play(used, "0").err(),
+            Some(SessionError::UnknownSession(used))
+        );
+        assert_eq!(sessions.expire_idle(), [young]);
+        assert!(sessions.is_empty());
+    }
+
+    #[test]
+    fn sessions_can_hold_anything() {
+        let time = ManualTime::default();
+        let sessions: SessionManager<_, Vec<&str>> = SessionManager::with_time_source(
+            Registry::builtin(),
+            Duration::from_secs(60),
+            time.clone(),
+        );
+        let (a, b) = (sessions.insert(vec!["a"]), sessions.insert(Vec::new()));
+        sessions
+            .with_session(a, |words| {
+                words.push("b");
+                Ok(())
+            })
+            .unwrap();
+        sessions.retain(|id, _| id != b);
+        assert_eq!(sessions.len(), 1);
+
+        time.0.store(100, Ordering::Relaxed);
+        assert_eq!(sessions.take_idle(), [(a, vec!["a", "b"])]);
+        assert_eq!(
+            sessions.with_session(a, |_| Ok(())),
+            Err(SessionError::UnknownSession(a))
+        );
+    }
+
+    #[test]
+    fn sessions_are_shared_between_threads() {
+        let (sessions, _) = manager();
+        let sessions = Arc::new(sessions);
+        let ids: Vec<SessionId> = thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    let sessions = &sessions;
+                    s.spawn(move || {
+                        let id = sessions.create("c4").unwrap();
+                        for mv in ["3", "3", "4"] {
+                            sessions.play(id, mv).unwrap();
+                        }
+                        id
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(sessions.len(), 4);
+        for id in ids {
+            assert_eq!(sessions.state(id).unwrap().notation(), "334");
+        }
+    }
+}
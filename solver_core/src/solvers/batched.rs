@@ -0,0 +1,301 @@
+use alloc::vec::Vec;
+
+use crate::eval::Evaluator;
+use crate::game::{GameState, Player};
+use crate::solvers::minimax::{MovePool, SearchStats, WIN_SCORE, no_moves_value};
+
+/// The bottom of the search tree below some position, built before its
+/// leaves are evaluated.
+enum Subtree {
+    /// A position scored without the evaluator: a finished game.
+    Scored(i32),
+    /// The leaf at this index of the batch.
+    Leaf(usize),
+    /// A position to move in, with one subtree per move.
+    Node {
+        maximizing: bool,
+        children: Vec<Subtree>,
+    },
+}
+
+impl Subtree {
+    /// The minimax value, with the leaves valued by `leaf_values`.
+    fn value(&self, leaf_values: &[i32]) -> i32 {
+        match self {
+            Subtree::Scored(value) => *value,
+            Subtree::Leaf(index) => leaf_values[*index],
+            Subtree::Node {
+                maximizing,
+                children,
+            } => {
+                let values = children.iter().map(|child| child.value(leaf_values));
+                if *maximizing {
+                    values.max()
+                } else {
+                    values.min()
+                }
+                .expect("nodes have children")
+            }
+        }
+    }
+}
+
+/// Depth-limited alpha-beta whose leaves are scored in batches.
+struct BatchedSearch<'a, E> {
+    evaluator: &'a E,
+    batch_plies: u32,
+    stats: SearchStats,
+}
+
+/// The score of a position without moves on the depth-limited scale.
+fn no_moves_score<G: GameState>(state: &G) -> i32 {
+    no_moves_value(state) * WIN_SCORE
+}
+
+impl<E> BatchedSearch<'_, E> {
+    /// Builds the tree below `state` down to `remaining` plies, collecting
+    /// its leaves into `leaves`.
+    fn expand<G: GameState>(&mut self, state: &G, remaining: u32, leaves: &mut Vec<G>) -> Subtree {
+        if let Some(v) = state.terminal_value() {
+            return Subtree::Scored(v * WIN_SCORE);
+        }
+        if remaining == 0 {
+            leaves.push(state.clone());
+            return Subtree::Leaf(leaves.len() - 1);
+        }
+        let moves = state.legal_moves();
+        if moves.is_empty() {
+            return Subtree::Scored(no_moves_score(state));
+        }
+        let children = moves
+            .iter()
+            .map(|mv| {
+                self.stats.nodes += 1;
+                self.expand(&state.apply_move(mv), remaining - 1, leaves)
+            })
+            .collect();
+        Subtree::Node {
+            maximizing: state.current_player() == Player::Player1,
+            children,
+        }
+    }
+
+    /// Scores the leaves of `tree` in one batch and returns the values of
+    /// its children, or of itself if it has none.
+    fn values<G: GameState>(&self, tree: &Subtree, leaves: &[G]) -> Vec<i32>
+    where
+        E: Evaluator<G>,
+    {
+        let leaf_values = if leaves.is_empty() {
+            Vec::new()
+        } else {
+            self.evaluator.evaluate_batch(leaves)
+        };
+        assert_eq!(
+            leaf_values.len(),
+            leaves.len(),
+            "evaluate_batch must return one value per position"
+        );
+        match tree {
+            Subtree::Node { children, .. } => children
+                .iter()
+                .map(|child| child.value(&leaf_values))
+                .collect(),
+            leaf => alloc::vec![leaf.value(&leaf_values)],
+        }
+    }
+
+    /// The value of `state` searched `remaining` plies deep, within the
+    /// window `alpha..beta`.
+    fn node<G: GameState>(
+        &mut self,
+        state: &G,
+        remaining: u32,
+        mut alpha: i32,
+        mut beta: i32,
+        pool: &mut MovePool<G::Move>,
+    ) -> i32
+    where
+        E: Evaluator<G>,
+    {
+        if remaining <= self.batch_plies {
+            let mut leaves = Vec::new();
+            let tree = self.expand(state, remaining, &mut leaves);
+            let values = self.values(&tree, &leaves).into_iter();
+            return match tree {
+                Subtree::Node {
+                    maximizing: true, ..
+                } => values.max(),
+                Subtree::Node { .. } => values.min(),
+                _ => values.last(),
+            }
+            .expect("every subtree has a value");
+        }
+        if let Some(v) = state.terminal_value() {
+            return v * WIN_SCORE;
+        }
+        let moves = pool.ordered_moves(state);
+        if moves.is_empty() {
+            pool.give_back(moves);
+            return no_moves_score(state);
+        }
+        let maximizing = state.current_player() == Player::Player1;
+        let mut value = if maximizing { i32::MIN } else { i32::MAX };
+        for mv in &moves {
+            self.stats.nodes += 1;
+            let child = self.node(&state.apply_move(mv), remaining - 1, alpha, beta, pool);
+            if maximizing {
+                value = value.max(child);
+                alpha = alpha.max(value);
+            } else {
+                value = value.min(child);
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        pool.give_back(moves);
+        value
+    }
+}
+
+/// Searches `state` to `depth` plies like `minimax::alpha_beta` with
+/// `SearchParams::depth`, but scores the leaves with `evaluator` in
+/// batches, and returns the best move with its value (`None` without legal
+/// moves) together with search statistics.
+///
+/// The last `batch_plies` plies (at least 1) of every line are searched
+/// without pruning, so that all their leaves go to the evaluator in one
+/// call: the higher it is, the bigger the batches and the more positions
+/// are evaluated that alpha-beta would have pruned. Above them the search
+/// prunes as usual, so values match the unbatched search. Depth is at
+/// least 1, and draws score 0.
+pub fn batched_alpha_beta<G: GameState, E: Evaluator<G>>(
+    state: &G,
+    depth: u32,
+    batch_plies: u32,
+    evaluator: &E,
+) -> (Option<(G::Move, i32)>, SearchStats) {
+    let depth = depth.max(1);
+    let mut search = BatchedSearch {
+        evaluator,
+        batch_plies: batch_plies.max(1),
+        stats: SearchStats::default(),
+    };
+    let moves = state.legal_moves();
+    if state.is_terminal() || moves.is_empty() {
+        return (None, search.stats);
+    }
+    let maximizing = state.current_player() == Player::Player1;
+    let values = if depth <= search.batch_plies {
+        // The whole tree is one batch.
+        let mut leaves = Vec::new();
+        let tree = search.expand(state, depth, &mut leaves);
+        search.values(&tree, &leaves)
+    } else {
+        let mut pool = MovePool::new();
+        let (mut alpha, mut beta) = (i32::MIN, i32::MAX);
+        let mut values = Vec::with_capacity(moves.len());
+        for mv in &moves {
+            search.stats.nodes += 1;
+            let value = search.node(&state.apply_move(mv), depth - 1, alpha, beta, &mut pool);
+            // Moves that can't beat the best so far may come back with
+            // bounds instead of values, which the strict comparison below
+            // never prefers.
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+            values.push(value);
+        }
+        values
+    };
+    let best = moves.into_iter().zip(values).reduce(|best, next| {
+        let better = if maximizing {
+            next.1 > best.1
+        } else {
+            next.1 < best.1
+        };
+        if better { next } else { best }
+    });
+    (best, search.stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::HeuristicEvaluator;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+    use crate::solvers::minimax::{SearchParams, alpha_beta};
+    use core::cell::RefCell;
+
+    /// Scores like the heuristic, recording the size of every batch.
+    struct Recording(RefCell<Vec<usize>>);
+
+    impl Evaluator<BitboardState> for Recording {
+        fn evaluate(&self, _: &BitboardState) -> i32 {
+            unreachable!("the search scores its leaves in batches")
+        }
+
+        fn evaluate_batch(&self, states: &[BitboardState]) -> Vec<i32> {
+            self.0.borrow_mut().push(states.len());
+            states.iter().map(GameState::heuristic_value).collect()
+        }
+    }
+
+    #[test]
+    fn batched_values_match_the_plain_search() {
+        for seq in ["", "33", "3342", "001122"] {
+            let state = BitboardState::from_move_sequence(seq).unwrap();
+            for depth in 1..=4 {
+                let (expected, _) =
+                    alpha_beta(&state, SearchParams::depth(depth), &HeuristicEvaluator);
+                for batch_plies in 1..=3 {
+                    let (best, stats) =
+                        batched_alpha_beta(&state, depth, batch_plies, &HeuristicEvaluator);
+                    assert_eq!(
+                        best.map(|(_, v)| v),
+                        expected.map(|(_, v)| v),
+                        "{seq:?} depth {depth} batch plies {batch_plies}"
+                    );
+                    assert!(stats.nodes > 0);
+                }
+            }
+        }
+        let ttt = TicTacToeState::new();
+        let (best, _) = batched_alpha_beta(&ttt, 9, 2, &HeuristicEvaluator);
+        assert_eq!(best.map(|(_, v)| v), Some(0));
+    }
+
+    #[test]
+    fn leaves_are_evaluated_in_batches() {
+        let recording = Recording(RefCell::new(Vec::new()));
+        let state = BitboardState::new();
+        let (best, _) = batched_alpha_beta(&state, 2, 2, &recording);
+        assert_eq!(best.map(|(mv, _)| mv), Some(3));
+        // The whole tree in one call: 7 replies to each of 7 moves.
+        assert_eq!(*recording.0.borrow(), [49]);
+
+        recording.0.borrow_mut().clear();
+        batched_alpha_beta(&state, 4, 2, &recording);
+        let batches = recording.0.borrow();
+        assert!(batches.iter().all(|&size| size == 49));
+        assert!(batches.len() < 49);
+    }
+
+    #[test]
+    fn finished_games_have_no_move() {
+        let state = BitboardState::from_move_sequence("0011223").unwrap();
+        assert_eq!(
+            batched_alpha_beta(&state, 3, 2, &HeuristicEvaluator).0,
+            None
+        );
+        assert_eq!(
+            batched_alpha_beta(&state, 1, 1, &HeuristicEvaluator).0,
+            None
+        );
+    }
+}
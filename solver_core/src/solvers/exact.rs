@@ -0,0 +1,272 @@
+use alloc::vec::Vec;
+
+use crate::cancel::CancelToken;
+use crate::game::{GameState, Outcome, PositionKey};
+use crate::solvers::minimax::{DEFAULT_MAX_PLY, SearchStats, WIN_SCORE};
+use crate::solvers::tt::{TranspositionTable, best_move_tt_from};
+
+/// The result of a position with best play, as found by `solve_exact`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExactSolution<M> {
+    pub outcome: Outcome,
+    /// Plies until the game ends with best play: the winner wins as fast
+    /// as possible and the loser holds out as long as possible. A drawn
+    /// game is played to the end.
+    pub plies: u32,
+    /// A best move, or `None` if the position is already over.
+    pub best_move: Option<M>,
+    /// Positions visited over every iteration.
+    pub nodes: u64,
+}
+
+/// How far a `solve_exact_from` got: the depth being searched, the root
+/// moves that depth has finished, and the best of them with its value.
+/// Every shallower depth is known not to settle the result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolveProgress<M> {
+    pub depth: u32,
+    pub searched: Vec<M>,
+    pub best: Option<(M, i32)>,
+}
+
+impl<M> Default for SolveProgress<M> {
+    /// The start of a solve: depth 1, nothing searched.
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            searched: Vec::new(),
+            best: None,
+        }
+    }
+}
+
+/// Solves `state` exactly by searching 1, 2, ... plies deep until a win is
+/// forced or the search reaches the end of the game.
+///
+/// The first depth that proves a win is the number of plies it takes, so
+/// the result comes with its distance. The table is cleared before each
+/// depth, because results stored by a deeper search would prove wins
+/// sooner than they happen. `on_depth(depth, stats)` is called after each
+/// depth that did not settle the result.
+///
+/// Returns `None` if `cancel` is cancelled first.
+pub fn solve_exact<G>(
+    state: &G,
+    tt: &mut TranspositionTable<G::Move>,
+    cancel: Option<&CancelToken>,
+    mut on_depth: impl FnMut(u32, &SearchStats),
+) -> Option<ExactSolution<G::Move>>
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    let mut reported = 0;
+    solve_exact_from(
+        state,
+        tt,
+        cancel,
+        SolveProgress::default(),
+        |progress, stats| {
+            if progress.searched.is_empty() {
+                let nodes = stats.nodes - reported;
+                reported = stats.nodes;
+                on_depth(progress.depth - 1, &SearchStats { nodes });
+            }
+        },
+    )
+}
+
+/// Like `solve_exact`, but carries on from `progress`, so a solve that was
+/// interrupted part way through a depth loses none of the root moves it
+/// had finished.
+///
+/// `on_progress(progress, stats)` is called after each root move, and
+/// once more when a depth doesn't settle the result, with the next depth
+/// and nothing searched yet; `stats` counts the nodes visited by this call
+/// so far. Saving the latest `progress` is enough to resume. A `progress`
+/// past the end of the game starts over at its last depth.
+pub fn solve_exact_from<G>(
+    state: &G,
+    tt: &mut TranspositionTable<G::Move>,
+    cancel: Option<&CancelToken>,
+    mut progress: SolveProgress<G::Move>,
+    mut on_progress: impl FnMut(&SolveProgress<G::Move>, &SearchStats),
+) -> Option<ExactSolution<G::Move>>
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    if let Some(outcome) = state.outcome() {
+        return Some(ExactSolution {
+            outcome,
+            plies: 0,
+            best_move: None,
+            nodes: 0,
+        });
+    }
+    let max_plies = state.complexity_hint().max_plies.unwrap_or(DEFAULT_MAX_PLY);
+    if !(1..=max_plies).contains(&progress.depth) {
+        progress = SolveProgress {
+            depth: progress.depth.clamp(1, max_plies),
+            ..SolveProgress::default()
+        };
+    }
+    let mut stats = SearchStats::default();
+    loop {
+        tt.clear();
+        let nodes = stats.nodes;
+        let depth_stats = best_move_tt_from(state, tt, cancel, &mut progress, |progress, s| {
+            on_progress(
+                progress,
+                &SearchStats {
+                    nodes: nodes + s.nodes,
+                },
+            )
+        });
+        stats.nodes += depth_stats.nodes;
+        if cancel.is_some_and(CancelToken::is_cancelled) {
+            return None;
+        }
+        let depth = progress.depth;
+        let (best_move, value) = progress.best.take()?;
+        if value.abs() >= WIN_SCORE || depth == max_plies {
+            return Some(ExactSolution {
+                outcome: Outcome::from_value(value / WIN_SCORE),
+                plies: depth,
+                best_move: Some(best_move),
+                nodes: stats.nodes,
+            });
+        }
+        progress = SolveProgress {
+            depth: depth + 1,
+            ..SolveProgress::default()
+        };
+        on_progress(&progress, &stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Player;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+    use crate::solvers::tt::ReplacementPolicy;
+    use alloc::vec::Vec;
+
+    fn table<M: Clone>() -> TranspositionTable<M> {
+        TranspositionTable::with_entries(1 << 14, ReplacementPolicy::DepthPreferred)
+    }
+
+    #[test]
+    fn ttt_from_the_start_is_a_full_length_draw() {
+        let mut depths = Vec::new();
+        let solution = solve_exact(&TicTacToeState::new(), &mut table(), None, |d, _| {
+            depths.push(d)
+        })
+        .unwrap();
+        assert_eq!(solution.outcome, Outcome::Draw);
+        assert_eq!(solution.plies, 9);
+        assert!(solution.best_move.is_some());
+        assert_eq!(depths, (1..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn wins_come_with_their_distance() {
+        // X forks on 6, threatening 3 and 2: O blocks one and X completes the
+        // other, three plies from now.
+        let state = TicTacToeState::from_board_str("XO..X...O").unwrap();
+        let solution = solve_exact(&state, &mut table(), None, |_, _| {}).unwrap();
+        assert_eq!(solution.outcome, Outcome::Win(Player::Player1));
+        assert_eq!(solution.plies, 3);
+
+        // Connect Four: Player1 has three in the bottom row and wins now.
+        let state = BitboardState::from_move_sequence("001122").unwrap();
+        let solution = solve_exact(&state, &mut table(), None, |_, _| {}).unwrap();
+        assert_eq!(solution.outcome, Outcome::Win(Player::Player1));
+        assert_eq!(solution.plies, 1);
+        assert_eq!(solution.best_move, Some(3));
+    }
+
+    #[test]
+    fn solves_resume_from_a_later_depth() {
+        let state = TicTacToeState::from_board_str("XO..X...O").unwrap();
+        let mut depths = Vec::new();
+        let from = SolveProgress {
+            depth: 2,
+            ..SolveProgress::default()
+        };
+        let solution = solve_exact_from(&state, &mut table(), None, from, |p, _| {
+            if p.searched.is_empty() {
+                depths.push(p.depth)
+            }
+        })
+        .unwrap();
+        assert_eq!(solution.outcome, Outcome::Win(Player::Player1));
+        assert_eq!(solution.plies, 3);
+        assert_eq!(depths, [3]);
+
+        // Past the end of the game, the last depth is searched.
+        let from = SolveProgress {
+            depth: 20,
+            ..SolveProgress::default()
+        };
+        let solution =
+            solve_exact_from(&TicTacToeState::new(), &mut table(), None, from, |_, _| {});
+        assert_eq!(
+            solution.map(|s| (s.outcome, s.plies)),
+            Some((Outcome::Draw, 9))
+        );
+    }
+
+    #[test]
+    fn solves_resume_inside_a_depth() {
+        let state = BitboardState::from_move_sequence("3344").unwrap();
+        let mut saved = Vec::new();
+        let full = solve_exact_from(
+            &state,
+            &mut table(),
+            None,
+            SolveProgress::default(),
+            |p, _| saved.push(p.clone()),
+        )
+        .unwrap();
+        assert_eq!(full.outcome, Outcome::Win(Player::Player1));
+        let partial = saved
+            .iter()
+            .rfind(|p| p.searched.len() == 3)
+            .unwrap()
+            .clone();
+        let mut searched = Vec::new();
+        let resumed = solve_exact_from(&state, &mut table(), None, partial.clone(), |p, _| {
+            if p.depth == partial.depth {
+                searched.push(p.searched.last().copied().unwrap())
+            }
+        })
+        .unwrap();
+        assert_eq!(resumed.outcome, full.outcome);
+        assert_eq!(resumed.plies, full.plies);
+        assert!(resumed.nodes < full.nodes);
+        // Only the root moves the checkpoint hadn't finished were searched.
+        assert_eq!(searched.len(), 7 - 3);
+        assert!(searched.iter().all(|mv| !partial.searched.contains(mv)));
+    }
+
+    #[test]
+    fn finished_positions_and_cancellation() {
+        let state = BitboardState::from_move_sequence("0011223").unwrap();
+        let solution = solve_exact(&state, &mut table(), None, |_, _| {}).unwrap();
+        assert_eq!(solution.outcome, Outcome::Win(Player::Player1));
+        assert_eq!((solution.plies, solution.best_move), (0, None));
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let state = BitboardState::new();
+        assert_eq!(
+            solve_exact(&state, &mut table(), Some(&cancel), |_, _| {}),
+            None
+        );
+    }
+}
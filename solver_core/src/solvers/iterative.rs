@@ -0,0 +1,238 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::cancel::CancelToken;
+use crate::game::GameState;
+use crate::game::Player;
+use crate::solvers::minimax::{minimax_best_move_ab_depth, ordered_root_moves, root_move_scores};
+use crate::trace::{search_event, search_span};
+
+/// The result of one completed iterative-deepening iteration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepthReport<M> {
+    /// The search depth (in plies) of this iteration.
+    pub depth: u32,
+    /// The best move found at this depth.
+    pub best_move: M,
+    /// The value of `best_move` from Player1's perspective, using the same
+    /// scale as `minimax_value_ab_depth` (terminal values are ±1_000_000).
+    pub value: i32,
+    /// The principal variation starting with `best_move`.
+    pub pv: Vec<M>,
+    /// Every root move in the order this iteration searched it, with its
+    /// score. The first of the best scores is `value` itself; the others
+    /// are bounds showing the move is no better. The next iteration
+    /// searches the moves in order of these scores, best first.
+    pub root_moves: Vec<(M, i32)>,
+}
+
+/// Returns the principal variation from `state` searched to `depth` plies.
+///
+/// The line is built by repeatedly taking the depth-limited best move and
+/// searching the resulting position one ply shallower, so every move in the
+/// line is the engine's choice at the remaining depth. The line stops early
+/// at terminal states.
+pub fn principal_variation<G: GameState>(state: &G, depth: u32) -> Vec<G::Move> {
+    let mut pv = Vec::new();
+    let mut current = state.clone();
+    for remaining in (1..=depth).rev() {
+        if current.is_terminal() {
+            break;
+        }
+        match minimax_best_move_ab_depth(&current, remaining) {
+            Some((mv, _)) => {
+                current = current.apply_move(&mv);
+                pv.push(mv);
+            }
+            None => break,
+        }
+    }
+    pv
+}
+
+/// Runs depth-limited alpha-beta at depths 1, 2, ..., `max_depth`, calling
+/// `on_iteration` after each completed depth.
+///
+/// Returns the report of the deepest completed iteration, or `None` if the
+/// state has no legal moves (or `max_depth` is 0).
+pub fn iterative_deepening<G, F>(
+    state: &G,
+    max_depth: u32,
+    on_iteration: F,
+) -> Option<DepthReport<G::Move>>
+where
+    G: GameState,
+    F: FnMut(&DepthReport<G::Move>),
+{
+    deepen(state, max_depth, None, on_iteration)
+}
+
+/// Like `iterative_deepening`, but stops as soon as `cancel` is cancelled
+/// and returns the deepest iteration completed before that.
+///
+/// Depth 1 always runs to completion, so a cancelled search still returns
+/// a move whenever the state has one.
+pub fn iterative_deepening_cancellable<G, F>(
+    state: &G,
+    max_depth: u32,
+    cancel: &CancelToken,
+    on_iteration: F,
+) -> Option<DepthReport<G::Move>>
+where
+    G: GameState,
+    F: FnMut(&DepthReport<G::Move>),
+{
+    deepen(state, max_depth, Some(cancel), on_iteration)
+}
+
+fn deepen<G, F>(
+    state: &G,
+    max_depth: u32,
+    cancel: Option<&CancelToken>,
+    mut on_iteration: F,
+) -> Option<DepthReport<G::Move>>
+where
+    G: GameState,
+    F: FnMut(&DepthReport<G::Move>),
+{
+    let maximizing = state.current_player() == Player::Player1;
+    let mut order = if state.is_terminal() {
+        Vec::new()
+    } else {
+        ordered_root_moves(state)
+    };
+    let mut last = None;
+    for depth in 1..=max_depth {
+        if order.is_empty() {
+            break;
+        }
+        let span = search_span!("iteration", depth);
+        // Depth 1 always completes, so there is a move to fall back on.
+        let cancel = cancel.filter(|_| depth > 1);
+        let Some(scores) = root_move_scores(state, &order, depth, cancel).0 else {
+            break;
+        };
+        let root_moves: Vec<(G::Move, i32)> = order.drain(..).zip(scores).collect();
+        let (best_move, value) = root_moves
+            .iter()
+            .reduce(|best, candidate| {
+                let better = if maximizing {
+                    candidate.1 > best.1
+                } else {
+                    candidate.1 < best.1
+                };
+                if better { candidate } else { best }
+            })
+            .cloned()
+            .expect("there are root moves");
+        let mut pv = vec![best_move.clone()];
+        pv.extend(principal_variation(
+            &state.apply_move(&best_move),
+            depth - 1,
+        ));
+        // The next iteration tries the moves that scored best here first,
+        // keeping the previous order among equal scores.
+        let mut ranked = root_moves.clone();
+        ranked.sort_by(|a, b| {
+            if maximizing {
+                b.1.cmp(&a.1)
+            } else {
+                a.1.cmp(&b.1)
+            }
+        });
+        order = ranked.into_iter().map(|(mv, _)| mv).collect();
+        let report = DepthReport {
+            depth,
+            best_move,
+            value,
+            pv,
+            root_moves,
+        };
+        search_event!(tracing::Level::DEBUG, depth, value, "iteration complete");
+        span.close();
+        on_iteration(&report);
+        last = Some(report);
+    }
+    last
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+
+    #[test]
+    fn pv_length_is_bounded_by_depth_and_game_end() {
+        let s = TicTacToeState::new();
+        assert_eq!(principal_variation(&s, 3).len(), 3);
+        // A full-depth TTT line is a complete game of at most 9 plies.
+        assert!(principal_variation(&s, 20).len() <= 9);
+    }
+
+    #[test]
+    fn iterative_deepening_reports_every_depth() {
+        let s = BitboardState::new();
+        let mut depths = Vec::new();
+        let last = iterative_deepening(&s, 4, |r| depths.push(r.depth)).unwrap();
+        assert_eq!(depths, vec![1, 2, 3, 4]);
+        assert_eq!(last.depth, 4);
+        assert_eq!(last.pv[0], last.best_move);
+        assert_eq!(last.pv.len(), 4);
+    }
+
+    #[test]
+    fn iterative_deepening_finds_immediate_win() {
+        let mut s = BitboardState::new();
+        for c in [0, 6, 1, 6, 2, 6] {
+            s = s.apply_column_move(c);
+        }
+        let last = iterative_deepening(&s, 3, |_| {}).unwrap();
+        assert_eq!(last.best_move, 3);
+        assert_eq!(last.value, 1_000_000);
+        // The game ends after the winning move, so the PV is just that move.
+        assert_eq!(last.pv, vec![3]);
+        assert!(iterative_deepening(&s.apply_column_move(3), 3, |_| {}).is_none());
+    }
+
+    #[test]
+    fn root_moves_are_reordered_by_the_previous_iteration() {
+        let s = BitboardState::from_move_sequence("332").unwrap();
+        let mut reports = Vec::new();
+        iterative_deepening(&s, 5, |r| reports.push(r.clone())).unwrap();
+        for pair in reports.windows(2) {
+            let (previous, next) = (&pair[0], &pair[1]);
+            assert_eq!(next.root_moves.len(), s.legal_moves().len());
+            // Player2 is to move, so lower scores come first.
+            let mut ranked = previous.root_moves.clone();
+            ranked.sort_by_key(|&(_, score)| score);
+            let order: Vec<u8> = next.root_moves.iter().map(|&(mv, _)| mv).collect();
+            assert_eq!(order, ranked.iter().map(|&(mv, _)| mv).collect::<Vec<_>>());
+        }
+        for report in &reports {
+            let (_, value) = minimax_best_move_ab_depth(&s, report.depth).unwrap();
+            assert_eq!(report.value, value);
+            assert!(report.root_moves.contains(&(report.best_move, value)));
+            assert!(report.root_moves.iter().all(|&(_, score)| score >= value));
+        }
+    }
+
+    #[test]
+    fn cancelled_search_still_returns_a_depth_one_move() {
+        let s = BitboardState::new();
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let mut depths = Vec::new();
+        let last = iterative_deepening_cancellable(&s, 12, &cancel, |r| depths.push(r.depth));
+        assert_eq!(depths, vec![1]);
+        assert_eq!(
+            last.map(|r| r.best_move),
+            minimax_best_move_ab_depth(&s, 1).map(|b| b.0)
+        );
+
+        cancel.reset();
+        let full = iterative_deepening_cancellable(&s, 3, &cancel, |_| {}).unwrap();
+        assert_eq!(full.depth, 3);
+    }
+}
@@ -0,0 +1,571 @@
+use crate::agent::{Agent, Decision};
+use crate::eval::{Evaluator, HeuristicEvaluator};
+use crate::game::{GameState, Player, PositionKey};
+use crate::rng::{Rng, SplitMix64};
+use crate::solvers::minimax::no_moves_value;
+
+/// Index of a node in `MctsTree::nodes`.
+type NodeId = u32;
+
+/// Marks a missing child or sibling.
+const NONE: NodeId = NodeId::MAX;
+
+/// The exploration constant used unless `with_exploration` sets another:
+/// the classic UCB1 value, the square root of 2.
+pub const DEFAULT_EXPLORATION: f64 = core::f64::consts::SQRT_2;
+
+/// One position in the tree. Children form a singly linked list through
+/// `next_sibling`, so that every node is one arena slot and freed slots
+/// can be handed out again one at a time.
+#[derive(Clone, Debug)]
+struct Node<M> {
+    /// The move that leads here from the parent; `None` for a fresh root.
+    mv: Option<M>,
+    first_child: NodeId,
+    next_sibling: NodeId,
+    /// Whether the children have been created.
+    expanded: bool,
+    visits: u32,
+    /// Sum of the playout rewards for the player who played `mv`: 1 for a
+    /// win, 0.5 for a draw, 0 for a loss.
+    reward: f64,
+}
+
+impl<M> Node<M> {
+    fn new(mv: Option<M>) -> Self {
+        Self {
+            mv,
+            first_child: NONE,
+            next_sibling: NONE,
+            expanded: false,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+}
+
+/// What a search kept of the tree from earlier searches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MctsWarmStart {
+    /// Nodes kept below the new root.
+    pub kept_nodes: usize,
+    /// Visits of the new root from earlier searches: iterations this one
+    /// didn't need to repeat.
+    pub kept_visits: u32,
+    /// Iterations the search ran.
+    pub iterations: u32,
+}
+
+/// A Monte Carlo tree search (UCT with random playouts) whose tree lives
+/// in an arena: one `Vec` of nodes linked by `u32` indices instead of
+/// boxed pointers, so the tree stays compact and cheap to walk.
+///
+/// The tree is kept between searches. When `search` is asked about a
+/// position up to two plies below the previous root (typically after the
+/// engine's own move and the opponent's reply), it re-roots the tree
+/// there, keeping that subtree's statistics and recycling the slots of
+/// everything else for the nodes the new search creates. `with_reuse`
+/// turns this off, e.g. to measure what it saves.
+///
+/// Playouts are random games to the end unless `with_evaluator` sets an
+/// evaluator `E`, which then scores them after `with_playout_plies`
+/// random moves.
+#[derive(Clone)]
+pub struct MctsTree<G: GameState, R = SplitMix64, E = HeuristicEvaluator> {
+    nodes: Vec<Node<G::Move>>,
+    /// Slots of discarded nodes, reused before the arena grows.
+    free: Vec<NodeId>,
+    root: NodeId,
+    /// The position at `root`, once a search has run.
+    root_state: Option<G>,
+    exploration: f64,
+    reuse: bool,
+    rng: R,
+    /// Scores playouts that reach `playout_plies` moves; without one,
+    /// they run to the end of the game.
+    evaluator: Option<E>,
+    playout_plies: u32,
+    warm_start: Option<MctsWarmStart>,
+}
+
+impl<G: GameState> MctsTree<G> {
+    /// Creates an empty tree whose playouts are determined by `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self::with_rng(SplitMix64::new(seed))
+    }
+}
+
+impl<G: GameState, R: Rng> MctsTree<G, R> {
+    /// Creates an empty tree drawing its playouts from `rng`.
+    pub fn with_rng(rng: R) -> Self {
+        Self {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NONE,
+            root_state: None,
+            exploration: DEFAULT_EXPLORATION,
+            reuse: true,
+            rng,
+            evaluator: None,
+            playout_plies: 0,
+            warm_start: None,
+        }
+    }
+}
+
+impl<G: GameState, R: Rng, E: Evaluator<G>> MctsTree<G, R, E> {
+    /// Cuts playouts short and scores them with `evaluator` instead of
+    /// playing to the end: after `with_playout_plies` random moves (none
+    /// by default, scoring the new leaf itself), a positive evaluation
+    /// counts as a win for Player1, a negative one as a win for Player2
+    /// and zero as a draw. Positions that are already over are scored by
+    /// the game as usual.
+    ///
+    /// Any statistics in the tree were gathered with the old playouts, so
+    /// set this before searching.
+    pub fn with_evaluator<F: Evaluator<G>>(self, evaluator: F) -> MctsTree<G, R, F> {
+        MctsTree {
+            nodes: self.nodes,
+            free: self.free,
+            root: self.root,
+            root_state: self.root_state,
+            exploration: self.exploration,
+            reuse: self.reuse,
+            rng: self.rng,
+            evaluator: Some(evaluator),
+            playout_plies: self.playout_plies,
+            warm_start: self.warm_start,
+        }
+    }
+
+    /// Sets how many random moves a playout makes before the evaluator
+    /// scores it. Has no effect without `with_evaluator`.
+    pub fn with_playout_plies(mut self, plies: u32) -> Self {
+        self.playout_plies = plies;
+        self
+    }
+
+    /// Sets the UCB1 exploration constant: higher values spread visits
+    /// more evenly over the moves.
+    pub fn with_exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+        self
+    }
+
+    /// Sets whether a search keeps the subtree of an earlier one (the
+    /// default) or always starts from an empty tree.
+    pub fn with_reuse(mut self, reuse: bool) -> Self {
+        self.reuse = reuse;
+        self
+    }
+
+    /// What the latest search kept from earlier ones, or `None` before
+    /// the first search.
+    pub fn warm_start(&self) -> Option<MctsWarmStart> {
+        self.warm_start
+    }
+
+    /// Nodes currently in the tree.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len() - self.free.len()
+    }
+
+    /// Slots in the arena, in use or free: the most nodes the tree has
+    /// held at once.
+    pub fn arena_len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Visits of the root, including those kept from earlier searches.
+    pub fn root_visits(&self) -> u32 {
+        self.nodes.get(self.root as usize).map_or(0, |n| n.visits)
+    }
+
+    /// Empties the tree, keeping the arena's memory.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.root = NONE;
+        self.root_state = None;
+    }
+
+    fn alloc(&mut self, node: Node<G::Move>) -> NodeId {
+        if let Some(id) = self.free.pop() {
+            self.nodes[id as usize] = node;
+            return id;
+        }
+        let id = NodeId::try_from(self.nodes.len())
+            .ok()
+            .filter(|&id| id != NONE)
+            .expect("MCTS tree has too many nodes");
+        self.nodes.push(node);
+        id
+    }
+
+    /// The children of `id`, in move generation order.
+    fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let mut next = self.nodes[id as usize].first_child;
+        core::iter::from_fn(move || {
+            let current = next;
+            (current != NONE).then(|| {
+                next = self.nodes[current as usize].next_sibling;
+                current
+            })
+        })
+    }
+
+    /// Makes `keep` the root and frees every node outside its subtree.
+    fn reroot(&mut self, keep: NodeId) {
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            if id == keep {
+                continue;
+            }
+            stack.extend(self.children(id));
+            // Drop the move now rather than when the slot is reused.
+            self.nodes[id as usize].mv = None;
+            self.free.push(id);
+        }
+        self.nodes[keep as usize].next_sibling = NONE;
+        self.root = keep;
+    }
+
+    /// Points the tree at `state`: re-roots it if `state` is the old root
+    /// or one or two plies below it, and starts over otherwise.
+    fn move_root_to(&mut self, state: &G)
+    where
+        G: PositionKey,
+    {
+        let key = state.position_key();
+        let old = self.root_state.as_ref().filter(|_| self.reuse);
+        let kept = old.and_then(|old| {
+            if old.position_key() == key {
+                return Some(self.root);
+            }
+            for child in self.children(self.root) {
+                let after = old.apply_move(self.nodes[child as usize].mv.as_ref()?);
+                if after.position_key() == key {
+                    return Some(child);
+                }
+                for grandchild in self.children(child) {
+                    let mv = self.nodes[grandchild as usize].mv.as_ref()?;
+                    if after.apply_move(mv).position_key() == key {
+                        return Some(grandchild);
+                    }
+                }
+            }
+            None
+        });
+        match kept {
+            Some(id) => self.reroot(id),
+            None => {
+                self.clear();
+                self.root = self.alloc(Node::new(None));
+            }
+        }
+        self.root_state = Some(state.clone());
+    }
+
+    /// Creates a child of `id` for every legal move in `state`.
+    fn expand(&mut self, id: NodeId, state: &G) {
+        let moves = state.legal_moves();
+        // Prepending in reverse keeps the list in generation order.
+        for mv in moves.into_iter().rev() {
+            let mut child = Node::new(Some(mv));
+            child.next_sibling = self.nodes[id as usize].first_child;
+            let child = self.alloc(child);
+            self.nodes[id as usize].first_child = child;
+        }
+        self.nodes[id as usize].expanded = true;
+    }
+
+    /// The child of `id` to descend into: the first one never visited,
+    /// otherwise the one with the highest UCB1 score.
+    fn select(&self, id: NodeId) -> Option<NodeId> {
+        let log_visits = f64::from(self.nodes[id as usize].visits.max(1)).ln();
+        let mut best = None;
+        let mut best_score = f64::NEG_INFINITY;
+        for child in self.children(id) {
+            let node = &self.nodes[child as usize];
+            if node.visits == 0 {
+                return Some(child);
+            }
+            let visits = f64::from(node.visits);
+            let score = node.reward / visits + self.exploration * (log_visits / visits).sqrt();
+            if score > best_score {
+                best = Some(child);
+                best_score = score;
+            }
+        }
+        best
+    }
+
+    /// Plays random moves from `state` to the end of the game, or until
+    /// the evaluator takes over, and returns the result on the
+    /// `terminal_value` scale.
+    fn playout(&mut self, mut state: G) -> i32 {
+        let mut plies = 0;
+        loop {
+            if let Some(value) = state.terminal_value() {
+                return value.signum();
+            }
+            if let Some(evaluator) = &self.evaluator
+                && plies >= self.playout_plies
+            {
+                return evaluator.evaluate(&state).signum();
+            }
+            let moves = state.legal_moves();
+            let Some(mv) = self.rng.choose(&moves) else {
+                return no_moves_value(&state).signum();
+            };
+            state = state.apply_move(mv);
+            plies += 1;
+        }
+    }
+
+    /// One iteration: descend to a leaf, expand it, play out from one of
+    /// its children and back the result up the path.
+    fn iterate(&mut self, root_state: &G, path: &mut Vec<(NodeId, Option<Player>)>) {
+        path.clear();
+        path.push((self.root, None));
+        let mut state = root_state.clone();
+        let mut id = self.root;
+        let value = loop {
+            // A decided position stays a leaf: its value is known, so there
+            // is nothing to expand or play out.
+            if let Some(value) = state.terminal_value() {
+                break value.signum();
+            }
+            let fresh = !self.nodes[id as usize].expanded;
+            if fresh {
+                self.expand(id, &state);
+            }
+            let Some(child) = self.select(id) else {
+                break self.playout(state);
+            };
+            let mover = state.current_player();
+            let mv = self.nodes[child as usize]
+                .mv
+                .as_ref()
+                .expect("children have moves");
+            state = state.apply_move(mv);
+            path.push((child, Some(mover)));
+            id = child;
+            if fresh {
+                break self.playout(state);
+            }
+        };
+        for &(id, mover) in path.iter() {
+            let node = &mut self.nodes[id as usize];
+            node.visits += 1;
+            if let Some(mover) = mover {
+                node.reward += match mover {
+                    Player::Player1 => f64::from(value + 1) / 2.0,
+                    Player::Player2 => f64::from(1 - value) / 2.0,
+                };
+            }
+        }
+    }
+
+    /// Runs `iterations` iterations from `state`, reusing what the tree
+    /// already knows about it, and returns the most visited move with its
+    /// average reward for the player to move (0 to 1), or `None` if the
+    /// game is over.
+    pub fn search(&mut self, state: &G, iterations: u32) -> Option<(G::Move, f64)>
+    where
+        G: PositionKey,
+    {
+        if state.is_terminal() {
+            return None;
+        }
+        self.move_root_to(state);
+        let iterations = iterations.max(1);
+        self.warm_start = Some(MctsWarmStart {
+            kept_nodes: self.node_count() - 1,
+            kept_visits: self.root_visits(),
+            iterations,
+        });
+        let mut path = Vec::new();
+        for _ in 0..iterations {
+            self.iterate(state, &mut path);
+        }
+        let best = self
+            .children(self.root)
+            .reduce(|best, child| {
+                if self.nodes[child as usize].visits > self.nodes[best as usize].visits {
+                    child
+                } else {
+                    best
+                }
+            })
+            .map(|id| &self.nodes[id as usize])?;
+        Some((
+            best.mv.clone().expect("children have moves"),
+            best.reward / f64::from(best.visits.max(1)),
+        ))
+    }
+}
+
+/// An agent that plays the move `MctsTree::search` prefers after a fixed
+/// number of iterations, keeping its tree from one move to the next.
+///
+/// Its decisions carry no value: MCTS estimates winning chances, not
+/// scores on the search's scale.
+#[derive(Clone)]
+pub struct MctsAgent<G: GameState, R = SplitMix64, E = HeuristicEvaluator> {
+    /// Iterations per move.
+    pub iterations: u32,
+    tree: MctsTree<G, R, E>,
+}
+
+impl<G: GameState> MctsAgent<G> {
+    /// Creates an agent running `iterations` iterations per move, with
+    /// playouts determined by `seed`.
+    pub fn new(iterations: u32, seed: u64) -> Self {
+        Self::with_tree(iterations, MctsTree::new(seed))
+    }
+}
+
+impl<G: GameState, R: Rng, E: Evaluator<G>> MctsAgent<G, R, E> {
+    /// Creates an agent running `iterations` iterations per move in
+    /// `tree`, e.g. one built with another `Rng`, exploration constant or
+    /// evaluator.
+    pub fn with_tree(iterations: u32, tree: MctsTree<G, R, E>) -> Self {
+        Self { iterations, tree }
+    }
+
+    /// The agent's search tree.
+    pub fn tree(&self) -> &MctsTree<G, R, E> {
+        &self.tree
+    }
+}
+
+impl<G: GameState + PositionKey, R: Rng, E: Evaluator<G>> Agent<G> for MctsAgent<G, R, E> {
+    fn select_move(&mut self, state: &G) -> Option<Decision<G::Move>> {
+        self.tree
+            .search(state, self.iterations)
+            .map(|(mv, _)| Decision { mv, value: None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::play_game;
+    use crate::eval::FnEvaluator;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt_bitboard::TicTacToeBitboard;
+    use crate::rng::RandomAgent;
+
+    #[test]
+    fn finds_winning_and_saving_moves() {
+        let mut tree = MctsTree::new(1);
+        let state = BitboardState::from_move_sequence("001122").unwrap();
+        let (mv, reward) = tree.search(&state, 2000).unwrap();
+        assert_eq!(mv, 3);
+        assert!(reward > 0.9);
+        // Player2 must block the same column.
+        let state = BitboardState::from_move_sequence("00112").unwrap();
+        assert_eq!(tree.search(&state, 4000).unwrap().0, 3);
+        assert_eq!(
+            tree.search(&BitboardState::from_move_sequence("0011223").unwrap(), 10),
+            None
+        );
+    }
+
+    #[test]
+    fn decided_positions_stay_leaves() {
+        let mut tree = MctsTree::new(3);
+        let state = BitboardState::from_move_sequence("001122").unwrap();
+        tree.search(&state, 2000);
+        let win = tree
+            .children(tree.root)
+            .find(|&child| tree.nodes[child as usize].mv == Some(3))
+            .unwrap();
+        let node = &tree.nodes[win as usize];
+        assert!(node.visits > 100);
+        assert!(!node.expanded);
+        assert_eq!(node.first_child, NONE);
+        assert_eq!(node.reward, f64::from(node.visits));
+    }
+
+    #[test]
+    fn reuses_the_subtree_and_recycles_the_rest() {
+        let mut tree = MctsTree::new(7);
+        let start = BitboardState::new();
+        tree.search(&start, 2000);
+        let len = tree.arena_len();
+        assert_eq!(tree.node_count(), len);
+        assert_eq!(tree.root_visits(), 2000);
+
+        // Two plies later the subtree below them is kept, and the slots
+        // freed by the rest hold the new nodes.
+        let later = start.apply_move(&3).apply_move(&3);
+        tree.search(&later, 100);
+        let warm = tree.warm_start().unwrap();
+        assert!(warm.kept_visits > 0);
+        assert!(warm.kept_nodes > 0);
+        assert_eq!(tree.root_visits(), warm.kept_visits + 100);
+        assert_eq!(tree.arena_len(), len);
+        assert!(tree.node_count() < len);
+
+        let mut cold = MctsTree::new(7).with_reuse(false);
+        cold.search(&start, 2000);
+        cold.search(&later, 100);
+        assert_eq!(cold.warm_start().unwrap().kept_visits, 0);
+        assert_eq!(cold.root_visits(), 100);
+
+        // An unrelated position starts over.
+        tree.search(&BitboardState::from_move_sequence("0123").unwrap(), 10);
+        assert_eq!(tree.root_visits(), 10);
+    }
+
+    #[test]
+    fn evaluators_score_cut_off_playouts() {
+        // Random playouts favor the center; this evaluator only cares
+        // whether column 5 has been played, which Player1 can settle at
+        // once.
+        let column_five =
+            || FnEvaluator::new(|s: &BitboardState| if s.heights()[5] > 0 { 1 } else { -1 });
+        let start = BitboardState::new();
+        assert_ne!(MctsTree::new(3).search(&start, 2000).unwrap().0, 5);
+        let mut tree = MctsTree::new(3).with_evaluator(column_five());
+        let (mv, reward) = tree.search(&start, 2000).unwrap();
+        assert_eq!(mv, 5);
+        assert!(reward > 0.9);
+        let mut tree = MctsTree::new(3)
+            .with_evaluator(column_five())
+            .with_playout_plies(2);
+        assert_eq!(tree.search(&start, 2000).unwrap().0, 5);
+
+        // Finished games are still scored by the game.
+        let mut tree = MctsTree::new(1).with_evaluator(FnEvaluator::new(|_: &BitboardState| 0));
+        let state = BitboardState::from_move_sequence("001122").unwrap();
+        assert_eq!(tree.search(&state, 500).unwrap().0, 3);
+        let mut agent = MctsAgent::with_tree(500, tree);
+        assert_eq!(agent.select_move(&state).unwrap().mv, 3);
+    }
+
+    #[test]
+    fn agent_beats_random_play() {
+        let start = TicTacToeBitboard::new();
+        let mut wins = 0;
+        for seed in 0..10 {
+            let (end, _) = play_game(
+                &start,
+                &mut MctsAgent::new(300, seed),
+                &mut RandomAgent::new(seed),
+            );
+            if end.terminal_value() == Some(1) {
+                wins += 1;
+            }
+            let (end, _) = play_game(
+                &start,
+                &mut RandomAgent::new(seed),
+                &mut MctsAgent::new(300, seed),
+            );
+            assert_ne!(end.terminal_value(), Some(1), "seed {seed}");
+        }
+        assert!(wins >= 8, "{wins} wins");
+    }
+}
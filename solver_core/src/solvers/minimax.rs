@@ -1,4 +1,89 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::cancel::CancelToken;
+use crate::eval::{Evaluator, HeuristicEvaluator};
 use crate::game::{GameState, Player};
+use crate::trace::search_span;
+
+/// Most plies a search descends below its root by default.
+///
+/// The searches recurse once per ply, so this bounds their stack use on
+/// games with very long lines. Depth-limited searches never look further
+/// ahead than this; the exact solvers score positions this deep as draws
+/// (see `minimax_value_ab_max_ply`). Tic-Tac-Toe and Connect Four games are
+/// far shorter, so their results are unaffected.
+pub const DEFAULT_MAX_PLY: u32 = 256;
+
+/// Move lists recycled across a search.
+///
+/// Each node takes a list, fills it, and gives it back when done. Because
+/// the search is depth-first, the pool holds at most one list per ply, so
+/// after the first descent no node allocates.
+pub(crate) struct MovePool<M> {
+    lists: Vec<Vec<M>>,
+}
+
+impl<M> MovePool<M> {
+    pub(crate) fn new() -> Self {
+        Self { lists: Vec::new() }
+    }
+
+    /// Returns the legal moves of `state`, highest `move_ordering_key`
+    /// first (ties keep generation order).
+    pub(crate) fn ordered_moves<G: GameState<Move = M>>(&mut self, state: &G) -> Vec<M> {
+        let mut moves = self.lists.pop().unwrap_or_default();
+        state.for_each_legal_move(|mv| moves.push(mv));
+        // Higher move_ordering_key = more promising for the current player
+        moves.sort_by_key(|m| core::cmp::Reverse(state.move_ordering_key(m)));
+        moves
+    }
+
+    pub(crate) fn give_back(&mut self, mut moves: Vec<M>) {
+        moves.clear();
+        self.lists.push(moves);
+    }
+}
+
+/// The counter-move heuristic: for each move, the reply that last caused a
+/// cutoff after it.
+///
+/// A reply that refutes a move in one position often refutes it in sibling
+/// positions too, so it is worth trying early there. Moves are only
+/// compared for equality, so the table is a short list searched linearly;
+/// it holds at most one entry per side and distinct move.
+pub(crate) struct CounterMoves<M> {
+    /// (replying side, move replied to, reply)
+    replies: Vec<(Player, M, M)>,
+}
+
+impl<M: Clone + PartialEq> CounterMoves<M> {
+    pub(crate) fn new() -> Self {
+        Self {
+            replies: Vec::new(),
+        }
+    }
+
+    /// The reply with which `side` last refuted `previous`, if any.
+    pub(crate) fn get(&self, side: Player, previous: &M) -> Option<&M> {
+        self.replies
+            .iter()
+            .find(|(s, m, _)| *s == side && m == previous)
+            .map(|(_, _, reply)| reply)
+    }
+
+    /// Remembers `reply` as `side`'s refutation of `previous`.
+    pub(crate) fn record(&mut self, side: Player, previous: M, reply: &M) {
+        match self
+            .replies
+            .iter_mut()
+            .find(|(s, m, _)| *s == side && *m == previous)
+        {
+            Some((_, _, r)) => r.clone_from(reply),
+            None => self.replies.push((side, previous, reply.clone())),
+        }
+    }
+}
 
 /// Computes the minimax value of a state from Player1's perspective.
 ///
@@ -9,20 +94,36 @@ use crate::game::{GameState, Player};
 /// - -1 if Player1 is losing
 /// -  0 if the position is a forced draw
 ///
-/// This version only returns the value, not the best move.
+/// This version only returns the value, not the best move. Positions
+/// more than `DEFAULT_MAX_PLY` plies deep count as draws.
 pub fn minimax_value<G: GameState>(state: &G) -> i32 {
+    plain_value(state, DEFAULT_MAX_PLY)
+}
+
+fn plain_value<G: GameState>(state: &G, plies_left: u32) -> i32 {
     if state.is_terminal() {
         return state.terminal_value().unwrap();
     }
-    let mvs = state
-        .legal_moves()
-        .into_iter()
-        .map(|mv| minimax_value(&state.apply_move(&mv)));
-    match state.current_player() {
-        Player::Player1 => mvs.max(),
-        Player::Player2 => mvs.min(),
+    if plies_left == 0 {
+        return 0;
     }
-    .unwrap()
+    let maximizing = state.current_player() == Player::Player1;
+    let mut best: Option<i32> = None;
+    state.for_each_legal_move(|mv| {
+        let v = plain_value(&state.apply_move(&mv), plies_left - 1);
+        best = Some(match best {
+            Some(b) if maximizing => b.max(v),
+            Some(b) => b.min(v),
+            None => v,
+        });
+    });
+    best.unwrap_or_else(|| no_moves_value(state))
+}
+
+/// The exact value of a position that isn't terminal but has no legal
+/// moves, by the game's `NoMovesRule`.
+pub(crate) fn no_moves_value<G: GameState>(state: &G) -> i32 {
+    state.no_moves_rule().value(state.current_player())
 }
 
 /// Computes the best move and its minimax value.
@@ -41,7 +142,7 @@ pub fn minimax_best_move<G: GameState>(state: &G) -> Option<(G::Move, i32)> {
     let mut best_move = None;
 
     for mv in &moves {
-        let child_value = minimax_value(&state.apply_move(mv));
+        let child_value = plain_value(&state.apply_move(mv), DEFAULT_MAX_PLY - 1);
 
         let is_better = if maximizing {
             child_value > best_value
@@ -58,95 +159,327 @@ pub fn minimax_best_move<G: GameState>(state: &G) -> Option<(G::Move, i32)> {
     best_move.map(|m| (m, best_value))
 }
 
-/// Computes the minimax value with alpha-beta pruning.
-///
-/// `alpha` is the best value that Player1 (maximizing player) has guaranteed so far.
-/// `beta` is the best value that Player2 (minimizing player) has guaranteed so far.
-///
-/// Returns:
-/// - +1, 0, or -1 depending on perfect play outcome.
+/// Score of a won position in depth-limited searches, chosen to outrank
+/// any evaluation: values at least this large are proven wins.
+pub const WIN_SCORE: i32 = 1_000_000;
+
+/// Settings for one alpha-beta search (see `alpha_beta`).
 ///
-/// IMPORTANT:
-/// - This function must prune branches where `alpha >= beta`.
-/// - This is the core recursive function; the user should usually call
-///   `minimax_value_ab` instead.
-pub fn minimax_value_ab<G: GameState>(state: &G, mut alpha: i32, mut beta: i32) -> i32 {
-    if state.is_terminal() {
-        return state.terminal_value().unwrap();
+/// `Default` solves exactly with a full window and `DEFAULT_MAX_PLY`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchParams {
+    /// Plies to search before scoring positions with the evaluator, or
+    /// `None` to search every line to the end of the game.
+    ///
+    /// Exact searches score wins as ±1, depth-limited ones as ±1_000_000
+    /// so that they outrank any evaluation.
+    pub depth: Option<u32>,
+    /// Most plies to search below the root, whatever `depth` says. Exact
+    /// searches score positions this deep as draws.
+    pub max_ply: u32,
+    /// Initial lower bound of the search window.
+    pub alpha: i32,
+    /// Initial upper bound of the search window.
+    pub beta: i32,
+    /// How much the side to move at the root dislikes a draw, in
+    /// evaluation units: depth-limited searches score drawn positions as
+    /// `-contempt` for that side instead of 0. A positive contempt makes
+    /// the engine avoid draws while it has anything better, e.g. against a
+    /// weaker opponent; a negative one makes it settle for them.
+    ///
+    /// Its magnitude is capped below ±1_000_000, so forced wins and losses
+    /// still outrank every draw. Exact searches ignore it: their ±1 scale
+    /// has no room between a draw and a result.
+    ///
+    /// `Search::contempt`, `MinimaxAgent::contempt`, `SearchAgent::contempt`
+    /// and the `_with_contempt` MTD(f) drivers set it for their searches.
+    pub contempt: i32,
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        Self {
+            depth: None,
+            max_ply: DEFAULT_MAX_PLY,
+            alpha: i32::MIN,
+            beta: i32::MAX,
+            contempt: 0,
+        }
     }
-    let maximizing = state.current_player() == Player::Player1;
-    let mut value = if maximizing { i32::MIN } else { i32::MAX };
-    let mut moves = state.legal_moves();
+}
 
-    // Higher move_ordering_key = more promising for the current player
-    moves.sort_by_key(|m| std::cmp::Reverse(state.move_ordering_key(m)));
-    for mv in &moves {
-        let child_value = minimax_value_ab(&state.apply_move(mv), alpha, beta);
+impl SearchParams {
+    /// A full-window search to `depth` plies.
+    pub fn depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
 
-        if maximizing {
-            value = value.max(child_value);
-            alpha = alpha.max(value);
-        } else {
-            value = value.min(child_value);
-            beta = beta.min(value);
+    /// Plies below the root at which positions are scored without
+    /// searching further.
+    fn horizon(&self) -> u32 {
+        self.depth
+            .map_or(self.max_ply, |depth| depth.min(self.max_ply))
+    }
+
+    /// The score of a draw from Player1's perspective when `root` is to
+    /// move at the root.
+    pub(crate) fn draw_score(&self, root: Player) -> i32 {
+        let contempt = self.contempt.clamp(1 - WIN_SCORE, WIN_SCORE - 1);
+        match root {
+            Player::Player1 => -contempt,
+            Player::Player2 => contempt,
         }
+    }
+}
+
+/// Counters collected during a search.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchStats {
+    /// Number of positions visited (every call into the recursive search,
+    /// including the root's children and leaves).
+    pub nodes: u64,
+}
 
-        if alpha >= beta {
-            break;
+/// Scratch state threaded through one search.
+struct SearchContext<'a, M> {
+    stats: SearchStats,
+    pool: MovePool<M>,
+    cancel: Option<&'a CancelToken>,
+}
+
+impl<'a, M> SearchContext<'a, M> {
+    fn new(cancel: Option<&'a CancelToken>) -> Self {
+        Self {
+            stats: SearchStats::default(),
+            pool: MovePool::new(),
+            cancel,
         }
     }
-    value
+
+    fn cancelled(&self) -> bool {
+        self.cancel.is_some_and(CancelToken::is_cancelled)
+    }
 }
 
-/// Computes the minimax value with alpha-beta pruning,
-/// using the full range [-∞, +∞] as the initial bounds.
-pub fn minimax_value_ab_root<G: GameState>(state: &G) -> i32 {
-    minimax_value_ab(state, i32::MIN, i32::MAX)
+/// The alpha-beta search behind the exact and depth-limited searches of
+/// this module.
+///
+/// The root is searched like any other node, except that the best move
+/// found there is remembered in `root_move`.
+struct AlphaBeta<'a, 'c, G: GameState, E> {
+    params: SearchParams,
+    evaluator: &'a E,
+    ctx: SearchContext<'c, G::Move>,
+    root_move: Option<G::Move>,
+    /// The score of drawn positions, by `params.contempt`.
+    draw_score: i32,
+    /// With `Some`, the best line found below each ply of the current path
+    /// (a triangular PV table), so the root's entry is the principal
+    /// variation once the search is done.
+    pv: Option<Vec<Vec<G::Move>>>,
 }
 
-fn minimax_best_move_ab_inner<G: GameState>(
-    state: &G,
-    mut alpha: i32,
-    mut beta: i32,
-) -> Option<(G::Move, i32)> {
-    let mut moves = state.legal_moves();
-    if moves.is_empty() {
-        return None;
+impl<'a, 'c, G: GameState, E: Evaluator<G>> AlphaBeta<'a, 'c, G, E> {
+    fn new(params: SearchParams, evaluator: &'a E, cancel: Option<&'c CancelToken>) -> Self {
+        Self {
+            params,
+            evaluator,
+            ctx: SearchContext::new(cancel),
+            root_move: None,
+            draw_score: 0,
+            pv: None,
+        }
     }
-    let maximizing = state.current_player() == Player::Player1;
 
-    // Higher move_ordering_key = more promising for the current player
-    moves.sort_by_key(|m| std::cmp::Reverse(state.move_ordering_key(m)));
+    /// Records the principal variation while searching (see `line`).
+    fn with_pv(mut self) -> Self {
+        self.pv = Some(Vec::new());
+        self
+    }
 
-    let mut best_value = if maximizing { i32::MIN } else { i32::MAX };
-    let mut best_move = None;
+    /// The principal variation of the last search, starting with the
+    /// root's best move; empty unless made `with_pv`.
+    fn line(&mut self) -> Vec<G::Move> {
+        self.pv
+            .as_mut()
+            .and_then(|pv| pv.first_mut())
+            .map(core::mem::take)
+            .unwrap_or_default()
+    }
 
-    for mv in &moves {
-        let child_value = minimax_value_ab(&state.apply_move(mv), alpha, beta);
+    /// Searches the root and returns its value. The root itself is not
+    /// counted in the statistics.
+    fn root(&mut self, state: &G) -> i32 {
+        self.draw_score = self.params.draw_score(state.current_player());
+        self.node(state, 0, self.params.alpha, self.params.beta)
+    }
 
-        let is_better = if maximizing {
-            child_value > best_value
+    /// The depth-limited score of a position whose exact value is `v`.
+    fn limited_score(&self, v: i32) -> i32 {
+        if v == 0 {
+            self.draw_score
         } else {
-            child_value < best_value
-        };
+            v * WIN_SCORE
+        }
+    }
 
-        if is_better {
-            best_value = child_value;
-            best_move = Some(mv.clone());
+    /// Returns the value of `state`, `ply` plies below the root, within the
+    /// window `alpha..beta`.
+    fn node(&mut self, state: &G, ply: u32, mut alpha: i32, mut beta: i32) -> i32 {
+        // The value is discarded by the caller once the search is cancelled.
+        if self.ctx.cancelled() {
+            return 0;
+        }
+        if let Some(pv) = &mut self.pv {
+            let ply = ply as usize;
+            if pv.len() <= ply {
+                pv.resize_with(ply + 1, Vec::new);
+            }
+            pv[ply].clear();
+        }
+        let exact = self.params.depth.is_none();
+        if let Some(v) = state.terminal_value() {
+            return if exact { v } else { self.limited_score(v) };
+        }
+        if ply >= self.params.horizon() {
+            return if exact {
+                0
+            } else {
+                self.evaluator.evaluate(state)
+            };
+        }
+        let maximizing = state.current_player() == Player::Player1;
+        let mut value = if maximizing { i32::MIN } else { i32::MAX };
+        let moves = self.ctx.pool.ordered_moves(state);
+        if moves.is_empty() {
+            self.ctx.pool.give_back(moves);
+            let v = no_moves_value(state);
+            return if exact { v } else { self.limited_score(v) };
         }
 
-        if maximizing {
-            alpha = alpha.max(best_value);
-        } else {
-            beta = beta.min(best_value);
+        for mv in &moves {
+            self.ctx.stats.nodes += 1;
+            let child_value = self.node(&state.apply_move(mv), ply + 1, alpha, beta);
+
+            let is_better = if maximizing {
+                child_value > value
+            } else {
+                child_value < value
+            };
+            if is_better {
+                value = child_value;
+                if ply == 0 {
+                    self.root_move = Some(mv.clone());
+                }
+                if let Some(pv) = &mut self.pv {
+                    let (line, below) = pv.split_at_mut(ply as usize + 1);
+                    let line = &mut line[ply as usize];
+                    line.clear();
+                    line.push(mv.clone());
+                    line.extend(below.first().into_iter().flatten().cloned());
+                }
+            }
+
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+
+            if alpha >= beta {
+                break;
+            }
         }
+        self.ctx.pool.give_back(moves);
+        value
+    }
 
-        if alpha >= beta {
-            break;
+    /// Searches the root and returns the best move with its value.
+    fn best_move(&mut self, state: &G) -> Option<(G::Move, i32)> {
+        // The root's moves are always searched, even at depth 0.
+        if let Some(depth) = &mut self.params.depth {
+            *depth = (*depth).max(1);
         }
+        let value = self.root(state);
+        self.root_move.take().map(|mv| (mv, value))
     }
+}
 
-    best_move.map(|m| (m, best_value))
+/// Searches `state` with `params`, scoring positions at the depth limit
+/// with `evaluator`, and returns the best move with its value (`None` if
+/// there are no legal moves, or `params.max_ply` is 0) together with
+/// search statistics.
+///
+/// Every alpha-beta search in this module is a thin wrapper around this
+/// one. A depth-limited search always searches the root's moves, even at
+/// depth 0.
+pub fn alpha_beta<G: GameState, E: Evaluator<G>>(
+    state: &G,
+    params: SearchParams,
+    evaluator: &E,
+) -> (Option<(G::Move, i32)>, SearchStats) {
+    let mut search = AlphaBeta::new(params, evaluator, None);
+    let best = search.best_move(state);
+    (best, search.ctx.stats)
+}
+
+/// Computes the minimax value with alpha-beta pruning.
+///
+/// `alpha` is the best value that Player1 (maximizing player) has guaranteed so far.
+/// `beta` is the best value that Player2 (minimizing player) has guaranteed so far.
+///
+/// Returns:
+/// - +1, 0, or -1 depending on perfect play outcome.
+///
+/// IMPORTANT:
+/// - This function must prune branches where `alpha >= beta`.
+/// - This is the core recursive function; the user should usually call
+///   `minimax_value_ab` instead.
+pub fn minimax_value_ab<G: GameState>(state: &G, alpha: i32, beta: i32) -> i32 {
+    let params = SearchParams {
+        alpha,
+        beta,
+        ..SearchParams::default()
+    };
+    AlphaBeta::new(params, &HeuristicEvaluator, None).root(state)
+}
+
+/// Computes the minimax value with alpha-beta pruning,
+/// using the full range [-∞, +∞] as the initial bounds.
+pub fn minimax_value_ab_root<G: GameState>(state: &G) -> i32 {
+    minimax_value_ab(state, i32::MIN, i32::MAX)
+}
+
+/// Like `minimax_value_ab_root`, but searches at most `max_ply` plies deep
+/// instead of `DEFAULT_MAX_PLY`.
+///
+/// Positions the search cannot resolve within `max_ply` plies count as
+/// draws, so the result is only exact if every line ends by then.
+pub fn minimax_value_ab_max_ply<G: GameState>(state: &G, max_ply: u32) -> i32 {
+    let params = SearchParams {
+        max_ply,
+        ..SearchParams::default()
+    };
+    AlphaBeta::new(params, &HeuristicEvaluator, None).root(state)
+}
+
+fn minimax_best_move_ab_inner<G: GameState>(
+    state: &G,
+    alpha: i32,
+    beta: i32,
+    max_ply: u32,
+) -> Option<(G::Move, i32)> {
+    let params = SearchParams {
+        max_ply,
+        alpha,
+        beta,
+        ..SearchParams::default()
+    };
+    alpha_beta(state, params, &HeuristicEvaluator).0
 }
 
 /// Computes the best move using alpha-beta pruning.
@@ -157,49 +490,51 @@ fn minimax_best_move_ab_inner<G: GameState>(
 ///
 /// This should prune as much as possible during search.
 pub fn minimax_best_move_ab<G: GameState>(state: &G) -> Option<(G::Move, i32)> {
-    minimax_best_move_ab_inner(state, i32::MIN, i32::MAX)
+    minimax_best_move_ab_inner(state, i32::MIN, i32::MAX, DEFAULT_MAX_PLY)
+}
+
+/// Like `minimax_best_move_ab`, but searches at most `max_ply` plies deep
+/// (see `minimax_value_ab_max_ply`). Returns `None` if `max_ply` is 0.
+pub fn minimax_best_move_ab_max_ply<G: GameState>(
+    state: &G,
+    max_ply: u32,
+) -> Option<(G::Move, i32)> {
+    minimax_best_move_ab_inner(state, i32::MIN, i32::MAX, max_ply)
 }
 
 /// Depth-limited alpha-beta minimax.
 ///
-/// - `depth` = maximum remaining ply to search.
+/// - `depth` = maximum remaining ply to search, at most `DEFAULT_MAX_PLY`.
 /// - Uses `state.heuristic_value()` when depth == 0 or at terminal states.
-pub fn minimax_value_ab_depth<G: GameState>(
+pub fn minimax_value_ab_depth<G: GameState>(state: &G, depth: u32, alpha: i32, beta: i32) -> i32 {
+    let params = SearchParams {
+        alpha,
+        beta,
+        ..SearchParams::depth(depth)
+    };
+    value_ab_depth(state, params, &HeuristicEvaluator)
+}
+
+/// Like `minimax_value_ab_depth_root`, but scores depth-0 leaves with
+/// `evaluator` instead of `state.heuristic_value()`.
+pub fn minimax_value_ab_depth_with_eval<G: GameState, E: Evaluator<G>>(
     state: &G,
     depth: u32,
-    mut alpha: i32,
-    mut beta: i32,
+    evaluator: &E,
 ) -> i32 {
-    if let Some(v) = state.terminal_value() {
-        return v * 1_000_000;
-    }
-
-    // At depth 0, use the heuristic only (non-terminal states).
-    if depth == 0 {
-        return state.heuristic_value();
-    }
-    let maximizing = state.current_player() == Player::Player1;
-    let mut value = if maximizing { i32::MIN } else { i32::MAX };
-    let mut moves = state.legal_moves();
-
-    // Higher move_ordering_key = more promising for the current player
-    moves.sort_by_key(|m| std::cmp::Reverse(state.move_ordering_key(m)));
-
-    for mv in &moves {
-        let child_value = minimax_value_ab_depth(&state.apply_move(mv), depth - 1, alpha, beta);
-
-        if maximizing {
-            value = value.max(child_value);
-            alpha = alpha.max(value);
-        } else {
-            value = value.min(child_value);
-            beta = beta.min(value);
-        }
+    value_ab_depth(state, SearchParams::depth(depth), evaluator)
+}
 
-        if alpha >= beta {
-            break;
-        }
-    }
+fn value_ab_depth<G: GameState, E: Evaluator<G>>(
+    state: &G,
+    params: SearchParams,
+    evaluator: &E,
+) -> i32 {
+    let span = search_span!("search", depth = params.horizon());
+    let mut search = AlphaBeta::new(params, evaluator, None);
+    let value = search.root(state);
+    // Count the root too, as it is a position like any other here.
+    span.finish(search.ctx.stats.nodes + 1);
     value
 }
 
@@ -213,58 +548,367 @@ pub fn minimax_value_ab_depth_root<G: GameState>(state: &G, depth: u32) -> i32 {
 pub fn minimax_best_move_ab_depth_inner<G: GameState>(
     state: &G,
     depth: u32,
-    mut alpha: i32,
-    mut beta: i32,
+    alpha: i32,
+    beta: i32,
 ) -> Option<(G::Move, i32)> {
-    let mut moves = state.legal_moves();
-    if moves.is_empty() {
-        return None;
-    }
+    let params = SearchParams {
+        alpha,
+        beta,
+        ..SearchParams::depth(depth)
+    };
+    best_move_ab_depth(AlphaBeta::new(params, &HeuristicEvaluator, None), state).0
+}
+
+/// Runs a depth-limited best-move `search` inside a trace span.
+fn best_move_ab_depth<G: GameState, E: Evaluator<G>>(
+    mut search: AlphaBeta<'_, '_, G, E>,
+    state: &G,
+) -> (Option<(G::Move, i32)>, SearchStats) {
+    let span = search_span!("search", depth = search.params.horizon());
+    let best = search.best_move(state);
+    span.finish(search.ctx.stats.nodes);
+    (best, search.ctx.stats)
+}
+
+/// Returns the best move and its value at the given search depth.
+///
+/// The search is deterministic: moves are tried in `legal_moves` order,
+/// stably sorted by descending `move_ordering_key`, and the best move is
+/// only replaced by a strictly better one. Among equally valued moves the
+/// first one in that order wins, so identical inputs give identical
+/// results on every run and platform.
+pub fn minimax_best_move_ab_depth<G: GameState>(state: &G, depth: u32) -> Option<(G::Move, i32)> {
+    minimax_best_move_ab_depth_inner(state, depth, i32::MIN, i32::MAX)
+}
+
+/// Returns every root move tied for the best depth-limited value, in search
+/// order, together with that value.
+///
+/// Each root move is searched with a full window so that its value is
+/// exact; this costs more than `minimax_best_move_ab_depth` but lets callers
+/// break ties themselves (e.g. randomly with a seeded `SplitMix64`).
+/// Returns `None` if there are no legal moves.
+pub fn minimax_best_moves_ab_depth<G: GameState>(
+    state: &G,
+    depth: u32,
+) -> Option<(Vec<G::Move>, i32)> {
+    let depth = depth.min(DEFAULT_MAX_PLY);
+    let span = search_span!("search", depth);
+    // Each child is the root of its own search, one ply shorter.
+    let params = SearchParams::depth(depth.saturating_sub(1));
+    let mut search = AlphaBeta::new(params, &HeuristicEvaluator, None);
+    let moves = search.ctx.pool.ordered_moves(state);
     let maximizing = state.current_player() == Player::Player1;
+    let mut best: Option<(Vec<G::Move>, i32)> = None;
+    for mv in moves {
+        search.ctx.stats.nodes += 1;
+        let value = search.root(&state.apply_move(&mv));
+        match &mut best {
+            Some((tied, best_value)) if value == *best_value => tied.push(mv),
+            Some((_, best_value))
+                if (maximizing && value < *best_value) || (!maximizing && value > *best_value) => {}
+            _ => best = Some((vec![mv], value)),
+        }
+    }
+    span.finish(search.ctx.stats.nodes);
+    best
+}
 
-    // Higher move_ordering_key = more promising for the current player
-    moves.sort_by_key(|m| std::cmp::Reverse(state.move_ordering_key(m)));
+/// Like `minimax_best_move_ab_depth`, but also returns search statistics.
+pub fn minimax_best_move_ab_depth_with_stats<G: GameState>(
+    state: &G,
+    depth: u32,
+) -> (Option<(G::Move, i32)>, SearchStats) {
+    minimax_best_move_ab_depth_with_eval(state, depth, &HeuristicEvaluator)
+}
 
-    let mut best_value = if maximizing { i32::MIN } else { i32::MAX };
-    let mut best_move = None;
+/// Like `minimax_best_move_ab_depth_with_stats`, but scores depth-0 leaves
+/// with `evaluator` instead of `state.heuristic_value()`.
+pub fn minimax_best_move_ab_depth_with_eval<G: GameState, E: Evaluator<G>>(
+    state: &G,
+    depth: u32,
+    evaluator: &E,
+) -> (Option<(G::Move, i32)>, SearchStats) {
+    best_move_ab_depth(
+        AlphaBeta::new(SearchParams::depth(depth), evaluator, None),
+        state,
+    )
+}
 
-    for mv in &moves {
-        let child_value = minimax_value_ab_depth(&state.apply_move(mv), depth - 1, alpha, beta);
+/// Like `minimax_best_move_ab_depth`, but gives up as soon as `cancel` is
+/// cancelled.
+///
+/// Returns `None` if the search was cancelled (its partial result is
+/// discarded) or if there are no legal moves.
+pub fn minimax_best_move_ab_depth_cancellable<G: GameState>(
+    state: &G,
+    depth: u32,
+    cancel: &CancelToken,
+) -> Option<(G::Move, i32)> {
+    best_move_depth(state, depth, Some(cancel)).0
+}
 
-        let is_better = if maximizing {
-            child_value > best_value
-        } else {
-            child_value < best_value
-        };
+/// Depth-limited best-move search with statistics, giving up (and
+/// returning `None`) as soon as `cancel` is cancelled.
+pub(crate) fn best_move_depth<G: GameState>(
+    state: &G,
+    depth: u32,
+    cancel: Option<&CancelToken>,
+) -> (Option<(G::Move, i32)>, SearchStats) {
+    let search = AlphaBeta::new(SearchParams::depth(depth), &HeuristicEvaluator, cancel);
+    let (best, stats) = best_move_ab_depth(search, state);
+    (
+        best.filter(|_| !cancel.is_some_and(CancelToken::is_cancelled)),
+        stats,
+    )
+}
 
-        if is_better {
-            best_value = child_value;
-            best_move = Some(mv.clone());
-        }
+/// A value with the principal variation that leads to it.
+pub(crate) type ValuedLine<M> = (i32, Vec<M>);
 
-        if maximizing {
-            alpha = alpha.max(best_value);
-        } else {
-            beta = beta.min(best_value);
-        }
+/// `best_move_depth` with `contempt` for the side to move, returning the
+/// principal variation (starting with the best move) instead of only the
+/// move.
+pub(crate) fn best_line_depth<G: GameState>(
+    state: &G,
+    depth: u32,
+    contempt: i32,
+    cancel: Option<&CancelToken>,
+) -> (Option<ValuedLine<G::Move>>, SearchStats) {
+    let params = SearchParams {
+        contempt,
+        ..SearchParams::depth(depth)
+    };
+    let mut search = AlphaBeta::new(params, &HeuristicEvaluator, cancel).with_pv();
+    let span = search_span!("search", depth = search.params.horizon());
+    let best = search.best_move(state);
+    span.finish(search.ctx.stats.nodes);
+    let line = search.line();
+    (
+        best.filter(|_| !search.ctx.cancelled())
+            .map(|(_, value)| (value, line)),
+        search.ctx.stats,
+    )
+}
 
-        if alpha >= beta {
-            break;
-        }
-    }
+/// The depth-limited value of `state` with `contempt` for the side to
+/// move, and its principal variation, or `None` if `cancel` was cancelled
+/// first. The statistics count `state` itself.
+pub(crate) fn value_depth<G: GameState>(
+    state: &G,
+    depth: u32,
+    contempt: i32,
+    cancel: Option<&CancelToken>,
+) -> (Option<ValuedLine<G::Move>>, SearchStats) {
+    let params = SearchParams {
+        contempt,
+        ..SearchParams::depth(depth)
+    };
+    let mut search = AlphaBeta::new(params, &HeuristicEvaluator, cancel).with_pv();
+    let value = search.root(state);
+    search.ctx.stats.nodes += 1;
+    let line = search.line();
+    let value = Some((value, line)).filter(|_| !search.ctx.cancelled());
+    (value, search.ctx.stats)
+}
 
-    best_move.map(|m| (m, best_value))
+/// The root moves of `state` in `legal_moves` order, stably sorted by
+/// descending `move_ordering_key`: the order every search here tries them.
+pub(crate) fn ordered_root_moves<G: GameState>(state: &G) -> Vec<G::Move> {
+    MovePool::new().ordered_moves(state)
 }
 
-pub fn minimax_best_move_ab_depth<G: GameState>(state: &G, depth: u32) -> Option<(G::Move, i32)> {
-    minimax_best_move_ab_depth_inner(state, depth, i32::MIN, i32::MAX)
+/// Searches `moves` (root moves of `state`) in the given order to `depth`
+/// plies, as `minimax_best_move_ab_depth` would, and returns each move's
+/// score, or `None` if `cancel` was cancelled first.
+///
+/// The window narrows as the moves are searched, so only the first of the
+/// best scores is exact; the others are bounds that show the move is no
+/// better.
+pub(crate) fn root_move_scores<G: GameState>(
+    state: &G,
+    moves: &[G::Move],
+    depth: u32,
+    cancel: Option<&CancelToken>,
+) -> (Option<Vec<i32>>, SearchStats) {
+    let span = search_span!("search", depth);
+    let mut search = AlphaBeta::new(
+        SearchParams::depth(depth.max(1)),
+        &HeuristicEvaluator,
+        cancel,
+    );
+    let maximizing = state.current_player() == Player::Player1;
+    let (mut alpha, mut beta) = (i32::MIN, i32::MAX);
+    let mut scores = Vec::with_capacity(moves.len());
+    for mv in moves {
+        search.ctx.stats.nodes += 1;
+        let score = search.node(&state.apply_move(mv), 1, alpha, beta);
+        if maximizing {
+            alpha = alpha.max(score);
+        } else {
+            beta = beta.min(score);
+        }
+        scores.push(score);
+    }
+    span.finish(search.ctx.stats.nodes);
+    let scores = Some(scores).filter(|_| !search.ctx.cancelled());
+    (scores, search.ctx.stats)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::{NoMovesRule, PositionKey};
     use crate::games::c4_bitboard::BitboardState;
     use crate::games::ttt::TicTacToeState;
+    use crate::solvers::mtdf::mtdf;
+    use crate::solvers::tt::{
+        ReplacementPolicy, TranspositionTable, minimax_best_move_ab_depth_tt,
+    };
+
+    /// A game of `plies` forced moves, won by whoever makes the last one.
+    #[derive(Clone)]
+    struct Marathon {
+        plies: u32,
+        to_move: Player,
+    }
+
+    impl GameState for Marathon {
+        type Move = ();
+
+        fn current_player(&self) -> Player {
+            self.to_move
+        }
+
+        fn legal_moves(&self) -> Vec<()> {
+            if self.plies == 0 { vec![] } else { vec![()] }
+        }
+
+        fn apply_move(&self, _: &()) -> Self {
+            Self {
+                plies: self.plies - 1,
+                to_move: match self.to_move {
+                    Player::Player1 => Player::Player2,
+                    Player::Player2 => Player::Player1,
+                },
+            }
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.plies == 0
+        }
+
+        fn terminal_value(&self) -> Option<i32> {
+            // The player to move has no moves left and loses.
+            (self.plies == 0).then_some(match self.to_move {
+                Player::Player1 => -1,
+                Player::Player2 => 1,
+            })
+        }
+    }
+
+    /// `Marathon` without the ending: after the last forced move the side
+    /// to move is simply stuck, and `rule` says what that means.
+    #[derive(Clone)]
+    struct Stuck {
+        plies: u32,
+        to_move: Player,
+        rule: NoMovesRule,
+    }
+
+    impl GameState for Stuck {
+        type Move = ();
+
+        fn current_player(&self) -> Player {
+            self.to_move
+        }
+
+        fn legal_moves(&self) -> Vec<()> {
+            if self.plies == 0 { vec![] } else { vec![()] }
+        }
+
+        fn apply_move(&self, _: &()) -> Self {
+            Self {
+                plies: self.plies - 1,
+                to_move: crate::utils::opposite_player(self.to_move),
+                ..*self
+            }
+        }
+
+        fn is_terminal(&self) -> bool {
+            false
+        }
+
+        fn terminal_value(&self) -> Option<i32> {
+            None
+        }
+
+        fn no_moves_rule(&self) -> NoMovesRule {
+            self.rule
+        }
+    }
+
+    impl PositionKey for Stuck {
+        fn position_key(&self) -> u64 {
+            u64::from(self.plies)
+        }
+    }
+
+    #[test]
+    fn running_out_of_moves_follows_the_games_rule() {
+        // Player2 is stuck after three moves.
+        for (rule, value) in [(NoMovesRule::LossForMover, 1), (NoMovesRule::Draw, 0)] {
+            let s = Stuck {
+                plies: 3,
+                to_move: Player::Player1,
+                rule,
+            };
+            assert_eq!(minimax_value(&s), value, "{rule:?}");
+            assert_eq!(minimax_value_ab_root(&s), value, "{rule:?}");
+            assert_eq!(minimax_best_move_ab(&s), Some(((), value)), "{rule:?}");
+            assert_eq!(
+                minimax_value_ab_depth_root(&s, 5),
+                value * WIN_SCORE,
+                "{rule:?}"
+            );
+            let mut tt = TranspositionTable::with_entries(64, ReplacementPolicy::TwoTier);
+            let (best, _) = minimax_best_move_ab_depth_tt(&s, 5, &mut tt);
+            assert_eq!(best, Some(((), value * WIN_SCORE)), "{rule:?}");
+            tt.clear();
+            let (best, _) = mtdf(&s, 5, 0, &mut tt);
+            assert_eq!(best, Some(((), value * WIN_SCORE)), "{rule:?}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "has no legal moves")]
+    fn running_out_of_moves_panics_where_impossible() {
+        let s = Stuck {
+            plies: 2,
+            to_move: Player::Player1,
+            rule: NoMovesRule::Impossible,
+        };
+        minimax_value_ab_root(&s);
+    }
+
+    #[test]
+    fn c4_search_is_deterministic() {
+        let s = BitboardState::from_move_sequence("3342").unwrap();
+        let first = minimax_best_move_ab_depth_with_stats(&s, 6);
+        for _ in 0..3 {
+            assert_eq!(minimax_best_move_ab_depth_with_stats(&s, 6), first);
+        }
+    }
+
+    #[test]
+    fn ttt_best_moves_lists_all_ties_in_order() {
+        // Every opening move draws with perfect play.
+        let (moves, value) = minimax_best_moves_ab_depth(&TicTacToeState::new(), 9).unwrap();
+        assert_eq!(value, 0);
+        assert_eq!(moves.len(), 9);
+        let (first, _) = minimax_best_move_ab_depth(&TicTacToeState::new(), 9).unwrap();
+        assert_eq!(moves[0], first);
+    }
 
     #[test]
     fn minimax_and_ab_agree_on_ttt_start() {
@@ -300,4 +944,152 @@ mod tests {
         assert_eq!(v0, s.heuristic_value());
         assert!(v1 >= v0);
     }
+
+    #[test]
+    fn custom_evaluator_drives_depth_limited_search() {
+        use crate::eval::FnEvaluator;
+
+        // An evaluator that only likes discs in column 0 (bit 0 of each column
+        // is the bottom row, so column 0 occupies bits 0..6).
+        let likes_col0 = FnEvaluator::new(|s: &BitboardState| {
+            let col0 = 0b111111u64;
+            (s.player_bb & col0).count_ones() as i32
+        });
+        let s = BitboardState::new();
+        let (best, _) = minimax_best_move_ab_depth_with_eval(&s, 1, &likes_col0);
+        assert_eq!(best, Some((0, 1)));
+
+        // The default heuristic prefers the center instead.
+        let (best, _) = minimax_best_move_ab_depth_with_stats(&s, 1);
+        assert_eq!(best.map(|(mv, _)| mv), Some(3));
+        assert_eq!(minimax_value_ab_depth_with_eval(&s, 1, &likes_col0), 1);
+    }
+
+    #[test]
+    fn stats_count_root_children_at_depth_one() {
+        let s = BitboardState::new();
+        let (best, stats) = minimax_best_move_ab_depth_with_stats(&s, 1);
+        assert!(best.is_some());
+        // Depth 1 visits each of the 7 children exactly once.
+        assert_eq!(stats.nodes, 7);
+
+        let (_, deeper) = minimax_best_move_ab_depth_with_stats(&s, 3);
+        assert!(deeper.nodes > stats.nodes);
+    }
+
+    #[test]
+    fn very_long_games_are_cut_off_at_max_ply() {
+        let marathon = Marathon {
+            plies: 1_000_000,
+            to_move: Player::Player1,
+        };
+        assert_eq!(minimax_value(&marathon), 0);
+        assert_eq!(minimax_value_ab_root(&marathon), 0);
+        assert_eq!(minimax_best_move_ab(&marathon), Some(((), 0)));
+        assert_eq!(minimax_value_ab_depth_root(&marathon, u32::MAX), 0);
+        assert_eq!(minimax_best_move_ab_max_ply(&marathon, 0), None);
+
+        let short = Marathon {
+            plies: 301,
+            to_move: Player::Player1,
+        };
+        assert_eq!(minimax_value_ab_root(&short), 0);
+        assert_eq!(minimax_value_ab_max_ply(&short, 301), 1);
+        assert_eq!(minimax_best_move_ab_max_ply(&short, 400), Some(((), 1)));
+    }
+
+    #[test]
+    fn alpha_beta_params_select_exact_or_depth_limited_search() {
+        let s = TicTacToeState::new();
+        let (exact, _) = alpha_beta(&s, SearchParams::default(), &HeuristicEvaluator);
+        assert_eq!(exact, minimax_best_move_ab(&s));
+
+        let c4 = BitboardState::new();
+        let (best, stats) = alpha_beta(&c4, SearchParams::depth(3), &HeuristicEvaluator);
+        assert_eq!((best, stats), minimax_best_move_ab_depth_with_stats(&c4, 3));
+        // Depth 0 still searches the root's moves.
+        let (best, stats) = alpha_beta(&c4, SearchParams::depth(0), &HeuristicEvaluator);
+        assert!(best.is_some());
+        assert_eq!(stats.nodes, 7);
+    }
+
+    /// Player1 can take a draw at once (move 0) or play on into a slightly
+    /// worse position (move 1) that Player2 can only draw.
+    #[derive(Clone)]
+    struct DrawOffer {
+        played_on: bool,
+        over: bool,
+        to_move: Player,
+    }
+
+    impl GameState for DrawOffer {
+        type Move = u8;
+
+        fn current_player(&self) -> Player {
+            self.to_move
+        }
+
+        fn legal_moves(&self) -> Vec<u8> {
+            match (self.over, self.played_on) {
+                (true, _) => vec![],
+                (false, false) => vec![0, 1],
+                (false, true) => vec![0],
+            }
+        }
+
+        fn apply_move(&self, mv: &u8) -> Self {
+            Self {
+                played_on: *mv == 1,
+                over: *mv == 0,
+                to_move: crate::utils::opposite_player(self.to_move),
+            }
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.over
+        }
+
+        fn terminal_value(&self) -> Option<i32> {
+            self.over.then_some(0)
+        }
+
+        fn heuristic_value(&self) -> i32 {
+            if self.played_on { -5 } else { 0 }
+        }
+    }
+
+    #[test]
+    fn contempt_makes_draws_less_attractive_than_playing_on() {
+        let s = DrawOffer {
+            played_on: false,
+            over: false,
+            to_move: Player::Player1,
+        };
+        let search = |contempt| {
+            let params = SearchParams {
+                contempt,
+                ..SearchParams::depth(1)
+            };
+            alpha_beta(&s, params, &HeuristicEvaluator).0
+        };
+        assert_eq!(search(0), Some((0, 0)));
+        assert_eq!(search(3), Some((0, -3)));
+        assert_eq!(search(10), Some((1, -5)));
+        assert_eq!(search(-10), Some((0, 10)));
+        // Exact searches keep their ±1 scale.
+        let params = SearchParams {
+            contempt: 10,
+            ..SearchParams::default()
+        };
+        assert_eq!(alpha_beta(&s, params, &HeuristicEvaluator).0, Some((0, 0)));
+
+        // Contempt is relative to the side to move, and never reaches a
+        // loss.
+        let params = SearchParams {
+            contempt: i32::MAX,
+            ..SearchParams::default()
+        };
+        assert_eq!(params.draw_score(Player::Player1), 1 - WIN_SCORE);
+        assert_eq!(params.draw_score(Player::Player2), WIN_SCORE - 1);
+    }
 }
@@ -1 +1,9 @@
+pub mod batched;
+pub mod exact;
+pub mod iterative;
+#[cfg(feature = "std")]
+pub mod mcts;
 pub mod minimax;
+pub mod mtdf;
+pub mod search;
+pub mod tt;
@@ -0,0 +1,188 @@
+use crate::eval::HeuristicEvaluator;
+use crate::game::{GameState, Player, PositionKey};
+use crate::solvers::minimax::{DEFAULT_MAX_PLY, SearchStats};
+use crate::solvers::tt::{TranspositionTable, TtSearch, best_move_tt};
+use crate::trace::{search_event, search_span};
+
+/// Which root driver `iterative_search` uses at each depth.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SearchAlgorithm {
+    /// One full-window alpha-beta search per depth.
+    #[default]
+    AlphaBeta,
+    /// MTD(f): a series of null-window searches per depth, seeded with the
+    /// previous depth's value.
+    Mtdf,
+}
+
+/// MTD(f) search of `state` to `depth` plies, starting from `first_guess`.
+///
+/// Repeats null-window alpha-beta searches through `tt`, narrowing the
+/// bounds on the root value until they meet. The closer `first_guess` is to
+/// the true value, the fewer passes are needed; games with few distinct
+/// values (like won/drawn/lost Connect Four positions) converge quickly.
+///
+/// Returns the best move and its value on the `minimax_value_ab_depth`
+/// scale, or `None` if the state has no legal moves.
+pub fn mtdf<G>(
+    state: &G,
+    depth: u32,
+    first_guess: i32,
+    tt: &mut TranspositionTable<G::Move>,
+) -> (Option<(G::Move, i32)>, SearchStats)
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    mtdf_with_contempt(state, depth, first_guess, 0, tt)
+}
+
+/// Like `mtdf`, but scores draws by `contempt` for the side to move (see
+/// `SearchParams::contempt`). `tt` must not hold results searched with
+/// another contempt or for the other side.
+pub fn mtdf_with_contempt<G>(
+    state: &G,
+    depth: u32,
+    first_guess: i32,
+    contempt: i32,
+    tt: &mut TranspositionTable<G::Move>,
+) -> (Option<(G::Move, i32)>, SearchStats)
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    let mut search = TtSearch::new(tt, &HeuristicEvaluator).with_contempt(state, contempt);
+    if state.is_terminal() {
+        return (None, search.stats);
+    }
+    let depth = depth.clamp(1, DEFAULT_MAX_PLY);
+    let span = search_span!("mtdf", depth, first_guess);
+    let maximizing = state.current_player() == Player::Player1;
+    let (mut lower, mut upper) = (i32::MIN, i32::MAX);
+    let mut g = first_guess;
+    let mut best = None;
+    while lower < upper {
+        let beta = if g == lower { g + 1 } else { g };
+        let (value, mv) = search.node(state, depth, beta - 1, beta);
+        search_event!(tracing::Level::TRACE, beta, value, "mtdf pass");
+        g = value;
+        // The move proving the side to move's bound is the best move.
+        if g < beta {
+            upper = g;
+            if !maximizing {
+                best = mv;
+            }
+        } else {
+            lower = g;
+            if maximizing {
+                best = mv;
+            }
+        }
+    }
+    span.finish(search.stats.nodes);
+    (best.map(|mv| (mv, g)), search.stats)
+}
+
+/// Searches depths 1, 2, ..., `max_depth` with `algorithm`, sharing `tt`
+/// across iterations so each depth is ordered by the previous one.
+///
+/// Returns the deepest result and the nodes visited over all iterations.
+pub fn iterative_search<G>(
+    state: &G,
+    max_depth: u32,
+    algorithm: SearchAlgorithm,
+    tt: &mut TranspositionTable<G::Move>,
+) -> (Option<(G::Move, i32)>, SearchStats)
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    iterative_search_with_contempt(state, max_depth, algorithm, 0, tt)
+}
+
+/// Like `iterative_search`, but scores draws by `contempt` for the side to
+/// move (see `SearchParams::contempt`). `tt` must not hold results searched
+/// with another contempt or for the other side.
+pub fn iterative_search_with_contempt<G>(
+    state: &G,
+    max_depth: u32,
+    algorithm: SearchAlgorithm,
+    contempt: i32,
+    tt: &mut TranspositionTable<G::Move>,
+) -> (Option<(G::Move, i32)>, SearchStats)
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    let mut stats = SearchStats::default();
+    let mut best = None;
+    for depth in 1..=max_depth.max(1) {
+        let span = search_span!("iteration", depth, ?algorithm);
+        let (result, iteration) = match algorithm {
+            SearchAlgorithm::AlphaBeta => best_move_tt(state, depth, contempt, tt, None),
+            SearchAlgorithm::Mtdf => {
+                let guess = best.as_ref().map_or(0, |(_, v)| *v);
+                mtdf_with_contempt(state, depth, guess, contempt, tt)
+            }
+        };
+        stats.nodes += iteration.nodes;
+        span.finish(iteration.nodes);
+        if result.is_none() {
+            break;
+        }
+        best = result;
+    }
+    (best, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+    use crate::solvers::minimax::minimax_best_move_ab_depth;
+    use crate::solvers::tt::ReplacementPolicy;
+
+    #[test]
+    fn ttt_mtdf_matches_alpha_beta_from_any_guess() {
+        for guess in [-1_000_000, -5, 0, 7, 1_000_000] {
+            let mut tt = TranspositionTable::with_entries(1 << 14, ReplacementPolicy::TwoTier);
+            let (best, _) = mtdf(&TicTacToeState::new(), 9, guess, &mut tt);
+            assert_eq!(best.map(|b| b.1), Some(0), "guess {guess}");
+        }
+        let s = TicTacToeState::new().apply_move(&TicTacToeState::new().legal_moves()[4]);
+        let mut tt = TranspositionTable::with_entries(1 << 14, ReplacementPolicy::TwoTier);
+        let (best, _) = mtdf(&s, 8, 0, &mut tt);
+        let (mv, value) = best.unwrap();
+        assert_eq!(value, 0);
+        assert_eq!(
+            minimax_best_move_ab_depth(&s.apply_move(&mv), 7).map(|b| b.1),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn c4_mtdf_values_match_alpha_beta() {
+        for seq in ["", "33", "060606", "3344", "0123"] {
+            let s = BitboardState::from_move_sequence(seq).unwrap();
+            let (_, expected) = minimax_best_move_ab_depth(&s, 6).unwrap();
+            for algorithm in [SearchAlgorithm::AlphaBeta, SearchAlgorithm::Mtdf] {
+                let mut tt = TranspositionTable::with_entries(1 << 16, ReplacementPolicy::TwoTier);
+                let (best, stats) = iterative_search(&s, 6, algorithm, &mut tt);
+                let (col, value) = best.unwrap();
+                assert!(s.can_play(col));
+                assert!(stats.nodes > 0);
+                assert_eq!(value, expected, "{seq:?} {algorithm:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn c4_mtdf_plays_the_winning_move() {
+        let s = BitboardState::from_move_sequence("060606").unwrap();
+        let mut tt = TranspositionTable::with_entries(1024, ReplacementPolicy::DepthPreferred);
+        let (best, _) = mtdf(&s, 4, 0, &mut tt);
+        assert_eq!(best, Some((0, 1_000_000)));
+    }
+}
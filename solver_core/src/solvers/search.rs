@@ -0,0 +1,566 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::thread;
+
+use crate::cancel::CancelToken;
+use crate::game::{GameState, Player, PositionKey};
+use crate::solvers::minimax::{
+    DEFAULT_MAX_PLY, MovePool, SearchStats, ValuedLine, WIN_SCORE, best_line_depth, value_depth,
+};
+use crate::solvers::tt::{ReplacementPolicy, TranspositionTable, best_move_tt, value_tt};
+use crate::trace::search_span;
+
+/// Best-move search through a table, as `tt::best_move_tt`.
+type TtBestMove<G, M> = fn(
+    &G,
+    u32,
+    i32,
+    &mut TranspositionTable<M>,
+    Option<&CancelToken>,
+) -> (Option<(M, i32)>, SearchStats);
+/// Value search through a table, as `tt::value_tt`.
+type TtValue<G, M> = fn(
+    &G,
+    u32,
+    i32,
+    &mut TranspositionTable<M>,
+    Option<&CancelToken>,
+) -> (Option<i32>, SearchStats);
+
+/// Root moves by index, with their values and the principal variations
+/// below them (`None` once the search is cancelled).
+type Found<M> = Vec<(usize, Option<ValuedLine<M>>)>;
+
+/// The line of best moves stored in a table, as `TranspositionTable::pv`.
+type TtPv<G, M> = fn(&TranspositionTable<M>, &G, u32) -> Vec<M>;
+
+/// The table searches of `Search::with_tt`, captured where the game is
+/// known to have position keys.
+struct TtConfig<G: GameState> {
+    megabytes: usize,
+    best_move: TtBestMove<G, G::Move>,
+    value: TtValue<G, G::Move>,
+    pv: TtPv<G, G::Move>,
+}
+
+/// One line of a search result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PvLine<M> {
+    /// The value of the line from Player1's perspective, on the
+    /// `minimax_value_ab_depth` scale (terminal values are ±1_000_000).
+    pub value: i32,
+    /// The principal variation, starting with the root move.
+    pub moves: Vec<M>,
+}
+
+/// What `Search::run` found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchResult<M> {
+    /// The best lines of the deepest completed depth, best first: `multipv`
+    /// of them, or one per legal move if there are fewer. Empty if the
+    /// position has no legal moves.
+    pub lines: Vec<PvLine<M>>,
+    /// The deepest completed depth, or 0 if none was.
+    pub depth: u32,
+    /// Counters summed over all depths and threads.
+    pub stats: SearchStats,
+    /// Whether the time limit or the cancel token ended the search.
+    pub stopped: bool,
+}
+
+impl<M> SearchResult<M> {
+    /// The best move, if there is one.
+    pub fn best_move(&self) -> Option<&M> {
+        self.lines.first().map(|line| &line.moves[0])
+    }
+
+    /// The value of the best move, if there is one.
+    pub fn value(&self) -> Option<i32> {
+        self.lines.first().map(|line| line.value)
+    }
+
+    /// The principal variation of the best move (empty without one).
+    pub fn pv(&self) -> &[M] {
+        self.lines.first().map_or(&[], |line| &line.moves)
+    }
+}
+
+/// A depth-limited search, configured step by step and started by `run`.
+///
+/// The search deepens iteratively up to `depth`, so a time limit or a
+/// cancelled token still leaves the result of the deepest completed depth.
+/// Depth 1 always completes, so there is a move whenever the position has
+/// one.
+///
+/// ```
+/// use solver_core::games::c4_bitboard::BitboardState;
+/// use solver_core::solvers::search::Search;
+///
+/// let result = Search::new(&BitboardState::new())
+///     .depth(6)
+///     .with_tt(4)
+///     .multipv(3)
+///     .run();
+/// assert_eq!(result.depth, 6);
+/// assert_eq!(result.lines.len(), 3);
+/// assert_eq!(result.best_move(), Some(&3));
+/// ```
+pub struct Search<'a, G: GameState> {
+    state: &'a G,
+    depth: Option<u32>,
+    time_limit: Option<Duration>,
+    cancel: Option<&'a CancelToken>,
+    tt: Option<TtConfig<G>>,
+    threads: usize,
+    multipv: usize,
+    contempt: i32,
+}
+
+impl<'a, G: GameState> Search<'a, G> {
+    /// A single-threaded search of `state` without a table, to the game's
+    /// `recommended_depth`.
+    pub fn new(state: &'a G) -> Self {
+        Self {
+            state,
+            depth: None,
+            time_limit: None,
+            cancel: None,
+            tt: None,
+            threads: 1,
+            multipv: 1,
+            contempt: 0,
+        }
+    }
+
+    /// Searches at most `depth` plies deep (at most `DEFAULT_MAX_PLY`).
+    ///
+    /// Without it, a search with a time limit goes as deep as the game can
+    /// last, and one without goes to the game's `recommended_depth`.
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Stops the search after `limit`, keeping the deepest completed depth.
+    /// A token given to `cancel` is cancelled when the time runs out.
+    #[cfg(feature = "std")]
+    pub fn time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Stops the search once `cancel` is cancelled, keeping the deepest
+    /// completed depth.
+    pub fn cancel(mut self, cancel: &'a CancelToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Searches through a two-tier transposition table of `megabytes` MiB,
+    /// shared across depths (and split between threads).
+    pub fn with_tt(mut self, megabytes: usize) -> Self
+    where
+        G: PositionKey,
+        G::Move: PartialEq,
+    {
+        self.tt = Some(TtConfig {
+            megabytes,
+            best_move: best_move_tt::<G>,
+            value: value_tt::<G>,
+            pv: TranspositionTable::pv::<G>,
+        });
+        self
+    }
+
+    /// Splits the root moves between `threads` threads (at least 1). Each
+    /// root move is then searched with a full window, which costs more
+    /// nodes than a single-threaded search of the same depth.
+    #[cfg(feature = "std")]
+    pub fn parallel(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Reports the best `lines` root moves (at least 1) with their values
+    /// and principal variations, instead of only the best one.
+    pub fn multipv(mut self, lines: usize) -> Self {
+        self.multipv = lines.max(1);
+        self
+    }
+
+    /// Scores draws as `-contempt` for the side to move at the root (see
+    /// `SearchParams::contempt`).
+    pub fn contempt(mut self, contempt: i32) -> Self {
+        self.contempt = contempt;
+        self
+    }
+
+    /// Runs the search.
+    pub fn run(self) -> SearchResult<G::Move>
+    where
+        G: Sync,
+        G::Move: Send + Sync,
+    {
+        self.run_reporting(|_| {})
+    }
+
+    /// Runs the search, passing the result so far to `on_depth` after each
+    /// completed depth.
+    pub fn run_reporting(
+        self,
+        mut on_depth: impl FnMut(&SearchResult<G::Move>),
+    ) -> SearchResult<G::Move>
+    where
+        G: Sync,
+        G::Move: Send + Sync,
+    {
+        let max_depth = match (self.depth, self.time_limit) {
+            (Some(depth), _) => depth,
+            (None, Some(_)) => self
+                .state
+                .complexity_hint()
+                .max_plies
+                .unwrap_or(DEFAULT_MAX_PLY),
+            (None, None) => self.state.recommended_depth(),
+        }
+        .min(DEFAULT_MAX_PLY);
+        let own_token = CancelToken::new();
+        let cancel = self
+            .cancel
+            .or(self.time_limit.is_some().then_some(&own_token));
+        #[cfg(feature = "std")]
+        let _timer = self
+            .time_limit
+            .zip(cancel)
+            .map(|(limit, cancel)| cancel.cancel_after(limit));
+        let mut tables: Vec<Option<TranspositionTable<G::Move>>> = (0..self.threads)
+            .map(|_| {
+                self.tt.as_ref().map(|tt| {
+                    let bytes = tt.megabytes * 1024 * 1024 / self.threads;
+                    TranspositionTable::with_entries(
+                        bytes / TranspositionTable::<G::Move>::entry_size(),
+                        ReplacementPolicy::TwoTier,
+                    )
+                })
+            })
+            .collect();
+
+        let mut result = SearchResult {
+            lines: Vec::new(),
+            depth: 0,
+            stats: SearchStats::default(),
+            stopped: false,
+        };
+        for depth in 1..=max_depth {
+            let span = search_span!("iteration", depth);
+            let cancel = cancel.filter(|_| depth > 1);
+            let (lines, stats) = if self.multipv == 1 && self.threads == 1 {
+                self.best_line(depth, &mut tables[0], cancel)
+            } else {
+                self.root_lines(depth, &mut tables, cancel)
+            };
+            result.stats.nodes += stats.nodes;
+            span.finish(stats.nodes);
+            let Some(lines) = lines else {
+                result.stopped = true;
+                break;
+            };
+            if lines.is_empty() {
+                break;
+            }
+            // A proven win or loss cannot change at greater depths.
+            let proven = lines[0].value.abs() >= WIN_SCORE;
+            result.lines = lines;
+            result.depth = depth;
+            on_depth(&result);
+            if proven && self.multipv == 1 {
+                break;
+            }
+        }
+        result
+    }
+
+    /// The best line at `depth`, from a single alpha-beta search. Returns
+    /// `None` if the search was cancelled.
+    fn best_line(
+        &self,
+        depth: u32,
+        table: &mut Option<TranspositionTable<G::Move>>,
+        cancel: Option<&CancelToken>,
+    ) -> (Option<Vec<PvLine<G::Move>>>, SearchStats) {
+        let (best, stats) = match (&self.tt, table) {
+            (Some(tt), Some(table)) => {
+                let (best, stats) = (tt.best_move)(self.state, depth, self.contempt, table, cancel);
+                let line = best.map(|(_, value)| PvLine {
+                    value,
+                    moves: (tt.pv)(table, self.state, depth),
+                });
+                (line, stats)
+            }
+            _ => {
+                let (best, stats) = best_line_depth(self.state, depth, self.contempt, cancel);
+                (best.map(|(value, moves)| PvLine { value, moves }), stats)
+            }
+        };
+        if cancel.is_some_and(CancelToken::is_cancelled) {
+            return (None, stats);
+        }
+        (Some(best.into_iter().collect()), stats)
+    }
+
+    /// The best `multipv` lines at `depth`, from a full-window search of
+    /// every root move, spread over the threads. Returns `None` if the
+    /// search was cancelled.
+    fn root_lines(
+        &self,
+        depth: u32,
+        tables: &mut [Option<TranspositionTable<G::Move>>],
+        cancel: Option<&CancelToken>,
+    ) -> (Option<Vec<PvLine<G::Move>>>, SearchStats)
+    where
+        G: Sync,
+        G::Move: Send + Sync,
+    {
+        let moves = MovePool::new().ordered_moves(self.state);
+        let workers = tables.len().min(moves.len()).max(1);
+        let mut values: Vec<Option<ValuedLine<G::Move>>> = vec![None; moves.len()];
+        let mut stats = SearchStats::default();
+        let mut collect = |(found, worker_stats): (Vec<_>, SearchStats)| {
+            for (i, value) in found {
+                values[i] = value;
+            }
+            stats.nodes += worker_stats.nodes;
+        };
+        #[cfg(feature = "std")]
+        if workers > 1 {
+            thread::scope(|scope| {
+                let handles: Vec<_> = tables[..workers]
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(worker, table)| {
+                        let moves = &moves;
+                        scope.spawn(move || {
+                            self.search_moves(moves, worker, workers, depth, table, cancel)
+                        })
+                    })
+                    .collect();
+                for handle in handles {
+                    collect(handle.join().expect("search thread panicked"));
+                }
+            });
+        } else {
+            collect(self.search_moves(&moves, 0, 1, depth, &mut tables[0], cancel));
+        }
+        #[cfg(not(feature = "std"))]
+        collect(self.search_moves(&moves, 0, workers, depth, &mut tables[0], cancel));
+
+        let Some(values) = values.into_iter().collect::<Option<Vec<_>>>() else {
+            return (None, stats);
+        };
+        let maximizing = self.state.current_player() == Player::Player1;
+        let mut lines: Vec<PvLine<G::Move>> = moves
+            .into_iter()
+            .zip(values)
+            .map(|(mv, (value, rest))| {
+                let mut moves = vec![mv];
+                moves.extend(rest);
+                PvLine { value, moves }
+            })
+            .collect();
+        // Stable, so ties keep the search order.
+        lines.sort_by_key(|line| if maximizing { -line.value } else { line.value });
+        lines.truncate(self.multipv);
+        (Some(lines), stats)
+    }
+
+    /// Searches every `stride`-th move of `moves` from index `first`, and
+    /// returns each one's index, value and principal variation (`None`
+    /// once cancelled).
+    fn search_moves(
+        &self,
+        moves: &[G::Move],
+        first: usize,
+        stride: usize,
+        depth: u32,
+        table: &mut Option<TranspositionTable<G::Move>>,
+        cancel: Option<&CancelToken>,
+    ) -> (Found<G::Move>, SearchStats) {
+        let mut stats = SearchStats::default();
+        let mut found = Vec::new();
+        // Below the root, the side to move is the root's opponent.
+        let contempt = self.contempt.saturating_neg();
+        for (i, mv) in moves.iter().enumerate().skip(first).step_by(stride) {
+            let child = self.state.apply_move(mv);
+            let (value, child_stats) = match (&self.tt, &mut *table) {
+                (Some(tt), Some(table)) => {
+                    let (value, stats) = (tt.value)(&child, depth - 1, contempt, table, cancel);
+                    let line = |value| (value, (tt.pv)(table, &child, depth - 1));
+                    (value.map(line), stats)
+                }
+                _ => value_depth(&child, depth - 1, contempt, cancel),
+            };
+            stats.nodes += child_stats.nodes;
+            found.push((i, value));
+        }
+        (found, stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::{TicTacToeMove, TicTacToeState};
+    use crate::solvers::minimax::{minimax_best_move_ab_depth, minimax_best_moves_ab_depth};
+    use crate::solvers::mtdf::{SearchAlgorithm, iterative_search};
+
+    #[test]
+    fn c4_search_matches_the_free_functions() {
+        let s = BitboardState::new().apply_column_move(3);
+        let result = Search::new(&s).depth(5).run();
+        let (mv, value) = minimax_best_move_ab_depth(&s, 5).unwrap();
+        assert_eq!(
+            (result.best_move(), result.value()),
+            (Some(&mv), Some(value))
+        );
+        assert_eq!(result.pv().len(), 5);
+        assert_eq!(result.depth, 5);
+        assert!(!result.stopped);
+
+        let result = Search::new(&s).depth(5).with_tt(1).run();
+        let mut tt = TranspositionTable::with_megabytes(1, ReplacementPolicy::TwoTier);
+        let (best, _) = iterative_search(&s, 5, SearchAlgorithm::AlphaBeta, &mut tt);
+        assert_eq!(result.value(), best.map(|(_, v)| v));
+    }
+
+    #[test]
+    fn lines_lead_to_the_positions_that_give_their_values() {
+        use crate::eval::{Evaluator, HeuristicEvaluator};
+        let s = BitboardState::from_move_sequence("3324").unwrap();
+        let result = Search::new(&s).depth(5).multipv(3).run();
+        for line in result
+            .lines
+            .iter()
+            .chain(Search::new(&s).depth(5).run().lines.iter())
+        {
+            let end = line.moves.iter().fold(s.clone(), |s, mv| s.apply_move(mv));
+            let value = match end.terminal_value() {
+                Some(v) => v * WIN_SCORE,
+                None => {
+                    assert_eq!(line.moves.len(), 5);
+                    HeuristicEvaluator.evaluate(&end)
+                }
+            };
+            assert_eq!(value, line.value, "{:?}", line.moves);
+        }
+
+        // With a table, the line is read back from it.
+        let result = Search::new(&s).depth(5).with_tt(1).run();
+        let pv = result.pv();
+        assert_eq!(pv.first(), result.best_move());
+        assert!(pv.len() > 1 && pv.len() <= 5);
+        pv.iter().fold(s.clone(), |s, mv| {
+            assert!(s.legal_moves().contains(mv));
+            s.apply_move(mv)
+        });
+    }
+
+    #[test]
+    fn contempt_scores_draws_for_the_side_to_move() {
+        let start = TicTacToeState::new();
+        let reply = start.apply_move(&TicTacToeMove { index: 4 });
+        for contempt in [25, -25] {
+            let draw = |s: &TicTacToeState| {
+                let search = || Search::new(s).depth(9).contempt(contempt);
+                let values = [
+                    search().run().value(),
+                    search().with_tt(1).run().value(),
+                    search().multipv(2).run().value(),
+                    search().multipv(2).with_tt(1).run().value(),
+                ];
+                assert!(values.iter().all(|&v| v == values[0]), "{values:?}");
+                values[0].unwrap()
+            };
+            assert_eq!(draw(&start), -contempt);
+            assert_eq!(draw(&reply), contempt);
+        }
+    }
+
+    #[test]
+    fn c4_multipv_ranks_root_moves_and_threads_agree() {
+        let s = BitboardState::new();
+        let result = Search::new(&s).depth(4).multipv(3).run();
+        assert_eq!(result.lines.len(), 3);
+        assert!(result.lines.windows(2).all(|w| w[0].value >= w[1].value));
+        let (tied, value) = minimax_best_moves_ab_depth(&s, 4).unwrap();
+        assert_eq!(
+            (result.best_move(), result.value()),
+            (Some(&tied[0]), Some(value))
+        );
+
+        #[cfg(feature = "std")]
+        for threads in [2, 7, 16] {
+            let parallel = Search::new(&s).depth(4).multipv(3).parallel(threads).run();
+            assert_eq!(parallel.lines, result.lines, "{threads} threads");
+            let with_tt = Search::new(&s).depth(4).parallel(threads).with_tt(1).run();
+            assert_eq!(with_tt.best_move(), result.best_move(), "{threads} threads");
+        }
+        assert_eq!(Search::new(&s).depth(4).multipv(9).run().lines.len(), 7);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn time_limits_and_cancellation_keep_the_deepest_completed_depth() {
+        let s = BitboardState::new();
+        let result = Search::new(&s).time_limit(Duration::from_millis(50)).run();
+        assert!(result.stopped);
+        assert!(result.depth >= 1 && result.depth < 42);
+        assert!(result.best_move().is_some());
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let result = Search::new(&s).depth(8).cancel(&cancel).run();
+        assert!(result.stopped);
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    fn reports_each_completed_depth() {
+        let s = BitboardState::new();
+        let mut reports = Vec::new();
+        let result = Search::new(&s)
+            .depth(4)
+            .run_reporting(|r| reports.push((r.depth, r.stats.nodes, r.best_move().copied())));
+        assert_eq!(
+            reports.iter().map(|r| r.0).collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+        assert!(reports.windows(2).all(|w| w[0].1 < w[1].1));
+        assert_eq!(
+            reports[3],
+            (4, result.stats.nodes, result.best_move().copied())
+        );
+    }
+
+    #[test]
+    fn ttt_search_stops_at_proven_results_and_game_end() {
+        // X to move completes the first row.
+        let s = [0, 3, 1, 4]
+            .iter()
+            .fold(TicTacToeState::new(), |s, &index| {
+                s.apply_move(&TicTacToeMove { index })
+            });
+        let result = Search::new(&s).depth(9).run();
+        assert_eq!(result.best_move(), Some(&TicTacToeMove { index: 2 }));
+        assert_eq!(result.depth, 1);
+
+        let finished = s.apply_move(&TicTacToeMove { index: 2 });
+        let result = Search::new(&finished).run();
+        assert!(result.lines.is_empty());
+        assert_eq!(result.best_move(), None);
+    }
+}
@@ -0,0 +1,707 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use crate::cancel::CancelToken;
+use crate::eval::{Evaluator, HeuristicEvaluator};
+use crate::game::{GameState, Player, PositionKey};
+use crate::solvers::exact::SolveProgress;
+use crate::solvers::minimax::{
+    CounterMoves, DEFAULT_MAX_PLY, MovePool, SearchParams, SearchStats, WIN_SCORE, no_moves_value,
+};
+use crate::trace::{search_event, search_span};
+
+/// How a stored value relates to the true value of the position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    /// The value is exact.
+    Exact,
+    /// The true value is at least the stored value (the search failed high).
+    Lower,
+    /// The true value is at most the stored value (the search failed low).
+    Upper,
+}
+
+/// One stored search result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TtEntry<M> {
+    pub key: u64,
+    /// Remaining depth the position was searched to.
+    pub depth: u32,
+    /// Value from Player1's perspective, on the `minimax_value_ab_depth` scale.
+    pub value: i32,
+    pub bound: Bound,
+    /// Best (or refuting) move found, tried first when the position recurs.
+    pub best_move: Option<M>,
+}
+
+/// What to do when a new result maps to an occupied slot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplacementPolicy {
+    /// The newest result always wins.
+    #[default]
+    AlwaysReplace,
+    /// Keep the existing entry if it was searched deeper, unless it is for
+    /// the same position.
+    DepthPreferred,
+    /// Two slots per bucket: a depth-preferred slot and an always-replace
+    /// slot that catches whatever the first one rejects.
+    TwoTier,
+}
+
+/// Counters describing how a table has been used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TtStats {
+    /// Lookups performed.
+    pub probes: u64,
+    /// Lookups that found an entry for the position.
+    pub hits: u64,
+    /// Results written to the table.
+    pub stores: u64,
+    /// Stores that evicted an entry for a different position.
+    pub evictions: u64,
+    /// Stores dropped by the replacement policy.
+    pub rejected: u64,
+}
+
+impl TtStats {
+    /// Fraction of probes that hit, or 0 if nothing was probed.
+    pub fn hit_rate(&self) -> f64 {
+        if self.probes == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.probes as f64
+        }
+    }
+
+    /// The counts added since `earlier`, a snapshot of the same table.
+    pub fn since(self, earlier: TtStats) -> TtStats {
+        TtStats {
+            probes: self.probes - earlier.probes,
+            hits: self.hits - earlier.hits,
+            stores: self.stores - earlier.stores,
+            evictions: self.evictions - earlier.evictions,
+            rejected: self.rejected - earlier.rejected,
+        }
+    }
+}
+
+/// A fixed-capacity transposition table.
+///
+/// The table never grows after creation: its capacity is chosen up front in
+/// entries or megabytes, and a full table makes room according to its
+/// `ReplacementPolicy`.
+#[derive(Clone, Debug)]
+pub struct TranspositionTable<M> {
+    slots: Vec<Option<TtEntry<M>>>,
+    ways: usize,
+    policy: ReplacementPolicy,
+    used: usize,
+    stats: TtStats,
+}
+
+impl<M: Clone> TranspositionTable<M> {
+    /// Creates a table holding up to `entries` results (at least one bucket).
+    pub fn with_entries(entries: usize, policy: ReplacementPolicy) -> Self {
+        let ways = match policy {
+            ReplacementPolicy::TwoTier => 2,
+            _ => 1,
+        };
+        let buckets = (entries / ways).max(1);
+        Self {
+            slots: vec![None; buckets * ways],
+            ways,
+            policy,
+            used: 0,
+            stats: TtStats::default(),
+        }
+    }
+
+    /// Creates a table using at most `megabytes` MiB for its slots.
+    pub fn with_megabytes(megabytes: usize, policy: ReplacementPolicy) -> Self {
+        Self::with_entries(megabytes * 1024 * 1024 / Self::entry_size(), policy)
+    }
+
+    /// Bytes used per slot.
+    pub fn entry_size() -> usize {
+        size_of::<Option<TtEntry<M>>>()
+    }
+
+    /// Number of slots.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Number of occupied slots.
+    pub fn len(&self) -> usize {
+        self.used
+    }
+
+    /// Returns true if no slot is occupied.
+    pub fn is_empty(&self) -> bool {
+        self.used == 0
+    }
+
+    /// Fraction of slots in use.
+    pub fn occupancy(&self) -> f64 {
+        self.used as f64 / self.capacity() as f64
+    }
+
+    pub fn policy(&self) -> ReplacementPolicy {
+        self.policy
+    }
+
+    pub fn stats(&self) -> TtStats {
+        self.stats
+    }
+
+    /// Removes every entry and resets the statistics.
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|s| *s = None);
+        self.used = 0;
+        self.stats = TtStats::default();
+    }
+
+    fn bucket(&self, key: u64) -> core::ops::Range<usize> {
+        // Fibonacci hashing spreads structured keys (like bitboards) evenly.
+        let hash = key.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let buckets = (self.slots.len() / self.ways) as u64;
+        let start = ((hash >> 32) % buckets) as usize * self.ways;
+        start..start + self.ways
+    }
+
+    /// Looks up the entry for `key`.
+    pub fn probe(&mut self, key: u64) -> Option<&TtEntry<M>> {
+        self.stats.probes += 1;
+        let range = self.bucket(key);
+        let found = self.slots[range].iter().flatten().find(|e| e.key == key);
+        if found.is_some() {
+            self.stats.hits += 1;
+        }
+        search_event!(
+            tracing::Level::TRACE,
+            key,
+            hit = found.is_some(),
+            "tt probe"
+        );
+        found
+    }
+
+    /// The line of stored best moves from `state`, at most `depth` plies
+    /// long: the principal variation of a search through this table. It
+    /// stops early at the end of the game or at a position the table no
+    /// longer holds. Doesn't count as probes.
+    pub fn pv<G>(&self, state: &G, depth: u32) -> Vec<M>
+    where
+        G: GameState<Move = M> + PositionKey,
+        M: PartialEq,
+    {
+        let mut line = Vec::new();
+        let mut state = state.clone();
+        while (line.len() as u32) < depth && !state.is_terminal() {
+            let key = state.position_key();
+            let Some(mv) = self.slots[self.bucket(key)]
+                .iter()
+                .flatten()
+                .find(|e| e.key == key)
+                .and_then(|e| e.best_move.clone())
+                .filter(|mv| state.legal_moves().contains(mv))
+            else {
+                break;
+            };
+            state = state.apply_move(&mv);
+            line.push(mv);
+        }
+        line
+    }
+
+    /// Stores `entry`, subject to the replacement policy.
+    pub fn store(&mut self, entry: TtEntry<M>) {
+        let key = entry.key;
+        let range = self.bucket(key);
+        let bucket = &mut self.slots[range];
+        // Same position: always refresh in place.
+        let slot = match bucket
+            .iter()
+            .position(|s| s.as_ref().is_some_and(|e| e.key == entry.key))
+        {
+            Some(i) => Some(i),
+            None => match self.policy {
+                ReplacementPolicy::AlwaysReplace => Some(0),
+                ReplacementPolicy::DepthPreferred => match &bucket[0] {
+                    Some(old) if old.depth > entry.depth => None,
+                    _ => Some(0),
+                },
+                ReplacementPolicy::TwoTier => match &bucket[0] {
+                    Some(old) if old.depth > entry.depth => Some(1),
+                    _ => Some(0),
+                },
+            },
+        };
+        let Some(i) = slot else {
+            self.stats.rejected += 1;
+            search_event!(
+                tracing::Level::TRACE,
+                key,
+                depth = entry.depth,
+                "tt store rejected"
+            );
+            return;
+        };
+        if self.policy == ReplacementPolicy::TwoTier
+            && i == 0
+            && bucket[0].as_ref().is_some_and(|e| e.key != entry.key)
+        {
+            // Demote the displaced deep entry to the always-replace slot.
+            let demoted = bucket[0].take();
+            match bucket[1].replace(demoted.expect("slot 0 is occupied")) {
+                Some(_) => self.stats.evictions += 1,
+                None => self.used += 1,
+            }
+            self.used -= 1;
+        }
+        search_event!(tracing::Level::TRACE, key, depth = entry.depth, "tt store");
+        match bucket[i].replace(entry) {
+            Some(old) if old.key != key => self.stats.evictions += 1,
+            Some(_) => {}
+            None => self.used += 1,
+        }
+        self.stats.stores += 1;
+    }
+}
+
+/// Depth-limited alpha-beta (same values as `minimax_value_ab_depth`) that
+/// reuses results through `tt`.
+///
+/// Stored results searched at least as deep as needed are used directly,
+/// and the stored best move is searched first. Because a deeper stored
+/// result may be used for a shallower search, values can differ from a
+/// plain search at the same depth, but never by looking less far ahead.
+pub fn minimax_best_move_ab_depth_tt<G>(
+    state: &G,
+    depth: u32,
+    tt: &mut TranspositionTable<G::Move>,
+) -> (Option<(G::Move, i32)>, SearchStats)
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    best_move_tt(state, depth, 0, tt, None)
+}
+
+/// `minimax_best_move_ab_depth_tt` with `contempt` for the side to move
+/// (see `SearchParams::contempt`), giving up (and returning `None`) as
+/// soon as `cancel` is cancelled.
+pub(crate) fn best_move_tt<G>(
+    state: &G,
+    depth: u32,
+    contempt: i32,
+    tt: &mut TranspositionTable<G::Move>,
+    cancel: Option<&CancelToken>,
+) -> (Option<(G::Move, i32)>, SearchStats)
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    let mut search = TtSearch::new(tt, &HeuristicEvaluator).with_contempt(state, contempt);
+    search.cancel = cancel;
+    let best = search.root(state, depth.clamp(1, DEFAULT_MAX_PLY));
+    (best.filter(|_| !search.cancelled()), search.stats)
+}
+
+/// `best_move_tt` at `progress.depth`, skipping the root moves in
+/// `progress.searched` and starting from `progress.best`, so a root search
+/// can carry on where an interrupted one stopped. `progress` is updated
+/// and passed to `on_move` after each root move, with the nodes visited so
+/// far. The result is in `progress.best` once every move is searched;
+/// if `cancel` is cancelled first, the move being searched is left out.
+pub(crate) fn best_move_tt_from<G>(
+    state: &G,
+    tt: &mut TranspositionTable<G::Move>,
+    cancel: Option<&CancelToken>,
+    progress: &mut SolveProgress<G::Move>,
+    on_move: impl FnMut(&SolveProgress<G::Move>, &SearchStats),
+) -> SearchStats
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    let mut search = TtSearch::new(tt, &HeuristicEvaluator);
+    search.cancel = cancel;
+    search.root_from(state, progress, on_move);
+    search.stats
+}
+
+/// The depth-limited value of `state` through `tt`, with `contempt` for
+/// the side to move, or `None` if `cancel` was cancelled first.
+pub(crate) fn value_tt<G>(
+    state: &G,
+    depth: u32,
+    contempt: i32,
+    tt: &mut TranspositionTable<G::Move>,
+    cancel: Option<&CancelToken>,
+) -> (Option<i32>, SearchStats)
+where
+    G: GameState + PositionKey,
+    G::Move: PartialEq,
+{
+    let mut search = TtSearch::new(tt, &HeuristicEvaluator).with_contempt(state, contempt);
+    search.cancel = cancel;
+    let (value, _) = search.node(state, depth.min(DEFAULT_MAX_PLY), i32::MIN, i32::MAX);
+    (Some(value).filter(|_| !search.cancelled()), search.stats)
+}
+
+/// Table-backed alpha-beta shared by the fixed-window and MTD(f) drivers.
+///
+/// Stored values include the draw score, so a table searched with one
+/// contempt (or root side) holds the wrong values for another.
+pub(crate) struct TtSearch<'a, M, E> {
+    tt: &'a mut TranspositionTable<M>,
+    evaluator: &'a E,
+    pub(crate) stats: SearchStats,
+    pool: MovePool<M>,
+    counter_moves: CounterMoves<M>,
+    cancel: Option<&'a CancelToken>,
+    /// The score of drawn positions, from Player1's perspective.
+    draw_score: i32,
+}
+
+impl<'a, M: Clone + PartialEq, E> TtSearch<'a, M, E> {
+    pub(crate) fn new(tt: &'a mut TranspositionTable<M>, evaluator: &'a E) -> Self {
+        Self {
+            tt,
+            evaluator,
+            stats: SearchStats::default(),
+            pool: MovePool::new(),
+            counter_moves: CounterMoves::new(),
+            cancel: None,
+            draw_score: 0,
+        }
+    }
+
+    /// Scores draws by `contempt` for the side to move at `root`.
+    pub(crate) fn with_contempt<G: GameState>(mut self, root: &G, contempt: i32) -> Self {
+        let params = SearchParams {
+            contempt,
+            ..SearchParams::default()
+        };
+        self.draw_score = params.draw_score(root.current_player());
+        self
+    }
+
+    /// The depth-limited score of a position whose exact value is `v`.
+    fn limited_score(&self, v: i32) -> i32 {
+        if v == 0 {
+            self.draw_score
+        } else {
+            v * WIN_SCORE
+        }
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.is_some_and(CancelToken::is_cancelled)
+    }
+
+    /// Legal moves in search order, with the table's best move (if any)
+    /// moved to the front. The counter-move to the opponent's last move is
+    /// tried right after the first move, as the game's own ordering is
+    /// usually a better first guess.
+    fn moves<G: GameState<Move = M>>(&mut self, state: &G, hint: Option<M>) -> Vec<M> {
+        let mut moves = self.pool.ordered_moves(state);
+        let counter = state.last_move().and_then(|last| {
+            self.counter_moves
+                .get(state.current_player(), &last)
+                .cloned()
+        });
+        if let Some(counter) = counter
+            && let Some(i) = moves.iter().position(|m| *m == counter)
+            && i > 1
+        {
+            moves[1..=i].rotate_right(1);
+        }
+        if let Some(hint) = hint
+            && let Some(i) = moves.iter().position(|m| *m == hint)
+        {
+            moves[..=i].rotate_right(1);
+        }
+        moves
+    }
+
+    fn root<G>(&mut self, state: &G, depth: u32) -> Option<(M, i32)>
+    where
+        G: GameState<Move = M> + PositionKey,
+        E: Evaluator<G>,
+    {
+        let mut progress = SolveProgress {
+            depth,
+            searched: Vec::new(),
+            best: None,
+        };
+        self.root_from(state, &mut progress, |_, _| {});
+        progress.best
+    }
+
+    /// The root search behind `root` and `best_move_tt_from`.
+    fn root_from<G>(
+        &mut self,
+        state: &G,
+        progress: &mut SolveProgress<M>,
+        mut on_move: impl FnMut(&SolveProgress<M>, &SearchStats),
+    ) where
+        G: GameState<Move = M> + PositionKey,
+        E: Evaluator<G>,
+    {
+        let depth = progress.depth;
+        let span = search_span!("search", depth, tt = true);
+        let start_nodes = self.stats.nodes;
+        let key = state.position_key();
+        let hint = self.tt.probe(key).and_then(|e| e.best_move.clone());
+        let moves = self.moves(state, hint);
+        let maximizing = state.current_player() == Player::Player1;
+        // The moves already searched narrowed the window to their best.
+        let (mut alpha, mut beta) = match &progress.best {
+            Some((_, value)) if maximizing => (*value, i32::MAX),
+            Some((_, value)) => (i32::MIN, *value),
+            None => (i32::MIN, i32::MAX),
+        };
+        for mv in &moves {
+            if progress.searched.contains(mv) {
+                continue;
+            }
+            let value = self.search(&state.apply_move(mv), depth - 1, alpha, beta);
+            if self.cancelled() {
+                break;
+            }
+            let better = match &progress.best {
+                None => true,
+                Some((_, b)) => (maximizing && value > *b) || (!maximizing && value < *b),
+            };
+            if better {
+                progress.best = Some((mv.clone(), value));
+            }
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+            progress.searched.push(mv.clone());
+            on_move(progress, &self.stats);
+        }
+        self.pool.give_back(moves);
+        if let Some((mv, value)) = &progress.best
+            && !self.cancelled()
+        {
+            self.tt.store(TtEntry {
+                key,
+                depth,
+                value: *value,
+                bound: Bound::Exact,
+                best_move: Some(mv.clone()),
+            });
+        }
+        span.finish(self.stats.nodes - start_nodes);
+    }
+
+    fn search<G>(&mut self, state: &G, depth: u32, alpha: i32, beta: i32) -> i32
+    where
+        G: GameState<Move = M> + PositionKey,
+        E: Evaluator<G>,
+    {
+        self.node(state, depth, alpha, beta).0
+    }
+
+    /// Fail-soft alpha-beta on `state`, returning its value and the move
+    /// that produced it (`None` at leaves).
+    pub(crate) fn node<G>(
+        &mut self,
+        state: &G,
+        depth: u32,
+        mut alpha: i32,
+        mut beta: i32,
+    ) -> (i32, Option<M>)
+    where
+        G: GameState<Move = M> + PositionKey,
+        E: Evaluator<G>,
+    {
+        self.stats.nodes += 1;
+        // The value is discarded by the caller once the search is cancelled.
+        if self.cancelled() {
+            return (0, None);
+        }
+        if let Some(v) = state.terminal_value() {
+            return (self.limited_score(v), None);
+        }
+        if depth == 0 {
+            return (self.evaluator.evaluate(state), None);
+        }
+
+        let key = state.position_key();
+        let mut hint = None;
+        if let Some(entry) = self.tt.probe(key) {
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return (entry.value, entry.best_move.clone()),
+                    Bound::Lower => alpha = alpha.max(entry.value),
+                    Bound::Upper => beta = beta.min(entry.value),
+                }
+                if alpha >= beta {
+                    return (entry.value, entry.best_move.clone());
+                }
+            }
+            hint = entry.best_move.clone();
+        }
+
+        let (alpha0, beta0) = (alpha, beta);
+        let maximizing = state.current_player() == Player::Player1;
+        let mut value = if maximizing { i32::MIN } else { i32::MAX };
+        let mut best_move = None;
+        let moves = self.moves(state, hint);
+        if moves.is_empty() {
+            self.pool.give_back(moves);
+            return (self.limited_score(no_moves_value(state)), None);
+        }
+        for mv in &moves {
+            let child = self.search(&state.apply_move(mv), depth - 1, alpha, beta);
+            let better = if maximizing {
+                child > value
+            } else {
+                child < value
+            };
+            if better {
+                value = child;
+                best_move = Some(mv.clone());
+            }
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                if let Some(last) = state.last_move() {
+                    self.counter_moves.record(state.current_player(), last, mv);
+                }
+                break;
+            }
+        }
+        self.pool.give_back(moves);
+        // Never store values from a cancelled search.
+        if self.cancelled() {
+            return (value, best_move);
+        }
+
+        let bound = if value <= alpha0 {
+            Bound::Upper
+        } else if value >= beta0 {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.tt.store(TtEntry {
+            key,
+            depth,
+            value,
+            bound,
+            best_move: best_move.clone(),
+        });
+        (value, best_move)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+    use crate::solvers::minimax::minimax_best_move_ab_depth_with_stats;
+
+    fn entry(key: u64, depth: u32) -> TtEntry<u8> {
+        TtEntry {
+            key,
+            depth,
+            value: 0,
+            bound: Bound::Exact,
+            best_move: None,
+        }
+    }
+
+    #[test]
+    fn counter_moves_are_tried_after_the_first_move() {
+        let mut tt = TranspositionTable::with_entries(64, ReplacementPolicy::TwoTier);
+        let mut search = TtSearch::new(&mut tt, &HeuristicEvaluator);
+        let s = BitboardState::from_move_sequence("3").unwrap();
+        assert_eq!(search.moves(&s, None), [3, 2, 4, 1, 5, 0, 6]);
+
+        // Player2's refutation of column 3; Player1's replies are separate.
+        search.counter_moves.record(Player::Player2, 3, &0);
+        search.counter_moves.record(Player::Player1, 3, &6);
+        assert_eq!(search.moves(&s, None), [3, 0, 2, 4, 1, 5, 6]);
+        // The table's move still comes first.
+        assert_eq!(search.moves(&s, Some(5)), [5, 3, 0, 2, 4, 1, 6]);
+        search.counter_moves.record(Player::Player2, 3, &1);
+        assert_eq!(search.moves(&s, None), [3, 1, 2, 4, 5, 0, 6]);
+        assert_eq!(search.counter_moves.get(Player::Player2, &4), None);
+    }
+
+    #[test]
+    fn capacity_is_fixed_and_policies_decide_replacement() {
+        let mut always = TranspositionTable::with_entries(1, ReplacementPolicy::AlwaysReplace);
+        always.store(entry(1, 5));
+        always.store(entry(2, 1));
+        assert_eq!(always.capacity(), 1);
+        assert!(always.probe(1).is_none());
+        assert_eq!(always.probe(2).unwrap().depth, 1);
+        assert_eq!(always.stats().evictions, 1);
+
+        let mut deep = TranspositionTable::with_entries(1, ReplacementPolicy::DepthPreferred);
+        deep.store(entry(1, 5));
+        deep.store(entry(2, 1));
+        assert!(deep.probe(1).is_some());
+        assert_eq!(deep.stats().rejected, 1);
+
+        let mut two = TranspositionTable::with_entries(2, ReplacementPolicy::TwoTier);
+        two.store(entry(1, 5));
+        two.store(entry(2, 1));
+        two.store(entry(3, 2));
+        assert!(two.probe(1).is_some(), "deep entry stays");
+        assert!(two.probe(2).is_none(), "shallow slot was overwritten");
+        assert!(two.probe(3).is_some());
+        assert_eq!(two.len(), 2);
+        assert_eq!(two.occupancy(), 1.0);
+        assert_eq!(two.stats().hit_rate(), 2.0 / 3.0);
+    }
+
+    #[test]
+    fn megabyte_capacity_accounts_for_entry_size() {
+        let tt = TranspositionTable::<u8>::with_megabytes(1, ReplacementPolicy::AlwaysReplace);
+        assert!(tt.capacity() * TranspositionTable::<u8>::entry_size() <= 1024 * 1024);
+        assert!(tt.capacity() > 1000);
+    }
+
+    #[test]
+    fn ttt_tt_search_matches_plain_search() {
+        for policy in [
+            ReplacementPolicy::AlwaysReplace,
+            ReplacementPolicy::DepthPreferred,
+            ReplacementPolicy::TwoTier,
+        ] {
+            let mut tt = TranspositionTable::with_entries(1 << 12, policy);
+            let s = TicTacToeState::new();
+            let (best, stats) = minimax_best_move_ab_depth_tt(&s, 9, &mut tt);
+            let (plain, plain_stats) = minimax_best_move_ab_depth_with_stats(&s, 9);
+            assert_eq!(best.map(|b| b.1), plain.map(|b| b.1));
+            assert!(stats.nodes < plain_stats.nodes, "{policy:?}");
+            assert!(tt.stats().hits > 0);
+        }
+    }
+
+    #[test]
+    fn c4_tt_search_finds_win_with_tiny_table() {
+        let s = BitboardState::from_move_sequence("060606").unwrap();
+        let mut tt = TranspositionTable::with_entries(8, ReplacementPolicy::TwoTier);
+        let (best, _) = minimax_best_move_ab_depth_tt(&s, 5, &mut tt);
+        let (col, value) = best.unwrap();
+        assert_eq!(col, 0);
+        assert_eq!(value, 1_000_000);
+        assert!(tt.len() <= 8);
+    }
+}
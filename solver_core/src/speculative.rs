@@ -0,0 +1,394 @@
+use crate::agent::{Agent, Decision};
+use crate::game::{GameState, Player};
+use crate::solvers::minimax::{
+    WIN_SCORE, minimax_best_move_ab_depth, minimax_value_ab_depth_root, no_moves_value,
+};
+use crate::utils::opposite_player;
+
+/// How a speculative search assumes the opponent picks its replies.
+///
+/// Both models rate each reply with a depth-limited search of `depth`
+/// plies after it (0 scores the position it reaches with the heuristic),
+/// so a low depth models an opponent that doesn't see far ahead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OpponentModel {
+    /// The opponent plays the reply its search rates best, choosing
+    /// evenly among ties.
+    DepthLimited { depth: u32 },
+    /// The opponent plays each reply with probability proportional to
+    /// `exp(score / temperature)`, its score in evaluation units: the
+    /// higher the temperature, the more often it errs.
+    Softmax { depth: u32, temperature: f64 },
+}
+
+impl OpponentModel {
+    /// The legal moves in `state` with the probability that the opponent,
+    /// who is to move, plays each.
+    pub fn reply_weights<G: GameState>(&self, state: &G) -> Vec<(G::Move, f64)> {
+        let (OpponentModel::DepthLimited { depth } | OpponentModel::Softmax { depth, .. }) = *self;
+        let sign = match state.current_player() {
+            Player::Player1 => 1.0,
+            Player::Player2 => -1.0,
+        };
+        let scored: Vec<(G::Move, f64)> = state
+            .legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let score = minimax_value_ab_depth_root(&state.apply_move(&mv), depth);
+                (mv, sign * f64::from(score))
+            })
+            .collect();
+        let Some(best) = scored.iter().map(|(_, s)| *s).reduce(f64::max) else {
+            return scored;
+        };
+        let weights: Vec<f64> = match *self {
+            OpponentModel::DepthLimited { .. } => scored
+                .iter()
+                .map(|(_, s)| if *s == best { 1.0 } else { 0.0 })
+                .collect(),
+            // Shifting by the best score keeps the exponents from
+            // overflowing and gives the best reply weight 1.
+            OpponentModel::Softmax { temperature, .. } => scored
+                .iter()
+                .map(|(_, s)| ((s - best) / temperature.max(f64::MIN_POSITIVE)).exp())
+                .collect(),
+        };
+        let total: f64 = weights.iter().sum();
+        scored
+            .into_iter()
+            .zip(weights)
+            .map(|((mv, _), w)| (mv, w / total))
+            .collect()
+    }
+}
+
+/// The expected value of `state` from Player1's perspective, on the
+/// depth-limited scale, when `us` plays its best moves and the opponent
+/// follows `model`.
+fn expected_value<G: GameState>(state: &G, depth: u32, us: Player, model: &OpponentModel) -> f64 {
+    if let Some(value) = state.terminal_value() {
+        return f64::from(value * WIN_SCORE);
+    }
+    if depth == 0 {
+        return f64::from(state.heuristic_value());
+    }
+    if state.is_terminal() || state.legal_moves().is_empty() {
+        return f64::from(no_moves_value(state) * WIN_SCORE);
+    }
+    if state.current_player() == us {
+        let values = state
+            .legal_moves()
+            .into_iter()
+            .map(|mv| expected_value(&state.apply_move(&mv), depth - 1, us, model));
+        match us {
+            Player::Player1 => values.fold(f64::NEG_INFINITY, f64::max),
+            Player::Player2 => values.fold(f64::INFINITY, f64::min),
+        }
+    } else {
+        model
+            .reply_weights(state)
+            .into_iter()
+            .filter(|(_, p)| *p > 0.0)
+            .map(|(mv, p)| p * expected_value(&state.apply_move(&mv), depth - 1, us, model))
+            .sum()
+    }
+}
+
+/// Searches `state` to `depth` plies assuming the opponent replies as
+/// `model` predicts rather than perfectly, and returns every move with its
+/// expected value from Player1's perspective, best first for the player to
+/// move (ties in `legal_moves` order).
+///
+/// This is speculative play, not solving: against an opponent that
+/// replies better than the model the moves it prefers can lose positions a
+/// minimax search would hold. The values are averages over the model's
+/// replies on the depth-limited scale, where a certain win is
+/// ±1_000_000.
+pub fn speculative_search<G: GameState>(
+    state: &G,
+    depth: u32,
+    model: &OpponentModel,
+) -> Vec<(G::Move, f64)> {
+    if state.is_terminal() {
+        return Vec::new();
+    }
+    let us = state.current_player();
+    let mut lines: Vec<(G::Move, f64)> = state
+        .legal_moves()
+        .into_iter()
+        .map(|mv| {
+            let value = expected_value(&state.apply_move(&mv), depth.max(1) - 1, us, model);
+            (mv, value)
+        })
+        .collect();
+    lines.sort_by(|a, b| match us {
+        Player::Player1 => b.1.total_cmp(&a.1),
+        Player::Player2 => a.1.total_cmp(&b.1),
+    });
+    lines
+}
+
+/// An agent for playing weaker opponents: it plays a forced win when its
+/// `depth`-ply search finds one, and otherwise the move with the best
+/// expected value against `model`, so that in drawn and lost positions it
+/// steers towards the replies the opponent is likely to get wrong.
+///
+/// Its decisions carry the expected value, rounded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpeculativeAgent {
+    /// Search depth in plies.
+    pub depth: u32,
+    pub model: OpponentModel,
+}
+
+impl<G: GameState> Agent<G> for SpeculativeAgent {
+    fn select_move(&mut self, state: &G) -> Option<Decision<G::Move>> {
+        if state.is_terminal() {
+            return None;
+        }
+        let depth = self.depth.max(1);
+        let (mv, value) = minimax_best_move_ab_depth(state, depth)?;
+        let sign = match state.current_player() {
+            Player::Player1 => 1,
+            Player::Player2 => -1,
+        };
+        if value * sign >= WIN_SCORE {
+            return Some(Decision {
+                mv,
+                value: Some(value),
+            });
+        }
+        let (mv, expected) = speculative_search(state, depth, &self.model)
+            .into_iter()
+            .next()?;
+        Some(Decision {
+            mv,
+            value: Some(expected.round() as i32),
+        })
+    }
+}
+
+/// One of our moves in a lost position, rated by how hard it makes the
+/// win for the opponent (see `swindle_lines`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwindleLine<M> {
+    pub mv: M,
+    /// Plies until the game is lost with best play, this move included,
+    /// or `None` if the search doesn't prove the move loses.
+    pub loses_in: Option<u32>,
+    /// Replies that keep the opponent's forced win.
+    pub refutations: usize,
+    /// The probability that the opponent, replying as the model predicts,
+    /// plays anything else and lets the win slip.
+    pub error_chance: f64,
+}
+
+/// The plies within `depth` in which `winner` forces a win from `state`,
+/// or `None` if the search can't prove one.
+fn wins_within<G: GameState>(state: &G, winner: Player, depth: u32) -> Option<u32> {
+    let sign = match winner {
+        Player::Player1 => 1,
+        Player::Player2 => -1,
+    };
+    // As in `solve_exact`, the first depth that proves the win is its
+    // distance.
+    (0..=depth).find(|&d| minimax_value_ab_depth_root(state, d) * sign >= WIN_SCORE)
+}
+
+/// Rates every move in `state`, where the player to move is lost, by how
+/// likely it is to swindle the opponent out of the win: moves the
+/// `depth`-ply search can't prove lost come first, then the ones the
+/// opponent modelled by `model` most likely answers wrongly, then those
+/// that lose latest, then those with the fewest refutations.
+///
+/// The model makes the mistakes; distances only separate moves it rates
+/// alike, e.g. against an opponent it expects to play perfectly.
+pub fn swindle_lines<G: GameState>(
+    state: &G,
+    depth: u32,
+    model: &OpponentModel,
+) -> Vec<SwindleLine<G::Move>> {
+    let us = state.current_player();
+    let them = opposite_player(us);
+    let depth = depth.max(1);
+    let mut lines: Vec<SwindleLine<G::Move>> = state
+        .legal_moves()
+        .into_iter()
+        .map(|mv| {
+            let after = state.apply_move(&mv);
+            let loses_in = wins_within(&after, them, depth - 1).map(|plies| plies + 1);
+            let (refutations, error_chance) = if after.is_terminal() {
+                (0, if loses_in.is_some() { 0.0 } else { 1.0 })
+            } else {
+                let mut refutations = 0;
+                let mut error_chance = 0.0;
+                for (reply, p) in model.reply_weights(&after) {
+                    let next = after.apply_move(&reply);
+                    if wins_within(&next, them, depth.saturating_sub(2)).is_some() {
+                        refutations += 1;
+                    } else {
+                        error_chance += p;
+                    }
+                }
+                (refutations, error_chance)
+            };
+            SwindleLine {
+                mv,
+                loses_in,
+                refutations,
+                error_chance,
+            }
+        })
+        .collect();
+    lines.sort_by(|a, b| {
+        let held = |line: &SwindleLine<G::Move>| line.loses_in.is_none();
+        held(b)
+            .cmp(&held(a))
+            .then(b.error_chance.total_cmp(&a.error_chance))
+            .then(b.loses_in.cmp(&a.loses_in))
+            .then(a.refutations.cmp(&b.refutations))
+    });
+    lines
+}
+
+/// An agent that plays the depth-limited alpha-beta best move until its
+/// search finds it is lost, and then the move `swindle_lines` rates best,
+/// instead of one that loses as quickly as any other.
+///
+/// Its decisions carry the search's value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SwindleAgent {
+    /// Search depth in plies.
+    pub depth: u32,
+    pub model: OpponentModel,
+}
+
+impl<G: GameState> Agent<G> for SwindleAgent {
+    fn select_move(&mut self, state: &G) -> Option<Decision<G::Move>> {
+        if state.is_terminal() {
+            return None;
+        }
+        let depth = self.depth.max(1);
+        let (mv, value) = minimax_best_move_ab_depth(state, depth)?;
+        let sign = match state.current_player() {
+            Player::Player1 => 1,
+            Player::Player2 => -1,
+        };
+        let mv = if value * sign <= -WIN_SCORE {
+            swindle_lines(state, depth, &self.model)
+                .into_iter()
+                .next()
+                .map_or(mv, |line| line.mv)
+        } else {
+            mv
+        };
+        Some(Decision {
+            mv,
+            value: Some(value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::play_game;
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::{TicTacToeMove, TicTacToeState};
+    use crate::rng::RandomAgent;
+
+    #[test]
+    fn models_weight_the_replies() {
+        // O must block the top row.
+        let state = TicTacToeState::from_str("XX..O....", Player::Player2).unwrap();
+        let weights = OpponentModel::DepthLimited { depth: 1 }.reply_weights(&state);
+        assert_eq!(weights.len(), 6);
+        for (mv, p) in &weights {
+            let expected = if *mv == (TicTacToeMove { index: 2 }) {
+                1.0
+            } else {
+                0.0
+            };
+            assert_eq!(*p, expected, "{mv:?}");
+        }
+        let soft = OpponentModel::Softmax {
+            depth: 1,
+            temperature: 1e5,
+        }
+        .reply_weights(&state);
+        assert!((soft.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+        let block = soft.iter().find(|(mv, _)| mv.index == 2).unwrap().1;
+        assert!(soft.iter().all(|(_, p)| *p <= block && *p > 0.0));
+    }
+
+    #[test]
+    fn expects_weak_opponents_to_err() {
+        // A perfect opponent holds the draw.
+        let corner = TicTacToeState::from_str("X...O....", Player::Player1).unwrap();
+        let perfect = speculative_search(&corner, 7, &OpponentModel::DepthLimited { depth: 7 });
+        assert_eq!(perfect[0].1, 0.0);
+        // One that looks two plies ahead can be led into a fork from the
+        // start, and one that errs at random often enough.
+        let start = TicTacToeState::new();
+        let weak = speculative_search(&start, 9, &OpponentModel::DepthLimited { depth: 2 });
+        assert_eq!(weak.len(), 9);
+        assert_eq!(weak[0].1, f64::from(WIN_SCORE));
+        let noisy = OpponentModel::Softmax {
+            depth: 1,
+            temperature: 100.0,
+        };
+        let weak = speculative_search(&start, 9, &noisy);
+        assert!(weak[0].1 > 0.0);
+        assert!(weak.windows(2).all(|w| w[0].1 >= w[1].1));
+        let over = TicTacToeState::from_str("XXXOO....", Player::Player2).unwrap();
+        assert!(speculative_search(&over, 3, &OpponentModel::DepthLimited { depth: 1 }).is_empty());
+    }
+
+    #[test]
+    fn agent_never_loses_to_random_play() {
+        let mut agent = SpeculativeAgent {
+            depth: 9,
+            model: OpponentModel::Softmax {
+                depth: 1,
+                temperature: 100.0,
+            },
+        };
+        let start = TicTacToeState::new();
+        for seed in 0..4 {
+            let (end, _) = play_game(&start, &mut agent, &mut RandomAgent::new(seed));
+            assert_ne!(end.terminal_value(), Some(-1), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn swindles_prefer_moves_that_are_easy_to_answer_wrongly() {
+        // Player1 threatens both ends of the bottom row.
+        let state = BitboardState::from_move_sequence("11223").unwrap();
+        let noisy = OpponentModel::Softmax {
+            depth: 0,
+            temperature: 3e5,
+        };
+        let lines = swindle_lines(&state, 4, &noisy);
+        assert_eq!(lines.len(), 7);
+        assert!(lines.iter().all(|line| line.loses_in == Some(2)));
+        // Blocking one end leaves a single winning reply to find.
+        assert!([0, 4].contains(&lines[0].mv), "{:?}", lines[0]);
+        assert_eq!(lines[0].refutations, 1);
+        assert!(lines[0].error_chance > lines[6].error_chance);
+        assert!(lines[6].refutations > 1);
+
+        let mut agent = SwindleAgent {
+            depth: 4,
+            model: noisy,
+        };
+        let decision = agent.select_move(&state).unwrap();
+        assert_eq!(decision.mv, lines[0].mv);
+        assert_eq!(decision.value, Some(WIN_SCORE));
+        // Won and open positions are played normally.
+        let start = BitboardState::new();
+        assert_eq!(
+            agent.select_move(&start).map(|d| d.mv),
+            minimax_best_move_ab_depth(&start, 4).map(|(mv, _)| mv)
+        );
+    }
+}
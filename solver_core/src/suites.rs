@@ -0,0 +1,305 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::error::ParseError;
+use crate::game::{GameState, Player};
+use crate::games::c4_bitboard::BitboardState;
+use crate::solvers::minimax::SearchStats;
+use crate::solvers::mtdf::mtdf;
+use crate::solvers::tt::{ReplacementPolicy, TranspositionTable};
+
+/// A Connect Four test position with its game-theoretic value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SuitePosition {
+    /// The moves from the empty board, as 1-based column digits.
+    pub moves: String,
+    pub state: BitboardState,
+    /// The result with perfect play for the side to move: 1 (win), 0
+    /// (draw) or -1 (loss).
+    pub expected: i32,
+}
+
+/// A line of a suite that could not be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SuiteError {
+    /// The 1-based line number.
+    pub line: usize,
+    pub error: ParseError,
+}
+
+impl fmt::Display for SuiteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl core::error::Error for SuiteError {}
+
+/// Reads test positions in the format of Pascal Pons' Connect Four test
+/// sets: one position per line, its moves as 1-based column digits and its
+/// score for the side to move, e.g. `4453 -1`.
+///
+/// Pons' scores also count how fast the game is won; only their sign (the
+/// result) is kept. Blank lines and lines starting with `#` are skipped.
+pub fn parse_suite(text: &str) -> Result<Vec<SuitePosition>, SuiteError> {
+    let mut positions = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |error| SuiteError { line: i + 1, error };
+        let mut fields = line.split_whitespace();
+        let moves = fields.next().unwrap_or_default();
+        let score = fields.next().unwrap_or_default();
+        if let Some(extra) = fields.next() {
+            return Err(error(ParseError::NotANumber(extra.to_string())));
+        }
+        let expected = score
+            .parse::<i32>()
+            .map_err(|_| error(ParseError::NotANumber(score.to_string())))?
+            .signum();
+        let state = BitboardState::from_pons_sequence(moves).map_err(error)?;
+        positions.push(SuitePosition {
+            moves: moves.to_string(),
+            state,
+            expected,
+        });
+    }
+    Ok(positions)
+}
+
+/// A bundled test suite.
+#[derive(Clone, Copy, Debug)]
+pub struct Suite {
+    /// Short name, e.g. `"end"`.
+    pub name: &'static str,
+    pub description: &'static str,
+    /// The positions, in the `parse_suite` format.
+    pub text: &'static str,
+}
+
+impl Suite {
+    /// The suite's positions.
+    pub fn positions(&self) -> Vec<SuitePosition> {
+        parse_suite(self.text).expect("bundled suites are valid")
+    }
+}
+
+/// The bundled suites, easiest first.
+///
+/// Their positions come from random games in which neither side allows an
+/// immediate win, 4 won, 4 drawn and 4 lost for the side to move at each
+/// depth. Every value was solved by this crate's bitboard search and
+/// confirmed by solving the same position on the `games::c4` board.
+pub const SUITES: &[Suite] = &[
+    Suite {
+        name: "end",
+        description: "endgames after 26 and 30 moves",
+        text: END,
+    },
+    Suite {
+        name: "middle",
+        description: "middlegames after 20 and 22 moves",
+        text: MIDDLE,
+    },
+];
+
+const END: &str = "\
+# 30 moves
+312675152616667736551153125232 -1
+541566711474452477646275512113 -1
+572776345115765352466232232377 -1
+743276416743112423146622642331 -1
+173142421774362162554227414656 0
+613776456727755117643316452221 0
+713576522544213562332445536377 0
+764443775222151766773452515522 0
+124775775325342111132233527713 1
+134417567766113737755565634234 1
+454554245343433671231215661167 1
+537364165213352611211763755522 1
+# 26 moves
+37216333763134742446777111 -1
+44217311465637533362412441 -1
+74534124736651245514455332 -1
+77557174553214772143143252 -1
+23217136162214551173543445 0
+37633754444774152216663511 0
+43354532575574563334644717 0
+73754713451725514436722554 0
+13314352767365551654446414 1
+31133742237256244567351161 1
+42641715674246115172247417 1
+52535511233413361231244444 1
+";
+
+const MIDDLE: &str = "\
+# 22 moves
+2566241562165426111167 -1
+5131641736272455177334 -1
+7336254377433375446414 -1
+7771322433512555145315 -1
+3116143157565543223221 0
+3172653633433775455521 0
+3312154711635622222455 0
+6154532724222616233566 0
+3731127656721663474447 1
+4223155556675766436133 1
+5127122774711142576155 1
+7562643551667623363733 1
+# 20 moves
+15614654237737333656 -1
+23556265471552311611 -1
+31323354317525763441 -1
+74417177462353567413 -1
+33434465123561317366 0
+35566472342523226341 0
+44446336755675672556 0
+57667514433551644441 0
+21765153556532125161 1
+32214765236266643655 1
+41636517353775553332 1
+67411344572456554132 1
+";
+
+/// The bundled suite called `name`.
+pub fn suite(name: &str) -> Option<&'static Suite> {
+    SUITES.iter().find(|suite| suite.name == name)
+}
+
+/// Solves `state` exactly with MTD(f) through `tt`, returning the result
+/// for the side to move (as `SuitePosition::expected`) and the search
+/// statistics.
+pub fn solve(state: &BitboardState, tt: &mut TranspositionTable<u8>) -> (i32, SearchStats) {
+    if let Some(value) = state.terminal_value() {
+        return (for_side_to_move(state, value), SearchStats::default());
+    }
+    // Searching as deep as there are empty cells reaches the end of every
+    // line, so the value is exact.
+    let empty = 42 - state.ply().unwrap_or(0);
+    let (best, stats) = mtdf(state, empty, 0, tt);
+    let value = best.map_or(0, |(_, value)| value);
+    (for_side_to_move(state, value), stats)
+}
+
+fn for_side_to_move(state: &BitboardState, value: i32) -> i32 {
+    match state.current_player() {
+        Player::Player1 => value.signum(),
+        Player::Player2 => -value.signum(),
+    }
+}
+
+/// The solver's result for one suite position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionResult {
+    pub expected: i32,
+    pub found: i32,
+    pub nodes: u64,
+}
+
+impl PositionResult {
+    pub fn is_correct(&self) -> bool {
+        self.expected == self.found
+    }
+}
+
+/// The results of running a suite, in the order of its positions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SuiteReport {
+    pub results: Vec<PositionResult>,
+}
+
+impl SuiteReport {
+    /// Number of positions solved correctly.
+    pub fn correct(&self) -> usize {
+        self.results.iter().filter(|r| r.is_correct()).count()
+    }
+
+    /// Share of the positions solved correctly (1 for an empty suite).
+    pub fn accuracy(&self) -> f64 {
+        if self.results.is_empty() {
+            return 1.0;
+        }
+        self.correct() as f64 / self.results.len() as f64
+    }
+
+    /// Nodes searched over all positions.
+    pub fn nodes(&self) -> u64 {
+        self.results.iter().map(|r| r.nodes).sum()
+    }
+}
+
+/// Solves every position of `positions` with `solve`, each with a cleared
+/// table of `megabytes` so node counts don't depend on the order.
+pub fn run_suite(positions: &[SuitePosition], megabytes: usize) -> SuiteReport {
+    let mut tt = TranspositionTable::with_megabytes(megabytes, ReplacementPolicy::TwoTier);
+    let results = positions
+        .iter()
+        .map(|position| {
+            tt.clear();
+            let (found, stats) = solve(&position.state, &mut tt);
+            PositionResult {
+                expected: position.expected,
+                found,
+                nodes: stats.nodes,
+            }
+        })
+        .collect();
+    SuiteReport { results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_pons_positions() {
+        let positions = parse_suite("# comment\n\n4453 -3\n 11 12\n").unwrap();
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].moves, "4453");
+        assert_eq!(
+            positions[0].state,
+            BitboardState::from_move_sequence("3342").unwrap()
+        );
+        assert_eq!(positions[0].expected, -1);
+        assert_eq!(positions[1].expected, 1);
+
+        let error = parse_suite("4453 1\n4480 0\n").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.error, ParseError::InvalidChar { ch: '8', index: 2 });
+        assert!(parse_suite("4453 win\n").is_err());
+        assert!(parse_suite("1111111 0\n").is_err());
+
+        for suite in SUITES {
+            let positions = suite.positions();
+            assert_eq!(positions.len(), 24, "{}", suite.name);
+            for expected in -1..=1 {
+                let count = positions.iter().filter(|p| p.expected == expected).count();
+                assert_eq!(count, 8, "{}", suite.name);
+            }
+        }
+        assert!(suite("end").is_some());
+        assert!(suite("opening").is_none());
+    }
+
+    #[test]
+    fn the_solver_passes_the_end_suite() {
+        let report = run_suite(&suite("end").unwrap().positions(), 4);
+        assert_eq!(report.correct(), 24);
+        assert_eq!(report.accuracy(), 1.0);
+        assert!(report.nodes() > 0);
+    }
+
+    #[test]
+    fn wrong_values_are_reported() {
+        let mut positions = parse_suite("312675152616667736551153125232 0\n").unwrap();
+        let report = run_suite(&positions, 1);
+        assert_eq!(report.correct(), 0);
+        assert_eq!(report.accuracy(), 0.0);
+        assert_eq!(report.results[0].found, -1);
+        positions[0].expected = -1;
+        assert_eq!(run_suite(&positions, 1).correct(), 1);
+    }
+}
@@ -0,0 +1,455 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use crate::game::{GameState, Outcome};
+
+const MAGIC: &[u8; 4] = b"TBWD";
+const VERSION: u8 = 1;
+/// Magic, version, flags, two reserved bytes and the index count.
+const HEADER_BYTES: usize = 16;
+/// Flag set in tablebases with a distance layer.
+const DISTANCE_FLAG: u8 = 1;
+/// Stored distance of positions that have none: unsolved positions and
+/// draws.
+const NO_DISTANCE: u8 = u8::MAX;
+
+/// A game whose positions are numbered densely from zero, so a table can
+/// hold one entry per number instead of a map from positions.
+///
+/// Not every number has to be a reachable position; unranking such
+/// numbers may return a position or `None`.
+pub trait PositionIndex: GameState {
+    /// Every position ranks below this.
+    fn index_count() -> u64;
+
+    /// The number of this position.
+    fn rank(&self) -> u64;
+
+    /// The position numbered `index`, or `None` if no position is.
+    fn unrank(index: u64) -> Option<Self>;
+}
+
+/// A solved result from the point of view of the player to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Wdl {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Wdl {
+    /// The same result seen by the other player.
+    pub fn flipped(self) -> Self {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+
+    /// The two-bit code stored in a tablebase; 0 is an unsolved position.
+    fn code(self) -> u8 {
+        self as u8 + 1
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Wdl::Loss),
+            2 => Some(Wdl::Draw),
+            3 => Some(Wdl::Win),
+            _ => None,
+        }
+    }
+}
+
+/// Win/draw/loss results for every position of a small game, two bits per
+/// position, with an optional layer giving how many plies each decided
+/// game lasts with best play.
+///
+/// Entries are found by `PositionIndex::rank` rather than stored in a map,
+/// so the table costs a quarter of a byte per index (plus one byte with
+/// distances), whether or not the index is a reachable position. Connect
+/// Four on a 4x5 board has 31^5 indices: a 7 MB tablebase, 36 MB with
+/// distances.
+///
+/// The bytes are the on-disk format, so a file can be used in place, e.g.
+/// memory-mapped with `open_mmap` (feature `mmap`): the header `TBWD`, a
+/// version byte, a flags byte (bit 0: there is a distance layer), two zero
+/// bytes and the little-endian `u64` index count, followed by the results,
+/// four per byte starting from the low bits, and the distances, one byte
+/// per index with 255 for none.
+pub struct Tablebase<G, B = Vec<u8>> {
+    bytes: B,
+    index_count: u64,
+    distances: bool,
+    game: PhantomData<fn() -> G>,
+}
+
+impl<G, B: AsRef<[u8]>> core::fmt::Debug for Tablebase<G, B> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Tablebase")
+            .field("index_count", &self.index_count)
+            .field("distances", &self.distances)
+            .field("bytes", &self.bytes.as_ref().len())
+            .finish()
+    }
+}
+
+/// How a result ranks for the player it belongs to: wins soonest first,
+/// then draws, then losses latest first.
+fn preference(wdl: Wdl, distance: u8) -> (Wdl, i16) {
+    match wdl {
+        Wdl::Win => (wdl, -i16::from(distance)),
+        Wdl::Draw => (wdl, 0),
+        Wdl::Loss => (wdl, i16::from(distance)),
+    }
+}
+
+/// Bytes taken by the results of `index_count` positions.
+fn results_bytes(index_count: u64) -> usize {
+    index_count.div_ceil(4) as usize
+}
+
+impl<G: PositionIndex> Tablebase<G> {
+    /// Solves every position reachable from `root`, with the distance
+    /// layer if `distances` is set.
+    ///
+    /// Allocates the whole table up front, so it is only for games with a
+    /// modest `index_count`.
+    pub fn build(root: &G, distances: bool) -> Self {
+        let index_count = G::index_count();
+        let results = results_bytes(index_count);
+        let mut bytes = vec![0; HEADER_BYTES + results];
+        if distances {
+            bytes.resize(bytes.len() + index_count as usize, NO_DISTANCE);
+        }
+        bytes[..4].copy_from_slice(MAGIC);
+        bytes[4] = VERSION;
+        bytes[5] = if distances { DISTANCE_FLAG } else { 0 };
+        bytes[8..HEADER_BYTES].copy_from_slice(&index_count.to_le_bytes());
+        let mut table = Self {
+            bytes,
+            index_count,
+            distances,
+            game: PhantomData,
+        };
+        table.solve(root);
+        table
+    }
+
+    /// Solves `state` and everything after it that isn't solved yet,
+    /// returning its result and distance.
+    fn solve(&mut self, state: &G) -> (Wdl, u8) {
+        let index = state.rank();
+        if let Some(wdl) = self.result_at(index) {
+            return (wdl, self.distance_at(index));
+        }
+        let (wdl, distance) = match state.outcome() {
+            Some(Outcome::Win(winner)) if winner == state.current_player() => (Wdl::Win, 0),
+            Some(Outcome::Win(_)) => (Wdl::Loss, 0),
+            Some(Outcome::Draw) => (Wdl::Draw, 0),
+            None => state
+                .legal_moves()
+                .iter()
+                .map(|mv| {
+                    let child = state.apply_move(mv);
+                    let (wdl, distance) = self.solve(&child);
+                    let wdl = if child.current_player() == state.current_player() {
+                        wdl
+                    } else {
+                        wdl.flipped()
+                    };
+                    (wdl, distance.saturating_add(1).min(NO_DISTANCE - 1))
+                })
+                .max_by_key(|&(wdl, distance)| preference(wdl, distance))
+                .expect("non-terminal positions have moves"),
+        };
+        self.store(index, wdl, distance);
+        (wdl, distance)
+    }
+
+    fn store(&mut self, index: u64, wdl: Wdl, distance: u8) {
+        assert!(
+            index < self.index_count,
+            "rank {index} is out of range (index count {})",
+            self.index_count
+        );
+        let byte = HEADER_BYTES + (index / 4) as usize;
+        let shift = (index % 4) * 2;
+        self.bytes[byte] = self.bytes[byte] & !(3 << shift) | wdl.code() << shift;
+        if self.distances && wdl != Wdl::Draw {
+            let offset = HEADER_BYTES + results_bytes(self.index_count);
+            self.bytes[offset + index as usize] = distance;
+        }
+    }
+}
+
+impl<G: PositionIndex, B: AsRef<[u8]>> Tablebase<G, B> {
+    /// Uses `bytes` in the on-disk format as a tablebase, after checking
+    /// its header and size.
+    pub fn from_bytes(bytes: B) -> Result<Self, String> {
+        let data = bytes.as_ref();
+        if data.len() < HEADER_BYTES {
+            return Err("tablebase is truncated".to_string());
+        }
+        if &data[..4] != MAGIC {
+            return Err("not a tablebase".to_string());
+        }
+        if data[4] != VERSION {
+            return Err(format!("unsupported tablebase version {}", data[4]));
+        }
+        let distances = data[5] & DISTANCE_FLAG != 0;
+        let index_count = u64::from_le_bytes(data[8..HEADER_BYTES].try_into().unwrap());
+        if index_count != G::index_count() {
+            return Err(format!(
+                "tablebase has {index_count} positions, expected {}",
+                G::index_count()
+            ));
+        }
+        let expected = HEADER_BYTES
+            + results_bytes(index_count)
+            + if distances { index_count as usize } else { 0 };
+        if data.len() != expected {
+            return Err(format!(
+                "tablebase holds {} bytes, expected {expected}",
+                data.len()
+            ));
+        }
+        Ok(Self {
+            bytes,
+            index_count,
+            distances,
+            game: PhantomData,
+        })
+    }
+
+    /// The tablebase in its on-disk format.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.as_ref()
+    }
+
+    /// Returns true if the tablebase has the distance layer.
+    pub fn has_distances(&self) -> bool {
+        self.distances
+    }
+
+    /// Number of solved positions.
+    pub fn solved_count(&self) -> u64 {
+        let results =
+            &self.as_bytes()[HEADER_BYTES..HEADER_BYTES + results_bytes(self.index_count)];
+        results
+            .iter()
+            .map(|&byte| (0..4).filter(|i| byte >> (i * 2) & 3 != 0).count() as u64)
+            .sum()
+    }
+
+    fn result_at(&self, index: u64) -> Option<Wdl> {
+        if index >= self.index_count {
+            return None;
+        }
+        let byte = self.as_bytes()[HEADER_BYTES + (index / 4) as usize];
+        Wdl::from_code(byte >> ((index % 4) * 2) & 3)
+    }
+
+    fn distance_at(&self, index: u64) -> u8 {
+        if !self.distances {
+            return NO_DISTANCE;
+        }
+        self.as_bytes()[HEADER_BYTES + results_bytes(self.index_count) + index as usize]
+    }
+
+    /// The result of `state` for the player to move, if it was solved.
+    pub fn probe(&self, state: &G) -> Option<Wdl> {
+        self.result_at(state.rank())
+    }
+
+    /// The result of `state`, if it was solved.
+    pub fn outcome(&self, state: &G) -> Option<Outcome> {
+        let mover = state.current_player();
+        Some(match self.probe(state)? {
+            Wdl::Win => Outcome::Win(mover),
+            Wdl::Draw => Outcome::Draw,
+            Wdl::Loss => Outcome::Win(crate::utils::opposite_player(mover)),
+        })
+    }
+
+    /// How many plies a decided `state` lasts with best play: the winner
+    /// ending it as soon as possible, the loser as late. `None` without
+    /// the distance layer, for draws and for unsolved positions.
+    pub fn distance(&self, state: &G) -> Option<u8> {
+        let index = state.rank();
+        self.result_at(index)?;
+        Some(self.distance_at(index)).filter(|&d| d != NO_DISTANCE)
+    }
+
+    /// A best move in `state`: one keeping its result and, with the
+    /// distance layer, winning soonest or losing latest. `None` if the game
+    /// is over or some move leads to an unsolved position.
+    pub fn best_move(&self, state: &G) -> Option<G::Move> {
+        let mut best = None;
+        for mv in state.legal_moves() {
+            let child = state.apply_move(&mv);
+            let wdl = self.probe(&child)?;
+            let wdl = if child.current_player() == state.current_player() {
+                wdl
+            } else {
+                wdl.flipped()
+            };
+            let key = preference(wdl, self.distance(&child).unwrap_or(0));
+            if best.as_ref().is_none_or(|(best_key, _)| key > *best_key) {
+                best = Some((key, mv));
+            }
+        }
+        best.map(|(_, mv)| mv)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<G: PositionIndex, B: AsRef<[u8]>> Tablebase<G, B> {
+    /// Writes the tablebase in its on-disk format.
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(self.as_bytes())
+    }
+
+    /// Saves the tablebase to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = io::BufWriter::new(fs::File::create(path)?);
+        self.write_to(&mut out)?;
+        out.flush()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<G: PositionIndex> Tablebase<G> {
+    /// Reads a tablebase written by `write_to` into memory.
+    pub fn read_from<R: Read>(mut r: R) -> Result<Self, String> {
+        let mut bytes = Vec::new();
+        r.read_to_end(&mut bytes)
+            .map_err(|e| format!("failed to read tablebase: {e}"))?;
+        Self::from_bytes(bytes)
+    }
+
+    /// Loads a tablebase from `path` into memory.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let file =
+            fs::File::open(path).map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+        Self::read_from(io::BufReader::new(file))
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl<G: PositionIndex> Tablebase<G, memmap2::Mmap> {
+    /// Maps the tablebase file at `path` into memory instead of reading
+    /// it, so only the pages that probes touch are loaded.
+    ///
+    /// The file must not be changed while it is mapped.
+    pub fn open_mmap(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let file =
+            fs::File::open(path).map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+        // SAFETY: the mapping is read-only, and the caller guarantees that
+        // the file isn't modified while it is mapped.
+        let map = unsafe { memmap2::Mmap::map(&file) }
+            .map_err(|e| format!("failed to map {}: {e}", path.display()))?;
+        Self::from_bytes(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Player;
+    use crate::games::c4_bitboard::C4Bitboard;
+    use crate::games::ttt::TicTacToeMove;
+    use crate::games::ttt_bitboard::TicTacToeBitboard;
+
+    #[test]
+    fn tic_tac_toe_tablebase_solves_every_reachable_position() {
+        let tb = Tablebase::build(&TicTacToeBitboard::new(), true);
+        // The 5478 legal positions, finished games included.
+        assert_eq!(tb.solved_count(), 5478);
+        assert_eq!(tb.probe(&TicTacToeBitboard::new()), Some(Wdl::Draw));
+        assert_eq!(tb.distance(&TicTacToeBitboard::new()), None);
+
+        // X wins at once in the top row; O would otherwise win next move.
+        let s = TicTacToeBitboard::from_str("XX.OO....", Player::Player1).unwrap();
+        assert_eq!(tb.probe(&s), Some(Wdl::Win));
+        assert_eq!(tb.distance(&s), Some(1));
+        assert_eq!(tb.best_move(&s), Some(TicTacToeMove { index: 2 }));
+        let s = TicTacToeBitboard::from_str("XX.OO...X", Player::Player2).unwrap();
+        assert_eq!(tb.outcome(&s), Some(Outcome::Win(Player::Player2)));
+        // Never reached from the empty board: O can't have moved first.
+        let s = TicTacToeBitboard::from_str("O........", Player::Player1).unwrap();
+        assert_eq!(tb.probe(&s), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn connect_four_tablebase_round_trips_through_bytes() {
+        type Small = C4Bitboard<4, 4>;
+        let tb = Tablebase::build(&Small::new(), false);
+        assert_eq!(
+            tb.as_bytes().len(),
+            HEADER_BYTES + 31usize.pow(4).div_ceil(4)
+        );
+        assert_eq!(tb.probe(&Small::new()), Some(Wdl::Draw));
+        assert_eq!(tb.distance(&Small::new()), None);
+
+        let mut bytes = Vec::new();
+        tb.write_to(&mut bytes).unwrap();
+        let read = Tablebase::<Small>::read_from(&bytes[..]).unwrap();
+        assert_eq!(read.solved_count(), tb.solved_count());
+        let s = Small::from_move_sequence("0101").unwrap();
+        assert_eq!(read.probe(&s), tb.probe(&s));
+        let best = read.best_move(&s).unwrap();
+        assert_eq!(
+            read.probe(&s.apply_move(&best)).map(Wdl::flipped),
+            read.probe(&s)
+        );
+
+        assert!(Tablebase::<Small, _>::from_bytes(&bytes[..20]).is_err());
+        assert!(Tablebase::<C4Bitboard<4, 5>, _>::from_bytes(&bytes[..]).is_err());
+        bytes[0] = b'X';
+        assert!(Tablebase::<Small, _>::from_bytes(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn positions_unrank_to_themselves() {
+        type Small = C4Bitboard<4, 5>;
+        for seq in ["", "0", "01234", "2222", "00112"] {
+            let s = Small::from_move_sequence(seq).unwrap();
+            assert_eq!(Small::unrank(s.rank()), Some(s));
+        }
+        // Player2 can't have more discs than Player1.
+        assert_eq!(Small::unrank(1), None);
+        assert_eq!(Small::unrank(Small::index_count()), None);
+        let s = TicTacToeBitboard::from_str("XO.X....O", Player::Player1).unwrap();
+        assert_eq!(TicTacToeBitboard::unrank(s.rank()), Some(s));
+        assert_eq!(TicTacToeBitboard::index_count(), 19683);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped_tablebases_answer_like_built_ones() {
+        let tb = Tablebase::build(&TicTacToeBitboard::new(), true);
+        let path = std::env::temp_dir().join(format!("ttt-{}.tb", std::process::id()));
+        tb.save(&path).unwrap();
+        let mapped = Tablebase::<TicTacToeBitboard, _>::open_mmap(&path).unwrap();
+        let s = TicTacToeBitboard::from_str("XX.OO....", Player::Player1).unwrap();
+        assert_eq!(mapped.distance(&s), Some(1));
+        assert_eq!(mapped.solved_count(), 5478);
+        drop(mapped);
+        fs::remove_file(path).unwrap();
+    }
+}
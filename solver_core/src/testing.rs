@@ -0,0 +1,1043 @@
+use std::collections::HashSet;
+use std::fmt::{self, Debug};
+use std::ops::RangeInclusive;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::error::MoveError;
+use crate::eval::{Evaluator, HeuristicEvaluator};
+use crate::game::{BoardGame, GameState, Player, PositionKey, SwapPlayers};
+use crate::games::c4::{ConnectFourMove, ConnectFourState};
+use crate::games::c4_bitboard::BitboardState;
+use crate::games::ttt::TicTacToeState;
+use crate::games::ttt_bitboard::TicTacToeBitboard;
+use crate::rng::{Rng, SplitMix64};
+use crate::solvers::batched::batched_alpha_beta;
+use crate::solvers::exact::solve_exact;
+use crate::solvers::minimax::{
+    WIN_SCORE, minimax_best_move, minimax_best_move_ab, minimax_best_move_ab_depth, minimax_value,
+    minimax_value_ab_depth_root, minimax_value_ab_root,
+};
+use crate::solvers::mtdf::{SearchAlgorithm, iterative_search, mtdf};
+use crate::solvers::search::{Search, SearchResult};
+use crate::solvers::tt::{ReplacementPolicy, TranspositionTable, minimax_best_move_ab_depth_tt};
+use crate::utils::opposite_player;
+
+/// Checks that applying any legal move leaves the original state untouched.
+///
+/// `apply_move` is documented to return a fresh state; an implementation
+/// that accidentally shares or mutates data would fail here.
+pub fn check_apply_move_is_pure<G: GameState + PartialEq + Debug>(state: &G) {
+    let before = state.clone();
+    for mv in state.legal_moves() {
+        let _ = state.apply_move(&mv);
+        assert_eq!(
+            *state, before,
+            "apply_move mutated the state it was called on"
+        );
+    }
+}
+
+/// Checks that every move returned by `legal_moves` can be applied and
+/// actually changes the position, and that non-terminal states offer at
+/// least one move.
+pub fn check_legal_moves_applicable<G: GameState + PartialEq + Debug>(state: &G) {
+    let moves = state.legal_moves();
+    if !state.is_terminal() {
+        assert!(
+            !moves.is_empty(),
+            "non-terminal state has no legal moves: {state:?}"
+        );
+    }
+    for mv in &moves {
+        let child = state.apply_move(mv);
+        assert_ne!(
+            child, *state,
+            "applying a legal move did not change the state"
+        );
+    }
+}
+
+/// Checks that `for_each_legal_move` visits exactly the moves returned by
+/// `legal_moves`, in the same order.
+pub fn check_for_each_matches_legal_moves<G>(state: &G)
+where
+    G: GameState + Debug,
+    G::Move: PartialEq + Debug,
+{
+    let mut visited = Vec::new();
+    state.for_each_legal_move(|mv| visited.push(mv));
+    assert_eq!(
+        visited,
+        state.legal_moves(),
+        "for_each_legal_move disagrees with legal_moves for {state:?}"
+    );
+}
+
+/// Checks that `is_terminal` and `terminal_value` agree, and that terminal
+/// values follow the +1 / 0 / -1 convention.
+pub fn check_terminal_consistency<G: GameState + Debug>(state: &G) {
+    match state.terminal_value() {
+        Some(v) => {
+            assert!(
+                state.is_terminal(),
+                "terminal_value is Some({v}) but is_terminal is false: {state:?}"
+            );
+            assert!(
+                (-1..=1).contains(&v),
+                "terminal value {v} is outside -1..=1: {state:?}"
+            );
+        }
+        None => assert!(
+            !state.is_terminal(),
+            "is_terminal is true but terminal_value is None: {state:?}"
+        ),
+    }
+}
+
+/// Checks that every legal move hands the turn to the opponent.
+///
+/// Only meaningful for games where turns strictly alternate.
+pub fn check_player_alternates<G: GameState + Debug>(state: &G) {
+    let mover = state.current_player();
+    for mv in state.legal_moves() {
+        let child = state.apply_move(&mv);
+        assert_eq!(
+            child.current_player(),
+            opposite_player(mover),
+            "turn did not pass to the opponent after a move from {state:?}"
+        );
+    }
+}
+
+/// Checks that `try_apply_move` accepts every legal move of an ongoing
+/// game (with the same result as `apply_move`) and rejects every move once
+/// the game is over.
+pub fn check_try_apply_move<G>(state: &G)
+where
+    G: GameState + PartialEq + Debug,
+    G::Move: Debug,
+{
+    for mv in state.legal_moves() {
+        if state.is_terminal() {
+            assert_eq!(
+                state.check_move(&mv),
+                Err(MoveError::GameOver),
+                "move {mv:?} was accepted after the game ended: {state:?}"
+            );
+        } else {
+            assert_eq!(
+                state.try_apply_move(&mv),
+                Ok(state.apply_move(&mv)),
+                "legal move {mv:?} was rejected in {state:?}"
+            );
+        }
+    }
+}
+
+/// Checks that formatting a state and parsing it back yields the same state.
+///
+/// `to_notation` and `from_notation` are the game's own codec, e.g.
+/// `TicTacToeState::to_repr` paired with `TicTacToeState::from_str`.
+pub fn check_notation_round_trip<G, F, P, E>(state: &G, to_notation: F, from_notation: P)
+where
+    G: GameState + PartialEq + Debug,
+    F: Fn(&G) -> String,
+    P: Fn(&str) -> Result<G, E>,
+    E: Debug,
+{
+    let text = to_notation(state);
+    let parsed = from_notation(&text)
+        .unwrap_or_else(|e| panic!("failed to parse own notation {text:?}: {e:?}"));
+    assert_eq!(parsed, *state, "notation {text:?} did not round-trip");
+}
+
+/// Runs every generic property check on a single state.
+///
+/// - `alternating`: also check that turns strictly alternate.
+pub fn check_state_properties<G>(state: &G, alternating: bool)
+where
+    G: GameState + PartialEq + Debug,
+    G::Move: PartialEq + Debug,
+{
+    check_apply_move_is_pure(state);
+    check_legal_moves_applicable(state);
+    check_for_each_matches_legal_moves(state);
+    check_terminal_consistency(state);
+    check_try_apply_move(state);
+    if alternating {
+        check_player_alternates(state);
+    }
+}
+
+/// Checks that `swap_players` exchanges the players: swapping twice gives
+/// the state back, the other player is to move, and terminal and heuristic
+/// values are negated.
+pub fn check_swap_players<G>(state: &G)
+where
+    G: GameState + SwapPlayers + PartialEq + Debug,
+{
+    let swapped = state.swap_players();
+    assert_eq!(
+        &swapped.swap_players(),
+        state,
+        "swapping twice changed the state"
+    );
+    assert_eq!(
+        swapped.current_player(),
+        opposite_player(state.current_player()),
+        "swapping kept the side to move: {state:?}"
+    );
+    assert_eq!(
+        swapped.terminal_value(),
+        state.terminal_value().map(|v| -v),
+        "swapping didn't negate the terminal value: {state:?}"
+    );
+    assert_eq!(
+        swapped.heuristic_value(),
+        -state.heuristic_value(),
+        "swapping didn't negate the heuristic value: {state:?}"
+    );
+}
+
+/// Walks the game tree from `root` up to `max_plies` deep (stopping at
+/// terminal states) and calls `check` on every visited state.
+///
+/// Returns the number of states visited.
+pub fn walk_game_tree<G: GameState>(root: &G, max_plies: u32, check: &mut impl FnMut(&G)) -> u64 {
+    check(root);
+    if max_plies == 0 || root.is_terminal() {
+        return 1;
+    }
+    let mut visited = 1;
+    for mv in root.legal_moves() {
+        visited += walk_game_tree(&root.apply_move(&mv), max_plies - 1, check);
+    }
+    visited
+}
+
+/// Plays out `choices` from `root`, picking `legal_moves()[choice % len]` at
+/// each ply and stopping early at terminal states.
+///
+/// This is the deterministic core of the random-position strategies below:
+/// the same choices always produce the same position.
+pub fn playout<G: GameState>(root: &G, choices: &[u32]) -> G {
+    let mut state = root.clone();
+    for &choice in choices {
+        if state.is_terminal() {
+            break;
+        }
+        let moves = state.legal_moves();
+        if moves.is_empty() {
+            break;
+        }
+        state = state.apply_move(&moves[choice as usize % moves.len()]);
+    }
+    state
+}
+
+/// Returns a proptest strategy producing random legal positions reached by
+/// random playouts of up to `max_plies` moves from `root`.
+///
+/// Shrinking shortens the playout, so failures shrink towards positions
+/// close to `root`.
+pub fn random_position<G>(root: G, max_plies: usize) -> impl Strategy<Value = G>
+where
+    G: GameState + Debug + 'static,
+{
+    vec(any::<u32>(), 0..=max_plies).prop_map(move |choices| playout(&root, &choices))
+}
+
+/// Checks that `with_extra`, which is `state` with one more piece for
+/// `owner` and the same player to move, is evaluated at least as well for
+/// `owner`: an extra piece never hurts its owner.
+pub fn check_extra_piece_helps<G, E>(evaluator: &E, state: &G, with_extra: &G, owner: Player)
+where
+    G: GameState + Debug,
+    E: Evaluator<G>,
+{
+    let (before, after) = (evaluator.evaluate(state), evaluator.evaluate(with_extra));
+    let helps = match owner {
+        Player::Player1 => after >= before,
+        Player::Player2 => after <= before,
+    };
+    assert!(
+        helps,
+        "an extra piece for {owner:?} moved the evaluation from {before} to {after}: {state:?} -> {with_extra:?}"
+    );
+}
+
+/// Checks that `state` and `image`, its image under a symmetry of the
+/// board that the rules respect (e.g. a mirror), are evaluated equally.
+pub fn check_eval_symmetric<G, E>(evaluator: &E, state: &G, image: &G)
+where
+    G: GameState + Debug,
+    E: Evaluator<G>,
+{
+    assert_eq!(
+        evaluator.evaluate(state),
+        evaluator.evaluate(image),
+        "symmetric positions evaluated differently: {state:?} and {image:?}"
+    );
+}
+
+/// Checks that `swapped`, which is `state` with every piece handed to the
+/// other player (and the other player to move), is evaluated as the
+/// negation of `state`.
+pub fn check_eval_swaps_sign<G, E>(evaluator: &E, state: &G, swapped: &G)
+where
+    G: GameState + Debug,
+    E: Evaluator<G>,
+{
+    assert_eq!(
+        evaluator.evaluate(swapped),
+        -evaluator.evaluate(state),
+        "swapping the players didn't negate the evaluation: {state:?} and {swapped:?}"
+    );
+}
+
+/// Checks that the evaluation of a non-terminal `state` stays below the
+/// score of a proven win, so no heuristic value outweighs a forced win.
+pub fn check_wins_dominate_eval<G, E>(evaluator: &E, state: &G)
+where
+    G: GameState + Debug,
+    E: Evaluator<G>,
+{
+    if state.is_terminal() {
+        return;
+    }
+    let value = evaluator.evaluate(state);
+    assert!(
+        value.abs() < WIN_SCORE,
+        "evaluation {value} rivals a proven win: {state:?}"
+    );
+}
+
+/// Asserts that the array-based and bitboard Connect Four states describe
+/// the same position: same side to move, same discs, same legal columns,
+/// and the same terminal status and value.
+pub fn assert_c4_states_agree(array: &ConnectFourState, bitboard: &BitboardState) {
+    assert_eq!(
+        array.current_player(),
+        bitboard.current_player(),
+        "side to move differs"
+    );
+    assert_eq!(
+        (array.rows(), array.cols()),
+        (bitboard.rows(), bitboard.cols()),
+        "board sizes differ"
+    );
+    for row in 0..array.rows() {
+        for col in 0..array.cols() {
+            assert_eq!(
+                array.cell(row, col),
+                bitboard.cell(row, col),
+                "cell (row {row}, col {col}) differs"
+            );
+        }
+    }
+    let array_moves: Vec<u8> = array.legal_moves().iter().map(|m| m.column).collect();
+    assert_eq!(array_moves, bitboard.legal_moves(), "legal moves differ");
+    assert_eq!(
+        array.is_terminal(),
+        bitboard.is_terminal(),
+        "terminal status differs"
+    );
+    assert_eq!(
+        array.terminal_value(),
+        bitboard.terminal_value(),
+        "terminal value differs"
+    );
+}
+
+/// Plays both Connect Four implementations in lockstep, choosing moves from
+/// `choices` as in `playout`, and checks they agree after every ply.
+///
+/// Returns the number of plies played.
+pub fn check_c4_lockstep(choices: &[u32]) -> usize {
+    let mut array = ConnectFourState::new();
+    let mut bitboard = BitboardState::new();
+    assert_c4_states_agree(&array, &bitboard);
+
+    let mut plies = 0;
+    for &choice in choices {
+        if bitboard.is_terminal() {
+            break;
+        }
+        let moves = bitboard.legal_moves();
+        let col = moves[choice as usize % moves.len()];
+        array = array.apply_move(&ConnectFourMove { column: col });
+        bitboard = bitboard.apply_move(&col);
+        plies += 1;
+        assert_c4_states_agree(&array, &bitboard);
+    }
+    plies
+}
+
+/// Runs the evaluator checks on a Connect Four position: an extra disc in
+/// any playable cell helps its owner, the mirrored position is evaluated
+/// the same, swapping the discs' colours negates the evaluation, and no
+/// evaluation rivals a win.
+///
+/// The transformed positions may not be reachable in a game (e.g. after an
+/// extra disc both players have as many discs but Player2 is to move);
+/// evaluators should still score them sensibly.
+pub fn check_c4_evaluator_properties<E: Evaluator<BitboardState>>(
+    evaluator: &E,
+    state: &BitboardState,
+) {
+    if state.is_terminal() {
+        return;
+    }
+    let with = |player_bb: u64, mask_bb: u64, current_player: Player| {
+        let mut s = BitboardState::new();
+        s.player_bb = player_bb;
+        s.mask_bb = mask_bb;
+        s.current_player = current_player;
+        s
+    };
+    let mut cells = state.playable_cells();
+    while cells != 0 {
+        let bit = cells & cells.wrapping_neg();
+        for owner in [Player::Player1, Player::Player2] {
+            let player_bb = match owner {
+                Player::Player1 => state.player_bb | bit,
+                Player::Player2 => state.player_bb,
+            };
+            let with_extra = with(player_bb, state.mask_bb | bit, state.current_player);
+            if !with_extra.is_terminal() {
+                check_extra_piece_helps(evaluator, state, &with_extra, owner);
+            }
+        }
+        cells &= cells - 1;
+    }
+    check_eval_symmetric(evaluator, state, &state.mirrored());
+    check_eval_swaps_sign(evaluator, state, &state.swap_players());
+    check_wins_dominate_eval(evaluator, state);
+}
+
+/// Asserts that the array-based and bitboard Tic-Tac-Toe states are the
+/// same game state: same side to move, marks, legal moves (in order),
+/// terminal status and value, heuristic value, move ordering and position
+/// key.
+pub fn assert_ttt_states_agree(array: &TicTacToeState, bitboard: &TicTacToeBitboard) {
+    assert_eq!(
+        array.current_player(),
+        bitboard.current_player(),
+        "side to move differs"
+    );
+    assert_eq!(array.to_repr(), bitboard.to_repr(), "marks differ");
+    let moves = array.legal_moves();
+    assert_eq!(moves, bitboard.legal_moves(), "legal moves differ");
+    assert_eq!(
+        array.terminal_value(),
+        bitboard.terminal_value(),
+        "terminal value differs"
+    );
+    assert_eq!(
+        array.is_terminal(),
+        bitboard.is_terminal(),
+        "terminal status differs"
+    );
+    assert_eq!(
+        array.heuristic_value(),
+        bitboard.heuristic_value(),
+        "heuristic value differs"
+    );
+    for mv in &moves {
+        assert_eq!(
+            array.move_ordering_key(mv),
+            bitboard.move_ordering_key(mv),
+            "ordering of move {} differs",
+            mv.index
+        );
+    }
+    assert_eq!(
+        array.position_key(),
+        bitboard.position_key(),
+        "position key differs"
+    );
+}
+
+/// Walks the whole Tic-Tac-Toe game tree with both implementations in
+/// lockstep, checking they agree at every node.
+///
+/// Returns the number of distinct positions seen.
+pub fn check_ttt_lockstep() -> usize {
+    fn walk(array: &TicTacToeState, bitboard: &TicTacToeBitboard, seen: &mut HashSet<u64>) {
+        assert_ttt_states_agree(array, bitboard);
+        if !seen.insert(bitboard.position_key()) || bitboard.is_terminal() {
+            return;
+        }
+        for mv in bitboard.legal_moves() {
+            walk(&array.apply_move(&mv), &bitboard.apply_move(&mv), seen);
+        }
+    }
+    let mut seen = HashSet::new();
+    walk(&TicTacToeState::new(), &TicTacToeBitboard::new(), &mut seen);
+    seen.len()
+}
+
+/// Transposition table size used by `solver_results`, in entries.
+const DIFFERENTIAL_TT_ENTRIES: usize = 1 << 16;
+
+/// Solves `state` with every solver in the crate and returns each one's
+/// result for Player1 (1 win, 0 draw, -1 loss), labelled by solver.
+///
+/// The depth-limited solvers search `depth` plies; it must reach the end of
+/// every line from `state` for their results to be exact. Each
+/// table-backed solver gets a fresh table, so one can't mask another. New
+/// solvers belong in this list, so `differential_test` covers them.
+pub fn solver_results<G>(state: &G, depth: u32) -> Vec<(&'static str, i32)>
+where
+    G: GameState + PositionKey + Sync,
+    G::Move: PartialEq + Send + Sync,
+{
+    let table =
+        || TranspositionTable::with_entries(DIFFERENTIAL_TT_ENTRIES, ReplacementPolicy::TwoTier);
+    let exact = |best: Option<(G::Move, i32)>| match best {
+        Some((_, v)) => v.signum(),
+        None => state.terminal_value().unwrap_or(0),
+    };
+    let searched =
+        |result: SearchResult<G::Move>| exact(result.best_move().cloned().zip(result.value()));
+    vec![
+        ("minimax_value", minimax_value(state)),
+        ("minimax_best_move", exact(minimax_best_move(state))),
+        ("minimax_value_ab_root", minimax_value_ab_root(state)),
+        ("minimax_best_move_ab", exact(minimax_best_move_ab(state))),
+        (
+            "minimax_value_ab_depth_root",
+            minimax_value_ab_depth_root(state, depth).signum(),
+        ),
+        (
+            "minimax_best_move_ab_depth",
+            exact(minimax_best_move_ab_depth(state, depth)),
+        ),
+        (
+            "minimax_best_move_ab_depth_tt",
+            exact(minimax_best_move_ab_depth_tt(state, depth, &mut table()).0),
+        ),
+        ("mtdf", exact(mtdf(state, depth, 0, &mut table()).0)),
+        (
+            "iterative_search (mtdf)",
+            exact(iterative_search(state, depth, SearchAlgorithm::Mtdf, &mut table()).0),
+        ),
+        (
+            "batched_alpha_beta",
+            exact(batched_alpha_beta(state, depth, 2, &HeuristicEvaluator).0),
+        ),
+        ("Search", searched(Search::new(state).depth(depth).run())),
+        (
+            "Search (tt, threads)",
+            searched(Search::new(state).depth(depth).with_tt(1).parallel(3).run()),
+        ),
+        (
+            "solve_exact",
+            solve_exact(state, &mut table(), None, |_, _| {})
+                .expect("the solve has no cancel token")
+                .outcome
+                .value(),
+        ),
+    ]
+}
+
+/// A position on which the solvers disagree, found by `differential_test`.
+#[derive(Clone)]
+pub struct Mismatch<G> {
+    /// The `playout` choices leading from the root to `state`, shrunk as
+    /// far as the disagreement allows.
+    pub choices: Vec<u32>,
+    pub state: G,
+    /// Every solver's result, as returned by `solver_results`.
+    pub results: Vec<(&'static str, i32)>,
+}
+
+impl<G: Debug> fmt::Display for Mismatch<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "solvers disagree on {:?}", self.state)?;
+        writeln!(f, "reached by playout choices {:?}", self.choices)?;
+        for (solver, result) in &self.results {
+            writeln!(f, "  {solver:<30} {result:+}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<G: Debug> Debug for Mismatch<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// The solver results for `state`, or `None` if they all agree.
+fn disagreement<G>(state: &G, depth: u32) -> Option<Vec<(&'static str, i32)>>
+where
+    G: GameState + PositionKey + Sync,
+    G::Move: PartialEq + Send + Sync,
+{
+    let results = solver_results(state, depth);
+    let first = results[0].1;
+    results.iter().any(|&(_, r)| r != first).then_some(results)
+}
+
+/// Cross-checks the solvers on `cases` random positions reached by playouts
+/// (as in `playout`) of a length in `plies` from `root`, and returns the
+/// number of positions checked.
+///
+/// A playout stops short of any move that would end the game, since the
+/// best-move solvers only search ongoing positions; playouts cut below
+/// `plies.start()` are replaced, so that many plies must be reachable
+/// without ending the game.
+///
+/// `depth` is the depth given to the depth-limited solvers, so it should
+/// cover every remaining move of the generated positions. On the first
+/// disagreement, the playout is shrunk, by dropping and lowering choices
+/// while the solvers still disagree, and the smallest failing position is
+/// returned. The positions depend only on `seed`.
+pub fn differential_test<G>(
+    root: &G,
+    plies: RangeInclusive<usize>,
+    depth: u32,
+    cases: usize,
+    seed: u64,
+) -> Result<usize, Mismatch<G>>
+where
+    G: GameState + PositionKey + Sync,
+    G::Move: PartialEq + Send + Sync,
+{
+    let mut rng = SplitMix64::new(seed);
+    let span = (plies.end() - plies.start() + 1) as u64;
+    for _ in 0..cases {
+        let choices = loop {
+            let len = plies.start() + rng.below(span) as usize;
+            let choices: Vec<u32> = (0..len).map(|_| rng.next_u64() as u32).collect();
+            let choices = ongoing_choices(root, &choices);
+            if choices.len() >= *plies.start() {
+                break choices;
+            }
+        };
+        if disagreement(&playout(root, &choices), depth).is_some() {
+            return Err(shrink_mismatch(root, choices, depth));
+        }
+    }
+    Ok(cases)
+}
+
+/// The `playout` of `choices` from `root` stopped before the game ends,
+/// with each choice replaced by the index of the move it picks (so that
+/// lowering a choice always picks a different move).
+fn ongoing_choices<G: GameState>(root: &G, choices: &[u32]) -> Vec<u32> {
+    let mut state = root.clone();
+    choices
+        .iter()
+        .map_while(|&choice| {
+            let moves = state.legal_moves();
+            if moves.is_empty() {
+                return None;
+            }
+            let index = choice as usize % moves.len();
+            let child = state.apply_move(&moves[index]);
+            if child.is_terminal() {
+                return None;
+            }
+            state = child;
+            Some(index as u32)
+        })
+        .collect()
+}
+
+/// Shrinks failing playout `choices` from `root` to a shorter or smaller
+/// sequence on which the solvers still disagree.
+fn shrink_mismatch<G>(root: &G, choices: Vec<u32>, depth: u32) -> Mismatch<G>
+where
+    G: GameState + PositionKey + Sync,
+    G::Move: PartialEq + Send + Sync,
+{
+    let choices = shrink_choices(root, choices, |choices| {
+        disagreement(&playout(root, choices), depth).is_some()
+    });
+    let state = playout(root, &choices);
+    let results = disagreement(&state, depth).expect("shrinking keeps the disagreement");
+    Mismatch {
+        choices,
+        state,
+        results,
+    }
+}
+
+/// Shrinks playout `choices` from `root`, by dropping and lowering them,
+/// to a sequence of `ongoing_choices` that still `fails`.
+fn shrink_choices<G: GameState>(
+    root: &G,
+    choices: Vec<u32>,
+    fails: impl Fn(&[u32]) -> bool,
+) -> Vec<u32> {
+    let mut choices = ongoing_choices(root, &choices);
+    let fails = |choices: &[u32]| fails(&ongoing_choices(root, choices));
+    let mut shrunk = true;
+    while shrunk {
+        shrunk = false;
+        // Dropping choice `i` keeps the ones before it, so at least `i`
+        // remain and the next, lower index is still in range.
+        for i in (0..choices.len()).rev() {
+            let mut shorter = choices.clone();
+            shorter.remove(i);
+            if fails(&shorter) {
+                choices = ongoing_choices(root, &shorter);
+                shrunk = true;
+            }
+        }
+        // A lowered choice can end the game sooner and cut the choices
+        // after it, so the length is checked again every time.
+        let mut i = 0;
+        while i < choices.len() {
+            // Smaller choices pick earlier legal moves.
+            for smaller in 0..choices[i] {
+                let mut candidate = choices.clone();
+                candidate[i] = smaller;
+                if fails(&candidate) {
+                    choices = ongoing_choices(root, &candidate);
+                    shrunk = true;
+                    break;
+                }
+            }
+            i += 1;
+        }
+    }
+    choices
+}
+
+impl Arbitrary for TicTacToeState {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Random reachable Tic-Tac-Toe positions (possibly terminal).
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        random_position(TicTacToeState::new(), 9).boxed()
+    }
+}
+
+impl Arbitrary for BitboardState {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Random reachable Connect Four positions (possibly terminal).
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        random_position(BitboardState::new(), 42).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::HeuristicEvaluator;
+    use crate::game::Player;
+    use crate::games::breakthrough::BreakthroughState;
+    use crate::games::c4_3d::ScoreFourState;
+    use crate::games::c4_bitboard::C4EvalParams;
+    use crate::games::c4_variants::{C4Rules, VariantState};
+    use crate::games::pentago::PentagoState;
+    use crate::solvers::minimax::{minimax_value_ab_depth_root, minimax_value_ab_root};
+
+    #[test]
+    fn ttt_satisfies_properties_on_full_tree() {
+        let visited = walk_game_tree(&TicTacToeState::new(), 9, &mut |s| {
+            check_state_properties(s, true);
+            check_notation_round_trip(s, TicTacToeState::to_repr, |r| {
+                TicTacToeState::from_str(r, s.current_player)
+            });
+        });
+        // Full TTT game tree (terminal positions cut off), including the root.
+        assert_eq!(visited, 549_946);
+    }
+
+    #[test]
+    fn ttt_bitboard_satisfies_properties_on_full_tree() {
+        let visited = walk_game_tree(&TicTacToeBitboard::new(), 9, &mut |s| {
+            check_state_properties(s, true);
+            check_notation_round_trip(s, TicTacToeBitboard::to_repr, |r| {
+                TicTacToeBitboard::from_str(r, s.current_player())
+            });
+        });
+        assert_eq!(visited, 549_946);
+    }
+
+    #[test]
+    fn ttt_implementations_agree_on_every_position() {
+        assert_eq!(check_ttt_lockstep(), 5_478);
+    }
+
+    #[test]
+    fn c4_array_satisfies_properties_near_root() {
+        walk_game_tree(&ConnectFourState::new(), 4, &mut |s| {
+            check_state_properties(s, true);
+            check_notation_round_trip(s, ConnectFourState::to_repr, |r| {
+                ConnectFourState::from_str(r, s.current_player)
+            });
+        });
+    }
+
+    #[test]
+    fn c4_bitboard_satisfies_properties_near_root() {
+        walk_game_tree(&BitboardState::new(), 5, &mut |s| {
+            check_state_properties(s, true);
+        });
+    }
+
+    #[test]
+    fn solvers_agree_on_random_positions() {
+        let checked = differential_test(&TicTacToeState::new(), 2..=9, 9, 150, 1);
+        assert_eq!(checked.unwrap(), 150);
+        let checked = differential_test(&BitboardState::new(), 34..=42, 8, 40, 2);
+        assert_eq!(checked.unwrap(), 40);
+    }
+
+    #[test]
+    fn disagreements_are_shrunk_and_reported() {
+        // Two plies can't see Tic-Tac-Toe to the end, so the depth-limited
+        // solvers' heuristic results differ from the exact ones somewhere.
+        let mismatch = differential_test(&TicTacToeState::new(), 2..=6, 2, 200, 3).unwrap_err();
+        assert_eq!(
+            mismatch.state,
+            playout(&TicTacToeState::new(), &mismatch.choices)
+        );
+        let first = mismatch.results[0].1;
+        assert!(mismatch.results.iter().any(|&(_, r)| r != first));
+        // No single choice can be dropped or lowered any further.
+        for i in 0..mismatch.choices.len() {
+            let mut shorter = mismatch.choices.clone();
+            shorter.remove(i);
+            assert!(disagreement(&playout(&TicTacToeState::new(), &shorter), 2).is_none());
+        }
+        let report = mismatch.to_string();
+        assert!(report.starts_with("solvers disagree on "));
+        assert!(report.contains("minimax_value_ab_depth_root"));
+    }
+
+    #[test]
+    fn shrinking_survives_choices_that_end_the_game_sooner() {
+        // Lowering a choice changes the moves after it, which can end the
+        // game earlier and cut the sequence short of the next index.
+        let root = TicTacToeState::new();
+        let mut rng = SplitMix64::new(5);
+        for _ in 0..200 {
+            let choices: Vec<u32> = (0..9).map(|_| rng.below(9) as u32).collect();
+            let fails = |choices: &[u32]| [4, 6].contains(&choices.len());
+            if fails(&ongoing_choices(&root, &choices)) {
+                assert!(fails(&shrink_choices(&root, choices, fails)));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "did not round-trip")]
+    fn notation_round_trip_detects_lossy_codec() {
+        let s = TicTacToeState::from_str("X........", Player::Player2).unwrap();
+        check_notation_round_trip(&s, TicTacToeState::to_repr, |r| {
+            TicTacToeState::from_str(r, Player::Player1)
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "an extra piece for Player1")]
+    fn evaluator_checks_catch_weights_that_punish_pieces() {
+        let params = C4EvalParams {
+            window_two: -10,
+            ..C4EvalParams::default()
+        };
+        let s = BitboardState::from_move_sequence("3").unwrap();
+        check_c4_evaluator_properties(&params, &s);
+    }
+
+    #[test]
+    #[should_panic(expected = "symmetric positions evaluated differently")]
+    fn evaluator_checks_catch_lopsided_column_weights() {
+        let params = C4EvalParams {
+            col_weights: [1, 2, 3, 4, 5, 6, 7],
+            ..C4EvalParams::default()
+        };
+        let s = BitboardState::from_move_sequence("0").unwrap();
+        check_c4_evaluator_properties(&params, &s);
+    }
+
+    proptest! {
+        #[test]
+        fn ttt_random_positions_satisfy_properties(s in any::<TicTacToeState>()) {
+            check_state_properties(&s, true);
+        }
+
+        #[test]
+        fn c4_random_positions_satisfy_properties(s in any::<BitboardState>()) {
+            check_state_properties(&s, true);
+        }
+
+        #[test]
+        fn c4_variant_positions_satisfy_properties(choices in vec(any::<u32>(), 0..=60)) {
+            let rules = C4Rules { pop_out: true, win_length: 5, wrap_around: true };
+            check_state_properties(&playout(&VariantState::new(rules), &choices), true);
+        }
+
+        #[test]
+        fn c4_heuristics_satisfy_evaluator_properties(
+            s in random_position(BitboardState::new(), 30)
+        ) {
+            check_c4_evaluator_properties(&C4EvalParams::default(), &s);
+            check_c4_evaluator_properties(&HeuristicEvaluator, &s);
+        }
+
+        #[test]
+        fn swapping_players_negates_values(choices in vec(any::<u32>(), 0..=60)) {
+            let ttt = playout(&TicTacToeState::new(), &choices);
+            check_swap_players(&ttt);
+            check_swap_players(&TicTacToeBitboard::from(&ttt));
+            check_swap_players(&playout(&ConnectFourState::new(), &choices));
+            check_swap_players(&playout(&BitboardState::new(), &choices));
+            let rules = C4Rules { pop_out: true, win_length: 5, wrap_around: true };
+            check_swap_players(&playout(&VariantState::new(rules), &choices));
+            check_swap_players(&playout(&BreakthroughState::new(), &choices));
+        }
+
+        #[test]
+        fn score_four_positions_satisfy_properties(choices in vec(any::<u32>(), 0..=64)) {
+            check_state_properties(&playout(&ScoreFourState::new(), &choices), true);
+        }
+
+        #[test]
+        fn pentago_positions_satisfy_properties(choices in vec(any::<u32>(), 0..=36)) {
+            check_state_properties(&playout(&PentagoState::new(), &choices), true);
+        }
+
+        #[test]
+        fn breakthrough_positions_satisfy_properties(choices in vec(any::<u32>(), 0..=100)) {
+            check_state_properties(&playout(&BreakthroughState::new(), &choices), true);
+        }
+
+        #[test]
+        fn ttt_full_depth_search_matches_exact_value(s in any::<TicTacToeState>()) {
+            let exact = minimax_value_ab_root(&s);
+            prop_assert_eq!(minimax_value_ab_depth_root(&s, 9), exact * 1_000_000);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2_000))]
+
+        #[test]
+        fn c4_implementations_agree_in_lockstep(choices in vec(any::<u32>(), 0..=42)) {
+            check_c4_lockstep(&choices);
+        }
+    }
+
+    #[test]
+    fn c4_lockstep_covers_full_board_draw() {
+        // Greedily play the first legal column that does not end the game,
+        // which fills the whole board without a winner. This exercises the
+        // draw path that random playouts rarely reach.
+        let mut choices = Vec::new();
+        let mut bitboard = BitboardState::new();
+        for ply in 0..42 {
+            let legal = bitboard.legal_moves();
+            let idx = legal
+                .iter()
+                .position(|c| ply == 41 || !bitboard.apply_move(c).is_terminal())
+                .expect("a non-terminating column should exist");
+            choices.push(idx as u32);
+            bitboard = bitboard.apply_move(&legal[idx]);
+        }
+        assert_eq!(bitboard.terminal_value(), Some(0));
+        assert_eq!(check_c4_lockstep(&choices), 42);
+    }
+
+    #[cfg(feature = "serde")]
+    proptest! {
+        #[test]
+        fn ttt_serde_round_trips(s in any::<TicTacToeState>()) {
+            let bitboard = TicTacToeBitboard::from(&s);
+            let json = serde_json::to_string(&s).unwrap();
+            prop_assert_eq!(serde_json::from_str::<TicTacToeState>(&json).unwrap(), s);
+            let json = serde_json::to_string(&bitboard).unwrap();
+            prop_assert_eq!(serde_json::from_str::<TicTacToeBitboard>(&json).unwrap(), bitboard);
+        }
+
+        #[test]
+        fn c4_serde_round_trips(choices in vec(any::<u32>(), 0..=42)) {
+            let bitboard = playout(&BitboardState::new(), &choices);
+            let json = serde_json::to_string(&bitboard).unwrap();
+            prop_assert_eq!(serde_json::from_str::<BitboardState>(&json).unwrap(), bitboard);
+
+            let array = playout(&ConnectFourState::new(), &choices);
+            let json = serde_json::to_string(&array).unwrap();
+            prop_assert_eq!(serde_json::from_str::<ConnectFourState>(&json).unwrap(), array);
+        }
+
+        #[test]
+        fn c4_variant_serde_round_trips(choices in vec(any::<u32>(), 0..=60)) {
+            let rules = C4Rules { pop_out: true, wrap_around: true, ..C4Rules::STANDARD };
+            let state = playout(&VariantState::new(rules), &choices);
+            let json = serde_json::to_string(&state).unwrap();
+            let back = serde_json::from_str::<VariantState>(&json).unwrap();
+            prop_assert_eq!(back.ply(), state.ply());
+            prop_assert_eq!(back, state);
+        }
+
+        #[test]
+        fn score_four_serde_round_trips(choices in vec(any::<u32>(), 0..=64)) {
+            let state = playout(&ScoreFourState::new(), &choices);
+            let json = serde_json::to_string(&state).unwrap();
+            prop_assert_eq!(serde_json::from_str::<ScoreFourState>(&json).unwrap(), state);
+        }
+
+        #[test]
+        fn pentago_serde_round_trips(choices in vec(any::<u32>(), 0..=36)) {
+            let state = playout(&PentagoState::new(), &choices);
+            let json = serde_json::to_string(&state).unwrap();
+            prop_assert_eq!(serde_json::from_str::<PentagoState>(&json).unwrap(), state);
+        }
+
+        #[test]
+        fn breakthrough_serde_round_trips(choices in vec(any::<u32>(), 0..=100)) {
+            let state = playout(&BreakthroughState::new(), &choices);
+            let json = serde_json::to_string(&state).unwrap();
+            let back = serde_json::from_str::<BreakthroughState>(&json).unwrap();
+            prop_assert_eq!(back.ply(), state.ply());
+            prop_assert_eq!(back, state);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ttt_bitboard_serde_rejects_impossible_marks() {
+        let parse = |x: u16, o: u16| {
+            let json = format!(r#"{{"x":{x},"o":{o},"current_player":"Player1"}}"#);
+            serde_json::from_str::<TicTacToeBitboard>(&json)
+        };
+        assert!(parse(0b1_0000_0001, 0b10).is_ok());
+        assert!(parse(512, 0).is_err());
+        assert!(parse(0, u16::MAX).is_err());
+        assert!(parse(0b11, 0b10).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn c4_serde_rejects_floating_discs() {
+        let mut board = ".".repeat(42);
+        board.replace_range(0..1, "X");
+        let json = format!(r#"{{"board":"{board}","current_player":"Player2"}}"#);
+        assert!(serde_json::from_str::<ConnectFourState>(&json).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn pentago_serde_rejects_impossible_marbles() {
+        let parse = |player1: u64, player2: u64| {
+            let json = format!(r#"{{"player1":{player1},"player2":{player2}}}"#);
+            serde_json::from_str::<PentagoState>(&json)
+        };
+        assert!(parse(0b1, 0b10).is_ok());
+        assert!(parse(0b11, 0).is_err());
+        assert!(parse(0b1, 0b1).is_err());
+        assert!(parse(1 << 40, 0).is_err());
+    }
+}
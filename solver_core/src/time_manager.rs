@@ -0,0 +1,265 @@
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::cancel::CancelToken;
+use crate::clock::{Clock, TimeControl};
+use crate::game::GameState;
+use crate::solvers::iterative::{
+    DepthReport, iterative_deepening, iterative_deepening_cancellable,
+};
+
+/// How much longer than planned a move may take when the search is
+/// unstable, as a multiple of the planned time.
+const MAX_STRETCH: u32 = 3;
+/// The planned time grows by this factor each time the best move changes
+/// between iterations.
+const UNSTABLE_EXTENSION: f64 = 1.5;
+/// A new iteration is only started while less than this fraction of the
+/// planned time is used: the next depth usually takes longer than all
+/// previous ones together.
+const START_ITERATION_FRACTION: f64 = 0.5;
+/// Fraction of the remaining time a single move may never exceed.
+const SAFETY: f64 = 0.9;
+
+/// A monotonic clock for `TimeManager`.
+///
+/// With `std`, `InstantTimeSource` reads `std::time::Instant`; on targets
+/// without it, implement this over a hardware timer.
+pub trait TimeSource {
+    /// The time since some fixed point. It must never go backwards.
+    fn now(&self) -> Duration;
+}
+
+/// `std::time::Instant` as a `TimeSource`, counting from its creation.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct InstantTimeSource {
+    origin: Instant,
+}
+
+#[cfg(feature = "std")]
+impl Default for InstantTimeSource {
+    fn default() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl TimeSource for InstantTimeSource {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// Decides how long an engine thinks about one move.
+///
+/// Each move gets a planned time (the clock's `budget`) and a hard limit.
+/// Iterative deepening stops before the next depth once the planned time
+/// is mostly used; every change of best move between depths extends the
+/// planned time towards the hard limit, and positions with a single legal
+/// move are played at once.
+#[derive(Clone, Debug)]
+pub struct TimeManager<T> {
+    planned: Duration,
+    hard: Duration,
+    time: T,
+    started: Duration,
+    stopped: bool,
+}
+
+#[cfg(feature = "std")]
+impl TimeManager<InstantTimeSource> {
+    /// Starts timing a move for the player owning `clock`.
+    pub fn new(clock: &Clock) -> Self {
+        Self::with_time_source(clock, InstantTimeSource::default())
+    }
+}
+
+impl<T: TimeSource> TimeManager<T> {
+    /// Like `new`, but reads the time from `time`.
+    pub fn with_time_source(clock: &Clock, time: T) -> Self {
+        let remaining = clock.remaining().mul_f64(SAFETY);
+        let planned = clock.budget().min(remaining);
+        let hard = match clock.control() {
+            // Unused time is lost, so plan to use the whole limit.
+            TimeControl::PerMove(_) => planned,
+            _ => (planned * MAX_STRETCH).min(remaining).max(planned),
+        };
+        Self {
+            planned,
+            hard,
+            started: time.now(),
+            time,
+            stopped: false,
+        }
+    }
+
+    /// Time spent on this move so far.
+    fn elapsed(&self) -> Duration {
+        self.time.now().saturating_sub(self.started)
+    }
+
+    /// The time currently planned for this move.
+    pub fn planned(&self) -> Duration {
+        self.planned
+    }
+
+    /// The time after which the search is cancelled outright.
+    pub fn hard_limit(&self) -> Duration {
+        self.hard
+    }
+
+    /// Returns true if the manager ended the search (as opposed to it
+    /// finishing, or being cancelled by someone else).
+    pub fn stopped(&self) -> bool {
+        self.stopped || self.elapsed() >= self.hard
+    }
+
+    /// Called after each completed iteration, `elapsed` into the move.
+    /// Returns whether to search another depth.
+    pub fn next_iteration(&mut self, best_move_changed: bool, elapsed: Duration) -> bool {
+        if best_move_changed {
+            self.planned = self.planned.mul_f64(UNSTABLE_EXTENSION).min(self.hard);
+        }
+        let more = elapsed < self.planned.mul_f64(START_ITERATION_FRACTION);
+        self.stopped |= !more;
+        more
+    }
+
+    /// Iterative deepening to at most `max_depth`, timed by this manager.
+    ///
+    /// `cancel` is also cancelled when time runs out, so other code (e.g. a
+    /// Ctrl-C handler) can share it. Forced moves are returned after a
+    /// depth-1 search.
+    ///
+    /// Without `std` there is no timer thread to cancel a running depth, so
+    /// the time is only checked between depths.
+    pub fn search<G>(
+        &mut self,
+        state: &G,
+        max_depth: u32,
+        cancel: &CancelToken,
+    ) -> Option<DepthReport<G::Move>>
+    where
+        G: GameState,
+        G::Move: PartialEq,
+    {
+        self.search_reporting(state, max_depth, cancel, |_| {})
+    }
+
+    /// Like `search`, but also calls `on_iteration` after each completed
+    /// depth.
+    pub fn search_reporting<G, F>(
+        &mut self,
+        state: &G,
+        max_depth: u32,
+        cancel: &CancelToken,
+        mut on_iteration: F,
+    ) -> Option<DepthReport<G::Move>>
+    where
+        G: GameState,
+        G::Move: PartialEq,
+        F: FnMut(&DepthReport<G::Move>),
+    {
+        if state.legal_moves().len() == 1 {
+            self.stopped = true;
+            return iterative_deepening(state, 1, on_iteration);
+        }
+        #[cfg(feature = "std")]
+        let _timer = cancel.cancel_after(self.hard.saturating_sub(self.elapsed()));
+        let mut previous: Option<G::Move> = None;
+        iterative_deepening_cancellable(state, max_depth, cancel, |report| {
+            on_iteration(report);
+            let changed = previous.as_ref().is_some_and(|mv| *mv != report.best_move);
+            previous = Some(report.best_move.clone());
+            if !self.next_iteration(changed, self.elapsed()) {
+                cancel.cancel();
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::game::Player;
+    #[cfg(feature = "std")]
+    use crate::games::c4_bitboard::BitboardState;
+    #[cfg(feature = "std")]
+    use crate::games::ttt::TicTacToeState;
+    use core::cell::Cell;
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn unstable_searches_get_more_time_up_to_the_hard_limit() {
+        let mut tm = TimeManager::new(&Clock::new(TimeControl::Absolute(60 * SEC)));
+        assert_eq!(tm.planned(), 3 * SEC);
+        assert_eq!(tm.hard_limit(), 9 * SEC);
+
+        assert!(!tm.next_iteration(false, 2 * SEC));
+        assert!(tm.next_iteration(true, 2 * SEC));
+        assert_eq!(tm.planned(), SEC * 9 / 2);
+        for _ in 0..5 {
+            tm.next_iteration(true, SEC);
+        }
+        assert_eq!(tm.planned(), tm.hard_limit());
+    }
+
+    /// A time source that only moves when told to.
+    struct ManualTime<'a>(&'a Cell<Duration>);
+
+    impl TimeSource for ManualTime<'_> {
+        fn now(&self) -> Duration {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn hard_limit_is_measured_with_the_time_source() {
+        let now = Cell::new(100 * SEC);
+        let clock = Clock::new(TimeControl::Absolute(60 * SEC));
+        let tm = TimeManager::with_time_source(&clock, ManualTime(&now));
+        now.set(108 * SEC);
+        assert!(!tm.stopped());
+        now.set(109 * SEC);
+        assert!(tm.stopped());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn per_move_limits_are_used_in_full() {
+        let tm = TimeManager::new(&Clock::new(TimeControl::PerMove(2 * SEC)));
+        assert_eq!(tm.planned(), tm.hard_limit());
+        assert!(tm.hard_limit() < 2 * SEC);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn ttt_forced_move_is_played_at_once() {
+        let s = TicTacToeState::from_str("XOXXOOOX.", Player::Player1).unwrap();
+        let mut tm = TimeManager::new(&Clock::new(TimeControl::Absolute(60 * SEC)));
+        let report = tm.search(&s, 9, &CancelToken::new()).unwrap();
+        assert_eq!(report.depth, 1);
+        assert_eq!(report.best_move.index, 8);
+        assert!(tm.stopped());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn c4_search_stops_within_the_hard_limit() {
+        let clock = Clock::new(TimeControl::Absolute(Duration::from_millis(400)));
+        let mut tm = TimeManager::new(&clock);
+        let cancel = CancelToken::new();
+        let started = Instant::now();
+        let report = tm.search(&BitboardState::new(), 42, &cancel).unwrap();
+        assert!(report.depth >= 1);
+        assert!(tm.stopped());
+        assert!(started.elapsed() < 2 * tm.hard_limit() + Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,333 @@
+use std::fmt::Write as _;
+
+use crate::annotate::{Annotation, annotate_game};
+use crate::game::{Outcome, Player};
+use crate::registry::RegisteredGame;
+use crate::render::svg::{SvgBoard, changed_cell, winning_line};
+use crate::solvers::minimax::WIN_SCORE;
+use crate::tournament::{PairingGame, TournamentReport};
+
+/// Pixels per cell of the replay boards.
+const CELL_SIZE: u32 = 36;
+/// Size of the evaluation graphs, in pixels.
+const GRAPH_WIDTH: u32 = 480;
+const GRAPH_HEIGHT: u32 = 120;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1.5em}\
+th,td{border:1px solid #ccc;padding:.25em .6em;text-align:right}\
+th{background:#f2f2f2}td.name{text-align:left}\
+section{border-top:1px solid #ccc;padding-top:1em;margin-top:1.5em}\
+.replay{display:flex;gap:1.5em;align-items:flex-start;flex-wrap:wrap}\
+.moves{max-width:22em;line-height:1.8}\
+.moves button{margin:0 .15em;border:1px solid #ccc;background:#fff;cursor:pointer}\
+.moves button.current{background:#1d4e9e;color:#fff}\
+.moves .good{color:#1a7f37}.moves .poor{color:#b42318}\
+.nav button{margin-right:.3em}";
+
+/// Shows one position of each replay, driven by its buttons.
+const SCRIPT: &str = "document.querySelectorAll('.replay').forEach(function(r){\
+var plies=r.querySelectorAll('.ply'),moves=r.querySelectorAll('.moves button'),cur=0;\
+function show(i){cur=Math.max(0,Math.min(plies.length-1,i));\
+plies.forEach(function(p,j){p.hidden=j!==cur});\
+moves.forEach(function(m,j){m.classList.toggle('current',j+1===cur)})}\
+r.querySelector('.first').onclick=function(){show(0)};\
+r.querySelector('.prev').onclick=function(){show(cur-1)};\
+r.querySelector('.next').onclick=function(){show(cur+1)};\
+r.querySelector('.last').onclick=function(){show(plies.length-1)};\
+moves.forEach(function(m,j){m.onclick=function(){show(j+1)}});\
+show(plies.length-1)});";
+
+/// A self-contained HTML page describing a tournament of `G`: the
+/// standings, a table of the games, and for every game a replay (boards
+/// and a clickable move list) and a graph of the engines' evaluations.
+///
+/// `starts` are the start positions the tournament was played from, and
+/// `names` name the players by index.
+pub fn html_report<G: RegisteredGame>(
+    report: &TournamentReport<G::Move>,
+    starts: &[G],
+    names: &[String],
+) -> String {
+    render(report, starts, names, &[])
+}
+
+/// `html_report` with every move of the replays annotated (`!!`, `!`,
+/// `?!`, `?`, `??`) by a `depth`-ply search; see `annotate`.
+pub fn html_report_annotated<G>(
+    report: &TournamentReport<G::Move>,
+    starts: &[G],
+    names: &[String],
+    depth: u32,
+) -> String
+where
+    G: RegisteredGame,
+    G::Move: PartialEq,
+{
+    let annotations: Vec<Vec<Option<Annotation>>> = report
+        .games
+        .iter()
+        .map(|game| annotate_game(&starts[game.record.start], &game.record.moves, depth))
+        .collect();
+    render(report, starts, names, &annotations)
+}
+
+/// The report, with `annotations[i]` marking the moves of game `i` (none
+/// if missing).
+fn render<G: RegisteredGame>(
+    report: &TournamentReport<G::Move>,
+    starts: &[G],
+    names: &[String],
+    annotations: &[Vec<Option<Annotation>>],
+) -> String {
+    let name = |player: usize| escape(names.get(player).map_or("?", String::as_str));
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{} tournament</title><style>{STYLE}</style></head><body>\n<h1>{} tournament</h1>\n",
+        G::DISPLAY_NAME,
+        G::DISPLAY_NAME
+    );
+
+    out.push_str("<h2>Standings</h2>\n<table><tr><th>Rank</th><th>Engine</th><th>Points</th><th>SB</th><th>W</th><th>D</th><th>L</th></tr>\n");
+    let tournament = &report.tournament;
+    for (rank, row) in tournament.standings().iter().enumerate() {
+        let (draws, losses) = (0..tournament.players())
+            .map(|opponent| tournament.score(row.player, opponent))
+            .fold((0, 0), |(d, l), s| (d + s.draws, l + s.losses));
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"name\">{}</td><td>{}</td><td>{:.2}</td><td>{}</td><td>{draws}</td><td>{losses}</td></tr>",
+            rank + 1,
+            name(row.player),
+            row.points,
+            row.sonneborn_berger,
+            row.wins
+        );
+    }
+    out.push_str("</table>\n");
+
+    out.push_str("<h2>Games</h2>\n<table><tr><th>Game</th><th>Player1</th><th>Player2</th><th>Result</th><th>Moves</th></tr>\n");
+    for (i, game) in report.games.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "<tr><td><a href=\"#game-{n}\">{n}</a></td><td class=\"name\">{}</td><td class=\"name\">{}</td><td>{}</td><td>{}</td></tr>",
+            name(game.player1()),
+            name(game.player2()),
+            result_text(game.record.outcome),
+            game.record.moves.len(),
+            n = i + 1
+        );
+    }
+    out.push_str("</table>\n");
+
+    for (i, game) in report.games.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "<section id=\"game-{}\"><h3>Game {}: {} vs {} ({})</h3>",
+            i + 1,
+            i + 1,
+            name(game.player1()),
+            name(game.player2()),
+            result_text(game.record.outcome)
+        );
+        let notes = annotations.get(i).map_or(&[][..], Vec::as_slice);
+        replay(&mut out, game, &starts[game.record.start], notes);
+        evaluation_graph(&mut out, &game.record.values);
+        out.push_str("</section>\n");
+    }
+    let _ = write!(out, "<script>{SCRIPT}</script>\n</body></html>\n");
+    out
+}
+
+/// The boards after every move of `game`, with buttons to step through
+/// them, labelled with the moves and their `annotations`.
+fn replay<G: RegisteredGame>(
+    out: &mut String,
+    game: &PairingGame<G::Move>,
+    start: &G,
+    annotations: &[Option<Annotation>],
+) {
+    let mut positions = vec![start.clone()];
+    for mv in &game.record.moves {
+        let next = positions[positions.len() - 1].apply_move(mv);
+        positions.push(next);
+    }
+    out.push_str("<div class=\"replay\"><div>");
+    for (ply, state) in positions.iter().enumerate() {
+        let mut board = SvgBoard::new().style(G::SVG_STYLE).cell_size(CELL_SIZE);
+        if ply > 0
+            && let Some(cell) = changed_cell(&positions[ply - 1], state)
+        {
+            board = board.last_move(cell);
+        }
+        if let Some(length) = G::WIN_LENGTH {
+            board = board.winning_line(winning_line(state, length));
+        }
+        let _ = write!(out, "<div class=\"ply\">{}</div>", board.render(state));
+    }
+    out.push_str("<div class=\"nav\"><button class=\"first\">&lt;&lt;</button><button class=\"prev\">&lt;</button><button class=\"next\">&gt;</button><button class=\"last\">&gt;&gt;</button></div></div>\n<div class=\"moves\">");
+    for (ply, mv) in game.record.moves.iter().enumerate() {
+        if ply.is_multiple_of(2) {
+            let _ = write!(out, "{}.", ply / 2 + 1);
+        }
+        let mv = escape(&G::format_move(mv));
+        match annotations.get(ply).copied().flatten() {
+            Some(note) => {
+                let class = match note {
+                    Annotation::Brilliant | Annotation::Good => "good",
+                    _ => "poor",
+                };
+                let _ = write!(
+                    out,
+                    "<button class=\"{class}\" title=\"{}\">{mv}{note}</button>",
+                    note.name()
+                );
+            }
+            None => {
+                let _ = write!(out, "<button>{mv}</button>");
+            }
+        }
+    }
+    out.push_str("</div></div>\n");
+}
+
+/// A line graph of `values` (Player1's perspective, one per move), or a
+/// note if no move has one. Wins are drawn at the edge of the graph.
+fn evaluation_graph(out: &mut String, values: &[Option<i32>]) {
+    let points: Vec<(usize, i32)> = values
+        .iter()
+        .enumerate()
+        .filter_map(|(ply, v)| v.map(|v| (ply, v)))
+        .collect();
+    if points.is_empty() {
+        out.push_str("<p>No evaluations.</p>\n");
+        return;
+    }
+    let scale = points
+        .iter()
+        .map(|&(_, v)| v.abs())
+        .filter(|&v| v < WIN_SCORE)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let (width, height) = (f64::from(GRAPH_WIDTH), f64::from(GRAPH_HEIGHT));
+    let step = width / values.len().max(2).saturating_sub(1) as f64;
+    let coords: Vec<String> = points
+        .iter()
+        .map(|&(ply, v)| {
+            let v = f64::from(v.clamp(-scale, scale)) / f64::from(scale);
+            format!("{:.1},{:.1}", ply as f64 * step, height / 2.0 * (1.0 - v))
+        })
+        .collect();
+    let _ = writeln!(
+        out,
+        "<p>Evaluation (Player1's view, ±{scale}):</p><svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{GRAPH_WIDTH}\" height=\"{GRAPH_HEIGHT}\" viewBox=\"0 0 {GRAPH_WIDTH} {GRAPH_HEIGHT}\"><rect width=\"{GRAPH_WIDTH}\" height=\"{GRAPH_HEIGHT}\" fill=\"#fafafa\" stroke=\"#ccc\"/><line x1=\"0\" y1=\"{}\" x2=\"{GRAPH_WIDTH}\" y2=\"{}\" stroke=\"#aaa\"/><polyline points=\"{}\" fill=\"none\" stroke=\"#1d4e9e\" stroke-width=\"2\"/></svg>",
+        height / 2.0,
+        height / 2.0,
+        coords.join(" ")
+    );
+}
+
+/// A game's result as `1-0`, `½-½` or `0-1` from Player1's side, or `*`
+/// if it was not finished.
+fn result_text(outcome: Option<Outcome>) -> &'static str {
+    match outcome {
+        Some(Outcome::Win(Player::Player1)) => "1-0",
+        Some(Outcome::Win(Player::Player2)) => "0-1",
+        Some(Outcome::Draw) => "½-½",
+        None => "*",
+    }
+}
+
+/// Escapes text for HTML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{Agent, Decision, MinimaxAgent};
+    use crate::game::GameState;
+    use crate::games::ttt::{TicTacToeMove, TicTacToeState};
+    use crate::rng::RandomAgent;
+    use crate::tournament::run_round_robin;
+
+    #[test]
+    fn report_has_standings_games_replays_and_graphs() {
+        let starts = vec![TicTacToeState::new()];
+        let mut agents: Vec<Box<dyn Agent<TicTacToeState>>> = vec![
+            Box::new(MinimaxAgent::new(9)),
+            Box::new(RandomAgent::new(1)),
+        ];
+        let report = run_round_robin(&starts, &mut agents, 2);
+        let names = ["perfect".to_string(), "<random>".to_string()];
+        let html = html_report(&report, &starts, &names);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Tic-Tac-Toe tournament</title>"));
+        assert!(html.contains("<tr><td>1</td><td class=\"name\">perfect</td>"));
+        assert!(html.contains("&lt;random&gt;"));
+        assert!(!html.contains("<random>"));
+        assert!(html.contains("<a href=\"#game-2\">2</a>"));
+        assert_eq!(html.matches("<section id=\"game-").count(), 2);
+
+        let moves: usize = report.games.iter().map(|g| g.record.moves.len()).sum();
+        assert_eq!(html.matches("<div class=\"ply\">").count(), moves + 2);
+        assert_eq!(html.matches("<polyline").count(), 2);
+        assert!(html.ends_with("</body></html>\n"));
+        assert!(!html.contains("title=\"blunder\""));
+    }
+
+    #[test]
+    fn annotated_reports_mark_the_moves() {
+        // Against X's corner, O's edge reply loses.
+        let starts = vec![TicTacToeState::from_str("X........", Player::Player2).unwrap()];
+        let mut agents: Vec<Box<dyn Agent<TicTacToeState>>> =
+            vec![Box::new(MinimaxAgent::new(9)), Box::new(ScriptedAgent(1))];
+        let report = run_round_robin(&starts, &mut agents, 2);
+        let names = ["perfect".to_string(), "edge".to_string()];
+        let html = html_report_annotated(&report, &starts, &names, 9);
+        assert!(html.contains("<button class=\"poor\" title=\"blunder\">1??</button>"));
+    }
+
+    /// Plays the given cell if it is free, otherwise the first free one.
+    struct ScriptedAgent(u8);
+
+    impl Agent<TicTacToeState> for ScriptedAgent {
+        fn select_move(&mut self, state: &TicTacToeState) -> Option<Decision<TicTacToeMove>> {
+            let moves = state.legal_moves();
+            let mv = moves
+                .iter()
+                .find(|mv| mv.index == self.0)
+                .or(moves.first())?;
+            Some(Decision {
+                mv: *mv,
+                value: None,
+            })
+        }
+    }
+
+    #[test]
+    fn graphs_skip_moves_without_values() {
+        let mut out = String::new();
+        evaluation_graph(&mut out, &[None, None]);
+        assert_eq!(out, "<p>No evaluations.</p>\n");
+
+        let mut out = String::new();
+        evaluation_graph(&mut out, &[Some(10), None, Some(-WIN_SCORE)]);
+        assert!(out.contains("(Player1's view, ±10)"));
+        assert!(out.contains("points=\"0.0,0.0 480.0,120.0\""));
+    }
+}
@@ -0,0 +1,534 @@
+use crate::agent::Agent;
+use crate::arena::{
+    AgentFactory, GameRecord, MatchReport, MatchScore, run_match, run_match_parallel,
+};
+use crate::game::GameState;
+
+mod html;
+
+pub use html::{html_report, html_report_annotated};
+
+/// A pairing of two players by index. In Swiss rounds the first player is
+/// the higher-ranked one.
+pub type Pairing = (usize, usize);
+
+/// The rounds of a round robin among `players`, by the circle method.
+///
+/// Every player meets every other exactly once. With an odd number of
+/// players, one player sits out each round.
+pub fn round_robin_rounds(players: usize) -> Vec<Vec<Pairing>> {
+    // A phantom player `players` makes the count even; pairing with it is
+    // a bye.
+    let n = players + players % 2;
+    if n < 2 {
+        return Vec::new();
+    }
+    let mut circle: Vec<usize> = (0..n).collect();
+    let mut rounds = Vec::with_capacity(n - 1);
+    for _ in 0..n - 1 {
+        let round = (0..n / 2)
+            .map(|i| (circle[i], circle[n - 1 - i]))
+            .filter(|&(a, b)| a < players && b < players)
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        rounds.push(round);
+        // Keep the first player fixed and rotate the rest.
+        circle[1..].rotate_right(1);
+    }
+    rounds
+}
+
+/// Results of a tournament between a fixed set of players.
+///
+/// Each pairing is a mini-match of one or more games (see `run_match`);
+/// players score a point per win and half a point per draw. A bye is worth
+/// a win of every game of the match the player sat out.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tournament {
+    /// `scores[i][j]`: player `i`'s results against player `j`.
+    scores: Vec<Vec<MatchScore>>,
+    /// Byes received per player.
+    byes: Vec<u32>,
+    /// Points awarded for those byes.
+    bye_points: Vec<u32>,
+}
+
+/// One row of the standings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Standing {
+    /// Index of the player.
+    pub player: usize,
+    /// Game points, byes included.
+    pub points: f64,
+    /// Sonneborn-Berger score: the sum, over opponents, of the points
+    /// scored against them times their total points.
+    pub sonneborn_berger: f64,
+    /// Games won.
+    pub wins: u32,
+}
+
+/// One game of a tournament.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PairingGame<M> {
+    /// The pairing the game belongs to; the record's "A" is its first
+    /// player.
+    pub pairing: Pairing,
+    /// The game, with the start position indexing the tournament's starts.
+    pub record: GameRecord<M>,
+}
+
+impl<M> PairingGame<M> {
+    /// The index of the player who played Player1.
+    pub fn player1(&self) -> usize {
+        if self.record.a_is_player1 {
+            self.pairing.0
+        } else {
+            self.pairing.1
+        }
+    }
+
+    /// The index of the player who played Player2.
+    pub fn player2(&self) -> usize {
+        if self.record.a_is_player1 {
+            self.pairing.1
+        } else {
+            self.pairing.0
+        }
+    }
+}
+
+/// What a tournament run returns: the results, and every game in the order
+/// played.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TournamentReport<M> {
+    pub tournament: Tournament,
+    pub games: Vec<PairingGame<M>>,
+}
+
+impl Tournament {
+    /// An empty tournament between `players` players.
+    pub fn new(players: usize) -> Self {
+        Self {
+            scores: vec![vec![MatchScore::default(); players]; players],
+            byes: vec![0; players],
+            bye_points: vec![0; players],
+        }
+    }
+
+    /// Number of players.
+    pub fn players(&self) -> usize {
+        self.byes.len()
+    }
+
+    /// Records `score` for `a` against `b`, from `a`'s perspective.
+    pub fn record(&mut self, a: usize, b: usize, score: MatchScore) {
+        let mirrored = MatchScore {
+            wins: score.losses,
+            draws: score.draws,
+            losses: score.wins,
+        };
+        add_score(&mut self.scores[a][b], score);
+        add_score(&mut self.scores[b][a], mirrored);
+    }
+
+    /// Records a bye for `player` in a round of `games`-game matches,
+    /// scored as winning all of them.
+    pub fn record_bye(&mut self, player: usize, games: u32) {
+        self.byes[player] += 1;
+        self.bye_points[player] += games;
+    }
+
+    /// `a`'s results against `b`.
+    pub fn score(&self, a: usize, b: usize) -> MatchScore {
+        self.scores[a][b]
+    }
+
+    /// Returns true if `a` and `b` have played each other.
+    pub fn have_met(&self, a: usize, b: usize) -> bool {
+        self.scores[a][b].games() > 0
+    }
+
+    /// Byes `player` received.
+    pub fn byes(&self, player: usize) -> u32 {
+        self.byes[player]
+    }
+
+    /// Game points scored by `player` against `opponent`.
+    pub fn points_against(&self, player: usize, opponent: usize) -> f64 {
+        let s = self.scores[player][opponent];
+        s.wins as f64 + 0.5 * s.draws as f64
+    }
+
+    /// Total points of `player`, byes included.
+    pub fn points(&self, player: usize) -> f64 {
+        let games: f64 = (0..self.players())
+            .map(|opponent| self.points_against(player, opponent))
+            .sum();
+        games + self.bye_points[player] as f64
+    }
+
+    /// The standings, best first: by points, then Sonneborn-Berger, then
+    /// wins, then player index.
+    pub fn standings(&self) -> Vec<Standing> {
+        let points: Vec<f64> = (0..self.players()).map(|p| self.points(p)).collect();
+        let mut standings: Vec<Standing> = (0..self.players())
+            .map(|player| Standing {
+                player,
+                points: points[player],
+                sonneborn_berger: (0..self.players())
+                    .map(|opponent| self.points_against(player, opponent) * points[opponent])
+                    .sum(),
+                wins: self.scores[player].iter().map(|s| s.wins).sum(),
+            })
+            .collect();
+        standings.sort_by(|a, b| {
+            b.points
+                .total_cmp(&a.points)
+                .then(b.sonneborn_berger.total_cmp(&a.sonneborn_berger))
+                .then(b.wins.cmp(&a.wins))
+                .then(a.player.cmp(&b.player))
+        });
+        standings
+    }
+
+    /// Pairs the next Swiss round.
+    ///
+    /// Players are ranked by the standings and paired top-down with the
+    /// highest-ranked opponent they have not met, backtracking when that
+    /// strands a lower player. With an odd number of players, the
+    /// lowest-ranked player with the fewest byes sits out and is returned
+    /// as the bye. If no pairing avoids all rematches, rematches are
+    /// allowed.
+    pub fn swiss_pairings(&self) -> (Vec<Pairing>, Option<usize>) {
+        let mut ranked: Vec<usize> = self.standings().iter().map(|s| s.player).collect();
+        let bye = (ranked.len() % 2 == 1).then(|| {
+            let (position, _) = ranked
+                .iter()
+                .enumerate()
+                .rev()
+                .min_by_key(|&(_, &p)| self.byes[p])
+                .expect("an odd number of players is at least one");
+            ranked.remove(position)
+        });
+        let pairings = pair_up(&ranked, &|a, b| !self.have_met(a, b))
+            .or_else(|| pair_up(&ranked, &|_, _| true))
+            .unwrap_or_default();
+        (pairings, bye)
+    }
+}
+
+fn add_score(total: &mut MatchScore, score: MatchScore) {
+    total.wins += score.wins;
+    total.draws += score.draws;
+    total.losses += score.losses;
+}
+
+/// Pairs `ranked` (best first) so that every pair is `allowed`, preferring
+/// to pair each player with the best-ranked available opponent.
+fn pair_up(ranked: &[usize], allowed: &dyn Fn(usize, usize) -> bool) -> Option<Vec<Pairing>> {
+    let Some((&first, rest)) = ranked.split_first() else {
+        return Some(Vec::new());
+    };
+    for (i, &opponent) in rest.iter().enumerate() {
+        if !allowed(first, opponent) {
+            continue;
+        }
+        let mut remaining = rest.to_vec();
+        remaining.remove(i);
+        if let Some(mut pairings) = pair_up(&remaining, allowed) {
+            pairings.insert(0, (first, opponent));
+            return Some(pairings);
+        }
+    }
+    None
+}
+
+/// Plays a round robin between `players` players, `play` returning the
+/// first player's match report in a pairing.
+fn round_robin_with<M>(
+    players: usize,
+    mut play: impl FnMut(Pairing) -> MatchReport<M>,
+) -> TournamentReport<M> {
+    let mut report = TournamentReport {
+        tournament: Tournament::new(players),
+        games: Vec::new(),
+    };
+    for round in round_robin_rounds(players) {
+        for pairing in round {
+            report.record(pairing, play(pairing));
+        }
+    }
+    report
+}
+
+/// Plays `rounds` Swiss rounds of `games`-game matches between `players`
+/// players, `play` returning the first player's match report in a
+/// pairing.
+fn swiss_with<M>(
+    players: usize,
+    rounds: u32,
+    games: u32,
+    mut play: impl FnMut(Pairing) -> MatchReport<M>,
+) -> TournamentReport<M> {
+    let mut report = TournamentReport {
+        tournament: Tournament::new(players),
+        games: Vec::new(),
+    };
+    for _ in 0..rounds {
+        let (pairings, bye) = report.tournament.swiss_pairings();
+        if let Some(player) = bye {
+            report.tournament.record_bye(player, games);
+        }
+        for pairing in pairings {
+            report.record(pairing, play(pairing));
+        }
+    }
+    report
+}
+
+impl<M> TournamentReport<M> {
+    /// Records the match `played` for `pairing`.
+    fn record(&mut self, pairing: Pairing, played: MatchReport<M>) {
+        let (a, b) = pairing;
+        self.tournament.record(a, b, played.score);
+        self.games.extend(
+            played
+                .games
+                .into_iter()
+                .map(|record| PairingGame { pairing, record }),
+        );
+    }
+}
+
+/// Plays one pairing as a `games`-game match, from `a`'s perspective.
+fn play_pairing<G: GameState>(
+    starts: &[G],
+    agents: &mut [Box<dyn Agent<G>>],
+    (a, b): Pairing,
+    games: u32,
+) -> MatchReport<G::Move> {
+    let (low, high) = (a.min(b), a.max(b));
+    let (left, right) = agents.split_at_mut(high);
+    let (a_agent, b_agent) = if a < b {
+        (left[low].as_mut(), right[0].as_mut())
+    } else {
+        (right[0].as_mut(), left[low].as_mut())
+    };
+    run_match(starts, a_agent, b_agent, games)
+}
+
+/// Plays a round robin between `agents`, each pairing being a `games`-game
+/// match from `starts` (see `run_match`).
+///
+/// Panics if `starts` is empty.
+pub fn run_round_robin<G: GameState>(
+    starts: &[G],
+    agents: &mut [Box<dyn Agent<G>>],
+    games: u32,
+) -> TournamentReport<G::Move> {
+    round_robin_with(agents.len(), |pairing| {
+        play_pairing(starts, agents, pairing, games)
+    })
+}
+
+/// Plays `rounds` Swiss rounds between `agents`, each pairing being a
+/// `games`-game match from `starts` (see `run_match`).
+///
+/// Panics if `starts` is empty.
+pub fn run_swiss<G: GameState>(
+    starts: &[G],
+    agents: &mut [Box<dyn Agent<G>>],
+    rounds: u32,
+    games: u32,
+) -> TournamentReport<G::Move> {
+    swiss_with(agents.len(), rounds, games, |pairing| {
+        play_pairing(starts, agents, pairing, games)
+    })
+}
+
+/// Like `run_round_robin`, but each pairing's games are played on `jobs`
+/// threads with fresh agents from `factories` (see `run_match_parallel`).
+///
+/// Panics if `starts` is empty.
+pub fn run_round_robin_parallel<G>(
+    starts: &[G],
+    factories: &[&AgentFactory<G>],
+    games: u32,
+    jobs: usize,
+) -> TournamentReport<G::Move>
+where
+    G: GameState + Sync,
+    G::Move: Send,
+{
+    round_robin_with(factories.len(), |(a, b)| {
+        run_match_parallel(starts, factories[a], factories[b], games, jobs, None)
+    })
+}
+
+/// Like `run_swiss`, but each pairing's games are played on `jobs` threads
+/// with fresh agents from `factories` (see `run_match_parallel`).
+///
+/// Panics if `starts` is empty.
+pub fn run_swiss_parallel<G>(
+    starts: &[G],
+    factories: &[&AgentFactory<G>],
+    rounds: u32,
+    games: u32,
+    jobs: usize,
+) -> TournamentReport<G::Move>
+where
+    G: GameState + Sync,
+    G::Move: Send,
+{
+    swiss_with(factories.len(), rounds, games, |(a, b)| {
+        run_match_parallel(starts, factories[a], factories[b], games, jobs, None)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::MinimaxAgent;
+    use crate::arena::opening_positions;
+    use crate::games::ttt::TicTacToeState;
+    use crate::rng::RandomAgent;
+
+    fn win() -> MatchScore {
+        MatchScore {
+            wins: 1,
+            draws: 0,
+            losses: 0,
+        }
+    }
+
+    #[test]
+    fn round_robin_meets_everyone_once() {
+        for players in 1..=7 {
+            let rounds = round_robin_rounds(players);
+            let mut met = vec![vec![0; players]; players];
+            for round in &rounds {
+                let mut busy = vec![false; players];
+                for &(a, b) in round {
+                    assert!(!busy[a] && !busy[b], "{players} players: {round:?}");
+                    busy[a] = true;
+                    busy[b] = true;
+                    met[a][b] += 1;
+                    met[b][a] += 1;
+                }
+            }
+            for (a, row) in met.iter().enumerate() {
+                for (b, &count) in row.iter().enumerate() {
+                    assert_eq!(count, usize::from(a != b), "{players}: {a} vs {b}");
+                }
+            }
+        }
+        assert_eq!(round_robin_rounds(4).len(), 3);
+        assert_eq!(round_robin_rounds(5).len(), 5);
+    }
+
+    #[test]
+    fn sonneborn_berger_breaks_ties() {
+        // 0 beats 1, 1 beats 2, 2 beats 0: everyone has one point. 3 loses
+        // to 1 and 2 but beats 0.
+        let mut t = Tournament::new(4);
+        t.record(0, 1, win());
+        t.record(1, 2, win());
+        t.record(2, 0, win());
+        t.record(1, 3, win());
+        t.record(2, 3, win());
+        t.record(3, 0, win());
+        let standings = t.standings();
+        let order: Vec<usize> = standings.iter().map(|s| s.player).collect();
+        // 1 and 2 have 2 points; 1 beat 2 (2 points) and 3 (1), 2 beat 0
+        // (1) and 3 (1).
+        assert_eq!(order, [1, 2, 0, 3]);
+        assert_eq!(standings[0].sonneborn_berger, 3.0);
+        assert_eq!(standings[1].sonneborn_berger, 2.0);
+    }
+
+    #[test]
+    fn swiss_pairs_leaders_and_avoids_rematches() {
+        let mut t = Tournament::new(5);
+        t.record(0, 1, win());
+        t.record(2, 3, win());
+        t.record_bye(4, 1);
+        // Leaders 0, 2 and 4 have a point each; 3 is last without a bye.
+        let (pairings, bye) = t.swiss_pairings();
+        assert_eq!(bye, Some(3));
+        assert_eq!(pairings.len(), 2);
+        for &(a, b) in &pairings {
+            assert!(!t.have_met(a, b));
+        }
+        assert_eq!(pairings[0], (0, 2));
+    }
+
+    #[test]
+    fn byes_are_worth_a_won_match() {
+        // Three players, two-game matches: 0 and 1 split theirs while 2
+        // sits out, so 2 leads with a full match's points.
+        let mut t = Tournament::new(3);
+        t.record(
+            0,
+            1,
+            MatchScore {
+                wins: 1,
+                draws: 0,
+                losses: 1,
+            },
+        );
+        t.record_bye(2, 2);
+        assert_eq!((t.points(0), t.points(1), t.points(2)), (1.0, 1.0, 2.0));
+        assert_eq!(t.standings()[0].player, 2);
+        assert_eq!(t.byes(2), 1);
+    }
+
+    #[test]
+    fn ttt_swiss_ranks_perfect_play_first() {
+        let starts: Vec<TicTacToeState> = opening_positions(&TicTacToeState::new(), 1)
+            .into_iter()
+            .map(|(_, s)| s)
+            .collect();
+        let mut agents: Vec<Box<dyn Agent<TicTacToeState>>> = vec![
+            Box::new(RandomAgent::new(1)),
+            Box::new(MinimaxAgent::new(9)),
+            Box::new(RandomAgent::new(2)),
+        ];
+        let report = run_swiss(&starts, &mut agents, 2, 4);
+        assert_eq!(report.games.len(), 8);
+        let t = report.tournament;
+        assert_eq!(t.standings()[0].player, 1);
+        assert_eq!((0..3).map(|p| t.byes(p)).sum::<u32>(), 2);
+        // Each bye is worth a whole match: the points add up to the games
+        // played plus four per bye.
+        let points: f64 = (0..3).map(|p| t.points(p)).sum();
+        assert_eq!(points, 8.0 + 2.0 * 4.0);
+
+        let report = run_round_robin(&starts, &mut agents, 2);
+        assert_eq!(report.games.len(), 6);
+        let first = &report.games[0];
+        assert_eq!((first.player1(), first.player2()), first.pairing);
+        assert_ne!(report.games[1].player1(), first.player1());
+        let t = report.tournament;
+        assert_eq!(t.standings()[0].player, 1);
+        assert_eq!(t.score(1, 0).losses, 0);
+    }
+
+    #[test]
+    fn ttt_parallel_round_robin_matches_any_job_count() {
+        let starts = vec![TicTacToeState::new()];
+        let random =
+            |i: usize| -> Box<dyn Agent<TicTacToeState>> { Box::new(RandomAgent::new(i as u64)) };
+        let perfect =
+            |_: usize| -> Box<dyn Agent<TicTacToeState>> { Box::new(MinimaxAgent::new(9)) };
+        let factories: [&AgentFactory<TicTacToeState>; 3] = [&random, &perfect, &random];
+        let serial = run_round_robin_parallel(&starts, &factories, 6, 1);
+        let parallel = run_round_robin_parallel(&starts, &factories, 6, 3);
+        assert_eq!(serial, parallel);
+        assert_eq!(serial.tournament.standings()[0].player, 1);
+        let swiss = run_swiss_parallel(&starts, &factories, 2, 6, 3).tournament;
+        assert_eq!((0..3).map(|p| swiss.byes(p)).sum::<u32>(), 2);
+    }
+}
@@ -0,0 +1,139 @@
+/// A span around one search or search iteration. With the `tracing`
+/// feature it is entered on creation and, when finished, records the nodes
+/// visited and the elapsed time in its `nodes` and `elapsed_us` fields;
+/// without it, it does nothing.
+pub(crate) struct SearchSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::span::EnteredSpan,
+    #[cfg(feature = "tracing")]
+    start: std::time::Instant,
+}
+
+impl SearchSpan {
+    #[cfg(feature = "tracing")]
+    pub(crate) fn enter(span: tracing::Span) -> Self {
+        Self {
+            span: span.entered(),
+            start: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn disabled() -> Self {
+        Self {}
+    }
+
+    /// Records `nodes` and the elapsed time, and exits the span.
+    pub(crate) fn finish(self, nodes: u64) {
+        #[cfg(feature = "tracing")]
+        self.span.record("nodes", nodes);
+        #[cfg(not(feature = "tracing"))]
+        let _ = nodes;
+        self.close();
+    }
+
+    /// Records the elapsed time (but no node count), and exits the span.
+    pub(crate) fn close(self) {
+        #[cfg(feature = "tracing")]
+        self.span
+            .record("elapsed_us", self.start.elapsed().as_micros() as u64);
+    }
+}
+
+/// Enters a `debug` span named `$name` with the given fields, plus empty
+/// `nodes` and `elapsed_us` fields, and returns it as a `SearchSpan`.
+macro_rules! search_span {
+    ($name:literal $(, $($fields:tt)+)?) => {{
+        #[cfg(feature = "tracing")]
+        let span = $crate::trace::SearchSpan::enter(tracing::debug_span!(
+            $name,
+            $($($fields)+,)?
+            nodes = tracing::field::Empty,
+            elapsed_us = tracing::field::Empty
+        ));
+        #[cfg(not(feature = "tracing"))]
+        let span = $crate::trace::SearchSpan::disabled();
+        span
+    }};
+}
+
+/// `tracing::event!` with the `tracing` feature, nothing without it.
+macro_rules! search_event {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::event!($($arg)+);
+    };
+}
+
+pub(crate) use {search_event, search_span};
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::fmt;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use tracing::field::Field;
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::TicTacToeState;
+    use crate::solvers::iterative::iterative_deepening;
+    use crate::solvers::mtdf::{SearchAlgorithm, iterative_search};
+    use crate::solvers::tt::{ReplacementPolicy, TranspositionTable};
+
+    /// Collects the metadata names of created spans and events.
+    #[derive(Default)]
+    struct Recorder {
+        next_id: AtomicU64,
+        spans: Mutex<Vec<&'static str>>,
+        events: Mutex<Vec<&'static str>>,
+        node_counts: AtomicU64,
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            self.spans.lock().unwrap().push(span.metadata().name());
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut |field: &Field, _: &dyn fmt::Debug| {
+                if field.name() == "nodes" {
+                    self.node_counts.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            self.events.lock().unwrap().push(event.metadata().name());
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn searches_emit_spans_with_node_counts() {
+        let recorder = std::sync::Arc::new(Recorder::default());
+        tracing::subscriber::with_default(recorder.clone(), || {
+            iterative_deepening(&TicTacToeState::new(), 2, |_| {});
+            let mut tt = TranspositionTable::with_entries(1 << 10, ReplacementPolicy::TwoTier);
+            iterative_search(&BitboardState::new(), 3, SearchAlgorithm::Mtdf, &mut tt);
+        });
+        let spans = recorder.spans.lock().unwrap();
+        assert_eq!(spans.iter().filter(|&&s| s == "iteration").count(), 5);
+        assert!(spans.contains(&"search"));
+        assert!(spans.contains(&"mtdf"));
+        assert!(recorder.node_counts.load(Ordering::Relaxed) > 0);
+        assert!(!recorder.events.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+use alloc::vec::Vec;
+
+use crate::game::{GameState, PositionKey};
+
+/// A symmetry of a board: an optional left-right mirror followed by some
+/// clockwise quarter turns.
+///
+/// The eight transforms of a square board are `Transform::ALL`, in the
+/// order of `TicTacToeBitboard::symmetries`: the four rotations, then the
+/// four rotations of the mirrored board. Boards that aren't square only
+/// have the transforms that `fits` them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform {
+    /// Mirror the board left to right first.
+    pub mirror: bool,
+    /// Then turn it this many quarter turns clockwise, 0 to 3.
+    pub quarter_turns: u8,
+}
+
+impl Transform {
+    /// Leaves the board as it is.
+    pub const IDENTITY: Self = Self {
+        mirror: false,
+        quarter_turns: 0,
+    };
+
+    /// Mirrors the board left to right.
+    pub const MIRROR: Self = Self {
+        mirror: true,
+        quarter_turns: 0,
+    };
+
+    /// Every symmetry of a square board, starting with the identity.
+    pub const ALL: [Self; 8] = {
+        let mut all = [Self::IDENTITY; 8];
+        let mut i = 0;
+        while i < 8 {
+            all[i] = Self {
+                mirror: i >= 4,
+                quarter_turns: (i % 4) as u8,
+            };
+            i += 1;
+        }
+        all
+    };
+
+    /// The position of this transform in `ALL`.
+    pub fn index(self) -> usize {
+        usize::from(self.mirror) * 4 + usize::from(self.quarter_turns % 4)
+    }
+
+    /// Whether the transform maps a `rows` by `cols` board onto itself:
+    /// always for square boards, otherwise only with a whole number of
+    /// half turns.
+    pub fn fits(self, rows: usize, cols: usize) -> bool {
+        rows == cols || self.quarter_turns.is_multiple_of(2)
+    }
+
+    /// The transform that undoes this one.
+    pub fn inverse(self) -> Self {
+        if self.mirror {
+            // Mirroring then turning is a reflection, its own inverse.
+            self
+        } else {
+            Self {
+                mirror: false,
+                quarter_turns: (4 - self.quarter_turns % 4) % 4,
+            }
+        }
+    }
+
+    /// Where the cell at (`row`, `col`) of a `rows` by `cols` board goes.
+    ///
+    /// Panics if the transform doesn't `fit` the board.
+    pub fn map_cell(self, rows: usize, cols: usize, row: usize, col: usize) -> (usize, usize) {
+        assert!(
+            self.fits(rows, cols),
+            "{self:?} doesn't map a {rows}x{cols} board onto itself"
+        );
+        let (mut row, mut col) = (row, if self.mirror { cols - 1 - col } else { col });
+        let mut height = rows;
+        for _ in 0..self.quarter_turns % 4 {
+            // Each turn makes the columns into rows, so the board's height
+            // changes when it isn't square.
+            (row, col) = (col, height - 1 - row);
+            height = if height == rows { cols } else { rows };
+        }
+        (row, col)
+    }
+
+    /// Like `map_cell`, for row-major cell indices.
+    pub fn map_index(self, rows: usize, cols: usize, index: usize) -> usize {
+        let (row, col) = self.map_cell(rows, cols, index / cols, index % cols);
+        row * cols + col
+    }
+}
+
+/// A game whose rules are unchanged by some symmetries of its board, so
+/// that a position, its moves and their values can be carried through
+/// them: to canonicalize positions, augment training data or share
+/// tablebase entries.
+pub trait Symmetric: GameState {
+    /// The transforms the rules respect, starting with the identity: all
+    /// eight for Tic-Tac-Toe, only the mirror for Connect Four, which
+    /// gravity keeps from turning.
+    fn transforms() -> &'static [Transform];
+
+    /// The position under `t`, with the last move mapped along. `t` should
+    /// be one of `transforms`.
+    fn transformed(&self, t: Transform) -> Self;
+
+    /// `mv` under `t`: the move that plays in `transformed(t)` what `mv`
+    /// plays in the original position.
+    fn transform_move(mv: &Self::Move, t: Transform) -> Self::Move;
+}
+
+/// The image of `state` with the smallest position key, shared by all
+/// positions that differ by one of the game's transforms, and the
+/// transform that leads to it.
+///
+/// A move found in the canonical position is played in `state` through
+/// the inverse: `G::transform_move(&mv, t.inverse())`.
+pub fn canonical<G: Symmetric + PositionKey>(state: &G) -> (G, Transform) {
+    G::transforms()
+        .iter()
+        .map(|&t| (state.transformed(t), t))
+        .min_by_key(|(image, _)| image.position_key())
+        .unwrap_or_else(|| (state.clone(), Transform::IDENTITY))
+}
+
+/// `state` and `mv` (a move in it) under each of the game's transforms,
+/// starting with themselves, e.g. to multiply training examples.
+pub fn augment<G: Symmetric>(state: &G, mv: &G::Move) -> Vec<(G, G::Move)> {
+    G::transforms()
+        .iter()
+        .map(|&t| (state.transformed(t), G::transform_move(mv, t)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::c4::{ConnectFourMove, ConnectFourState};
+    use crate::games::c4_bitboard::BitboardState;
+    use crate::games::ttt::{TicTacToeMove, TicTacToeState};
+    use crate::games::ttt_bitboard::TicTacToeBitboard;
+
+    #[test]
+    fn transforms_turn_and_mirror_cells() {
+        // A quarter turn clockwise takes the top left corner to the top
+        // right; mirroring takes it there directly.
+        let quarter = Transform::ALL[1];
+        assert_eq!(quarter.map_cell(3, 3, 0, 0), (0, 2));
+        assert_eq!(Transform::MIRROR.map_cell(3, 3, 0, 0), (0, 2));
+        assert_eq!(Transform::MIRROR.map_cell(6, 7, 5, 1), (5, 5));
+        assert_eq!(Transform::ALL[2].map_cell(6, 7, 0, 0), (5, 6));
+        assert!(!quarter.fits(6, 7));
+        for t in Transform::ALL {
+            assert_eq!(Transform::ALL[t.index()], t);
+            for i in 0..9 {
+                assert_eq!(t.inverse().map_index(3, 3, t.map_index(3, 3, i)), i);
+            }
+        }
+    }
+
+    #[test]
+    fn tic_tac_toe_images_match_the_bitboard_symmetries() {
+        let s = TicTacToeState::from_str("XO..X...O", crate::game::Player::Player2).unwrap();
+        let bitboard = TicTacToeBitboard::from(&s);
+        for (t, image) in Transform::ALL.into_iter().zip(bitboard.symmetries()) {
+            assert_eq!(TicTacToeBitboard::from(&s.transformed(t)), image);
+            assert_eq!(bitboard.transformed(t), image);
+        }
+        // Playing a move and transforming agrees with transforming and
+        // playing the mapped move.
+        let mv = TicTacToeMove { index: 2 };
+        for (t, (image, mapped)) in Transform::ALL.into_iter().zip(augment(&s, &mv)) {
+            assert_eq!(image.apply_move(&mapped), s.apply_move(&mv).transformed(t));
+            assert_eq!(TicTacToeState::transform_move(&mapped, t.inverse()), mv);
+        }
+        let (canon, t) = canonical(&s);
+        assert_eq!(s.transformed(t), canon);
+        assert_eq!(canonical(&s.transformed(Transform::ALL[6])).0, canon);
+    }
+
+    #[test]
+    fn connect_four_only_mirrors() {
+        assert_eq!(
+            BitboardState::transforms(),
+            [Transform::IDENTITY, Transform::MIRROR]
+        );
+        let s = BitboardState::from_move_sequence("0012").unwrap();
+        let mirrored = s.transformed(Transform::MIRROR);
+        assert_eq!(mirrored, s.mirrored());
+        assert_eq!(BitboardState::transform_move(&1, Transform::MIRROR), 5);
+        assert_eq!(mirrored.apply_move(&5), s.apply_move(&1).mirrored());
+
+        let array = ConnectFourState::new()
+            .apply_move(&ConnectFourMove { column: 0 })
+            .apply_move(&ConnectFourMove { column: 2 });
+        let image = array.transformed(Transform::MIRROR);
+        assert_eq!(image.heights, [0, 0, 0, 0, 1, 0, 1]);
+        assert_eq!(image.last_move(), Some(ConnectFourMove { column: 4 }));
+        let (canon, t) = canonical(&s);
+        assert_eq!(canon, s.transformed(t));
+    }
+}
@@ -0,0 +1,191 @@
+use crate::agent::MinimaxAgent;
+pub use crate::arena::MatchScore;
+use crate::arena::{opening_positions, run_match};
+use crate::games::c4_bitboard::{BitboardState, C4EvalParams};
+
+/// Settings for `tune`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TuningConfig {
+    /// Search depth used by both engines in every game.
+    pub depth: u32,
+    /// Number of passes over all tunable parameters.
+    pub rounds: u32,
+    /// Step size for each trial change, as a percentage of the parameter's
+    /// current value (at least 1).
+    pub step_percent: i32,
+    /// Opening move sequences; each is played twice with colors swapped.
+    /// The engines are deterministic, so varied openings are what make a
+    /// match more than two games.
+    pub openings: Vec<Vec<u8>>,
+}
+
+impl Default for TuningConfig {
+    fn default() -> Self {
+        Self {
+            depth: 2,
+            rounds: 3,
+            step_percent: 25,
+            openings: openings(2),
+        }
+    }
+}
+
+/// Returns every sequence of `plies` columns from the empty board that does
+/// not end the game.
+pub fn openings(plies: u32) -> Vec<Vec<u8>> {
+    opening_positions(&BitboardState::new(), plies)
+        .into_iter()
+        .map(|(seq, _)| seq)
+        .collect()
+}
+
+/// Plays `candidate` against `baseline` from every opening in `config`,
+/// once with each color, and returns the candidate's score.
+pub fn play_match(
+    candidate: &C4EvalParams,
+    baseline: &C4EvalParams,
+    config: &TuningConfig,
+) -> MatchScore {
+    let mut cand = MinimaxAgent::with_evaluator(config.depth, *candidate);
+    let mut base = MinimaxAgent::with_evaluator(config.depth, *baseline);
+    let starts: Vec<BitboardState> = config
+        .openings
+        .iter()
+        .map(|opening| {
+            opening
+                .iter()
+                .fold(BitboardState::new(), |s, &c| s.apply_column_move(c))
+        })
+        .collect();
+    run_match(&starts, &mut cand, &mut base, 2 * starts.len() as u32).score
+}
+
+/// Names of the parameters `tune` adjusts. Column weights are tuned in
+/// mirrored pairs so the heuristic stays left-right symmetric, and
+/// `window_four` is left alone because four-in-a-row positions are terminal
+/// and scored by the search, not the heuristic.
+pub const TUNABLE: [&str; 6] = [
+    "window_two",
+    "window_three",
+    "col_weights[0,6]",
+    "col_weights[1,5]",
+    "col_weights[2,4]",
+    "col_weights[3]",
+];
+
+fn get(params: &C4EvalParams, i: usize) -> i32 {
+    match i {
+        0 => params.window_two,
+        1 => params.window_three,
+        _ => params.col_weights[i - 2],
+    }
+}
+
+fn set(params: &mut C4EvalParams, i: usize, value: i32) {
+    match i {
+        0 => params.window_two = value,
+        1 => params.window_three = value,
+        _ => {
+            params.col_weights[i - 2] = value;
+            params.col_weights[6 - (i - 2)] = value;
+        }
+    }
+}
+
+/// An accepted change reported by `tune`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuningStep {
+    pub round: u32,
+    /// Which entry of `TUNABLE` changed.
+    pub param: &'static str,
+    /// The new best parameters.
+    pub params: C4EvalParams,
+    /// The new parameters' score against the previous best.
+    pub score: MatchScore,
+}
+
+/// Tunes `start` by coordinate-wise hill climbing.
+///
+/// For each round and each tunable parameter, tries the parameter one step
+/// up and one step down; a trial replaces the current best if it scores
+/// more than half the points in a match against it. Every accepted change
+/// is reported to `on_improvement`. Returns the best parameters found.
+pub fn tune(
+    start: C4EvalParams,
+    config: &TuningConfig,
+    mut on_improvement: impl FnMut(&TuningStep),
+) -> C4EvalParams {
+    let mut best = start;
+    for round in 1..=config.rounds {
+        for (i, name) in TUNABLE.iter().enumerate() {
+            let value = get(&best, i);
+            let step = (value.abs() * config.step_percent / 100).max(1);
+            for trial_value in [value + step, value - step] {
+                let mut trial = best;
+                set(&mut trial, i, trial_value);
+                let score = play_match(&trial, &best, config);
+                if score.score() > 0.5 {
+                    best = trial;
+                    on_improvement(&TuningStep {
+                        round,
+                        param: name,
+                        params: best,
+                        score,
+                    });
+                    break;
+                }
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn openings_enumerate_all_two_ply_sequences() {
+        assert_eq!(openings(0), vec![Vec::<u8>::new()]);
+        assert_eq!(openings(2).len(), 49);
+        assert_eq!(openings(2)[0], vec![0, 0]);
+    }
+
+    #[test]
+    fn identical_params_score_exactly_half() {
+        let params = C4EvalParams::default();
+        let config = TuningConfig {
+            depth: 1,
+            openings: openings(1),
+            ..TuningConfig::default()
+        };
+        let score = play_match(&params, &params, &config);
+        assert_eq!(score.wins + score.draws + score.losses, 14);
+        assert_eq!(score.score(), 0.5);
+    }
+
+    #[test]
+    fn mirrored_column_weights_stay_symmetric() {
+        let mut params = C4EvalParams::default();
+        set(&mut params, 3, 9);
+        assert_eq!(params.col_weights, [3, 9, 5, 7, 5, 9, 3]);
+        assert_eq!(get(&params, 3), 9);
+    }
+
+    #[test]
+    fn tune_only_reports_winning_changes() {
+        let config = TuningConfig {
+            depth: 1,
+            rounds: 1,
+            step_percent: 50,
+            openings: openings(1),
+        };
+        let mut steps = Vec::new();
+        let best = tune(C4EvalParams::default(), &config, |s| steps.push(s.clone()));
+        assert!(steps.iter().all(|s| s.score.score() > 0.5));
+        assert_eq!(
+            steps.last().map_or(C4EvalParams::default(), |s| s.params),
+            best
+        );
+    }
+}
@@ -1,3 +1,10 @@
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::Path;
+
 use crate::game::Player;
 
 /// Returns the opponent of the given player.
@@ -7,3 +14,19 @@ pub fn opposite_player(p: Player) -> Player {
         Player::Player2 => Player::Player1,
     }
 }
+
+/// Writes a file at `path` with `write` atomically: to a temporary file
+/// next to it, which then replaces `path`, so an interruption never leaves
+/// half a file behind.
+#[cfg(feature = "std")]
+pub fn write_atomic(
+    path: &Path,
+    write: impl FnOnce(&mut io::BufWriter<fs::File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut out = io::BufWriter::new(fs::File::create(&tmp)?);
+    write(&mut out)?;
+    out.into_inner()?.sync_all()?;
+    fs::rename(&tmp, path)
+}
@@ -0,0 +1,255 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::game::{GameState, Player, PositionKey};
+use crate::games::ttt_bitboard::TicTacToeBitboard;
+use crate::solvers::tt::{ReplacementPolicy, TranspositionTable, minimax_best_move_ab_depth_tt};
+
+/// Reachable Tic-Tac-Toe positions, the empty board included.
+pub const TTT_POSITIONS: usize = 5_478;
+/// Reachable positions up to rotation and reflection.
+pub const TTT_CLASSES: usize = 765;
+/// Finished positions won by X, won by O, and drawn.
+pub const TTT_FINISHED: [usize; 3] = [626, 316, 16];
+
+/// Entries of the transposition table shared by every cross-check search.
+const TT_ENTRIES: usize = 1 << 12;
+
+/// A broken invariant found by `verify_ttt`. Positions are written as by
+/// `TicTacToeBitboard::to_repr`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// A count differs from the known one.
+    Count {
+        what: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// A solved value is not -1, 0 or 1.
+    ValueOutOfRange { position: String, value: i32 },
+    /// A symmetric image of a position is missing or has another value.
+    Asymmetric {
+        position: String,
+        image: String,
+        value: i32,
+        image_value: Option<i32>,
+    },
+    /// The transposition-table search disagrees with the memoized solver.
+    SolverMismatch {
+        position: String,
+        memoized: i32,
+        searched: i32,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Count {
+                what,
+                expected,
+                found,
+            } => write!(f, "expected {expected} {what}, found {found}"),
+            Violation::ValueOutOfRange { position, value } => {
+                write!(f, "{position} has value {value}")
+            }
+            Violation::Asymmetric {
+                position,
+                image,
+                value,
+                image_value: Some(image_value),
+            } => write!(
+                f,
+                "{position} has value {value} but its image {image} has {image_value}"
+            ),
+            Violation::Asymmetric {
+                position, image, ..
+            } => write!(f, "{position} is reachable but its image {image} is not"),
+            Violation::SolverMismatch {
+                position,
+                memoized,
+                searched,
+            } => write!(
+                f,
+                "{position} solves to {memoized} but searches to {searched}"
+            ),
+        }
+    }
+}
+
+/// What `verify_ttt` found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TttVerification {
+    /// Reachable positions.
+    pub positions: usize,
+    /// Reachable positions up to symmetry.
+    pub classes: usize,
+    /// Finished positions won by X, won by O, and drawn.
+    pub finished: [usize; 3],
+    /// The value of the empty board.
+    pub root_value: i32,
+    pub violations: Vec<Violation>,
+}
+
+impl TttVerification {
+    /// Returns true if every invariant held.
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Solves `state` and every position reachable from it, memoized by
+/// position key.
+fn solve(state: &TicTacToeBitboard, memo: &mut BTreeMap<u64, (TicTacToeBitboard, i32)>) -> i32 {
+    let key = state.position_key();
+    if let Some(&(_, value)) = memo.get(&key) {
+        return value;
+    }
+    let value = match state.terminal_value() {
+        Some(value) => value,
+        None => {
+            let values = state
+                .legal_moves()
+                .into_iter()
+                .map(|mv| solve(&state.apply_move(&mv), memo));
+            match state.current_player() {
+                Player::Player1 => values.max(),
+                Player::Player2 => values.min(),
+            }
+            .unwrap_or(0)
+        }
+    };
+    memo.insert(key, (*state, value));
+    value
+}
+
+/// Enumerates every reachable Tic-Tac-Toe position, solves each with a
+/// memoized solver, and checks:
+///
+/// - the counts of positions, of positions up to symmetry, and of
+///   finished positions by result match the known ones;
+/// - every value is -1, 0 or 1, and the empty board is a draw;
+/// - the rotations and reflections of every position are reachable and
+///   share its value;
+/// - a transposition-table search, sharing one table across all the
+///   positions, agrees with every value.
+pub fn verify_ttt() -> TttVerification {
+    let mut memo = BTreeMap::new();
+    let root_value = solve(&TicTacToeBitboard::new(), &mut memo);
+    let mut violations = Vec::new();
+    let mut classes = BTreeSet::new();
+    let mut finished = [0; 3];
+    let mut tt = TranspositionTable::with_entries(TT_ENTRIES, ReplacementPolicy::TwoTier);
+
+    for (state, value) in memo.values() {
+        let (state, value) = (*state, *value);
+        classes.insert(state.canonical().position_key());
+        match state.terminal_value() {
+            Some(1) => finished[0] += 1,
+            Some(-1) => finished[1] += 1,
+            Some(_) => finished[2] += 1,
+            None => {
+                let depth = 9 - state.ply().unwrap_or(0);
+                let (best, _) = minimax_best_move_ab_depth_tt(&state, depth, &mut tt);
+                let searched = best.map_or(0, |(_, v)| v.signum());
+                if searched != value {
+                    violations.push(Violation::SolverMismatch {
+                        position: state.to_repr(),
+                        memoized: value,
+                        searched,
+                    });
+                }
+            }
+        }
+        if !(-1..=1).contains(&value) {
+            violations.push(Violation::ValueOutOfRange {
+                position: state.to_repr(),
+                value,
+            });
+        }
+        for image in state.symmetries() {
+            let image_value = memo.get(&image.position_key()).map(|&(_, v)| v);
+            if image_value != Some(value) {
+                violations.push(Violation::Asymmetric {
+                    position: state.to_repr(),
+                    image: image.to_repr(),
+                    value,
+                    image_value,
+                });
+            }
+        }
+    }
+
+    let counts = [
+        ("positions", TTT_POSITIONS, memo.len()),
+        ("positions up to symmetry", TTT_CLASSES, classes.len()),
+        ("positions won by X", TTT_FINISHED[0], finished[0]),
+        ("positions won by O", TTT_FINISHED[1], finished[1]),
+        ("drawn positions", TTT_FINISHED[2], finished[2]),
+    ];
+    for (what, expected, found) in counts {
+        if found != expected {
+            violations.push(Violation::Count {
+                what,
+                expected,
+                found,
+            });
+        }
+    }
+    if root_value != 0 {
+        violations.push(Violation::SolverMismatch {
+            position: TicTacToeBitboard::new().to_repr(),
+            memoized: root_value,
+            searched: 0,
+        });
+    }
+    TttVerification {
+        positions: memo.len(),
+        classes: classes.len(),
+        finished,
+        root_value,
+        violations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every reachable position: the long-running check behind
+    /// `solver_cli verify ttt`.
+    #[test]
+    fn every_ttt_position_verifies() {
+        let report = verify_ttt();
+        assert_eq!(report.violations, []);
+        assert_eq!(report.positions, TTT_POSITIONS);
+        assert_eq!(report.classes, TTT_CLASSES);
+        assert_eq!(report.finished, TTT_FINISHED);
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn violations_say_what_broke() {
+        let broken = Violation::Asymmetric {
+            position: "X........".into(),
+            image: "..X......".into(),
+            value: 0,
+            image_value: None,
+        };
+        assert_eq!(
+            alloc::format!("{broken}"),
+            "X........ is reachable but its image ..X...... is not"
+        );
+        let count = Violation::Count {
+            what: "positions",
+            expected: 5_478,
+            found: 5_477,
+        };
+        assert_eq!(
+            alloc::format!("{count}"),
+            "expected 5478 positions, found 5477"
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! JSON API: request types, position construction, and route handlers.
+
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+use solver_core::dyn_game::{DynGameState, DynMove};
+use solver_core::error::{MoveError, ParseError};
+use solver_core::registry::{GameEntry, Registry};
+use solver_core::solvers::iterative::DepthReport;
+
+use crate::http::{ChunkedWriter, Request, write_response};
+
+/// Server-wide settings shared by all connections.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Requests asking for a deeper search are clamped to this depth.
+    pub max_depth: u32,
+    /// The games requests can name.
+    pub registry: Registry,
+}
+
+/// One entry of `GET /games`.
+#[derive(Debug, Serialize)]
+struct GameInfo {
+    name: &'static str,
+    display_name: &'static str,
+    /// The depth searched when a request gives none (clamped to the
+    /// server's limit).
+    default_depth: u32,
+}
+
+fn games_json(config: &Config) -> Vec<u8> {
+    let games: Vec<GameInfo> = config
+        .registry
+        .games()
+        .iter()
+        .map(|game| GameInfo {
+            name: game.name,
+            display_name: game.display_name,
+            default_depth: game.default_depth.min(config.max_depth),
+        })
+        .collect();
+    serde_json::to_vec(&games).expect("game list serializes")
+}
+
+/// Body of `POST /solve` and `POST /solve/stream`.
+///
+/// `game` is a registry name (see `GET /games`), and the position is given
+/// as the list of moves played from the initial position, in the game's
+/// notation: cell indices ("0" to "8") for Tic-Tac-Toe, column indices
+/// ("0" to "6") for Connect Four.
+#[derive(Debug, Deserialize)]
+pub struct SolveRequest {
+    pub game: String,
+    #[serde(default)]
+    pub moves: Vec<String>,
+    pub depth: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+}
+
+/// Replays `moves` from the initial position of `game`, rejecting illegal
+/// moves and moves played after the game has ended.
+pub fn replay(game: &GameEntry, moves: &[String]) -> Result<Box<dyn DynGameState>, String> {
+    let mut state = (game.new_game)();
+    for (ply, mv) in moves.iter().enumerate() {
+        state = state.play(mv).map_err(|e| match e {
+            ParseError::IllegalMove(MoveError::GameOver) => {
+                format!("move {ply} ({mv}) was played after the game ended")
+            }
+            e => format!("move {ply} ({mv}) is not legal: {e}"),
+        })?;
+    }
+    Ok(state)
+}
+
+/// `report` with its moves in the game's notation.
+fn to_api_report(report: &DepthReport<DynMove>) -> DepthReport<String> {
+    let notation = |mv: &DynMove| mv.notation().to_string();
+    DepthReport {
+        depth: report.depth,
+        best_move: notation(&report.best_move),
+        value: report.value,
+        pv: report.pv.iter().map(notation).collect(),
+        root_moves: report
+            .root_moves
+            .iter()
+            .map(|(mv, score)| (notation(mv), *score))
+            .collect(),
+    }
+}
+
+/// Serializes `{"error": msg}`.
+pub fn error_json(msg: &str) -> Vec<u8> {
+    serde_json::to_vec(&ErrorBody { error: msg }).expect("error body serializes")
+}
+
+/// Parses a solve request body, looks up its game and clamps its depth.
+fn parse_solve<'a>(
+    req: &Request,
+    config: &'a Config,
+) -> Result<(SolveRequest, &'a GameEntry, u32), String> {
+    let solve: SolveRequest =
+        serde_json::from_slice(&req.body).map_err(|e| format!("invalid request body: {e}"))?;
+    let game = config.registry.get(&solve.game).ok_or_else(|| {
+        format!(
+            "unknown game '{}' (expected one of: {})",
+            solve.game,
+            config.registry.names().join(", ")
+        )
+    })?;
+    let depth = solve
+        .depth
+        .unwrap_or(game.default_depth)
+        .min(config.max_depth);
+    if depth == 0 {
+        return Err("depth must be at least 1".to_string());
+    }
+    Ok((solve, game, depth))
+}
+
+/// Builds the position and runs iterative deepening, reporting every
+/// completed depth to `on_iteration`.
+fn solve(
+    game: &GameEntry,
+    moves: &[String],
+    depth: u32,
+    mut on_iteration: impl FnMut(&DepthReport<String>),
+) -> Result<DepthReport<String>, String> {
+    let state = replay(game, moves)?;
+    if state.is_terminal() {
+        return Err("the game is already over".to_string());
+    }
+    state
+        .iterative_deepening(depth, &mut |r| on_iteration(&to_api_report(r)))
+        .map(|r| to_api_report(&r))
+        .ok_or_else(|| "no legal moves".to_string())
+}
+
+/// Routes one request and writes the response to `out`.
+///
+/// Routes:
+/// - `GET /health`: liveness check.
+/// - `GET /games`: the games that can be solved, with their default depths.
+/// - `POST /solve`: returns the final `{depth, best_move, value, pv, root_moves}`
+///   report.
+/// - `POST /solve/stream`: streams one report per completed depth as
+///   newline-delimited JSON.
+pub fn handle<W: Write>(req: &Request, config: &Config, mut out: W) -> io::Result<()> {
+    let json = "application/json";
+    match (req.method.as_str(), req.path.as_str()) {
+        ("GET", "/health") => write_response(&mut out, 200, json, br#"{"status":"ok"}"#),
+        ("GET", "/games") => write_response(&mut out, 200, json, &games_json(config)),
+        ("POST", "/solve") => match parse_solve(req, config) {
+            Ok((s, game, depth)) => match solve(game, &s.moves, depth, |_| {}) {
+                Ok(report) => {
+                    let body = serde_json::to_vec(&report).expect("report serializes");
+                    write_response(&mut out, 200, json, &body)
+                }
+                Err(e) => write_response(&mut out, 400, json, &error_json(&e)),
+            },
+            Err(e) => write_response(&mut out, 400, json, &error_json(&e)),
+        },
+        ("POST", "/solve/stream") => {
+            let (s, game, depth) = match parse_solve(req, config) {
+                Ok(parsed) => parsed,
+                Err(e) => return write_response(&mut out, 400, json, &error_json(&e)),
+            };
+            let mut writer = ChunkedWriter::start(out, 200, "application/x-ndjson")?;
+            let mut io_result = Ok(());
+            let outcome = solve(game, &s.moves, depth, |report| {
+                if io_result.is_ok() {
+                    let mut line = serde_json::to_vec(report).expect("report serializes");
+                    line.push(b'\n');
+                    io_result = writer.send(&line);
+                }
+            });
+            io_result?;
+            if let Err(e) = outcome {
+                let mut line = error_json(&e);
+                line.push(b'\n');
+                writer.send(&line)?;
+            }
+            writer.finish()
+        }
+        (_, "/health" | "/games" | "/solve" | "/solve/stream") => {
+            write_response(&mut out, 405, json, &error_json("method not allowed"))
+        }
+        _ => write_response(&mut out, 404, json, &error_json("not found")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(path: &str, body: &str) -> String {
+        send("POST", path, body)
+    }
+
+    fn send(method: &str, path: &str, body: &str) -> String {
+        let req = Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+        };
+        let mut out = Vec::new();
+        let config = Config {
+            max_depth: 9,
+            registry: Registry::builtin(),
+        };
+        handle(&req, &config, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn solve_returns_winning_move() {
+        let resp = post(
+            "/solve",
+            r#"{"game":"c4","moves":["0","6","1","6","2","6"],"depth":3}"#,
+        );
+        assert!(resp.starts_with("HTTP/1.1 200"));
+        assert!(resp.contains(r#""best_move":"3""#));
+        assert!(resp.contains(r#""pv":["3"]"#));
+    }
+
+    #[test]
+    fn solve_takes_any_registered_game() {
+        let resp = post("/solve", r#"{"game":"breakthrough","depth":1}"#);
+        assert!(resp.starts_with("HTTP/1.1 200"), "{resp}");
+        let resp = post("/solve", r#"{"game":"chess"}"#);
+        assert!(resp.starts_with("HTTP/1.1 400"));
+        assert!(resp.contains("unknown game 'chess'"));
+    }
+
+    #[test]
+    fn solve_rejects_illegal_moves() {
+        let resp = post("/solve", r#"{"game":"ttt","moves":["4","4"]}"#);
+        assert!(resp.starts_with("HTTP/1.1 400"));
+        assert!(resp.contains("not legal"));
+    }
+
+    #[test]
+    fn stream_emits_one_line_per_depth() {
+        let resp = post("/solve/stream", r#"{"game":"ttt","moves":[],"depth":3}"#);
+        assert!(resp.contains("Transfer-Encoding: chunked"));
+        assert_eq!(resp.matches(r#""depth":"#).count(), 3);
+    }
+
+    #[test]
+    fn depth_is_clamped_to_server_limit() {
+        let resp = post("/solve", r#"{"game":"ttt","depth":50}"#);
+        assert!(resp.contains(r#""depth":9"#));
+    }
+
+    #[test]
+    fn games_lists_registered_games() {
+        let resp = send("GET", "/games", "");
+        assert!(resp.starts_with("HTTP/1.1 200"));
+        assert!(resp.contains(r#"{"name":"ttt","display_name":"Tic-Tac-Toe","default_depth":9}"#));
+        assert!(resp.contains(r#""name":"c4""#));
+        assert!(send("POST", "/games", "").starts_with("HTTP/1.1 405"));
+    }
+}
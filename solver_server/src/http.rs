@@ -0,0 +1,268 @@
+//! Minimal HTTP/1.1 request parsing and response writing.
+//!
+//! Only what the JSON API needs: a request line, headers, and an optional
+//! `Content-Length` body. Every connection handles a single request.
+
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+
+/// Largest request body the server will read.
+pub const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Largest number of header lines the server will read.
+const MAX_HEADERS: usize = 64;
+
+/// Largest request line plus headers the server will read, so a client
+/// that never ends a line can't grow a request without bound.
+pub const MAX_HEAD_BYTES: usize = 8 * 1024;
+
+/// A parsed HTTP request.
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Header names are lower-cased.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Returns the value of the first header named `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Errors produced while reading a request.
+#[derive(Debug)]
+pub enum HttpError {
+    Io(io::Error),
+    Malformed(&'static str),
+    BodyTooLarge(usize),
+    /// The request line and headers run past `MAX_HEAD_BYTES`.
+    HeadTooLarge,
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::Io(e) => write!(f, "I/O error: {e}"),
+            HttpError::Malformed(what) => write!(f, "malformed request: {what}"),
+            HttpError::BodyTooLarge(n) => {
+                write!(f, "body of {n} bytes exceeds limit of {MAX_BODY_BYTES}")
+            }
+            HttpError::HeadTooLarge => {
+                write!(f, "request line and headers exceed {MAX_HEAD_BYTES} bytes")
+            }
+        }
+    }
+}
+
+impl From<io::Error> for HttpError {
+    fn from(e: io::Error) -> Self {
+        HttpError::Io(e)
+    }
+}
+
+/// Reads a line of the request head from `head`, failing once the head's
+/// byte allowance runs out before the line ends.
+fn read_head_line<R: BufRead>(
+    head: &mut io::Take<R>,
+    line: &mut String,
+) -> Result<usize, HttpError> {
+    let read = head.read_line(line)?;
+    if head.limit() == 0 && !line.ends_with('\n') {
+        return Err(HttpError::HeadTooLarge);
+    }
+    Ok(read)
+}
+
+/// Reads one request (line, headers, body) from `reader`. The request line
+/// and headers together may take at most `MAX_HEAD_BYTES`.
+pub fn read_request<R: BufRead>(reader: &mut R) -> Result<Request, HttpError> {
+    let mut head = reader.by_ref().take(MAX_HEAD_BYTES as u64);
+    let mut line = String::new();
+    if read_head_line(&mut head, &mut line)? == 0 {
+        return Err(HttpError::Malformed("empty request"));
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts
+        .next()
+        .ok_or(HttpError::Malformed("missing method"))?
+        .to_string();
+    let path = parts
+        .next()
+        .ok_or(HttpError::Malformed("missing path"))?
+        .to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        if read_head_line(&mut head, &mut line)? == 0 {
+            return Err(HttpError::Malformed("unexpected end of headers"));
+        }
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(HttpError::Malformed("too many headers"));
+        }
+        let (name, value) = trimmed
+            .split_once(':')
+            .ok_or(HttpError::Malformed("header without colon"))?;
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+
+    let mut request = Request {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+    };
+    if let Some(len) = request.header("content-length") {
+        let len: usize = len
+            .parse()
+            .map_err(|_| HttpError::Malformed("invalid content-length"))?;
+        if len > MAX_BODY_BYTES {
+            return Err(HttpError::BodyTooLarge(len));
+        }
+        request.body = vec![0; len];
+        reader.read_exact(&mut request.body)?;
+    }
+    Ok(request)
+}
+
+/// Returns the reason phrase for the status codes the server uses.
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Writes a complete response with a `Content-Length` body.
+pub fn write_response<W: Write>(
+    w: &mut W,
+    status: u16,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    write!(
+        w,
+        "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        reason(status),
+        body.len()
+    )?;
+    w.write_all(body)?;
+    w.flush()
+}
+
+/// Streams a response body using chunked transfer encoding.
+///
+/// Each `send` call is flushed immediately so clients see progress as it
+/// happens; `finish` writes the terminating chunk.
+pub struct ChunkedWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    /// Writes the response head and returns a writer for the body.
+    pub fn start(mut inner: W, status: u16, content_type: &str) -> io::Result<Self> {
+        write!(
+            inner,
+            "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+            reason(status)
+        )?;
+        inner.flush()?;
+        Ok(Self { inner })
+    }
+
+    /// Sends one chunk.
+    pub fn send(&mut self, data: &[u8]) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        write!(self.inner, "{:x}\r\n", data.len())?;
+        self.inner.write_all(data)?;
+        self.inner.write_all(b"\r\n")?;
+        self.inner.flush()
+    }
+
+    /// Ends the body.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.inner.write_all(b"0\r\n\r\n")?;
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_post_with_body() {
+        let raw = b"POST /solve HTTP/1.1\r\nHost: x\r\nContent-Length: 2\r\n\r\n{}";
+        let req = read_request(&mut &raw[..]).unwrap();
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/solve");
+        assert_eq!(req.header("HOST"), Some("x"));
+        assert_eq!(req.body, b"{}");
+    }
+
+    #[test]
+    fn rejects_oversized_body() {
+        let raw = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_BYTES + 1
+        );
+        assert!(matches!(
+            read_request(&mut raw.as_bytes()),
+            Err(HttpError::BodyTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_oversized_heads() {
+        // A request line that never ends.
+        let raw = vec![b'G'; MAX_HEAD_BYTES * 2];
+        assert!(matches!(
+            read_request(&mut raw.as_slice()),
+            Err(HttpError::HeadTooLarge)
+        ));
+        let mut raw = b"GET / HTTP/1.1\r\n".to_vec();
+        for i in 0..MAX_HEADERS {
+            raw.extend(format!("X-Padding-{i}: {}\r\n", "a".repeat(200)).bytes());
+        }
+        raw.extend(b"\r\n");
+        assert!(matches!(
+            read_request(&mut raw.as_slice()),
+            Err(HttpError::HeadTooLarge)
+        ));
+        // A head just under the limit is fine.
+        let padding = "a".repeat(MAX_HEAD_BYTES - 40);
+        let raw = format!("GET / HTTP/1.1\r\nX-Pad: {padding}\r\n\r\n");
+        assert!(raw.len() < MAX_HEAD_BYTES);
+        assert!(read_request(&mut raw.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn chunked_writer_frames_chunks() {
+        let mut out = Vec::new();
+        let mut w = ChunkedWriter::start(&mut out, 200, "text/plain").unwrap();
+        w.send(b"hello").unwrap();
+        w.finish().unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("Transfer-Encoding: chunked"));
+        assert!(text.ends_with("\r\n\r\n5\r\nhello\r\n0\r\n\r\n"));
+    }
+}
@@ -0,0 +1,588 @@
+//! Live games played against the engine: sessions, move validation, engine
+//! replies, and spectator fan-out. Transport-agnostic; `ws` drives it.
+
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use solver_core::agent::{Agent, Decision, MinimaxAgent};
+use solver_core::book::{BookAgent, OpeningBook};
+use solver_core::dyn_game::{DynGameState, DynMove};
+use solver_core::events::{GameEvent, GameObserver};
+use solver_core::game::{GameState, Player};
+use solver_core::games::c4_bitboard::BitboardState;
+use solver_core::referee::{Referee, Submission, Verdict};
+use solver_core::registry::Registry;
+use solver_core::sessions::{SessionId, SessionManager};
+use solver_core::time_manager::InstantTimeSource;
+
+/// Messages sent by clients.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Start a game of `game` (a registry name) against the engine; the
+    /// sender owns the session. The engine searches the game's default
+    /// depth unless `depth` says otherwise.
+    NewGame {
+        game: String,
+        /// The side the human plays (defaults to Player1).
+        #[serde(default = "default_human")]
+        human: Player,
+        depth: Option<u32>,
+    },
+    /// Play a move, in the game's notation, in an owned session. `ply` is
+    /// the number of moves the client saw played; if given, the move is
+    /// refused once the game has moved on.
+    Move {
+        session: u64,
+        mv: String,
+        #[serde(default)]
+        ply: Option<u32>,
+    },
+    /// Subscribe to a session's moves without playing.
+    Spectate { session: u64 },
+}
+
+fn default_human() -> Player {
+    Player::Player1
+}
+
+/// Messages sent to clients. Moves are in the game's notation.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Snapshot sent on creating or subscribing to a session.
+    Joined {
+        session: u64,
+        game: String,
+        human: Player,
+        moves: Vec<String>,
+        to_move: Player,
+    },
+    /// A move was played by the human or the engine.
+    MoveMade {
+        session: u64,
+        player: Player,
+        mv: String,
+        /// The engine's evaluation (Player1's perspective) for engine moves.
+        value: Option<i32>,
+        by_engine: bool,
+    },
+    /// The game ended with the given value (+1 / 0 / -1 for Player1).
+    GameOver { session: u64, result: i32 },
+    /// A request was rejected or the session went away.
+    Error { message: String },
+}
+
+impl ServerMessage {
+    /// The message telling the clients of `session`, whose human plays
+    /// `human`, about `event`, if they are told about such events.
+    fn from_event(session: u64, human: Player, event: &GameEvent<DynMove>) -> Option<Self> {
+        match event {
+            GameEvent::MoveMade { player, mv, eval } => Some(ServerMessage::MoveMade {
+                session,
+                player: *player,
+                mv: mv.notation().to_string(),
+                value: *eval,
+                by_engine: *player != human,
+            }),
+            GameEvent::GameEnded {
+                outcome: Some(outcome),
+            } => Some(ServerMessage::GameOver {
+                session,
+                result: outcome.value(),
+            }),
+            // Live games have no clocks, and are never abandoned while
+            // anyone is connected.
+            GameEvent::ClockUpdate { .. } | GameEvent::GameEnded { outcome: None } => None,
+        }
+    }
+}
+
+/// A live game of any registered game, refereed so that moves are
+/// validated the same way as in every other transport.
+type LiveGame = Referee<Box<dyn DynGameState>>;
+
+/// Picks the engine's move in `state`, playing from `book` in Connect Four
+/// positions it covers.
+fn engine_move(
+    state: &dyn DynGameState,
+    depth: u32,
+    book: Option<&Arc<OpeningBook>>,
+) -> Option<Decision<DynMove>> {
+    if let (Some(book), Some(c4)) = (book, state.downcast_ref::<BitboardState>()) {
+        let decision = BookAgent::new(book.clone(), MinimaxAgent::new(depth)).select_move(c4)?;
+        return Some(Decision {
+            mv: DynMove::new::<BitboardState>(decision.mv),
+            value: decision.value,
+        });
+    }
+    let result = state.search(depth, None);
+    Some(Decision {
+        mv: result.best_move()?.clone(),
+        value: result.value(),
+    })
+}
+
+struct LiveSession {
+    state: LiveGame,
+    human: Player,
+    depth: u32,
+    /// Connection that created the session; only it may move.
+    owner: u64,
+    subscribers: Vec<(u64, Sender<ServerMessage>)>,
+}
+
+impl LiveSession {
+    fn snapshot(&self, id: u64) -> ServerMessage {
+        ServerMessage::Joined {
+            session: id,
+            game: self.state.state().game_name().to_string(),
+            human: self.human,
+            moves: self
+                .state
+                .state()
+                .history()
+                .iter()
+                .map(|mv| mv.notation().to_string())
+                .collect(),
+            to_move: self.state.state().current_player(),
+        }
+    }
+
+    /// Sends `msg` to every subscriber, dropping those that hung up.
+    fn broadcast(&mut self, msg: &ServerMessage) {
+        self.subscribers
+            .retain(|(_, tx)| tx.send(msg.clone()).is_ok());
+    }
+
+    /// Plays `mv` for `player`, made after `ply` moves if given, and
+    /// notifies subscribers of the move (with the engine's evaluation
+    /// `eval`) and, if it ended the game, of the result.
+    fn play(
+        &mut self,
+        id: u64,
+        player: Player,
+        mv: DynMove,
+        ply: Option<u32>,
+        eval: Option<i32>,
+    ) -> Result<(), String> {
+        // Live games have no clocks, so the time is irrelevant.
+        let submission = Submission {
+            player,
+            ply: ply.unwrap_or(self.state.ply()),
+            mv,
+        };
+        let mut relay = Relay {
+            session: id,
+            human: self.human,
+            subscribers: &mut self.subscribers,
+        };
+        let event = self
+            .state
+            .submit_observed(submission, eval, Duration::ZERO, &mut relay);
+        if let Verdict::Rejected { reason, .. } = event.verdict {
+            return Err(reason.to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Passes a session's game events on to its subscribers.
+struct Relay<'a> {
+    session: u64,
+    human: Player,
+    subscribers: &'a mut Vec<(u64, Sender<ServerMessage>)>,
+}
+
+impl GameObserver<DynMove> for Relay<'_> {
+    fn on_event(&mut self, event: &GameEvent<DynMove>) {
+        if let Some(msg) = ServerMessage::from_event(self.session, self.human, event) {
+            self.subscribers
+                .retain(|(_, tx)| tx.send(msg.clone()).is_ok());
+        }
+    }
+}
+
+/// All live sessions, shared between connection threads. Sessions idle
+/// for longer than the timeout expire (see `expire_idle`).
+pub struct Sessions {
+    sessions: SessionManager<InstantTimeSource, LiveSession>,
+    max_depth: u32,
+    book: Option<Arc<OpeningBook>>,
+}
+
+/// Runs `f` on session `id` of `sessions`, with errors as text.
+fn with_session<R>(
+    sessions: &SessionManager<InstantTimeSource, LiveSession>,
+    id: SessionId,
+    f: impl FnOnce(&mut LiveSession) -> Result<R, String>,
+) -> Result<R, String> {
+    sessions
+        .with_session(id, |session| Ok(f(session)))
+        .map_err(|e| e.to_string())?
+}
+
+impl Sessions {
+    /// Creates an empty session store for the games of `registry`, whose
+    /// sessions expire after `idle_timeout` without moves or spectators
+    /// joining; engine depths are clamped to `max_depth`.
+    pub fn new(registry: Registry, max_depth: u32, idle_timeout: Duration) -> Self {
+        Self {
+            sessions: SessionManager::new(registry, idle_timeout),
+            max_depth,
+            book: None,
+        }
+    }
+
+    /// Makes the engine play Connect Four openings from `book`.
+    pub fn with_book(mut self, book: Arc<OpeningBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    /// Dispatches one client message from connection `conn`, whose outbound
+    /// queue is `tx`. Errors are reported back on `tx`.
+    pub fn handle(&self, conn: u64, tx: &Sender<ServerMessage>, msg: ClientMessage) {
+        let result = match msg {
+            ClientMessage::NewGame { game, human, depth } => {
+                self.create(conn, tx, &game, human, depth).map(|_| ())
+            }
+            ClientMessage::Move { session, mv, ply } => self.human_move(conn, session, &mv, ply),
+            ClientMessage::Spectate { session } => self.spectate(conn, tx, session),
+        };
+        if let Err(message) = result {
+            let _ = tx.send(ServerMessage::Error { message });
+        }
+    }
+
+    fn create(
+        &self,
+        conn: u64,
+        tx: &Sender<ServerMessage>,
+        game: &str,
+        human: Player,
+        depth: Option<u32>,
+    ) -> Result<SessionId, String> {
+        let registry = self.sessions.registry();
+        let entry = registry.get(game).ok_or_else(|| {
+            format!(
+                "unknown game '{game}' (expected one of: {})",
+                registry.names().join(", ")
+            )
+        })?;
+        let session = LiveSession {
+            state: Referee::new((entry.new_game)()),
+            human,
+            depth: depth
+                .unwrap_or(entry.default_depth)
+                .clamp(1, self.max_depth),
+            owner: conn,
+            subscribers: vec![(conn, tx.clone())],
+        };
+        let engine_first = session.state.state().current_player() != human;
+        let id = self.sessions.insert(session);
+        with_session(&self.sessions, id, |session| {
+            let _ = tx.send(session.snapshot(id));
+            Ok(())
+        })?;
+        if engine_first {
+            self.engine_reply(id);
+        }
+        Ok(id)
+    }
+
+    fn human_move(
+        &self,
+        conn: u64,
+        id: SessionId,
+        mv: &str,
+        ply: Option<u32>,
+    ) -> Result<(), String> {
+        with_session(&self.sessions, id, |session| {
+            if session.owner != conn {
+                return Err("only the session owner may move".to_string());
+            }
+            let human = session.human;
+            let mv = session
+                .state
+                .state()
+                .parse_move(mv)
+                .map_err(|e| e.to_string())?;
+            session.play(id, human, mv, ply, None)
+        })?;
+        self.engine_reply(id);
+        Ok(())
+    }
+
+    /// Lets the engine move if the game is still running. The search runs
+    /// without holding the session lock.
+    fn engine_reply(&self, id: SessionId) {
+        let position = with_session(&self.sessions, id, |s| {
+            Ok((!s.state.is_over()).then(|| (s.state.state().clone(), s.depth, s.state.ply())))
+        });
+        let Ok(Some((state, depth, ply))) = position else {
+            return;
+        };
+        let Some(decision) = engine_move(&*state, depth, self.book.as_ref()) else {
+            return;
+        };
+        // If another reply was played meanwhile, the referee rejects this
+        // one as stale; if the session went away, there is no one to tell.
+        let _ = with_session(&self.sessions, id, |session| {
+            let engine = session.state.state().current_player();
+            session.play(id, engine, decision.mv, Some(ply), decision.value)
+        });
+    }
+
+    fn spectate(&self, conn: u64, tx: &Sender<ServerMessage>, id: SessionId) -> Result<(), String> {
+        with_session(&self.sessions, id, |session| {
+            let _ = tx.send(session.snapshot(id));
+            session.subscribers.push((conn, tx.clone()));
+            Ok(())
+        })
+    }
+
+    /// Forgets connection `conn`: unsubscribes it everywhere and closes the
+    /// sessions it owned.
+    pub fn disconnect(&self, conn: u64) {
+        self.sessions.retain(|_, s| {
+            s.subscribers.retain(|(c, _)| *c != conn);
+            if s.owner == conn {
+                s.broadcast(&ServerMessage::Error {
+                    message: "session closed by its owner".to_string(),
+                });
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Closes the sessions idle for longer than the timeout, telling their
+    /// subscribers, and returns how many there were.
+    pub fn expire_idle(&self) -> usize {
+        let expired = self.sessions.take_idle();
+        let count = expired.len();
+        for (id, mut session) in expired {
+            session.broadcast(&ServerMessage::Error {
+                message: format!("session {id} expired"),
+            });
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{Receiver, channel};
+
+    const IDLE: Duration = Duration::from_secs(600);
+
+    fn drain(rx: &Receiver<ServerMessage>) -> Vec<ServerMessage> {
+        rx.try_iter().collect()
+    }
+
+    #[test]
+    fn engine_replies_to_human_move() {
+        let sessions = Sessions::new(Registry::builtin(), 9, IDLE);
+        let (tx, rx) = channel();
+        sessions.handle(
+            1,
+            &tx,
+            ClientMessage::NewGame {
+                game: "ttt".to_string(),
+                human: Player::Player1,
+                depth: Some(9),
+            },
+        );
+        assert!(matches!(
+            drain(&rx)[..],
+            [ServerMessage::Joined { session: 1, .. }]
+        ));
+
+        sessions.handle(
+            1,
+            &tx,
+            ClientMessage::Move {
+                session: 1,
+                mv: "4".to_string(),
+                ply: None,
+            },
+        );
+        let msgs = drain(&rx);
+        assert_eq!(msgs.len(), 2);
+        assert!(matches!(
+            &msgs[0],
+            ServerMessage::MoveMade {
+                mv,
+                by_engine: false,
+                ..
+            } if mv == "4"
+        ));
+        assert!(matches!(
+            msgs[1],
+            ServerMessage::MoveMade {
+                by_engine: true,
+                value: Some(0),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn engine_moves_first_when_human_is_player2() {
+        let sessions = Sessions::new(Registry::builtin(), 9, IDLE);
+        let (tx, rx) = channel();
+        sessions.handle(
+            1,
+            &tx,
+            ClientMessage::NewGame {
+                game: "c4".to_string(),
+                human: Player::Player2,
+                depth: Some(2),
+            },
+        );
+        let msgs = drain(&rx);
+        assert!(matches!(
+            msgs[1],
+            ServerMessage::MoveMade {
+                player: Player::Player1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_illegal_and_foreign_moves() {
+        let sessions = Sessions::new(Registry::builtin(), 9, IDLE);
+        let (tx, rx) = channel();
+        sessions.handle(
+            1,
+            &tx,
+            ClientMessage::NewGame {
+                game: "c4".to_string(),
+                human: Player::Player1,
+                depth: Some(1),
+            },
+        );
+        drain(&rx);
+
+        sessions.handle(
+            1,
+            &tx,
+            ClientMessage::Move {
+                session: 1,
+                mv: "9".to_string(),
+                ply: None,
+            },
+        );
+        assert!(matches!(drain(&rx)[..], [ServerMessage::Error { .. }]));
+
+        let (tx2, rx2) = channel();
+        sessions.handle(
+            2,
+            &tx2,
+            ClientMessage::Move {
+                session: 1,
+                mv: "3".to_string(),
+                ply: None,
+            },
+        );
+        assert!(matches!(drain(&rx2)[..], [ServerMessage::Error { .. }]));
+    }
+
+    #[test]
+    fn refuses_moves_made_against_an_old_position() {
+        let sessions = Sessions::new(Registry::builtin(), 9, IDLE);
+        let (tx, rx) = channel();
+        let new_game = ClientMessage::NewGame {
+            game: "c4".to_string(),
+            human: Player::Player1,
+            depth: Some(1),
+        };
+        sessions.handle(1, &tx, new_game);
+        let play = |mv: &str, ply| ClientMessage::Move {
+            session: 1,
+            mv: mv.to_string(),
+            ply: Some(ply),
+        };
+        sessions.handle(1, &tx, play("3", 0));
+        drain(&rx);
+
+        // A resend of the first move arrives after the engine replied.
+        sessions.handle(1, &tx, play("3", 0));
+        assert_eq!(
+            drain(&rx),
+            [ServerMessage::Error {
+                message: "the move was made after 0 moves, but 2 have been played".to_string(),
+            }]
+        );
+        sessions.handle(1, &tx, play("3", 2));
+        assert_eq!(drain(&rx).len(), 2);
+    }
+
+    #[test]
+    fn spectators_see_moves_and_closure() {
+        let sessions = Sessions::new(Registry::builtin(), 9, IDLE);
+        let (tx, rx) = channel();
+        sessions.handle(
+            1,
+            &tx,
+            ClientMessage::NewGame {
+                game: "c4".to_string(),
+                human: Player::Player1,
+                depth: Some(1),
+            },
+        );
+        let (spec_tx, spec_rx) = channel();
+        sessions.handle(2, &spec_tx, ClientMessage::Spectate { session: 1 });
+        sessions.handle(
+            1,
+            &tx,
+            ClientMessage::Move {
+                session: 1,
+                mv: "3".to_string(),
+                ply: None,
+            },
+        );
+        let seen = drain(&spec_rx);
+        assert!(matches!(seen[0], ServerMessage::Joined { .. }));
+        assert_eq!(seen.len(), 3);
+
+        sessions.disconnect(1);
+        assert!(matches!(drain(&spec_rx)[..], [ServerMessage::Error { .. }]));
+        drop(rx);
+    }
+
+    #[test]
+    fn idle_sessions_expire() {
+        let sessions = Sessions::new(Registry::builtin(), 9, Duration::from_millis(200));
+        let (tx, rx) = channel();
+        let new_game = ClientMessage::NewGame {
+            game: "ttt".to_string(),
+            human: Player::Player1,
+            depth: Some(1),
+        };
+        sessions.handle(1, &tx, new_game);
+        drain(&rx);
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(sessions.expire_idle(), 1);
+        assert_eq!(
+            drain(&rx),
+            [ServerMessage::Error {
+                message: "session 1 expired".to_string(),
+            }]
+        );
+
+        sessions.handle(2, &tx, ClientMessage::Spectate { session: 1 });
+        assert_eq!(
+            drain(&rx),
+            [ServerMessage::Error {
+                message: "no session 1".to_string(),
+            }]
+        );
+    }
+}
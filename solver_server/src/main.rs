@@ -0,0 +1,144 @@
+//! HTTP/JSON API server for the game solver engine.
+//!
+//! This binary crate:
+//! - listens for HTTP connections (one request per connection),
+//! - parses JSON solve requests into game states,
+//! - runs solver_core searches,
+//! - returns (or streams) the results as JSON,
+//! - hosts live games against the engine over WebSocket.
+
+mod api;
+mod http;
+mod live;
+mod ws;
+
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream};
+use std::process;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use api::Config;
+use live::Sessions;
+use solver_core::book::OpeningBook;
+use solver_core::registry::Registry;
+
+const USAGE: &str = "usage: solver_server [--addr HOST:PORT] [--ws-addr HOST:PORT] [--max-depth N] [--session-timeout SECONDS]";
+
+/// How long a connection may go without sending anything while its
+/// request is read, so idle clients don't hold their threads forever.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a live game may go unplayed before it is closed, unless
+/// `--session-timeout` says otherwise.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// How often idle live games are looked for.
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
+fn handle_connection(stream: TcpStream, config: &Config) {
+    if let Err(e) = stream.set_read_timeout(Some(READ_TIMEOUT)) {
+        eprintln!("connection error: {e}");
+        return;
+    }
+    let mut reader = BufReader::new(&stream);
+    let result = match http::read_request(&mut reader) {
+        Ok(req) => api::handle(&req, config, &stream),
+        Err(e) => {
+            let status = match e {
+                http::HttpError::BodyTooLarge(_) => 413,
+                http::HttpError::HeadTooLarge => 431,
+                _ => 400,
+            };
+            let body = api::error_json(&e.to_string());
+            http::write_response(&mut &stream, status, "application/json", &body)
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("connection error: {e}");
+    }
+}
+
+fn main() {
+    let mut addr = "127.0.0.1:8080".to_string();
+    let mut ws_addr = "127.0.0.1:8081".to_string();
+    let mut config = Config {
+        max_depth: 12,
+        registry: Registry::builtin(),
+    };
+
+    let mut session_timeout = SESSION_TIMEOUT;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--addr" => addr = args.next().unwrap_or_else(|| exit_usage()),
+            "--ws-addr" => ws_addr = args.next().unwrap_or_else(|| exit_usage()),
+            "--max-depth" => {
+                config.max_depth = args
+                    .next()
+                    .and_then(|d| d.parse().ok())
+                    .unwrap_or_else(|| exit_usage())
+            }
+            "--session-timeout" => {
+                session_timeout = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .map(Duration::from_secs)
+                    .unwrap_or_else(|| exit_usage())
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ => exit_usage(),
+        }
+    }
+
+    let listener = bind(&addr);
+    let ws_listener = bind(&ws_addr);
+    println!("Listening on http://{addr} (live games on ws://{ws_addr})");
+
+    let mut sessions = Sessions::new(config.registry.clone(), config.max_depth, session_timeout);
+    match OpeningBook::load_default() {
+        Ok(Some(book)) => {
+            let kind = if book.is_exact() { "exact " } else { "" };
+            println!("Loaded {kind}opening book ({} positions)", book.len());
+            sessions = sessions.with_book(Arc::new(book));
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("ignoring opening book: {e}"),
+    }
+    let sessions = Arc::new(sessions);
+    let expiring = Arc::clone(&sessions);
+    thread::spawn(move || {
+        loop {
+            thread::sleep(EXPIRY_INTERVAL);
+            expiring.expire_idle();
+        }
+    });
+    thread::spawn(move || ws::serve(ws_listener, sessions));
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let config = config.clone();
+                thread::spawn(move || handle_connection(stream, &config));
+            }
+            Err(e) => eprintln!("accept failed: {e}"),
+        }
+    }
+}
+
+fn bind(addr: &str) -> TcpListener {
+    TcpListener::bind(addr).unwrap_or_else(|e| {
+        eprintln!("failed to bind {addr}: {e}");
+        process::exit(1);
+    })
+}
+
+fn exit_usage() -> ! {
+    eprintln!("{USAGE}");
+    process::exit(2);
+}
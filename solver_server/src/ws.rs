@@ -0,0 +1,81 @@
+//! WebSocket transport for live games (see `live` for the protocol).
+//!
+//! Each connection gets its own thread and outbound queue. The thread
+//! alternates between reading client frames (with a short timeout) and
+//! flushing queued server messages, so spectators receive moves made on
+//! other connections promptly.
+
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+
+use tungstenite::error::ProtocolError;
+use tungstenite::{Error, Message};
+
+use crate::live::{ClientMessage, ServerMessage, Sessions};
+
+/// How long a connection blocks on reads before flushing queued messages.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Accepts WebSocket connections forever.
+pub fn serve(listener: TcpListener, sessions: Arc<Sessions>) {
+    let next_conn = AtomicU64::new(1);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let conn = next_conn.fetch_add(1, Ordering::Relaxed);
+                let sessions = Arc::clone(&sessions);
+                thread::spawn(move || {
+                    if let Err(e) = handle_connection(stream, conn, &sessions) {
+                        eprintln!("websocket connection {conn}: {e}");
+                    }
+                    sessions.disconnect(conn);
+                });
+            }
+            Err(e) => eprintln!("accept failed: {e}"),
+        }
+    }
+}
+
+fn to_message(msg: &ServerMessage) -> Message {
+    Message::text(serde_json::to_string(msg).expect("server messages serialize"))
+}
+
+fn handle_connection(stream: TcpStream, conn: u64, sessions: &Sessions) -> Result<(), Error> {
+    let mut ws = tungstenite::accept(stream).map_err(|e| match e {
+        tungstenite::HandshakeError::Failure(e) => e,
+        tungstenite::HandshakeError::Interrupted(_) => Error::ConnectionClosed,
+    })?;
+    ws.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+    let (tx, rx) = channel();
+
+    loop {
+        match ws.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str::<ClientMessage>(&text) {
+                Ok(msg) => sessions.handle(conn, &tx, msg),
+                Err(e) => {
+                    let _ = tx.send(ServerMessage::Error {
+                        message: format!("invalid message: {e}"),
+                    });
+                }
+            },
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(
+                Error::ConnectionClosed
+                | Error::AlreadyClosed
+                | Error::Protocol(ProtocolError::ResetWithoutClosingHandshake),
+            ) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+        for msg in rx.try_iter() {
+            ws.send(to_message(&msg))?;
+        }
+    }
+}